@@ -0,0 +1,146 @@
+use std::path::{Path, PathBuf};
+
+use crate::{
+    backup_dir, exec,
+    outcome::{OperationError, OperationOutcome},
+    plan::Plan,
+};
+
+/// Splits the typed folder list on `;` and drops surrounding quotes.
+fn parse_folders(input: &str) -> Vec<PathBuf> {
+    input
+        .split(';')
+        .map(|f| f.trim().trim_matches('"').trim_end_matches('\\'))
+        .filter(|f| !f.is_empty())
+        .map(PathBuf::from)
+        .collect()
+}
+
+/// Saves the NTFS permissions of each folder tree with `icacls /save` into the backup
+/// directory, plus an `ACLs_<timestamp>.acls` manifest listing the folder each file belongs to.
+pub fn backup(input: &str) -> OperationOutcome {
+    let folders = parse_folders(input);
+    if folders.is_empty() {
+        return OperationOutcome::failed(OperationError::Refused("No folders entered.".to_string()));
+    }
+    if let Some(missing) = folders.iter().find(|f| !f.is_dir()) {
+        return OperationOutcome::failed(OperationError::Refused(format!("Folder not found: {}", missing.display())));
+    }
+    if let Some(root) = folders.iter().find(|f| f.parent().is_none() || f.to_string_lossy().ends_with(':')) {
+        return OperationOutcome::failed(OperationError::Refused(format!(
+            "{} is a drive root; enter the folders on it instead.",
+            root.display()
+        )));
+    }
+
+    let dir = backup_dir();
+    if let Err(e) = std::fs::create_dir_all(&dir) {
+        return OperationOutcome::failed(OperationError::Io { path: dir, reason: e.to_string() });
+    }
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    let mut manifest = Vec::new();
+    let mut report = Vec::new();
+    let mut failed_folders = Vec::new();
+    for (i, folder) in folders.iter().enumerate() {
+        let file = format!("ACLs_{}_{}.txt", timestamp, i + 1);
+        let folder_arg = folder.to_string_lossy();
+        let file_arg = dir.join(&file).to_string_lossy().to_string();
+        let output = exec::change_program("icacls", &[&folder_arg, "/save", &file_arg, "/t", "/c", "/q"]);
+        // icacls ends with "Successfully processed N files; Failed processing M files"
+        match output {
+            Ok(output) => {
+                let summary = output.stdout.lines().rev().map(str::trim).find(|l| !l.is_empty()).unwrap_or("").to_string();
+                if !output.success {
+                    failed_folders.push(folder.display().to_string());
+                }
+                report.push(format!("{}: {}", folder.display(), if summary.is_empty() { "saved" } else { &summary }));
+                manifest.push(format!("{}\t{}", folder.display(), file));
+            }
+            Err(e) => {
+                failed_folders.push(folder.display().to_string());
+                report.push(format!("{}: failed to run icacls: {}", folder.display(), e));
+            }
+        }
+    }
+
+    let manifest_path = dir.join(format!("ACLs_{}.acls", timestamp));
+    let contents = format!(
+        "# Folder<TAB>icacls save file. To restore onto a new location, change the path but keep the folder name.\r\n{}\r\n",
+        manifest.join("\r\n")
+    );
+    if let Err(e) = exec::write_file(&manifest_path, &contents) {
+        return OperationOutcome::failed(OperationError::Io { path: manifest_path, reason: e.to_string() });
+    }
+    report.push(String::new());
+    report.push(format!("Manifest: {}", manifest_path.display()));
+    match failed_folders.len() {
+        0 => OperationOutcome::Success(report.join("\n")),
+        n if n == folders.len() => OperationOutcome::failed(OperationError::Command {
+            action: "icacls /save".to_string(),
+            detail: report.join("\n"),
+        }),
+        _ => OperationOutcome::Partial { message: report.join("\n"), failed_items: failed_folders },
+    }
+}
+
+/// Plans restoring the permissions recorded in a manifest onto the same folders.
+/// The current permissions of each folder are saved first so the restore can be undone.
+pub fn restore_plan(manifest: &Path) -> Result<Plan, String> {
+    let content = std::fs::read_to_string(manifest).map_err(|e| format!("Failed to read {}: {}", manifest.display(), e))?;
+    let dir = manifest.parent().map(Path::to_path_buf).unwrap_or_else(backup_dir);
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    let mut plan = Plan::new(format!("Restore NTFS permissions from {}", manifest.display()));
+    for (i, (folder, file)) in content
+        .lines()
+        .filter(|l| !l.trim().is_empty() && !l.starts_with('#'))
+        .filter_map(|l| l.split_once('\t'))
+        .enumerate()
+    {
+        let folder = Path::new(folder.trim());
+        let saved = dir.join(file.trim());
+        // icacls stores names relative to the parent of the saved folder, so it restores from there
+        let parent = match folder.parent() {
+            Some(parent) if folder.is_dir() => parent,
+            _ => {
+                plan.note(format!("{} does not exist here; skipped", folder.display()));
+                continue;
+            }
+        };
+        if !saved.exists() {
+            plan.note(format!("{} is missing; {} skipped", saved.display(), folder.display()));
+            continue;
+        }
+        let before = dir.join(format!("PreRestoreACLs_{}_{}.txt", timestamp, i + 1));
+        plan.step(
+            format!("Restore permissions on {}", folder.display()),
+            format!(
+                "& icacls {} /save {} /t /c /q | Out-Null\n\
+                & icacls {} /restore {} /c /q\n\
+                if ($LASTEXITCODE -ne 0) {{ throw \"icacls failed with exit code $LASTEXITCODE\" }}",
+                exec::quote(&folder.to_string_lossy()),
+                exec::quote(&before.to_string_lossy()),
+                exec::quote(&parent.to_string_lossy()),
+                exec::quote(&saved.to_string_lossy())
+            ),
+        );
+    }
+    if plan.steps.is_empty() && plan.notes.is_empty() {
+        return Err(format!("{} lists no folders.", manifest.display()));
+    }
+    if !plan.steps.is_empty() {
+        plan.note(format!(
+            "The current permissions are saved as PreRestoreACLs_{}_*.txt in {} first.",
+            timestamp,
+            dir.display()
+        ));
+    }
+    Ok(plan)
+}
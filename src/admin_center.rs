@@ -0,0 +1,101 @@
+use crate::{
+    exec,
+    outcome::{OperationError, OperationOutcome},
+};
+
+const INSTALL_SCRIPT: &str = r#"
+$ErrorActionPreference = 'Stop'
+$msi = Join-Path $env:TEMP 'WindowsAdminCenter.msi'
+$log = Join-Path $env:TEMP 'WindowsAdminCenter-install.log'
+Invoke-WebRequest -Uri 'https://aka.ms/WACDownload' -OutFile $msi -UseBasicParsing
+$msiArgs = @('/i', "`"$msi`"", '/qn', '/L*v', "`"$log`"", "SME_PORT=$port")
+if ($thumbprint) {
+    $msiArgs += @("SME_THUMBPRINT=$thumbprint", 'SSL_CERTIFICATE_OPTION=installed')
+} else {
+    $msiArgs += 'SSL_CERTIFICATE_OPTION=generate'
+}
+$p = Start-Process msiexec.exe -ArgumentList $msiArgs -Wait -PassThru
+"ExitCode=$($p.ExitCode)"
+"Log=$log"
+"Host=$([System.Net.Dns]::GetHostEntry('').HostName)"
+"#;
+
+/// Parses `port [thumbprint]` from the options prompt.
+pub fn parse_options(input: &str) -> Result<(u16, Option<String>), String> {
+    let mut parts = input.split_whitespace();
+    let port = match parts.next() {
+        Some(p) => p.parse::<u16>().ok().filter(|p| *p > 0).ok_or_else(|| format!("Invalid port: {}", p))?,
+        None => 443,
+    };
+    let thumbprint = parts.next().map(|t| t.to_uppercase());
+    if let Some(ref t) = thumbprint {
+        if t.len() != 40 || !t.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!("Invalid certificate thumbprint: {}", t));
+        }
+    }
+    Ok((port, thumbprint))
+}
+
+/// Downloads and silently installs Windows Admin Center, returning the URL it listens on.
+pub fn install(port: u16, thumbprint: Option<&str>) -> OperationOutcome {
+    if let Some(t) = thumbprint {
+        let check = format!("if (-not (Test-Path 'Cert:\\LocalMachine\\My\\{}')) {{ exit 1 }}", t);
+        if !matches!(exec::powershell(&check), Ok(output) if output.success) {
+            return OperationOutcome::failed(OperationError::Refused(format!(
+                "No certificate with thumbprint {} in LocalMachine\\My.",
+                t
+            )));
+        }
+    }
+
+    let script = format!(
+        "$port = {}; $thumbprint = {}; {}",
+        port,
+        exec::quote(thumbprint.unwrap_or("")),
+        INSTALL_SCRIPT
+    );
+    let output = match exec::change(&script) {
+        Ok(output) => output,
+        Err(e) => {
+            return OperationOutcome::failed(OperationError::Command {
+                action: "The Windows Admin Center installer".to_string(),
+                detail: e.to_string(),
+            })
+        }
+    };
+    let values = exec::key_values(&output.stdout);
+    let exit_code = values.get("ExitCode").map(String::as_str).unwrap_or("");
+    let log = values.get("Log").cloned().unwrap_or_default();
+
+    match exit_code {
+        "0" | "3010" => {
+            let host = values.get("Host").cloned().unwrap_or_else(|| "localhost".to_string());
+            let url = if port == 443 {
+                format!("https://{}/", host)
+            } else {
+                format!("https://{}:{}/", host, port)
+            };
+            let certificate = match thumbprint {
+                Some(t) => format!("certificate {}", t),
+                None => "a self-signed certificate (valid for 60 days; replace it before production use)".to_string(),
+            };
+            let message = format!(
+                "Windows Admin Center installed successfully!\n\nURL: {}\n\nIt is served with {}.",
+                url, certificate
+            );
+            if exit_code == "3010" {
+                OperationOutcome::SuccessNeedsReboot(message)
+            } else {
+                OperationOutcome::Success(message)
+            }
+        }
+        "" => OperationOutcome::failed(OperationError::Command {
+            action: "Windows Admin Center installation".to_string(),
+            detail: output.stderr,
+        }),
+        code => OperationOutcome::failed(OperationError::Command {
+            action: "Windows Admin Center installation".to_string(),
+            detail: format!("msiexec exit code {}.\n\nSee the log: {}", code, log),
+        }),
+    }
+}
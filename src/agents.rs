@@ -0,0 +1,458 @@
+use std::path::PathBuf;
+
+use crate::{
+    config_dir, exec, history,
+    outcome::{OperationError, OperationOutcome},
+    profiles, rds,
+};
+
+const EXAMPLE_CATALOG: &str = r#"; Server Helper agent catalog
+;
+; Define one section per agent. Each agent needs either a winget package id
+; or an installer URL (.msi or .exe), plus the silent install arguments.
+; `service` is optional and is used to show whether the agent is installed.
+; `runtimes` optionally lists the .NET runtimes the agent needs (netfx, dotnet,
+; aspnetcore or desktop plus a version), checked by .NET Runtimes.
+; `eula` is the agent's license agreement (a URL or a short text). It has to be
+; accepted once, on a confirmation screen, before the agent is installed; who
+; accepted it and when is recorded in the history log.
+;
+; `fleet` lists the hosts offered by default for fleet installs (PowerShell
+; remoting must be enabled on them). Only URL installers can be pushed to
+; the fleet.
+;
+; fleet = srv-app01, srv-app02, srv-db01
+;
+; [Zabbix Agent 2]
+; winget = Zabbix.ZabbixAgent2
+; args = SERVER=zabbix.corp.local SERVERACTIVE=zabbix.corp.local
+; service = Zabbix Agent 2
+;
+; [Datadog Agent]
+; url = https://s3.amazonaws.com/ddagent-windows-stable/datadog-agent-7-latest.amd64.msi
+; args = /qn APIKEY=<your api key> SITE=datadoghq.eu
+; service = datadogagent
+;
+; [Veeam Agent]
+; url = https://files.corp.local/agents/VeeamAgentWindows.exe
+; args = /silent /accepteula /acceptthirdpartylicenses
+; service = VeeamEndpointBackupSvc
+; runtimes = netfx 4.7.2
+; eula = https://www.veeam.com/eula.html
+;
+; [CrowdStrike Falcon]
+; url = https://files.corp.local/agents/WindowsSensor.exe
+; args = /install /quiet /norestart CID=<your customer id>
+; service = CSFalconService
+"#;
+
+/// Runs an installer from a URL. Used locally and as the remoting script block.
+const INSTALLER_BLOCK: &str = r#"
+param($url, $arguments)
+$ErrorActionPreference = 'Stop'
+[Net.ServicePointManager]::SecurityProtocol = [Net.SecurityProtocolType]::Tls12
+$name = [IO.Path]::GetFileName(([Uri]$url).AbsolutePath)
+if (-not $name) { $name = 'agent-installer.exe' }
+$file = Join-Path $env:TEMP $name
+Invoke-WebRequest -Uri $url -OutFile $file -UseBasicParsing
+if ($file -like '*.msi') {
+    $p = Start-Process msiexec.exe -ArgumentList "/i `"$file`" $arguments" -Wait -PassThru
+} elseif ($arguments) {
+    $p = Start-Process $file -ArgumentList $arguments -Wait -PassThru
+} else {
+    $p = Start-Process $file -Wait -PassThru
+}
+"$env:COMPUTERNAME`t$($p.ExitCode)"
+"#;
+
+pub struct Agent {
+    pub name: String,
+    pub winget: Option<String>,
+    pub url: Option<String>,
+    pub args: String,
+    pub service: Option<String>,
+    /// .NET runtimes the agent needs, e.g. `aspnetcore 8.0`.
+    pub runtimes: Vec<String>,
+    /// License agreement to accept before the first install: a URL or a short text.
+    pub eula: Option<String>,
+}
+
+pub struct Catalog {
+    pub fleet: Vec<String>,
+    pub agents: Vec<Agent>,
+}
+
+/// The agent catalog of the active profile.
+pub fn catalog_path() -> PathBuf {
+    profiles::current_dir().join("agents.ini")
+}
+
+/// Accepted license agreements, one `agent<TAB>eula<TAB>user<TAB>time` line each.
+fn eula_path() -> PathBuf {
+    config_dir().join("eula-accepted.txt")
+}
+
+/// The signed-in account as `DOMAIN\user`.
+fn current_user() -> String {
+    let user = std::env::var("USERNAME").unwrap_or_else(|_| "unknown".to_string());
+    match std::env::var("USERDOMAIN") {
+        Ok(domain) if !domain.is_empty() => format!("{}\\{}", domain, user),
+        _ => user,
+    }
+}
+
+/// Who accepted the agent's current license agreement and when (UTC). An edited `eula`
+/// needs accepting again.
+fn eula_acceptance(agent: &Agent) -> Option<(String, String)> {
+    let eula = agent.eula.as_deref()?;
+    let content = std::fs::read_to_string(eula_path()).ok()?;
+    content.lines().rev().find_map(|line| match line.split('\t').collect::<Vec<_>>().as_slice() {
+        [name, accepted, user, time] if *name == agent.name && *accepted == eula => {
+            Some((user.to_string(), time.to_string()))
+        }
+        _ => None,
+    })
+}
+
+/// The license agreement of `name` when it has one that has not been accepted yet.
+pub fn pending_eula(name: &str) -> Result<Option<String>, String> {
+    let agent = find_agent(name)?;
+    Ok(agent.eula.clone().filter(|_| eula_acceptance(&agent).is_none()))
+}
+
+/// Records that the signed-in user accepted the license agreement of `name`, in the
+/// acceptance file and the history log.
+pub fn accept_eula(name: &str) -> Result<String, String> {
+    let agent = find_agent(name)?;
+    let Some(eula) = agent.eula.as_deref() else { return Ok(format!("{} has no license agreement.", name)) };
+    let (user, time) = (current_user(), history::timestamp());
+    let path = eula_path();
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    let line = format!("{}\t{}\t{}\t{}\r\n", agent.name, eula, user, time);
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| std::io::Write::write_all(&mut file, line.as_bytes()))
+        .map_err(|e| format!("Failed to record the acceptance in {}: {}", path.display(), e))?;
+    history::record(&format!("License agreement accepted: {}\nAccepted by: {}\nAgreement: {}", agent.name, user, eula));
+    Ok(format!("{} accepted the license agreement of {}.", user, agent.name))
+}
+
+fn eula_refusal(agent: &Agent) -> Option<OperationOutcome> {
+    (agent.eula.is_some() && eula_acceptance(agent).is_none()).then(|| {
+        OperationOutcome::failed(OperationError::Refused(format!(
+            "The license agreement of {} has not been accepted. Install it from the agent catalog to accept it first.",
+            agent.name
+        )))
+    })
+}
+
+/// Parses the INI-style catalog: `key = value` lines under `[Agent Name]` sections,
+/// with `fleet` allowed before the first section.
+fn parse_catalog(content: &str) -> Result<Catalog, String> {
+    let mut catalog = Catalog { fleet: Vec::new(), agents: Vec::new() };
+    for (i, raw) in content.lines().enumerate() {
+        let line = raw.trim();
+        if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            catalog.agents.push(Agent {
+                name: name.trim().to_string(),
+                winget: None,
+                url: None,
+                args: String::new(),
+                service: None,
+                runtimes: Vec::new(),
+                eula: None,
+            });
+            continue;
+        }
+        let (key, value) = line
+            .split_once('=')
+            .map(|(k, v)| (k.trim().to_lowercase(), v.trim().to_string()))
+            .ok_or_else(|| format!("agents.ini line {}: expected key = value", i + 1))?;
+        match (catalog.agents.last_mut(), key.as_str()) {
+            (None, "fleet") => catalog.fleet = split_hosts(&value),
+            (Some(agent), "winget") => agent.winget = Some(value),
+            (Some(agent), "url") => agent.url = Some(value),
+            (Some(agent), "args") => agent.args = value,
+            (Some(agent), "service") => agent.service = Some(value),
+            (Some(agent), "eula") => agent.eula = Some(value),
+            (Some(agent), "runtimes") => {
+                agent.runtimes = value.split(',').map(str::trim).filter(|r| !r.is_empty()).map(String::from).collect()
+            }
+            _ => return Err(format!("agents.ini line {}: unknown key '{}'", i + 1, key)),
+        }
+    }
+    if let Some(agent) = catalog.agents.iter().find(|a| a.winget.is_none() && a.url.is_none()) {
+        return Err(format!("agents.ini: [{}] needs a winget id or an installer url", agent.name));
+    }
+    Ok(catalog)
+}
+
+/// Splits a host list separated by commas and/or spaces.
+pub fn split_hosts(value: &str) -> Vec<String> {
+    value
+        .split([',', ' '])
+        .map(str::trim)
+        .filter(|h| !h.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Loads the catalog, creating a commented example file on first use.
+pub fn load_catalog() -> Result<Catalog, String> {
+    let path = catalog_path();
+    if !path.exists() {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        std::fs::write(&path, EXAMPLE_CATALOG.replace('\n', "\r\n"))
+            .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    }
+    let content = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    parse_catalog(&content)
+}
+
+fn find_agent(name: &str) -> Result<Agent, String> {
+    load_catalog()?
+        .agents
+        .into_iter()
+        .find(|a| a.name == name)
+        .ok_or_else(|| format!("{} is no longer in the catalog", name))
+}
+
+/// Lists the catalog with whether each agent's service is present on this host.
+pub fn agent_rows() -> Result<Vec<Vec<String>>, String> {
+    let catalog = load_catalog()?;
+
+    let services: Vec<&str> = catalog.agents.iter().filter_map(|a| a.service.as_deref()).collect();
+    let mut running = Vec::new();
+    if !services.is_empty() {
+        let list: Vec<String> = services.iter().map(|s| exec::quote(s)).collect();
+        let script = format!(
+            "Get-Service -Name {} -ErrorAction SilentlyContinue | ForEach-Object {{ \"$($_.Name)`t$($_.Status)\" }}",
+            list.join(",")
+        );
+        if let Ok(output) = exec::powershell(&script) {
+            running = exec::tab_rows(&output.stdout);
+        }
+    }
+
+    Ok(catalog
+        .agents
+        .iter()
+        .map(|agent| {
+            let source = match (&agent.winget, &agent.url) {
+                (Some(id), _) => format!("winget: {}", id),
+                (None, Some(url)) => url.clone(),
+                (None, None) => String::new(),
+            };
+            let status = match &agent.service {
+                Some(service) => running
+                    .iter()
+                    .find(|row| row.first().is_some_and(|n| n.eq_ignore_ascii_case(service)))
+                    .and_then(|row| row.get(1))
+                    .map(|state| format!("installed ({})", state))
+                    .unwrap_or_else(|| "not installed".to_string()),
+                None => "unknown".to_string(),
+            };
+            let eula = match (&agent.eula, eula_acceptance(agent)) {
+                (None, _) => String::new(),
+                (Some(_), Some((user, time))) => format!("accepted by {} {} UTC", user, time),
+                (Some(_), None) => "not accepted".to_string(),
+            };
+            vec![agent.name.clone(), source, agent.args.clone(), status, eula]
+        })
+        .collect())
+}
+
+fn exit_code_ok(code: &str) -> bool {
+    matches!(code, "0" | "3010" | "1641")
+}
+
+/// Installs an agent on this host.
+pub fn install_local(name: &str) -> OperationOutcome {
+    let agent = match find_agent(name) {
+        Ok(agent) => agent,
+        Err(e) => return OperationOutcome::failed(e),
+    };
+    if let Some(refusal) = eula_refusal(&agent) {
+        return refusal;
+    }
+
+    if let Some(id) = &agent.winget {
+        let mut args = vec!["install", "--id", id.as_str(), "-e", "--silent", "--accept-source-agreements", "--accept-package-agreements"];
+        if !agent.args.is_empty() {
+            args.extend(["--override", agent.args.as_str()]);
+        }
+        // A per-user install on a session host reaches only this administrator
+        if rds::is_session_host() {
+            args.extend(["--scope", "machine"]);
+        }
+        return match exec::change_program("winget", &args) {
+            Ok(output) if output.success || output.stdout.contains("Successfully installed") => {
+                OperationOutcome::Success(format!("{} installed via winget ({}).", agent.name, id))
+            }
+            Ok(output) if output.stdout.contains("already installed") => {
+                OperationOutcome::Success(format!("{} is already installed.", agent.name))
+            }
+            Ok(output) => OperationOutcome::failed(OperationError::Command {
+                action: format!("Installing {}", agent.name),
+                detail: format!("{}\n{}", output.stdout.trim(), output.stderr.trim()),
+            }),
+            Err(e) => OperationOutcome::failed(OperationError::Command {
+                action: "Running winget".to_string(),
+                detail: e.to_string(),
+            }),
+        };
+    }
+
+    let url = agent.url.as_deref().unwrap_or_default();
+    let script = format!("& {{ {} }} {} {}", INSTALLER_BLOCK, exec::quote(url), exec::quote(&agent.args));
+    match exec::change(&script) {
+        Ok(output) => match exec::tab_rows(&output.stdout).last().and_then(|row| row.get(1)) {
+            Some(code) if exit_code_ok(code) => {
+                OperationOutcome::Success(format!("{} installed (installer exit code {}).", agent.name, code))
+            }
+            Some(code) => OperationOutcome::failed(OperationError::Command {
+                action: format!("The {} installer", agent.name),
+                detail: format!("Exit code {}.", code),
+            }),
+            None => OperationOutcome::failed(OperationError::Command {
+                action: format!("Installing {}", agent.name),
+                detail: output.stderr,
+            }),
+        },
+        Err(e) => OperationOutcome::failed(OperationError::Command {
+            action: format!("Installing {}", agent.name),
+            detail: e.to_string(),
+        }),
+    }
+}
+
+/// Installs an agent on each host over PowerShell remoting and reports per-host results.
+pub fn install_fleet(name: &str, hosts: &[String]) -> OperationOutcome {
+    let agent = match find_agent(name) {
+        Ok(agent) => agent,
+        Err(e) => return OperationOutcome::failed(e),
+    };
+    if let Some(refusal) = eula_refusal(&agent) {
+        return refusal;
+    }
+    let url = match &agent.url {
+        Some(url) => url,
+        None => {
+            return OperationOutcome::failed(OperationError::Refused(format!(
+                "{} is a winget package; only URL installers can be pushed to the fleet.",
+                agent.name
+            )))
+        }
+    };
+    if hosts.is_empty() {
+        return OperationOutcome::failed(OperationError::Refused("Enter at least one host.".to_string()));
+    }
+
+    let targets: Vec<String> = hosts.iter().map(|h| exec::quote(h)).collect();
+    let script = format!(
+        "Invoke-Command -ComputerName {} -ScriptBlock {{ {} }} -ArgumentList {}, {} -ErrorAction SilentlyContinue -ErrorVariable failed | \
+            ForEach-Object {{ \"$($_.PSComputerName)`t$(($_ -split \"`t\")[1])\" }}; \
+        foreach ($e in $failed) {{ \
+            $h = if ($e.OriginInfo) {{ $e.OriginInfo.PSComputerName }} else {{ $e.TargetObject }}; \
+            \"$h`tERROR: $($e.Exception.Message -replace '\\s+', ' ')\" \
+        }}",
+        targets.join(","),
+        INSTALLER_BLOCK,
+        exec::quote(url),
+        exec::quote(&agent.args)
+    );
+    let output = match exec::change(&script) {
+        Ok(output) => output,
+        Err(e) => {
+            return OperationOutcome::failed(OperationError::Command {
+                action: "Starting the fleet install".to_string(),
+                detail: e.to_string(),
+            })
+        }
+    };
+
+    let rows = exec::tab_rows(&output.stdout);
+    let mut failed_hosts = Vec::new();
+    let mut report = Vec::new();
+    for host in hosts {
+        let result = rows
+            .iter()
+            .find(|row| row.first().is_some_and(|h| h.eq_ignore_ascii_case(host)))
+            .and_then(|row| row.get(1))
+            .map(String::as_str);
+        let line = match result {
+            Some(code) if exit_code_ok(code) => format!("  [OK]   {} (exit code {})", host, code),
+            Some(code) if code.starts_with("ERROR") => format!("  [FAIL] {} {}", host, code),
+            Some(code) => format!("  [FAIL] {} (exit code {})", host, code),
+            None => format!("  [FAIL] {} (no result)", host),
+        };
+        if line.starts_with("  [FAIL]") {
+            failed_hosts.push(host.clone());
+        }
+        report.push(line);
+    }
+    let message = format!(
+        "{}: installed on {} of {} hosts\n\n{}",
+        agent.name,
+        hosts.len() - failed_hosts.len(),
+        hosts.len(),
+        report.join("\n")
+    );
+    match failed_hosts.len() {
+        0 => OperationOutcome::Success(message),
+        n if n == hosts.len() => OperationOutcome::failed(OperationError::Command {
+            action: format!("Installing {} on the fleet", agent.name),
+            detail: report.join("\n"),
+        }),
+        _ => OperationOutcome::Partial { message, failed_items: failed_hosts },
+    }
+}
+
+/// Opens the catalog in Notepad so it can be edited without leaving the TUI.
+pub fn edit_catalog() -> Result<String, String> {
+    let path = catalog_path();
+    std::process::Command::new("notepad.exe")
+        .arg(&path)
+        .spawn()
+        .map(|_| format!("Opened {} in Notepad. Press r to reload after saving.", path.display()))
+        .map_err(|e| format!("Failed to open Notepad: {}", e))
+}
+
+/// Default host list for fleet installs, from the catalog's `fleet` key.
+pub fn default_fleet() -> String {
+    load_catalog().map(|c| c.fleet.join(" ")).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn license_agreements_are_accepted_once_per_text() {
+        let catalog = parse_catalog("[Veeam Agent]\nurl = https://files.corp.local/veeam.exe\neula = https://www.veeam.com/eula.html\n")
+            .unwrap_or_else(|e| panic!("{}", e));
+        let mut agent = catalog.agents.into_iter().next().expect("agent");
+        std::fs::create_dir_all(config_dir()).expect("create config dir");
+        std::fs::write(
+            eula_path(),
+            "Veeam Agent\thttps://www.veeam.com/eula.html\tCORP\\admin\t2026-01-05 09:30:00\r\n",
+        )
+        .expect("write acceptances");
+
+        assert_eq!(eula_acceptance(&agent), Some(("CORP\\admin".to_string(), "2026-01-05 09:30:00".to_string())));
+        assert!(eula_refusal(&agent).is_none());
+
+        agent.eula = Some("https://www.veeam.com/eula-v2.html".to_string());
+        assert!(eula_acceptance(&agent).is_none());
+        assert!(matches!(eula_refusal(&agent), Some(OperationOutcome::Failed { .. })));
+    }
+}
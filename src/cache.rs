@@ -0,0 +1,175 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use crate::{dates, exec, history};
+
+/// Serializes index updates from parallel downloads.
+static INDEX_LOCK: Mutex<()> = Mutex::new(());
+
+/// `%LOCALAPPDATA%\server-helper\cache`, or next to the config in portable mode. Files are
+/// stored under their SHA-256 hash.
+pub fn dir() -> PathBuf {
+    if cfg!(test) || crate::portable_dir().is_some() {
+        return crate::config_dir().join("cache");
+    }
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("C:\\ProgramData"))
+        .join("server-helper")
+        .join("cache")
+}
+
+fn index_path() -> PathBuf {
+    dir().join("index.tsv")
+}
+
+/// One cached download: where it came from, the content it had and the server's ETag or
+/// Last-Modified for it.
+struct Entry {
+    hash: String,
+    url: String,
+    name: String,
+    size: u64,
+    stored: String,
+    validator: String,
+}
+
+/// A cached copy of a download, and the validator to ask the server whether it changed.
+pub struct Cached {
+    pub path: PathBuf,
+    pub validator: String,
+}
+
+fn read_index() -> Vec<Entry> {
+    let text = std::fs::read_to_string(index_path()).unwrap_or_default();
+    exec::tab_rows(&text)
+        .into_iter()
+        .filter(|r| r.len() >= 5)
+        .map(|r| Entry {
+            hash: r[0].clone(),
+            url: r[1].clone(),
+            name: r[2].clone(),
+            size: r[3].parse().unwrap_or(0),
+            stored: r[4].clone(),
+            // Indexes written before validators were kept have five columns
+            validator: r.get(5).cloned().unwrap_or_default(),
+        })
+        .collect()
+}
+
+fn write_index(entries: &[Entry]) -> Result<(), String> {
+    let text: String = entries
+        .iter()
+        .map(|e| format!("{}\t{}\t{}\t{}\t{}\t{}\r\n", e.hash, e.url, e.name, e.size, e.stored, e.validator))
+        .collect();
+    std::fs::write(index_path(), text).map_err(|e| format!("Failed to write the cache index: {}", e))
+}
+
+/// Deletes stored files that no index entry refers to any more.
+fn remove_unreferenced(entries: &[Entry]) {
+    let Ok(files) = std::fs::read_dir(dir()) else { return };
+    for file in files.flatten() {
+        let name = file.file_name().to_string_lossy().to_string();
+        if name.len() == 64 && !entries.iter().any(|e| e.hash == name) {
+            let _ = std::fs::remove_file(file.path());
+        }
+    }
+}
+
+/// SHA-256 of a file as upper-case hex, as printed by `Get-FileHash`.
+fn hash(path: &Path) -> Result<String, String> {
+    let output = exec::powershell(&format!(
+        "(Get-FileHash -Algorithm SHA256 -LiteralPath {} -ErrorAction Stop).Hash",
+        exec::quote(&path.to_string_lossy())
+    ))
+    .map_err(|e| format!("Failed to hash {}: {}", path.display(), e))?;
+    let hash = output.stdout.trim().to_string();
+    if !output.success || hash.len() != 64 {
+        return Err(format!("Failed to hash {}: {}", path.display(), output.stderr.trim()));
+    }
+    Ok(hash)
+}
+
+/// The cached copy of `url`, if there is one and its content still matches its hash. Whether
+/// the server has a newer file is for the caller to ask with the validator.
+pub fn lookup(url: &str) -> Option<Cached> {
+    let entry = read_index().into_iter().find(|e| e.url == url)?;
+    let path = dir().join(&entry.hash);
+    let size = std::fs::metadata(&path).ok()?.len();
+    (size == entry.size && hash(&path).ok()? == entry.hash).then_some(Cached { path, validator: entry.validator })
+}
+
+/// Copies a finished download of `url` into the cache with the server's `validator` for it,
+/// replacing what was cached for it before.
+pub fn store(url: &str, path: &Path, validator: &str) -> Result<(), String> {
+    let hash = hash(path)?;
+    let size = std::fs::metadata(path).map(|m| m.len()).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    std::fs::create_dir_all(dir()).map_err(|e| format!("Failed to create {}: {}", dir().display(), e))?;
+    let blob = dir().join(&hash);
+    if !blob.exists() {
+        std::fs::copy(path, &blob).map_err(|e| format!("Failed to copy {} to the cache: {}", path.display(), e))?;
+    }
+
+    let _lock = INDEX_LOCK.lock();
+    let mut entries: Vec<Entry> = read_index().into_iter().filter(|e| e.url != url).collect();
+    entries.push(Entry {
+        hash,
+        url: url.to_string(),
+        name: path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default(),
+        size,
+        stored: history::timestamp(),
+        validator: validator.to_string(),
+    });
+    write_index(&entries)?;
+    remove_unreferenced(&entries);
+    Ok(())
+}
+
+/// Cached downloads as rows of [File, Size (MB), Stored, SHA-256, URL], stored in local time.
+pub fn entries() -> Result<Vec<Vec<String>>, String> {
+    Ok(read_index()
+        .into_iter()
+        .map(|e| {
+            vec![
+                e.name,
+                format!("{:.1}", e.size as f64 / 1_048_576.0),
+                dates::local_from_utc(&e.stored),
+                e.hash,
+                e.url,
+            ]
+        })
+        .collect())
+}
+
+/// Total size and location of the cache, for the screen's status line.
+pub fn summary() -> String {
+    let entries = read_index();
+    let total: u64 = entries.iter().map(|e| e.size).sum();
+    format!(
+        "{} file(s), {:.1} MB in {}",
+        entries.len(),
+        total as f64 / 1_048_576.0,
+        dir().display()
+    )
+}
+
+/// Removes the cached copy with `hash`, or everything when `hash` is `None`.
+pub fn purge(hash: Option<&str>) -> Result<String, String> {
+    let _lock = INDEX_LOCK.lock();
+    let entries = read_index();
+    let (removed, kept): (Vec<Entry>, Vec<Entry>) = entries.into_iter().partition(|e| hash.is_none_or(|h| e.hash == h));
+    if removed.is_empty() {
+        return Err("Nothing to remove.".to_string());
+    }
+    write_index(&kept)?;
+    remove_unreferenced(&kept);
+    let freed: u64 = removed.iter().map(|e| e.size).sum();
+    history::record(&format!(
+        "Removed {} cached download(s) ({:.1} MB)\n{}",
+        removed.len(),
+        freed as f64 / 1_048_576.0,
+        removed.iter().map(|e| e.name.as_str()).collect::<Vec<_>>().join("\n")
+    ));
+    Ok(format!("Removed {} cached download(s), freeing {:.1} MB.", removed.len(), freed as f64 / 1_048_576.0))
+}
@@ -0,0 +1,240 @@
+//! Notes and tags on backups. `catalog.txt` in the backup directory holds, per backup file, the
+//! tags (e.g. `pre-patch`, `golden`) and free-text note given when it was taken or added later
+//! from the Backup Catalog screen, whose search filter then finds backups by either.
+
+use std::path::Path;
+
+use crate::{backup_dir, dates, delta, golden, rename, signing};
+
+const CATALOG_FILE: &str = "catalog.txt";
+
+/// Backups the catalog lists, by prefix and extension: full and differential role exports on
+/// Windows, package lists on Linux. Sidecar files such as `.source` and `.manifest` are left out.
+const BACKUP_FILES: [(&str, &str); 3] = [("ServerRoles_", "xml"), ("ServerRoles_", delta::EXTENSION), ("Packages_", "txt")];
+
+fn is_backup(name: &str) -> bool {
+    let extension = Path::new(name).extension().unwrap_or_default();
+    BACKUP_FILES.iter().any(|(prefix, ext)| name.starts_with(prefix) && extension == *ext)
+}
+
+/// The tags and note of one backup.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Annotation {
+    pub tags: Vec<String>,
+    pub note: String,
+}
+
+impl Annotation {
+    /// Reads `#tag` words as tags and the rest as the note, e.g.
+    /// `#pre-patch #golden Before the March cumulative update`.
+    pub fn parse(text: &str) -> Annotation {
+        let mut tags: Vec<String> = Vec::new();
+        let mut words = Vec::new();
+        for word in text.split_whitespace() {
+            // Commas separate the tags in the catalog file
+            match word.strip_prefix('#').map(|t| t.trim_end_matches(',').replace(',', "-")).filter(|t| !t.is_empty()) {
+                Some(tag) => {
+                    let tag = tag.to_lowercase();
+                    if !tags.contains(&tag) {
+                        tags.push(tag);
+                    }
+                }
+                None => words.push(word),
+            }
+        }
+        Annotation { tags, note: words.join(" ") }
+    }
+
+    /// The text form read by `parse`, to edit an annotation in place.
+    pub fn render(&self) -> String {
+        self.tags
+            .iter()
+            .map(|t| format!("#{}", t))
+            .chain((!self.note.is_empty()).then(|| self.note.clone()))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn is_empty(&self) -> bool {
+        self.tags.is_empty() && self.note.is_empty()
+    }
+}
+
+fn read_entries(dir: &Path) -> Vec<(String, Annotation)> {
+    std::fs::read_to_string(dir.join(CATALOG_FILE))
+        .unwrap_or_default()
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split('\t').collect();
+            let backup = fields.first().filter(|b| !b.is_empty())?.to_string();
+            let tags = fields.get(1).unwrap_or(&"").split(',').filter(|t| !t.is_empty()).map(str::to_string).collect();
+            Some((backup, Annotation { tags, note: fields.get(2).unwrap_or(&"").to_string() }))
+        })
+        .collect()
+}
+
+fn write_entries(dir: &Path, entries: &[(String, Annotation)]) -> Result<(), String> {
+    let mut text = "# backup\ttags\tnote\r\n".to_string();
+    for (backup, annotation) in entries {
+        text.push_str(&format!(
+            "{}\t{}\t{}\r\n",
+            backup,
+            annotation.tags.join(","),
+            annotation.note.replace(['\t', '\r', '\n'], " ")
+        ));
+    }
+    let path = dir.join(CATALOG_FILE);
+    std::fs::write(&path, text).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// The annotation of `backup`, empty when it has none.
+pub fn annotation(backup: &str) -> Annotation {
+    read_entries(&backup_dir())
+        .into_iter()
+        .find(|(b, _)| b.eq_ignore_ascii_case(backup))
+        .map(|(_, a)| a)
+        .unwrap_or_default()
+}
+
+/// Sets the tags and note of `backup` from `text` (see `Annotation::parse`); empty text clears them.
+pub fn annotate(backup: &str, text: &str) -> Result<String, String> {
+    annotate_in(&backup_dir(), backup, text)
+}
+
+fn annotate_in(dir: &Path, backup: &str, text: &str) -> Result<String, String> {
+    if !dir.join(backup).exists() {
+        return Err(format!("{} is no longer in {}.", backup, dir.display()));
+    }
+    let annotation = Annotation::parse(text);
+    let mut entries = read_entries(dir);
+    entries.retain(|(b, _)| !b.eq_ignore_ascii_case(backup));
+    let status = if annotation.is_empty() {
+        format!("Cleared the tags and note of {}.", backup)
+    } else {
+        format!("Annotated {}: {}", backup, annotation.render())
+    };
+    if !annotation.is_empty() {
+        entries.push((backup.to_string(), annotation));
+    }
+    write_entries(dir, &entries)?;
+    Ok(status)
+}
+
+fn backups(dir: &Path) -> Vec<(i64, String)> {
+    let mut backups: Vec<(i64, String)> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| e.file_name().to_string_lossy().to_string())
+                .filter(|name| is_backup(name))
+                .filter_map(|name| Some((dates::from_file_name(&name)?, name)))
+                .collect()
+        })
+        .unwrap_or_default();
+    backups.sort_by(|a, b| b.cmp(a));
+    backups
+}
+
+/// Annotates the backup just taken.
+pub fn annotate_newest(text: &str) -> Result<String, String> {
+    annotate_newest_in(&backup_dir(), text)
+}
+
+fn annotate_newest_in(dir: &Path, text: &str) -> Result<String, String> {
+    match backups(dir).first() {
+        Some((_, name)) => annotate_in(dir, name, text),
+        None => Err(format!("No backup was found in {}.", dir.display())),
+    }
+}
+
+/// Backups, newest first: file, when it was taken, tags, note and the role types it is the
+/// golden baseline for.
+pub fn rows() -> Result<Vec<Vec<String>>, String> {
+    Ok(rows_in(&backup_dir()))
+}
+
+fn rows_in(dir: &Path) -> Vec<Vec<String>> {
+    let entries = read_entries(dir);
+    backups(dir)
+        .into_iter()
+        .map(|(secs, name)| {
+            let annotation = entries.iter().find(|(b, _)| b.eq_ignore_ascii_case(&name)).map(|(_, a)| a);
+            vec![
+                name.clone(),
+                dates::local(secs),
+                annotation.map(|a| a.tags.join(", ")).unwrap_or_default(),
+                annotation.map(|a| a.note.clone()).unwrap_or_default(),
+                golden::roles_of(&name),
+            ]
+        })
+        .collect()
+}
+
+/// What the restore preview shows about `backup`: when it was taken and where, whether it is
+/// differential, its tags and note, and whether its signature holds.
+pub fn preview(backup: &Path) -> String {
+    let name = backup.file_name().unwrap_or_default().to_string_lossy().to_string();
+    let mut lines = vec![name.clone(), String::new()];
+    if let Some(secs) = dates::from_file_name(&name) {
+        lines.push(format!("Taken: {}", dates::local(secs)));
+    }
+    if let Some(host) = rename::recorded_host(backup) {
+        lines.push(format!("Server: {}", host));
+    }
+    if backup.extension().is_some_and(|e| e == delta::EXTENSION) {
+        lines.push("Differential: rebuilt from its full backup on restore".to_string());
+    }
+    let annotation = annotation(&name);
+    if !annotation.tags.is_empty() {
+        lines.push(format!("Tags: {}", annotation.tags.join(", ")));
+    }
+    if !annotation.note.is_empty() {
+        lines.push(format!("Note: {}", annotation.note));
+    }
+    let golden = golden::roles_of(&name);
+    if !golden.is_empty() {
+        lines.push(format!("Golden baseline for: {}", golden));
+    }
+    lines.push(String::new());
+    let verification = signing::verify(backup);
+    lines.push(match signing::integrity_problem(&verification) {
+        Some(problem) => signing::Verification::Invalid(problem).describe(),
+        None => verification.describe(),
+    });
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    #[test]
+    fn annotates_backups_and_lists_them_newest_first() {
+        let dir = testing::temp_dir("catalog");
+        std::fs::write(dir.join("ServerRoles_1709288100.xml"), "<Objs />").unwrap_or_default();
+        std::fs::write(dir.join("ServerRoles_1709374500.xml"), "<Objs />").unwrap_or_default();
+        std::fs::write(dir.join("ServerRoles_1709460900.delta"), "<Objs />").unwrap_or_default();
+        for sidecar in ["ServerRoles_1709460900.source", "ServerRoles_1709460900.manifest", "ServerRoles_1709460900.manifest.minisig"] {
+            std::fs::write(dir.join(sidecar), "").unwrap_or_default();
+        }
+
+        let parsed = Annotation::parse("#Pre-Patch Before the\tMarch update #golden #pre-patch");
+        assert_eq!(parsed.tags, vec!["pre-patch", "golden"]);
+        assert_eq!(parsed.note, "Before the March update");
+        assert_eq!(Annotation::parse(&parsed.render()), parsed);
+
+        assert!(annotate_newest_in(&dir, "#golden Baseline").is_ok());
+        assert!(annotate_in(&dir, "ServerRoles_1709288100.xml", "#pre-patch").is_ok());
+        let rows = rows_in(&dir);
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0][0], "ServerRoles_1709460900.delta");
+        assert_eq!((rows[0][2].as_str(), rows[0][3].as_str()), ("golden", "Baseline"));
+        assert_eq!(rows[2][2], "pre-patch");
+
+        assert!(annotate_in(&dir, "ServerRoles_1709460900.delta", "").is_ok());
+        assert_eq!(read_entries(&dir).len(), 1);
+        assert!(annotate_in(&dir, "ServerRoles_1.xml", "#x").is_err());
+    }
+}
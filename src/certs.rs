@@ -0,0 +1,229 @@
+use std::net::IpAddr;
+use std::path::Path;
+
+use crate::{backup_dir, exec};
+
+/// Writes the certreq INF and creates the key pair and request in the machine store.
+/// The private key stays on this server until the issued certificate is accepted.
+const REQUEST_SCRIPT: &str = r#"
+$ErrorActionPreference = 'Stop'
+New-Item -ItemType Directory -Force -Path (Split-Path $req) | Out-Null
+$inf = [IO.Path]::ChangeExtension($req, '.inf')
+$lines = @(
+    '[Version]'
+    'Signature="$Windows NT$"'
+    '[NewRequest]'
+    "Subject = ""$subject"""
+    'KeyAlgorithm = RSA'
+    'KeyLength = 2048'
+    'HashAlgorithm = SHA256'
+    'KeySpec = 1'
+    'KeyUsage = 0xA0'
+    'MachineKeySet = TRUE'
+    'Exportable = TRUE'
+    'ProviderName = "Microsoft RSA SChannel Cryptographic Provider"'
+    'RequestType = PKCS10'
+    '[EnhancedKeyUsageExtension]'
+    'OID = 1.3.6.1.5.5.7.3.1'
+    '[Extensions]'
+    '2.5.29.17 = "{text}"'
+)
+$lines += $sans | ForEach-Object { "_continue_ = ""$_&""" }
+Set-Content -LiteralPath $inf -Value $lines -Encoding ASCII
+certreq.exe -new -q $inf $req | Out-Null
+if ($LASTEXITCODE -ne 0) { throw "certreq -new failed with exit code $LASTEXITCODE" }
+"#;
+
+/// Accepts an issued certificate into LocalMachine\My, pairing it with its pending key.
+const ACCEPT_SCRIPT: &str = r#"
+$ErrorActionPreference = 'Stop'
+certreq.exe -accept -machine -q $file | Out-Null
+if ($LASTEXITCODE -ne 0) { throw "certreq -accept failed with exit code $LASTEXITCODE (was the request created on this server?)" }
+$issued = New-Object Security.Cryptography.X509Certificates.X509Certificate2 $file
+$cert = Get-Item "Cert:\LocalMachine\My\$($issued.Thumbprint)"
+"Thumbprint=$($cert.Thumbprint)"
+"Subject=$($cert.Subject)"
+"Expires=$($cert.NotAfter.ToString('yyyy-MM-dd'))"
+"PrivateKey=$($cert.HasPrivateKey)"
+"#;
+
+const IIS_BINDING_SCRIPT: &str = r#"
+$ErrorActionPreference = 'Stop'
+Import-Module WebAdministration
+if (-not (Get-Website -Name $site)) { throw "IIS has no site named '$site'" }
+if (-not (Get-WebBinding -Name $site -Protocol https -Port $port)) {
+    New-WebBinding -Name $site -Protocol https -Port $port -IPAddress '*'
+}
+Remove-Item -Path "IIS:\SslBindings\0.0.0.0!$port" -ErrorAction SilentlyContinue
+(Get-WebBinding -Name $site -Protocol https -Port $port).AddSslCertificate($thumbprint, 'My')
+"#;
+
+const WINRM_LISTENER_SCRIPT: &str = r#"
+$ErrorActionPreference = 'Stop'
+Get-ChildItem -Path WSMan:\localhost\Listener |
+    Where-Object { $_.Keys -contains 'Transport=HTTPS' } |
+    Remove-Item -Recurse -Force
+New-Item -Path WSMan:\localhost\Listener -Transport HTTPS -Address * -CertificateThumbPrint $thumbprint -Force | Out-Null
+if (-not (Get-NetFirewallRule -Name 'WINRM-HTTPS-In-TCP' -ErrorAction SilentlyContinue)) {
+    New-NetFirewallRule -Name 'WINRM-HTTPS-In-TCP' -DisplayName 'Windows Remote Management (HTTPS-In)' `
+        -Direction Inbound -Protocol TCP -LocalPort 5986 -Action Allow | Out-Null
+}
+"#;
+
+/// Certificates with a private key in LocalMachine\My: [Thumbprint, Subject, Names, Expires, Issuer].
+pub fn machine_certificates() -> Result<Vec<Vec<String>>, String> {
+    let script = "Get-ChildItem -Path Cert:\\LocalMachine\\My | Where-Object HasPrivateKey | Sort-Object NotAfter -Descending | \
+        ForEach-Object { \"$($_.Thumbprint)`t$($_.Subject)`t$($_.DnsNameList -join ', ')`t$($_.NotAfter.ToString('yyyy-MM-dd'))`t$($_.Issuer)\" }";
+    let output = exec::powershell(script).map_err(|e| format!("Failed to list certificates: {}", e))?;
+    Ok(exec::tab_rows(&output.stdout))
+}
+
+/// `CN=<fqdn>` and the FQDN plus short name, to prefill the wizard.
+pub fn default_names() -> (String, String) {
+    let output = exec::powershell(
+        "$fqdn = [Net.Dns]::GetHostEntry('').HostName; \"Fqdn=$fqdn\"; \"Host=$env:COMPUTERNAME\"",
+    );
+    let values = output.map(|o| exec::key_values(&o.stdout)).unwrap_or_default();
+    let host = values.get("Host").map(|h| h.to_lowercase()).unwrap_or_default();
+    let fqdn = values.get("Fqdn").map(|f| f.to_lowercase()).unwrap_or_else(|| host.clone());
+    let names = if fqdn == host { fqdn.clone() } else { format!("{} {}", fqdn, host) };
+    (format!("CN={}", fqdn), names)
+}
+
+/// Generates a key pair and CSR for `subject` with `names` (DNS names or IP addresses,
+/// separated by spaces or commas) as subject alternative names, saved to the backup folder.
+pub fn create_request(subject: &str, names: &str) -> (bool, String) {
+    let subject = subject.trim();
+    let common_name = match subject
+        .split(',')
+        .find_map(|part| part.trim().strip_prefix("CN=").or_else(|| part.trim().strip_prefix("cn=")))
+    {
+        Some(cn) if !cn.trim().is_empty() => cn.trim().to_string(),
+        _ => return (false, "The subject needs a common name, e.g. CN=web01.corp.local".to_string()),
+    };
+    if subject.contains('"') {
+        return (false, "The subject cannot contain double quotes.".to_string());
+    }
+
+    let names: Vec<&str> = names.split([' ', ',']).filter(|n| !n.is_empty()).collect();
+    if names.is_empty() {
+        return (false, "Enter at least one subject alternative name; browsers ignore the common name.".to_string());
+    }
+    let sans: Vec<String> = names
+        .iter()
+        .map(|n| match n.parse::<IpAddr>() {
+            Ok(_) => exec::quote(&format!("ipaddress={}", n)),
+            Err(_) => exec::quote(&format!("dns={}", n)),
+        })
+        .collect();
+
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let file_name: String = common_name.chars().map(|c| if c.is_ascii_alphanumeric() || c == '.' { c } else { '_' }).collect();
+    let req = backup_dir().join(format!("CSR_{}_{}.req", file_name, timestamp));
+    let script = format!(
+        "$req = {}\n$subject = {}\n$sans = @({})\n{}",
+        exec::quote(&req.to_string_lossy()),
+        exec::quote(subject),
+        sans.join(", "),
+        REQUEST_SCRIPT.trim()
+    );
+    match exec::change(&script) {
+        Ok(output) if output.success => (true, format!(
+            "Certificate request created for {}\n\
+            Subject alternative names: {}\n\n\
+            CSR: {}\n\n\
+            Submit the .req file to your CA (web enrollment, certreq -submit or a public CA portal).\n\
+            The private key stays in this server's certificate store. When the certificate is issued,\n\
+            save it next to the request and use Complete Certificate Request to import and bind it.",
+            subject,
+            names.join(", "),
+            req.display()
+        )),
+        Ok(output) => (false, format!("Failed to create the certificate request:\n{}", output.stderr.trim())),
+        Err(e) => (false, format!("Failed to create the certificate request: {}", e)),
+    }
+}
+
+/// Imports an issued certificate for a pending request and returns its thumbprint and a summary.
+pub fn accept(file: &Path) -> Result<(String, String), String> {
+    let script = format!("$file = {}\n{}", exec::quote(&file.to_string_lossy()), ACCEPT_SCRIPT.trim());
+    let output = exec::change(&script).map_err(|e| format!("Failed to import the certificate: {}", e))?;
+    if !output.success {
+        return Err(format!("Failed to import the certificate:\n{}", output.stderr.trim()));
+    }
+    let values = exec::key_values(&output.stdout);
+    let get = |key: &str| values.get(key).cloned().unwrap_or_default();
+    // Nothing to read back when the step was only written to the export script
+    let thumbprint = match get("Thumbprint") {
+        t if t.is_empty() => "<thumbprint>".to_string(),
+        t => t,
+    };
+    if get("PrivateKey") == "False" {
+        return Err(format!(
+            "{} was imported but has no private key; it was not requested from this server.",
+            get("Subject")
+        ));
+    }
+    Ok((thumbprint.clone(), format!(
+        "Imported {} (expires {}) into LocalMachine\\My, thumbprint {}.",
+        get("Subject"),
+        get("Expires"),
+        thumbprint
+    )))
+}
+
+/// Binds a LocalMachine\My certificate to an IIS site (`iis <site> [port]`) or to the
+/// WinRM HTTPS listener (`winrm`).
+pub fn bind(thumbprint: &str, target: &str) -> (bool, String) {
+    let mut words: Vec<&str> = target.split_whitespace().collect();
+    let kind = if words.is_empty() { String::new() } else { words.remove(0).to_lowercase() };
+    match kind.as_str() {
+        "iis" => {
+            let port = match words.last().and_then(|p| p.parse::<u16>().ok()) {
+                Some(port) => {
+                    words.pop();
+                    port
+                }
+                None => 443,
+            };
+            if words.is_empty() {
+                return (false, "Enter the IIS site name, e.g. iis Default Web Site 443".to_string());
+            }
+            bind_iis(thumbprint, &words.join(" "), port)
+        }
+        "winrm" => {
+            let script = format!("$thumbprint = {}\n{}", exec::quote(thumbprint), WINRM_LISTENER_SCRIPT.trim());
+            match exec::change(&script) {
+                Ok(output) if output.success => (true, format!(
+                    "WinRM now listens for HTTPS on port 5986 with certificate {}.\n\n\
+                    Connect with: Enter-PSSession -ComputerName <name> -UseSSL",
+                    thumbprint
+                )),
+                Ok(output) => (false, format!("Failed to create the WinRM HTTPS listener:\n{}", output.stderr.trim())),
+                Err(e) => (false, format!("Failed to create the WinRM HTTPS listener: {}", e)),
+            }
+        }
+        _ => (false, "Expected: iis <site> [port] or winrm".to_string()),
+    }
+}
+
+/// Adds an HTTPS binding on `port` to an IIS site (if missing) and assigns the certificate.
+pub fn bind_iis(thumbprint: &str, site: &str, port: u16) -> (bool, String) {
+    let script = format!(
+        "$thumbprint = {}\n$site = {}\n$port = {}\n{}",
+        exec::quote(thumbprint),
+        exec::quote(site),
+        port,
+        IIS_BINDING_SCRIPT.trim()
+    );
+    match exec::change(&script) {
+        Ok(output) if output.success => {
+            (true, format!("{} now serves HTTPS on port {} with certificate {}.", site, port, thumbprint))
+        }
+        Ok(output) => (false, format!("Failed to bind the certificate to {}:\n{}", site, output.stderr.trim())),
+        Err(e) => (false, format!("Failed to bind the certificate to {}: {}", site, e)),
+    }
+}
@@ -0,0 +1,82 @@
+use std::path::PathBuf;
+
+use crate::{backup_dir, exec, history};
+
+/// Registry keys touched by the hardening, TLS, SMB, RDP, pagefile and
+/// environment actions.
+const REGISTRY_KEYS: &[&str] = &[
+    "HKLM\\SYSTEM\\CurrentControlSet\\Control\\SecurityProviders\\SCHANNEL",
+    "HKLM\\SYSTEM\\CurrentControlSet\\Control\\Lsa",
+    "HKLM\\SYSTEM\\CurrentControlSet\\Control\\Session Manager\\Memory Management",
+    "HKLM\\SYSTEM\\CurrentControlSet\\Control\\Session Manager\\Environment",
+    "HKLM\\SYSTEM\\CurrentControlSet\\Control\\Terminal Server",
+    "HKLM\\SYSTEM\\CurrentControlSet\\Services\\LanmanServer\\Parameters",
+    "HKLM\\SYSTEM\\CurrentControlSet\\Services\\LanmanWorkstation\\Parameters",
+    "HKLM\\SOFTWARE\\Policies",
+];
+
+const CHECKPOINT_SCRIPT: &str = r#"
+New-Item -ItemType Directory -Force -Path $dir | Out-Null
+$restorePoint = $false
+# System Restore only exists on client editions
+if ((Get-CimInstance Win32_OperatingSystem).ProductType -eq 1 -and (Get-Command Checkpoint-Computer -ErrorAction SilentlyContinue)) {
+    try {
+        Checkpoint-Computer -Description $reason -RestorePointType MODIFY_SETTINGS -ErrorAction Stop
+        $restorePoint = $true
+    } catch { "RestorePointError=$($_.Exception.Message)" }
+}
+"RestorePoint=$restorePoint"
+foreach ($key in $keys) {
+    $file = Join-Path $dir (($key -replace '^HKLM\\', '' -replace '[\\ ]', '_') + '.reg')
+    reg.exe export $key $file /y 2>&1 | Out-Null
+}
+secedit.exe /export /cfg (Join-Path $dir 'secpol.inf') /quiet | Out-Null
+auditpol.exe /backup /file:(Join-Path $dir 'auditpol.csv') | Out-Null
+powercfg.exe /getactivescheme | Set-Content -Path (Join-Path $dir 'powerplan.txt')
+if (Get-Command Get-WindowsFeature -ErrorAction SilentlyContinue) {
+    Get-WindowsFeature | Where-Object Installed | ForEach-Object Name | Set-Content -Path (Join-Path $dir 'features.txt')
+}
+"Files=$((Get-ChildItem -Path $dir).Count)"
+"#;
+
+/// How to put things back from a checkpoint directory, for the history log.
+const REVERT_HINT: &str = "To revert: reg import <file>.reg for the affected key, \
+    secedit /configure /db secedit.sdb /cfg secpol.inf, auditpol /restore /file:auditpol.csv, \
+    and compare features.txt with Get-WindowsFeature.";
+
+/// Directory for a new checkpoint and the script that fills it.
+pub fn script(reason: &str) -> (PathBuf, String) {
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let dir = backup_dir().join(format!("Checkpoint_{}", timestamp));
+    let keys: Vec<String> = REGISTRY_KEYS.iter().map(|k| exec::quote(k)).collect();
+    let script = format!(
+        "$dir = {}\n$reason = {}\n$keys = @({})\n{}",
+        exec::quote(&dir.to_string_lossy()),
+        exec::quote(reason),
+        keys.join(", "),
+        CHECKPOINT_SCRIPT.trim()
+    );
+    (dir, script)
+}
+
+/// Creates a checkpoint before `reason` and records it in the history log.
+/// Returns a one-line summary for the action's result.
+pub fn create(reason: &str) -> Result<String, String> {
+    let (dir, script) = script(reason);
+    let output = exec::change(&script).map_err(|e| format!("Failed to create a checkpoint: {}", e))?;
+    if !output.success {
+        return Err(format!("Failed to create a checkpoint:\n{}", output.stderr.trim()));
+    }
+
+    let values = exec::key_values(&output.stdout);
+    let summary = if values.get("RestorePoint").map(String::as_str) == Some("True") {
+        format!("System restore point and registry/feature snapshot in {}", dir.display())
+    } else {
+        format!("Registry/feature snapshot in {}", dir.display())
+    };
+    history::record(&format!("Checkpoint before: {}\n{}\n{}", reason, summary, REVERT_HINT));
+    Ok(summary)
+}
@@ -0,0 +1,144 @@
+use std::path::Path;
+
+use crate::{cache, exec, plan::Plan};
+
+/// Deletes everything under `$paths` that isn't locked and prints the space freed.
+const REMOVE_CONTENTS: &str = r#"
+function Get-Size($paths) { [double](Get-ChildItem -Path $paths -Recurse -Force -File -ErrorAction SilentlyContinue | Measure-Object -Property Length -Sum).Sum }
+$before = Get-Size $paths
+Get-ChildItem -Path $paths -Force -ErrorAction SilentlyContinue | Remove-Item -Recurse -Force -ErrorAction SilentlyContinue
+"Reclaimed {0:N1} MB" -f (($before - (Get-Size $paths)) / 1MB)
+"#;
+
+const SIZES_SCRIPT: &str = r#"
+function Get-Size($paths) { [double](Get-ChildItem -Path $paths -Recurse -Force -File -ErrorAction SilentlyContinue | Measure-Object -Property Length -Sum).Sum }
+"temp={0:N1}" -f ((Get-Size @("$env:SystemRoot\Temp\*", "$env:SystemDrive\Users\*\AppData\Local\Temp\*")) / 1MB)
+"updates={0:N1}" -f ((Get-Size "$env:SystemRoot\SoftwareDistribution\Download\*") / 1MB)
+"cbs={0:N1}" -f ((Get-Size "$env:SystemRoot\Logs\CBS\*") / 1MB)
+"free={0:N1}" -f ((Get-PSDrive -Name $env:SystemDrive.TrimEnd(':')).Free / 1GB)
+"#;
+
+/// Categories offered, in the order they run.
+const CATEGORIES: [&str; 4] = ["temp", "updates", "cbs", "components"];
+
+/// Plans cleaning the chosen categories (`temp updates components`, plus `cbs` for CBS logs).
+/// Each step prints the space it reclaimed.
+pub fn cleanup_plan(value: &str) -> Result<Plan, String> {
+    let chosen: Vec<String> = value.split([' ', ',']).filter(|c| !c.is_empty()).map(str::to_lowercase).collect();
+    if let Some(unknown) = chosen.iter().find(|c| !CATEGORIES.contains(&c.as_str())) {
+        return Err(format!("Unknown category '{}': use temp, updates, cbs or components", unknown));
+    }
+    if chosen.is_empty() {
+        return Err("Choose at least one category.".to_string());
+    }
+
+    let output = exec::powershell(SIZES_SCRIPT).map_err(|e| format!("Failed to measure the system drive: {}", e))?;
+    let sizes = exec::key_values(&output.stdout);
+    let size = |key: &str| sizes.get(key).cloned().unwrap_or_else(|| "?".to_string());
+
+    let mut plan = Plan::new("Free up space on the system drive");
+    plan.note(format!("{} GB free before cleanup", size("free")));
+    for category in CATEGORIES.iter().filter(|c| chosen.iter().any(|x| x == *c)) {
+        match *category {
+            "temp" => plan.step(
+                format!("Clear the Windows and user temp directories ({} MB)", size("temp")),
+                format!(
+                    "$paths = @(\"$env:SystemRoot\\Temp\\*\", \"$env:SystemDrive\\Users\\*\\AppData\\Local\\Temp\\*\")\n{}",
+                    REMOVE_CONTENTS.trim()
+                ),
+            ),
+            "updates" => plan.step(
+                format!("Delete old Windows Update downloads ({} MB)", size("updates")),
+                format!(
+                    "Stop-Service -Name wuauserv, bits -Force\n\
+                    $paths = \"$env:SystemRoot\\SoftwareDistribution\\Download\\*\"\n{}\n\
+                    Start-Service -Name wuauserv, bits",
+                    REMOVE_CONTENTS.trim()
+                ),
+            ),
+            "cbs" => plan.step(
+                format!("Delete CBS servicing logs ({} MB)", size("cbs")),
+                format!(
+                    "Stop-Service -Name TrustedInstaller -Force -ErrorAction SilentlyContinue\n\
+                    $paths = \"$env:SystemRoot\\Logs\\CBS\\*\"\n{}",
+                    REMOVE_CONTENTS.trim()
+                ),
+            ),
+            _ => plan.step(
+                "Clean up superseded components (DISM /StartComponentCleanup, can take a while)",
+                "$drive = Get-PSDrive -Name $env:SystemDrive.TrimEnd(':')\n\
+                $before = $drive.Free\n\
+                Dism.exe /Online /Cleanup-Image /StartComponentCleanup | Out-Null\n\
+                if ($LASTEXITCODE -ne 0) { throw \"DISM failed with exit code $LASTEXITCODE\" }\n\
+                \"Reclaimed {0:N1} MB\" -f (((Get-PSDrive -Name $drive.Name).Free - $before) / 1MB)",
+            ),
+        }
+    }
+    if !chosen.iter().any(|c| c == "cbs") {
+        plan.note(format!("CBS logs ({} MB) are kept; add cbs to delete them", size("cbs")));
+    }
+    plan.note("Files in use are skipped, so a category may reclaim less than its size.");
+    Ok(plan)
+}
+
+/// Files and folders the installers in this app leave in %TEMP%, including partial downloads.
+/// Install logs are not included; they are kept for troubleshooting.
+fn is_installer_residue(name: &str) -> bool {
+    let name = name.strip_suffix(".part.state").or_else(|| name.strip_suffix(".part")).unwrap_or(name);
+    let name = name.to_lowercase();
+    ["winget_install", "netbird_installer.exe", "windowsadmincenter.msi", "sqlexpress", "sysmon.zip", "sysmon", "sysmon-default.xml"]
+        .contains(&name.as_str())
+        || (name.starts_with("docker-") && name.ends_with(".zip"))
+        || (name.starts_with("vc_redist.") && name.ends_with(".exe"))
+}
+
+fn size_on_disk(path: &Path) -> u64 {
+    match std::fs::read_dir(path) {
+        Ok(entries) => entries.flatten().map(|e| size_on_disk(&e.path())).sum(),
+        Err(_) => std::fs::metadata(path).map(|m| m.len()).unwrap_or(0),
+    }
+}
+
+fn remove_script(path: &Path) -> String {
+    format!("Remove-Item -LiteralPath {} -Recurse -Force -ErrorAction Stop", exec::quote(&path.to_string_lossy()))
+}
+
+/// Removes installer files from %TEMP% once an install has succeeded. Anything inside the
+/// download cache is left alone.
+pub fn remove_installer_files(path: &Path) -> Result<(), String> {
+    if path.starts_with(cache::dir()) || !path.exists() {
+        return Ok(());
+    }
+    match exec::change(&remove_script(path)) {
+        Ok(output) if output.success => Ok(()),
+        Ok(output) => Err(format!("Failed to remove {}: {}", path.display(), output.stderr.trim())),
+        Err(e) => Err(format!("Failed to remove {}: {}", path.display(), e)),
+    }
+}
+
+/// Plans removing installer leftovers from %TEMP%, one step per file or folder. The download
+/// cache is not touched.
+pub fn temp_residue_plan() -> Plan {
+    let mut plan = Plan::new("Clean installer temp files");
+    let temp = std::env::temp_dir();
+    let mut residue: Vec<_> = std::fs::read_dir(&temp)
+        .map(|entries| entries.flatten().map(|e| e.path()).collect())
+        .unwrap_or_default();
+    residue.retain(|p| {
+        p.file_name().is_some_and(|n| is_installer_residue(&n.to_string_lossy())) && !p.starts_with(cache::dir())
+    });
+    residue.sort();
+    for path in &residue {
+        plan.step(
+            format!("Delete {} ({:.1} MB)", path.display(), size_on_disk(path) as f64 / 1_048_576.0),
+            remove_script(path),
+        );
+    }
+    if residue.is_empty() {
+        plan.note(format!("No installer leftovers in {}.", temp.display()));
+    } else if residue.iter().any(|p| p.extension().is_some_and(|e| e == "part")) {
+        plan.note("Partial downloads are included; the next install of that package starts from zero.");
+    }
+    plan.note(format!("The download cache is kept ({}); purge it from the Download Cache screen.", cache::summary()));
+    plan
+}
@@ -0,0 +1,92 @@
+use crate::exec;
+
+const VALIDATE_SCRIPT: &str = r#"
+$report = Test-Cluster -Node $nodes -Ignore 'Storage' -WarningVariable w -WarningAction SilentlyContinue -ErrorAction Stop
+"Report=$($report.FullName)"
+$w | ForEach-Object { "Warning=$($_.Message -replace '\s+', ' ')" }
+"#;
+
+/// Installs Failover Clustering where it is missing, then runs cluster validation
+/// (without the disruptive storage tests) across `nodes` and summarizes the report.
+pub fn validate(nodes: &[String]) -> (bool, String) {
+    if nodes.len() < 2 {
+        return (false, "Enter at least two nodes to validate.".to_string());
+    }
+
+    let mut report = vec!["Failover Clustering feature:".to_string()];
+    for node in nodes {
+        let script = format!(
+            "if ((Get-WindowsFeature -Name Failover-Clustering -ComputerName {0} -ErrorAction Stop).Installed) {{ 'State=present' }} \
+            else {{ $r = Install-WindowsFeature -Name Failover-Clustering -IncludeManagementTools -ComputerName {0} -ErrorAction Stop; \
+            \"State=installed\"; \"RestartNeeded=$($r.RestartNeeded)\" }}",
+            exec::quote(node)
+        );
+        match exec::change(&script) {
+            Ok(output) if output.success => {
+                let values = exec::key_values(&output.stdout);
+                let state = values.get("State").map(String::as_str).unwrap_or("installed");
+                let restart = if values.get("RestartNeeded").map(String::as_str) == Some("Yes") {
+                    " (restart required)"
+                } else {
+                    ""
+                };
+                report.push(format!("  [OK]   {}: {}{}", node, state, restart));
+            }
+            Ok(output) => {
+                return (false, format!(
+                    "{}\n  [FAIL] {}: {}\n\nFix the node and run the check again.",
+                    report.join("\n"),
+                    node,
+                    output.stderr.trim()
+                ));
+            }
+            Err(e) => return (false, format!("Failed to check {}: {}", node, e)),
+        }
+    }
+
+    let targets: Vec<String> = nodes.iter().map(|n| exec::quote(n)).collect();
+    let script = format!("$nodes = @({})\n{}", targets.join(", "), VALIDATE_SCRIPT);
+    let output = match exec::powershell(&script) {
+        Ok(output) => output,
+        Err(e) => return (false, format!("Failed to run Test-Cluster: {}", e)),
+    };
+    if !output.success {
+        return (false, format!(
+            "{}\n\nTest-Cluster could not complete:\n{}",
+            report.join("\n"),
+            output.stderr.trim()
+        ));
+    }
+
+    let values = exec::key_values(&output.stdout);
+    let warnings: Vec<&str> = output.stdout.lines().filter_map(|l| l.strip_prefix("Warning=")).collect();
+    let mut blocking = false;
+    let mut result = String::from("unknown");
+    let mut findings = Vec::new();
+    for warning in &warnings {
+        if let Some(outcome) = warning.strip_prefix("Test Result: ") {
+            result = outcome.split('.').next().unwrap_or(outcome).trim().to_string();
+            blocking |= result.contains("NotApproved") || result.contains("Failed");
+        } else if warning.contains("failed") || warning.contains("failures") {
+            blocking = true;
+            findings.push(format!("  [FAIL] {}", warning));
+        } else {
+            findings.push(format!("  [WARN] {}", warning));
+        }
+    }
+    if findings.is_empty() {
+        findings.push("  No warnings or failures.".to_string());
+    }
+
+    report.push(String::new());
+    report.push(format!("Validation result: {}", result));
+    report.extend(findings);
+    report.push(String::new());
+    report.push(format!("Full report: {}", values.get("Report").cloned().unwrap_or_default()));
+    report.push("Storage tests were skipped because they take shared disks offline.".to_string());
+    if blocking {
+        report.push(String::new());
+        report.push("Blocking failures were found; resolve them before creating the cluster.".to_string());
+    }
+    (!blocking, report.join("\n"))
+}
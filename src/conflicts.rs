@@ -0,0 +1,118 @@
+//! Checks a restore against the workloads running on this server, using a table of known
+//! conflicts: restoring Hyper-V next to VirtualBox or VMware Workstation, WDS next to a DHCP
+//! server on the same host, or DNS and IIS where another program already holds their ports.
+
+use crate::exec;
+
+/// A role or feature that conflicts with a workload, when that workload is running here.
+struct Rule {
+    /// Feature name from the backup, as `Install-WindowsFeature` takes it.
+    feature: &'static str,
+    /// Key in the reading of `WORKLOADS_SCRIPT`; the rule applies when its value is
+    /// `True` or names the programs found.
+    workload: &'static str,
+    conflict: &'static str,
+}
+
+const RULES: &[Rule] = &[
+    Rule {
+        feature: "Hyper-V",
+        workload: "VirtualBox",
+        conflict: "VirtualBox is running. Once the Hyper-V hypervisor starts, VirtualBox VMs run much slower \
+                   or fail to start (older VirtualBox versions cannot run at all).",
+    },
+    Rule {
+        feature: "Hyper-V",
+        workload: "VMware",
+        conflict: "VMware Workstation is running. Versions before 15.5 cannot start VMs once the Hyper-V \
+                   hypervisor runs, and later ones run them through it.",
+    },
+    Rule {
+        feature: "WDS",
+        workload: "DhcpServer",
+        conflict: "The DHCP Server service is running here. WDS must then not listen on UDP port 67 and needs \
+                   DHCP option 60 set (wdsutil /Set-Server /UseDhcpPorts:No /DhcpOption60:Yes).",
+    },
+    Rule {
+        feature: "DHCP",
+        workload: "Wds",
+        conflict: "Windows Deployment Services is running here and may hold UDP port 67. Set it to stop \
+                   listening on the DHCP ports (wdsutil /Set-Server /UseDhcpPorts:No /DhcpOption60:Yes).",
+    },
+    Rule {
+        feature: "DNS",
+        workload: "Port53",
+        conflict: "Another program listens on port 53, so the DNS Server service cannot bind to it.",
+    },
+    Rule {
+        feature: "Web-Server",
+        workload: "Port80",
+        conflict: "Another web server listens on port 80 or 443, so IIS sites bound to them will not start.",
+    },
+];
+
+/// Reads the running workloads the rules look at, as `Key=Value` lines.
+const WORKLOADS_SCRIPT: &str = r#"
+$running = { param($names) [bool](Get-Service -Name $names -ErrorAction SilentlyContinue | Where-Object Status -eq 'Running') }
+$owners = { param($ids) @($ids | Sort-Object -Unique | ForEach-Object { (Get-Process -Id $_ -ErrorAction SilentlyContinue).ProcessName }) -join ',' }
+"VirtualBox=$([bool](Get-Process -Name VBoxSVC,VBoxSDS,VirtualBoxVM -ErrorAction SilentlyContinue))"
+"VMware=$(& $running @('VMAuthdService','vmware-authd'))"
+"DhcpServer=$(& $running 'DHCPServer')"
+"Wds=$(& $running 'WDSServer')"
+$dns = @(Get-NetUDPEndpoint -LocalPort 53 -ErrorAction SilentlyContinue) + @(Get-NetTCPConnection -LocalPort 53 -State Listen -ErrorAction SilentlyContinue)
+"Port53=$(& $owners ($dns | ForEach-Object OwningProcess))"
+$web = Get-NetTCPConnection -LocalPort 80,443 -State Listen -ErrorAction SilentlyContinue | Where-Object OwningProcess -ne 4
+"Port80=$(& $owners ($web | ForEach-Object OwningProcess))"
+"#;
+
+/// The conflicts for `features` given a reading of `WORKLOADS_SCRIPT`.
+fn matching(features: &[String], stdout: &str) -> Vec<String> {
+    let workloads = exec::key_values(stdout);
+    RULES
+        .iter()
+        .filter(|rule| features.iter().any(|f| f.eq_ignore_ascii_case(rule.feature)))
+        .filter_map(|rule| {
+            let value = workloads.get(rule.workload).map(|v| v.trim()).unwrap_or_default();
+            // The DNS Server service itself, when a restore only repairs it
+            let programs: Vec<&str> = value.split(',').filter(|p| !p.is_empty() && !p.eq_ignore_ascii_case("dns")).collect();
+            match value {
+                "True" => Some(format!("{}: {}", rule.feature, rule.conflict)),
+                "False" | "" => None,
+                _ if programs.is_empty() => None,
+                _ => Some(format!("{}: {} ({})", rule.feature, rule.conflict, programs.join(", "))),
+            }
+        })
+        .collect()
+}
+
+/// Conflicts between the roles and features about to be installed and the workloads running
+/// here, one line per rule that applies.
+pub fn check(features: &[String]) -> Result<Vec<String>, String> {
+    if !RULES.iter().any(|rule| features.iter().any(|f| f.eq_ignore_ascii_case(rule.feature))) {
+        return Ok(Vec::new());
+    }
+    let output = exec::powershell(WORKLOADS_SCRIPT).map_err(|e| format!("Failed to read the running workloads: {}", e))?;
+    if !output.success {
+        return Err(format!("Failed to read the running workloads:\n{}", output.stderr.trim()));
+    }
+    Ok(matching(features, &output.stdout))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn applies_the_rules_for_the_features_being_restored() {
+        let reading = "VirtualBox=True\nVMware=False\nDhcpServer=True\nWds=False\nPort53=dns\nPort80=httpd,nginx\n";
+        let features = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+
+        let conflicts = matching(&features(&["Hyper-V", "WDS", "DNS", "Web-Server"]), reading);
+
+        assert_eq!(conflicts.len(), 3);
+        assert!(conflicts[0].starts_with("Hyper-V: VirtualBox is running."));
+        assert!(conflicts[1].starts_with("WDS: The DHCP Server service is running here."));
+        assert!(conflicts[2].ends_with("(httpd, nginx)"));
+        assert!(matching(&features(&["FS-FileServer", "DHCP"]), reading).is_empty());
+    }
+}
@@ -0,0 +1,265 @@
+use crate::{exec, performance};
+use crate::{CheckItem, CheckStatus, Remediation};
+
+const CRASH_CONTROL_KEY: &str = "HKLM:\\SYSTEM\\CurrentControlSet\\Control\\CrashControl";
+
+/// Crash dump settings, memory, the system drive pagefile and free space where the dump is
+/// written, as `key=value` lines. Sizes are in MB; a pagefile maximum of 0 means system managed.
+const STATUS_SCRIPT: &str = r#"
+$cc = Get-ItemProperty 'HKLM:\SYSTEM\CurrentControlSet\Control\CrashControl'
+"Enabled=$($cc.CrashDumpEnabled)"
+"FilterPages=$($cc.FilterPages)"
+$dump = [Environment]::ExpandEnvironmentVariables($cc.DumpFile)
+"DumpFile=$dump"
+"Dedicated=$($cc.DedicatedDumpFile)"
+"AutoReboot=$($cc.AutoReboot)"
+"Overwrite=$($cc.Overwrite)"
+"RamMB=$([math]::Round((Get-CimInstance Win32_OperatingSystem).TotalVisibleMemorySize / 1KB))"
+"SystemDrive=$env:SystemDrive"
+"AutoPagefile=$((Get-CimInstance Win32_ComputerSystem).AutomaticManagedPagefile)"
+$setting = Get-CimInstance Win32_PageFileSetting | Where-Object { $_.Name -like "$env:SystemDrive*" } | Select-Object -First 1
+if ($setting) { "PagefileMaxMB=$($setting.MaximumSize)" }
+$usage = Get-CimInstance Win32_PageFileUsage | Where-Object { $_.Name -like "$env:SystemDrive*" } | Select-Object -First 1
+if ($usage) { "PagefileMB=$($usage.AllocatedBaseSize)" }
+$drive = Get-PSDrive -Name $dump.Substring(0, 1) -PSProvider FileSystem -ErrorAction SilentlyContinue
+if ($drive) { "FreeMB=$([math]::Floor($drive.Free / 1MB))" }
+"#;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DumpType {
+    None,
+    Small,
+    Kernel,
+    Complete,
+    Automatic,
+    Active,
+}
+
+impl DumpType {
+    const ALL: [DumpType; 6] =
+        [DumpType::None, DumpType::Small, DumpType::Kernel, DumpType::Complete, DumpType::Automatic, DumpType::Active];
+
+    fn word(self) -> &'static str {
+        match self {
+            DumpType::None => "none",
+            DumpType::Small => "small",
+            DumpType::Kernel => "kernel",
+            DumpType::Complete => "complete",
+            DumpType::Automatic => "automatic",
+            DumpType::Active => "active",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            DumpType::None => "None",
+            DumpType::Small => "Small memory dump (256 KB)",
+            DumpType::Kernel => "Kernel memory dump",
+            DumpType::Complete => "Complete memory dump",
+            DumpType::Automatic => "Automatic memory dump",
+            DumpType::Active => "Active memory dump",
+        }
+    }
+
+    /// `CrashDumpEnabled` and `FilterPages`; an active dump is a complete dump with user pages filtered.
+    fn registry(self) -> (u32, u32) {
+        match self {
+            DumpType::None => (0, 0),
+            DumpType::Complete => (1, 0),
+            DumpType::Active => (1, 1),
+            DumpType::Kernel => (2, 0),
+            DumpType::Small => (3, 0),
+            DumpType::Automatic => (7, 0),
+        }
+    }
+
+    fn from_registry(enabled: &str, filter_pages: &str) -> Option<DumpType> {
+        DumpType::ALL.into_iter().find(|t| {
+            let (e, f) = t.registry();
+            enabled == e.to_string() && (e != 1 || (f == 1) == (filter_pages == "1"))
+        })
+    }
+
+    /// Space the dump needs in the pagefile and on disk, in MB. Kernel dumps are estimated at
+    /// a third of memory; complete dumps need all of it plus 257 MB for the header.
+    fn needed_mb(self, ram_mb: u64) -> u64 {
+        match self {
+            DumpType::None => 0,
+            DumpType::Small => 1,
+            DumpType::Kernel | DumpType::Automatic => ram_mb / 3,
+            DumpType::Active => ram_mb / 2,
+            DumpType::Complete => ram_mb + 257,
+        }
+    }
+}
+
+/// Reads `kernel`, `complete` and the other type words.
+pub fn parse_type(text: &str) -> Result<DumpType, String> {
+    let text = text.trim();
+    DumpType::ALL.into_iter().find(|t| text.eq_ignore_ascii_case(t.word())).ok_or_else(|| {
+        "Enter none, small, kernel, complete, automatic or active.".to_string()
+    })
+}
+
+/// The current dump type word, to prefill the prompt.
+pub fn type_spec() -> String {
+    let Ok(output) = exec::powershell(STATUS_SCRIPT) else { return String::new() };
+    let values = exec::key_values(&output.stdout);
+    let value = |key: &str| values.get(key).map(|v| v.trim().to_string()).unwrap_or_default();
+    DumpType::from_registry(&value("Enabled"), &value("FilterPages")).map(|t| t.word().to_string()).unwrap_or_default()
+}
+
+/// The dump type and file, and whether the system drive pagefile and the dump drive have room
+/// for that dump. A pagefile that is too small carries a fix that sizes it for the dump.
+pub fn checks() -> Result<Vec<CheckItem>, String> {
+    let output = exec::powershell(STATUS_SCRIPT).map_err(|e| format!("Failed to read the crash dump settings: {}", e))?;
+    if !output.success {
+        return Err(format!("Failed to read the crash dump settings:\n{}", output.stderr.trim()));
+    }
+    let values = exec::key_values(&output.stdout);
+    let value = |key: &str| values.get(key).map(|v| v.trim().to_string()).unwrap_or_default();
+    let mb = |key: &str| value(key).parse::<u64>().ok();
+    let item = |name: &str, status, detail: String, advice: Option<String>| CheckItem {
+        name: name.to_string(),
+        status,
+        detail,
+        remediation: None,
+        advice,
+    };
+
+    let dump_type = DumpType::from_registry(&value("Enabled"), &value("FilterPages"));
+    let ram = mb("RamMB").unwrap_or(0);
+    let needed = dump_type.map_or(0, |t| t.needed_mb(ram));
+    let mut items = vec![match dump_type {
+        Some(DumpType::None) => item(
+            "Dump type",
+            CheckStatus::Fail,
+            "None: a crash leaves nothing for vendor support".to_string(),
+            Some("Press t to pick a dump type; support usually asks for kernel or complete.".to_string()),
+        ),
+        Some(t) => item("Dump type", CheckStatus::Pass, t.label().to_string(), None),
+        None => item("Dump type", CheckStatus::Unknown, format!("Unrecognised (CrashDumpEnabled = {})", value("Enabled")), None),
+    }];
+    if matches!(dump_type, Some(DumpType::None) | Some(DumpType::Small)) {
+        return Ok(items);
+    }
+
+    let dump_file = value("DumpFile");
+    items.push(item(
+        "Dump file",
+        CheckStatus::Pass,
+        format!(
+            "{}{}",
+            dump_file,
+            if value("Overwrite") == "0" { " (kept: the next crash is not written)" } else { " (overwritten)" }
+        ),
+        None,
+    ));
+
+    let system_drive = value("SystemDrive");
+    let dedicated = value("Dedicated");
+    let system_managed = value("AutoPagefile").eq_ignore_ascii_case("True") || mb("PagefileMaxMB") == Some(0);
+    let size = if system_managed { mb("PagefileMB") } else { mb("PagefileMaxMB") }.unwrap_or(0);
+    let name = format!("Pagefile on {}", system_drive);
+    items.push(if !dedicated.is_empty() {
+        item(&name, CheckStatus::Pass, format!("Not needed: dumps go to the dedicated file {}", dedicated), None)
+    } else if size >= needed || (system_managed && dump_type == Some(DumpType::Automatic)) {
+        item(
+            &name,
+            CheckStatus::Pass,
+            format!("{} MB{} (dump needs about {} MB)", size, if system_managed { ", system managed" } else { "" }, needed),
+            None,
+        )
+    } else {
+        let path = format!("{}\\pagefile.sys", system_drive);
+        CheckItem {
+            remediation: Some(Remediation {
+                summary: format!(
+                    "Replace the pagefiles with a fixed {} MB pagefile at {}, large enough for the dump. Takes effect after a restart.",
+                    needed, path
+                ),
+                script: performance::fixed_pagefile_script(&path, needed as u32, needed as u32),
+            }),
+            ..item(
+                &name,
+                CheckStatus::Fail,
+                format!("{} MB{}, but the dump needs about {} MB", size, if system_managed { ", system managed" } else { "" }, needed),
+                None,
+            )
+        }
+    });
+
+    let drive = dump_file.get(..2).unwrap_or(&system_drive).to_string();
+    items.push(match mb("FreeMB") {
+        Some(free) if free >= needed => {
+            item(&format!("Free space on {}", drive), CheckStatus::Pass, format!("{} MB free", free), None)
+        }
+        Some(free) => item(
+            &format!("Free space on {}", drive),
+            CheckStatus::Fail,
+            format!("{} MB free, the dump needs about {} MB", free, needed),
+            Some("Free up space, or point DumpFile under CrashControl at a drive with room.".to_string()),
+        ),
+        None => item(&format!("Free space on {}", drive), CheckStatus::Unknown, "Not reported".to_string(), None),
+    });
+    items.push(item(
+        "Restart after a crash",
+        CheckStatus::Pass,
+        if value("AutoReboot") == "0" { "No: the blue screen stays up" } else { "Yes" }.to_string(),
+        None,
+    ));
+    Ok(items)
+}
+
+/// Sets the dump type from `text`; takes effect after a restart.
+pub fn set_type(text: &str) -> Result<String, String> {
+    let dump_type = parse_type(text)?;
+    let (enabled, filter_pages) = dump_type.registry();
+    let script = format!(
+        "Set-ItemProperty -Path '{0}' -Name CrashDumpEnabled -Value {1} -Type DWord -ErrorAction Stop\n\
+        Set-ItemProperty -Path '{0}' -Name FilterPages -Value {2} -Type DWord -ErrorAction Stop",
+        CRASH_CONTROL_KEY, enabled, filter_pages
+    );
+    match exec::change(&script) {
+        Ok(output) if output.success => {
+            Ok(format!("Dump type set to {}. Takes effect after a restart.", dump_type.label()))
+        }
+        Ok(output) => Err(format!("Failed to set the dump type:\n{}", output.stderr.trim())),
+        Err(e) => Err(format!("Failed to set the dump type: {}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::FakeRunner;
+
+    #[test]
+    fn offers_a_larger_pagefile_for_a_complete_dump() {
+        FakeRunner::new()
+            .on(
+                "CrashControl",
+                "Enabled=1\nFilterPages=\nDumpFile=C:\\Windows\\MEMORY.DMP\nRamMB=16384\nSystemDrive=C:\n\
+                AutoPagefile=False\nPagefileMaxMB=4096\nFreeMB=40000\n",
+            )
+            .install();
+
+        let items = checks().unwrap_or_default();
+
+        assert_eq!(items[0].detail, "Complete memory dump");
+        let pagefile = items.iter().find(|i| i.name == "Pagefile on C:").expect("pagefile item");
+        assert!(pagefile.status == CheckStatus::Fail);
+        assert!(pagefile.remediation.as_ref().is_some_and(|r| r.script.contains("InitialSize = [uint32]16641")));
+        assert!(items.iter().any(|i| i.name == "Free space on C:" && i.status == CheckStatus::Pass));
+    }
+
+    #[test]
+    fn sets_an_active_dump_with_filtered_pages() {
+        let runner = FakeRunner::new().install();
+
+        assert!(set_type("full").is_err());
+        assert!(set_type("Active").is_ok());
+        assert!(runner.ran("-Name CrashDumpEnabled -Value 1 "));
+        assert!(runner.ran("-Name FilterPages -Value 1 "));
+    }
+}
@@ -0,0 +1,257 @@
+//! Timestamps are kept in UTC wherever they are stored (Unix seconds in backup file names,
+//! `YYYY-MM-DD HH:MM:SS` in the history log and the cache index), so backups copied between
+//! servers in different time zones still sort by when they were made. They are shown in the
+//! server's time zone, with the offset in force at that instant (so a backup from before a
+//! daylight saving change shows the time it was taken), and the Windows short date and long
+//! time patterns of the current user.
+
+use std::{
+    sync::OnceLock,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use chrono::{DateTime, Datelike, Local, NaiveDateTime, TimeZone, Timelike};
+
+use crate::exec;
+
+/// Stored timestamps, e.g. `2024-03-01 10:15:00`.
+const UTC_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// The current user's culture, as `key=value` lines.
+const LOCALE_SCRIPT: &str = r#"
+$f = (Get-Culture).DateTimeFormat
+"Date=$($f.ShortDatePattern)"
+"Time=$($f.LongTimePattern)"
+"DateSeparator=$($f.DateSeparator)"
+"TimeSeparator=$($f.TimeSeparator)"
+"AM=$($f.AMDesignator)"
+"PM=$($f.PMDesignator)"
+"Months=$($f.MonthNames[0..11] -join '|')"
+"ShortMonths=$($f.AbbreviatedMonthNames[0..11] -join '|')"
+"Days=$($f.DayNames -join '|')"
+"ShortDays=$($f.AbbreviatedDayNames -join '|')"
+"#;
+
+/// How dates are written for the user. The default is ISO 8601, used when the culture can't be
+/// read.
+#[derive(Clone, Debug)]
+struct Locale {
+    pattern: String,
+    date_separator: String,
+    time_separator: String,
+    am: String,
+    pm: String,
+    months: Vec<String>,
+    short_months: Vec<String>,
+    days: Vec<String>,
+    short_days: Vec<String>,
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        let names = |list: &str| list.split('|').map(String::from).collect();
+        Locale {
+            pattern: "yyyy-MM-dd HH:mm:ss".to_string(),
+            date_separator: "-".to_string(),
+            time_separator: ":".to_string(),
+            am: "AM".to_string(),
+            pm: "PM".to_string(),
+            months: names("January|February|March|April|May|June|July|August|September|October|November|December"),
+            short_months: names("Jan|Feb|Mar|Apr|May|Jun|Jul|Aug|Sep|Oct|Nov|Dec"),
+            days: names("Sunday|Monday|Tuesday|Wednesday|Thursday|Friday|Saturday"),
+            short_days: names("Sun|Mon|Tue|Wed|Thu|Fri|Sat"),
+        }
+    }
+}
+
+fn parse_locale(stdout: &str) -> Option<Locale> {
+    let values = exec::key_values(stdout);
+    let names = |key: &str, count: usize| {
+        let list: Vec<String> = values.get(key)?.split('|').map(String::from).collect();
+        (list.len() == count).then_some(list)
+    };
+    let date = values.get("Date").filter(|p| !p.is_empty())?;
+    let time = values.get("Time").filter(|p| !p.is_empty())?;
+    Some(Locale {
+        pattern: format!("{} {}", date, time),
+        date_separator: values.get("DateSeparator").cloned().unwrap_or_else(|| "/".to_string()),
+        time_separator: values.get("TimeSeparator").cloned().unwrap_or_else(|| ":".to_string()),
+        am: values.get("AM").cloned().unwrap_or_default(),
+        pm: values.get("PM").cloned().unwrap_or_default(),
+        months: names("Months", 12)?,
+        short_months: names("ShortMonths", 12)?,
+        days: names("Days", 7)?,
+        short_days: names("ShortDays", 7)?,
+    })
+}
+
+/// Read once per run; the culture doesn't change under a running session often enough to pay
+/// for a PowerShell call per timestamp.
+fn locale() -> &'static Locale {
+    static LOCALE: OnceLock<Locale> = OnceLock::new();
+    LOCALE.get_or_init(|| {
+        if cfg!(test) {
+            return Locale::default();
+        }
+        exec::powershell(LOCALE_SCRIPT)
+            .ok()
+            .and_then(|output| parse_locale(&output.stdout))
+            .unwrap_or_default()
+    })
+}
+
+/// Seconds since the Unix epoch, as embedded in backup file names.
+pub fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// `secs` as UTC `YYYY-MM-DD HH:MM:SS`, the form stored in logs and indexes.
+pub fn utc(secs: i64) -> String {
+    DateTime::from_timestamp(secs, 0).map(|t| t.format(UTC_FORMAT).to_string()).unwrap_or_default()
+}
+
+/// Reads a stored UTC `YYYY-MM-DD HH:MM:SS` back into Unix seconds.
+pub fn parse_utc(text: &str) -> Option<i64> {
+    NaiveDateTime::parse_from_str(text.trim(), UTC_FORMAT).ok().map(|t| t.and_utc().timestamp())
+}
+
+/// `secs` in the server's time zone and the user's date and time format.
+pub fn local(secs: i64) -> String {
+    in_zone(secs, &Local, locale())
+}
+
+fn in_zone<Tz: TimeZone>(secs: i64, zone: &Tz, locale: &Locale) -> String {
+    zone.timestamp_opt(secs, 0).earliest().map(|time| format(&time, locale)).unwrap_or_else(|| utc(secs))
+}
+
+/// A stored UTC timestamp shown as `local`; text that isn't one is returned as is.
+pub fn local_from_utc(text: &str) -> String {
+    parse_utc(text).map(local).unwrap_or_else(|| text.to_string())
+}
+
+/// The Unix seconds in a backup file name, e.g. `ServerRoles_1709288100.xml` or
+/// `ACLs_D_Shares_1709288100.acls`.
+pub fn from_file_name(name: &str) -> Option<i64> {
+    let stem = name.rsplit_once('.').map_or(name, |(stem, _)| stem);
+    stem.split('_')
+        .rev()
+        .find(|part| part.len() == 10 && part.bytes().all(|b| b.is_ascii_digit()))
+        .and_then(|part| part.parse().ok())
+}
+
+/// Replaces each `[YYYY-MM-DD HH:MM:SS UTC]` stamp of the history log with local time.
+pub fn localize_history(log: &str) -> String {
+    localize_history_in(log, &Local, locale())
+}
+
+fn localize_history_in<Tz: TimeZone>(log: &str, zone: &Tz, locale: &Locale) -> String {
+    log.split_inclusive('\n')
+        .map(|line| {
+            let stamp = line.strip_prefix('[').and_then(|rest| rest.split_once(" UTC]"));
+            match stamp.and_then(|(stamp, rest)| Some((parse_utc(stamp)?, rest))) {
+                Some((secs, rest)) => format!("[{}]{}", in_zone(secs, zone, locale), rest),
+                None => line.to_string(),
+            }
+        })
+        .collect()
+}
+
+/// Writes `time` with a .NET custom date and time pattern such as `dd/MM/yyyy HH:mm:ss`.
+fn format<Tz: TimeZone>(time: &DateTime<Tz>, locale: &Locale) -> String {
+    let (year, month, day) = (i64::from(time.year()), time.month(), time.day());
+    let weekday = time.weekday().num_days_from_sunday() as usize;
+    let (hour, minute, second) = (i64::from(time.hour()), i64::from(time.minute()), i64::from(time.second()));
+    let hour12 = if hour % 12 == 0 { 12 } else { hour % 12 };
+    let designator = if hour < 12 { &locale.am } else { &locale.pm };
+
+    let chars: Vec<char> = locale.pattern.chars().collect();
+    let mut out = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let run = chars[i..].iter().take_while(|&&d| d == c).count();
+        let number = |value: i64| if run == 1 { value.to_string() } else { format!("{:02}", value) };
+        match c {
+            'y' if run == 1 => out.push_str(&(year % 100).to_string()),
+            'y' if run == 2 => out.push_str(&format!("{:02}", year % 100)),
+            'y' => out.push_str(&format!("{:0width$}", year, width = run)),
+            'M' if run >= 4 => out.push_str(&locale.months[month as usize - 1]),
+            'M' if run == 3 => out.push_str(&locale.short_months[month as usize - 1]),
+            'M' => out.push_str(&number(i64::from(month))),
+            'd' if run >= 4 => out.push_str(&locale.days[weekday]),
+            'd' if run == 3 => out.push_str(&locale.short_days[weekday]),
+            'd' => out.push_str(&number(i64::from(day))),
+            'H' => out.push_str(&number(hour)),
+            'h' => out.push_str(&number(hour12)),
+            'm' => out.push_str(&number(minute)),
+            's' => out.push_str(&number(second)),
+            't' if run == 1 => out.extend(designator.chars().next()),
+            't' => out.push_str(designator),
+            'f' | 'F' => out.push_str(&"0".repeat(run)),
+            '/' => out.push_str(&locale.date_separator.repeat(run)),
+            ':' => out.push_str(&locale.time_separator.repeat(run)),
+            '\'' | '"' => {
+                let end = chars[i + 1..].iter().position(|&d| d == c).map_or(chars.len(), |p| i + 1 + p);
+                out.extend(&chars[i + 1..end]);
+                i = end + 1;
+                continue;
+            }
+            '\\' => {
+                out.extend(chars.get(i + 1));
+                i += 2;
+                continue;
+            }
+            _ => out.extend(std::iter::repeat_n(c, run)),
+        }
+        i += run;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{FixedOffset, Utc};
+
+    use super::*;
+
+    const MARCH_1_2024_10_15: i64 = 1_709_288_100;
+
+    #[test]
+    fn utc_round_trips() {
+        assert_eq!(utc(MARCH_1_2024_10_15), "2024-03-01 10:15:00");
+        assert_eq!(parse_utc("2024-03-01 10:15:00"), Some(MARCH_1_2024_10_15));
+        assert_eq!(parse_utc("1969-12-31 23:59:59"), Some(-1));
+        assert_eq!(parse_utc("2024-13-01 10:15:00"), None);
+    }
+
+    #[test]
+    fn formats_with_the_culture_patterns() {
+        let british = Locale {
+            pattern: "dd/MM/yyyy HH:mm:ss".to_string(),
+            date_separator: "/".to_string(),
+            ..Locale::default()
+        };
+        let american = Locale { pattern: "M/d/yyyy h:mm:ss tt".to_string(), ..british.clone() };
+        let long = Locale { pattern: "dddd, d MMMM yyyy 'at' HH:mm".to_string(), ..Locale::default() };
+        let evening = MARCH_1_2024_10_15 + 10 * 3_600;
+
+        assert_eq!(in_zone(MARCH_1_2024_10_15, &Utc, &british), "01/03/2024 10:15:00");
+        assert_eq!(in_zone(evening, &Utc, &american), "3/1/2024 8:15:00 PM");
+        assert_eq!(in_zone(MARCH_1_2024_10_15, &Utc, &long), "Friday, 1 March 2024 at 10:15");
+        assert_eq!(in_zone(MARCH_1_2024_10_15, &Utc, &Locale::default()), "2024-03-01 10:15:00");
+    }
+
+    #[test]
+    fn reads_times_from_backup_names_and_the_history_log() {
+        let japanese = Locale { pattern: "yyyy/MM/dd H:mm:ss".to_string(), ..Locale::default() };
+        let tokyo = FixedOffset::east_opt(9 * 3_600).expect("a valid offset");
+
+        assert_eq!(from_file_name("ServerRoles_1709288100.xml"), Some(MARCH_1_2024_10_15));
+        assert_eq!(from_file_name("ACLs_D_Shares_1709288100.acls"), Some(MARCH_1_2024_10_15));
+        assert_eq!(from_file_name("notes.txt"), None);
+        assert_eq!(
+            localize_history_in("[2024-03-01 10:15:00 UTC] Backed up server roles\r\n    3 features\r\n", &tokyo, &japanese),
+            "[2024-03-01 19:15:00] Backed up server roles\r\n    3 features\r\n"
+        );
+    }
+}
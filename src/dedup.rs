@@ -0,0 +1,68 @@
+use crate::exec;
+
+const NOT_INSTALLED: &str = "Data Deduplication is not installed.\n\
+    Install the FS-Data-Deduplication role service from Browse Roles & Features first.";
+
+/// One tab-separated row per fixed NTFS/ReFS volume with its dedup settings, savings and jobs.
+const VOLUMES_SCRIPT: &str = r#"
+$system = $env:SystemDrive
+$dedup = @{}; $status = @{}; $jobs = @{}
+if (Get-Command Get-DedupVolume -ErrorAction SilentlyContinue) {
+    Get-DedupVolume -ErrorAction SilentlyContinue | ForEach-Object { $dedup[$_.Volume] = $_ }
+    Get-DedupStatus -ErrorAction SilentlyContinue | ForEach-Object { $status[$_.Volume] = $_ }
+    Get-DedupJob -ErrorAction SilentlyContinue | ForEach-Object { $jobs[$_.Volume] += @("$($_.Type) $($_.State) $($_.Progress)%") }
+}
+Get-Volume | Where-Object { $_.DriveLetter -and $_.DriveType -eq 'Fixed' -and $_.FileSystemType -in 'NTFS', 'ReFS' } |
+    Sort-Object DriveLetter | ForEach-Object {
+        $v = "$($_.DriveLetter):"
+        $d = $dedup[$v]; $s = $status[$v]
+        $state = if ($v -eq $system) { 'n/a (system)' } elseif ($d -and $d.Enabled) { 'Enabled' } elseif ($d) { 'Disabled' } else { 'Off' }
+        $saved = if ($d) { '{0:N1} GB' -f ($d.SavedSpace / 1GB) } else { '' }
+        $rate = if ($d) { "$($d.SavingsRate)%" } else { '' }
+        $last = if ($s -and $s.LastOptimizationTime) {
+            $r = if ($s.LastOptimizationResult -eq 0) { 'OK' } else { "error 0x{0:X8}" -f $s.LastOptimizationResult }
+            "$($s.LastOptimizationTime.ToString('yyyy-MM-dd HH:mm')) $r"
+        } else { '' }
+        "$v`t$($_.FileSystemLabel)`t$('{0:N1} GB' -f ($_.Size / 1GB))`t$state`t$($d.UsageType)`t$saved`t$rate`t$last`t$($jobs[$v] -join ', ')"
+    }
+"#;
+
+/// Volumes with dedup state, usage type, saved space, savings rate, last optimization and running jobs.
+pub fn volumes() -> Result<Vec<Vec<String>>, String> {
+    let output = exec::powershell(VOLUMES_SCRIPT).map_err(|e| format!("Failed to list volumes: {}", e))?;
+    if !output.success {
+        return Err(format!("Failed to list volumes:\n{}", output.stderr.trim()));
+    }
+    Ok(exec::tab_rows(&output.stdout).into_iter().filter(|r| r.len() == 9).collect())
+}
+
+/// Enables deduplication on `volume` with the given usage type and starts a first optimization.
+pub fn enable(volume: &str, usage: &str) -> Result<String, String> {
+    let usage = match usage.trim().to_lowercase().as_str() {
+        "" | "default" => "Default",
+        "hyperv" | "hyper-v" => "HyperV",
+        "backup" => "Backup",
+        other => return Err(format!("Unknown usage type '{}': use Default, HyperV or Backup", other)),
+    };
+    let installed = exec::powershell("[bool](Get-Command Enable-DedupVolume -ErrorAction SilentlyContinue)")
+        .map(|o| o.stdout.trim() == "True")
+        .unwrap_or(false);
+    if !installed {
+        return Err(NOT_INSTALLED.to_string());
+    }
+
+    let script = format!(
+        "Enable-DedupVolume -Volume {v} -UsageType {u} -ErrorAction Stop | Out-Null\n\
+        Start-DedupJob -Volume {v} -Type Optimization -ErrorAction Stop | Out-Null",
+        v = exec::quote(volume),
+        u = usage
+    );
+    match exec::change(&script) {
+        Ok(output) if output.success => Ok(format!(
+            "Deduplication enabled on {} ({}); a first optimization job has started. Press r to refresh its progress.",
+            volume, usage
+        )),
+        Ok(output) => Err(format!("Failed to enable deduplication on {}: {}", volume, output.stderr.trim())),
+        Err(e) => Err(format!("Failed to enable deduplication on {}: {}", volume, e)),
+    }
+}
@@ -0,0 +1,137 @@
+//! Differential role backups. Once a full `ServerRoles_<time>.xml` of this server exists, a
+//! backup stores only the features added and removed since then, with the name of that base,
+//! as `ServerRoles_<time>.delta` (an `Export-Clixml` file like the full backup). The full set
+//! is rebuilt from the base and the delta whenever a backup is read, so weekly backups stay
+//! small. The base must stay in the same folder as its deltas.
+
+use std::path::{Path, PathBuf};
+
+use crate::{dates, exec, rename};
+
+pub const EXTENSION: &str = "delta";
+
+pub fn is_delta(backup: &Path) -> bool {
+    backup.extension().is_some_and(|e| e == EXTENSION)
+}
+
+/// The full backup in `dir` a new backup of this server can be stored against: the newest
+/// one, when it was taken here.
+pub fn base_for(dir: &Path) -> Option<PathBuf> {
+    let host = std::env::var("COMPUTERNAME").ok()?;
+    base_in(dir, &host)
+}
+
+fn base_in(dir: &Path, host: &str) -> Option<PathBuf> {
+    let newest = std::fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|e| e == "xml"))
+        .filter_map(|path| {
+            let name = path.file_name()?.to_string_lossy().to_string();
+            if !name.starts_with("ServerRoles_") {
+                return None;
+            }
+            Some((dates::from_file_name(&name)?, path))
+        })
+        .max_by_key(|(secs, _)| *secs)
+        .map(|(_, path)| path)?;
+    let source = rename::recorded_host(&newest)?;
+    source.eq_ignore_ascii_case(host).then_some(newest)
+}
+
+/// The script storing the installed features as a delta against `base`. It prints the
+/// `Added=` and `Removed=` counts.
+pub fn export_script(base: &Path, delta: &Path) -> String {
+    let base_name = base.file_name().unwrap_or_default().to_string_lossy().to_string();
+    format!(
+        "$base = @(Import-Clixml -Path {} | Where-Object Installed | ForEach-Object Name)\n\
+        $now = @(Get-WindowsFeature | Where-Object Installed)\n\
+        $names = @($now | ForEach-Object Name)\n\
+        $delta = [pscustomobject]@{{\n    \
+            Base = {}\n    \
+            Added = @($now | Where-Object {{ $base -notcontains $_.Name }})\n    \
+            Removed = @($base | Where-Object {{ $names -notcontains $_ }})\n\
+        }}\n\
+        $delta | Export-Clixml -Path {}\n\
+        \"Added=$($delta.Added.Count)\"\n\
+        \"Removed=$($delta.Removed.Count)\"",
+        exec::quote(&base.to_string_lossy()),
+        exec::quote(&base_name),
+        exec::quote(&delta.to_string_lossy())
+    )
+}
+
+/// The file name of the full backup `delta` was stored against.
+pub fn base_name(delta: &Path) -> Result<String, String> {
+    let output = exec::powershell(&format!("(Import-Clixml -Path {}).Base", exec::quote(&delta.to_string_lossy())))
+        .map_err(|e| format!("Failed to read {}: {}", delta.display(), e))?;
+    match output.stdout.trim() {
+        name if output.success && !name.is_empty() => Ok(name.to_string()),
+        _ => Err(format!("Failed to read the base backup of {}:\n{}", delta.display(), output.stderr.trim())),
+    }
+}
+
+/// A full backup with the content of `backup`: the backup itself when it is full, or the
+/// base and delta combined into a temporary `.xml` file.
+pub fn full(backup: &Path) -> Result<PathBuf, String> {
+    if !is_delta(backup) {
+        return Ok(backup.to_path_buf());
+    }
+    let dir = backup.parent().unwrap_or(Path::new("."));
+    let stem = backup.file_stem().unwrap_or_default().to_string_lossy().to_string();
+    let out = std::env::temp_dir().join(format!("server-helper-{}.xml", stem));
+    let script = format!(
+        "$delta = Import-Clixml -Path {}\n\
+        $base = Join-Path {} $delta.Base\n\
+        if (-not (Test-Path -LiteralPath $base)) {{ throw \"The base backup $($delta.Base) is no longer next to the delta.\" }}\n\
+        (@(Import-Clixml -Path $base | Where-Object {{ $delta.Removed -notcontains $_.Name }}) + @($delta.Added)) | \
+        Export-Clixml -Path {}",
+        exec::quote(&backup.to_string_lossy()),
+        exec::quote(&dir.to_string_lossy()),
+        exec::quote(&out.to_string_lossy())
+    );
+    let output = exec::powershell(&script).map_err(|e| format!("Failed to rebuild {}: {}", backup.display(), e))?;
+    if !output.success {
+        return Err(format!("Failed to rebuild {}:\n{}", backup.display(), output.stderr.trim()));
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, FakeRunner};
+
+    #[test]
+    fn stores_against_the_newest_full_backup_of_this_host() {
+        let dir = testing::temp_dir("delta-base");
+        for (name, host) in [("ServerRoles_1709288100", "FS01"), ("ServerRoles_1709374500", "FS01")] {
+            std::fs::write(dir.join(format!("{}.xml", name)), "<Objs />").unwrap_or_default();
+            std::fs::write(dir.join(format!("{}.source", name)), format!("Host={}\r\n", host)).unwrap_or_default();
+        }
+        std::fs::write(dir.join("ServerRoles_1709460900.delta"), "<Objs />").unwrap_or_default();
+
+        assert_eq!(base_in(&dir, "fs01"), Some(dir.join("ServerRoles_1709374500.xml")));
+        assert_eq!(base_in(&dir, "FS02"), None);
+        assert!(export_script(&dir.join("ServerRoles_1709374500.xml"), &dir.join("ServerRoles_1709460900.delta"))
+            .contains("Base = 'ServerRoles_1709374500.xml'"));
+    }
+
+    #[test]
+    fn rebuilds_the_full_set_from_a_delta() {
+        let fake = FakeRunner::new().install();
+        let dir = testing::temp_dir("delta-full");
+        let xml = dir.join("ServerRoles_1709374500.xml");
+
+        assert_eq!(full(&xml), Ok(xml.clone()));
+        assert!(fake.calls().is_empty());
+
+        let rebuilt = full(&dir.join("ServerRoles_1709460900.delta"));
+        assert!(rebuilt.is_ok_and(|p| p.ends_with("server-helper-ServerRoles_1709460900.xml")));
+        assert!(fake.ran("$delta.Removed -notcontains $_.Name"));
+
+        FakeRunner::new().fail("Import-Clixml", "The base backup ServerRoles_1709374500.xml is no longer next to the delta.").install();
+        assert!(full(&dir.join("ServerRoles_1709460900.delta")).is_err_and(|e| e.contains("no longer next to")));
+    }
+}
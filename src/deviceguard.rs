@@ -0,0 +1,143 @@
+use crate::exec;
+use crate::{CheckItem, CheckStatus};
+
+/// `Win32_DeviceGuard`, Secure Boot and the hypervisor, as `key=value` lines. Service lists are
+/// comma-separated codes: 1 Credential Guard, 2 HVCI.
+const STATUS_SCRIPT: &str = r#"
+$dg = Get-CimInstance -ClassName Win32_DeviceGuard -Namespace root\Microsoft\Windows\DeviceGuard -ErrorAction SilentlyContinue
+if ($dg) {
+    "Vbs=$($dg.VirtualizationBasedSecurityStatus)"
+    "Configured=$($dg.SecurityServicesConfigured -join ',')"
+    "Running=$($dg.SecurityServicesRunning -join ',')"
+}
+try { "SecureBoot=$(Confirm-SecureBootUEFI -ErrorAction Stop)" } catch { "SecureBoot=Unsupported" }
+$cs = Get-CimInstance Win32_ComputerSystem
+"Hypervisor=$($cs.HypervisorPresent)"
+"Model=$($cs.Model)"
+"#;
+
+/// Credential Guard, HVCI, virtualization-based security and Secure Boot, with what each means
+/// for Hyper-V and agents. These are settings, not faults: only a service that is configured but
+/// not running is reported as a problem.
+pub fn checks() -> Result<Vec<CheckItem>, String> {
+    let output =
+        exec::powershell(STATUS_SCRIPT).map_err(|e| format!("Failed to read the Device Guard status: {}", e))?;
+    if !output.success {
+        return Err(format!("Failed to read the Device Guard status:\n{}", output.stderr.trim()));
+    }
+    let values = exec::key_values(&output.stdout);
+    let value = |key: &str| values.get(key).map(|v| v.trim().to_string()).unwrap_or_default();
+    let codes = |key: &str| value(key).split(',').map(|c| c.trim().to_string()).collect::<Vec<_>>();
+    let (configured, running) = (codes("Configured"), codes("Running"));
+    let item = |name: &str, status, detail: String, advice: Option<&str>| CheckItem {
+        name: name.to_string(),
+        status,
+        detail,
+        remediation: None,
+        advice: advice.map(String::from),
+    };
+    let service = |name: &str, code: &str, on_advice: &str, configured_advice: &str| {
+        match (configured.iter().any(|c| c == code), running.iter().any(|c| c == code)) {
+            (_, true) => item(name, CheckStatus::Pass, "Running".to_string(), Some(on_advice)),
+            (true, false) => item(
+                name,
+                CheckStatus::Fail,
+                "Configured but not running".to_string(),
+                Some(configured_advice),
+            ),
+            (false, false) => item(name, CheckStatus::Unknown, "Off".to_string(), None),
+        }
+    };
+
+    let vbs = value("Vbs");
+    let mut items = vec![item(
+        "Virtualization-based security",
+        match vbs.as_str() {
+            "2" => CheckStatus::Pass,
+            "1" => CheckStatus::Fail,
+            _ => CheckStatus::Unknown,
+        },
+        match vbs.as_str() {
+            "2" => "Running".to_string(),
+            "1" => "Enabled but not running".to_string(),
+            "0" => "Off".to_string(),
+            _ => "Not reported (Win32_DeviceGuard is unavailable)".to_string(),
+        },
+        match vbs.as_str() {
+            "2" => Some("The Windows hypervisor is running, so other hypervisors on this host (older VMware Workstation, VirtualBox) run slowly or not at all."),
+            "1" => Some("Check that virtualization is enabled in the firmware and that Secure Boot is on, then restart."),
+            _ => None,
+        },
+    )];
+    items.push(service(
+        "Credential Guard",
+        "1",
+        "NTLMv1, MS-CHAPv2 and unconstrained Kerberos delegation stop working for signed-in users; agents that read LSASS secrets fail.",
+        "Usually a missing prerequisite (VBS not running, or no UEFI/Secure Boot). Restart after fixing it.",
+    ));
+    items.push(service(
+        "HVCI (memory integrity)",
+        "2",
+        "Drivers that are not HVCI-compatible are blocked; older backup, antivirus and monitoring agents may fail to load theirs.",
+        "An incompatible driver keeps it off. Check Core isolation in Windows Security for the driver to update.",
+    ));
+
+    let secure_boot = value("SecureBoot");
+    items.push(match secure_boot.to_ascii_lowercase().as_str() {
+        "true" => item("Secure Boot", CheckStatus::Pass, "On".to_string(), None),
+        "false" => item(
+            "Secure Boot",
+            CheckStatus::Fail,
+            "Off".to_string(),
+            Some("Credential Guard and HVCI need Secure Boot for full protection. Turn it on in the firmware (Generation 2 VM settings on Hyper-V)."),
+        ),
+        _ => item(
+            "Secure Boot",
+            CheckStatus::Unknown,
+            "Not supported (legacy BIOS boot)".to_string(),
+            Some("Credential Guard and HVCI need UEFI boot."),
+        ),
+    });
+
+    let model = value("Model");
+    let virtual_machine = ["Virtual Machine", "VMware", "VirtualBox", "KVM", "HVM domU"].iter().any(|m| model.contains(m));
+    let hypervisor = value("Hypervisor").eq_ignore_ascii_case("True");
+    items.push(item(
+        "Hypervisor",
+        CheckStatus::Unknown,
+        format!(
+            "{}{}",
+            if hypervisor { "Present" } else { "Not present" },
+            if virtual_machine { format!(" (this server is a VM: {})", model) } else { String::new() }
+        ),
+        virtual_machine.then_some(
+            "Hyper-V, VBS and Credential Guard inside this VM need nested virtualization: on a Hyper-V host run \
+            Set-VMProcessor -VMName <name> -ExposeVirtualizationExtensions $true while the VM is off.",
+        ),
+    ));
+    Ok(items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::FakeRunner;
+
+    #[test]
+    fn reports_running_credential_guard_and_configured_hvci() {
+        FakeRunner::new()
+            .on(
+                "Win32_DeviceGuard",
+                "Vbs=2\nConfigured=1,2\nRunning=1\nSecureBoot=True\nHypervisor=True\nModel=Virtual Machine\n",
+            )
+            .install();
+
+        let items = checks().unwrap_or_default();
+        let status = |name: &str| items.iter().find(|i| i.name == name).map(|i| (i.status == CheckStatus::Pass, i.detail.clone()));
+
+        assert_eq!(status("Credential Guard"), Some((true, "Running".to_string())));
+        assert_eq!(status("HVCI (memory integrity)"), Some((false, "Configured but not running".to_string())));
+        assert_eq!(status("Secure Boot"), Some((true, "On".to_string())));
+        assert!(items.iter().any(|i| i.name == "Hypervisor" && i.advice.as_deref().is_some_and(|a| a.contains("ExposeVirtualizationExtensions"))));
+    }
+}
@@ -0,0 +1,97 @@
+use crate::exec;
+
+const STATIC_INDEX: &str = "https://download.docker.com/win/static/stable/x86_64/";
+
+/// Downloads the newest static Docker engine build into Program Files and puts it on the machine PATH.
+const INSTALL_ENGINE_SCRIPT: &str = r#"
+$ErrorActionPreference = 'Stop'
+[Net.ServicePointManager]::SecurityProtocol = [Net.SecurityProtocolType]::Tls12
+$index = (Invoke-WebRequest -Uri $indexUrl -UseBasicParsing).Content
+$latest = [regex]::Matches($index, 'docker-(\d+\.\d+\.\d+)\.zip') |
+    ForEach-Object { [version]$_.Groups[1].Value } | Sort-Object -Descending | Select-Object -First 1
+if (-not $latest) { throw "No Docker release found at $indexUrl" }
+$zip = Join-Path $env:TEMP "docker-$latest.zip"
+Invoke-WebRequest -Uri "$indexUrl/docker-$latest.zip" -OutFile $zip -UseBasicParsing
+Expand-Archive -Path $zip -DestinationPath $env:ProgramFiles -Force
+$dir = Join-Path $env:ProgramFiles 'docker'
+$path = [Environment]::GetEnvironmentVariable('Path', 'Machine')
+if (($path -split ';') -notcontains $dir) {
+    [Environment]::SetEnvironmentVariable('Path', "$path;$dir", 'Machine')
+}
+"Version=$latest"
+"#;
+
+const SERVICE_SCRIPT: &str = r#"
+$ErrorActionPreference = 'Stop'
+$dockerd = Join-Path $env:ProgramFiles 'docker\dockerd.exe'
+if (-not (Get-Service -Name docker -ErrorAction SilentlyContinue)) {
+    & $dockerd --register-service
+    if ($LASTEXITCODE -ne 0) { throw "dockerd --register-service failed with exit code $LASTEXITCODE" }
+}
+Set-Service -Name docker -StartupType Automatic
+Start-Service -Name docker
+"Service=$((Get-Service -Name docker).Status)"
+"#;
+
+const VERIFY_SCRIPT: &str = r#"
+$docker = Join-Path $env:ProgramFiles 'docker\docker.exe'
+& $docker version --format 'Client={{.Client.Version}}|Server={{.Server.Version}}|OS={{.Server.Os}}' 2>&1
+"#;
+
+/// Sets up this server as a Windows container host: Containers feature, Docker engine,
+/// service, then `docker version`. `progress` gets a line per step as it happens.
+pub fn setup(mut progress: impl FnMut(Option<&str>, Option<u8>)) -> Result<String, String> {
+    progress(Some("Step 1/4: Installing the Containers feature..."), Some(0));
+    let output = exec::change(
+        "$r = Install-WindowsFeature -Name Containers -ErrorAction Stop; \"RestartNeeded=$($r.RestartNeeded)\"",
+    )
+    .map_err(|e| format!("Failed to install the Containers feature: {}", e))?;
+    if !output.success {
+        return Err(format!("Failed to install the Containers feature:\n{}", output.stderr.trim()));
+    }
+    if exec::key_values(&output.stdout).get("RestartNeeded").map(String::as_str) == Some("Yes") {
+        return Err("The Containers feature was installed and needs a restart. \
+            Restart the server, then run Docker Host Setup again to continue."
+            .to_string());
+    }
+    progress(Some("Containers feature is installed."), Some(25));
+
+    progress(Some("Step 2/4: Downloading the Docker engine (static binaries)..."), None);
+    let script = format!("$indexUrl = {}\n{}", exec::quote(STATIC_INDEX.trim_end_matches('/')), INSTALL_ENGINE_SCRIPT);
+    let output = exec::change(&script).map_err(|e| format!("Failed to install Docker: {}", e))?;
+    if !output.success {
+        return Err(format!("Failed to install Docker:\n{}", output.stderr.trim()));
+    }
+    let version = exec::key_values(&output.stdout).get("Version").cloned().unwrap_or_default();
+    progress(Some(&format!("Docker {} installed to Program Files\\docker and added to PATH.", version)), Some(50));
+
+    progress(Some("Step 3/4: Registering and starting the docker service..."), None);
+    let output = exec::change(SERVICE_SCRIPT).map_err(|e| format!("Failed to configure the docker service: {}", e))?;
+    if !output.success {
+        return Err(format!("Failed to configure the docker service:\n{}", output.stderr.trim()));
+    }
+    let service = exec::key_values(&output.stdout).get("Service").cloned().unwrap_or_default();
+    progress(Some(&format!("docker service: {}", service)), Some(75));
+
+    progress(Some("Step 4/4: Verifying with docker version..."), None);
+    let output = exec::powershell(VERIFY_SCRIPT).map_err(|e| format!("Failed to run docker version: {}", e))?;
+    let line = output.stdout.lines().find(|l| l.starts_with("Client=")).unwrap_or_default().to_string();
+    let fields: std::collections::HashMap<&str, &str> =
+        line.split('|').filter_map(|f| f.split_once('=')).collect();
+    match (fields.get("Client"), fields.get("Server")) {
+        (Some(client), Some(server)) if !server.is_empty() => {
+            progress(Some("docker version answered from the engine."), Some(100));
+            Ok(format!(
+                "Docker host ready: client {}, engine {} ({}).",
+                client,
+                server,
+                fields.get("OS").copied().unwrap_or("windows")
+            ))
+        }
+        _ => Err(format!(
+            "docker version did not reach the engine:\n{}{}",
+            output.stdout.trim(),
+            output.stderr.trim()
+        )),
+    }
+}
@@ -0,0 +1,144 @@
+use crate::exec;
+use crate::{CheckItem, CheckStatus};
+
+/// Fails unless this server is a domain controller (DomainRole 4 or 5).
+fn require_dc() -> Result<(), String> {
+    let output = exec::powershell("(Get-CimInstance Win32_ComputerSystem).DomainRole")
+        .map_err(|e| format!("Failed to read the domain role: {}", e))?;
+    match output.stdout.trim() {
+        "4" | "5" => Ok(()),
+        _ => Err("This server is not a domain controller.".to_string()),
+    }
+}
+
+fn item(name: String, pass: bool, detail: String, advice: Option<String>) -> CheckItem {
+    CheckItem {
+        name,
+        status: if pass { CheckStatus::Pass } else { CheckStatus::Fail },
+        detail,
+        remediation: None,
+        advice,
+    }
+}
+
+/// Runs dcdiag and turns each `passed test`/`failed test` line into a check, with the text
+/// dcdiag printed under a failed test as its detail.
+pub fn dcdiag_checks() -> Result<Vec<CheckItem>, String> {
+    require_dc()?;
+    let output = exec::run("dcdiag", &[]).map_err(|e| format!("Failed to run dcdiag: {}", e))?;
+
+    let mut tests = Vec::new();
+    let mut details: Vec<String> = Vec::new();
+    for raw in output.stdout.lines() {
+        let line = raw.trim();
+        if line.starts_with("Starting test:") {
+            details.clear();
+            continue;
+        }
+        let result = line.trim_start_matches('.').trim();
+        let parsed = result
+            .split_once(" passed test ")
+            .map(|(target, test)| (target, test, true))
+            .or_else(|| result.split_once(" failed test ").map(|(target, test)| (target, test, false)));
+        match parsed {
+            Some((target, test, passed)) => {
+                let detail = match (passed, details.is_empty()) {
+                    (true, _) => "Passed".to_string(),
+                    (false, true) => "Failed".to_string(),
+                    (false, false) => details.join(" "),
+                };
+                tests.push(item(format!("{} ({})", test.trim(), target), passed, detail, None));
+                details.clear();
+            }
+            None if !line.is_empty() && !line.starts_with("Testing server:") && !line.starts_with("Running ") => {
+                details.push(line.to_string());
+            }
+            None => {}
+        }
+    }
+    if tests.is_empty() {
+        let text = if output.stderr.trim().is_empty() { output.stdout } else { output.stderr };
+        return Err(format!("dcdiag produced no test results:\n{}", text.trim()));
+    }
+
+    let failed = tests.iter().filter(|t| t.status == CheckStatus::Fail).count();
+    let mut items = vec![item(
+        "dcdiag".to_string(),
+        failed == 0,
+        format!("{} of {} tests passed", tests.len() - failed, tests.len()),
+        (failed > 0).then(|| {
+            "Event log tests (SystemLog, DFSREvent, KccEvent) also fail on errors from the last 24 hours that \
+            may already be resolved; run them again after fixing the cause."
+                .to_string()
+        }),
+    )];
+    // Failures first, so they are visible without scrolling
+    tests.sort_by_key(|t| t.status != CheckStatus::Fail);
+    items.extend(tests);
+    Ok(items)
+}
+
+/// One `repadmin /replsummary` row: name, largest delta, failures, total, error text.
+fn replication_row(line: &str) -> Option<(String, String, u32, u32, String)> {
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    let slash = tokens.iter().position(|t| *t == "/")?;
+    if slash < 3 || tokens.len() < slash + 3 {
+        return None;
+    }
+    let fails = tokens[slash - 1].parse().ok()?;
+    let total = tokens[slash + 1].parse().ok()?;
+    let error = tokens.get(slash + 3..).map(|rest| rest.join(" ")).unwrap_or_default();
+    Some((tokens[0].to_string(), tokens[1..slash - 1].join(" "), fails, total, error))
+}
+
+/// Runs `repadmin /replsummary` and turns each source and destination partner into a check.
+pub fn replication_checks() -> Result<Vec<CheckItem>, String> {
+    require_dc()?;
+    let output = exec::run("repadmin", &["/replsummary"]).map_err(|e| format!("Failed to run repadmin: {}", e))?;
+
+    let mut items = Vec::new();
+    let mut direction = "";
+    let mut operational = false;
+    for raw in output.stdout.lines() {
+        let line = raw.trim();
+        if line.starts_with("Source DSA") {
+            (direction, operational) = ("Source", false);
+        } else if line.starts_with("Destination DSA") {
+            (direction, operational) = ("Destination", false);
+        } else if line.starts_with("Experienced the following operational errors") {
+            operational = true;
+        } else if operational {
+            // e.g. "58 - dc03.corp.local": the DC could not be queried at all
+            if let Some((code, server)) = line.split_once(" - ") {
+                items.push(item(
+                    format!("Unreachable {}", server.trim()),
+                    false,
+                    format!("Replication information could not be retrieved (error {})", code.trim()),
+                    Some("Check that the DC is online and that DNS and RPC (TCP 135 and dynamic ports) reach it.".to_string()),
+                ));
+            }
+        } else if !direction.is_empty() {
+            if let Some((name, delta, fails, total, error)) = replication_row(line) {
+                let detail = match (fails, error.is_empty()) {
+                    (0, _) => format!("{} of {} links OK, largest delta {}", total, total, delta),
+                    (_, true) => format!("{} of {} links failing, largest delta {}", fails, total, delta),
+                    (_, false) => format!("{} of {} links failing, largest delta {}: {}", fails, total, delta, error),
+                };
+                let advice = (fails > 0).then(|| {
+                    format!(
+                        "Run repadmin /showrepl {} for the failing naming contexts, then repadmin /syncall /AdeP \
+                        once connectivity is fixed.",
+                        name
+                    )
+                });
+                items.push(item(format!("{} {}", direction, name), fails == 0, detail, advice));
+            }
+        }
+    }
+    if items.is_empty() {
+        let text = if output.stderr.trim().is_empty() { output.stdout } else { output.stderr };
+        return Err(format!("repadmin reported no replication partners:\n{}", text.trim()));
+    }
+    items.sort_by_key(|t| t.status != CheckStatus::Fail);
+    Ok(items)
+}
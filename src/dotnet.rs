@@ -0,0 +1,197 @@
+use crate::{agents, exec};
+use crate::{CheckItem, CheckStatus, Remediation};
+
+/// Prints the .NET Framework release key and one `Runtime=<framework> <version>` line per
+/// installed .NET (Core) runtime.
+const RUNTIMES_SCRIPT: &str = r#"
+$ndp = 'HKLM:\SOFTWARE\Microsoft\NET Framework Setup\NDP'
+"NetFxRelease=$((Get-ItemProperty "$ndp\v4\Full" -ErrorAction SilentlyContinue).Release)"
+"NetFx35=$((Get-ItemProperty "$ndp\v3.5" -ErrorAction SilentlyContinue).Install)"
+$shared = Join-Path $env:ProgramFiles 'dotnet\shared'
+Get-ChildItem -Path $shared -Directory -ErrorAction SilentlyContinue | ForEach-Object {
+    $framework = $_.Name
+    Get-ChildItem -Path $_.FullName -Directory | ForEach-Object { "Runtime=$framework $($_.Name)" }
+}
+"#;
+
+/// Minimum `Release` value of each .NET Framework 4.5+ version, newest first.
+const NETFX_RELEASES: &[(u32, &str)] = &[
+    (533320, "4.8.1"),
+    (528040, "4.8"),
+    (461808, "4.7.2"),
+    (461308, "4.7.1"),
+    (460798, "4.7"),
+    (394802, "4.6.2"),
+    (394254, "4.6.1"),
+    (393295, "4.6"),
+    (379893, "4.5.2"),
+    (378675, "4.5.1"),
+    (378389, "4.5"),
+];
+
+/// Runtime kinds accepted in the catalog's `runtimes` key, with their shared framework folder.
+const KINDS: &[(&str, &str, &str)] = &[
+    ("dotnet", "Microsoft.NETCore.App", ".NET Runtime"),
+    ("aspnetcore", "Microsoft.AspNetCore.App", "ASP.NET Core Runtime"),
+    ("desktop", "Microsoft.WindowsDesktop.App", ".NET Desktop Runtime"),
+];
+
+fn parse_version(value: &str) -> Option<(u32, u32)> {
+    let mut parts = value.split('.');
+    let major = parts.next()?.trim().parse().ok()?;
+    let minor = parts.next().map(|m| m.trim().parse().ok()).unwrap_or(Some(0))?;
+    Some((major, minor))
+}
+
+/// A `runtimes` entry such as `aspnetcore 8.0` or `netfx 4.8`.
+struct Requirement {
+    kind: String,
+    version: (u32, u32),
+    apps: Vec<String>,
+}
+
+impl Requirement {
+    fn label(&self) -> String {
+        let name = KINDS
+            .iter()
+            .find(|(k, _, _)| *k == self.kind)
+            .map(|(_, _, label)| *label)
+            .unwrap_or(".NET Framework");
+        format!("{} {}.{}", name, self.version.0, self.version.1)
+    }
+
+    fn winget_id(&self) -> Option<String> {
+        let (major, minor) = self.version;
+        // Package ids use the major version from .NET 5 on, and major_minor before
+        let suffix = if major >= 5 { major.to_string() } else { format!("{}_{}", major, minor) };
+        match self.kind.as_str() {
+            "dotnet" => Some(format!("Microsoft.DotNet.Runtime.{}", suffix)),
+            "aspnetcore" => Some(format!("Microsoft.DotNet.AspNetCore.{}", suffix)),
+            "desktop" => Some(format!("Microsoft.DotNet.DesktopRuntime.{}", suffix)),
+            "netfx" if major == 4 => Some("Microsoft.DotNet.Framework.DeveloperPack_4".to_string()),
+            _ => None,
+        }
+    }
+}
+
+/// Collects the runtimes required by the agent catalog, merging apps that need the same one.
+fn catalog_requirements() -> Result<Vec<Requirement>, String> {
+    let mut requirements: Vec<Requirement> = Vec::new();
+    for agent in agents::load_catalog()?.agents {
+        for entry in &agent.runtimes {
+            let (kind, version) = entry
+                .split_once(' ')
+                .and_then(|(k, v)| Some((k.trim().to_lowercase(), parse_version(v)?)))
+                .ok_or_else(|| format!("agents.ini: [{}] runtime '{}' should look like 'aspnetcore 8.0'", agent.name, entry))?;
+            if kind != "netfx" && !KINDS.iter().any(|(k, _, _)| *k == kind) {
+                return Err(format!(
+                    "agents.ini: [{}] unknown runtime '{}': use netfx, dotnet, aspnetcore or desktop",
+                    agent.name, kind
+                ));
+            }
+            match requirements.iter_mut().find(|r| r.kind == kind && r.version == version) {
+                Some(existing) => existing.apps.push(agent.name.clone()),
+                None => requirements.push(Requirement { kind, version, apps: vec![agent.name.clone()] }),
+            }
+        }
+    }
+    Ok(requirements)
+}
+
+/// Lists the installed .NET Framework and .NET runtimes and checks them against the
+/// runtimes the agent catalog requires, offering a winget install for missing ones.
+pub fn runtime_checks() -> Result<Vec<CheckItem>, String> {
+    let requirements = catalog_requirements()?;
+    let output = exec::powershell(RUNTIMES_SCRIPT).map_err(|e| format!("Failed to read the installed runtimes: {}", e))?;
+    let values = exec::key_values(&output.stdout);
+    let runtimes: Vec<(&str, &str)> = output
+        .stdout
+        .lines()
+        .filter_map(|l| l.strip_prefix("Runtime="))
+        .filter_map(|l| l.split_once(' '))
+        .collect();
+
+    let release: u32 = values.get("NetFxRelease").and_then(|r| r.parse().ok()).unwrap_or(0);
+    let netfx = NETFX_RELEASES.iter().find(|(min, _)| release >= *min).map(|(_, v)| *v);
+    let netfx35 = values.get("NetFx35").map(String::as_str) == Some("1");
+
+    let mut items = vec![CheckItem {
+        name: ".NET Framework".to_string(),
+        status: if netfx.is_some() { CheckStatus::Pass } else { CheckStatus::Unknown },
+        detail: match (netfx, netfx35) {
+            (Some(v), true) => format!("{} (3.5 also installed)", v),
+            (Some(v), false) => v.to_string(),
+            (None, _) => "4.5 or later is not installed".to_string(),
+        },
+        remediation: None,
+        advice: None,
+    }];
+
+    for (kind, framework, label) in KINDS {
+        let versions: Vec<&str> = runtimes.iter().filter(|(f, _)| f == framework).map(|(_, v)| *v).collect();
+        items.push(CheckItem {
+            name: label.to_string(),
+            status: if versions.is_empty() { CheckStatus::Unknown } else { CheckStatus::Pass },
+            detail: if versions.is_empty() { "None installed".to_string() } else { versions.join(", ") },
+            remediation: None,
+            advice: Some(format!("Require it for a catalog app with: runtimes = {} 8.0", kind)),
+        });
+    }
+
+    if requirements.is_empty() {
+        items.push(CheckItem {
+            name: "Catalog requirements".to_string(),
+            status: CheckStatus::Unknown,
+            detail: "No agent in agents.ini lists runtimes".to_string(),
+            remediation: None,
+            advice: Some(format!(
+                "Add a line such as 'runtimes = aspnetcore 8.0, netfx 4.8' under an agent in {}.",
+                agents::catalog_path().display()
+            )),
+        });
+    }
+
+    for requirement in requirements {
+        let (major, minor) = requirement.version;
+        let installed = match requirement.kind.as_str() {
+            "netfx" if major < 4 => netfx35,
+            "netfx" => netfx.and_then(parse_version).is_some_and(|v| v >= requirement.version),
+            kind => {
+                let framework = KINDS.iter().find(|(k, _, _)| *k == kind).map(|(_, f, _)| *f).unwrap_or_default();
+                // Runtimes roll forward within a major version, so any newer minor satisfies the app
+                runtimes
+                    .iter()
+                    .filter(|(f, _)| *f == framework)
+                    .filter_map(|(_, v)| parse_version(v))
+                    .any(|(ma, mi)| ma == major && mi >= minor)
+            }
+        };
+
+        let remediation = match (installed, requirement.winget_id()) {
+            (true, _) => None,
+            // 3.5 ships as a Windows feature rather than a package
+            (false, _) if requirement.kind == "netfx" && major < 4 => Some(Remediation {
+                summary: "Install the .NET Framework 3.5 feature (NET-Framework-Core).".to_string(),
+                script: "Install-WindowsFeature -Name NET-Framework-Core -ErrorAction Stop | Out-Null".to_string(),
+            }),
+            (false, Some(id)) => Some(Remediation {
+                summary: format!("Install {} with winget ({}).", requirement.label(), id),
+                script: format!(
+                    "winget install --id {} -e --silent --accept-source-agreements --accept-package-agreements\n\
+                    # -1978335189: already installed\n\
+                    if ($LASTEXITCODE -ne 0 -and $LASTEXITCODE -ne -1978335189) {{ throw \"winget failed with exit code $LASTEXITCODE\" }}",
+                    exec::quote(&id)
+                ),
+            }),
+            (false, None) => None,
+        };
+        items.push(CheckItem {
+            name: format!("{} for {}", requirement.label(), requirement.apps.join(", ")),
+            status: if installed { CheckStatus::Pass } else { CheckStatus::Fail },
+            detail: if installed { "Installed".to_string() } else { "Missing".to_string() },
+            remediation,
+            advice: None,
+        });
+    }
+    Ok(items)
+}
@@ -0,0 +1,290 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    io::{self, Write},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    cache, exec,
+    message::{self, Message},
+    proxy,
+};
+
+/// An HTTP client that goes through the machine WinHTTP proxy, like the services we install.
+fn client() -> Result<reqwest::blocking::Client, String> {
+    let mut builder = reqwest::blocking::Client::builder()
+        .user_agent(format!("server-helper/{}", crate::VERSION))
+        .connect_timeout(Duration::from_secs(30))
+        .timeout(None);
+    if let Ok((server, bypass)) = proxy::current() {
+        if !server.is_empty() {
+            let hosts: Vec<&str> = bypass.split(';').map(str::trim).filter(|h| !h.is_empty() && *h != "<local>").collect();
+            let proxy = reqwest::Proxy::all(proxy::proxy_url(&server))
+                .map_err(|e| format!("Invalid WinHTTP proxy {}: {}", server, e))?
+                .no_proxy(reqwest::NoProxy::from_string(&hosts.join(",")));
+            builder = builder.proxy(proxy);
+        }
+    }
+    builder.build().map_err(|e| format!("Failed to create the HTTP client: {}", e))
+}
+
+/// Attempts per download before giving up; each one resumes where the last stopped.
+const ATTEMPTS: u32 = 5;
+
+/// Downloads `url` to `path`, returning the number of bytes written. In export mode the
+/// equivalent `Invoke-WebRequest` is written to the script instead.
+///
+/// Data goes to `<path>.part` first, with the URL and the server's ETag or Last-Modified
+/// in `<path>.part.state`. If the connection drops, the next attempt (or the next run) asks
+/// for the rest with an HTTP range request and only starts over if the file changed.
+///
+/// A copy from the download cache is used when the server confirms it is still current (or
+/// can't be reached); finished downloads are added to it.
+pub fn fetch(url: &str, path: &Path) -> Result<u64, String> {
+    if exec::export_status().is_some() {
+        let script = format!(
+            "Invoke-WebRequest -Uri {} -OutFile {} -UseBasicParsing",
+            exec::quote(url),
+            exec::quote(&path.to_string_lossy())
+        );
+        return exec::change(&script).map(|_| 0).map_err(|e| e.to_string());
+    }
+    exec::runner().download(url, path)
+}
+
+/// [`fetch`] over HTTP, for the system runner.
+pub(crate) fn fetch_http(url: &str, path: &Path) -> Result<u64, String> {
+    let client = client()?;
+    if let Some(cached) = cache::lookup(url).filter(|cached| is_current(&client, url, &cached.validator)) {
+        return std::fs::copy(&cached.path, path)
+            .map_err(|e| format!("Failed to copy {} from the cache: {}", cached.path.display(), e));
+    }
+    let part = with_suffix(path, ".part");
+    let state = with_suffix(path, ".part.state");
+    let mut last_error = String::new();
+    for attempt in 1..=ATTEMPTS {
+        if attempt > 1 {
+            std::thread::sleep(Duration::from_secs(2 * u64::from(attempt - 1)));
+        }
+        match fetch_part(&client, url, &part, &state) {
+            Ok(Transfer::Complete(bytes)) => {
+                std::fs::rename(&part, path).map_err(|e| format!("Failed to move {} into place: {}", part.display(), e))?;
+                let saved = std::fs::read_to_string(&state).map(|s| exec::key_values(&s)).unwrap_or_default();
+                let _ = std::fs::remove_file(&state);
+                // The cache only saves a later download; failing to fill it is not an error
+                let _ = cache::store(url, path, saved.get("Validator").map(String::as_str).unwrap_or_default());
+                return Ok(bytes);
+            }
+            Ok(Transfer::Interrupted(e)) => last_error = e,
+            Err(e) => return Err(e),
+        }
+    }
+    Err(format!("{}: {} (partial download kept in {})", url, last_error, part.display()))
+}
+
+/// Asks the server with a conditional request whether the cached copy with `validator` (an
+/// ETag or a Last-Modified date) is still current. A copy without one is fetched again; one
+/// whose server can't be reached is used as is, e.g. on a site without internet access.
+fn is_current(client: &reqwest::blocking::Client, url: &str, validator: &str) -> bool {
+    use reqwest::{header, StatusCode};
+
+    if validator.is_empty() {
+        return false;
+    }
+    let condition = if validator.starts_with('"') { header::IF_NONE_MATCH } else { header::IF_MODIFIED_SINCE };
+    match client.get(url).header(condition, validator).send() {
+        Ok(response) => response.status() == StatusCode::NOT_MODIFIED,
+        Err(e) => e.is_connect() || e.is_timeout(),
+    }
+}
+
+enum Transfer {
+    Complete(u64),
+    /// The connection failed; whatever arrived is kept in the `.part` file.
+    Interrupted(String),
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// Continues (or starts) one download into `part`. Network failures are `Interrupted` so the
+/// caller retries; HTTP and file errors are final.
+fn fetch_part(client: &reqwest::blocking::Client, url: &str, part: &Path, state: &Path) -> Result<Transfer, String> {
+    use reqwest::{header, StatusCode};
+
+    let saved = std::fs::read_to_string(state).map(|s| exec::key_values(&s)).unwrap_or_default();
+    let validator = saved.get("Validator").cloned().unwrap_or_default();
+    let offset = match std::fs::metadata(part) {
+        Ok(meta) if saved.get("Url").map(String::as_str) == Some(url) && !validator.is_empty() => meta.len(),
+        _ => 0,
+    };
+
+    let mut request = client.get(url);
+    if offset > 0 {
+        request = request.header(header::RANGE, format!("bytes={}-", offset)).header(header::IF_RANGE, &validator);
+    }
+    let response = match request.send() {
+        Ok(response) => response,
+        Err(e) if e.is_connect() || e.is_timeout() || e.is_request() => return Ok(Transfer::Interrupted(e.to_string())),
+        Err(e) => return Err(format!("{}: {}", url, e)),
+    };
+    if response.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+        // Nothing left to fetch, or the file on the server shrank; start over next attempt
+        let _ = std::fs::remove_file(part);
+        let _ = std::fs::remove_file(state);
+        return Ok(Transfer::Interrupted("the server rejected the resume range".to_string()));
+    }
+    let mut response = response.error_for_status().map_err(|e| format!("{}: {}", url, e))?;
+
+    let resumed = offset > 0 && response.status() == StatusCode::PARTIAL_CONTENT;
+    let file = if resumed {
+        std::fs::OpenOptions::new().append(true).open(part)
+    } else {
+        let value = |name| response.headers().get(name).and_then(|v: &header::HeaderValue| v.to_str().ok());
+        let validator = value(header::ETAG).filter(|v| !v.starts_with("W/")).or_else(|| value(header::LAST_MODIFIED));
+        let _ = std::fs::write(state, format!("Url={}\nValidator={}\n", url, validator.unwrap_or_default()));
+        std::fs::File::create(part)
+    }
+    .map_err(|e| format!("Failed to open {}: {}", part.display(), e))?;
+
+    let total = response.content_length().map(|length| length + if resumed { offset } else { 0 });
+    let name = url.rsplit('/').next().unwrap_or(url).to_string();
+    let mut file = Progress { file, name, received: if resumed { offset } else { 0 }, total, reported: None };
+    match response.copy_to(&mut file) {
+        Ok(bytes) => Ok(Transfer::Complete(if resumed { offset + bytes } else { bytes })),
+        Err(e) => Ok(Transfer::Interrupted(e.to_string())),
+    }
+}
+
+/// Writes a download to its file and reports how far it got to the main loop, a few times
+/// a second.
+struct Progress {
+    file: std::fs::File,
+    name: String,
+    received: u64,
+    total: Option<u64>,
+    reported: Option<Instant>,
+}
+
+impl Write for Progress {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.file.write(buf)?;
+        self.received += written as u64;
+        if self.reported.is_none_or(|at| at.elapsed() >= Duration::from_millis(250)) || Some(self.received) == self.total {
+            self.reported = Some(Instant::now());
+            message::send(Message::DownloadProgress { name: self.name.clone(), received: self.received, total: self.total });
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// Downloads every `(url, path)` with at most `parallel` transfers at a time. Results are in
+/// the order of `items`.
+pub fn fetch_all(items: &[(&str, PathBuf)], parallel: usize) -> Vec<Result<u64, String>> {
+    let next = AtomicUsize::new(0);
+    let runner = exec::runner();
+    let outbox = message::outbox();
+    let results = Mutex::new(vec![None; items.len()]);
+    std::thread::scope(|scope| {
+        for _ in 0..parallel.clamp(1, items.len().max(1)) {
+            scope.spawn(|| loop {
+                exec::set_runner(runner.clone());
+                if let Some(outbox) = &outbox {
+                    message::set_outbox(outbox.clone());
+                }
+                let i = next.fetch_add(1, Ordering::SeqCst);
+                let Some((url, path)) = items.get(i) else { break };
+                let result = fetch(url, path);
+                if let Ok(mut results) = results.lock() {
+                    results[i] = Some(result);
+                }
+            });
+        }
+    });
+    results
+        .into_inner()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|r| r.unwrap_or_else(|| Err("Download did not run".to_string())))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader},
+        net::TcpListener,
+        sync::{atomic::AtomicUsize, Arc},
+    };
+
+    use super::*;
+    use crate::testing::{self, output, FakeRunner};
+
+    /// Serves version `version` of a file at `/agent.msi` with the ETag `"v<version>"`, and
+    /// 304 to a request that already has it. Returns the URL and the count of 200 answers.
+    fn serve(version: Arc<AtomicUsize>) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind a local port");
+        let url = format!("http://{}/agent.msi", listener.local_addr().expect("local address"));
+        let sent = Arc::new(AtomicUsize::new(0));
+        let counter = sent.clone();
+        std::thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let headers: Vec<String> = BufReader::new(&stream).lines().map_while(Result::ok).take_while(|l| !l.is_empty()).collect();
+                let current = version.load(Ordering::SeqCst);
+                let etag = format!("\"v{}\"", current);
+                let has = headers.iter().any(|h| h.to_lowercase().starts_with("if-none-match:") && h.ends_with(&etag));
+                let body = format!("version {}", current);
+                let response = if has {
+                    format!("HTTP/1.1 304 Not Modified\r\nETag: {}\r\nConnection: close\r\n\r\n", etag)
+                } else {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                    format!("HTTP/1.1 200 OK\r\nETag: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", etag, body.len(), body)
+                };
+                let _ = stream.write_all(response.as_bytes());
+            }
+        });
+        (url, sent)
+    }
+
+    #[test]
+    fn a_changed_file_on_the_server_replaces_the_cached_copy() {
+        // The hash of a file is its content in hex, enough to tell versions apart
+        FakeRunner::new()
+            .on_with("Get-FileHash", |script| {
+                let path = script.split('\'').nth(1).unwrap_or_default();
+                let hex: String = std::fs::read(path)?.iter().map(|b| format!("{:02X}", b)).collect();
+                Ok(output(true, &format!("{:0>64}", &hex[hex.len().saturating_sub(64)..]), ""))
+            })
+            .install();
+        let version = Arc::new(AtomicUsize::new(1));
+        let (url, sent) = serve(version.clone());
+        let path = testing::temp_dir("download-cache").join("agent.msi");
+        let read = |path: &Path| std::fs::read_to_string(path).unwrap_or_default();
+
+        assert!(fetch_http(&url, &path).is_ok());
+        let cached = cache::lookup(&url).expect("the download to be cached");
+        assert_eq!((read(&cached.path), cached.validator.as_str()), ("version 1".to_string(), "\"v1\""));
+
+        let _ = std::fs::remove_file(&path);
+        assert!(fetch_http(&url, &path).is_ok());
+        assert_eq!(read(&path), "version 1");
+        assert_eq!(sent.load(Ordering::SeqCst), 1, "the unchanged file comes from the cache");
+
+        version.store(2, Ordering::SeqCst);
+        assert!(fetch_http(&url, &path).is_ok());
+        assert_eq!(read(&path), "version 2");
+        let cached = cache::lookup(&url).expect("the new version to be cached");
+        assert_eq!((read(&cached.path), cached.validator.as_str()), ("version 2".to_string(), "\"v2\""));
+    }
+}
@@ -0,0 +1,154 @@
+//! Export the current server as a PowerShell DSC configuration using only the resources built
+//! into Windows (PSDesiredStateConfiguration): installed roles and features, disabled services,
+//! machine environment variables, Remote Desktop settings and the local Administrators group.
+//! It is a starting point for declarative management, not a full copy of the server.
+
+use crate::{backup_dir, dates, exec, history, message, outcome::OperationOutcome};
+
+/// The settings to export, as tab-separated lines: `Host`, `Feature name`, `Disabled service`,
+/// `Env name value`, `Registry key name value` and `Admin member`.
+const SETTINGS_SCRIPT: &str = r#"
+"Host`t$env:COMPUTERNAME"
+if (Get-Command Get-WindowsFeature -ErrorAction SilentlyContinue) {
+    Get-WindowsFeature | Where-Object { $_.Installed } | ForEach-Object { "Feature`t$($_.Name)" }
+}
+Get-CimInstance Win32_Service -Filter "StartMode='Disabled'" | ForEach-Object { "Disabled`t$($_.Name)" }
+$skip = 'ComSpec','DriverData','NUMBER_OF_PROCESSORS','OS','Path','PATHEXT','PROCESSOR_ARCHITECTURE',
+    'PROCESSOR_IDENTIFIER','PROCESSOR_LEVEL','PROCESSOR_REVISION','PSModulePath','TEMP','TMP','USERNAME','windir'
+[Environment]::GetEnvironmentVariables('Machine').GetEnumerator() | Where-Object { $skip -notcontains $_.Key } |
+    ForEach-Object { "Env`t$($_.Key)`t$($_.Value)" }
+$ts = 'HKEY_LOCAL_MACHINE\SYSTEM\CurrentControlSet\Control\Terminal Server'
+"Registry`t$ts`tfDenyTSConnections`t$((Get-ItemProperty "Registry::$ts").fDenyTSConnections)"
+$rdp = "$ts\WinStations\RDP-Tcp"
+"Registry`t$rdp`tUserAuthentication`t$((Get-ItemProperty "Registry::$rdp").UserAuthentication)"
+Get-LocalGroupMember -SID S-1-5-32-544 -ErrorAction SilentlyContinue | ForEach-Object { "Admin`t$($_.Name)" }
+"#;
+
+/// A DSC resource name made from `text`: letters, digits and underscores.
+fn resource_name(prefix: &str, text: &str) -> String {
+    let name: String = text.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect();
+    format!("{}_{}", prefix, name)
+}
+
+/// The DSC configuration for the settings in `stdout` of `SETTINGS_SCRIPT`.
+fn render(stdout: &str) -> String {
+    let rows = exec::tab_rows(stdout);
+    let of = |kind: &'static str| rows.iter().filter(move |r| r[0] == kind);
+    let host = of("Host").next().and_then(|r| r.get(1)).cloned().unwrap_or_else(|| "localhost".to_string());
+    let mut body = Vec::new();
+
+    for row in of("Feature").filter(|r| r.len() == 2) {
+        body.push(format!(
+            "        WindowsFeature {} {{\n            Name   = {}\n            Ensure = 'Present'\n        }}",
+            resource_name("Feature", &row[1]),
+            exec::quote(&row[1])
+        ));
+    }
+    for row in of("Disabled").filter(|r| r.len() == 2) {
+        body.push(format!(
+            "        Service {} {{\n            Name        = {}\n            StartupType = 'Disabled'\n            State       = 'Stopped'\n        }}",
+            resource_name("Service", &row[1]),
+            exec::quote(&row[1])
+        ));
+    }
+    for row in of("Env").filter(|r| r.len() == 3) {
+        body.push(format!(
+            "        Environment {} {{\n            Name   = {}\n            Value  = {}\n            Ensure = 'Present'\n        }}",
+            resource_name("Env", &row[1]),
+            exec::quote(&row[1]),
+            exec::quote(&row[2])
+        ));
+    }
+    for row in of("Registry").filter(|r| r.len() == 4 && !r[3].is_empty()) {
+        body.push(format!(
+            "        Registry {} {{\n            Key       = {}\n            ValueName = {}\n            ValueData = {}\n            ValueType = 'Dword'\n            Ensure    = 'Present'\n        }}",
+            resource_name("Registry", &row[2]),
+            exec::quote(&row[1]),
+            exec::quote(&row[2]),
+            exec::quote(&row[3])
+        ));
+    }
+    let admins: Vec<String> = of("Admin").filter(|r| r.len() == 2).map(|r| exec::quote(&r[1])).collect();
+    if !admins.is_empty() {
+        body.push(format!(
+            "        Group Administrators {{\n            GroupName        = 'Administrators'\n            MembersToInclude = @({})\n        }}",
+            admins.join(", ")
+        ));
+    }
+
+    format!(
+        "# DSC configuration exported by Server Helper from {host} on {time} UTC.\n\
+        # Review it before applying: it uses only the resources built into Windows and covers\n\
+        # roles and features, disabled services, machine environment variables, Remote Desktop\n\
+        # settings and the local Administrators group.\n\
+        #\n\
+        # To apply it to a server (as Administrator, in the folder of this file):\n\
+        #   . .\\ServerConfig_<time>.ps1\n\
+        #   ServerConfig -NodeName <server> -OutputPath .\\ServerConfig\n\
+        #   Start-DscConfiguration -Path .\\ServerConfig -Wait -Verbose\n\
+        \n\
+        Configuration ServerConfig {{\n\
+        \x20   param([string[]]$NodeName = {node})\n\
+        \n\
+        \x20   Import-DscResource -ModuleName PSDesiredStateConfiguration\n\
+        \n\
+        \x20   Node $NodeName {{\n\
+        {body}\n\
+        \x20   }}\n\
+        }}\n",
+        host = host,
+        time = history::timestamp(),
+        node = exec::quote(&host),
+        body = body.join("\n\n"),
+    )
+}
+
+/// Writes `ServerConfig_<time>.ps1` to the backup directory.
+pub fn export() -> OperationOutcome {
+    message::log("Reading roles, services and settings...");
+    let output = match exec::powershell(SETTINGS_SCRIPT) {
+        Ok(output) if output.success => output,
+        Ok(output) => return OperationOutcome::failed(format!("Failed to read the server settings:\n{}", output.stderr.trim())),
+        Err(e) => return OperationOutcome::failed(format!("Failed to read the server settings: {}", e)),
+    };
+    let dir = backup_dir();
+    let path = dir.join(format!("ServerConfig_{}.ps1", dates::now()));
+    let config = render(&output.stdout);
+    if let Err(e) = std::fs::create_dir_all(&dir).and_then(|_| std::fs::write(&path, config.replace('\n', "\r\n"))) {
+        return OperationOutcome::failed(format!("Failed to write {}: {}", path.display(), e));
+    }
+    let resources = config.lines().filter(|l| l.starts_with("        ") && l.ends_with(" {")).count();
+    history::record(&format!("Exported a DSC configuration to {}", path.display()));
+    OperationOutcome::Success(format!(
+        "Exported {} DSC resources to:\n  {}\n\n\
+        Review it, then compile and apply it on a server with:\n  \
+        . '{}'\n  \
+        ServerConfig -NodeName <server> -OutputPath .\\ServerConfig\n  \
+        Start-DscConfiguration -Path .\\ServerConfig -Wait -Verbose",
+        resources,
+        path.display(),
+        path.display()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_builtin_resources_for_the_exported_settings() {
+        let config = render(
+            "Host\tFS01\nFeature\tFS-FileServer\nDisabled\tSpooler\nEnv\tJAVA_HOME\tC:\\Java\n\
+            Registry\tHKEY_LOCAL_MACHINE\\SYSTEM\\CurrentControlSet\\Control\\Terminal Server\tfDenyTSConnections\t0\n\
+            Registry\tHKEY_LOCAL_MACHINE\\SYSTEM\\X\tUserAuthentication\t\nAdmin\tCORP\\Domain Admins\n",
+        );
+
+        assert!(config.contains("param([string[]]$NodeName = 'FS01')"));
+        assert!(config.contains("WindowsFeature Feature_FS_FileServer {\n            Name   = 'FS-FileServer'"));
+        assert!(config.contains("Service Service_Spooler {"));
+        assert!(config.contains("Value  = 'C:\\Java'"));
+        assert!(config.contains("ValueName = 'fDenyTSConnections'"));
+        assert!(!config.contains("UserAuthentication"));
+        assert!(config.contains("MembersToInclude = @('CORP\\Domain Admins')"));
+    }
+}
@@ -0,0 +1,192 @@
+use crate::{backup_dir, exec};
+
+const ENV_KEY: &str = r"HKLM:\SYSTEM\CurrentControlSet\Control\Session Manager\Environment";
+
+/// Tells running processes (Explorer, services started later) that the environment changed.
+const BROADCAST_SCRIPT: &str = r#"
+Add-Type -Namespace ServerHelper -Name Env -MemberDefinition '[DllImport("user32.dll", CharSet = CharSet.Auto)] public static extern IntPtr SendMessageTimeout(IntPtr hWnd, uint Msg, UIntPtr wParam, string lParam, uint fuFlags, uint uTimeout, out UIntPtr lpdwResult);'
+$r = [UIntPtr]::Zero
+[ServerHelper.Env]::SendMessageTimeout([IntPtr]0xffff, 0x1A, [UIntPtr]::Zero, 'Environment', 2, 5000, [ref]$r) | Out-Null
+"#;
+
+/// Lists machine-level variables with their raw (unexpanded) values.
+pub fn machine_variables() -> Result<Vec<Vec<String>>, String> {
+    let script = format!(
+        "$k = Get-Item -Path {}; foreach ($n in $k.GetValueNames()) {{ \"$n`t$($k.GetValue($n, $null, 'DoNotExpandEnvironmentNames'))\" }}",
+        exec::quote(ENV_KEY)
+    );
+    let output = exec::powershell(&script).map_err(|e| format!("Failed to read environment: {}", e))?;
+    if !output.success {
+        return Err(format!("Failed to read environment:\n{}", output.stderr.trim()));
+    }
+    let mut rows: Vec<Vec<String>> = output
+        .stdout
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .map(|(name, value)| vec![name.to_string(), value.trim_end().to_string()])
+        .collect();
+    rows.sort_by_key(|row| row[0].to_lowercase());
+    Ok(rows)
+}
+
+fn current_value(name: &str) -> Result<Option<String>, String> {
+    Ok(machine_variables()?
+        .into_iter()
+        .find(|row| row[0].eq_ignore_ascii_case(name))
+        .map(|row| row[1].clone()))
+}
+
+/// Lists the machine PATH one directory per row, flagging directories that do not exist.
+pub fn path_entries() -> Result<Vec<Vec<String>>, String> {
+    let path = current_value("Path")?.unwrap_or_default();
+    Ok(path
+        .split(';')
+        .filter(|dir| !dir.trim().is_empty())
+        .enumerate()
+        .map(|(i, dir)| {
+            let exists = std::path::Path::new(&expand(dir)).exists();
+            vec![(i + 1).to_string(), dir.to_string(), if exists { "yes" } else { "MISSING" }.to_string()]
+        })
+        .collect())
+}
+
+/// Expands `%VAR%` references using this process's environment.
+fn expand(value: &str) -> String {
+    let mut result = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find('%') {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        match after.find('%') {
+            Some(end) => {
+                let name = &after[..end];
+                match std::env::var(name) {
+                    Ok(v) => result.push_str(&v),
+                    Err(_) => result.push_str(&format!("%{}%", name)),
+                }
+                rest = &after[end + 1..];
+            }
+            None => {
+                result.push_str(&rest[start..]);
+                rest = "";
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Writes the previous value of `name` to the backup directory.
+fn backup_value(name: &str, value: &str) -> Result<std::path::PathBuf, String> {
+    let dir = backup_dir();
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create backup directory: {}", e))?;
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let file = dir.join(format!("env_{}_{}.txt", name, timestamp));
+    exec::write_file(&file, &format!("{}={}\r\n", name, value))
+        .map_err(|e| format!("Failed to back up {}: {}", name, e))?;
+    Ok(file)
+}
+
+fn valid_name(name: &str) -> bool {
+    !name.is_empty() && !name.contains(['=', '\t', '%']) && name.trim() == name
+}
+
+/// Sets a machine variable, keeping expandable (`%VAR%`) values as REG_EXPAND_SZ.
+pub fn set_variable(name: &str, value: &str) -> Result<String, String> {
+    if !valid_name(name) {
+        return Err(format!("Invalid variable name: {}", name));
+    }
+    let backup = match current_value(name)? {
+        Some(old) => Some(backup_value(name, &old)?),
+        None => None,
+    };
+
+    let kind = if value.contains('%') || name.eq_ignore_ascii_case("Path") {
+        "ExpandString"
+    } else {
+        "String"
+    };
+    let script = format!(
+        "New-ItemProperty -Path {} -Name {} -Value {} -PropertyType {} -Force -ErrorAction Stop | Out-Null; {}",
+        exec::quote(ENV_KEY),
+        exec::quote(name),
+        exec::quote(value),
+        kind,
+        BROADCAST_SCRIPT
+    );
+    match exec::change(&script) {
+        Ok(output) if output.success => Ok(match backup {
+            Some(file) => format!("Set {} (previous value saved to {})", name, file.display()),
+            None => format!("Created {}", name),
+        }),
+        Ok(output) => Err(format!("Failed to set {}: {}", name, output.stderr.trim())),
+        Err(e) => Err(format!("Failed to set {}: {}", name, e)),
+    }
+}
+
+/// Creates or replaces a variable from `NAME=value`.
+pub fn set_from_assignment(spec: &str) -> Result<String, String> {
+    match spec.split_once('=') {
+        Some((name, value)) => set_variable(name.trim(), value.trim()),
+        None => Err("Expected: NAME=value".to_string()),
+    }
+}
+
+pub fn delete_variable(name: &str) -> Result<String, String> {
+    let old = current_value(name)?.ok_or_else(|| format!("{} does not exist", name))?;
+    let backup = backup_value(name, &old)?;
+    let script = format!(
+        "Remove-ItemProperty -Path {} -Name {} -ErrorAction Stop; {}",
+        exec::quote(ENV_KEY),
+        exec::quote(name),
+        BROADCAST_SCRIPT
+    );
+    match exec::change(&script) {
+        Ok(output) if output.success => Ok(format!("Deleted {} (previous value saved to {})", name, backup.display())),
+        Ok(output) => Err(format!("Failed to delete {}: {}", name, output.stderr.trim())),
+        Err(e) => Err(format!("Failed to delete {}: {}", name, e)),
+    }
+}
+
+pub fn add_path_entry(dir: &str) -> Result<String, String> {
+    let dir = dir.trim().trim_end_matches('\\');
+    if dir.is_empty() || dir.contains(';') {
+        return Err("Enter a single directory".to_string());
+    }
+    let path = current_value("Path")?.unwrap_or_default();
+    let mut entries: Vec<&str> = path.split(';').filter(|d| !d.trim().is_empty()).collect();
+    if entries.iter().any(|d| d.trim_end_matches('\\').eq_ignore_ascii_case(dir)) {
+        return Err(format!("{} is already on PATH", dir));
+    }
+    entries.push(dir);
+    set_variable("Path", &entries.join(";")).map(|msg| format!("Added {} to PATH. {}", dir, msg))
+}
+
+/// Removes entry `index` (1-based, as listed) if it still matches `dir`.
+pub fn remove_path_entry(index: usize, dir: &str) -> Result<String, String> {
+    let path = current_value("Path")?.unwrap_or_default();
+    let mut entries: Vec<&str> = path.split(';').filter(|d| !d.trim().is_empty()).collect();
+    if index == 0 || entries.get(index - 1) != Some(&dir) {
+        return Err("PATH changed since it was listed. Press r to reload.".to_string());
+    }
+    entries.remove(index - 1);
+    set_variable("Path", &entries.join(";")).map(|msg| format!("Removed {} from PATH. {}", dir, msg))
+}
+
+/// Reloads PATH for this process from the machine and user values, so tools
+/// installed since start-up (e.g. winget) are found without restarting.
+pub fn reload_process_path() -> Result<String, String> {
+    let output = exec::powershell(
+        "[Environment]::GetEnvironmentVariable('Path', 'Machine') + ';' + [Environment]::GetEnvironmentVariable('Path', 'User')",
+    )
+    .map_err(|e| format!("Failed to read PATH: {}", e))?;
+    let path = output.stdout.trim();
+    if path.is_empty() || path == ";" {
+        return Err("PATH came back empty, leaving it unchanged".to_string());
+    }
+    std::env::set_var("PATH", path);
+    Ok(format!("Reloaded PATH for this session ({} entries)", path.split(';').filter(|d| !d.is_empty()).count()))
+}
@@ -0,0 +1,441 @@
+use std::{
+    fs,
+    io::{self, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+    sync::{Arc, Mutex, MutexGuard, TryLockError},
+};
+
+/// Captured result of an external command.
+pub struct CommandOutput {
+    pub success: bool,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// Where scripts and programs actually run. The system runner is used unless a thread
+/// installs another with [`set_runner`]; tests install a scripted fake this way.
+pub trait CommandRunner: Send + Sync {
+    fn powershell(&self, script: &str) -> io::Result<CommandOutput>;
+    fn run(&self, program: &str, args: &[&str]) -> io::Result<CommandOutput>;
+    /// Downloads `url` to `path`, returning the number of bytes written.
+    fn download(&self, url: &str, path: &Path) -> Result<u64, String>;
+}
+
+/// Runs everything for real: PowerShell on the persistent host, programs directly,
+/// downloads over HTTP.
+struct SystemRunner;
+
+impl CommandRunner for SystemRunner {
+    fn powershell(&self, script: &str) -> io::Result<CommandOutput> {
+        powershell_on_host(script)
+    }
+
+    fn run(&self, program: &str, args: &[&str]) -> io::Result<CommandOutput> {
+        let output = Command::new(program).args(args).output()?;
+
+        Ok(CommandOutput {
+            success: output.status.success(),
+            stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+        })
+    }
+
+    fn download(&self, url: &str, path: &Path) -> Result<u64, String> {
+        crate::download::fetch_http(url, path)
+    }
+}
+
+thread_local! {
+    static RUNNER: std::cell::RefCell<Option<Arc<dyn CommandRunner>>> = const { std::cell::RefCell::new(None) };
+}
+
+/// The runner for this thread.
+pub fn runner() -> Arc<dyn CommandRunner> {
+    RUNNER.with(|r| r.borrow().clone()).unwrap_or_else(|| Arc::new(SystemRunner))
+}
+
+/// Makes this thread run commands through `runner`. Worker threads call this with the
+/// spawning thread's [`runner`] so they run commands the same way.
+pub fn set_runner(runner: Arc<dyn CommandRunner>) {
+    RUNNER.with(|r| *r.borrow_mut() = Some(runner));
+}
+
+/// Read-eval loop run by the persistent PowerShell host. Each request is one line
+/// of base64 (UTF-8) script; output comes back as `O:`/`E:` lines followed by the
+/// end marker and 1/0 for success. Each script runs in its own scope, and `exit`
+/// in a script ends the host, which is then restarted for the next request. Everything
+/// but errors goes through one `Out-String`, as `Format-List` and `Format-Table` records
+/// only format in sequence.
+const HOST_SCRIPT: &str = r#"
+$ProgressPreference = 'SilentlyContinue'
+[Console]::OutputEncoding = New-Object System.Text.UTF8Encoding $false
+function Emit($prefix, $text) {
+    foreach ($l in ($text -split "`r?`n")) { [Console]::Out.WriteLine($prefix + $l) }
+}
+while ($true) {
+    $line = [Console]::In.ReadLine()
+    if ($line -eq $null) { break }
+    $script = [Text.Encoding]::UTF8.GetString([Convert]::FromBase64String($line))
+    $global:LASTEXITCODE = 0
+    $global:__ok = $true
+    try {
+        $sb = [ScriptBlock]::Create($script + "`n`$global:__ok = `$?")
+        & $sb *>&1 | ForEach-Object {
+            if ($_ -is [System.Management.Automation.ErrorRecord]) { Emit 'E:' "$_" }
+            elseif ($_ -is [System.Management.Automation.WarningRecord]) { "WARNING: $($_.Message)" }
+            else { $_ }
+        } | Out-String -Stream -Width 4096 | ForEach-Object { Emit 'O:' $_.TrimEnd() }
+        $ok = $global:__ok
+    } catch {
+        $ok = $false
+        Emit 'E:' "$_"
+    }
+    [Console]::Out.WriteLine('__MARKER__' + [int][bool]$ok)
+    [Console]::Out.Flush()
+}
+"#;
+
+/// A long-lived powershell.exe that runs scripts back-to-back, avoiding the
+/// 2-4 second start-up of a fresh process per command.
+struct Host {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    marker: String,
+}
+
+static HOST: Mutex<Option<Host>> = Mutex::new(None);
+
+impl Host {
+    fn start() -> io::Result<Host> {
+        let nonce = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+        let marker = format!("__SERVER_HELPER_END_{}_{}__", std::process::id(), nonce);
+        let script: Vec<u8> = HOST_SCRIPT
+            .replace("__MARKER__", &marker)
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        let mut child = Command::new("powershell")
+            .args(["-NoLogo", "-NoProfile", "-NonInteractive", "-ExecutionPolicy", "Bypass", "-EncodedCommand"])
+            .arg(base64(&script))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let stdin = child.stdin.take().ok_or_else(|| io::Error::other("no stdin"))?;
+        let stdout = child.stdout.take().ok_or_else(|| io::Error::other("no stdout"))?;
+        Ok(Host { child, stdin, stdout: BufReader::new(stdout), marker })
+    }
+
+    fn send(&mut self, script: &str) -> io::Result<()> {
+        writeln!(self.stdin, "{}", base64(script.as_bytes()))?;
+        self.stdin.flush()
+    }
+
+    /// Collects output up to the end marker. The flag is false when the host exited
+    /// instead (the script called `exit`); its exit code then decides success.
+    fn receive(&mut self) -> io::Result<(CommandOutput, bool)> {
+        let mut output = CommandOutput { success: false, stdout: String::new(), stderr: String::new() };
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            if self.stdout.read_until(b'\n', &mut line)? == 0 {
+                output.success = self.child.wait()?.success();
+                return Ok((output, false));
+            }
+            let text = String::from_utf8_lossy(&line);
+            let text = text.trim_end_matches(['\r', '\n']);
+            if let Some(flag) = text.strip_prefix(self.marker.as_str()) {
+                output.success = flag == "1";
+                return Ok((output, true));
+            } else if let Some(err) = text.strip_prefix("E:") {
+                output.stderr.push_str(err);
+                output.stderr.push('\n');
+            } else {
+                output.stdout.push_str(text.strip_prefix("O:").unwrap_or(text));
+                output.stdout.push('\n');
+            }
+        }
+    }
+}
+
+/// Runs `script` on the persistent host, starting it if needed. Returns
+/// `Ok(None)` when the host could not take the script, so nothing ran.
+fn run_on_host(slot: &mut Option<Host>, script: &str) -> io::Result<Option<CommandOutput>> {
+    // A host left over from an earlier `exit` fails on send; retry once with a new one
+    for _ in 0..2 {
+        let host = match slot {
+            Some(host) => host,
+            None => match Host::start() {
+                Ok(host) => slot.insert(host),
+                Err(_) => return Ok(None),
+            },
+        };
+        if host.send(script).is_err() {
+            *slot = None;
+            continue;
+        }
+        let (output, alive) = match host.receive() {
+            Ok(result) => result,
+            Err(e) => {
+                *slot = None;
+                return Err(e);
+            }
+        };
+        if !alive {
+            *slot = None;
+        }
+        return Ok(Some(output));
+    }
+    Ok(None)
+}
+
+/// Runs a PowerShell snippet and captures its output.
+pub fn powershell(script: &str) -> io::Result<CommandOutput> {
+    runner().powershell(script)
+}
+
+/// Scripts go to the persistent host; while it is busy (e.g. a background job
+/// is using it) or cannot be started, a fresh powershell.exe is used instead.
+fn powershell_on_host(script: &str) -> io::Result<CommandOutput> {
+    match HOST.try_lock() {
+        Ok(mut slot) => {
+            if let Some(output) = run_on_host(&mut slot, script)? {
+                return Ok(output);
+            }
+        }
+        Err(TryLockError::Poisoned(poisoned)) => {
+            let mut slot = poisoned.into_inner();
+            *slot = None;
+        }
+        Err(TryLockError::WouldBlock) => {}
+    }
+    powershell_process(script)
+}
+
+/// Runs a PowerShell snippet in its own powershell.exe.
+fn powershell_process(script: &str) -> io::Result<CommandOutput> {
+    let output = Command::new("powershell")
+        .args(["-Command", script])
+        .output()?;
+
+    Ok(CommandOutput {
+        success: output.status.success(),
+        stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+        stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+    })
+}
+
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = (u32::from(b[0]) << 16) | (u32::from(b[1]) << 8) | u32::from(b[2]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i)) as usize & 63] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Parses `Key=Value` lines (as printed by our PowerShell probes) into a map.
+pub fn key_values(stdout: &str) -> std::collections::HashMap<String, String> {
+    stdout
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+        .collect()
+}
+
+/// Splits tab-separated output into rows, dropping blank lines.
+pub fn tab_rows(stdout: &str) -> Vec<Vec<String>> {
+    stdout
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.split('\t').map(|c| c.trim().to_string()).collect())
+        .collect()
+}
+
+/// Quotes a value as a PowerShell single-quoted string literal.
+pub fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+/// Script file that changes are written to while export mode is on.
+struct Export {
+    path: PathBuf,
+    steps: usize,
+    /// Steps recorded since the UI last asked, so it can report them.
+    unseen: Vec<String>,
+}
+
+static EXPORT: Mutex<Option<Export>> = Mutex::new(None);
+
+fn export_slot() -> MutexGuard<'static, Option<Export>> {
+    match EXPORT.lock() {
+        Ok(slot) => slot,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+/// Starts export mode: from now on, changes are appended to the script at `path`
+/// instead of being run. Reads still run so actions can plan their changes.
+pub fn start_export(path: &Path) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let header = format!(
+        "# Generated by Server Helper on {}\r\n\
+        # Review each step before running this script in an elevated PowerShell.\r\n\
+        #Requires -RunAsAdministrator\r\n\r\n",
+        std::env::var("COMPUTERNAME").unwrap_or_else(|_| "this computer".to_string())
+    );
+    fs::write(path, header)?;
+    *export_slot() = Some(Export { path: path.to_path_buf(), steps: 0, unseen: Vec::new() });
+    Ok(())
+}
+
+/// Ends export mode, returning the script path and how many steps it holds.
+pub fn stop_export() -> Option<(PathBuf, usize)> {
+    export_slot().take().map(|export| (export.path, export.steps))
+}
+
+/// Script path and step count while export mode is on.
+pub fn export_status() -> Option<(PathBuf, usize)> {
+    export_slot().as_ref().map(|export| (export.path.clone(), export.steps))
+}
+
+/// Steps exported since the last call.
+pub fn take_exported() -> Vec<String> {
+    export_slot().as_mut().map(|export| std::mem::take(&mut export.unseen)).unwrap_or_default()
+}
+
+/// Appends `script` to the export file. Returns `Ok(None)` when export mode is off.
+fn record(script: &str) -> io::Result<Option<CommandOutput>> {
+    let mut slot = export_slot();
+    let export = match slot.as_mut() {
+        Some(export) => export,
+        None => return Ok(None),
+    };
+    export.steps += 1;
+    let first_line = script.lines().map(str::trim).find(|l| !l.is_empty()).unwrap_or_default();
+    let mut file = fs::OpenOptions::new().append(true).open(&export.path)?;
+    write!(
+        file,
+        "# Step {}\r\n{}\r\n\r\n",
+        export.steps,
+        script.trim().replace("\r\n", "\n").replace('\n', "\r\n")
+    )?;
+    export.unseen.push(first_line.to_string());
+    Ok(Some(CommandOutput {
+        success: true,
+        stdout: String::new(),
+        stderr: String::new(),
+    }))
+}
+
+/// Runs a PowerShell snippet that changes the system, or writes it to the
+/// export script when export mode is on.
+pub fn change(script: &str) -> io::Result<CommandOutput> {
+    match record(script)? {
+        Some(output) => Ok(output),
+        None => powershell(script),
+    }
+}
+
+/// [`run`] for programs that change the system; exported like [`change`].
+pub fn change_program(program: &str, args: &[&str]) -> io::Result<CommandOutput> {
+    let quoted: Vec<String> = args.iter().map(|a| quote(a)).collect();
+    match record(&format!("& {} {}", quote(program), quoted.join(" ")))? {
+        Some(output) => Ok(output),
+        None => run(program, args),
+    }
+}
+
+/// Writes a file, or the equivalent PowerShell in export mode.
+pub fn write_file(path: &Path, contents: &str) -> io::Result<()> {
+    let script = format!(
+        "[IO.File]::WriteAllText({}, {})",
+        quote(&path.to_string_lossy()),
+        quote(contents)
+    );
+    match record(&script)? {
+        Some(_) => Ok(()),
+        None => fs::write(path, contents),
+    }
+}
+
+/// Copies a file, or the equivalent PowerShell in export mode.
+pub fn copy_file(from: &Path, to: &Path) -> io::Result<()> {
+    let script = format!(
+        "Copy-Item -LiteralPath {} -Destination {} -Force",
+        quote(&from.to_string_lossy()),
+        quote(&to.to_string_lossy())
+    );
+    match record(&script)? {
+        Some(_) => Ok(()),
+        None => fs::copy(from, to).map(|_| ()),
+    }
+}
+
+/// Runs a program directly (without a shell) and captures its output.
+pub fn run(program: &str, args: &[&str]) -> io::Result<CommandOutput> {
+    runner().run(program, args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_host_formats_format_list_output_in_one_piece() {
+        // Needs Windows PowerShell; elsewhere there is no host to talk to
+        let Ok(mut host) = Host::start() else { return };
+        let script = "[pscustomobject]@{ Name = 'W3SVC'; Status = 'Running' }, \
+            [pscustomobject]@{ Name = 'WinRM'; Status = 'Stopped' } | Format-List\n\
+            Get-Item -LiteralPath 'C:\\' | Format-Table Name";
+
+        let sent = host.send(script);
+        let (output, alive) = host.receive().unwrap_or_else(|e| panic!("no reply from the host: {}", e));
+
+        assert!(sent.is_ok() && alive && output.success, "{}", output.stderr);
+        assert_eq!(output.stderr, "");
+        assert!(output.stdout.contains("Name   : W3SVC") && output.stdout.contains("Status : Stopped"), "{}", output.stdout);
+        assert_eq!(output.stdout.matches("Name   :").count(), 2);
+        assert!(output.stdout.lines().any(|l| l.trim() == "C:\\"));
+    }
+
+    #[test]
+    fn key_values_split_on_the_first_equals_sign() {
+        let values = key_values("Name=Administrator\nUrl=https://host/?a=b\nnoise\n");
+        assert_eq!(values["Name"], "Administrator");
+        assert_eq!(values["Url"], "https://host/?a=b");
+        assert_eq!(values.len(), 2);
+    }
+
+    #[test]
+    fn tab_rows_drop_blank_lines_and_trim_cells() {
+        assert_eq!(tab_rows("a\t b \n\n c\r\n"), [vec!["a", "b"], vec!["c"]]);
+    }
+
+    #[test]
+    fn quote_escapes_single_quotes() {
+        assert_eq!(quote("it's"), "'it''s'");
+    }
+
+    #[test]
+    fn base64_pads_partial_chunks() {
+        assert_eq!(base64(b"Ma"), "TWE=");
+        assert_eq!(base64(b"Man"), "TWFu");
+        assert_eq!(base64(b"M"), "TQ==");
+    }
+}
@@ -0,0 +1,360 @@
+use std::path::{Path, PathBuf};
+
+use crate::{
+    backup_dir, conflicts, delta, exec,
+    plan::{Plan, Step},
+    rename,
+    signing::{self, Verification},
+};
+
+/// First line of a pre-restore snapshot, naming the backup that was restored.
+const SNAPSHOT_SOURCE: &str = "# Source: ";
+
+const FEATURES_SCRIPT: &str = r#"
+Get-WindowsFeature | ForEach-Object { "$($_.Depth)`t$($_.Name)`t$($_.DisplayName)`t$($_.InstallState)" }
+"#;
+
+/// Lists every role, role service and feature in Server Manager order, indenting
+/// display names by depth so the table reads as a tree.
+pub fn feature_rows() -> Result<Vec<Vec<String>>, String> {
+    let output = exec::powershell(FEATURES_SCRIPT).map_err(|e| format!("Failed to list features: {}", e))?;
+    if !output.success {
+        return Err(format!("Failed to list features:\n{}", output.stderr.trim()));
+    }
+    Ok(exec::tab_rows(&output.stdout)
+        .into_iter()
+        .filter(|row| row.len() == 4)
+        .map(|row| {
+            let depth: usize = row[0].parse().unwrap_or(1);
+            let mark = match row[3].as_str() {
+                "Installed" => "[X]",
+                "InstallPending" | "UninstallPending" => "[~]",
+                _ => "[ ]",
+            };
+            let tree = format!("{}{} {}", "  ".repeat(depth.saturating_sub(1)), mark, row[2]);
+            vec![tree, row[1].clone(), row[3].clone()]
+        })
+        .collect())
+}
+
+/// Outcome of a successful feature install or removal.
+pub struct FeatureChange {
+    /// Display names of the features that changed state.
+    pub changed: String,
+    pub restart_needed: bool,
+    /// Command run, exit code and any warnings, for the job log.
+    pub output: String,
+}
+
+/// Installs (`install = true`) or removes a single feature, including its management tools.
+pub fn change_feature(name: &str, install: bool) -> Result<FeatureChange, String> {
+    let cmdlet = if install { "Install-WindowsFeature" } else { "Uninstall-WindowsFeature" };
+    let command = format!("{} -Name {} -IncludeManagementTools", cmdlet, exec::quote(name));
+    let script = format!(
+        "$r = {} -ErrorAction Stop -WarningVariable w -WarningAction SilentlyContinue; \
+        \"Success=$($r.Success)\"; \"RestartNeeded=$($r.RestartNeeded)\"; \"ExitCode=$($r.ExitCode)\"; \
+        \"Changed=$(($r.FeatureResult | ForEach-Object {{ $_.DisplayName }}) -join ', ')\"; \
+        $w | ForEach-Object {{ \"Warning=$_\" }}",
+        command
+    );
+    let output = exec::change(&script).map_err(|e| format!("Failed to run {}: {}", cmdlet, e))?;
+    if !output.success {
+        return Err(format!("{} failed:\n{}", command, output.stderr.trim()));
+    }
+
+    let values = exec::key_values(&output.stdout);
+    let get = |key: &str| values.get(key).cloned().unwrap_or_default();
+    let mut log = vec![format!("Ran: {}", command), format!("Exit code: {}", get("ExitCode"))];
+    log.extend(
+        output
+            .stdout
+            .lines()
+            .filter_map(|line| line.strip_prefix("Warning="))
+            .map(|w| format!("Warning: {}", w.trim())),
+    );
+    if get("Success") != "True" {
+        return Err(format!("{} did not succeed.\n{}", command, log.join("\n")));
+    }
+
+    let changed = match get("Changed") {
+        c if c.is_empty() => "nothing (already in the requested state)".to_string(),
+        c => c,
+    };
+    Ok(FeatureChange {
+        changed,
+        restart_needed: get("RestartNeeded") == "Yes",
+        output: log.join("\n"),
+    })
+}
+
+/// Newest `ServerRoles_<timestamp>.xml` or differential `.delta` in the backup directory.
+pub fn latest_backup() -> Option<PathBuf> {
+    std::fs::read_dir(backup_dir())
+        .ok()?
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let (timestamp, extension) = name.strip_prefix("ServerRoles_")?.split_once('.')?;
+            if extension != "xml" && extension != delta::EXTENSION {
+                return None;
+            }
+            Some((timestamp.parse::<u64>().ok()?, entry.path()))
+        })
+        .max_by_key(|(timestamp, _)| *timestamp)
+        .map(|(_, path)| path)
+}
+
+/// Reads the features recorded in the newest role backup as structured rows.
+pub fn last_backup_rows() -> Result<Vec<Vec<String>>, String> {
+    let path = latest_backup().ok_or_else(|| {
+        format!("No ServerRoles_*.xml backup found in {}", backup_dir().display())
+    })?;
+    let full = delta::full(&path)?;
+    let script = format!(
+        "Import-Clixml -Path {} | ForEach-Object {{ \"$($_.DisplayName)`t$($_.Name)`t$($_.FeatureType)`t$($_.Path)\" }}",
+        exec::quote(&full.to_string_lossy())
+    );
+    let output = exec::powershell(&script).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    if !output.success {
+        return Err(format!("Failed to read {}:\n{}", path.display(), output.stderr.trim()));
+    }
+    Ok(exec::tab_rows(&output.stdout)
+        .into_iter()
+        .filter(|row| row.len() == 4)
+        .collect())
+}
+
+/// Plans restoring `backup`: features recorded as installed there but missing here,
+/// installed in dependency order (roles, then role services, then features).
+pub fn restore_plan(backup: &Path) -> Result<Plan, String> {
+    if !backup.exists() {
+        return Err(format!("Backup file not found: {}", backup.display()));
+    }
+    let full = delta::full(backup)?;
+    let script = format!(
+        "$current = @{{}}; Get-WindowsFeature | ForEach-Object {{ $current[$_.Name] = $_.InstallState }}; \
+        Import-Clixml -Path {} | Where-Object {{ $_.Installed }} | ForEach-Object {{ \
+            $state = if ($current.ContainsKey($_.Name)) {{ $current[$_.Name] }} else {{ 'Unavailable' }}; \
+            \"$($_.Name)`t$($_.FeatureType)`t$state\" \
+        }}",
+        exec::quote(&full.to_string_lossy())
+    );
+    let output = exec::powershell(&script).map_err(|e| format!("Failed to read backup file: {}", e))?;
+    if !output.success {
+        return Err(format!("Failed to read {}:\n{}", backup.display(), output.stderr.trim()));
+    }
+
+    let rows: Vec<Vec<String>> = exec::tab_rows(&output.stdout).into_iter().filter(|r| r.len() == 3).collect();
+    let mut plan = Plan::new(format!("Restore roles and features from {}", backup.display()));
+    for (kind, label) in [("Role", "roles"), ("Role Service", "role services"), ("Feature", "features")] {
+        let names: Vec<String> = rows
+            .iter()
+            .filter(|r| r[1] == kind && r[2] != "Installed" && r[2] != "Unavailable")
+            .map(|r| exec::quote(&r[0]))
+            .collect();
+        if !names.is_empty() {
+            plan.step(
+                format!("Install {} {}", names.len(), label),
+                format!(
+                    "Install-WindowsFeature -Name {} -IncludeManagementTools -ErrorAction Stop | \
+                    Format-List Success, RestartNeeded, ExitCode, FeatureResult",
+                    names.join(",")
+                ),
+            );
+        }
+    }
+
+    if !plan.steps.is_empty() {
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let snapshot = backup_dir().join(format!("PreRestore_{}.txt", timestamp));
+        plan.steps.insert(0, Step {
+            description: "Snapshot the installed features so the restore can be rolled back".to_string(),
+            command: format!(
+                "New-Item -ItemType Directory -Force -Path {} | Out-Null\n\
+                $names = Get-WindowsFeature | Where-Object Installed | ForEach-Object Name\n\
+                Set-Content -LiteralPath {} -Value (@({}) + $names) -Encoding UTF8",
+                exec::quote(&backup_dir().to_string_lossy()),
+                exec::quote(&snapshot.to_string_lossy()),
+                exec::quote(&format!("{}{}", SNAPSHOT_SOURCE, backup.display()))
+            ),
+        });
+    }
+
+    let restoring: Vec<String> = rows
+        .iter()
+        .filter(|r| r[2] != "Installed" && r[2] != "Unavailable")
+        .map(|r| r[0].clone())
+        .collect();
+    match conflicts::check(&restoring) {
+        Ok(found) => plan.conflicts = found,
+        Err(message) => plan.note(format!("Could not check for conflicting workloads: {}", message)),
+    }
+    let verification = signing::verify(backup);
+    if !matches!(verification, Verification::Invalid(_)) {
+        plan.note(verification.describe());
+    }
+    if let Some(problem) = signing::integrity_problem(&verification) {
+        plan.conflicts.push(format!("Backup integrity: {}", problem));
+    }
+
+    rename::adapt_plan(&mut plan, backup);
+
+    let installed = rows.iter().filter(|r| r[2] == "Installed").count();
+    if installed > 0 {
+        plan.note(format!("{} feature(s) from the backup are already installed", installed));
+    }
+    let unavailable: Vec<&str> = rows.iter().filter(|r| r[2] == "Unavailable").map(|r| r[0].as_str()).collect();
+    if !unavailable.is_empty() {
+        plan.note(format!("Not available on this server, skipped: {}", unavailable.join(", ")));
+    }
+    Ok(plan)
+}
+
+/// Newest pre-restore snapshot that has not been rolled back yet.
+fn latest_snapshot() -> Option<PathBuf> {
+    std::fs::read_dir(backup_dir())
+        .ok()?
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let timestamp: u64 = name.strip_prefix("PreRestore_")?.strip_suffix(".txt")?.parse().ok()?;
+            Some((timestamp, entry.path()))
+        })
+        .max_by_key(|(timestamp, _)| *timestamp)
+        .map(|(_, path)| path)
+}
+
+/// Plans undoing the last restore: features installed now that were neither
+/// installed before it (per its snapshot) nor absent from the restored backup.
+/// Features that were already present are never removed.
+pub fn rollback_plan() -> Result<Plan, String> {
+    let snapshot = latest_snapshot().ok_or_else(|| {
+        format!("No restore snapshot (PreRestore_*.txt) found in {}", backup_dir().display())
+    })?;
+    let content = std::fs::read_to_string(&snapshot)
+        .map_err(|e| format!("Failed to read {}: {}", snapshot.display(), e))?;
+    let content = content.trim_start_matches('\u{feff}');
+    let source = content
+        .lines()
+        .next()
+        .and_then(|line| line.strip_prefix(SNAPSHOT_SOURCE))
+        .map(|s| s.trim().to_string())
+        .ok_or_else(|| format!("{} does not name the backup that was restored", snapshot.display()))?;
+    if !Path::new(&source).exists() {
+        return Err(format!(
+            "The restored backup {} is missing, so the features it added cannot be told apart.",
+            source
+        ));
+    }
+    let restored = delta::full(Path::new(&source))?;
+
+    let script = format!(
+        "$before = Get-Content -LiteralPath {} | Where-Object {{ $_ -and -not $_.StartsWith('#') }}; \
+        $restored = Import-Clixml -Path {} | Where-Object Installed | ForEach-Object Name; \
+        Get-WindowsFeature | Where-Object {{ $_.Installed -and $before -notcontains $_.Name -and $restored -contains $_.Name }} | \
+            ForEach-Object {{ \"$($_.Name)`t$($_.DisplayName)\" }}",
+        exec::quote(&snapshot.to_string_lossy()),
+        exec::quote(&restored.to_string_lossy())
+    );
+    let output = exec::powershell(&script).map_err(|e| format!("Failed to compare features: {}", e))?;
+    if !output.success {
+        return Err(format!("Failed to compare features:\n{}", output.stderr.trim()));
+    }
+    let added: Vec<Vec<String>> = exec::tab_rows(&output.stdout).into_iter().filter(|r| r.len() == 2).collect();
+
+    let mut plan = Plan::new(format!("Roll back the restore from {}", source));
+    if !added.is_empty() {
+        let names: Vec<String> = added.iter().map(|r| exec::quote(&r[0])).collect();
+        plan.step(
+            format!("Remove {} feature(s) added by the restore", added.len()),
+            format!(
+                "Uninstall-WindowsFeature -Name {} -ErrorAction Stop | \
+                Format-List Success, RestartNeeded, ExitCode, FeatureResult",
+                names.join(",")
+            ),
+        );
+        plan.note(format!("Features to remove:\n    {}", added.iter().map(|r| r[1].as_str()).collect::<Vec<_>>().join("\n    ")));
+    }
+    plan.step(
+        "Mark the snapshot as rolled back",
+        format!(
+            "Rename-Item -LiteralPath {} -NewName {}",
+            exec::quote(&snapshot.to_string_lossy()),
+            exec::quote(&snapshot.with_extension("rolledback.txt").file_name().unwrap_or_default().to_string_lossy())
+        ),
+    );
+    plan.note(format!("Snapshot taken before the restore: {}", snapshot.display()));
+    plan.note("Features installed before the restore are kept.");
+    Ok(plan)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, FakeRunner};
+
+    fn backup_file(name: &str) -> PathBuf {
+        let path = testing::temp_dir(name).join("ServerRoles_1700000000.xml");
+        std::fs::write(&path, "<Objs />").unwrap();
+        path
+    }
+
+    #[test]
+    fn restore_plan_installs_missing_features_in_dependency_order() {
+        FakeRunner::new()
+            .on(
+                "Import-Clixml",
+                "Web-Server\tRole\tAvailable\n\
+                Web-Mgmt-Console\tRole Service\tAvailable\n\
+                NET-Framework-45-Core\tFeature\tInstalled\n\
+                Web-Asp-Net45\tRole Service\tAvailable\n\
+                Hyper-V\tRole\tUnavailable\n\
+                Telnet-Client\tFeature\tAvailable\n",
+            )
+            .install();
+
+        let plan = restore_plan(&backup_file("restore-order")).unwrap();
+
+        let descriptions: Vec<&str> = plan.steps.iter().map(|s| s.description.as_str()).collect();
+        assert_eq!(
+            descriptions,
+            [
+                "Snapshot the installed features so the restore can be rolled back",
+                "Install 1 roles",
+                "Install 2 role services",
+                "Install 1 features",
+            ]
+        );
+        assert!(plan.steps[2].command.contains("'Web-Mgmt-Console','Web-Asp-Net45'"));
+        assert_eq!(
+            plan.notes,
+            [
+                "Not signed",
+                "1 feature(s) from the backup are already installed",
+                "Not available on this server, skipped: Hyper-V"
+            ]
+        );
+    }
+
+    #[test]
+    fn restore_plan_is_empty_when_everything_is_installed() {
+        FakeRunner::new().on("Import-Clixml", "Web-Server\tRole\tInstalled\nmalformed line\n").install();
+
+        let plan = restore_plan(&backup_file("restore-nothing")).unwrap();
+
+        assert!(plan.steps.is_empty());
+    }
+
+    #[test]
+    fn restore_plan_reports_unreadable_backups() {
+        FakeRunner::new().fail("Import-Clixml", "The data is invalid.").install();
+
+        let error = restore_plan(&backup_file("restore-invalid")).err().unwrap();
+
+        assert!(error.contains("The data is invalid."), "{}", error);
+        assert!(restore_plan(Path::new("missing.xml")).err().unwrap().starts_with("Backup file not found"));
+    }
+}
@@ -0,0 +1,38 @@
+//! Stand-in for `features.rs` in builds without the `windows-roles` feature. The menu hides
+//! the role actions in such builds; anything that still reaches here gets a plain error.
+
+use std::path::{Path, PathBuf};
+
+use crate::plan::Plan;
+
+const DISABLED: &str = "This build of Server Helper was made without Windows roles and features support.";
+
+pub struct FeatureChange {
+    pub changed: String,
+    pub restart_needed: bool,
+    pub output: String,
+}
+
+pub fn feature_rows() -> Result<Vec<Vec<String>>, String> {
+    Err(DISABLED.to_string())
+}
+
+pub fn change_feature(_name: &str, _install: bool) -> Result<FeatureChange, String> {
+    Err(DISABLED.to_string())
+}
+
+pub fn latest_backup() -> Option<PathBuf> {
+    None
+}
+
+pub fn last_backup_rows() -> Result<Vec<Vec<String>>, String> {
+    Err(DISABLED.to_string())
+}
+
+pub fn restore_plan(_backup: &Path) -> Result<Plan, String> {
+    Err(DISABLED.to_string())
+}
+
+pub fn rollback_plan() -> Result<Plan, String> {
+    Err(DISABLED.to_string())
+}
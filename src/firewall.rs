@@ -0,0 +1,118 @@
+use std::path::Path;
+
+use crate::exec;
+
+/// An inbound allow rule built by the firewall rule wizard.
+#[derive(Clone, PartialEq)]
+pub struct InboundRule {
+    pub name: String,
+    /// `TCP`, `UDP`, or `Any` for program rules.
+    pub protocol: String,
+    /// Local ports and ranges, e.g. `8080`, `8090-8095`. Empty for program rules.
+    pub ports: Vec<String>,
+    pub program: Option<String>,
+    pub profiles: Vec<&'static str>,
+}
+
+fn parse_port(port: &str) -> Option<u16> {
+    port.trim().parse::<u16>().ok().filter(|p| *p > 0)
+}
+
+impl InboundRule {
+    /// Builds a rule from the wizard answers: the target is `[tcp|udp] ports` (e.g. `tcp 8080,8443`
+    /// or `udp 161`) or the path of a program, and profiles are any of domain, private, public.
+    pub fn new(name: String, target: &str, profiles: &str) -> Result<InboundRule, String> {
+        let target = target.trim().trim_matches('"');
+        let (protocol, ports, program) = if target.to_lowercase().ends_with(".exe") {
+            if !Path::new(target).is_file() {
+                return Err(format!("Program not found: {}", target));
+            }
+            ("Any".to_string(), Vec::new(), Some(target.to_string()))
+        } else {
+            let (protocol, spec) = match target.split_once(char::is_whitespace) {
+                Some((p, spec)) if p.eq_ignore_ascii_case("tcp") || p.eq_ignore_ascii_case("udp") => {
+                    (p.to_uppercase(), spec)
+                }
+                Some(_) => return Err(format!("Unknown protocol in '{}': use tcp or udp", target)),
+                None => ("TCP".to_string(), target),
+            };
+            let ports: Vec<String> = spec.split(',').map(|p| p.trim().to_string()).filter(|p| !p.is_empty()).collect();
+            for port in &ports {
+                let ok = match port.split_once('-') {
+                    Some((from, to)) => matches!((parse_port(from), parse_port(to)), (Some(from), Some(to)) if from < to),
+                    None => parse_port(port).is_some(),
+                };
+                if !ok {
+                    return Err(format!("Invalid port '{}': use 1-65535 or a range such as 8090-8095", port));
+                }
+            }
+            if ports.is_empty() {
+                return Err("Enter a port or a program path.".to_string());
+            }
+            (protocol, ports, None)
+        };
+
+        let mut chosen = Vec::new();
+        for profile in profiles.split([' ', ',']).filter(|p| !p.is_empty()) {
+            let profile = match profile.to_lowercase().as_str() {
+                "domain" => "Domain",
+                "private" => "Private",
+                "public" => "Public",
+                "any" | "all" => "Any",
+                other => return Err(format!("Unknown profile '{}': use domain, private, public or any", other)),
+            };
+            if !chosen.contains(&profile) {
+                chosen.push(profile);
+            }
+        }
+        if chosen.is_empty() || chosen.contains(&"Any") {
+            chosen = vec!["Any"];
+        }
+        Ok(InboundRule { name, protocol, ports, program, profiles: chosen })
+    }
+
+    /// What the rule allows, e.g. `TCP 8080, 8443` or a program path.
+    pub fn describe(&self) -> String {
+        match &self.program {
+            Some(program) => format!("{} (any port)", program),
+            None => format!("{} {}", self.protocol, self.ports.join(", ")),
+        }
+    }
+
+    /// The PowerShell that creates the rule, shown before it runs.
+    pub fn command(&self) -> String {
+        let target = match &self.program {
+            Some(program) => format!("-Program {}", exec::quote(program)),
+            None => {
+                let ports: Vec<String> = self.ports.iter().map(|p| exec::quote(p)).collect();
+                format!("-Protocol {} -LocalPort {}", self.protocol, ports.join(","))
+            }
+        };
+        format!(
+            "New-NetFirewallRule -DisplayName {} -Direction Inbound -Action Allow {} -Profile {}",
+            exec::quote(&self.name),
+            target,
+            self.profiles.join(",")
+        )
+    }
+}
+
+/// Creates the rule unless one with the same display name already exists.
+pub fn create(rule: &InboundRule) -> (bool, String) {
+    let script = format!(
+        "if (Get-NetFirewallRule -DisplayName {} -ErrorAction SilentlyContinue) {{ throw 'A rule with this name already exists' }}\n\
+        {} -ErrorAction Stop | Out-Null",
+        exec::quote(&rule.name),
+        rule.command()
+    );
+    match exec::change(&script) {
+        Ok(output) if output.success => (true, format!(
+            "Created inbound rule '{}' allowing {} on the {} profile(s).",
+            rule.name,
+            rule.describe(),
+            rule.profiles.join(", ")
+        )),
+        Ok(output) => (false, format!("Failed to create the rule: {}", output.stderr.trim())),
+        Err(e) => (false, format!("Failed to create the rule: {}", e)),
+    }
+}
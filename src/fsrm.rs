@@ -0,0 +1,163 @@
+use std::path::Path;
+
+use crate::exec;
+
+const NOT_INSTALLED: &str = "File Server Resource Manager is not installed.\n\
+    Install the FS-Resource-Manager role service from Browse Roles & Features first.";
+
+/// Fails unless the FSRM role service and its cmdlets are present.
+fn require_fsrm() -> Result<(), String> {
+    let output = exec::powershell(
+        "(Get-WindowsFeature -Name FS-Resource-Manager).Installed -and [bool](Get-Command Get-FsrmQuota -ErrorAction SilentlyContinue)",
+    )
+    .map_err(|e| format!("Failed to check for FSRM: {}", e))?;
+    if output.stdout.trim() == "True" {
+        Ok(())
+    } else {
+        Err(NOT_INSTALLED.to_string())
+    }
+}
+
+fn gigabytes(bytes: &str) -> String {
+    bytes.parse::<f64>().map(|b| format!("{:.2} GB", b / 1_073_741_824.0)).unwrap_or_default()
+}
+
+/// Quotas as rows of path, limit, hard/soft, used, percent used and template.
+pub fn quotas() -> Result<Vec<Vec<String>>, String> {
+    require_fsrm()?;
+    let output = exec::powershell(
+        "Get-FsrmQuota | ForEach-Object { \
+            \"$($_.Path)`t$($_.Size)`t$($_.SoftLimit)`t$($_.Usage)`t$($_.Disabled)`t$($_.Template)\" }",
+    )
+    .map_err(|e| format!("Failed to list quotas: {}", e))?;
+    if !output.success {
+        return Err(format!("Failed to list quotas:\n{}", output.stderr.trim()));
+    }
+    Ok(exec::tab_rows(&output.stdout)
+        .into_iter()
+        .filter(|r| r.len() == 6)
+        .map(|r| {
+            let size: f64 = r[1].parse().unwrap_or(0.0);
+            let usage: f64 = r[3].parse().unwrap_or(0.0);
+            let kind = match (r[2].as_str(), r[4].as_str()) {
+                (_, "True") => "Disabled",
+                ("True", _) => "Soft",
+                _ => "Hard",
+            };
+            let percent = if size > 0.0 { format!("{:.0}%", usage * 100.0 / size) } else { String::new() };
+            vec![r[0].clone(), gigabytes(&r[1]), kind.to_string(), gigabytes(&r[3]), percent, r[5].clone()]
+        })
+        .collect())
+}
+
+/// File screens as rows of path, active/passive, blocked file groups and template.
+pub fn file_screens() -> Result<Vec<Vec<String>>, String> {
+    require_fsrm()?;
+    let output = exec::powershell(
+        "Get-FsrmFileScreen | ForEach-Object { \
+            \"$($_.Path)`t$(if ($_.Active) { 'Active' } else { 'Passive' })`t$($_.IncludeGroup -join ', ')`t$($_.Template)\" }",
+    )
+    .map_err(|e| format!("Failed to list file screens: {}", e))?;
+    if !output.success {
+        return Err(format!("Failed to list file screens:\n{}", output.stderr.trim()));
+    }
+    Ok(exec::tab_rows(&output.stdout).into_iter().filter(|r| r.len() == 4).collect())
+}
+
+/// Parses a size such as `500MB`, `10GB` or `1.5TB` into bytes.
+fn parse_size(value: &str) -> Option<u64> {
+    let value = value.trim().to_uppercase();
+    let split = value.find(|c: char| c.is_ascii_alphabetic())?;
+    let (number, unit) = value.split_at(split);
+    let factor: f64 = match unit.trim() {
+        "MB" => 1024.0 * 1024.0,
+        "GB" => 1024.0 * 1024.0 * 1024.0,
+        "TB" => 1024.0 * 1024.0 * 1024.0 * 1024.0,
+        _ => return None,
+    };
+    number.trim().parse::<f64>().ok().filter(|n| *n > 0.0).map(|n| (n * factor) as u64)
+}
+
+/// Splits `path; ...` input and checks that the folder exists.
+fn split_input(input: &str) -> Result<(String, Vec<String>), String> {
+    let mut parts = input.split(';').map(|p| p.trim().to_string());
+    let path = parts.next().unwrap_or_default().trim_matches('"').to_string();
+    if path.is_empty() {
+        return Err("Enter a folder path first.".to_string());
+    }
+    if !Path::new(&path).is_dir() {
+        return Err(format!("Folder not found: {}", path));
+    }
+    Ok((path, parts.filter(|p| !p.is_empty()).collect()))
+}
+
+/// Creates a quota from `path; size [soft]`, e.g. `D:\Shares\Finance; 10GB`.
+pub fn add_quota(input: &str) -> Result<String, String> {
+    let (path, rest) = split_input(input)?;
+    let spec = rest.first().ok_or("Enter the limit after the path, e.g. D:\\Shares\\Finance; 10GB")?;
+    let mut words = spec.split_whitespace();
+    let size_text = words.next().unwrap_or_default();
+    let size = parse_size(size_text).ok_or_else(|| format!("Invalid size '{}': use e.g. 500MB, 10GB or 1TB", size_text))?;
+    let soft = match words.next().map(str::to_lowercase).as_deref() {
+        None | Some("hard") => false,
+        Some("soft") => true,
+        Some(other) => return Err(format!("Unknown option '{}': use hard or soft", other)),
+    };
+
+    let script = format!(
+        "New-FsrmQuota -Path {} -Size {}{} -ErrorAction Stop | Out-Null",
+        exec::quote(&path),
+        size,
+        if soft { " -SoftLimit" } else { "" }
+    );
+    match exec::change(&script) {
+        Ok(output) if output.success => Ok(format!(
+            "Added a {} quota of {} on {}",
+            if soft { "soft" } else { "hard" },
+            size_text,
+            path
+        )),
+        Ok(output) => Err(format!("Failed to add the quota: {}", output.stderr.trim())),
+        Err(e) => Err(format!("Failed to add the quota: {}", e)),
+    }
+}
+
+/// Creates a file screen from `path; group, group [; passive]`,
+/// e.g. `D:\Shares\Public; Executable Files, Audio and Video Files`.
+pub fn add_file_screen(input: &str) -> Result<String, String> {
+    let (path, rest) = split_input(input)?;
+    let groups: Vec<String> = rest
+        .first()
+        .map(|g| g.split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect())
+        .unwrap_or_default();
+    if groups.is_empty() {
+        return Err("Enter the file groups to block after the path, e.g. D:\\Shares\\Public; Executable Files".to_string());
+    }
+    let passive = match rest.get(1).map(|m| m.to_lowercase()) {
+        None => false,
+        Some(mode) if mode == "active" => false,
+        Some(mode) if mode == "passive" => true,
+        Some(mode) => return Err(format!("Unknown mode '{}': use active or passive", mode)),
+    };
+
+    let quoted: Vec<String> = groups.iter().map(|g| exec::quote(g)).collect();
+    let script = format!(
+        "$missing = @({}) | Where-Object {{ -not (Get-FsrmFileGroup -Name $_ -ErrorAction SilentlyContinue) }}\n\
+        if ($missing) {{ throw \"Unknown file group(s): $($missing -join ', '). Available: $((Get-FsrmFileGroup).Name -join ', ')\" }}\n\
+        New-FsrmFileScreen -Path {} -IncludeGroup @({}){} -ErrorAction Stop | Out-Null",
+        quoted.join(", "),
+        exec::quote(&path),
+        quoted.join(", "),
+        if passive { " -Active:$false" } else { "" }
+    );
+    match exec::change(&script) {
+        Ok(output) if output.success => Ok(format!(
+            "Added a {} file screen on {} blocking {}",
+            if passive { "passive" } else { "active" },
+            path,
+            groups.join(", ")
+        )),
+        Ok(output) => Err(format!("Failed to add the file screen: {}", output.stderr.trim())),
+        Err(e) => Err(format!("Failed to add the file screen: {}", e)),
+    }
+}
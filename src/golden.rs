@@ -0,0 +1,233 @@
+//! Golden baselines: per role type (e.g. FileServer, WebServer), one backup marked as the
+//! reference build. Any server, this one or another reached over PowerShell remoting (SSH for
+//! Linux package lists), can be compared with a baseline to list the features or packages it
+//! lacks and those it has in addition. The designations are kept in `golden.txt` in the backup
+//! directory.
+
+use std::path::Path;
+
+use crate::{backup_dir, dates, delta, exec, platform};
+
+const GOLDEN_FILE: &str = "golden.txt";
+
+/// A feature or package: its name, and its display name or version.
+type Item = (String, String);
+
+fn read_baselines(dir: &Path) -> Vec<(String, String)> {
+    std::fs::read_to_string(dir.join(GOLDEN_FILE))
+        .unwrap_or_default()
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let (role, backup) = line.split_once('\t')?;
+            (!role.is_empty() && !backup.is_empty()).then(|| (role.to_string(), backup.to_string()))
+        })
+        .collect()
+}
+
+fn write_baselines(dir: &Path, baselines: &[(String, String)]) -> Result<(), String> {
+    let mut text = "# role type\tbackup\r\n".to_string();
+    for (role, backup) in baselines {
+        text.push_str(&format!("{}\t{}\r\n", role, backup));
+    }
+    let path = dir.join(GOLDEN_FILE);
+    std::fs::write(&path, text).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// The role types `backup` is the golden baseline for, e.g. `FileServer, Branch`.
+pub fn roles_of(backup: &str) -> String {
+    roles_in(&read_baselines(&backup_dir()), backup)
+}
+
+fn roles_in(baselines: &[(String, String)], backup: &str) -> String {
+    baselines.iter().filter(|(_, b)| b.eq_ignore_ascii_case(backup)).map(|(r, _)| r.as_str()).collect::<Vec<_>>().join(", ")
+}
+
+/// Makes `backup` the golden baseline of role type `role`, replacing the previous one. An empty
+/// `role` removes every designation of `backup`.
+pub fn designate(backup: &str, role: &str) -> Result<String, String> {
+    designate_in(&backup_dir(), backup, role)
+}
+
+fn designate_in(dir: &Path, backup: &str, role: &str) -> Result<String, String> {
+    let role = role.trim();
+    let mut baselines = read_baselines(dir);
+    if role.is_empty() {
+        baselines.retain(|(_, b)| !b.eq_ignore_ascii_case(backup));
+        write_baselines(dir, &baselines)?;
+        return Ok(format!("{} is no longer a golden baseline.", backup));
+    }
+    if !role.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err("Use letters, digits, - and _ for the role type, e.g. FileServer.".to_string());
+    }
+    if !dir.join(backup).exists() {
+        return Err(format!("{} is no longer in {}.", backup, dir.display()));
+    }
+    let previous = baselines.iter().position(|(r, _)| r.eq_ignore_ascii_case(role)).map(|i| baselines.remove(i));
+    baselines.push((role.to_string(), backup.to_string()));
+    baselines.sort_by_key(|(r, _)| r.to_lowercase());
+    write_baselines(dir, &baselines)?;
+    Ok(match previous {
+        Some((_, old)) if !old.eq_ignore_ascii_case(backup) => {
+            format!("{} is now the golden {} baseline (was {}).", backup, role, old)
+        }
+        _ => format!("{} is now the golden {} baseline.", backup, role),
+    })
+}
+
+fn contents(backup: &str) -> &'static str {
+    if backup.starts_with("Packages_") {
+        "Packages"
+    } else {
+        "Roles & Features"
+    }
+}
+
+/// Golden baselines: role type, backup, what it lists and when it was taken.
+pub fn rows() -> Result<Vec<Vec<String>>, String> {
+    let dir = backup_dir();
+    Ok(read_baselines(&dir)
+        .into_iter()
+        .map(|(role, backup)| {
+            let taken = match dates::from_file_name(&backup) {
+                Some(_) if !dir.join(&backup).exists() => "(missing)".to_string(),
+                Some(secs) => dates::local(secs),
+                None => String::new(),
+            };
+            vec![role, backup.clone(), contents(&backup).to_string(), taken]
+        })
+        .collect())
+}
+
+fn items(rows: Vec<Vec<String>>) -> Vec<Item> {
+    rows.into_iter().map(|row| (row[0].clone(), row.get(1).cloned().unwrap_or_default())).collect()
+}
+
+/// Items of `golden` missing from `host`, and items of `host` missing from `golden`.
+fn difference(golden: &[Item], host: &[Item]) -> (Vec<Item>, Vec<Item>) {
+    let lacks = |items: &[Item], name: &str| !items.iter().any(|(n, _)| n.eq_ignore_ascii_case(name));
+    let missing = golden.iter().filter(|(name, _)| lacks(host, name)).cloned().collect();
+    let extra = host.iter().filter(|(name, _)| lacks(golden, name)).cloned().collect();
+    (missing, extra)
+}
+
+/// Installed features of the backup and of `host` (this server when `None`).
+fn feature_sets(backup: &Path, host: Option<&str>) -> Result<(Vec<Item>, Vec<Item>), String> {
+    let list = "Get-WindowsFeature | Where-Object Installed | ForEach-Object { \"host`t$($_.Name)`t$($_.DisplayName)\" }";
+    let installed = match host {
+        Some(host) => format!("Invoke-Command -ComputerName {} -ErrorAction Stop -ScriptBlock {{ {} }}", exec::quote(host), list),
+        None => list.to_string(),
+    };
+    let script = format!(
+        "Import-Clixml -Path {} | Where-Object Installed | ForEach-Object {{ \"golden`t$($_.Name)`t$($_.DisplayName)\" }}\n{}",
+        exec::quote(&delta::full(backup)?.to_string_lossy()),
+        installed
+    );
+    let output = exec::powershell(&script).map_err(|e| format!("Failed to read the installed features: {}", e))?;
+    if !output.success {
+        return Err(format!("Failed to read the installed features:\n{}", output.stderr.trim()));
+    }
+    let rows = exec::tab_rows(&output.stdout);
+    let set = |side: &str| items(rows.iter().filter(|r| r.len() >= 2 && r[0] == side).map(|r| r[1..].to_vec()).collect());
+    Ok((set("golden"), set("host")))
+}
+
+/// Packages of the backup and of `host` (this server when `None`).
+fn package_sets(backup: &Path, host: Option<&str>) -> Result<(Vec<Item>, Vec<Item>), String> {
+    let golden = std::fs::read_to_string(backup).map_err(|e| format!("Failed to read {}: {}", backup.display(), e))?;
+    Ok((items(exec::tab_rows(&golden)), items(exec::tab_rows(&platform::installed_packages(host)?))))
+}
+
+fn section(title: String, items: &[Item]) -> String {
+    let width = items.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+    let lines: String = items.iter().map(|(name, detail)| format!("  {:width$}  {}\n", name, detail, width = width)).collect();
+    format!("{} ({}):\n{}", title, items.len(), if lines.is_empty() { "  (none)\n".to_string() } else { lines })
+}
+
+/// Compares `host` (this server when empty) with the golden baseline of `role` and reports the
+/// features or packages it is missing and those it has in addition.
+pub fn compare(role: &str, host: &str) -> Result<String, String> {
+    let dir = backup_dir();
+    let backup = read_baselines(&dir)
+        .into_iter()
+        .find(|(r, _)| r.eq_ignore_ascii_case(role))
+        .map(|(_, b)| b)
+        .ok_or_else(|| format!("There is no golden {} baseline.", role))?;
+    let path = dir.join(&backup);
+    if !path.exists() {
+        return Err(format!("The golden {} baseline {} is no longer in {}.", role, backup, dir.display()));
+    }
+    let host = host.trim();
+    if host.starts_with('-') || host.contains(char::is_whitespace) {
+        return Err(format!("Enter one server name, e.g. FS02 (not {}).", host));
+    }
+    let remote = (!host.is_empty()).then_some(host);
+    let (golden, current) = match contents(&backup) {
+        "Packages" => package_sets(&path, remote)?,
+        _ => feature_sets(&path, remote)?,
+    };
+    let (missing, extra) = difference(&golden, &current);
+    let target = remote.unwrap_or("this server");
+    let kind = contents(&backup).to_lowercase();
+    let mut report = format!(
+        "{} compared with the golden {} baseline\n  {}{}\n\n",
+        target,
+        role,
+        path.display(),
+        dates::from_file_name(&backup).map(|secs| format!(" (taken {})", dates::local(secs))).unwrap_or_default()
+    );
+    if missing.is_empty() && extra.is_empty() {
+        report.push_str(&format!("{} has the same {} as the baseline ({}).\n", target, kind, golden.len()));
+    } else {
+        report.push_str(&section(format!("Missing on {}", target), &missing));
+        report.push('\n');
+        report.push_str(&section(format!("Extra on {}", target), &extra));
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, FakeRunner};
+
+    #[test]
+    fn keeps_one_golden_backup_per_role_type() {
+        let dir = testing::temp_dir("golden");
+        for name in ["ServerRoles_1709288100.xml", "ServerRoles_1709374500.xml"] {
+            std::fs::write(dir.join(name), "<Objs />").unwrap_or_default();
+        }
+
+        assert!(designate_in(&dir, "ServerRoles_1709288100.xml", "FileServer").is_ok());
+        assert!(designate_in(&dir, "ServerRoles_1709288100.xml", "WebServer").is_ok());
+        let moved = designate_in(&dir, "ServerRoles_1709374500.xml", "fileserver");
+        assert!(moved.is_ok_and(|m| m.contains("(was ServerRoles_1709288100.xml)")));
+        assert_eq!(roles_in(&read_baselines(&dir), "ServerRoles_1709288100.xml"), "WebServer");
+
+        assert!(designate_in(&dir, "ServerRoles_1709288100.xml", "").is_ok());
+        assert_eq!(read_baselines(&dir), [("fileserver".to_string(), "ServerRoles_1709374500.xml".to_string())]);
+        assert!(designate_in(&dir, "ServerRoles_1709374500.xml", "File Server").is_err());
+    }
+
+    #[test]
+    fn reports_missing_and_extra_features_on_a_remote_server() {
+        let path = testing::temp_dir("golden-compare").join("ServerRoles_1709288100.xml");
+        std::fs::write(&path, "<Objs />").unwrap_or_default();
+        let fake = FakeRunner::new()
+            .on(
+                "Import-Clixml",
+                "golden\tFileAndStorage-Services\tFile and Storage Services\n\
+                golden\tFS-DFS-Namespace\tDFS Namespaces\n\
+                host\tFileAndStorage-Services\tFile and Storage Services\n\
+                host\tTelnet-Client\tTelnet Client\n",
+            )
+            .install();
+
+        let (golden, host) = feature_sets(&path, Some("FS02")).unwrap_or_default();
+        let (missing, extra) = difference(&golden, &host);
+
+        assert!(fake.ran("Invoke-Command -ComputerName 'FS02'"));
+        assert_eq!(missing, [("FS-DFS-Namespace".to_string(), "DFS Namespaces".to_string())]);
+        assert_eq!(extra, [("Telnet-Client".to_string(), "Telnet Client".to_string())]);
+    }
+}
@@ -0,0 +1,141 @@
+use std::io::{BufRead, BufReader, Read};
+use std::process::{Command, Stdio};
+
+use crate::exec;
+
+/// Percentage in a DISM or SFC progress line, e.g. `[====  20.0%  ]` or `Verification 45% complete.`
+fn percent(line: &str) -> Option<f32> {
+    let end = line.find('%')?;
+    let start = line[..end]
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| c.is_ascii_digit() || *c == '.')
+        .last()
+        .map(|(i, _)| i)?;
+    line[start..end].parse().ok().filter(|p| *p <= 100.0)
+}
+
+/// Runs a servicing tool, streaming its lines to `progress` and scaling its percentage into
+/// `from..to` of the whole run. Lines without letters or digits (spinners, bars) are dropped.
+/// Returns the exit status and the output lines.
+pub fn stream(
+    program: &str,
+    args: &[&str],
+    (from, to): (u8, u8),
+    progress: &mut impl FnMut(Option<&str>, Option<u8>),
+) -> Result<(bool, Vec<String>), String> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to start {}: {}", program, e))?;
+
+    let mut lines = Vec::new();
+    if let Some(stdout) = child.stdout.take() {
+        let mut reader = BufReader::new(stdout);
+        let mut buf = Vec::new();
+        while matches!(reader.read_until(b'\n', &mut buf), Ok(n) if n > 0) {
+            // sfc writes UTF-16; its text is ASCII, so dropping the zero bytes is enough
+            buf.retain(|b| *b != 0);
+            // Progress bars are redrawn with bare carriage returns
+            for line in String::from_utf8_lossy(&buf).split('\r') {
+                let line = line.trim();
+                if !line.chars().any(char::is_alphanumeric) {
+                    continue;
+                }
+                match percent(line) {
+                    Some(p) => progress(None, Some(from + ((to - from) as f32 * p / 100.0) as u8)),
+                    None => {
+                        progress(Some(line), None);
+                        lines.push(line.to_string());
+                    }
+                }
+            }
+            buf.clear();
+        }
+    }
+    let mut stderr = String::new();
+    if let Some(mut err) = child.stderr.take() {
+        let _ = err.read_to_string(&mut stderr);
+    }
+    lines.extend(stderr.lines().filter(|l| !l.trim().is_empty()).map(String::from));
+    let status = child.wait().map_err(|e| format!("Failed to wait for {}: {}", program, e))?;
+    Ok((status.success(), lines))
+}
+
+fn said(lines: &[String], text: &str) -> bool {
+    lines.iter().any(|l| l.contains(text))
+}
+
+/// Runs DISM /ScanHealth, /RestoreHealth (only when corruption was found) and sfc /scannow,
+/// reporting each tool's outcome in plain words and an overall verdict.
+pub fn repair(mut progress: impl FnMut(Option<&str>, Option<u8>)) -> Result<String, String> {
+    const SCAN: [&str; 3] = ["/Online", "/Cleanup-Image", "/ScanHealth"];
+    const RESTORE: [&str; 3] = ["/Online", "/Cleanup-Image", "/RestoreHealth"];
+    if exec::export_status().is_some() {
+        exec::change_program("dism", &RESTORE).map_err(|e| e.to_string())?;
+        exec::change_program("sfc", &["/scannow"]).map_err(|e| e.to_string())?;
+        return Ok("DISM /RestoreHealth and sfc /scannow recorded.".to_string());
+    }
+
+    let mut verdicts = Vec::new();
+    progress(Some("Step 1/3: DISM /ScanHealth (checks the component store)..."), Some(0));
+    let (ok, lines) = stream("dism", &SCAN, (0, 30), &mut progress)?;
+    let store = if said(&lines, "No component store corruption detected") {
+        "healthy"
+    } else if said(&lines, "component store is repairable") {
+        "repairable"
+    } else if said(&lines, "cannot be repaired") {
+        "unrepairable"
+    } else if ok {
+        "healthy"
+    } else {
+        return Err(format!("DISM /ScanHealth failed:\n{}", lines.join("\n")));
+    };
+    verdicts.push(format!("Component store: {}", store));
+
+    let mut repaired = false;
+    if store == "healthy" {
+        progress(Some("Step 2/3: DISM /RestoreHealth skipped, the component store is healthy."), Some(70));
+    } else {
+        progress(Some("Step 2/3: DISM /RestoreHealth (downloads replacements from Windows Update)..."), Some(30));
+        let (ok, lines) = stream("dism", &RESTORE, (30, 70), &mut progress)?;
+        if ok && said(&lines, "completed successfully") {
+            repaired = true;
+            verdicts.push("RestoreHealth: component store repaired".to_string());
+        } else if said(&lines, "0x800f081f") || said(&lines, "source files could not be found") {
+            return Err(format!(
+                "{}\nRestoreHealth: the repair source could not be found (0x800f081f).\n\
+                Mount matching installation media and run:\n  \
+                DISM /Online /Cleanup-Image /RestoreHealth /Source:WIM:D:\\sources\\install.wim:1 /LimitAccess",
+                verdicts.join("\n")
+            ));
+        } else {
+            return Err(format!("{}\nRestoreHealth failed:\n{}", verdicts.join("\n"), lines.join("\n")));
+        }
+    }
+
+    progress(Some("Step 3/3: sfc /scannow (checks protected system files)..."), Some(70));
+    let (_, lines) = stream("sfc", &["/scannow"], (70, 100), &mut progress)?;
+    let files = if said(&lines, "did not find any integrity violations") {
+        "no integrity violations"
+    } else if said(&lines, "successfully repaired") {
+        repaired = true;
+        "corrupt files found and repaired"
+    } else if said(&lines, "unable to fix some of them") {
+        return Err(format!(
+            "{}\nSystem files: corrupt files found that SFC could not fix.\n\
+            Details are in %WinDir%\\Logs\\CBS\\CBS.log (lines tagged [SR]).",
+            verdicts.join("\n")
+        ));
+    } else {
+        return Err(format!("{}\nsfc could not complete the scan:\n{}", verdicts.join("\n"), lines.join("\n")));
+    };
+    verdicts.push(format!("System files: {}", files));
+    progress(None, Some(100));
+
+    let verdict = if repaired { "REPAIRED (restart to complete the repair)" } else { "HEALTHY" };
+    Ok(format!("{}\nVerdict: {}", verdicts.join("\n"), verdict))
+}
@@ -0,0 +1,44 @@
+use std::{io::Write, path::PathBuf};
+
+use crate::{config_dir, dates};
+
+pub fn history_path() -> PathBuf {
+    config_dir().join("history.log")
+}
+
+/// Current UTC time as `YYYY-MM-DD HH:MM:SS`.
+pub fn timestamp() -> String {
+    dates::utc(dates::now() as i64)
+}
+
+/// Appends an entry to the history log. The first line of `text` is the summary;
+/// further lines are indented beneath it. Failures to write are ignored so that
+/// logging never blocks the action itself.
+pub fn record(text: &str) {
+    let path = history_path();
+    if let Some(dir) = path.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    let mut lines = text.trim_end().lines();
+    let mut entry = format!("[{} UTC] {}\r\n", timestamp(), lines.next().unwrap_or_default());
+    for line in lines {
+        entry.push_str(&format!("    {}\r\n", line));
+    }
+    entry.push_str("\r\n");
+    if let Ok(mut file) = std::fs::OpenOptions::new().create(true).append(true).open(&path) {
+        let _ = file.write_all(entry.as_bytes());
+    }
+}
+
+/// The whole history log in local time, for the history screen.
+pub fn read() -> Result<String, String> {
+    let path = history_path();
+    match std::fs::read_to_string(&path) {
+        Ok(content) if !content.trim().is_empty() => Ok(dates::localize_history(&content)),
+        Ok(_) => Ok(format!("No history yet. Entries are written to {}.", path.display())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            Ok(format!("No history yet. Entries are written to {}.", path.display()))
+        }
+        Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+    }
+}
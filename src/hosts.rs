@@ -0,0 +1,126 @@
+use std::{
+    net::IpAddr,
+    path::PathBuf,
+};
+
+use crate::{backup_dir, exec};
+
+fn hosts_path() -> PathBuf {
+    let system_root = std::env::var("SystemRoot").unwrap_or_else(|_| "C:\\Windows".to_string());
+    PathBuf::from(system_root)
+        .join("System32")
+        .join("drivers")
+        .join("etc")
+        .join("hosts")
+}
+
+fn read_lines() -> Result<Vec<String>, String> {
+    let path = hosts_path();
+    let content = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    Ok(content.lines().map(|l| l.to_string()).collect())
+}
+
+/// Splits `address host [host...] [# comment]` into address, hostnames and comment.
+fn parse_entry(text: &str) -> Option<(String, String, String)> {
+    let (body, comment) = match text.split_once('#') {
+        Some((body, comment)) => (body, comment.trim()),
+        None => (text, ""),
+    };
+    let mut tokens = body.split_whitespace();
+    let address = tokens.next()?;
+    address.parse::<IpAddr>().ok()?;
+    let hostnames: Vec<&str> = tokens.collect();
+    if hostnames.is_empty() {
+        return None;
+    }
+    Some((address.to_string(), hostnames.join(" "), comment.to_string()))
+}
+
+/// Classifies a hosts file line as an entry, a commented-out (disabled) entry,
+/// a plain comment or an invalid line. Blank lines are skipped.
+fn parse_line(number: usize, line: &str) -> Option<Vec<String>> {
+    let trimmed = line.trim();
+    if trimmed.is_empty() {
+        return None;
+    }
+
+    let (kind, address, hostnames, comment) = if let Some(rest) = trimmed.strip_prefix('#') {
+        match parse_entry(rest.trim()) {
+            Some((a, h, c)) => ("disabled", a, h, c),
+            None => ("comment", String::new(), String::new(), rest.trim().to_string()),
+        }
+    } else {
+        match parse_entry(trimmed) {
+            Some((a, h, c)) => ("entry", a, h, c),
+            None => ("invalid", String::new(), String::new(), trimmed.to_string()),
+        }
+    };
+
+    Some(vec![number.to_string(), kind.to_string(), address, hostnames, comment])
+}
+
+/// Lists every non-blank line of the hosts file.
+pub fn hosts_entries() -> Result<Vec<Vec<String>>, String> {
+    Ok(read_lines()?
+        .iter()
+        .enumerate()
+        .filter_map(|(i, line)| parse_line(i + 1, line))
+        .collect())
+}
+
+/// Copies the current hosts file to the backup directory, then writes `lines`.
+fn save(lines: &[String]) -> Result<PathBuf, String> {
+    let path = hosts_path();
+    let dir = backup_dir();
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create backup directory: {}", e))?;
+
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let backup = dir.join(format!("hosts_{}.bak", timestamp));
+    exec::copy_file(&path, &backup).map_err(|e| format!("Failed to back up hosts file: {}", e))?;
+
+    let mut content = lines.join("\r\n");
+    content.push_str("\r\n");
+    exec::write_file(&path, &content).map_err(|e| {
+        format!("Failed to write {} (are you running as Administrator?): {}", path.display(), e)
+    })?;
+    Ok(backup)
+}
+
+/// Appends an entry from `address hostname [hostname...] [# comment]`.
+pub fn add_entry(spec: &str) -> Result<String, String> {
+    let (address, hostnames, comment) = parse_entry(spec.trim())
+        .ok_or_else(|| "Expected: address hostname [hostname...] [# comment]".to_string())?;
+
+    let mut line = format!("{}\t{}", address, hostnames);
+    if !comment.is_empty() {
+        line.push_str(&format!("\t# {}", comment));
+    }
+
+    let mut lines = read_lines()?;
+    lines.push(line);
+    let backup = save(&lines)?;
+    Ok(format!("Added {} -> {} (previous version saved to {})", hostnames, address, backup.display()))
+}
+
+/// Removes the line shown as `row`, refusing if the file changed since it was listed.
+pub fn remove_line(row: &[String]) -> Result<String, String> {
+    let number: usize = row
+        .first()
+        .and_then(|n| n.parse().ok())
+        .filter(|n| *n > 0)
+        .ok_or_else(|| "Invalid line number".to_string())?;
+
+    let mut lines = read_lines()?;
+    let current = lines.get(number - 1).and_then(|line| parse_line(number, line));
+    if current.as_deref() != Some(row) {
+        return Err("The hosts file changed since it was listed. Press r to reload.".to_string());
+    }
+
+    lines.remove(number - 1);
+    let backup = save(&lines)?;
+    Ok(format!("Removed line {} (previous version saved to {})", number, backup.display()))
+}
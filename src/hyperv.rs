@@ -0,0 +1,212 @@
+use std::path::Path;
+
+use crate::{backup_dir, exec, history, plan::Plan};
+
+/// One tab-separated line per VM: name, state, CPU usage, vCPUs, assigned memory, uptime, checkpoints.
+const VMS_SCRIPT: &str = r#"
+if (-not (Get-Command Get-VM -ErrorAction SilentlyContinue)) { 'NotInstalled'; return }
+Get-VM | Sort-Object Name | ForEach-Object {
+    $checkpoints = @(Get-VMSnapshot -VM $_ -ErrorAction SilentlyContinue).Count
+    $uptime = if ($_.Uptime.TotalSeconds -gt 0) { '{0}d {1}h {2}m' -f $_.Uptime.Days, $_.Uptime.Hours, $_.Uptime.Minutes } else { '' }
+    "$($_.Name)`t$($_.State)`t$($_.CPUUsage)%`t$($_.ProcessorCount)`t$([int]($_.MemoryAssigned / 1MB))`t$uptime`t$checkpoints"
+}
+"#;
+
+/// Virtual machines on this host with state, CPU, memory, uptime and checkpoint count.
+pub fn vms() -> Result<Vec<Vec<String>>, String> {
+    let output = exec::powershell(VMS_SCRIPT).map_err(|e| format!("Failed to list virtual machines: {}", e))?;
+    if output.stdout.trim() == "NotInstalled" {
+        return Err("Hyper-V is not installed on this server (no Hyper-V PowerShell module).".to_string());
+    }
+    if !output.success {
+        return Err(format!("Failed to list virtual machines:\n{}", output.stderr.trim()));
+    }
+    Ok(exec::tab_rows(&output.stdout))
+}
+
+fn run(script: String, done: String, failed: &str) -> Result<String, String> {
+    match exec::change(&script) {
+        Ok(output) if output.success => Ok(done),
+        Ok(output) => Err(format!("{}: {}", failed, output.stderr.trim())),
+        Err(e) => Err(format!("{}: {}", failed, e)),
+    }
+}
+
+pub fn start_vm(name: &str) -> Result<String, String> {
+    run(
+        format!("Start-VM -Name {} -ErrorAction Stop", exec::quote(name)),
+        format!("Started {}", name),
+        &format!("Failed to start {}", name),
+    )
+}
+
+/// Shuts the guest down through integration services, turning it off if that is unavailable.
+pub fn stop_vm(name: &str) -> Result<String, String> {
+    run(
+        format!("Stop-VM -Name {} -Force -ErrorAction Stop", exec::quote(name)),
+        format!("Stopped {}", name),
+        &format!("Failed to stop {}", name),
+    )
+}
+
+pub fn checkpoint_vm(name: &str) -> Result<String, String> {
+    let checkpoint = format!("Server Helper {}", history::timestamp());
+    run(
+        format!(
+            "Checkpoint-VM -Name {} -SnapshotName {} -ErrorAction Stop",
+            exec::quote(name),
+            exec::quote(&checkpoint)
+        ),
+        format!("Created checkpoint '{}' of {}", checkpoint, name),
+        &format!("Failed to checkpoint {}", name),
+    )
+}
+
+/// The last Hyper-V management and worker events that mention the VM, newest first.
+pub fn vm_events(name: &str) -> Result<String, String> {
+    let script = format!(
+        "$name = {}\n\
+        $id = (Get-VM -Name $name -ErrorAction Stop).Id.ToString()\n\
+        Get-WinEvent -LogName 'Microsoft-Windows-Hyper-V-VMMS-Admin', 'Microsoft-Windows-Hyper-V-Worker-Admin' \
+            -MaxEvents 500 -ErrorAction SilentlyContinue |\n\
+            Where-Object {{ $_.Message -match [regex]::Escape($name) -or $_.Message -match $id }} |\n\
+            Sort-Object TimeCreated -Descending | Select-Object -First 30 | ForEach-Object {{\n\
+                \"$($_.TimeCreated.ToString('yyyy-MM-dd HH:mm:ss'))  $($_.LevelDisplayName.PadRight(11)) $(($_.Message -split \"`r?`n\")[0])\"\n\
+            }}",
+        exec::quote(name)
+    );
+    let output = exec::powershell(&script).map_err(|e| format!("Failed to read Hyper-V events: {}", e))?;
+    if !output.success {
+        return Err(format!("Failed to read Hyper-V events:\n{}", output.stderr.trim()));
+    }
+    Ok(match output.stdout.trim() {
+        "" => format!("No recent Hyper-V events for {}.", name),
+        events => events.to_string(),
+    })
+}
+
+/// Default folder for new VM files on this host.
+pub fn default_path() -> String {
+    exec::powershell("(Get-VMHost).VirtualMachinePath")
+        .map(|o| o.stdout.trim().to_string())
+        .unwrap_or_default()
+}
+
+/// Splits a comma separated list of VM names.
+pub fn split_names(input: &str) -> Vec<String> {
+    input.split(',').map(|n| n.trim().to_string()).filter(|n| !n.is_empty()).collect()
+}
+
+/// Exports the VMs into `VMs_<timestamp>` in the backup directory, with a `VMs_<timestamp>.vms`
+/// manifest listing the folder of each VM for the import flow.
+pub fn export_vms(names: &[String], mut progress: impl FnMut(Option<&str>, Option<u8>)) -> Result<String, String> {
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let folder = format!("VMs_{}", timestamp);
+    let target = backup_dir().join(&folder);
+    std::fs::create_dir_all(&target).map_err(|e| format!("Failed to create {}: {}", target.display(), e))?;
+
+    let mut manifest = Vec::new();
+    let mut failures = Vec::new();
+    for (i, name) in names.iter().enumerate() {
+        progress(Some(&format!("Exporting {} ({}/{})...", name, i + 1, names.len())), Some((i * 100 / names.len()) as u8));
+        // Export-VM creates a folder named after the VM under the target path
+        let script = format!(
+            "Export-VM -Name {} -Path {} -ErrorAction Stop",
+            exec::quote(name),
+            exec::quote(&target.to_string_lossy())
+        );
+        match exec::change(&script) {
+            Ok(output) if output.success => {
+                manifest.push(format!("{}\t{}\\{}", name, folder, name));
+                progress(Some(&format!("Exported {}", name)), None);
+            }
+            Ok(output) => failures.push(format!("{}: {}", name, output.stderr.trim())),
+            Err(e) => failures.push(format!("{}: {}", name, e)),
+        }
+    }
+    progress(None, Some(100));
+
+    let manifest_path = backup_dir().join(format!("{}.vms", folder));
+    if !manifest.is_empty() {
+        let contents = format!("# VM<TAB>export folder, relative to this file\r\n{}\r\n", manifest.join("\r\n"));
+        exec::write_file(&manifest_path, &contents)
+            .map_err(|e| format!("Failed to write {}: {}", manifest_path.display(), e))?;
+    }
+    let mut summary = vec![format!("Exported {} of {} VM(s) to {}", manifest.len(), names.len(), target.display())];
+    if !manifest.is_empty() {
+        summary.push(format!("Manifest: {}", manifest_path.display()));
+    }
+    if failures.is_empty() {
+        Ok(summary.join("\n"))
+    } else {
+        summary.push("Failed:".to_string());
+        summary.extend(failures);
+        Err(summary.join("\n"))
+    }
+}
+
+/// Plans importing the VMs in an export manifest as copies under `input`: a root folder for
+/// the VM files, optionally followed by a virtual switch for adapters whose switch is missing here.
+pub fn import_plan(manifest: &Path, input: &str) -> Result<Plan, String> {
+    let content = std::fs::read_to_string(manifest).map_err(|e| format!("Failed to read {}: {}", manifest.display(), e))?;
+    let dir = manifest.parent().map(Path::to_path_buf).unwrap_or_else(backup_dir);
+    let mut words = input.splitn(2, char::is_whitespace);
+    let root = words.next().unwrap_or_default().trim_end_matches('\\').to_string();
+    if root.is_empty() {
+        return Err("Enter the folder to import the VMs into, e.g. D:\\Hyper-V".to_string());
+    }
+    let switch = words.next().map(str::trim).filter(|s| !s.is_empty());
+    let existing: Vec<String> = vms()?.into_iter().map(|r| r[0].to_lowercase()).collect();
+
+    let mut plan = Plan::new(format!("Import Hyper-V VMs from {}", manifest.display()));
+    for (name, folder) in content
+        .lines()
+        .filter(|l| !l.trim().is_empty() && !l.starts_with('#'))
+        .filter_map(|l| l.split_once('\t'))
+    {
+        let (name, export) = (name.trim(), dir.join(folder.trim()));
+        if existing.contains(&name.to_lowercase()) {
+            plan.note(format!("{} already exists on this host; skipped", name));
+            continue;
+        }
+        if !export.is_dir() {
+            plan.note(format!("{} is missing; {} skipped", export.display(), name));
+            continue;
+        }
+        let target = format!("{}\\{}", root, name);
+        let network = match switch {
+            Some(switch) => format!("$i.Source | Connect-VMNetworkAdapter -SwitchName {}", exec::quote(switch)),
+            None => "$i.Source | Disconnect-VMNetworkAdapter".to_string(),
+        };
+        plan.step(
+            format!("Import {} into {}", name, target),
+            format!(
+                "$vmcx = Get-ChildItem -LiteralPath {} -Recurse -Filter *.vmcx | Select-Object -First 1\n\
+                if (-not $vmcx) {{ throw 'No .vmcx configuration found in the export' }}\n\
+                $report = Compare-VM -Path $vmcx.FullName -Copy -VirtualMachinePath {t} -SnapshotFilePath {t} \
+                    -SmartPagingFilePath {t} -VhdDestinationPath {vhd}\n\
+                foreach ($i in $report.Incompatibilities) {{\n\
+                    # 33012: the virtual switch the adapter was connected to does not exist here\n\
+                    if ($i.MessageId -eq 33012) {{ {network} }} else {{ throw \"$($i.MessageId): $($i.Message)\" }}\n\
+                }}\n\
+                Import-VM -CompatibilityReport $report -ErrorAction Stop | Out-Null",
+                exec::quote(&export.to_string_lossy()),
+                t = exec::quote(&target),
+                vhd = exec::quote(&format!("{}\\Virtual Hard Disks", target)),
+                network = network
+            ),
+        );
+    }
+    if plan.steps.is_empty() && plan.notes.is_empty() {
+        return Err(format!("{} lists no VMs.", manifest.display()));
+    }
+    plan.note(match switch {
+        Some(switch) => format!("Adapters whose virtual switch is missing here are connected to {}.", switch),
+        None => "Adapters whose virtual switch is missing here are left disconnected.".to_string(),
+    });
+    plan.note("The VMs keep their IDs and are imported turned off.");
+    Ok(plan)
+}
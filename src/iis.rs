@@ -0,0 +1,66 @@
+use crate::exec;
+
+/// One tab-separated line per site: name, state, app pool, pool state, bindings, path.
+const SITES_SCRIPT: &str = r#"
+if (-not (Get-WindowsFeature -Name Web-Server -ErrorAction SilentlyContinue).Installed) { 'NotInstalled'; return }
+Import-Module WebAdministration
+Get-ChildItem -Path IIS:\Sites | ForEach-Object {
+    $pool = $_.applicationPool
+    $poolState = (Get-WebAppPoolState -Name $pool -ErrorAction SilentlyContinue).Value
+    $bindings = @($_.bindings.Collection | ForEach-Object {
+        if ($_.certificateHash) { "$($_.protocol) $($_.bindingInformation) [$($_.certificateHash.Substring(0, 8))]" }
+        else { "$($_.protocol) $($_.bindingInformation)" }
+    }) -join ', '
+    "$($_.Name)`t$($_.State)`t$pool`t$poolState`t$bindings`t$($_.PhysicalPath)"
+}
+"#;
+
+/// Sites with their application pool and bindings; HTTPS bindings show the start of the certificate thumbprint.
+pub fn sites() -> Result<Vec<Vec<String>>, String> {
+    let output = exec::powershell(SITES_SCRIPT).map_err(|e| format!("Failed to list IIS sites: {}", e))?;
+    if output.stdout.trim() == "NotInstalled" {
+        return Err("IIS (the Web-Server role) is not installed on this server.".to_string());
+    }
+    if !output.success {
+        return Err(format!("Failed to list IIS sites:\n{}", output.stderr.trim()));
+    }
+    Ok(exec::tab_rows(&output.stdout))
+}
+
+fn run(script: String, done: String, failed: &str) -> Result<String, String> {
+    match exec::change(&format!("Import-Module WebAdministration\n{}", script)) {
+        Ok(output) if output.success => Ok(done),
+        Ok(output) => Err(format!("{}: {}", failed, output.stderr.trim())),
+        Err(e) => Err(format!("{}: {}", failed, e)),
+    }
+}
+
+pub fn start_site(site: &str) -> Result<String, String> {
+    run(
+        format!("Start-Website -Name {} -ErrorAction Stop", exec::quote(site)),
+        format!("Started {}", site),
+        &format!("Failed to start {}", site),
+    )
+}
+
+pub fn stop_site(site: &str) -> Result<String, String> {
+    run(
+        format!("Stop-Website -Name {} -ErrorAction Stop", exec::quote(site)),
+        format!("Stopped {}", site),
+        &format!("Failed to stop {}", site),
+    )
+}
+
+/// Recycles a running application pool, or starts a stopped one.
+pub fn recycle_pool(pool: &str, state: &str) -> Result<String, String> {
+    let (verb, done) = if state == "Started" {
+        ("Restart-WebAppPool", "Recycled")
+    } else {
+        ("Start-WebAppPool", "Started")
+    };
+    run(
+        format!("{} -Name {} -ErrorAction Stop", verb, exec::quote(pool)),
+        format!("{} application pool {}", done, pool),
+        &format!("Failed to recycle {}", pool),
+    )
+}
@@ -0,0 +1,284 @@
+use std::{
+    path::PathBuf,
+    sync::{mpsc, Mutex, OnceLock},
+    time::Instant,
+};
+
+use crate::{docker, exec, features, health, hyperv, robocopy, status::Level, wbadmin, winget_config};
+
+/// Work a job performs. Jobs are serialized because servicing allows only
+/// one install/uninstall at a time, and backups would compete for the disk.
+#[derive(Clone, PartialEq)]
+pub enum JobKind {
+    Feature { name: String, install: bool },
+    /// `wbadmin start systemstatebackup` to a volume or share.
+    SystemStateBackup { target: String },
+    /// Containers feature, Docker engine and service.
+    DockerHost,
+    /// DISM component store check/repair, then SFC.
+    HealthRepair,
+    /// Robocopy data copy or mirror.
+    Migration(robocopy::Migration),
+    /// `Export-VM` of the named VMs into the backup directory.
+    VmExport(Vec<String>),
+    /// `winget configure` of a WinGet Configuration file.
+    WingetConfigure(PathBuf),
+}
+
+impl JobKind {
+    fn describe(&self) -> String {
+        match self {
+            JobKind::Feature { name, install: true } => format!("Install {}", name),
+            JobKind::Feature { name, install: false } => format!("Remove {}", name),
+            JobKind::SystemStateBackup { target } => format!("System state backup to {}", target),
+            JobKind::DockerHost => "Docker host setup".to_string(),
+            JobKind::HealthRepair => "System health repair (DISM + SFC)".to_string(),
+            JobKind::Migration(migration) => migration.describe(),
+            JobKind::VmExport(names) => format!("Export VM(s) {}", names.join(", ")),
+            JobKind::WingetConfigure(path) => format!("winget configure {}", path.display()),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum JobState {
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+    /// Written to the export script instead of run.
+    Exported,
+}
+
+impl JobState {
+    fn label(&self) -> &'static str {
+        match self {
+            JobState::Queued => "queued",
+            JobState::Running => "running",
+            JobState::Succeeded => "succeeded",
+            JobState::Failed => "FAILED",
+            JobState::Exported => "exported",
+        }
+    }
+}
+
+struct Job {
+    id: usize,
+    kind: JobKind,
+    state: JobState,
+    restart_needed: bool,
+    /// Last percentage reported by a job that tracks progress.
+    progress: Option<u8>,
+    log: Vec<String>,
+    queued_at: Instant,
+    started_at: Option<Instant>,
+    finished_at: Option<Instant>,
+}
+
+static JOBS: Mutex<Vec<Job>> = Mutex::new(Vec::new());
+static QUEUE: OnceLock<mpsc::Sender<usize>> = OnceLock::new();
+
+fn with_job(id: usize, f: impl FnOnce(&mut Job)) {
+    if let Ok(mut jobs) = JOBS.lock() {
+        if let Some(job) = jobs.iter_mut().find(|j| j.id == id) {
+            f(job);
+        }
+    }
+}
+
+/// Records a running job's output line and/or percentage.
+fn report(id: usize, line: Option<&str>, percent: Option<u8>) {
+    with_job(id, |job| {
+        if let Some(line) = line {
+            job.log.push(line.to_string());
+        }
+        if percent.is_some() {
+            job.progress = percent;
+        }
+    });
+}
+
+/// Runs queued jobs one at a time, in the order they were added.
+fn worker(receiver: mpsc::Receiver<usize>) {
+    for id in receiver {
+        let kind = match JOBS.lock() {
+            Ok(jobs) => jobs.iter().find(|j| j.id == id).map(|j| j.kind.clone()),
+            Err(_) => None,
+        };
+        let kind = match kind {
+            Some(kind) => kind,
+            None => continue,
+        };
+
+        with_job(id, |job| {
+            job.state = JobState::Running;
+            job.started_at = Some(Instant::now());
+            job.log.push(format!(
+                "Started after {}s in the queue",
+                job.queued_at.elapsed().as_secs()
+            ));
+        });
+
+        let export = exec::export_status();
+        // Log lines and whether a restart is needed
+        let result: Result<(Vec<String>, bool), String> = match &kind {
+            JobKind::Feature { name, install } => features::change_feature(name, *install).map(|change| {
+                let mut log: Vec<String> = change.output.lines().map(String::from).collect();
+                log.push(format!("Changed: {}", change.changed));
+                if change.restart_needed {
+                    log.push("A restart is required to complete the change.".to_string());
+                }
+                (log, change.restart_needed)
+            }),
+            JobKind::SystemStateBackup { target } => {
+                wbadmin::system_state_backup(target, |line, percent| report(id, line, percent))
+                    .map(|summary| (vec![summary], false))
+            }
+            JobKind::DockerHost => {
+                docker::setup(|line, percent| report(id, line, percent)).map(|summary| (vec![summary], false))
+            }
+            JobKind::HealthRepair => health::repair(|line, percent| report(id, line, percent)).map(|summary| {
+                let restart = summary.contains("REPAIRED");
+                (summary.lines().map(String::from).collect(), restart)
+            }),
+            JobKind::Migration(migration) => robocopy::run(migration, |line, percent| report(id, line, percent))
+                .map(|summary| (summary.lines().map(String::from).collect(), false)),
+            JobKind::VmExport(names) => hyperv::export_vms(names, |line, percent| report(id, line, percent))
+                .map(|summary| (summary.lines().map(String::from).collect(), false)),
+            JobKind::WingetConfigure(path) => {
+                winget_config::apply(path, |line, percent| report(id, line, percent)).map(|summary| (vec![summary], false))
+            }
+        };
+
+        with_job(id, |job| {
+            job.finished_at = Some(Instant::now());
+            if let Some((path, _)) = export {
+                job.state = JobState::Exported;
+                job.log.push(format!("Export mode: written to {} instead of run", path.display()));
+                return;
+            }
+            match result {
+                Ok((log, restart_needed)) => {
+                    job.state = JobState::Succeeded;
+                    job.restart_needed = restart_needed;
+                    job.log.extend(log);
+                }
+                Err(e) => {
+                    job.state = JobState::Failed;
+                    job.log.extend(e.lines().map(String::from));
+                }
+            }
+        });
+    }
+}
+
+/// Adds a job to the queue and returns its number.
+pub fn enqueue(kind: JobKind) -> usize {
+    let sender = QUEUE.get_or_init(|| {
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || worker(receiver));
+        sender
+    });
+
+    let id = {
+        let mut jobs = match JOBS.lock() {
+            Ok(jobs) => jobs,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        let id = jobs.len() + 1;
+        jobs.push(Job {
+            id,
+            log: vec![format!("Queued: {}", kind.describe())],
+            kind,
+            state: JobState::Queued,
+            restart_needed: false,
+            progress: None,
+            queued_at: Instant::now(),
+            started_at: None,
+            finished_at: None,
+        });
+        id
+    };
+    let _ = sender.send(id);
+    id
+}
+
+/// Rows for the jobs screen, newest first.
+pub fn job_rows() -> Result<Vec<Vec<String>>, String> {
+    let jobs = JOBS.lock().map_err(|_| "The job list is unavailable".to_string())?;
+    Ok(jobs
+        .iter()
+        .rev()
+        .map(|job| {
+            let duration = match (job.started_at, job.finished_at) {
+                (Some(start), Some(end)) => format!("{}s", (end - start).as_secs()),
+                (Some(start), None) => format!("{}s", start.elapsed().as_secs()),
+                _ => String::new(),
+            };
+            vec![
+                job.id.to_string(),
+                job.kind.describe(),
+                match (job.state, job.progress) {
+                    (JobState::Running, Some(p)) => format!("running {}%", p),
+                    (state, _) => state.label().to_string(),
+                },
+                if job.restart_needed { "yes" } else { "" }.to_string(),
+                duration,
+            ]
+        })
+        .collect())
+}
+
+pub fn job_log(id: usize) -> Option<(String, String)> {
+    let jobs = JOBS.lock().ok()?;
+    let job = jobs.iter().find(|j| j.id == id)?;
+    Some((format!(" Job #{}: {} ", job.id, job.kind.describe()), job.log.join("\n")))
+}
+
+/// Jobs numbered above `after` that have finished, with a notification for each. Jobs run in
+/// the order they were queued, so the number of the last one reported is enough to go on.
+pub fn finished_after(after: usize) -> Vec<(usize, String, Level)> {
+    let jobs = match JOBS.lock() {
+        Ok(jobs) => jobs,
+        Err(_) => return Vec::new(),
+    };
+    jobs.iter()
+        .filter(|job| job.id > after && job.finished_at.is_some())
+        .map(|job| {
+            let level = match job.state {
+                JobState::Failed => Level::Bad,
+                _ if job.restart_needed => Level::Warning,
+                _ => Level::Good,
+            };
+            let restart = if job.restart_needed { ", restart needed" } else { "" };
+            (job.id, format!("Job #{} {}{}: {}", job.id, job.state.label().to_lowercase(), restart, job.kind.describe()), level)
+        })
+        .collect()
+}
+
+/// Number of jobs queued or running.
+pub fn pending() -> usize {
+    JOBS.lock()
+        .map(|jobs| {
+            jobs.iter()
+                .filter(|j| matches!(j.state, JobState::Queued | JobState::Running))
+                .count()
+        })
+        .unwrap_or(0)
+}
+
+/// Summary for the status line, e.g. "2 running/queued, 1 needs a restart".
+pub fn summary() -> String {
+    let pending = pending();
+    let jobs = match JOBS.lock() {
+        Ok(jobs) => jobs,
+        Err(_) => return String::new(),
+    };
+    let restart = jobs.iter().filter(|j| j.restart_needed).count();
+    match (pending, restart) {
+        (0, 0) => String::new(),
+        (p, 0) => format!("{} job(s) running/queued", p),
+        (0, r) => format!("{} job(s) need a restart", r),
+        (p, r) => format!("{} job(s) running/queued, {} need a restart", p, r),
+    }
+}
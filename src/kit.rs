@@ -0,0 +1,163 @@
+//! Build Provisioning Kit: a folder, and a ZIP of it, holding this executable set up for
+//! portable and offline use, the download cache, the agent catalog and validation baseline,
+//! and the newest role backup as a template. Copy it to a USB drive for offline site work.
+
+use std::path::{Path, PathBuf};
+
+use crate::{
+    agents, cache, dates, delta, exec, features, message,
+    outcome::{OperationError, OperationOutcome},
+    rename, validation, PORTABLE_FLAG,
+};
+
+const README: &str = "Server Helper provisioning kit\r\n\
+\r\n\
+Run server-helper.exe from this folder as Administrator. portable.flag keeps config,\r\n\
+history, the download cache and backups in the ServerHelper and ServerBackups folders\r\n\
+here, and the kit starts in offline mode: installers come from the cache only.\r\n\
+\r\n\
+ServerHelper\\agents.ini       agent catalog\r\n\
+ServerHelper\\validation.ini   post-provision validation baseline\r\n\
+ServerHelper\\cache           cached installers\r\n\
+ServerBackups                 role and feature backup to restore on new servers\r\n";
+
+fn io_error(path: &Path, e: std::io::Error) -> OperationOutcome {
+    OperationOutcome::failed(OperationError::Io { path: path.to_path_buf(), reason: e.to_string() })
+}
+
+/// Copies `from` into `to` recursively and returns the number of files copied.
+fn copy_dir(from: &Path, to: &Path) -> std::io::Result<usize> {
+    std::fs::create_dir_all(to)?;
+    let mut copied = 0;
+    for entry in std::fs::read_dir(from)?.flatten() {
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copied += copy_dir(&entry.path(), &target)?;
+        } else {
+            std::fs::copy(entry.path(), target)?;
+            copied += 1;
+        }
+    }
+    Ok(copied)
+}
+
+/// Builds `ServerHelperKit_<time>` and its ZIP in `destination`.
+pub fn build(destination: &Path) -> OperationOutcome {
+    let kit = destination.join(format!("ServerHelperKit_{}", dates::now()));
+    let data = kit.join("ServerHelper");
+    let backups = kit.join("ServerBackups");
+    for dir in [&data, &backups] {
+        if let Err(e) = std::fs::create_dir_all(dir) {
+            return io_error(dir, e);
+        }
+    }
+    let mut report = vec![format!("Kit: {}", kit.display())];
+
+    message::log("Copying the executable...");
+    let exe = match std::env::current_exe() {
+        Ok(exe) => exe,
+        Err(e) => return OperationOutcome::failed(format!("Failed to find the running executable: {}", e)),
+    };
+    let files = [
+        (exe, kit.join("server-helper.exe")),
+        (agents::catalog_path(), data.join("agents.ini")),
+        (validation::checks_path(), data.join("validation.ini")),
+    ];
+    for (from, to) in files {
+        if !from.exists() {
+            report.push(format!("Skipped {} (not set up on this server)", from.display()));
+            continue;
+        }
+        if let Err(e) = std::fs::copy(&from, &to) {
+            return io_error(&to, e);
+        }
+    }
+    for (marker, why) in [(kit.join(PORTABLE_FLAG), "portable mode"), (data.join("offline"), "offline mode")] {
+        if let Err(e) = std::fs::write(&marker, "") {
+            return io_error(&marker, e);
+        }
+        report.push(format!("Starts in {}", why));
+    }
+    if let Err(e) = std::fs::write(kit.join("README.txt"), README) {
+        return io_error(&kit.join("README.txt"), e);
+    }
+
+    message::log("Copying the download cache...");
+    let cached = match copy_dir(&cache::dir(), &data.join("cache")) {
+        Ok(count) => count,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => 0,
+        Err(e) => return io_error(&cache::dir(), e),
+    };
+    // The index is one of the copied files
+    report.push(format!("Cached installers: {}", cached.saturating_sub(1)));
+
+    message::log("Copying the newest role backup...");
+    match features::latest_backup() {
+        Some(roles) => {
+            // The kit carries no base, so a differential backup goes in rebuilt in full
+            let full = match delta::full(&roles) {
+                Ok(full) => full,
+                Err(message) => return OperationOutcome::failed(message),
+            };
+            let name = roles.with_extension("xml").file_name().unwrap_or_default().to_string_lossy().to_string();
+            let list = roles.with_file_name(name.replace("ServerRoles_", "InstalledFeatures_")).with_extension("txt");
+            let source = rename::source_path(&roles);
+            if let Err(e) = std::fs::copy(&full, backups.join(&name)) {
+                return io_error(&backups.join(&name), e);
+            }
+            for file in [list, source].iter().filter(|f| f.exists()) {
+                let target = backups.join(file.file_name().unwrap_or_default());
+                if let Err(e) = std::fs::copy(file, &target) {
+                    return io_error(&target, e);
+                }
+            }
+            report.push(format!("Role backup template: {}", name));
+        }
+        None => report.push("No role backup yet; back up a reference server first to include one.".to_string()),
+    }
+
+    message::log("Compressing the kit...");
+    let zip = PathBuf::from(format!("{}.zip", kit.display()));
+    let script = format!(
+        "Compress-Archive -Path {} -DestinationPath {} -Force",
+        exec::quote(&kit.join("*").to_string_lossy()),
+        exec::quote(&zip.to_string_lossy())
+    );
+    match exec::powershell(&script) {
+        Ok(output) if output.success => {
+            report.push(format!("ZIP: {}", zip.display()));
+            OperationOutcome::Success(report.join("\n"))
+        }
+        Ok(output) => OperationOutcome::Partial {
+            message: report.join("\n"),
+            failed_items: vec![format!("ZIP: {}", output.stderr.trim())],
+        },
+        Err(e) => OperationOutcome::Partial { message: report.join("\n"), failed_items: vec![format!("ZIP: {}", e)] },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{temp_dir, FakeRunner};
+
+    #[test]
+    fn builds_a_portable_offline_kit() {
+        let runner = FakeRunner::new().install();
+        let destination = temp_dir("kit");
+
+        let outcome = build(&destination);
+
+        assert!(matches!(&outcome, OperationOutcome::Success(_)), "{}", outcome.message());
+        let kit = std::fs::read_dir(&destination)
+            .expect("read destination")
+            .flatten()
+            .map(|e| e.path())
+            .find(|p| p.is_dir())
+            .expect("kit folder");
+        assert!(kit.join("server-helper.exe").exists());
+        assert!(kit.join(PORTABLE_FLAG).exists());
+        assert!(kit.join("ServerHelper").join("offline").exists());
+        assert!(runner.ran("Compress-Archive"));
+    }
+}
@@ -0,0 +1,140 @@
+use crate::{backup_dir, exec, history};
+
+/// The built-in Administrator account (RID 500, whatever it was renamed to).
+pub struct Account {
+    pub name: String,
+    pub enabled: bool,
+    pub password_last_set: String,
+    /// Windows LAPS manages the password, so it must be rotated through LAPS instead.
+    pub laps: bool,
+}
+
+const ACCOUNT_SCRIPT: &str = r#"
+$admin = Get-LocalUser | Where-Object { $_.SID.Value -like 'S-1-5-21-*-500' } | Select-Object -First 1
+if (-not $admin) { throw 'The built-in Administrator account was not found.' }
+$policy = Get-ItemProperty 'HKLM:\SOFTWARE\Microsoft\Policies\LAPS', 'HKLM:\SOFTWARE\Microsoft\Windows\CurrentVersion\LAPS\Config' -ErrorAction SilentlyContinue |
+    Where-Object { $_.BackupDirectory -gt 0 }
+"Name=$($admin.Name)"
+"Enabled=$($admin.Enabled)"
+"LastSet=$(if ($admin.PasswordLastSet) { $admin.PasswordLastSet.ToString('yyyy-MM-dd HH:mm') } else { 'never' })"
+"Laps=$([bool]$policy -and [bool](Get-Command Reset-LapsPassword -ErrorAction SilentlyContinue))"
+"#;
+
+/// Generates a password of at least 20 characters (longer if the policy minimum is) with all
+/// four character classes, sets it on the account and prints it. `$file`, when set, receives a
+/// DPAPI-encrypted copy.
+const ROTATE_SCRIPT: &str = r#"
+$admin = Get-LocalUser | Where-Object { $_.SID.Value -like 'S-1-5-21-*-500' } | Select-Object -First 1
+$min = 0
+net accounts | ForEach-Object { if ($_ -match '^Minimum password length:\s+(\d+)') { $min = [int]$Matches[1] } }
+$rng = [Security.Cryptography.RandomNumberGenerator]::Create()
+function Get-RandomIndex([int]$max) { $b = [byte[]]::new(4); $rng.GetBytes($b); [BitConverter]::ToUInt32($b, 0) % $max }
+$sets = 'ABCDEFGHJKLMNPQRSTUVWXYZ', 'abcdefghijkmnopqrstuvwxyz', '23456789', '!#%*+-?@_'
+$all = -join $sets
+$chars = [Collections.Generic.List[char]]::new()
+foreach ($set in $sets) { $chars.Add($set[(Get-RandomIndex $set.Length)]) }
+while ($chars.Count -lt [Math]::Max(20, $min)) { $chars.Add($all[(Get-RandomIndex $all.Length)]) }
+$password = -join ($chars | Sort-Object { Get-RandomIndex ([int]::MaxValue) })
+$secure = ConvertTo-SecureString $password -AsPlainText -Force
+$admin | Set-LocalUser -Password $secure -ErrorAction Stop
+if ($file) { $secure | ConvertFrom-SecureString | Set-Content -LiteralPath $file -ErrorAction Stop }
+"Password=$password"
+"#;
+
+pub fn account() -> Result<Account, String> {
+    let output = exec::powershell(ACCOUNT_SCRIPT).map_err(|e| format!("Failed to read the Administrator account: {}", e))?;
+    if !output.success {
+        return Err(format!("Failed to read the Administrator account:\n{}", output.stderr.trim()));
+    }
+    let values = exec::key_values(&output.stdout);
+    let value = |key: &str| values.get(key).cloned().unwrap_or_default();
+    Ok(Account {
+        name: value("Name"),
+        enabled: value("Enabled") == "True",
+        password_last_set: value("LastSet"),
+        laps: value("Laps") == "True",
+    })
+}
+
+/// Sets a new random password on the built-in Administrator and returns it for display, or
+/// asks Windows LAPS to rotate it when LAPS manages the account. The rotation (never the
+/// password) is written to the history log.
+pub fn rotate(save: bool) -> (bool, String) {
+    if exec::export_status().is_some() {
+        return (
+            false,
+            "Password rotation is not exported: the generated password would end up in the script. \
+            Stop export mode and run it directly."
+                .to_string(),
+        );
+    }
+    let account = match account() {
+        Ok(account) => account,
+        Err(e) => return (false, e),
+    };
+
+    if account.laps {
+        return match exec::change("Reset-LapsPassword -ErrorAction Stop") {
+            Ok(output) if output.success => {
+                history::record(&format!("Rotated the {} password through Windows LAPS", account.name));
+                (
+                    true,
+                    format!(
+                        "Windows LAPS rotated the {} password and backed it up to the directory.\n\n\
+                        Read it with Get-LapsADPassword (or Get-LapsAADPassword) from a management host.",
+                        account.name
+                    ),
+                )
+            }
+            Ok(output) => (false, format!("Reset-LapsPassword failed: {}", output.stderr.trim())),
+            Err(e) => (false, format!("Reset-LapsPassword failed: {}", e)),
+        };
+    }
+
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let file = backup_dir().join(format!("AdminPassword_{}.sec", timestamp));
+    if save {
+        if let Err(e) = std::fs::create_dir_all(backup_dir()) {
+            return (false, format!("Failed to create backup directory: {}", e));
+        }
+    }
+    let script = format!(
+        "$file = {}\n{}",
+        if save { exec::quote(&file.to_string_lossy()) } else { "$null".to_string() },
+        ROTATE_SCRIPT
+    );
+    let output = match exec::change(&script) {
+        Ok(output) if output.success => output,
+        Ok(output) => return (false, format!("Failed to set the {} password: {}", account.name, output.stderr.trim())),
+        Err(e) => return (false, format!("Failed to set the {} password: {}", account.name, e)),
+    };
+    let password = match exec::key_values(&output.stdout).remove("Password") {
+        Some(password) if !password.is_empty() => password,
+        _ => return (false, format!("The {} password may have changed but was not returned.", account.name)),
+    };
+
+    let mut entry = format!("Rotated the local {} password", account.name);
+    let mut message = format!(
+        "New password for {}:\n\n    {}\n\n\
+        This is the only time it is shown. Store it in your password manager now.",
+        account.name, password
+    );
+    if save {
+        entry.push_str(&format!("\nEncrypted copy: {}", file.display()));
+        message.push_str(&format!(
+            "\n\nAn encrypted copy was saved to {}.\n\
+            Only this user on this server can read it back:\n  \
+            [Net.NetworkCredential]::new('', (Get-Content '{}' | ConvertTo-SecureString)).Password",
+            file.display(),
+            file.display()
+        ));
+    }
+    if !account.enabled {
+        message.push_str(&format!("\n\nNote: the {} account is disabled.", account.name));
+    }
+    history::record(&entry);
+    (true, message)
+}
@@ -0,0 +1,242 @@
+//! Repeat-last-action and keyboard macros. An action is a menu item and the keys pressed until
+//! the menu shows again; a macro is the actions recorded between two presses of `m`. Macros are
+//! saved as text under `macros` in the config directory, one step per line, so they can be copied
+//! to another server and replayed there with `--macro <name or file>`. Passwords are never
+//! recorded: a masked prompt is kept as a `secret` step, and the replay waits there for the
+//! password to be typed again.
+
+use std::path::{Path, PathBuf};
+
+use crossterm::event::KeyCode;
+
+use crate::config_dir;
+
+const EXTENSION: &str = "macro";
+
+/// One recorded step: a menu item chosen by its label, a key pressed on a screen, or an answer
+/// to a masked prompt, typed again on replay.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Step {
+    Menu(String),
+    Key(KeyCode),
+    Secret,
+}
+
+const NAMED_KEYS: [(&str, KeyCode); 13] = [
+    ("Enter", KeyCode::Enter),
+    ("Esc", KeyCode::Esc),
+    ("Tab", KeyCode::Tab),
+    ("Backspace", KeyCode::Backspace),
+    ("Delete", KeyCode::Delete),
+    ("Up", KeyCode::Up),
+    ("Down", KeyCode::Down),
+    ("Left", KeyCode::Left),
+    ("Right", KeyCode::Right),
+    ("Home", KeyCode::Home),
+    ("End", KeyCode::End),
+    ("PageUp", KeyCode::PageUp),
+    ("PageDown", KeyCode::PageDown),
+];
+
+fn key_name(code: KeyCode) -> Option<String> {
+    match code {
+        KeyCode::Char('<') => Some("<lt>".to_string()),
+        KeyCode::Char(c) => Some(c.to_string()),
+        code => NAMED_KEYS.iter().find(|(_, k)| *k == code).map(|(name, _)| format!("<{}>", name)),
+    }
+}
+
+fn parse_keys(text: &str) -> Result<Vec<KeyCode>, String> {
+    let mut keys = Vec::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if c == '<' {
+            let end = rest.find('>').ok_or_else(|| format!("Unclosed key name in: {}", text))?;
+            let name = &rest[1..end];
+            let key = match name {
+                "lt" => KeyCode::Char('<'),
+                name => NAMED_KEYS
+                    .iter()
+                    .find(|(n, _)| n.eq_ignore_ascii_case(name))
+                    .map(|(_, k)| *k)
+                    .ok_or_else(|| format!("Unknown key <{}>.", name))?,
+            };
+            keys.push(key);
+            rest = &rest[end + 1..];
+        } else {
+            keys.push(KeyCode::Char(c));
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    Ok(keys)
+}
+
+/// The text form of `steps`: `menu <label>` lines, `keys <keys>` lines with named keys such as
+/// `<Enter>` in angle brackets, and `secret` lines.
+pub fn render(steps: &[Step]) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    let mut keys = String::new();
+    for step in steps {
+        let line = match step {
+            Step::Menu(label) => format!("menu {}", label),
+            Step::Secret => "secret".to_string(),
+            Step::Key(code) => {
+                keys.push_str(&key_name(*code).unwrap_or_default());
+                continue;
+            }
+        };
+        if !keys.is_empty() {
+            lines.push(format!("keys {}", std::mem::take(&mut keys)));
+        }
+        lines.push(line);
+    }
+    if !keys.is_empty() {
+        lines.push(format!("keys {}", keys));
+    }
+    lines.iter().map(|l| format!("{}\n", l)).collect()
+}
+
+/// Steps from the text form written by `render`. Blank lines and `#` comments are skipped.
+pub fn parse(text: &str) -> Result<Vec<Step>, String> {
+    let mut steps = Vec::new();
+    for (n, line) in text.lines().enumerate() {
+        let line = line.trim_end_matches('\r');
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+        if line.trim() == "secret" {
+            steps.push(Step::Secret);
+            continue;
+        }
+        match line.split_once(' ') {
+            Some(("menu", label)) => steps.push(Step::Menu(label.trim().to_string())),
+            Some(("keys", keys)) => steps.extend(parse_keys(keys)?.into_iter().map(Step::Key)),
+            _ => return Err(format!("Line {}: expected `menu <item>`, `keys <keys>` or `secret`, found: {}", n + 1, line)),
+        }
+    }
+    Ok(steps)
+}
+
+/// The menu items in `steps`, e.g. `Listening Ports > History`.
+pub fn summary(steps: &[Step]) -> String {
+    steps
+        .iter()
+        .filter_map(|s| match s {
+            Step::Menu(label) => Some(label.as_str()),
+            Step::Key(_) | Step::Secret => None,
+        })
+        .collect::<Vec<_>>()
+        .join(" > ")
+}
+
+fn dir() -> PathBuf {
+    config_dir().join("macros")
+}
+
+fn path(name: &str) -> PathBuf {
+    dir().join(format!("{}.{}", name, EXTENSION))
+}
+
+/// Saves `steps` as macro `name`. Returns where it was written.
+pub fn save(name: &str, steps: &[Step]) -> Result<PathBuf, String> {
+    let name = name.trim();
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err("Use letters, digits, - and _ for the macro name, e.g. PrepareFileServer.".to_string());
+    }
+    let path = path(name);
+    std::fs::create_dir_all(dir())
+        .and_then(|_| std::fs::write(&path, render(steps).replace('\n', "\r\n")))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+/// The steps of macro `name`, or of the macro file at that path.
+pub fn load(name: &str) -> Result<Vec<Step>, String> {
+    let path = if Path::new(name).extension().is_some() { PathBuf::from(name) } else { path(name) };
+    let text = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let steps = parse(&text).map_err(|e| format!("{}: {}", path.display(), e))?;
+    if steps.is_empty() {
+        return Err(format!("{} has no steps.", path.display()));
+    }
+    Ok(steps)
+}
+
+/// Deletes macro `name`.
+pub fn delete(name: &str) -> Result<String, String> {
+    let path = path(name);
+    std::fs::remove_file(&path).map_err(|e| format!("Failed to delete {}: {}", path.display(), e))?;
+    Ok(format!("Deleted macro {}.", name))
+}
+
+/// Saved macros: name, menu items run, step count and file.
+pub fn rows() -> Result<Vec<Vec<String>>, String> {
+    let entries = match std::fs::read_dir(dir()) {
+        Ok(entries) => entries,
+        Err(_) => return Ok(Vec::new()),
+    };
+    let mut rows: Vec<Vec<String>> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|e| e == EXTENSION))
+        .map(|p| {
+            let name = p.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+            let (actions, steps) = match load(&name) {
+                Ok(steps) => (summary(&steps), steps.len().to_string()),
+                Err(e) => (e, String::new()),
+            };
+            vec![name, actions, steps, p.display().to_string()]
+        })
+        .collect();
+    rows.sort();
+    Ok(rows)
+}
+
+/// The macro named by `--macro <name or file>` or `--macro=<name or file>`.
+pub fn from_args(args: &[String]) -> Option<String> {
+    args.iter().enumerate().find_map(|(i, arg)| match arg.strip_prefix("--macro") {
+        Some(rest) => match rest.strip_prefix('=') {
+            Some(name) => Some(name.to_string()),
+            None if rest.is_empty() => args.get(i + 1).cloned(),
+            None => None,
+        },
+        None => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_steps_through_the_text_form() {
+        let steps = vec![
+            Step::Menu("Set Static DNS".to_string()),
+            Step::Key(KeyCode::Char('1')),
+            Step::Key(KeyCode::Char('<')),
+            Step::Key(KeyCode::Enter),
+            Step::Menu("History".to_string()),
+            Step::Key(KeyCode::Esc),
+            Step::Secret,
+        ];
+
+        let text = render(&steps);
+
+        assert_eq!(text, "menu Set Static DNS\nkeys 1<lt><Enter>\nmenu History\nkeys <Esc>\nsecret\n");
+        assert_eq!(parse(&text.replace('\n', "\r\n")), Ok(steps.clone()));
+        assert_eq!(summary(&steps), "Set Static DNS > History");
+        assert!(parse("keys <F13>").is_err());
+        assert!(parse("run History").is_err());
+    }
+
+    #[test]
+    fn saves_and_loads_a_macro() {
+        let steps = vec![Step::Menu("Listening Ports".to_string()), Step::Key(KeyCode::Esc)];
+
+        let path = save("PortsCheck", &steps).unwrap_or_default();
+
+        assert_eq!(load("PortsCheck"), Ok(steps.clone()));
+        assert_eq!(load(&path.to_string_lossy()), Ok(steps));
+        assert!(save("../escape", &[]).is_err());
+        assert_eq!(from_args(&["x".into(), "--macro=PortsCheck".into()]), Some("PortsCheck".to_string()));
+    }
+}
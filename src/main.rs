@@ -1,20 +1,112 @@
 use std::{
+    collections::VecDeque,
     io::stdout,
-    path::PathBuf,
-    process::Command,
-    time::Duration,
+    ops::ControlFlow,
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        OnceLock,
+    },
+    time::{Duration, Instant},
 };
 
 use anyhow::Result;
 use crossterm::{
-    event::{self, Event, KeyCode, KeyEventKind},
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
     ExecutableCommand,
 };
 use ratatui::{
     prelude::*,
-    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap},
 };
+use message::Message;
+use plan::Plan;
+use outcome::{OperationError, OperationOutcome};
+use table::{TableKind, TableView};
+
+mod acls;
+mod admin_center;
+mod agents;
+mod cache;
+mod catalog;
+mod certs;
+mod checkpoint;
+mod cleanup;
+mod cluster;
+#[cfg(feature = "windows-roles")]
+mod conflicts;
+mod crashdump;
+mod dates;
+mod dedup;
+mod delta;
+mod deviceguard;
+mod docker;
+mod domain;
+mod dotnet;
+mod download;
+mod dsc;
+mod environment;
+mod exec;
+#[cfg(feature = "windows-roles")]
+mod features;
+#[cfg(not(feature = "windows-roles"))]
+#[path = "features_disabled.rs"]
+mod features;
+mod firewall;
+mod fsrm;
+mod golden;
+mod health;
+mod history;
+mod hosts;
+mod hyperv;
+mod iis;
+mod jobs;
+mod kit;
+mod localadmin;
+mod macros;
+mod message;
+mod network;
+mod offsite;
+mod outcome;
+mod performance;
+mod plan;
+mod platform;
+mod policy;
+mod processes;
+mod profiles;
+mod proxy;
+mod rds;
+mod remote;
+mod rename;
+mod robocopy;
+mod security;
+mod selfinstall;
+#[cfg(feature = "web")]
+mod serve;
+mod sessions;
+mod signing;
+mod snmp;
+mod spooler;
+mod sql;
+mod status;
+mod sysmon;
+mod table;
+mod tasks;
+mod telemetry;
+#[cfg(test)]
+mod testing;
+#[cfg(test)]
+mod ui_snapshots;
+mod tls;
+mod toast;
+mod uptime;
+mod validation;
+mod vcredist;
+mod wbadmin;
+mod winget_config;
+mod wsl;
+mod wsus;
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 
@@ -22,55 +114,1152 @@ const VERSION: &str = env!("CARGO_PKG_VERSION");
 enum InstallItem {
     Winget,
     NetBird,
+    AdminCenter { port: u16, thumbprint: Option<String> },
+    /// Sysmon with the chosen config file, or the bundled default.
+    Sysmon(Option<PathBuf>),
+    SqlExpress { instance: String, password: String },
+}
+
+impl InstallItem {
+    fn install(&self) -> OperationOutcome {
+        match self {
+            InstallItem::Winget => App::install_winget(),
+            InstallItem::NetBird => platform::current().install_vpn(),
+            InstallItem::AdminCenter { port, thumbprint } => admin_center::install(*port, thumbprint.as_deref()),
+            InstallItem::Sysmon(config) => sysmon::install(config.as_deref()),
+            InstallItem::SqlExpress { instance, password } => sql::install_express(instance, password),
+        }
+    }
+
+    /// Title and message of the screen shown while the install runs.
+    fn progress(&self) -> (&'static str, &'static str) {
+        match self {
+            InstallItem::Winget => (" Installing Winget ", "Installing Winget... Please wait.\n\nThis may take a few minutes."),
+            InstallItem::NetBird => (" Installing NetBird ", "Installing NetBird... Please wait.\n\nThis may take a few minutes."),
+            InstallItem::AdminCenter { .. } => (" Installing Windows Admin Center ", "Downloading and installing Windows Admin Center... Please wait.\n\nThis may take several minutes."),
+            InstallItem::Sysmon(_) => (" Deploying Sysmon ", "Downloading and installing Sysmon, then checking for events... Please wait."),
+            InstallItem::SqlExpress { .. } => (" Installing SQL Server Express ", "Downloading and installing SQL Server Express, then testing a TCP connection... Please wait.\n\nThis may take 10 minutes or more."),
+        }
+    }
+}
+
+/// What a file picked in the file browser is used for.
+#[derive(Clone, Copy, PartialEq)]
+enum BrowsePurpose {
+    RestoreRoles,
+    SysmonConfig,
+    IssuedCertificate,
+    AclBackup,
+    VmExport,
+    WingetConfig,
+}
+
+impl BrowsePurpose {
+    fn title(&self) -> &'static str {
+        match self {
+            BrowsePurpose::RestoreRoles => "Select Backup File",
+            BrowsePurpose::SysmonConfig => "Select Sysmon Config",
+            BrowsePurpose::IssuedCertificate => "Select Issued Certificate",
+            BrowsePurpose::AclBackup => "Select Permissions Backup",
+            BrowsePurpose::VmExport => "Select VM Export",
+            BrowsePurpose::WingetConfig => "Select WinGet Configuration",
+        }
+    }
+
+    /// File extensions listed in the browser.
+    fn extensions(&self) -> &'static [&'static str] {
+        match self {
+            BrowsePurpose::RestoreRoles => &["xml", delta::EXTENSION],
+            BrowsePurpose::SysmonConfig => &["xml"],
+            BrowsePurpose::IssuedCertificate => &["cer", "crt", "pem", "p7b"],
+            BrowsePurpose::AclBackup => &["acls"],
+            BrowsePurpose::VmExport => &["vms"],
+            BrowsePurpose::WingetConfig => &["yaml", "yml"],
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+// Builds without some cargo features leave their actions out of the menu
+#[cfg_attr(not(all(feature = "netbird", feature = "windows-roles")), allow(dead_code))]
+enum MenuAction {
+    CheckWinget,
+    InstallWinget,
+    WingetConfigure,
+    CheckNetBird,
+    InstallNetBird,
+    NetBirdConflicts,
+    InstallAdminCenter,
+    Snmp,
+    Agents,
+    DotNet,
+    VcRedist,
+    SqlExpress,
+    BackupRoles,
+    BackupCatalog,
+    GoldenBaselines,
+    RestoreRoles,
+    RemoteRestore,
+    RollbackRestore,
+    DscExport,
+    CopyData,
+    BackupAcls,
+    RestoreAcls,
+    Fsrm,
+    Dedup,
+    VirtualMachines,
+    ImportVms,
+    SystemStateBackup,
+    ClusterValidation,
+    DcDiag,
+    Replication,
+    GpUpdate,
+    GpResult,
+    CertificateRequest,
+    CompleteCertificateRequest,
+    Rds,
+    DockerHost,
+    Wsl,
+    ViewLastBackup,
+    OffsiteBackups,
+    Features,
+    Jobs,
+    SecurityAudit,
+    Schannel,
+    Hardening,
+    Telemetry,
+    DeviceGuard,
+    AuditPolicy,
+    RotateAdminPassword,
+    AccountPolicy,
+    Sysmon,
+    ListeningPorts,
+    ConnectivityTest,
+    ShareTest,
+    FirewallRule,
+    DnsLookup,
+    Proxy,
+    Wsus,
+    Routes,
+    NetworkAdapters,
+    IisSites,
+    HostsFile,
+    Environment,
+    ScheduledTasks,
+    Processes,
+    RdpSessions,
+    PowerPlan,
+    Uptime,
+    Validation,
+    HealthRepair,
+    FixSpooler,
+    FreeUpSpace,
+    CleanTemp,
+    DownloadCache,
+    ProvisioningKit,
+    ServiceControl,
+    Pagefile,
+    CrashDump,
+    History,
+    Checkpoints,
+    ExportMode,
+    Offline,
+    Profiles,
+    Macros,
+    Exit,
+}
+
+impl MenuAction {
+    /// Why the action is unavailable in offline mode, for actions that download from the internet.
+    fn needs_internet(&self) -> Option<&'static str> {
+        match self {
+            MenuAction::InstallWinget => Some("downloads winget from GitHub"),
+            MenuAction::WingetConfigure => Some("downloads DSC modules from the PowerShell Gallery"),
+            MenuAction::InstallNetBird => Some("downloads NetBird"),
+            MenuAction::InstallAdminCenter => Some("downloads Windows Admin Center"),
+            MenuAction::VcRedist => Some("downloads the redistributables from Microsoft"),
+            MenuAction::SqlExpress => Some("downloads the SQL Server Express installer"),
+            MenuAction::DockerHost => Some("downloads Docker from download.docker.com"),
+            MenuAction::Wsl => Some("downloads the distribution from the Microsoft Store"),
+            MenuAction::Sysmon => Some("downloads Sysmon from Sysinternals"),
+            MenuAction::Proxy => Some("tests internet sites"),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Clone, PartialEq)]
 enum AppState {
     Menu,
     Installing(InstallItem),
+    FileBrowser(BrowsePurpose),
+    /// A change plan awaiting approval; `y` applies it.
+    PlanReview,
+    Applying,
+    Checklist,
+    Table,
+    Input(InputPurpose),
+    Running(Task),
+    /// Scrollable read-only text; Esc returns to the screen it was opened from.
+    TextView,
+    Confirm { message: String, action: ConfirmAction },
+    Result(OperationOutcome),
+}
+
+/// An `AppState` without its data, for the transition table.
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum StateKind {
+    Menu,
+    Installing,
     FileBrowser,
-    Restoring,
-    Result { success: bool, message: String },
+    PlanReview,
+    Applying,
+    Checklist,
+    Table,
+    Input,
+    Running,
+    TextView,
+    Confirm,
+    Result,
+}
+
+/// The states each state can move to. Installing, Applying and Running are busy states: they
+/// run their operation and always end on the Result screen.
+const TRANSITIONS: &[(StateKind, &[StateKind])] = {
+    use StateKind::*;
+    &[
+        (Menu, &[Installing, FileBrowser, PlanReview, Checklist, Table, Input, Running, TextView, Confirm, Result]),
+        (Installing, &[Result]),
+        (FileBrowser, &[Menu, Installing, PlanReview, Input, Confirm, Result]),
+        (PlanReview, &[Menu, FileBrowser, Checklist, Table, Applying, Result]),
+        (Applying, &[Result]),
+        (Checklist, &[Menu, Checklist, Input, Confirm, Result]),
+        (Table, &[Menu, PlanReview, Table, Input, TextView, Confirm, Result]),
+        (Input, &[Menu, Installing, PlanReview, Checklist, Table, Input, Running, TextView, Confirm, Result]),
+        (Running, &[Result]),
+        (TextView, &[Menu, Checklist, Table]),
+        (Confirm, &[Menu, Checklist, Table, Running, Result]),
+        (Result, &[Menu, FileBrowser, Checklist, Table, Result]),
+    ]
+};
+
+impl AppState {
+    fn kind(&self) -> StateKind {
+        match self {
+            AppState::Menu => StateKind::Menu,
+            AppState::Installing(_) => StateKind::Installing,
+            AppState::FileBrowser(_) => StateKind::FileBrowser,
+            AppState::PlanReview => StateKind::PlanReview,
+            AppState::Applying => StateKind::Applying,
+            AppState::Checklist => StateKind::Checklist,
+            AppState::Table => StateKind::Table,
+            AppState::Input(_) => StateKind::Input,
+            AppState::Running(_) => StateKind::Running,
+            AppState::TextView => StateKind::TextView,
+            AppState::Confirm { .. } => StateKind::Confirm,
+            AppState::Result(_) => StateKind::Result,
+        }
+    }
+}
+
+fn can_transition(from: StateKind, to: StateKind) -> bool {
+    TRANSITIONS.iter().any(|(kind, next)| *kind == from && next.contains(&to))
+}
+
+/// A screen on the navigation stack: one that Esc can return to and that is named in the
+/// breadcrumb. Prompts, busy states and results are not screens; leaving them shows the
+/// screen they were started from.
+#[derive(Clone, PartialEq)]
+enum Screen {
+    Menu,
+    Table(TableKind),
+    Checklist(ChecklistKind),
+    FileBrowser(BrowsePurpose),
+    TextView(String),
+}
+
+impl Screen {
+    fn title(&self) -> String {
+        match self {
+            Screen::Menu => "Menu".to_string(),
+            Screen::Table(kind) => kind.title().to_string(),
+            Screen::Checklist(kind) => kind.title().trim().to_string(),
+            Screen::FileBrowser(purpose) => purpose.title().to_string(),
+            Screen::TextView(title) => title.trim().to_string(),
+        }
+    }
+}
+
+/// What the text typed on the input screen is used for.
+#[derive(Clone, PartialEq)]
+enum InputPurpose {
+    ConnectivityTargets,
+    DnsLookup,
+    AddRoute,
+    AddHostsEntry,
+    AddEnvVar,
+    EditEnvVar(String),
+    AddPathEntry,
+    /// With the current pagefile settings.
+    Pagefile(String),
+    AdminCenterOptions,
+    AgentFleet(String),
+    SystemStateTarget,
+    AdapterProperty { adapter: String, property: String },
+    AdapterVlan(String),
+    SnmpOptions,
+    ClusterNodes,
+    RdsLicensing,
+    ProxySettings,
+    WsusSettings,
+    TelemetryLevel,
+    CrashDumpType,
+    CsrSubject,
+    CsrNames { subject: String },
+    CertificateBinding { thumbprint: String, imported: String },
+    HttpsBindingPort { site: String, thumbprint: String },
+    SqlInstance,
+    SqlPassword(String),
+    CleanupCategories,
+    SharePath,
+    ShareUser(String),
+    SharePassword { path: String, user: String },
+    CopySource,
+    KitDestination,
+    ServiceCommand,
+    CopyDestination(String),
+    CopyOptions { source: String, destination: String },
+    AclFolders,
+    AddQuota,
+    AddFileScreen,
+    DedupUsage(String),
+    ExportVms,
+    /// Session ID to send a message to.
+    SessionMessage(String),
+    AddProfile,
+    /// Name for the macro just recorded.
+    SaveMacro(Vec<macros::Step>),
+    RemoteBackupSource,
+    /// Optional tags and note for the backup about to be taken.
+    BackupNote,
+    /// Tags and note of the named backup file.
+    AnnotateBackup(String),
+    /// Role type the named backup is the golden baseline for.
+    GoldenRole(String),
+    /// Server to compare with the golden baseline of the role type.
+    GoldenCompare(String),
+    ImportVmsTo(PathBuf),
+    FirewallRuleName,
+    FirewallRuleTarget(String),
+    FirewallRuleProfiles { name: String, target: String },
+    AdminPasswordSave { name: String, last_set: String },
+    AccountPolicyValue(String),
+}
+
+impl InputPurpose {
+    fn title(&self) -> String {
+        let title = match self {
+            InputPurpose::ConnectivityTargets => " Connectivity Test ",
+            InputPurpose::DnsLookup => " DNS Lookup ",
+            InputPurpose::AddRoute => " Add Persistent Route ",
+            InputPurpose::AddHostsEntry => " Add Hosts Entry ",
+            InputPurpose::AddEnvVar => " Add Machine Variable ",
+            InputPurpose::EditEnvVar(name) => return format!(" Edit {} ", name),
+            InputPurpose::AddPathEntry => " Add PATH Entry ",
+            InputPurpose::Pagefile(_) => " Pagefile Settings ",
+            InputPurpose::AdminCenterOptions => " Install Windows Admin Center ",
+            InputPurpose::AgentFleet(name) => return format!(" Install {} on Fleet ", name),
+            InputPurpose::SystemStateTarget => " System State Backup ",
+            InputPurpose::AdapterProperty { adapter, property } => return format!(" {}: {} ", adapter, property),
+            InputPurpose::AccountPolicyValue(setting) => return format!(" {} ", setting),
+            InputPurpose::AdapterVlan(adapter) => return format!(" {}: VLAN ID ", adapter),
+            InputPurpose::SessionMessage(id) => return format!(" Message to Session {} ", id),
+            InputPurpose::AddProfile => " Add Profile ",
+            InputPurpose::SaveMacro(_) => " Save Macro ",
+            InputPurpose::RemoteBackupSource => " Restore from Another Server ",
+            InputPurpose::BackupNote => " Backup: Tags & Note ",
+            InputPurpose::AnnotateBackup(backup) => return format!(" {}: Tags & Note ", backup),
+            InputPurpose::GoldenRole(backup) => return format!(" {}: Golden Baseline ", backup),
+            InputPurpose::GoldenCompare(role) => return format!(" Compare with the Golden {} Baseline ", role),
+            InputPurpose::SnmpOptions => " SNMP Service Setup ",
+            InputPurpose::ClusterNodes => " Failover Cluster Validation ",
+            InputPurpose::RdsLicensing => " RDS Licensing ",
+            InputPurpose::ProxySettings => " WinHTTP Proxy ",
+            InputPurpose::WsusSettings => " WSUS Server ",
+            InputPurpose::TelemetryLevel => " Diagnostic Data Level ",
+            InputPurpose::CrashDumpType => " Crash Dump Type ",
+            InputPurpose::CsrSubject => " Certificate Request: Subject ",
+            InputPurpose::CsrNames { .. } => " Certificate Request: Alternative Names ",
+            InputPurpose::CertificateBinding { .. } => " Bind Certificate ",
+            InputPurpose::HttpsBindingPort { site, .. } => return format!(" {}: HTTPS Binding ", site),
+            InputPurpose::SqlInstance => " Install SQL Server Express ",
+            InputPurpose::CleanupCategories => " Free Up Space ",
+            InputPurpose::SharePath => " Share Access Test ",
+            InputPurpose::ShareUser(_) => " Share Access Test: Credentials ",
+            InputPurpose::SharePassword { user, .. } => return format!(" Share Access Test: Password for {} ", user),
+            InputPurpose::CopySource => " Copy Data: Source ",
+            InputPurpose::KitDestination => " Build Provisioning Kit ",
+            InputPurpose::ServiceCommand => " Service Control ",
+            InputPurpose::CopyDestination(_) => " Copy Data: Destination ",
+            InputPurpose::CopyOptions { .. } => " Copy Data: Options ",
+            InputPurpose::AclFolders => " Back Up Folder Permissions ",
+            InputPurpose::AddQuota => " Add Quota ",
+            InputPurpose::AddFileScreen => " Add File Screen ",
+            InputPurpose::DedupUsage(volume) => return format!(" Enable Deduplication on {} ", volume),
+            InputPurpose::ExportVms => " Export VMs to the Backup Directory ",
+            InputPurpose::ImportVmsTo(_) => " Import VMs ",
+            InputPurpose::FirewallRuleName => " New Firewall Rule: Name ",
+            InputPurpose::FirewallRuleTarget(_) => " New Firewall Rule: Port or Program ",
+            InputPurpose::FirewallRuleProfiles { .. } => " New Firewall Rule: Profiles ",
+            InputPurpose::AdminPasswordSave { name, last_set } => {
+                return format!(" Rotate {} Password (last set {}) ", name, last_set)
+            }
+            InputPurpose::SqlPassword(instance) => return format!(" SQL Server Express ({}): sa Password ", instance),
+        };
+        title.to_string()
+    }
+
+    /// Screen to go back to when input is cancelled.
+    fn return_state(&self) -> AppState {
+        match self {
+            InputPurpose::AddRoute
+            | InputPurpose::AddHostsEntry
+            | InputPurpose::AddEnvVar
+            | InputPurpose::EditEnvVar(_)
+            | InputPurpose::AddPathEntry
+            | InputPurpose::AgentFleet(_)
+            | InputPurpose::SystemStateTarget
+            | InputPurpose::AdapterProperty { .. }
+            | InputPurpose::AdapterVlan(_)
+            | InputPurpose::HttpsBindingPort { .. }
+            | InputPurpose::AddQuota
+            | InputPurpose::AddFileScreen
+            | InputPurpose::DedupUsage(_)
+            | InputPurpose::AccountPolicyValue(_)
+            | InputPurpose::ExportVms
+            | InputPurpose::SessionMessage(_)
+            | InputPurpose::AnnotateBackup(_)
+            | InputPurpose::GoldenRole(_)
+            | InputPurpose::GoldenCompare(_)
+            | InputPurpose::AddProfile => AppState::Table,
+            InputPurpose::RdsLicensing
+            | InputPurpose::ProxySettings
+            | InputPurpose::WsusSettings
+            | InputPurpose::TelemetryLevel
+            | InputPurpose::CrashDumpType => AppState::Checklist,
+            _ => AppState::Menu,
+        }
+    }
+
+    /// Input that is masked on screen.
+    fn secret(&self) -> bool {
+        matches!(self, InputPurpose::SqlPassword(_) | InputPurpose::SharePassword { .. })
+    }
+
+    /// What the prompt is about, shown above it, e.g. settings that can't be pre-filled.
+    fn context(&self) -> String {
+        match self {
+            InputPurpose::Pagefile(current) => format!("Current setting: {}\n\n", current),
+            _ => String::new(),
+        }
+    }
+
+    fn prompt(&self) -> &'static str {
+        match self {
+            InputPurpose::ConnectivityTargets => {
+                "Enter one or more targets separated by spaces, e.g.\n  \
+                api.netbird.io:443 dc01.corp.local:389 10.0.0.1\n\n\
+                Each host is pinged; hosts with a port also get a TCP connect test."
+            }
+            InputPurpose::DnsLookup => {
+                "Enter a name to resolve, optionally followed by extra DNS servers, e.g.\n  \
+                intranet.corp.local 1.1.1.1 100.64.0.1\n\n\
+                The name is resolved by the system resolver, every configured server and the extra servers."
+            }
+            InputPurpose::AddRoute => {
+                "Enter destination/prefix, gateway and optional metric, e.g.\n  \
+                10.20.0.0/16 192.168.1.1 10\n\n\
+                The route is added with `route -p add` and survives reboots."
+            }
+            InputPurpose::AddHostsEntry => {
+                "Enter an address, one or more hostnames and an optional comment, e.g.\n  \
+                10.0.0.5 fileserver fileserver.corp.local # migrated share\n\n\
+                The previous hosts file is copied to the backup directory before saving."
+            }
+            InputPurpose::AddEnvVar => {
+                "Enter NAME=value for a new machine-level variable, e.g.\n  \
+                JAVA_HOME=C:\\Program Files\\Java\\jdk-21\n\n\
+                Values containing %VAR% references are stored as expandable strings."
+            }
+            InputPurpose::EditEnvVar(_) => {
+                "Edit the value below. The previous value is saved to the backup directory before it is replaced."
+            }
+            InputPurpose::AddPathEntry => {
+                "Enter a directory to append to the machine PATH, e.g.\n  \
+                C:\\Program Files\\NetBird\n\n\
+                The previous PATH is saved to the backup directory before it is replaced."
+            }
+            InputPurpose::Pagefile(_) => {
+                "Edit the setting below. Enter `auto` to let Windows manage the pagefile, or\n  \
+                D:\\pagefile.sys 4096 8192\n\n\
+                for a fixed location with initial and maximum size in MB (0 0 = system managed size).\n\
+                Takes effect after a restart."
+            }
+            InputPurpose::AdminCenterOptions => {
+                "Enter the HTTPS port, optionally followed by the thumbprint of a certificate in\n\
+                LocalMachine\\My, e.g.\n  \
+                443\n  \
+                6516 3A1F0C9E6D4B2A8F7E5C3B1A9D8E7F6A5B4C3D2E\n\n\
+                Without a thumbprint the installer generates a self-signed certificate."
+            }
+            InputPurpose::AgentFleet(_) => {
+                "Enter the hosts to install on, separated by spaces or commas, e.g.\n  \
+                srv-app01 srv-app02 srv-db01\n\n\
+                The installer is downloaded and run on each host over PowerShell remoting."
+            }
+            InputPurpose::SystemStateTarget => {
+                "Enter the backup target: a volume other than the system volume, or a share, e.g.\n  \
+                E:\n  \
+                \\\\nas01\\backups\n\n\
+                The backup runs as a background job; its progress is shown on the jobs screen."
+            }
+            InputPurpose::AdapterProperty { .. } => {
+                "Edit the value below using one of the allowed values shown in the table\n\
+                (e.g. 9014 Bytes for Jumbo Packet, Enabled/Disabled for Receive Side Scaling).\n\n\
+                The adapter restarts to apply the change, dropping connectivity for a few seconds."
+            }
+            InputPurpose::AccountPolicyValue(_) => {
+                "Enter the new value within the allowed range shown in the table (0 or \"never\" where allowed).\n\n\
+                Domain Group Policy overrides these values on domain members and domain controllers."
+            }
+            InputPurpose::SaveMacro(_) => {
+                "Enter a name for the recorded macro, e.g. PrepareFileServer.\n\n\
+                Replay it from Macros, or on another server by copying the file there and running\n  \
+                server-helper.exe --macro <file>\n\n\
+                Passwords are not recorded: the replay stops at each password prompt for you to type it.\n\
+                Esc discards the recording."
+            }
+            InputPurpose::AddProfile => {
+                "Enter a name for the profile, e.g. SiteA or Lab.\n\n\
+                It starts empty: set its backup directory and proxy with n, and its agent catalog\n\
+                and validation baseline are created the first time they are opened under it."
+            }
+            InputPurpose::RemoteBackupSource => {
+                "Enter the server whose backups to restore from, e.g.\n  \
+                FS01\n\n\
+                Its backup directories are listed over the C$ admin share, or through a PowerShell\n\
+                session when the share is closed. A share holding backups also works, e.g.\n  \
+                \\\\nas01\\ServerBackups\n\n\
+                The chosen backup is copied here and restored to this server."
+            }
+            InputPurpose::BackupNote => {
+                "Optionally tag the backup and describe it: words starting with # are tags, the rest is\n\
+                the note, e.g.\n  \
+                #pre-patch #golden Before the March cumulative update\n\n\
+                Press Enter with nothing typed to skip. Tags and notes can be added later from Backup Catalog."
+            }
+            InputPurpose::AnnotateBackup(_) => {
+                "Edit the tags and note below: words starting with # are tags, the rest is the note, e.g.\n  \
+                #golden Baseline after the file server roles were added\n\n\
+                Clear the text to remove them."
+            }
+            InputPurpose::GoldenRole(_) => {
+                "Enter the role type this backup is the golden baseline for, e.g.\n  \
+                FileServer\n\n\
+                It replaces the role type's previous golden backup. Clear the text to stop using this\n\
+                backup as a baseline."
+            }
+            InputPurpose::GoldenCompare(_) => {
+                "Enter the server to compare, e.g.\n  \
+                FS02\n\n\
+                Its installed roles and features are read over PowerShell remoting (package lists over\n\
+                SSH with key authentication). Press Enter with nothing typed to compare this server."
+            }
+            InputPurpose::SessionMessage(_) => {
+                "Enter the message to show in the session, e.g.\n  \
+                Please save your work and sign out: a restore starts at 18:00.\n\n\
+                It stays on screen for five minutes or until the user closes it."
+            }
+            InputPurpose::AdapterVlan(_) => {
+                "Enter the VLAN ID (1-4094), or 0 to remove tagging.\n\n\
+                The adapter restarts to apply the change. If you are connected through this adapter,\n\
+                make sure the switch port carries the new VLAN first."
+            }
+            InputPurpose::SnmpOptions => {
+                "Enter the community string (append :rw for read-write, default read-only) followed by\n\
+                the monitoring servers allowed to query this host, e.g.\n  \
+                monitoring zabbix01.corp.local 10.0.0.20\n\n\
+                A change plan is shown before anything is installed or changed."
+            }
+            InputPurpose::ClusterNodes => {
+                "Enter the cluster nodes, separated by spaces or commas, e.g.\n  \
+                hv-node01 hv-node02\n\n\
+                Failover Clustering is installed on nodes that lack it, then Test-Cluster validates\n\
+                them (storage tests are skipped). This can take several minutes."
+            }
+            InputPurpose::RdsLicensing => {
+                "Enter the license server(s), comma separated, and the licensing mode (user or device), e.g.\n  \
+                rdlic01.corp.local user\n\n\
+                The mode must match the CALs installed on the license server."
+            }
+            InputPurpose::ProxySettings => {
+                "Enter the proxy server and optionally a bypass list, e.g.\n  \
+                proxy.corp.local:8080\n  \
+                proxy.corp.local:8080 <local>;*.corp.local\n\n\
+                Leave empty or enter direct to remove the proxy. The connectivity tests run again afterwards."
+            }
+            InputPurpose::WsusSettings => {
+                "Enter the WSUS server URL and optionally the target group, e.g.\n  \
+                http://wsus01.corp.local:8530\n  \
+                https://wsus01.corp.local:8531 Member Servers\n\n\
+                Enter none to remove the WSUS settings. The Windows Update service is restarted afterwards."
+            }
+            InputPurpose::TelemetryLevel => {
+                "Enter the diagnostic data level:\n  \
+                0  Security  (Enterprise, Education and Server editions only)\n  \
+                1  Required\n  \
+                2  Enhanced\n  \
+                3  Optional\n\n\
+                Hardening baselines ask for 0 or 1 on servers."
+            }
+            InputPurpose::CrashDumpType => {
+                "Enter the dump type:\n  \
+                kernel     kernel memory; what most vendor support cases ask for\n  \
+                complete   all memory; needs a pagefile on the system drive of RAM + 257 MB\n  \
+                active     memory in use, without guest VM memory on Hyper-V hosts\n  \
+                automatic  kernel dump with a system-managed pagefile\n  \
+                small      256 KB minidump\n  \
+                none\n\n\
+                Takes effect after a restart."
+            }
+            InputPurpose::CsrSubject => {
+                "Enter the certificate subject, e.g.\n  \
+                CN=web01.corp.local, O=Contoso Ltd, L=Athens, C=GR\n\n\
+                A 2048-bit RSA key is generated in the machine store; only the request leaves this server."
+            }
+            InputPurpose::CsrNames { .. } => {
+                "Enter the subject alternative names (DNS names or IP addresses), separated by spaces, e.g.\n  \
+                web01.corp.local web01 www.contoso.com 10.0.0.15\n\n\
+                The request is saved as a .req file in the backup folder."
+            }
+            InputPurpose::CertificateBinding { .. } => {
+                "The certificate was imported. Bind it to an IIS site (HTTPS port defaults to 443) or to WinRM, e.g.\n  \
+                iis Default Web Site 443\n  \
+                winrm\n\n\
+                Esc leaves the certificate imported without a binding."
+            }
+            InputPurpose::HttpsBindingPort { .. } => {
+                "Enter the HTTPS port for the new binding (all IP addresses, no host name).\n\n\
+                An existing HTTPS binding on this port keeps its place and gets the selected certificate."
+            }
+            InputPurpose::SqlInstance => {
+                "Enter the instance name, e.g.\n  \
+                SQLEXPRESS\n\n\
+                SQL Server 2022 Express is installed with mixed-mode authentication and TCP enabled;\n\
+                the sa password is asked for next."
+            }
+            InputPurpose::CleanupCategories => {
+                "Enter the categories to clean, separated by spaces:\n  \
+                temp        Windows and user temp directories\n  \
+                updates     old Windows Update downloads\n  \
+                components  superseded components (DISM component cleanup)\n  \
+                cbs         CBS servicing logs (only when asked for)\n\n\
+                A change plan with the current size of each category is shown first."
+            }
+            InputPurpose::SharePath => {
+                "Enter the UNC path to test, e.g.\n  \
+                \\\\nas01\\backups\\srv-app01\n\n\
+                The share is listed and a 1 MB test file is written and deleted, to check it before\n\
+                using it as a backup destination."
+            }
+            InputPurpose::ShareUser(_) => {
+                "Enter the user to connect as, e.g.\n  \
+                CORP\\svc-backup\n  \
+                nas01\\backup\n\n\
+                Leave empty to test with the current user."
+            }
+            InputPurpose::SharePassword { .. } => {
+                "Enter the password. The connection is removed again after the test."
+            }
+            InputPurpose::ServiceCommand => {
+                "Enter start, stop, restart or status and the service name, e.g.\n  \
+                restart nginx\n  \
+                status Spooler\n\n\
+                The service's status is shown afterwards."
+            }
+            InputPurpose::KitDestination => {
+                "Enter the folder to build the kit in, e.g. the USB drive:\n  \
+                E:\\\n\n\
+                The kit holds this program in portable and offline mode, the download cache, the agent\n\
+                catalog, the validation baseline and the newest role backup, plus a ZIP of it all."
+            }
+            InputPurpose::CopySource => {
+                "Enter the folder to copy from, e.g.\n  \
+                D:\\Shares\\Finance\n  \
+                \\\\oldfs01\\d$\\Shares\\Finance"
+            }
+            InputPurpose::CopyDestination(_) => {
+                "Enter the folder to copy to, e.g.\n  \
+                E:\\Shares\\Finance\n\n\
+                It is created if it doesn't exist."
+            }
+            InputPurpose::CopyOptions { .. } => {
+                "Choose the mode and whether to copy permissions:\n  \
+                copy    copy new and changed files, keep everything else in the destination\n  \
+                mirror  make the destination identical, DELETING files that are not in the source\n  \
+                acls    also copy NTFS permissions, owner and auditing (noacls to skip)\n\n\
+                The robocopy command is shown for confirmation and then runs as a background job."
+            }
+            InputPurpose::AclFolders => {
+                "Enter the folders whose permissions to save, separated by semicolons, e.g.\n  \
+                D:\\Shares\\Finance; D:\\Shares\\HR\n\n\
+                Each folder tree is saved with icacls /save into the backup directory, together with\n\
+                a .acls manifest used by Restore Folder Permissions."
+            }
+            InputPurpose::AddQuota => {
+                "Enter the folder and the limit, optionally followed by soft, e.g.\n  \
+                D:\\Shares\\Finance; 10GB\n  \
+                D:\\Home\\jdoe; 2GB soft\n\n\
+                A hard quota blocks writes at the limit; a soft quota only reports it."
+            }
+            InputPurpose::AddFileScreen => {
+                "Enter the folder, the file groups to block and optionally passive, e.g.\n  \
+                D:\\Shares\\Public; Executable Files, Audio and Video Files\n  \
+                D:\\Shares\\Finance; Compressed Files; passive\n\n\
+                Built-in groups include Audio and Video Files, Backup Files, Compressed Files, E-mail Files,\n\
+                Executable Files, Image Files, Office Files, System Files, Temporary Files and Web Page Files.\n\
+                A passive screen only reports matching files instead of blocking them."
+            }
+            InputPurpose::DedupUsage(_) => {
+                "Enter the usage type:\n  \
+                Default  general purpose file servers\n  \
+                HyperV   VDI and Hyper-V storage (optimizes open VHDX files)\n  \
+                Backup   virtualized backup targets such as DPM\n\n\
+                A first optimization job starts right away; savings show up as it progresses."
+            }
+            InputPurpose::ExportVms => {
+                "Enter the VMs to export, separated by commas, e.g.\n  \
+                app01, sql01\n\n\
+                Running VMs are exported live. The export runs as a background job into the backup\n\
+                directory, with a .vms manifest used by Import Hyper-V VMs."
+            }
+            InputPurpose::ImportVmsTo(_) => {
+                "Enter the folder to place the VM files in, optionally followed by a virtual switch, e.g.\n  \
+                D:\\Hyper-V\n  \
+                D:\\Hyper-V External\n\n\
+                Each VM is copied into its own subfolder. Adapters whose switch doesn't exist on this host\n\
+                are connected to the given switch, or left disconnected. A plan is shown first."
+            }
+            InputPurpose::FirewallRuleName => {
+                "Enter a name for the inbound rule, e.g.\n  \
+                Monitoring Agent (TCP 10050)\n\n\
+                The name is shown in the firewall console and must be unique."
+            }
+            InputPurpose::FirewallRuleTarget(_) => {
+                "Enter the protocol and local port(s), or the full path of a program, e.g.\n  \
+                tcp 8080\n  \
+                tcp 8443, 9000-9010\n  \
+                udp 161\n  \
+                C:\\Program Files\\Zabbix Agent\\zabbix_agentd.exe\n\n\
+                A program rule allows any port the program listens on."
+            }
+            InputPurpose::FirewallRuleProfiles { .. } => {
+                "Enter the network profiles the rule applies to: domain, private, public, or any.\n\n\
+                Servers on a domain network use the domain profile; avoid public unless needed."
+            }
+            InputPurpose::AdminPasswordSave { .. } => {
+                "Save an encrypted copy of the new password to the backup directory? (yes/no)\n\n\
+                The copy is encrypted for the current user on this server only. \
+                Either way the password is shown once on the next screen."
+            }
+            InputPurpose::SqlPassword(_) => {
+                "Enter the sa password: at least 8 characters from three of upper case, lower case, digits and symbols.\n\n\
+                Local administrators are also sysadmins, so a lost sa password can be reset.\n\
+                Setup downloads about 300 MB and takes several minutes."
+            }
+        }
+    }
+}
+
+/// Long-running operations that show a "please wait" screen while they run.
+#[derive(Clone, PartialEq)]
+enum Task {
+    ConnectivityTest(String),
+    InstallAgent(String),
+    InstallAgentFleet { agent: String, hosts: Vec<String> },
+    ValidateCluster(Vec<String>),
+    GpUpdate,
+    ShareTest { path: String, credentials: Option<(String, String)> },
+    BackupAcls(String),
+    BuildKit(PathBuf),
+    DscExport,
+    Service { action: platform::ServiceAction, name: String },
+}
+
+impl Task {
+    fn title(&self) -> &'static str {
+        match self {
+            Task::ConnectivityTest(_) => " Testing Connectivity ",
+            Task::InstallAgent(_) => " Installing Agent ",
+            Task::InstallAgentFleet { .. } => " Installing Agent on Fleet ",
+            Task::ValidateCluster(_) => " Validating Cluster Nodes ",
+            Task::GpUpdate => " Updating Group Policy ",
+            Task::ShareTest { .. } => " Testing Share Access ",
+            Task::BackupAcls(_) => " Saving Folder Permissions ",
+            Task::BuildKit(_) => " Building Provisioning Kit ",
+            Task::DscExport => " Exporting DSC Configuration ",
+            Task::Service { .. } => " Service Control ",
+        }
+    }
+
+    fn run(&self) -> OperationOutcome {
+        match self {
+            Task::ConnectivityTest(targets) => network::connectivity_test(targets).into(),
+            Task::InstallAgent(name) => agents::install_local(name),
+            Task::InstallAgentFleet { agent, hosts } => agents::install_fleet(agent, hosts),
+            Task::ValidateCluster(nodes) => cluster::validate(nodes).into(),
+            Task::GpUpdate => policy::gpupdate().into(),
+            Task::ShareTest { path, credentials } => network::share_test(path, credentials.as_ref()).into(),
+            Task::BackupAcls(folders) => acls::backup(folders),
+            Task::BuildKit(destination) => kit::build(destination),
+            Task::DscExport => dsc::export(),
+            Task::Service { action, name } => platform::current().service(name, *action),
+        }
+    }
+}
+
+/// Action to run once the user answers "yes" on the confirmation screen.
+#[derive(Clone, PartialEq)]
+enum ConfirmAction {
+    Remediate(usize),
+    RemoveRoute { prefix: String, next_hop: String },
+    RemoveHostsLine(Vec<String>),
+    DeleteEnvVar(String),
+    RemovePathEntry(usize, String),
+    KillProcess { pid: String, name: String },
+    /// Accept the license agreement of a catalog agent, then continue to its local or fleet install.
+    AcceptEula { agent: String, fleet: bool },
+    DisconnectSession(String),
+    LogOffSession(String),
+    StopVm(String),
+    AddFirewallRule(firewall::InboundRule),
+    RotateAdminPassword { save: bool },
+    SetAccountPolicy { setting: String, argument: String },
+    /// Removes one cached download by hash, or the whole cache.
+    PurgeCache(Option<String>),
+    DeleteMacro(String),
+    SetHighPerformance,
+    InstallAgent(String),
+    ChangeFeature { name: String, install: bool },
+    SetupDocker,
+    FixSpooler,
+    HealthRepair,
+    WingetConfigure(PathBuf),
+    Migrate(robocopy::Migration),
+    Quit,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ChecklistKind {
+    Telemetry,
+    DeviceGuard,
+    CrashDump,
+    SecurityAudit,
+    Hardening,
+    NetBirdConflicts,
+    Rds,
+    DotNet,
+    Validation,
+    DcDiag,
+    Replication,
+    Proxy,
+    Wsus,
+}
+
+impl ChecklistKind {
+    fn title(&self) -> &'static str {
+        match self {
+            ChecklistKind::SecurityAudit => " Security Audit ",
+            ChecklistKind::Hardening => " Hardening Checklist (CIS subset) ",
+            ChecklistKind::Telemetry => " Diagnostic Data & Consumer Experiences ",
+            ChecklistKind::DeviceGuard => " Credential Guard, HVCI & Secure Boot ",
+            ChecklistKind::CrashDump => " Crash Dump Settings ",
+            ChecklistKind::NetBirdConflicts => " NetBird Route/DNS Conflicts ",
+            ChecklistKind::Rds => " Remote Desktop Services ",
+            ChecklistKind::DotNet => " .NET Runtimes ",
+            ChecklistKind::Validation => " Post-Provision Validation ",
+            ChecklistKind::DcDiag => " Domain Controller Diagnostics (dcdiag) ",
+            ChecklistKind::Replication => " AD Replication Summary (repadmin) ",
+            ChecklistKind::Proxy => " WinHTTP Proxy & Outbound Connectivity ",
+            ChecklistKind::Wsus => " WSUS Client ",
+        }
+    }
+
+    fn evaluate(&self) -> Result<Vec<CheckItem>, String> {
+        match self {
+            ChecklistKind::SecurityAudit => security::audit_insecure_protocols(),
+            ChecklistKind::Hardening => security::hardening_checklist(),
+            ChecklistKind::Telemetry => telemetry::checks(),
+            ChecklistKind::DeviceGuard => deviceguard::checks(),
+            ChecklistKind::CrashDump => crashdump::checks(),
+            ChecklistKind::NetBirdConflicts => network::netbird_conflicts(),
+            ChecklistKind::Rds => rds::rds_checks(),
+            ChecklistKind::DotNet => dotnet::runtime_checks(),
+            ChecklistKind::Validation => validation::run_checks(),
+            ChecklistKind::DcDiag => domain::dcdiag_checks(),
+            ChecklistKind::Replication => domain::replication_checks(),
+            ChecklistKind::Proxy => proxy::checks(),
+            ChecklistKind::Wsus => wsus::checks(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum CheckStatus {
+    Pass,
+    Fail,
+    Unknown,
+}
+
+/// PowerShell that fixes a failed check, plus a human-readable description of the change.
+#[derive(Clone)]
+struct Remediation {
+    summary: String,
+    script: String,
+}
+
+#[derive(Clone)]
+struct CheckItem {
+    name: String,
+    status: CheckStatus,
+    detail: String,
+    remediation: Option<Remediation>,
+    /// Manual fix to suggest when there is no automatic remediation.
+    advice: Option<String>,
 }
 
 struct App {
     state: AppState,
     menu_state: ListState,
-    menu_items: Vec<&'static str>,
+    menu_items: Vec<(&'static str, MenuAction)>,
+    /// Progress lines from the running operation.
     log_messages: Vec<String>,
+    /// ID of the operation running on a worker thread, and the last ID handed out.
+    operation: Option<u64>,
+    last_operation: u64,
+    /// Latest progress of the running operation's download: name, bytes received, total.
+    download: Option<(String, u64, Option<u64>)>,
+    /// Messages for the main loop, from the terminal and from operations on worker threads.
+    inbox: Receiver<Message>,
+    outbox: Sender<Message>,
     // File browser
     current_dir: PathBuf,
     dir_entries: Vec<PathBuf>,
     file_list_state: ListState,
-    selected_file: Option<PathBuf>,
+    // Checklist (security audit, hardening)
+    checklist_kind: ChecklistKind,
+    checklist: Vec<CheckItem>,
+    checklist_state: ListState,
+    // Table screens (listening ports, ...)
+    table: TableView,
+    // Text input screen
+    input: String,
+    // Text viewer (task history, ...)
+    text_title: String,
+    text: String,
+    text_scroll: u16,
+    /// Job whose log is shown, refreshed while it runs.
+    text_job: Option<usize>,
+    // Change plan shown for approval before a composite operation
+    plan: Option<Plan>,
+    // Snapshot registry and feature state before risky operations
+    checkpoint: bool,
+    /// Block actions that need the internet (air-gapped sites).
+    offline: bool,
+    /// Last background reading of the host, for the menu badges.
+    host_status: Option<status::HostStatus>,
+    /// Steps of the action under way: from choosing a menu item until the menu shows again.
+    action: Vec<macros::Step>,
+    /// The last completed action, replayed with `.` on the menu.
+    last_action: Vec<macros::Step>,
+    /// Steps recorded since `m` was pressed on the menu.
+    recording: Option<Vec<macros::Step>>,
+    /// Steps still to replay, applied while no operation is running.
+    replay: VecDeque<macros::Step>,
+    /// Screens from the menu to the current one; Esc returns to the one before.
+    screens: Vec<Screen>,
+    /// Show the host status, jobs and log in a pane on the right (Tab).
+    split: bool,
+    /// When `host_status` was read, in seconds since the epoch.
+    status_read: Option<u64>,
+    /// Notifications for background events, in the bottom-right corner.
+    toasts: toast::Toasts,
+    /// Number of the last finished job that was notified.
+    jobs_notified: usize,
+    /// Idle time before sensitive screens are blanked, and the PIN that resumes them.
+    idle_lock: Option<Duration>,
+    lock_pin: Option<String>,
+    last_key: Instant,
+    locked: bool,
+    /// PIN typed on the lock screen, and the outcome of the last attempt.
+    pin_input: String,
+    lock_status: String,
+    /// The result on screen shows a secret (a generated password).
+    secret_result: bool,
+    /// Backup selected in the restore file browser, and what the preview pane shows about it.
+    preview_path: Option<PathBuf>,
+    preview: String,
 }
 
 impl App {
     fn new() -> Self {
         let mut menu_state = ListState::default();
         menu_state.select(Some(0));
+        let (outbox, inbox) = mpsc::channel();
         
-        let default_dir = dirs::document_dir()
-            .unwrap_or_else(|| PathBuf::from("C:\\"))
-            .join("ServerBackups");
+        let default_dir = backup_dir();
         
         Self {
             state: AppState::Menu,
             menu_state,
-            menu_items: vec![
-                "Check Winget Status",
-                "Install Winget",
-                "Check NetBird Status",
-                "Install NetBird",
-                "Backup Server Roles & Features",
-                "Restore Server Roles & Features",
-                "Exit",
-            ],
+            menu_items: match platform::current().os() {
+                platform::Os::Windows => vec![
+                    ("Check Winget Status", MenuAction::CheckWinget),
+                    ("Install Winget", MenuAction::InstallWinget),
+                    ("Apply WinGet Configuration (winget configure)", MenuAction::WingetConfigure),
+                    #[cfg(feature = "netbird")]
+                    ("Check NetBird Status", MenuAction::CheckNetBird),
+                    #[cfg(feature = "netbird")]
+                    ("Install NetBird", MenuAction::InstallNetBird),
+                    #[cfg(feature = "netbird")]
+                    ("Check NetBird Route/DNS Conflicts", MenuAction::NetBirdConflicts),
+                    ("Install Windows Admin Center", MenuAction::InstallAdminCenter),
+                    ("Agent Catalog (Monitoring/Backup/EDR)", MenuAction::Agents),
+                    (".NET Runtimes (Catalog Requirements)", MenuAction::DotNet),
+                    ("Visual C++ Redistributables (2015-2022)", MenuAction::VcRedist),
+                    ("Install SQL Server Express", MenuAction::SqlExpress),
+                    ("SNMP Service Setup", MenuAction::Snmp),
+                    #[cfg(feature = "windows-roles")]
+                    ("Backup Server Roles & Features", MenuAction::BackupRoles),
+                    #[cfg(feature = "windows-roles")]
+                    ("Restore Server Roles & Features", MenuAction::RestoreRoles),
+                    #[cfg(feature = "windows-roles")]
+                    ("Restore from Another Server's Backup", MenuAction::RemoteRestore),
+                    #[cfg(feature = "windows-roles")]
+                    ("Rollback Last Restore", MenuAction::RollbackRestore),
+                    ("Export DSC Configuration (Declarative)", MenuAction::DscExport),
+                    ("Copy Data (Robocopy Migration)", MenuAction::CopyData),
+                    ("Back Up Folder Permissions (icacls)", MenuAction::BackupAcls),
+                    ("Restore Folder Permissions", MenuAction::RestoreAcls),
+                    ("Quotas & File Screens (FSRM)", MenuAction::Fsrm),
+                    ("Data Deduplication", MenuAction::Dedup),
+                    ("System State Backup (wbadmin)", MenuAction::SystemStateBackup),
+                    ("Failover Cluster Validation", MenuAction::ClusterValidation),
+                    ("Domain Controller Diagnostics (dcdiag)", MenuAction::DcDiag),
+                    ("AD Replication Summary (repadmin)", MenuAction::Replication),
+                    ("Group Policy Update (gpupdate /force)", MenuAction::GpUpdate),
+                    ("Group Policy Result (Applied GPOs & Groups)", MenuAction::GpResult),
+                    ("Certificate Request (CSR) Wizard", MenuAction::CertificateRequest),
+                    ("Complete Certificate Request (Import & Bind)", MenuAction::CompleteCertificateRequest),
+                    ("RDS Licensing & Sessions", MenuAction::Rds),
+                    ("Docker Host Setup (Windows Containers)", MenuAction::DockerHost),
+                    ("Enable WSL & Install a Distribution", MenuAction::Wsl),
+                    ("Hyper-V Virtual Machines", MenuAction::VirtualMachines),
+                    ("Import Hyper-V VMs (from Backup)", MenuAction::ImportVms),
+                    #[cfg(feature = "windows-roles")]
+                    ("View Last Backup", MenuAction::ViewLastBackup),
+                    ("Backup Catalog (Tags & Notes)", MenuAction::BackupCatalog),
+                    ("Golden Baselines (Compare Servers)", MenuAction::GoldenBaselines),
+                    ("Offsite Backup Uploads (SFTP/S3)", MenuAction::OffsiteBackups),
+                    #[cfg(feature = "windows-roles")]
+                    ("Browse Roles & Features", MenuAction::Features),
+                    ("Background Jobs", MenuAction::Jobs),
+                    ("Security Audit (SMB1, TLS, LLMNR, NTLM)", MenuAction::SecurityAudit),
+                    ("TLS Protocols & Ciphers (Schannel)", MenuAction::Schannel),
+                    ("Hardening Checklist (CIS subset)", MenuAction::Hardening),
+                    ("Diagnostic Data (Telemetry) Level", MenuAction::Telemetry),
+                    ("Credential Guard & VBS Status", MenuAction::DeviceGuard),
+                    ("Enable Recommended Audit Policy (auditpol)", MenuAction::AuditPolicy),
+                    ("Password & Lockout Policy (net accounts)", MenuAction::AccountPolicy),
+                    ("Rotate Local Administrator Password", MenuAction::RotateAdminPassword),
+                    ("Deploy Sysmon", MenuAction::Sysmon),
+                    ("Listening Ports", MenuAction::ListeningPorts),
+                    ("Connectivity Test (Ping/Port)", MenuAction::ConnectivityTest),
+                    ("Share Access Test (UNC Path)", MenuAction::ShareTest),
+                    ("New Firewall Rule (Inbound)", MenuAction::FirewallRule),
+                    ("DNS Lookup & Cache Flush", MenuAction::DnsLookup),
+                    ("WinHTTP Proxy & Connectivity Check", MenuAction::Proxy),
+                    ("WSUS Client (Update Server & Check-in)", MenuAction::Wsus),
+                    ("Static Routes", MenuAction::Routes),
+                    ("Network Adapters (VLAN, Jumbo Frames, RSS)", MenuAction::NetworkAdapters),
+                    ("IIS Sites & Bindings", MenuAction::IisSites),
+                    ("Hosts File Editor", MenuAction::HostsFile),
+                    ("Environment Variables (Machine)", MenuAction::Environment),
+                    ("Scheduled Tasks", MenuAction::ScheduledTasks),
+                    ("Processes", MenuAction::Processes),
+                    ("Remote Desktop Sessions", MenuAction::RdpSessions),
+                    ("Service Control (Start/Stop/Restart)", MenuAction::ServiceControl),
+                    ("Uptime & Last Boot Reason", MenuAction::Uptime),
+                    ("Post-Provision Validation", MenuAction::Validation),
+                    ("System Health Repair (DISM + SFC)", MenuAction::HealthRepair),
+                    ("Fix Print Spooler (Clear Stuck Jobs)", MenuAction::FixSpooler),
+                    ("Free Up Space (Disk Cleanup)", MenuAction::FreeUpSpace),
+                    ("Clean Temp Files (Installer Leftovers)", MenuAction::CleanTemp),
+                    ("Download Cache (Installers)", MenuAction::DownloadCache),
+                    ("Build Provisioning Kit (USB)", MenuAction::ProvisioningKit),
+                    ("Power Plan (High Performance)", MenuAction::PowerPlan),
+                    ("Pagefile Size & Location", MenuAction::Pagefile),
+                    ("Crash Dump Settings (Vendor Support)", MenuAction::CrashDump),
+                    ("History", MenuAction::History),
+                    ("Checkpoint Before Changes (on/off)", MenuAction::Checkpoints),
+                    ("Export Mode (write changes to a .ps1)", MenuAction::ExportMode),
+                    ("Offline Mode (block downloads) (on/off)", MenuAction::Offline),
+                    ("Profiles (Per-Site Defaults)", MenuAction::Profiles),
+                    ("Macros (Record & Replay)", MenuAction::Macros),
+                    ("Exit", MenuAction::Exit),
+                ],
+                platform::Os::Linux => platform::LINUX_MENU.to_vec(),
+            },
             log_messages: Vec::new(),
+            operation: None,
+            last_operation: 0,
+            download: None,
+            inbox,
+            outbox,
             current_dir: default_dir,
             dir_entries: Vec::new(),
             file_list_state: ListState::default(),
-            selected_file: None,
+            checklist_kind: ChecklistKind::SecurityAudit,
+            checklist: Vec::new(),
+            checklist_state: ListState::default(),
+            table: TableView::new(TableKind::ListeningPorts),
+            input: String::new(),
+            text_title: String::new(),
+            text: String::new(),
+            text_scroll: 0,
+            text_job: None,
+            plan: None,
+            checkpoint: false,
+            offline: std::env::args().any(|a| a == "--offline") || offline_marker().exists(),
+            host_status: None,
+            action: Vec::new(),
+            last_action: Vec::new(),
+            recording: None,
+            replay: VecDeque::new(),
+            screens: vec![Screen::Menu],
+            split: false,
+            status_read: None,
+            toasts: toast::Toasts::default(),
+            jobs_notified: 0,
+            idle_lock: None,
+            lock_pin: None,
+            last_key: Instant::now(),
+            locked: false,
+            pin_input: String::new(),
+            lock_status: String::new(),
+            secret_result: false,
+            preview_path: None,
+            preview: String::new(),
         }
     }
 
@@ -102,16 +1291,11 @@ impl App {
         self.menu_state.select(Some(i));
     }
 
-    fn add_log(&mut self, msg: impl Into<String>) {
-        self.log_messages.push(msg.into());
-    }
-
-    fn check_winget_status(&self) -> (bool, String) {
-        match Command::new("winget").arg("--version").output() {
+    fn check_winget_status() -> (bool, String) {
+        match exec::run("winget", &["--version"]) {
             Ok(output) => {
-                if output.status.success() {
-                    let version = String::from_utf8_lossy(&output.stdout);
-                    (true, format!("Winget is installed: {}", version.trim()))
+                if output.success {
+                    (true, format!("Winget is installed: {}", output.stdout.trim()))
                 } else {
                     (false, "Winget is not working properly".to_string())
                 }
@@ -120,184 +1304,162 @@ impl App {
         }
     }
 
-    fn install_winget(&mut self) -> (bool, String) {
-        self.log_messages.clear();
-        self.add_log("Starting Winget installation for Windows Server...");
+    fn install_winget() -> OperationOutcome {
+        message::log("Starting Winget installation for Windows Server...");
 
         // Create temp directory
         let temp_dir = std::env::temp_dir().join("winget_install");
         if let Err(e) = std::fs::create_dir_all(&temp_dir) {
-            return (false, format!("Failed to create temp directory: {}", e));
+            return OperationOutcome::failed(OperationError::Io { path: temp_dir, reason: e.to_string() });
         }
 
-        self.add_log("Downloading required packages...");
-
-        // URLs for required components
+        let vclibs_path = temp_dir.join("Microsoft.VCLibs.x64.14.00.Desktop.appx");
+        let xaml_nupkg_path = temp_dir.join("microsoft.ui.xaml.2.8.6.nupkg");
+        let winget_path = temp_dir.join("Microsoft.DesktopAppInstaller.msixbundle");
+        let license_path = temp_dir.join("license.xml");
         let downloads = [
+            ("VCLibs", "https://aka.ms/Microsoft.VCLibs.x64.14.00.Desktop.appx", vclibs_path.clone()),
+            ("UI.Xaml", "https://www.nuget.org/api/v2/package/Microsoft.UI.Xaml/2.8.6", xaml_nupkg_path.clone()),
             (
-                "Microsoft.VCLibs.x64.14.00.Desktop.appx",
-                "https://aka.ms/Microsoft.VCLibs.x64.14.00.Desktop.appx"
+                "Winget",
+                "https://github.com/microsoft/winget-cli/releases/latest/download/Microsoft.DesktopAppInstaller_8wekyb3d8bbwe.msixbundle",
+                winget_path.clone(),
             ),
             (
-                "Microsoft.UI.Xaml.2.8.x64.appx",
-                "https://github.com/nickel-org/nickel.rs/releases/download/0.0.0/Microsoft.UI.Xaml.2.8.x64.appx"
+                "license",
+                "https://github.com/microsoft/winget-cli/releases/latest/download/b]_License1.xml",
+                license_path.clone(),
             ),
         ];
 
-        // Download VCLibs
-        self.add_log("Downloading Microsoft.VCLibs...");
-        let vclibs_path = temp_dir.join("Microsoft.VCLibs.x64.14.00.Desktop.appx");
-        
-        let download_result = Command::new("powershell")
-            .args([
-                "-Command",
-                &format!(
-                    "Invoke-WebRequest -Uri '{}' -OutFile '{}'",
-                    downloads[0].1,
-                    vclibs_path.display()
-                )
-            ])
-            .output();
-
-        if let Err(e) = download_result {
-            return (false, format!("Failed to download VCLibs: {}", e));
-        }
-
-        // Download UI.Xaml from NuGet
-        self.add_log("Downloading Microsoft.UI.Xaml...");
-        let xaml_nupkg_path = temp_dir.join("microsoft.ui.xaml.2.8.6.nupkg");
-        let xaml_result = Command::new("powershell")
-            .args([
-                "-Command",
-                &format!(
-                    "Invoke-WebRequest -Uri 'https://www.nuget.org/api/v2/package/Microsoft.UI.Xaml/2.8.6' -OutFile '{}'",
-                    xaml_nupkg_path.display()
-                )
-            ])
-            .output();
-
-        if let Err(e) = xaml_result {
-            return (false, format!("Failed to download UI.Xaml: {}", e));
+        message::log("Downloading VCLibs, UI.Xaml, Winget and its license...");
+        let files: Vec<(&str, PathBuf)> = downloads.iter().map(|(_, url, path)| (*url, path.clone())).collect();
+        let results = download::fetch_all(&files, 3);
+        for ((name, _, _), result) in downloads.iter().zip(results) {
+            match result {
+                Ok(_) => message::log(format!("Downloaded {}", name)),
+                // The license is optional; Add-AppxPackage installs without it
+                Err(e) if *name == "license" => message::log(format!("Warning: license download failed: {}", e)),
+                Err(reason) => return OperationOutcome::failed(OperationError::Download { item: name.to_string(), reason }),
+            }
         }
 
         // Extract UI.Xaml
-        self.add_log("Extracting Microsoft.UI.Xaml...");
+        message::log("Extracting Microsoft.UI.Xaml...");
         let xaml_extract_dir = temp_dir.join("xaml_extract");
         let _ = std::fs::create_dir_all(&xaml_extract_dir);
         
-        let extract_result = Command::new("powershell")
-            .args([
-                "-Command",
-                &format!(
-                    "Expand-Archive -Path '{}' -DestinationPath '{}' -Force",
-                    xaml_nupkg_path.display(),
-                    xaml_extract_dir.display()
-                )
-            ])
-            .output();
+        let extract_result = exec::change(&format!(
+            "Expand-Archive -Path '{}' -DestinationPath '{}' -Force",
+            xaml_nupkg_path.display(),
+            xaml_extract_dir.display()
+        ));
 
         if let Err(e) = extract_result {
-            return (false, format!("Failed to extract UI.Xaml: {}", e));
+            return OperationOutcome::failed(OperationError::Command {
+                action: "Extracting UI.Xaml".to_string(),
+                detail: e.to_string(),
+            });
         }
 
         let xaml_appx_path = xaml_extract_dir.join("tools").join("AppX").join("x64").join("Release").join("Microsoft.UI.Xaml.2.8.appx");
 
-        // Download Winget
-        self.add_log("Downloading Winget...");
-        let winget_path = temp_dir.join("Microsoft.DesktopAppInstaller.msixbundle");
-        let winget_result = Command::new("powershell")
-            .args([
-                "-Command",
-                &format!(
-                    "Invoke-WebRequest -Uri 'https://github.com/microsoft/winget-cli/releases/latest/download/Microsoft.DesktopAppInstaller_8wekyb3d8bbwe.msixbundle' -OutFile '{}'",
-                    winget_path.display()
-                )
-            ])
-            .output();
+        // Per-user app packages only reach the user who installed them, so on a session host
+        // winget and its dependencies are provisioned for every user instead
+        let session_host = rds::is_session_host();
+        let winget_install = if session_host {
+            message::log("This is an RD Session Host: provisioning Winget for all users...");
+            let dependencies: Vec<String> = [&vclibs_path, &xaml_appx_path]
+                .iter()
+                .filter(|path| path.exists())
+                .map(|path| exec::quote(&path.to_string_lossy()))
+                .collect();
+            let dependencies = match dependencies.is_empty() {
+                true => String::new(),
+                false => format!(" -DependencyPackagePath {}", dependencies.join(",")),
+            };
+            let license = if license_path.exists() {
+                format!("-LicensePath {}", exec::quote(&license_path.to_string_lossy()))
+            } else {
+                "-SkipLicense".to_string()
+            };
+            exec::change(&format!(
+                "Add-AppxProvisionedPackage -Online -PackagePath {}{} {} -ErrorAction Stop | Out-Null\n\
+                Add-AppxPackage -RegisterByFamilyName -MainPackage Microsoft.DesktopAppInstaller_8wekyb3d8bbwe -ErrorAction SilentlyContinue",
+                exec::quote(&winget_path.to_string_lossy()),
+                dependencies,
+                license
+            ))
+        } else {
+            // Install packages
+            message::log("Installing Microsoft.VCLibs...");
+            let vclibs_install = exec::change(&format!("Add-AppxPackage -Path '{}'", vclibs_path.display()));
 
-        if let Err(e) = winget_result {
-            return (false, format!("Failed to download Winget: {}", e));
-        }
+            if let Err(e) = vclibs_install {
+                message::log(format!("Warning: VCLibs install issue: {}", e));
+            }
 
-        // Download license
-        self.add_log("Downloading license...");
-        let license_path = temp_dir.join("license.xml");
-        let _license_result = Command::new("powershell")
-            .args([
-                "-Command",
-                &format!(
-                    "Invoke-WebRequest -Uri 'https://github.com/microsoft/winget-cli/releases/latest/download/b]_License1.xml' -OutFile '{}'",
-                    license_path.display()
-                )
-            ])
-            .output();
-
-        // Install packages
-        self.add_log("Installing Microsoft.VCLibs...");
-        let vclibs_install = Command::new("powershell")
-            .args([
-                "-Command",
-                &format!("Add-AppxPackage -Path '{}'", vclibs_path.display())
-            ])
-            .output();
-
-        if let Err(e) = vclibs_install {
-            self.add_log(format!("Warning: VCLibs install issue: {}", e));
-        }
-
-        self.add_log("Installing Microsoft.UI.Xaml...");
-        if xaml_appx_path.exists() {
-            let xaml_install = Command::new("powershell")
-                .args([
-                    "-Command",
-                    &format!("Add-AppxPackage -Path '{}'", xaml_appx_path.display())
-                ])
-                .output();
-
-            if let Err(e) = xaml_install {
-                self.add_log(format!("Warning: UI.Xaml install issue: {}", e));
-            }
-        }
-
-        self.add_log("Installing Winget...");
-        let winget_install = Command::new("powershell")
-            .args([
-                "-Command",
-                &format!(
-                    "Add-AppxPackage -Path '{}'",
-                    winget_path.display()
-                )
-            ])
-            .output();
+            message::log("Installing Microsoft.UI.Xaml...");
+            if xaml_appx_path.exists() {
+                let xaml_install = exec::change(&format!("Add-AppxPackage -Path '{}'", xaml_appx_path.display()));
+
+                if let Err(e) = xaml_install {
+                    message::log(format!("Warning: UI.Xaml install issue: {}", e));
+                }
+            }
+
+            message::log("Installing Winget...");
+            exec::change(&format!(
+                "Add-AppxPackage -Path '{}'",
+                winget_path.display()
+            ))
+        };
+        let provisioned = if session_host {
+            "\n\nProvisioned for all users because this is an RD Session Host: per-user app packages \
+            would only reach the administrator who installed them. Signed-in users get Winget at their next sign-in."
+        } else {
+            ""
+        };
 
         match winget_install {
             Ok(output) => {
-                if output.status.success() {
-                    self.add_log("Installation completed!");
+                if output.success {
+                    message::log("Installation completed!");
+                    match cleanup::remove_installer_files(&temp_dir) {
+                        Ok(()) => message::log("Removed the downloaded packages from the temp directory"),
+                        Err(e) => message::log(format!("Warning: {}", e)),
+                    }
                     
                     // Verify installation
                     std::thread::sleep(Duration::from_secs(2));
-                    let (installed, msg) = self.check_winget_status();
+                    let (installed, msg) = Self::check_winget_status();
                     if installed {
-                        (true, format!("Winget installed successfully!\n{}", msg))
+                        OperationOutcome::Success(format!("Winget installed successfully!\n{}{}", msg, provisioned))
                     } else {
-                        (true, "Installation completed. You may need to restart your terminal or system.".to_string())
+                        OperationOutcome::Success(format!(
+                            "Installation completed. You may need to restart your terminal or system.{}",
+                            provisioned
+                        ))
                     }
                 } else {
-                    let stderr = String::from_utf8_lossy(&output.stderr);
-                    (false, format!("Installation failed: {}", stderr))
+                    OperationOutcome::failed(OperationError::Command {
+                        action: "Installing Winget".to_string(),
+                        detail: output.stderr,
+                    })
                 }
             }
-            Err(e) => (false, format!("Failed to install Winget: {}", e)),
+            Err(e) => OperationOutcome::failed(OperationError::Command {
+                action: "Installing Winget".to_string(),
+                detail: e.to_string(),
+            }),
         }
     }
 
-    fn check_netbird_status(&self) -> (bool, String) {
-        match Command::new("netbird").arg("version").output() {
+    fn check_netbird_status() -> (bool, String) {
+        match exec::run("netbird", &["version"]) {
             Ok(output) => {
-                if output.status.success() {
-                    let version = String::from_utf8_lossy(&output.stdout);
-                    (true, format!("NetBird is installed: {}", version.trim()))
+                if output.success {
+                    (true, format!("NetBird is installed: {}", output.stdout.trim()))
                 } else {
                     (false, "NetBird is not working properly".to_string())
                 }
@@ -315,79 +1477,105 @@ impl App {
         }
     }
 
-    fn install_netbird(&mut self) -> (bool, String) {
-        self.log_messages.clear();
-        self.add_log("Starting NetBird installation...");
+    #[cfg(feature = "netbird")]
+    fn install_netbird() -> OperationOutcome {
+        message::log("Starting NetBird installation...");
 
         // First check if winget is available
-        let (winget_available, _) = self.check_winget_status();
+        let (winget_available, _) = Self::check_winget_status();
         
         if winget_available {
-            self.add_log("Using winget to install NetBird...");
+            message::log("Using winget to install NetBird...");
             
-            let install_result = Command::new("winget")
-                .args(["install", "--id", "NetBird.NetBird", "-e", "--accept-source-agreements", "--accept-package-agreements"])
-                .output();
+            let install_result = exec::change_program(
+                "winget",
+                &["install", "--id", "NetBird.NetBird", "-e", "--accept-source-agreements", "--accept-package-agreements"],
+            );
 
             match install_result {
                 Ok(output) => {
-                    let stdout = String::from_utf8_lossy(&output.stdout);
-                    let stderr = String::from_utf8_lossy(&output.stderr);
+                    let stdout = output.stdout;
+                    let stderr = output.stderr;
                     
-                    if output.status.success() || stdout.contains("Successfully installed") {
-                        self.add_log("NetBird installed successfully!");
-                        (true, format!("NetBird installed successfully via winget!\n\nTo connect, run:\n  netbird up"))
+                    if output.success || stdout.contains("Successfully installed") {
+                        message::log("NetBird installed successfully!");
+                        OperationOutcome::Success("NetBird installed successfully via winget!\n\nTo connect, run:\n  netbird up".to_string())
                     } else if stdout.contains("already installed") {
-                        (true, "NetBird is already installed.".to_string())
+                        OperationOutcome::Success("NetBird is already installed.".to_string())
                     } else {
-                        (false, format!("Installation may have failed:\n{}\n{}", stdout, stderr))
+                        OperationOutcome::failed(OperationError::Command {
+                            action: "winget install NetBird.NetBird".to_string(),
+                            detail: format!("{}\n{}", stdout, stderr),
+                        })
                     }
                 }
-                Err(e) => (false, format!("Failed to run winget: {}", e)),
+                Err(e) => OperationOutcome::failed(OperationError::Command {
+                    action: "Running winget".to_string(),
+                    detail: e.to_string(),
+                }),
             }
         } else {
             // Fallback to PowerShell script installation
-            self.add_log("Winget not available, using PowerShell installer...");
+            message::log("Winget not available, using PowerShell installer...");
             
-            let install_result = Command::new("powershell")
-                .args([
-                    "-ExecutionPolicy", "Bypass",
-                    "-Command",
-                    "Invoke-WebRequest -Uri 'https://github.com/netbirdio/netbird/releases/latest/download/netbird_installer_windows_amd64.exe' -OutFile '$env:TEMP\\netbird_installer.exe'; Start-Process -FilePath '$env:TEMP\\netbird_installer.exe' -ArgumentList '/S' -Wait"
-                ])
-                .output();
+            let installer = std::env::temp_dir().join("netbird_installer.exe");
+            if let Err(e) = download::fetch(
+                "https://github.com/netbirdio/netbird/releases/latest/download/netbird_installer_windows_amd64.exe",
+                &installer,
+            ) {
+                return OperationOutcome::failed(OperationError::Download { item: "the NetBird installer".to_string(), reason: e });
+            }
+            let install_result = exec::change(&format!(
+                "Start-Process -FilePath {} -ArgumentList '/S' -Wait",
+                exec::quote(&installer.to_string_lossy())
+            ));
 
             match install_result {
                 Ok(output) => {
-                    if output.status.success() {
+                    if output.success {
+                        if let Err(e) = cleanup::remove_installer_files(&installer) {
+                            message::log(format!("Warning: {}", e));
+                        }
                         std::thread::sleep(Duration::from_secs(3));
-                        let (installed, msg) = self.check_netbird_status();
+                        let (installed, msg) = Self::check_netbird_status();
                         if installed {
-                            (true, format!("NetBird installed successfully!\n{}\n\nTo connect, run:\n  netbird up", msg))
+                            OperationOutcome::Success(format!(
+                                "NetBird installed successfully!\n{}\n\nTo connect, run:\n  netbird up",
+                                msg
+                            ))
                         } else {
-                            (true, "Installation completed. You may need to restart your terminal.".to_string())
+                            OperationOutcome::Success("Installation completed. You may need to restart your terminal.".to_string())
                         }
                     } else {
-                        let stderr = String::from_utf8_lossy(&output.stderr);
-                        (false, format!("Installation failed: {}", stderr))
+                        OperationOutcome::failed(OperationError::Command {
+                            action: "The NetBird installer".to_string(),
+                            detail: output.stderr,
+                        })
                     }
                 }
-                Err(e) => (false, format!("Failed to install NetBird: {}", e)),
+                Err(e) => OperationOutcome::failed(OperationError::Command {
+                    action: "Installing NetBird".to_string(),
+                    detail: e.to_string(),
+                }),
             }
         }
     }
 
-    fn backup_server_roles(&mut self) -> (bool, String) {
-        self.log_messages.clear();
-        self.add_log("Backing up Server Roles and Features...");
+    #[cfg(not(feature = "netbird"))]
+    fn install_netbird() -> OperationOutcome {
+        OperationOutcome::failed(OperationError::Refused(
+            "This build of Server Helper was made without NetBird support.".to_string(),
+        ))
+    }
+
+    fn backup_server_roles() -> OperationOutcome {
+        message::log("Backing up Server Roles and Features...");
 
         // Create backup directory
-        let backup_dir = dirs::document_dir()
-            .unwrap_or_else(|| std::path::PathBuf::from("C:\\ServerBackups"))
-            .join("ServerBackups");
+        let backup_dir = backup_dir();
         
         if let Err(e) = std::fs::create_dir_all(&backup_dir) {
-            return (false, format!("Failed to create backup directory: {}", e));
+            return OperationOutcome::failed(OperationError::Io { path: backup_dir, reason: e.to_string() });
         }
 
         // Generate timestamp for filename
@@ -396,39 +1584,43 @@ impl App {
             .map(|d| d.as_secs())
             .unwrap_or(0);
         
-        let backup_file = backup_dir.join(format!("ServerRoles_{}.xml", timestamp));
+        // Only the changes since the last full backup of this server are stored
+        let base = delta::base_for(&backup_dir);
+        let backup_file = match base {
+            Some(_) => backup_dir.join(format!("ServerRoles_{}.{}", timestamp, delta::EXTENSION)),
+            None => backup_dir.join(format!("ServerRoles_{}.xml", timestamp)),
+        };
         let features_file = backup_dir.join(format!("InstalledFeatures_{}.txt", timestamp));
 
-        self.add_log("Exporting installed roles and features...");
+        message::log("Exporting installed roles and features...");
 
         // Export Windows Features to XML (can be used for restoration)
-        let export_result = Command::new("powershell")
-            .args([
-                "-Command",
-                &format!(
-                    "Get-WindowsFeature | Where-Object {{$_.Installed -eq $true}} | Export-Clixml -Path '{}'",
-                    backup_file.display()
-                )
-            ])
-            .output();
+        let export_result = match &base {
+            Some(base) => exec::change(&delta::export_script(base, &backup_file)),
+            None => exec::change(&format!(
+                "Get-WindowsFeature | Where-Object {{$_.Installed -eq $true}} | Export-Clixml -Path '{}'",
+                backup_file.display()
+            )),
+        };
 
-        if let Err(e) = export_result {
-            return (false, format!("Failed to export roles: {}", e));
-        }
+        let export_output = match export_result {
+            Ok(output) => output,
+            Err(e) => {
+                return OperationOutcome::failed(OperationError::Command {
+                    action: "Exporting roles".to_string(),
+                    detail: e.to_string(),
+                });
+            }
+        };
 
         // Also create a human-readable list
-        let list_result = Command::new("powershell")
-            .args([
-                "-Command",
-                &format!(
-                    "Get-WindowsFeature | Where-Object {{$_.Installed -eq $true}} | Select-Object Name, DisplayName, FeatureType | Format-Table -AutoSize | Out-File -FilePath '{}' -Width 200",
-                    features_file.display()
-                )
-            ])
-            .output();
+        let list_result = exec::change(&format!(
+            "Get-WindowsFeature | Where-Object {{$_.Installed -eq $true}} | Select-Object Name, DisplayName, FeatureType | Format-Table -AutoSize | Out-File -FilePath '{}' -Width 200",
+            features_file.display()
+        ));
 
         if let Err(e) = list_result {
-            self.add_log(format!("Warning: Could not create readable list: {}", e));
+            message::log(format!("Warning: Could not create readable list: {}", e));
         }
 
         // Verify the backup was created
@@ -437,30 +1629,74 @@ impl App {
             let size = metadata.map(|m| m.len()).unwrap_or(0);
             
             if size > 0 {
-                (true, format!(
+                // Lets a restore onto a renamed server update what names this one
+                let source = match rename::record(&backup_file) {
+                    Ok(host) => format!("Source host: {}\n\n", host),
+                    Err(e) => {
+                        message::log(format!("Warning: Could not record the source host: {}", e));
+                        String::new()
+                    }
+                };
+                let signed = match signing::sign(&backup_file, base.as_deref()) {
+                    Some(Ok(report)) => format!("{}\n\n", report),
+                    Some(Err(e)) => format!("Warning: the backup is not signed. {}\n\n", e),
+                    None => String::new(),
+                };
+                let offsite = offsite::after_backup(&backup_file).map(|report| format!("\n\n{}", report)).unwrap_or_default();
+                let restore = match &base {
+                    Some(base) => {
+                        let counts = exec::key_values(&export_output.stdout);
+                        let count = |key: &str| counts.get(key).cloned().unwrap_or_else(|| "?".to_string());
+                        format!(
+                            "Differential backup: {} feature(s) added and {} removed since the full backup\n  {}\n\n\
+                            Keep that file next to this one. Restore it from the menu, which rebuilds the full set.",
+                            count("Added"),
+                            count("Removed"),
+                            base.display()
+                        )
+                    }
+                    None => format!(
+                        "To restore on another server, use:\n  \
+                        Import-Clixml '{}' | Where-Object {{$_.Installed}} | Install-WindowsFeature",
+                        backup_file.display()
+                    ),
+                };
+                OperationOutcome::Success(format!(
                     "Server Roles and Features backed up successfully!\n\n\
-                    Backup location:\n  {}\n\n\
+                    {}Backup location:\n  {}\n\n\
                     Readable list:\n  {}\n\n\
-                    To restore on another server, use:\n  \
-                    Import-Clixml '{}' | Where-Object {{$_.Installed}} | Install-WindowsFeature",
+                    {}{}{}",
+                    source,
                     backup_file.display(),
                     features_file.display(),
-                    backup_file.display()
+                    signed,
+                    restore,
+                    offsite
                 ))
             } else {
-                (false, "Backup file was created but appears empty. Ensure you have admin rights.".to_string())
+                OperationOutcome::failed(OperationError::Io {
+                    path: backup_file,
+                    reason: "The backup file was created but appears empty. Ensure you have admin rights.".to_string(),
+                })
             }
         } else {
-            (false, "Failed to create backup file. Ensure you are running as Administrator.".to_string())
+            OperationOutcome::failed(OperationError::Io {
+                path: backup_file,
+                reason: "The backup file was not created. Ensure you are running as Administrator.".to_string(),
+            })
         }
     }
 
     fn load_directory(&mut self) {
         self.dir_entries.clear();
+        let extensions = match self.state {
+            AppState::FileBrowser(purpose) => purpose.extensions(),
+            _ => &["xml"],
+        };
         
         // Add parent directory option if not at root
         if let Some(parent) = self.current_dir.parent() {
-            if parent.as_os_str().len() > 0 {
+            if !parent.as_os_str().is_empty() {
                 self.dir_entries.push(PathBuf::from(".."));
             }
         }
@@ -474,7 +1710,11 @@ impl App {
                 let path = entry.path();
                 if path.is_dir() {
                     dirs.push(path);
-                } else if path.extension().map(|e| e == "xml").unwrap_or(false) {
+                } else if path
+                    .extension()
+                    .and_then(|e| e.to_str())
+                    .is_some_and(|e| extensions.iter().any(|x| x.eq_ignore_ascii_case(e)))
+                {
                     files.push(path);
                 }
             }
@@ -483,7 +1723,7 @@ impl App {
             dirs.sort();
             files.sort();
             
-            // Add directories first, then XML files
+            // Add directories first, then matching files
             self.dir_entries.extend(dirs);
             self.dir_entries.extend(files);
         }
@@ -554,93 +1794,1059 @@ impl App {
         None
     }
 
-    fn restore_server_roles(&mut self, backup_file: &PathBuf) -> (bool, String) {
-        self.log_messages.clear();
-        self.add_log(format!("Restoring from: {}", backup_file.display()));
-
-        // Verify file exists
-        if !backup_file.exists() {
-            return (false, format!("Backup file not found: {}", backup_file.display()));
-        }
+    /// Handles the "add" key on table screens that support it.
+    fn table_add(&mut self) {
+        let purpose = match self.table.kind {
+            TableKind::PersistentRoutes => InputPurpose::AddRoute,
+            TableKind::HostsFile => InputPurpose::AddHostsEntry,
+            TableKind::EnvironmentVariables => InputPurpose::AddEnvVar,
+            TableKind::PathEntries => InputPurpose::AddPathEntry,
+            TableKind::FsrmQuotas => InputPurpose::AddQuota,
+            TableKind::FsrmFileScreens => InputPurpose::AddFileScreen,
+            TableKind::Profiles => InputPurpose::AddProfile,
+            _ => return,
+        };
+        self.input.clear();
+        self.transition(AppState::Input(purpose));
+    }
 
-        self.add_log("Reading backup file...");
-        
-        // First, let's see what features will be installed
-        let preview_result = Command::new("powershell")
-            .args([
-                "-Command",
-                &format!(
-                    "$features = Import-Clixml -Path '{}'; $features | Where-Object {{$_.Installed -eq $true}} | Select-Object -ExpandProperty Name",
-                    backup_file.display()
-                )
-            ])
-            .output();
-
-        let features_list = match preview_result {
-            Ok(output) => String::from_utf8_lossy(&output.stdout).to_string(),
-            Err(e) => return (false, format!("Failed to read backup file: {}", e)),
-        };
-
-        self.add_log("Installing server roles and features...");
-        self.add_log("This may take several minutes...");
-
-        // Perform the actual restore
-        let restore_result = Command::new("powershell")
-            .args([
-                "-Command",
-                &format!(
-                    "$features = Import-Clixml -Path '{}'; \
-                    $toInstall = $features | Where-Object {{$_.Installed -eq $true}} | Select-Object -ExpandProperty Name; \
-                    if ($toInstall) {{ \
-                        Install-WindowsFeature -Name $toInstall -IncludeManagementTools -ErrorAction SilentlyContinue | Out-String \
-                    }} else {{ \
-                        'No features to install' \
-                    }}",
-                    backup_file.display()
-                )
-            ])
-            .output();
+    /// Handles the "remove" key on table screens that support it, asking for confirmation first.
+    fn table_remove(&mut self) {
+        let row = match self.table.selected_row() {
+            Some(row) => row.clone(),
+            None => return,
+        };
+        let (message, action) = match self.table.kind {
+            TableKind::PersistentRoutes => (
+                format!("Remove the route to {} via {}? (y/n)", row[0], row[1]),
+                ConfirmAction::RemoveRoute { prefix: row[0].clone(), next_hop: row[1].clone() },
+            ),
+            TableKind::HostsFile => (
+                format!("Remove line {} of the hosts file?\n\n  {} {} {}\n\n(y/n)", row[0], row[2], row[3], row[4]),
+                ConfirmAction::RemoveHostsLine(row),
+            ),
+            TableKind::EnvironmentVariables => (
+                format!("Delete the machine variable {}?\n\nCurrent value: {}\n\n(y/n)", row[0], row[1]),
+                ConfirmAction::DeleteEnvVar(row[0].clone()),
+            ),
+            TableKind::PathEntries => (
+                format!("Remove {} from the machine PATH? (y/n)", row[1]),
+                ConfirmAction::RemovePathEntry(row[0].parse().unwrap_or(0), row[1].clone()),
+            ),
+            TableKind::Processes => (
+                format!("Kill {} (PID {})?\n\n{}\n\nUnsaved work in it will be lost. (y/n)", row[1], row[0], row[5]),
+                ConfirmAction::KillProcess { pid: row[0].clone(), name: row[1].clone() },
+            ),
+            TableKind::RdpSessions => (
+                format!(
+                    "Log off session {} ({})?\n\nIts programs are closed and unsaved work is lost.{}\n\n(y/n)",
+                    row[0],
+                    if row[2].is_empty() { &row[1] } else { &row[2] },
+                    if row[5].is_empty() { "" } else { "\nThis is your own session: Server Helper closes with it." }
+                ),
+                ConfirmAction::LogOffSession(row[0].clone()),
+            ),
+            TableKind::DownloadCache => (
+                format!("Remove the cached copy of {} ({} MB)?\n\n{}\n\n(y/n)", row[0], row[1], row[4]),
+                ConfirmAction::PurgeCache(Some(row[3].clone())),
+            ),
+            TableKind::Macros => (
+                format!("Delete the macro {}?\n\n  {}\n\n(y/n)", row[0], row[3]),
+                ConfirmAction::DeleteMacro(row[0].clone()),
+            ),
+            TableKind::Features => (
+                format!("Remove {} ({}) and its management tools? (y/n)", row[1], row[0].trim()),
+                ConfirmAction::ChangeFeature { name: row[1].clone(), install: false },
+            ),
+            _ => return,
+        };
+        self.transition(AppState::Confirm { message, action });
+    }
 
-        match restore_result {
-            Ok(output) => {
-                let stdout = String::from_utf8_lossy(&output.stdout);
-                let stderr = String::from_utf8_lossy(&output.stderr);
-                
-                if output.status.success() {
-                    let restart_needed = stdout.contains("RestartNeeded") && stdout.contains("Yes");
-                    let restart_msg = if restart_needed {
-                        "\n\n⚠️  A system restart is required to complete the installation."
-                    } else {
-                        ""
-                    };
-                    
-                    (true, format!(
-                        "Server Roles and Features restoration completed!\n\n\
-                        Features processed:\n{}\n\
-                        Output:\n{}{}",
-                        features_list.trim(),
-                        stdout.trim(),
-                        restart_msg
-                    ))
+    /// Handles Enter on table screens with a drill-down or edit action.
+    fn table_open_selected(&mut self) {
+        // Editing the catalog doesn't need a row: it is how the first agent gets added
+        if self.table.kind == TableKind::Agents {
+            self.table.status = agents::edit_catalog().unwrap_or_else(|e| e);
+            return;
+        }
+        let row = match self.table.selected_row() {
+            Some(row) => row.clone(),
+            None => return,
+        };
+        match self.table.kind {
+            TableKind::EnvironmentVariables => {}
+            TableKind::NetworkAdapters => {
+                if let Err(message) = self.table.open(TableKind::AdapterProperties(row[0].clone())) {
+                    self.transition(AppState::Result(OperationOutcome::failed(message)));
+                }
+                return;
+            }
+            TableKind::AccountPolicy => {
+                self.input = row[1].clone();
+                self.transition(AppState::Input(InputPurpose::AccountPolicyValue(row[0].clone())));
+                return;
+            }
+            TableKind::AdapterProperties(ref adapter) => {
+                self.input = row[1].clone();
+                self.transition(AppState::Input(InputPurpose::AdapterProperty {
+                    adapter: adapter.clone(),
+                    property: row[0].clone(),
+                }));
+                return;
+            }
+            TableKind::Certificates(ref site) => {
+                self.input = "443".to_string();
+                self.transition(AppState::Input(InputPurpose::HttpsBindingPort {
+                    site: site.clone(),
+                    thumbprint: row[0].clone(),
+                }));
+                return;
+            }
+            TableKind::VirtualMachines => {
+                match hyperv::vm_events(&row[0]) {
+                    Ok(text) => self.show_text(format!(" {}: Recent Events ", row[0]), text),
+                    Err(e) => self.table.status = e,
+                }
+                return;
+            }
+            TableKind::DedupVolumes => {
+                if row[3].starts_with("n/a") {
+                    self.table.status = format!("Deduplication is not supported on the system volume {}", row[0]);
                 } else {
-                    (false, format!(
-                        "Restoration encountered errors:\n{}\n{}",
-                        stdout.trim(),
-                        stderr.trim()
-                    ))
+                    self.input = if row[4].is_empty() { "Default".to_string() } else { row[4].clone() };
+                    self.transition(AppState::Input(InputPurpose::DedupUsage(row[0].clone())));
+                }
+                return;
+            }
+            TableKind::WslDistros => {
+                match wsl::install_plan(&row[0]) {
+                    Ok(plan) => self.review_plan(plan),
+                    Err(e) => self.table.status = e,
+                }
+                return;
+            }
+            TableKind::Jobs => {
+                let id = row[0].parse().ok();
+                if let Some((title, log)) = id.and_then(jobs::job_log) {
+                    self.show_text(title, log);
+                    self.text_job = id;
+                }
+                return;
+            }
+            TableKind::RemoteBackups(ref source) => {
+                let next = match remote::fetch(source, &row[2], &row[3]) {
+                    Ok(local) => match features::restore_plan(&local) {
+                        Ok(plan) => self.stage_plan(plan),
+                        Err(message) => AppState::Result(OperationOutcome::failed(message)),
+                    },
+                    Err(message) => AppState::Result(OperationOutcome::failed(message)),
+                };
+                self.transition(next);
+                return;
+            }
+            TableKind::BackupCatalog => {
+                self.input = catalog::annotation(&row[0]).render();
+                self.transition(AppState::Input(InputPurpose::AnnotateBackup(row[0].clone())));
+                return;
+            }
+            TableKind::GoldenBaselines => {
+                self.input.clear();
+                self.transition(AppState::Input(InputPurpose::GoldenCompare(row[0].clone())));
+                return;
+            }
+            TableKind::OffsiteBackups => {
+                let status = offsite::upload(&row[0]).unwrap_or_else(|e| e);
+                let next = self.refresh_table_with_status(status);
+                self.transition(next);
+                return;
+            }
+            TableKind::Macros => {
+                match macros::load(&row[0]) {
+                    Ok(steps) => {
+                        self.replay = steps.into();
+                        self.transition(AppState::Menu);
+                    }
+                    Err(e) => self.table.status = e,
+                }
+                return;
+            }
+            TableKind::Profiles => {
+                let name = (row[1] != "(default)").then_some(row[1].as_str());
+                let status = match profiles::set_active(name) {
+                    Ok(()) => {
+                        self.current_dir = backup_dir();
+                        self.load_lock_settings();
+                        history::record(&format!("Switched to {}", profiles::describe(name)));
+                        format!(
+                            "Switched to {}. Backups go to {}.",
+                            profiles::describe(name),
+                            backup_dir().display()
+                        )
+                    }
+                    Err(e) => e,
+                };
+                let next = self.refresh_table_with_status(status);
+                self.transition(next);
+                return;
+            }
+            TableKind::ScheduledTasks { .. } => {
+                match tasks::task_details(&row[0], &row[1]) {
+                    Ok(text) => self.show_text(format!(" {}{} ", row[0], row[1]), text),
+                    Err(e) => self.table.status = e,
                 }
+                return;
             }
-            Err(e) => (false, format!("Failed to execute restore: {}", e)),
+            _ => return,
         }
-    }
-}
+        if row[0].eq_ignore_ascii_case("Path") {
+            if let Err(message) = self.table.open(TableKind::PathEntries) {
+                self.transition(AppState::Result(OperationOutcome::failed(message)));
+            }
+        } else {
+            self.input = row[1].clone();
+            self.transition(AppState::Input(InputPurpose::EditEnvVar(row[0].clone())));
+        }
+    }
+
+    /// Handles the agent catalog keys: install on this host or on a list of hosts.
+    fn table_agent_action(&mut self, key: char) {
+        let name = match self.table.selected_row() {
+            Some(row) => row[0].clone(),
+            None => return,
+        };
+        let winget = self.table.selected_row().is_some_and(|row| row[1].starts_with("winget:"));
+        if key == 'i' && winget && self.offline {
+            self.table.status = format!("{} installs with winget, which offline mode blocks.", name);
+            return;
+        }
+        match agents::pending_eula(&name) {
+            Ok(Some(eula)) => {
+                self.transition(AppState::Confirm {
+                    message: format!(
+                        "{} comes with a license agreement:\n\n  {}\n\n\
+                        Have you read it, and do you accept it? Your acceptance is recorded with your\n\
+                        account and the time in the history log, and is asked for once. (y/n)",
+                        name, eula
+                    ),
+                    action: ConfirmAction::AcceptEula { agent: name, fleet: key == 'f' },
+                });
+                return;
+            }
+            Ok(None) => {}
+            Err(message) => {
+                self.table.status = message;
+                return;
+            }
+        }
+        let next = self.agent_install_step(name, key == 'f');
+        self.transition(next);
+    }
+
+    /// Asks for the fleet hosts or confirms a local install, once any license agreement is accepted.
+    fn agent_install_step(&mut self, name: String, fleet: bool) -> AppState {
+        if fleet {
+            self.input = agents::default_fleet();
+            AppState::Input(InputPurpose::AgentFleet(name))
+        } else {
+            AppState::Confirm {
+                message: format!("Install {} on this host? (y/n)", name),
+                action: ConfirmAction::InstallAgent(name),
+            }
+        }
+    }
+
+    fn table_install_feature(&mut self) {
+        if let Some(row) = self.table.selected_row() {
+            self.transition(AppState::Confirm {
+                message: format!("Install {} ({}) with its management tools? (y/n)", row[1], row[0].trim()),
+                action: ConfirmAction::ChangeFeature { name: row[1].clone(), install: true },
+            });
+        }
+    }
+
+    /// Handles the scheduled task keys: run now, enable/disable and showing Microsoft tasks.
+    fn table_task_action(&mut self, key: char) {
+        let include_system = match self.table.kind {
+            TableKind::ScheduledTasks { include_system } => include_system,
+            _ => return,
+        };
+        if key == 'm' {
+            let kind = TableKind::ScheduledTasks { include_system: !include_system };
+            if let Err(message) = self.table.open(kind) {
+                self.transition(AppState::Result(OperationOutcome::failed(message)));
+            }
+            return;
+        }
+        let row = match self.table.selected_row() {
+            Some(row) => row.clone(),
+            None => return,
+        };
+        let status = match key {
+            's' => tasks::run_task(&row[0], &row[1]),
+            't' => tasks::toggle_task(&row[0], &row[1], &row[2]),
+            _ => return,
+        };
+        let next = self.refresh_table_with_status(status.unwrap_or_else(|e| e));
+        self.transition(next);
+    }
+
+    fn table_iis_action(&mut self, key: char) {
+        let row = match self.table.selected_row() {
+            Some(row) => row.clone(),
+            None => return,
+        };
+        if key == 'h' {
+            if let Err(message) = self.table.open(TableKind::Certificates(row[0].clone())) {
+                self.transition(AppState::Result(OperationOutcome::failed(message)));
+            } else if self.table.rows.is_empty() {
+                self.table.status =
+                    "No certificates with a private key in LocalMachine\\My. Use the CSR wizard to request one.".to_string();
+            }
+            return;
+        }
+        let status = match key {
+            's' => iis::start_site(&row[0]),
+            't' => iis::stop_site(&row[0]),
+            'c' => iis::recycle_pool(&row[2], &row[3]),
+            _ => return,
+        };
+        let next = self.refresh_table_with_status(status.unwrap_or_else(|e| e));
+        self.transition(next);
+    }
+
+    fn table_vm_action(&mut self, key: char) {
+        let row = match self.table.selected_row() {
+            Some(row) => row.clone(),
+            None => return,
+        };
+        let status = match key {
+            's' => hyperv::start_vm(&row[0]),
+            't' => {
+                self.transition(AppState::Confirm {
+                    message: format!(
+                        "Shut down {}? The guest OS is asked to shut down; without integration services it is turned off. (y/n)",
+                        row[0]
+                    ),
+                    action: ConfirmAction::StopVm(row[0].clone()),
+                });
+                return;
+            }
+            'c' => hyperv::checkpoint_vm(&row[0]),
+            'x' => {
+                self.input = row[0].clone();
+                self.transition(AppState::Input(InputPurpose::ExportVms));
+                return;
+            }
+            _ => return,
+        };
+        let next = self.refresh_table_with_status(status.unwrap_or_else(|e| e));
+        self.transition(next);
+    }
+
+    /// Handles m (message), c (shadow) and x (disconnect) on the Remote Desktop sessions screen.
+    fn table_session_action(&mut self, key: char) {
+        let row = match self.table.selected_row() {
+            Some(row) => row.clone(),
+            None => return,
+        };
+        match key {
+            'm' => {
+                self.input.clear();
+                self.transition(AppState::Input(InputPurpose::SessionMessage(row[0].clone())));
+            }
+            'c' => self.table.status = sessions::shadow(&row[0]).unwrap_or_else(|e| e),
+            'x' => self.transition(AppState::Confirm {
+                message: format!(
+                    "Disconnect session {} ({})? Its programs keep running until the user reconnects. (y/n)",
+                    row[0],
+                    if row[2].is_empty() { &row[1] } else { &row[2] }
+                ),
+                action: ConfirmAction::DisconnectSession(row[0].clone()),
+            }),
+            _ => {}
+        }
+    }
+
+    /// Asks before quitting while background jobs are still queued or running.
+    fn quit_confirmation(&self) -> Option<AppState> {
+        match jobs::pending() {
+            0 => None,
+            n => Some(AppState::Confirm {
+                message: format!(
+                    "{} job(s) are still queued or running and will be abandoned.\n\nQuit anyway? (y/n)",
+                    n
+                ),
+                action: ConfirmAction::Quit,
+            }),
+        }
+    }
+
+    /// Adds a key about to be handled to the action under way and to the recording. On the menu
+    /// only Enter counts, recorded as the label of the chosen item so that replays do not depend
+    /// on its position. A password is never recorded: submitting a masked prompt is recorded as
+    /// a secret step instead.
+    fn note_key(&mut self, key: &KeyEvent) {
+        let step = match (&self.state, key.code) {
+            (AppState::Menu, KeyCode::Enter) => match self.menu_state.selected().and_then(|i| self.menu_items.get(i)) {
+                Some((label, _)) => {
+                    self.action.clear();
+                    macros::Step::Menu(label.to_string())
+                }
+                None => return,
+            },
+            (AppState::Menu, _) | (_, KeyCode::Tab) => return,
+            (AppState::Input(p), KeyCode::Enter) if p.secret() => macros::Step::Secret,
+            (AppState::Input(p), code) if p.secret() && code != KeyCode::Esc => return,
+            (_, code) => macros::Step::Key(code),
+        };
+        self.action.push(step.clone());
+        if let Some(recording) = &mut self.recording {
+            recording.push(step);
+        }
+    }
+
+    /// Whether the replay waits for a password to be typed at a masked prompt.
+    fn replay_waits(&self) -> bool {
+        matches!(&self.state, AppState::Input(p) if p.secret()) && self.replay.front() != Some(&macros::Step::Secret)
+    }
+
+    /// Applies the next step to replay. A menu item that is not in this build's menu, a menu
+    /// step while a screen is still open, a secret step without a masked prompt, or a failed
+    /// result stops the replay.
+    fn replay_next(&mut self) -> ControlFlow<()> {
+        let key = match self.replay.pop_front() {
+            Some(macros::Step::Menu(label)) => {
+                match self.menu_items.iter().position(|(l, _)| *l == label) {
+                    Some(i) if self.state == AppState::Menu => self.menu_state.select(Some(i)),
+                    found => {
+                        self.replay.clear();
+                        let reason = if found.is_some() { "the previous step did not return to the menu" } else { "it is not in this menu" };
+                        self.transition(AppState::Result(OperationOutcome::failed(format!(
+                            "Replay stopped at \"{}\": {}.",
+                            label, reason
+                        ))));
+                        return ControlFlow::Continue(());
+                    }
+                }
+                KeyCode::Enter
+            }
+            // Keys on the menu are recorded as menu steps, so a stray one (e.g. the key that
+            // dismissed a result) must not choose whatever item is selected
+            Some(macros::Step::Key(_)) if self.state == AppState::Menu => return ControlFlow::Continue(()),
+            Some(macros::Step::Key(code)) => code,
+            // The password is typed by hand; the replay goes on once the prompt is left
+            Some(macros::Step::Secret) if matches!(&self.state, AppState::Input(p) if p.secret()) => {
+                return ControlFlow::Continue(())
+            }
+            Some(macros::Step::Secret) => {
+                self.replay.clear();
+                self.transition(AppState::Result(OperationOutcome::failed(
+                    "Replay stopped at a password: the previous step did not ask for one.".to_string(),
+                )));
+                return ControlFlow::Continue(());
+            }
+            None => return ControlFlow::Continue(()),
+        };
+        let flow = press(self, KeyEvent::new(key, KeyModifiers::NONE));
+        if matches!(self.state, AppState::Result(OperationOutcome::Failed { .. })) {
+            self.replay.clear();
+        }
+        flow
+    }
+
+    /// Starts recording a macro, or stops and asks for its name.
+    fn toggle_recording(&mut self) {
+        match self.recording.take() {
+            None => self.recording = Some(Vec::new()),
+            Some(steps) if steps.is_empty() => {}
+            Some(steps) => {
+                self.input.clear();
+                self.transition(AppState::Input(InputPurpose::SaveMacro(steps)));
+            }
+        }
+    }
+
+    fn show_text(&mut self, title: String, text: String) {
+        self.text_title = title;
+        self.text = text;
+        self.text_scroll = 0;
+        self.text_job = None;
+        self.transition(AppState::TextView);
+    }
+
+    /// Describes the backup selected in the restore file browser when the selection changes.
+    /// Checking its signature runs minisign, so this is done once per selection, not per frame.
+    fn update_preview(&mut self) {
+        let selected = match self.state {
+            AppState::FileBrowser(BrowsePurpose::RestoreRoles) => {
+                self.file_list_state.selected().and_then(|i| self.dir_entries.get(i)).filter(|p| p.is_file()).cloned()
+            }
+            _ => None,
+        };
+        if selected != self.preview_path {
+            self.preview = selected.as_deref().map(catalog::preview).unwrap_or_default();
+            self.preview_path = selected;
+        }
+    }
+
+    /// Moves to `next`, running the hooks for leaving the current state and entering the new
+    /// one. A move missing from `TRANSITIONS` is a bug and fails in debug builds.
+    fn transition(&mut self, next: AppState) {
+        let (from, to) = (self.state.kind(), next.kind());
+        debug_assert!(can_transition(from, to), "no transition from {:?} to {:?}", from, to);
+        self.state = next;
+
+        // Exit hooks
+        match from {
+            StateKind::PlanReview if to != StateKind::Applying => self.plan = None,
+            StateKind::TextView => self.text_job = None,
+            StateKind::Result => self.secret_result = false,
+            _ => {}
+        }
+
+        // Enter hooks
+        match to {
+            StateKind::FileBrowser => {
+                self.load_directory();
+                self.update_preview();
+            }
+            StateKind::Installing | StateKind::Applying | StateKind::Running => self.start_operation(),
+            _ => {}
+        }
+        self.sync_screens();
+    }
+
+    /// The screen shown now, `None` on prompts, busy states and results.
+    fn screen(&self) -> Option<Screen> {
+        match &self.state {
+            AppState::Menu => Some(Screen::Menu),
+            AppState::Table => Some(Screen::Table(self.table.kind.clone())),
+            AppState::Checklist => Some(Screen::Checklist(self.checklist_kind)),
+            AppState::FileBrowser(purpose) => Some(Screen::FileBrowser(*purpose)),
+            AppState::TextView => Some(Screen::TextView(self.text_title.clone())),
+            _ => None,
+        }
+    }
+
+    /// Brings the navigation stack up to date with the screen shown: a screen already on it
+    /// drops the ones after it, one with the same title as the current screen (a table shown
+    /// another way) replaces it, and any other is pushed. Tables opened from another table
+    /// without a state change are picked up after each key.
+    fn sync_screens(&mut self) {
+        let Some(screen) = self.screen() else { return };
+        if let Some(i) = self.screens.iter().position(|s| *s == screen) {
+            self.screens.truncate(i + 1);
+        } else if self.screens.last().is_some_and(|s| s.title() == screen.title()) {
+            self.screens.pop();
+            self.screens.push(screen);
+        } else {
+            self.screens.push(screen);
+        }
+    }
+
+    /// Goes back to the screen before the current one (Esc). A drill-down table reached
+    /// some other way goes back to its parent table.
+    fn back(&mut self) {
+        let parent = match (self.screens.pop(), self.screens.len()) {
+            (Some(Screen::Table(kind)), 1) => kind.parent().map(Screen::Table),
+            _ => None,
+        };
+        self.show_screen(parent.or_else(|| self.screens.last().cloned()).unwrap_or(Screen::Menu));
+    }
+
+    /// Leaves a result or plan for the screen it was started from.
+    fn leave(&mut self) {
+        self.show_screen(self.screens.last().cloned().unwrap_or(Screen::Menu));
+    }
+
+    /// Shows `screen` again. A table or checklist is read again when another one was shown
+    /// since, or when coming back from a result that may have changed it.
+    fn show_screen(&mut self, screen: Screen) {
+        let reload = self.state.kind() == StateKind::Result;
+        let next = match screen {
+            Screen::Menu => AppState::Menu,
+            Screen::Table(kind) if reload || self.table.kind != kind => match self.table.open(kind) {
+                Ok(()) => AppState::Table,
+                Err(message) => AppState::Result(OperationOutcome::failed(message)),
+            },
+            Screen::Table(_) => AppState::Table,
+            Screen::Checklist(kind) if reload || self.checklist_kind != kind => match self.open_checklist(kind) {
+                Ok(()) => AppState::Checklist,
+                Err(message) => AppState::Result(OperationOutcome::failed(message)),
+            },
+            Screen::Checklist(_) => AppState::Checklist,
+            Screen::FileBrowser(purpose) => AppState::FileBrowser(purpose),
+            // Text is not kept once closed; only the screen it was opened from is
+            Screen::TextView(_) => {
+                self.screens.pop();
+                return self.leave();
+            }
+        };
+        if next.kind() == StateKind::Result {
+            self.screens.truncate(1);
+        }
+        self.transition(next);
+    }
+
+    /// Reads the idle lock from the active profile's settings; `--lock-after=MINUTES` overrides
+    /// the time, and 0 turns it off.
+    fn load_lock_settings(&mut self) {
+        let settings = profiles::settings();
+        let minutes = std::env::args()
+            .find_map(|a| a.strip_prefix("--lock-after=").and_then(|m| m.parse().ok()))
+            .or(settings.lock_after)
+            .unwrap_or(0);
+        self.idle_lock = (minutes > 0).then(|| Duration::from_secs(minutes * 60));
+        self.lock_pin = settings.lock_pin;
+    }
+
+    /// Screens with credentials or the users signed in, blanked when the console is left idle.
+    fn sensitive(&self) -> bool {
+        match &self.state {
+            AppState::Input(purpose) => purpose.secret(),
+            AppState::Table => self.table.kind == TableKind::RdpSessions,
+            AppState::Result(_) => self.secret_result,
+            _ => false,
+        }
+    }
+
+    /// Locks a sensitive screen once no key has been pressed for the idle time.
+    fn check_idle(&mut self) {
+        if let Some(after) = self.idle_lock {
+            if !self.locked && self.last_key.elapsed() >= after && self.sensitive() {
+                self.locked = true;
+                self.pin_input.clear();
+                self.lock_status.clear();
+            }
+        }
+    }
+
+    /// A key on the lock screen: any key resumes, or the PIN followed by Enter when one is set.
+    fn unlock_key(&mut self, key: KeyEvent) {
+        let Some(pin) = &self.lock_pin else {
+            self.locked = false;
+            return;
+        };
+        match key.code {
+            KeyCode::Char(c) => self.pin_input.push(c),
+            KeyCode::Backspace => {
+                self.pin_input.pop();
+            }
+            KeyCode::Enter if self.pin_input == *pin => self.locked = false,
+            KeyCode::Enter => {
+                self.pin_input.clear();
+                self.lock_status = "Wrong PIN.".to_string();
+                history::record("Wrong PIN entered on the lock screen");
+            }
+            _ => self.pin_input.clear(),
+        }
+    }
+
+    /// The breadcrumb for the title bar, e.g. `Menu > Network Adapters > Ethernet0`.
+    fn breadcrumb(&self) -> String {
+        self.screens.iter().map(Screen::title).collect::<Vec<_>>().join(" > ")
+    }
+
+    /// Starts the operation of the busy state just entered on a worker thread. It reports
+    /// progress to the main loop and ends with `OperationFinished` carrying its ID.
+    ///
+    /// Only one operation runs at a time: while one is in flight this does nothing, so
+    /// entering or redrawing a busy state can never start the same install twice.
+    fn start_operation(&mut self) {
+        if self.operation.is_some() {
+            return;
+        }
+        let operation: Box<dyn FnOnce() -> OperationOutcome + Send> = match &self.state {
+            AppState::Installing(item) => {
+                let item = item.clone();
+                Box::new(move || item.install())
+            }
+            AppState::Applying => {
+                let plan = self.plan.take();
+                Box::new(move || App::apply_plan(plan))
+            }
+            AppState::Running(task) => {
+                let task = task.clone();
+                Box::new(move || task.run())
+            }
+            _ => return,
+        };
+        self.log_messages.clear();
+        self.download = None;
+        self.last_operation += 1;
+        let id = self.last_operation;
+        self.operation = Some(id);
+        let outbox = self.outbox.clone();
+        let runner = exec::runner();
+        std::thread::spawn(move || {
+            exec::set_runner(runner);
+            message::set_outbox(outbox.clone());
+            let outcome = std::panic::catch_unwind(std::panic::AssertUnwindSafe(operation))
+                .unwrap_or_else(|_| OperationOutcome::failed("The operation stopped unexpectedly.".to_string()));
+            let _ = outbox.send(Message::OperationFinished { id, outcome });
+        });
+    }
+
+    /// `message` for a busy screen, followed by the operation's download progress and its
+    /// latest progress lines.
+    fn busy_text(&self, message: &str) -> String {
+        let mut text = message.to_string();
+        if let Some((name, received, total)) = &self.download {
+            let mb = |bytes: u64| bytes as f64 / 1_048_576.0;
+            text.push_str(&match total {
+                Some(total) if *total > 0 => format!(
+                    "\n\nDownloading {}: {:.1} of {:.1} MB ({}%)",
+                    name,
+                    mb(*received),
+                    mb(*total),
+                    received * 100 / total
+                ),
+                _ => format!("\n\nDownloading {}: {:.1} MB", name, mb(*received)),
+            });
+        }
+        let start = self.log_messages.len().saturating_sub(8);
+        if start < self.log_messages.len() {
+            text.push_str("\n\n");
+            text.push_str(&self.log_messages[start..].join("\n"));
+        }
+        text
+    }
+
+    /// Status shown next to a menu entry, from the last background reading.
+    fn menu_badge(&self, action: MenuAction) -> Option<status::Badge> {
+        let status = self.host_status.as_ref()?;
+        match action {
+            MenuAction::CheckWinget => Some(status.winget_badge()),
+            MenuAction::InstallWinget => Some(status.winget_install_badge()),
+            MenuAction::InstallNetBird => Some(status.netbird_install_badge()),
+            MenuAction::CheckNetBird => Some(status.netbird_badge()),
+            MenuAction::Uptime => Some(status.reboot_badge()),
+            MenuAction::FreeUpSpace => status.disk_badge(),
+            _ => None,
+        }
+    }
+
+    /// Shows a change plan for approval; nothing runs until it is accepted.
+    fn review_plan(&mut self, plan: Plan) {
+        let next = self.stage_plan(plan);
+        self.transition(next);
+    }
+
+    /// Prepares the review screen for `plan` and returns the state to move to, for handlers
+    /// that pick the next state in one expression.
+    fn stage_plan(&mut self, mut plan: Plan) -> AppState {
+        if self.checkpoint && !plan.steps.is_empty() {
+            let (dir, script) = checkpoint::script(&plan.title);
+            plan.steps.insert(0, plan::Step {
+                description: format!("Create a checkpoint in {}", dir.display()),
+                command: script,
+            });
+        }
+        self.text_title = format!(" {} ", plan.title);
+        self.text = plan.render();
+        self.text_scroll = 0;
+        self.plan = Some(plan);
+        AppState::PlanReview
+    }
+
+
+    /// Runs a risky action, creating a checkpoint first when that is switched on.
+    /// Nothing runs if the checkpoint fails.
+    fn with_checkpoint(&mut self, reason: &str, action: impl FnOnce(&mut App) -> (bool, String)) -> (bool, String) {
+        if !self.checkpoint {
+            return action(self);
+        }
+        match checkpoint::create(reason) {
+            Ok(summary) => {
+                let (success, message) = action(self);
+                (success, format!("{}\n\nCheckpoint: {}", message, summary))
+            }
+            Err(e) => (false, format!("{}\n\nNothing was changed.", e)),
+        }
+    }
+
+    fn apply_plan(plan: Option<Plan>) -> OperationOutcome {
+        let plan = match plan {
+            Some(plan) => plan,
+            None => return OperationOutcome::failed("No change plan to apply.".to_string()),
+        };
+        match plan.apply() {
+            OperationOutcome::Success(message)
+                if message.contains("RestartNeeded : Yes") || message.contains("RestartNeeded : True") =>
+            {
+                OperationOutcome::SuccessNeedsReboot(message)
+            }
+            outcome => outcome,
+        }
+    }
+
+    /// Switches offline mode, remembering the choice for the next start.
+    fn toggle_offline(&mut self) -> (bool, String) {
+        self.offline = !self.offline;
+        let saved = if self.offline {
+            std::fs::create_dir_all(config_dir()).and_then(|_| std::fs::write(offline_marker(), ""))
+        } else {
+            match std::fs::remove_file(offline_marker()) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+                _ => Ok(()),
+            }
+        };
+        let message = if self.offline {
+            "Offline mode on.\n\n\
+            Actions that download from the internet are greyed out, and agents are only installed \
+            from their installer URL, never with winget. Start with --offline to force it on."
+        } else {
+            "Offline mode off. Downloads are allowed again."
+        };
+        match saved {
+            Ok(()) => (true, message.to_string()),
+            Err(e) => (true, format!("{}\n\nThe setting could not be saved for the next start: {}", message, e)),
+        }
+    }
+
+    /// Turns export mode on (new script under the backup directory) or off.
+    fn toggle_export_mode(&mut self) -> (bool, String) {
+        if let Some((path, steps)) = exec::stop_export() {
+            return (true, format!(
+                "Export mode off. {} step(s) were written to:\n  {}\n\n\
+                Actions run normally again.",
+                steps,
+                path.display()
+            ));
+        }
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let path = backup_dir().join("scripts").join(format!("ServerHelper_{}.ps1", timestamp));
+        match exec::start_export(&path) {
+            Ok(()) => (true, format!(
+                "Export mode on. Changes are now written to:\n  {}\n\n\
+                instead of being run. Checks and lists still run so each action can work out \
+                what it would do. Choose Export Mode again to finish.",
+                path.display()
+            )),
+            Err(e) => (false, format!("Failed to create {}: {}", path.display(), e)),
+        }
+    }
+
+    /// In export mode, replaces an action's outcome with what was written to the script,
+    /// since nothing actually ran.
+    fn report_exports(&mut self) {
+        let path = match exec::export_status() {
+            Some((path, _)) => path,
+            None => return,
+        };
+        match self.state {
+            AppState::Result(_) => {
+                let steps = exec::take_exported();
+                if !steps.is_empty() {
+                    self.transition(AppState::Result(OperationOutcome::Success(format!(
+                        "Export mode: nothing was changed. {} step(s) were written to\n  {}\n\n{}",
+                        steps.len(),
+                        path.display(),
+                        steps.iter().map(|s| format!("  {}", s)).collect::<Vec<_>>().join("\n")
+                    ))));
+                }
+            }
+            AppState::Table => {
+                let steps = exec::take_exported();
+                if !steps.is_empty() {
+                    self.table.status = format!("Export mode: {} step(s) written to {}", steps.len(), path.display());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Reloads the current table after an action and shows its outcome on the status line.
+    fn refresh_table_with_status(&mut self, status: String) -> AppState {
+        match self.table.refresh() {
+            Ok(()) => {
+                self.table.status = status;
+                AppState::Table
+            }
+            Err(message) => AppState::Result(OperationOutcome::failed(message)),
+        }
+    }
+
+    fn open_checklist(&mut self, kind: ChecklistKind) -> Result<(), String> {
+        self.checklist = kind.evaluate()?;
+        self.checklist_kind = kind;
+        if self.checklist.is_empty() {
+            self.checklist_state.select(None);
+        } else {
+            self.checklist_state.select(Some(0));
+        }
+        Ok(())
+    }
+
+    fn checklist_next(&mut self) {
+        if self.checklist.is_empty() {
+            return;
+        }
+        let i = match self.checklist_state.selected() {
+            Some(i) => {
+                if i >= self.checklist.len() - 1 {
+                    0
+                } else {
+                    i + 1
+                }
+            }
+            None => 0,
+        };
+        self.checklist_state.select(Some(i));
+    }
+
+    fn checklist_previous(&mut self) {
+        if self.checklist.is_empty() {
+            return;
+        }
+        let i = match self.checklist_state.selected() {
+            Some(i) => {
+                if i == 0 {
+                    self.checklist.len() - 1
+                } else {
+                    i - 1
+                }
+            }
+            None => 0,
+        };
+        self.checklist_state.select(Some(i));
+    }
+
+    fn apply_remediation(&mut self, index: usize) -> (bool, String) {
+        let item = match self.checklist.get(index) {
+            Some(item) => item.clone(),
+            None => return (false, "No check selected.".to_string()),
+        };
+        let remediation = match item.remediation {
+            Some(ref remediation) => remediation.clone(),
+            None => return (false, format!("No automatic fix is available for {}.", item.name)),
+        };
+
+        message::log(format!("Applying fix for {}...", item.name));
+
+        match exec::change(&remediation.script) {
+            Ok(output) if output.success => {}
+            Ok(output) => {
+                return (false, format!("Failed to apply fix for {}:\n{}", item.name, output.stderr.trim()));
+            }
+            Err(e) => return (false, format!("Failed to apply fix for {}: {}", item.name, e)),
+        }
+
+        // Re-run the checks so the report shows the state after the change
+        let after = match self.checklist_kind.evaluate() {
+            Ok(items) => {
+                self.checklist = items;
+                self.checklist
+                    .iter()
+                    .find(|i| i.name == item.name)
+                    .map(|i| i.detail.clone())
+                    .unwrap_or_else(|| "unknown".to_string())
+            }
+            Err(e) => format!("could not re-check ({})", e),
+        };
+
+        (true, format!(
+            "Changed {}:\n\n\
+            Before: {}\n\
+            After:  {}\n\n\
+            {}",
+            item.name,
+            item.detail,
+            after,
+            remediation.summary
+        ))
+    }
+}
+
+/// File next to the executable that switches on portable mode.
+const PORTABLE_FLAG: &str = "portable.flag";
+
+/// The executable's folder when `portable.flag` is next to it. Config, the history log, the
+/// download cache and backups then live there, so the tool runs entirely from a USB stick
+/// and leaves nothing behind on the server.
+fn portable_dir() -> Option<&'static Path> {
+    static DIR: OnceLock<Option<PathBuf>> = OnceLock::new();
+    DIR.get_or_init(|| {
+        if cfg!(test) {
+            return None;
+        }
+        let dir = std::env::current_exe().ok()?.parent()?.to_path_buf();
+        dir.join(PORTABLE_FLAG).exists().then_some(dir)
+    })
+    .as_deref()
+}
+
+/// Directory holding user-editable configuration such as the agent catalog.
+fn config_dir() -> PathBuf {
+    if cfg!(test) {
+        return std::env::temp_dir().join(format!("server-helper-test-{}", std::process::id())).join("ServerHelper");
+    }
+    if let Some(dir) = portable_dir() {
+        return dir.join("ServerHelper");
+    }
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("C:\\ProgramData"))
+        .join("ServerHelper")
+}
+
+/// Marker file that keeps offline mode on across restarts.
+fn offline_marker() -> PathBuf {
+    config_dir().join("offline")
+}
+
+/// Directory where backups (role exports, previous config file versions) are written.
+fn backup_dir() -> PathBuf {
+    if cfg!(test) {
+        return std::env::temp_dir().join(format!("server-helper-test-{}", std::process::id())).join("ServerBackups");
+    }
+    if let Some(dir) = profiles::settings().backup_dir {
+        return dir;
+    }
+    if let Some(dir) = portable_dir() {
+        return dir.join("ServerBackups");
+    }
+    dirs::document_dir()
+        .unwrap_or_else(|| PathBuf::from("C:\\ServerBackups"))
+        .join("ServerBackups")
+}
 
 fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        #[cfg(feature = "web")]
+        Some("serve") => return serve::run(serve::port(&args)?),
+        #[cfg(not(feature = "web"))]
+        Some("serve") => anyhow::bail!("This build was made without the web feature."),
+        Some("install-self") => {
+            let report = selfinstall::install(&std::env::current_exe()?).map_err(anyhow::Error::msg)?;
+            println!("{}", report);
+            return Ok(());
+        }
+        Some("uninstall-self") => {
+            println!("{}", selfinstall::uninstall().map_err(anyhow::Error::msg)?);
+            return Ok(());
+        }
+        _ => {}
+    }
+    if let Some(name) = profiles::from_args(&args) {
+        profiles::set_active(Some(&name)).map_err(anyhow::Error::msg)?;
+    }
+    let replay = match macros::from_args(&args) {
+        Some(name) => macros::load(&name).map_err(anyhow::Error::msg)?,
+        None => Vec::new(),
+    };
+
     enable_raw_mode()?;
     stdout().execute(EnterAlternateScreen)?;
     let mut terminal = Terminal::new(CrosstermBackend::new(stdout()))?;
 
     let mut app = App::new();
+    app.load_lock_settings();
+    app.replay = replay.into();
     let result = run_app(&mut terminal, &mut app);
 
     disable_raw_mode()?;
@@ -653,147 +2859,1496 @@ fn main() -> Result<()> {
     Ok(())
 }
 
+/// How often the menu badges are refreshed: `--status-interval=SECONDS`, 60 by default,
+/// 0 to switch the refresh off.
+fn status_interval() -> Option<Duration> {
+    let seconds = std::env::args()
+        .find_map(|a| a.strip_prefix("--status-interval=").and_then(|s| s.parse().ok()))
+        .unwrap_or(60);
+    (seconds > 0).then(|| Duration::from_secs(seconds))
+}
+
 fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Result<()> {
+    message::set_outbox(app.outbox.clone());
+    // The badges are read with PowerShell
+    if let Some(interval) = status_interval().filter(|_| platform::current().os() == platform::Os::Windows) {
+        status::start_refresh(interval);
+    }
     loop {
-        terminal.draw(|f| ui(f, app))?;
+        // The jobs screen and job logs follow the background queue while they are open
+        if app.state == AppState::Table && app.table.kind == TableKind::Jobs {
+            if let Ok(rows) = jobs::job_rows() {
+                app.table.update_rows(rows);
+            }
+        }
+        if let (AppState::TextView, Some(id)) = (&app.state, app.text_job) {
+            if let Some((_, log)) = jobs::job_log(id) {
+                app.text = log;
+            }
+        }
+
+        terminal.draw(|f| view(f, app))?;
 
         if event::poll(Duration::from_millis(100))? {
             if let Event::Key(key) = event::read()? {
                 if key.kind == KeyEventKind::Press {
-                    match &app.state {
-                        AppState::Menu => match key.code {
-                            KeyCode::Char('q') => return Ok(()),
-                            KeyCode::Down | KeyCode::Char('j') => app.next(),
-                            KeyCode::Up | KeyCode::Char('k') => app.previous(),
-                            KeyCode::Enter => {
-                                match app.menu_state.selected() {
-                                    Some(0) => {
-                                        let (success, message) = app.check_winget_status();
-                                        app.state = AppState::Result { success, message };
-                                    }
-                                    Some(1) => {
-                                        app.state = AppState::Installing(InstallItem::Winget);
-                                    }
-                                    Some(2) => {
-                                        let (success, message) = app.check_netbird_status();
-                                        app.state = AppState::Result { success, message };
-                                    }
-                                    Some(3) => {
-                                        app.state = AppState::Installing(InstallItem::NetBird);
-                                    }
-                                    Some(4) => {
-                                        let (success, message) = app.backup_server_roles();
-                                        app.state = AppState::Result { success, message };
+                    message::send(Message::KeyPressed(key));
+                }
+            }
+        }
+        let pending: Vec<Message> = app.inbox.try_iter().collect();
+        for message in pending {
+            if update(app, message).is_break() {
+                return Ok(());
+            }
+        }
+        app.check_idle();
+        // A replayed step waits for the operation started by the one before it
+        while app.operation.is_none() && !app.locked && !app.replay.is_empty() && !app.replay_waits() {
+            if app.replay_next().is_break() {
+                return Ok(());
+            }
+        }
+
+        for (id, text, level) in jobs::finished_after(app.jobs_notified) {
+            app.jobs_notified = id;
+            app.toasts.push(text, level);
+        }
+        app.toasts.expire();
+
+        app.report_exports();
+    }
+}
+
+/// Applies one message to the app. `Break` means quit.
+fn update(app: &mut App, message: Message) -> ControlFlow<()> {
+    match message {
+        Message::KeyPressed(key) => return press(app, key),
+        Message::CommandOutput(line) => app.log_messages.push(line),
+        Message::DownloadProgress { name, received, total } => app.download = Some((name, received, total)),
+        Message::StatusRefreshed(status) => {
+            if let Some(old) = &app.host_status {
+                for change in status::changes(old, &status) {
+                    app.toasts.push(format!("Status refresh: {}", change.text), change.level);
+                }
+            }
+            app.host_status = Some(status);
+            app.status_read = Some(dates::now());
+        }
+        Message::Notify { text, level } => app.toasts.push(text, level),
+        // A result for any other operation is stale and dropped
+        Message::OperationFinished { id, outcome } if app.operation == Some(id) => {
+            app.operation = None;
+            if app.state.kind() == StateKind::Installing {
+                // The badges should show what was just installed without waiting for the timer
+                status::refresh_now();
+            }
+            if matches!(app.state.kind(), StateKind::Installing | StateKind::Applying | StateKind::Running) {
+                app.transition(AppState::Result(outcome));
+            }
+        }
+        Message::OperationFinished { .. } => {}
+    }
+    ControlFlow::Continue(())
+}
+
+/// Handles a key typed or replayed, keeping track of the last action and the recording.
+fn press(app: &mut App, key: KeyEvent) -> ControlFlow<()> {
+    app.last_key = Instant::now();
+    if app.locked {
+        app.unlock_key(key);
+        return ControlFlow::Continue(());
+    }
+    app.note_key(&key);
+    let flow = handle_key(app, key);
+    app.sync_screens();
+    app.update_preview();
+    if app.state == AppState::Menu && !app.action.is_empty() {
+        app.last_action = std::mem::take(&mut app.action);
+    }
+    flow
+}
+
+fn handle_key(app: &mut App, key: KeyEvent) -> ControlFlow<()> {
+    if key.code == KeyCode::Tab {
+        app.split = !app.split;
+        return ControlFlow::Continue(());
+    }
+    match &app.state {
+        AppState::Menu => match key.code {
+            KeyCode::Char('q') => match app.quit_confirmation() {
+                Some(confirm) => app.transition(confirm),
+                None => return ControlFlow::Break(()),
+            },
+            KeyCode::Down | KeyCode::Char('j') => app.next(),
+            KeyCode::Up | KeyCode::Char('k') => app.previous(),
+            KeyCode::Char('.') if app.last_action.is_empty() => {
+                app.transition(AppState::Result(OperationOutcome::failed(OperationError::Refused(
+                    "Nothing to repeat yet: choose an action from the menu first.".to_string(),
+                ))));
+            }
+            KeyCode::Char('.') => app.replay = app.last_action.clone().into(),
+            KeyCode::Char('m') => app.toggle_recording(),
+            KeyCode::Enter => {
+                let action = app
+                    .menu_state
+                    .selected()
+                    .and_then(|i| app.menu_items.get(i))
+                    .map(|(_, action)| *action);
+                match action {
+                    Some(action) if app.offline && action.needs_internet().is_some() => {
+                        app.transition(AppState::Result(OperationOutcome::failed(OperationError::Refused(format!(
+                            "Unavailable in offline mode: this action {}.\n\n\
+                            Turn Offline Mode off from the menu to use it.",
+                            action.needs_internet().unwrap_or_default()
+                        )))));
+                    }
+                    Some(MenuAction::CheckWinget) => {
+                        app.transition(AppState::Result(platform::current().package_manager_status()));
+                    }
+                    Some(MenuAction::InstallWinget) => {
+                        app.transition(AppState::Installing(InstallItem::Winget));
+                    }
+                    Some(MenuAction::CheckNetBird) => {
+                        app.transition(AppState::Result(platform::current().vpn_status()));
+                    }
+                    Some(MenuAction::InstallNetBird) => {
+                        app.transition(AppState::Installing(InstallItem::NetBird));
+                    }
+                    Some(MenuAction::InstallAdminCenter) => {
+                        app.input = "443".to_string();
+                        app.transition(AppState::Input(InputPurpose::AdminCenterOptions));
+                    }
+                    Some(MenuAction::Snmp) => {
+                        app.input.clear();
+                        app.transition(AppState::Input(InputPurpose::SnmpOptions));
+                    }
+                    Some(MenuAction::Agents) => match app.table.open(TableKind::Agents) {
+                        Ok(()) => {
+                            if app.table.rows.is_empty() {
+                                app.table.status = format!(
+                                    "No agents defined yet. Press e to edit {}",
+                                    agents::catalog_path().display()
+                                );
+                            }
+                            app.transition(AppState::Table);
+                        }
+                        Err(message) => {
+                            app.transition(AppState::Result(OperationOutcome::failed(message)));
+                        }
+                    },
+                    Some(MenuAction::NetBirdConflicts) => {
+                        match app.open_checklist(ChecklistKind::NetBirdConflicts) {
+                            Ok(()) => app.transition(AppState::Checklist),
+                            Err(message) => {
+                                app.transition(AppState::Result(OperationOutcome::failed(message)));
+                            }
+                        }
+                    }
+                    Some(MenuAction::BackupRoles) => {
+                        app.input.clear();
+                        app.transition(AppState::Input(InputPurpose::BackupNote));
+                    }
+                    Some(MenuAction::BackupCatalog) => match app.table.open(TableKind::BackupCatalog) {
+                        Ok(()) => app.transition(AppState::Table),
+                        Err(message) => {
+                            app.transition(AppState::Result(OperationOutcome::failed(message)));
+                        }
+                    },
+                    Some(MenuAction::GoldenBaselines) => match app.table.open(TableKind::GoldenBaselines) {
+                        Ok(()) => app.transition(AppState::Table),
+                        Err(message) => {
+                            app.transition(AppState::Result(OperationOutcome::failed(message)));
+                        }
+                    },
+                    Some(MenuAction::RestoreRoles) => {
+                        app.transition(AppState::FileBrowser(BrowsePurpose::RestoreRoles));
+                    }
+                    Some(MenuAction::WingetConfigure) => {
+                        app.transition(AppState::FileBrowser(BrowsePurpose::WingetConfig));
+                    }
+                    Some(MenuAction::DscExport) => app.transition(AppState::Running(Task::DscExport)),
+                    Some(MenuAction::RemoteRestore) => {
+                        app.input.clear();
+                        app.transition(AppState::Input(InputPurpose::RemoteBackupSource));
+                    }
+                    Some(MenuAction::SqlExpress) => {
+                        app.input = "SQLEXPRESS".to_string();
+                        app.transition(AppState::Input(InputPurpose::SqlInstance));
+                    }
+                    Some(MenuAction::VcRedist) => match vcredist::install_plan() {
+                        Ok(plan) => app.review_plan(plan),
+                        Err(message) => {
+                            app.transition(AppState::Result(OperationOutcome::failed(message)));
+                        }
+                    },
+                    Some(MenuAction::RollbackRestore) => match features::rollback_plan() {
+                        Ok(plan) => app.review_plan(plan),
+                        Err(message) => {
+                            app.transition(AppState::Result(OperationOutcome::failed(message)));
+                        }
+                    },
+                    Some(MenuAction::SystemStateBackup) => match app.table.open(TableKind::SystemStateBackups) {
+                        Ok(()) => app.transition(AppState::Table),
+                        Err(message) => {
+                            app.transition(AppState::Result(OperationOutcome::failed(message)));
+                        }
+                    },
+                    Some(MenuAction::ClusterValidation) => {
+                        app.input.clear();
+                        app.transition(AppState::Input(InputPurpose::ClusterNodes));
+                    }
+                    Some(MenuAction::Validation) => {
+                        if let Err(message) = app.open_checklist(ChecklistKind::Validation) {
+                            app.transition(AppState::Result(OperationOutcome::failed(message)));
+                        }
+                    }
+                    Some(MenuAction::DotNet) => {
+                        if let Err(message) = app.open_checklist(ChecklistKind::DotNet) {
+                            app.transition(AppState::Result(OperationOutcome::failed(message)));
+                        }
+                    }
+                    Some(MenuAction::CertificateRequest) => {
+                        app.input = certs::default_names().0;
+                        app.transition(AppState::Input(InputPurpose::CsrSubject));
+                    }
+                    Some(MenuAction::CompleteCertificateRequest) => {
+                        app.current_dir = backup_dir();
+                        app.transition(AppState::FileBrowser(BrowsePurpose::IssuedCertificate));
+                    }
+                    Some(MenuAction::DcDiag) => {
+                        if let Err(message) = app.open_checklist(ChecklistKind::DcDiag) {
+                            app.transition(AppState::Result(OperationOutcome::failed(message)));
+                        }
+                    }
+                    Some(MenuAction::Replication) => {
+                        if let Err(message) = app.open_checklist(ChecklistKind::Replication) {
+                            app.transition(AppState::Result(OperationOutcome::failed(message)));
+                        }
+                    }
+                    Some(MenuAction::GpUpdate) => {
+                        app.transition(AppState::Running(Task::GpUpdate));
+                    }
+                    Some(MenuAction::GpResult) => match policy::gpresult_summary() {
+                        Ok(text) => app.show_text(" Group Policy Result ".to_string(), text),
+                        Err(message) => {
+                            app.transition(AppState::Result(OperationOutcome::failed(message)));
+                        }
+                    },
+                    Some(MenuAction::Rds) => {
+                        if let Err(message) = app.open_checklist(ChecklistKind::Rds) {
+                            app.transition(AppState::Result(OperationOutcome::failed(message)));
+                        }
+                    }
+                    Some(MenuAction::DockerHost) => {
+                        app.transition(AppState::Confirm {
+                            message: "Set up this server as a Windows container host?\n\n  \
+                                1. Install the Containers feature (a restart may be needed; run this again afterwards)\n  \
+                                2. Download the latest Docker engine from download.docker.com into Program Files\\docker\n  \
+                                3. Register and start the docker service\n  \
+                                4. Verify with docker version\n\n\
+                                This runs as a background job. (y/n)"
+                                .to_string(),
+                            action: ConfirmAction::SetupDocker,
+                        });
+                    }
+                    Some(MenuAction::Sysmon) => {
+                        app.transition(AppState::FileBrowser(BrowsePurpose::SysmonConfig));
+                    }
+                    Some(MenuAction::OffsiteBackups) => match app.table.open(TableKind::OffsiteBackups) {
+                        Ok(()) => {
+                            app.table.status = match offsite::destination() {
+                                Some(Ok(destination)) => format!("Destination: {}", destination.describe()),
+                                Some(Err(message)) => message,
+                                None => "No offsite destination: set offsite in profile.ini (Profiles, n).".to_string(),
+                            };
+                            app.transition(AppState::Table);
+                        }
+                        Err(message) => {
+                            app.transition(AppState::Result(OperationOutcome::failed(message)));
+                        }
+                    },
+                    Some(MenuAction::ViewLastBackup) => match app.table.open(TableKind::LastBackup) {
+                        Ok(()) => {
+                            if let Some(path) = features::latest_backup() {
+                                app.table.status = path.display().to_string();
+                            }
+                            app.transition(AppState::Table);
+                        }
+                        Err(message) => {
+                            app.transition(AppState::Result(OperationOutcome::failed(message)));
+                        }
+                    },
+                    Some(MenuAction::Features) => match app.table.open(TableKind::Features) {
+                        Ok(()) => app.transition(AppState::Table),
+                        Err(message) => {
+                            app.transition(AppState::Result(OperationOutcome::failed(message)));
+                        }
+                    },
+                    Some(MenuAction::DownloadCache) => match app.table.open(TableKind::DownloadCache) {
+                        Ok(()) => {
+                            app.table.status = cache::summary();
+                            app.transition(AppState::Table);
+                        }
+                        Err(message) => {
+                            app.transition(AppState::Result(OperationOutcome::failed(message)));
+                        }
+                    },
+                    Some(MenuAction::Jobs) => match app.table.open(TableKind::Jobs) {
+                        Ok(()) => app.transition(AppState::Table),
+                        Err(message) => {
+                            app.transition(AppState::Result(OperationOutcome::failed(message)));
+                        }
+                    },
+                    Some(MenuAction::SecurityAudit) => {
+                        match app.open_checklist(ChecklistKind::SecurityAudit) {
+                            Ok(()) => app.transition(AppState::Checklist),
+                            Err(message) => {
+                                app.transition(AppState::Result(OperationOutcome::failed(message)));
+                            }
+                        }
+                    }
+                    Some(MenuAction::Hardening) => {
+                        match app.open_checklist(ChecklistKind::Hardening) {
+                            Ok(()) => app.transition(AppState::Checklist),
+                            Err(message) => {
+                                app.transition(AppState::Result(OperationOutcome::failed(message)));
+                            }
+                        }
+                    }
+                    Some(MenuAction::Telemetry) => match app.open_checklist(ChecklistKind::Telemetry) {
+                        Ok(()) => app.transition(AppState::Checklist),
+                        Err(message) => {
+                            app.transition(AppState::Result(OperationOutcome::failed(message)));
+                        }
+                    },
+                    Some(MenuAction::DeviceGuard) => match app.open_checklist(ChecklistKind::DeviceGuard) {
+                        Ok(()) => app.transition(AppState::Checklist),
+                        Err(message) => {
+                            app.transition(AppState::Result(OperationOutcome::failed(message)));
+                        }
+                    },
+                    Some(MenuAction::Schannel) => match app.table.open(TableKind::Schannel) {
+                        Ok(()) => app.transition(AppState::Table),
+                        Err(message) => {
+                            app.transition(AppState::Result(OperationOutcome::failed(message)));
+                        }
+                    },
+                    Some(MenuAction::AuditPolicy) => match security::audit_policy_plan() {
+                        Ok(plan) => app.review_plan(plan),
+                        Err(message) => {
+                            app.transition(AppState::Result(OperationOutcome::failed(message)));
+                        }
+                    },
+                    Some(MenuAction::AccountPolicy) => match app.table.open(TableKind::AccountPolicy) {
+                        Ok(()) => app.transition(AppState::Table),
+                        Err(message) => {
+                            app.transition(AppState::Result(OperationOutcome::failed(message)));
+                        }
+                    },
+                    Some(MenuAction::RotateAdminPassword) => match localadmin::account() {
+                        Ok(account) if account.laps => {
+                            app.transition(AppState::Confirm {
+                                message: format!(
+                                    "Windows LAPS manages the {} password.\n\n\
+                                    Rotate it now with Reset-LapsPassword? The new password is \
+                                    backed up to the directory, not shown here.\n\n(y/n)",
+                                    account.name
+                                ),
+                                action: ConfirmAction::RotateAdminPassword { save: false },
+                            });
+                        }
+                        Ok(account) => {
+                            app.input = "no".to_string();
+                            app.transition(AppState::Input(InputPurpose::AdminPasswordSave {
+                                name: account.name,
+                                last_set: account.password_last_set,
+                            }));
+                        }
+                        Err(message) => {
+                            app.transition(AppState::Result(OperationOutcome::failed(message)));
+                        }
+                    },
+                    Some(MenuAction::ListeningPorts) => match app.table.open(TableKind::ListeningPorts) {
+                        Ok(()) => app.transition(AppState::Table),
+                        Err(message) => {
+                            app.transition(AppState::Result(OperationOutcome::failed(message)));
+                        }
+                    },
+                    Some(MenuAction::ConnectivityTest) => {
+                        app.input.clear();
+                        app.transition(AppState::Input(InputPurpose::ConnectivityTargets));
+                    }
+                    Some(MenuAction::BackupAcls) => {
+                        app.input.clear();
+                        app.transition(AppState::Input(InputPurpose::AclFolders));
+                    }
+                    Some(MenuAction::RestoreAcls) => {
+                        app.current_dir = backup_dir();
+                        app.transition(AppState::FileBrowser(BrowsePurpose::AclBackup));
+                    }
+                    Some(MenuAction::Fsrm) => match app.table.open(TableKind::FsrmQuotas) {
+                        Ok(()) => app.transition(AppState::Table),
+                        Err(message) => {
+                            app.transition(AppState::Result(OperationOutcome::failed(message)));
+                        }
+                    },
+                    Some(MenuAction::VirtualMachines) => match app.table.open(TableKind::VirtualMachines) {
+                        Ok(()) => app.transition(AppState::Table),
+                        Err(message) => {
+                            app.transition(AppState::Result(OperationOutcome::failed(message)));
+                        }
+                    },
+                    Some(MenuAction::ImportVms) => {
+                        app.current_dir = backup_dir();
+                        app.transition(AppState::FileBrowser(BrowsePurpose::VmExport));
+                    }
+                    Some(MenuAction::Dedup) => match app.table.open(TableKind::DedupVolumes) {
+                        Ok(()) => app.transition(AppState::Table),
+                        Err(message) => {
+                            app.transition(AppState::Result(OperationOutcome::failed(message)));
+                        }
+                    },
+                    Some(MenuAction::ServiceControl) => {
+                        app.input.clear();
+                        app.transition(AppState::Input(InputPurpose::ServiceCommand));
+                    }
+                    Some(MenuAction::ProvisioningKit) => {
+                        app.input.clear();
+                        app.transition(AppState::Input(InputPurpose::KitDestination));
+                    }
+                    Some(MenuAction::CopyData) => {
+                        app.input.clear();
+                        app.transition(AppState::Input(InputPurpose::CopySource));
+                    }
+                    Some(MenuAction::FirewallRule) => {
+                        app.input.clear();
+                        app.transition(AppState::Input(InputPurpose::FirewallRuleName));
+                    }
+                    Some(MenuAction::ShareTest) => {
+                        app.input.clear();
+                        app.transition(AppState::Input(InputPurpose::SharePath));
+                    }
+                    Some(MenuAction::Proxy) => match app.open_checklist(ChecklistKind::Proxy) {
+                        Ok(()) => app.transition(AppState::Checklist),
+                        Err(message) => {
+                            app.transition(AppState::Result(OperationOutcome::failed(message)));
+                        }
+                    },
+                    Some(MenuAction::Wsus) => match app.open_checklist(ChecklistKind::Wsus) {
+                        Ok(()) => app.transition(AppState::Checklist),
+                        Err(message) => {
+                            app.transition(AppState::Result(OperationOutcome::failed(message)));
+                        }
+                    },
+                    Some(MenuAction::DnsLookup) => {
+                        app.input.clear();
+                        app.transition(AppState::Input(InputPurpose::DnsLookup));
+                    }
+                    Some(MenuAction::Routes) => match app.table.open(TableKind::PersistentRoutes) {
+                        Ok(()) => app.transition(AppState::Table),
+                        Err(message) => {
+                            app.transition(AppState::Result(OperationOutcome::failed(message)));
+                        }
+                    },
+                    Some(MenuAction::Wsl) => match app.table.open(TableKind::WslDistros) {
+                        Ok(()) => app.transition(AppState::Table),
+                        Err(message) => {
+                            app.transition(AppState::Result(OperationOutcome::failed(message)));
+                        }
+                    },
+                    Some(MenuAction::NetworkAdapters) => match app.table.open(TableKind::NetworkAdapters) {
+                        Ok(()) => app.transition(AppState::Table),
+                        Err(message) => {
+                            app.transition(AppState::Result(OperationOutcome::failed(message)));
+                        }
+                    },
+                    Some(MenuAction::IisSites) => match app.table.open(TableKind::IisSites) {
+                        Ok(()) => app.transition(AppState::Table),
+                        Err(message) => {
+                            app.transition(AppState::Result(OperationOutcome::failed(message)));
+                        }
+                    },
+                    Some(MenuAction::HostsFile) => match app.table.open(TableKind::HostsFile) {
+                        Ok(()) => app.transition(AppState::Table),
+                        Err(message) => {
+                            app.transition(AppState::Result(OperationOutcome::failed(message)));
+                        }
+                    },
+                    Some(MenuAction::Environment) => {
+                        match app.table.open(TableKind::EnvironmentVariables) {
+                            Ok(()) => app.transition(AppState::Table),
+                            Err(message) => {
+                                app.transition(AppState::Result(OperationOutcome::failed(message)));
+                            }
+                        }
+                    }
+                    Some(MenuAction::ScheduledTasks) => {
+                        match app.table.open(TableKind::ScheduledTasks { include_system: false }) {
+                            Ok(()) => app.transition(AppState::Table),
+                            Err(message) => {
+                                app.transition(AppState::Result(OperationOutcome::failed(message)));
+                            }
+                        }
+                    }
+                    Some(MenuAction::Processes) => match app.table.open(TableKind::Processes) {
+                        Ok(()) => app.transition(AppState::Table),
+                        Err(message) => {
+                            app.transition(AppState::Result(OperationOutcome::failed(message)));
+                        }
+                    },
+                    Some(MenuAction::Macros) => match app.table.open(TableKind::Macros) {
+                        Ok(()) => app.transition(AppState::Table),
+                        Err(message) => {
+                            app.transition(AppState::Result(OperationOutcome::failed(message)));
+                        }
+                    },
+                    Some(MenuAction::Profiles) => match app.table.open(TableKind::Profiles) {
+                        Ok(()) => app.transition(AppState::Table),
+                        Err(message) => {
+                            app.transition(AppState::Result(OperationOutcome::failed(message)));
+                        }
+                    },
+                    Some(MenuAction::RdpSessions) => match app.table.open(TableKind::RdpSessions) {
+                        Ok(()) => app.transition(AppState::Table),
+                        Err(message) => {
+                            app.transition(AppState::Result(OperationOutcome::failed(message)));
+                        }
+                    },
+                    Some(MenuAction::HealthRepair) => {
+                        app.transition(AppState::Confirm {
+                            message: "Check and repair Windows system files?\n\n  \
+                                1. DISM /ScanHealth checks the component store\n  \
+                                2. DISM /RestoreHealth repairs it from Windows Update (only if corruption is found)\n  \
+                                3. sfc /scannow checks and repairs protected system files\n\n\
+                                This can take 15-60 minutes and runs as a background job. (y/n)"
+                                .to_string(),
+                            action: ConfirmAction::HealthRepair,
+                        });
+                    }
+                    Some(MenuAction::FixSpooler) => {
+                        app.transition(AppState::Confirm {
+                            message: "Stop the Print Spooler, delete every queued job from the spool directory\n\
+                                and start it again?\n\n\
+                                All pending print jobs on this server are lost. (y/n)"
+                                .to_string(),
+                            action: ConfirmAction::FixSpooler,
+                        });
+                    }
+                    Some(MenuAction::FreeUpSpace) => {
+                        app.input = "temp updates components".to_string();
+                        app.transition(AppState::Input(InputPurpose::CleanupCategories));
+                    }
+                    Some(MenuAction::CleanTemp) => app.review_plan(cleanup::temp_residue_plan()),
+                    Some(MenuAction::PowerPlan) => {
+                        let next = match performance::active_power_plan() {
+                            Ok(plan) => AppState::Confirm {
+                                message: format!(
+                                    "Current power plan: {}\n\nSwitch to High Performance? (y/n)",
+                                    plan
+                                ),
+                                action: ConfirmAction::SetHighPerformance,
+                            },
+                            Err(message) => AppState::Result(OperationOutcome::failed(message)),
+                        };
+                        app.transition(next);
+                    }
+                    Some(MenuAction::Pagefile) => match performance::pagefile_spec() {
+                        Ok(spec) => {
+                            app.input = performance::editable_pagefile_spec(&spec).unwrap_or_default();
+                            app.transition(AppState::Input(InputPurpose::Pagefile(spec)));
+                        }
+                        Err(message) => {
+                            app.transition(AppState::Result(OperationOutcome::failed(message)));
+                        }
+                    },
+                    Some(MenuAction::CrashDump) => match app.open_checklist(ChecklistKind::CrashDump) {
+                        Ok(()) => app.transition(AppState::Checklist),
+                        Err(message) => {
+                            app.transition(AppState::Result(OperationOutcome::failed(message)));
+                        }
+                    },
+                    Some(MenuAction::Uptime) => match uptime::uptime_report() {
+                        Ok(text) => app.show_text(" Uptime & Last Boot ".to_string(), text),
+                        Err(message) => {
+                            app.transition(AppState::Result(OperationOutcome::failed(message)));
+                        }
+                    },
+                    Some(MenuAction::History) => match history::read() {
+                        Ok(text) => app.show_text(" History ".to_string(), text),
+                        Err(message) => {
+                            app.transition(AppState::Result(OperationOutcome::failed(message)));
+                        }
+                    },
+                    Some(MenuAction::Checkpoints) => {
+                        app.checkpoint = !app.checkpoint;
+                        let message = if app.checkpoint {
+                            "Checkpoints are on.\n\n\
+                            Before change plans, security fixes, power plan and pagefile changes, \
+                            registry keys, security and audit policy, the power plan and the installed \
+                            features are saved to a Checkpoint_<timestamp> folder in the backup \
+                            directory (plus a System Restore point where Windows supports it). \
+                            Each checkpoint is listed in History."
+                        } else {
+                            "Checkpoints are off."
+                        };
+                        app.transition(AppState::Result(OperationOutcome::Success(message.to_string())));
+                    }
+                    Some(MenuAction::ExportMode) => {
+                        let (success, message) = app.toggle_export_mode();
+                        app.transition(AppState::Result((success, message).into()));
+                    }
+                    Some(MenuAction::Offline) => {
+                        let (success, message) = app.toggle_offline();
+                        app.transition(AppState::Result((success, message).into()));
+                    }
+                    Some(MenuAction::Exit) => match app.quit_confirmation() {
+                        Some(confirm) => app.transition(confirm),
+                        None => return ControlFlow::Break(()),
+                    },
+                    None => {}
+                }
+            }
+            _ => {}
+        },
+        AppState::FileBrowser(purpose) => {
+            let purpose = *purpose;
+            match key.code {
+                KeyCode::Esc | KeyCode::Char('q') => app.back(),
+                KeyCode::Down | KeyCode::Char('j') => app.file_browser_next(),
+                KeyCode::Up | KeyCode::Char('k') => app.file_browser_previous(),
+                KeyCode::Enter => {
+                    if let Some(file) = app.file_browser_select() {
+                        let next = match purpose {
+                            BrowsePurpose::RestoreRoles => match features::restore_plan(&file) {
+                                Ok(plan) => app.stage_plan(plan),
+                                Err(message) => AppState::Result(OperationOutcome::failed(message)),
+                            },
+                            BrowsePurpose::VmExport => {
+                                app.input = hyperv::default_path();
+                                AppState::Input(InputPurpose::ImportVmsTo(file))
+                            }
+                            BrowsePurpose::AclBackup => match acls::restore_plan(&file) {
+                                Ok(plan) => app.stage_plan(plan),
+                                Err(message) => AppState::Result(OperationOutcome::failed(message)),
+                            },
+                            BrowsePurpose::SysmonConfig => {
+                                AppState::Installing(InstallItem::Sysmon(Some(file)))
+                            }
+                            BrowsePurpose::WingetConfig => match winget_config::describe(&file) {
+                                Ok(message) => {
+                                    AppState::Confirm { message, action: ConfirmAction::WingetConfigure(file) }
+                                }
+                                Err(message) => AppState::Result(OperationOutcome::failed(message)),
+                            },
+                            BrowsePurpose::IssuedCertificate => match certs::accept(&file) {
+                                Ok((thumbprint, imported)) => {
+                                    app.input = "iis Default Web Site 443".to_string();
+                                    AppState::Input(InputPurpose::CertificateBinding { thumbprint, imported })
+                                }
+                                Err(message) => AppState::Result(OperationOutcome::failed(message)),
+                            },
+                        };
+                        app.transition(next);
+                    }
+                }
+                KeyCode::Char('b') if purpose == BrowsePurpose::SysmonConfig => {
+                    app.transition(AppState::Installing(InstallItem::Sysmon(None)));
+                }
+                KeyCode::Backspace => {
+                    // Go to parent directory
+                    if let Some(parent) = app.current_dir.parent() {
+                        app.current_dir = parent.to_path_buf();
+                        app.load_directory();
+                    }
+                }
+                _ => {}
+            }
+        }
+        AppState::Checklist => match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => app.back(),
+            KeyCode::Down | KeyCode::Char('j') => app.checklist_next(),
+            KeyCode::Up | KeyCode::Char('k') => app.checklist_previous(),
+            KeyCode::Char('r')
+                if matches!(
+                    app.checklist_kind,
+                    ChecklistKind::Validation
+                        | ChecklistKind::DcDiag
+                        | ChecklistKind::Replication
+                        | ChecklistKind::Proxy
+                        | ChecklistKind::Wsus
+                        | ChecklistKind::Telemetry
+                        | ChecklistKind::DeviceGuard
+                        | ChecklistKind::CrashDump
+                ) =>
+            {
+                if let Err(message) = app.open_checklist(app.checklist_kind) {
+                    app.transition(AppState::Result(OperationOutcome::failed(message)));
+                }
+            }
+            KeyCode::Char('e') if app.checklist_kind == ChecklistKind::Validation => {
+                if let Err(message) = validation::edit_checks() {
+                    app.transition(AppState::Result(OperationOutcome::failed(message)));
+                }
+            }
+            KeyCode::Char('w') if app.checklist_kind == ChecklistKind::Validation => {
+                let next = match validation::write_report(&app.checklist) {
+                    Ok(path) => AppState::Result(OperationOutcome::Success(format!(
+                        "Sign-off report saved to:\n{}",
+                        path.display()
+                    ))),
+                    Err(message) => AppState::Result(OperationOutcome::failed(message)),
+                };
+                app.transition(next);
+            }
+            KeyCode::Char('p') if app.checklist_kind == ChecklistKind::Proxy => {
+                app.input = proxy::spec();
+                app.transition(AppState::Input(InputPurpose::ProxySettings));
+            }
+            KeyCode::Char('i') if app.checklist_kind == ChecklistKind::Proxy => {
+                let result = proxy::import_user().and_then(|_| app.open_checklist(ChecklistKind::Proxy));
+                if let Err(message) = result {
+                    app.transition(AppState::Result(OperationOutcome::failed(message)));
+                }
+            }
+            KeyCode::Char('s') if app.checklist_kind == ChecklistKind::Wsus => {
+                app.input = wsus::spec();
+                app.transition(AppState::Input(InputPurpose::WsusSettings));
+            }
+            KeyCode::Char('d') if app.checklist_kind == ChecklistKind::Wsus => {
+                let (success, message) = match wsus::detect_now() {
+                    Ok(message) => (true, message),
+                    Err(message) => (false, message),
+                };
+                app.transition(AppState::Result((success, message).into()));
+            }
+            KeyCode::Char('l') if app.checklist_kind == ChecklistKind::Telemetry => {
+                app.input.clear();
+                app.transition(AppState::Input(InputPurpose::TelemetryLevel));
+            }
+            KeyCode::Char('t') if app.checklist_kind == ChecklistKind::CrashDump => {
+                app.input = crashdump::type_spec();
+                app.transition(AppState::Input(InputPurpose::CrashDumpType));
+            }
+            KeyCode::Char('l') if app.checklist_kind == ChecklistKind::Rds => {
+                app.input = rds::licensing_spec();
+                app.transition(AppState::Input(InputPurpose::RdsLicensing));
+            }
+            KeyCode::Enter if app.offline && app.checklist_kind == ChecklistKind::DotNet => {
+                app.transition(AppState::Result(OperationOutcome::failed(OperationError::Refused(
+                    "Runtimes are installed with winget, which offline mode blocks.".to_string(),
+                ))));
+            }
+            KeyCode::Enter => {
+                if let Some(i) = app.checklist_state.selected() {
+                    if let Some(item) = app.checklist.get(i) {
+                        if item.status != CheckStatus::Pass {
+                            if let Some(ref remediation) = item.remediation {
+                                let message = format!(
+                                    "{}: {}\n\n{}\n\nApply this change? (y/n)",
+                                    item.name, item.detail, remediation.summary
+                                );
+                                app.transition(AppState::Confirm {
+                                    message,
+                                    action: ConfirmAction::Remediate(i),
+                                });
+                            }
+                        }
+                    }
+                }
+            }
+            _ => {}
+        },
+        AppState::Table if app.table.editing_filter => match key.code {
+            KeyCode::Enter | KeyCode::Esc => app.table.editing_filter = false,
+            KeyCode::Backspace => app.table.pop_filter(),
+            KeyCode::Char(c) => app.table.push_filter(c),
+            _ => {}
+        },
+        AppState::Table => match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => app.back(),
+            KeyCode::Down | KeyCode::Char('j') => app.table.next(),
+            KeyCode::Up | KeyCode::Char('k') => app.table.previous(),
+            KeyCode::Char('/') => app.table.editing_filter = true,
+            KeyCode::Char('o') => {
+                if let Err(message) = app.table.cycle_sort() {
+                    app.transition(AppState::Result(OperationOutcome::failed(message)));
+                }
+            }
+            KeyCode::Char('r') => {
+                if let Err(message) = app.table.refresh() {
+                    app.transition(AppState::Result(OperationOutcome::failed(message)));
+                }
+            }
+            KeyCode::Enter | KeyCode::Char('e') => app.table_open_selected(),
+            KeyCode::Char('a') => app.table_add(),
+            KeyCode::Char('d') => app.table_remove(),
+            KeyCode::Char('p') if app.table.kind == TableKind::DownloadCache && !app.table.rows.is_empty() => {
+                app.transition(AppState::Confirm {
+                    message: format!("Delete every cached download?\n\n{}\n\n(y/n)", cache::summary()),
+                    action: ConfirmAction::PurgeCache(None),
+                });
+            }
+            KeyCode::Char('p') if app.table.kind == TableKind::EnvironmentVariables => {
+                app.table.status = environment::reload_process_path().unwrap_or_else(|e| e);
+            }
+            KeyCode::Char('f') if matches!(app.table.kind, TableKind::DnsLookup(_)) => {
+                app.table.status = match network::flush_dns_cache() {
+                    Ok(()) => "DNS client cache flushed. Press r to resolve again.".to_string(),
+                    Err(e) => format!("Failed to flush DNS cache: {}", e),
+                };
+            }
+            KeyCode::Char(c @ ('i' | 'f')) if app.table.kind == TableKind::Agents => {
+                app.table_agent_action(c)
+            }
+            KeyCode::Char('i') if app.table.kind == TableKind::Features => app.table_install_feature(),
+            KeyCode::Char('g') if app.table.kind == TableKind::BackupCatalog => {
+                if let Some(row) = app.table.selected_row() {
+                    let backup = row[0].clone();
+                    // Several role types can't be edited at once, so those start empty
+                    app.input = if row[4].contains(',') { String::new() } else { row[4].clone() };
+                    app.transition(AppState::Input(InputPurpose::GoldenRole(backup)));
+                }
+            }
+            KeyCode::Char('v') if app.table.kind == TableKind::NetworkAdapters => {
+                if let Some(row) = app.table.selected_row() {
+                    let adapter = row[0].clone();
+                    app.input = row[5].clone();
+                    app.transition(AppState::Input(InputPurpose::AdapterVlan(adapter)));
+                }
+            }
+            KeyCode::Char('u') if app.table.kind == TableKind::OffsiteBackups => {
+                let status = offsite::retry().unwrap_or_else(|e| e);
+                let next = app.refresh_table_with_status(status);
+                app.transition(next);
+            }
+            KeyCode::Char('b') if app.table.kind == TableKind::SystemStateBackups => {
+                app.input.clear();
+                app.transition(AppState::Input(InputPurpose::SystemStateTarget));
+            }
+            KeyCode::Char('v')
+                if matches!(app.table.kind, TableKind::Features | TableKind::SystemStateBackups) =>
+            {
+                if let Err(message) = app.table.open(TableKind::Jobs) {
+                    app.transition(AppState::Result(OperationOutcome::failed(message)));
+                }
+            }
+            KeyCode::Char('b') if app.table.kind == TableKind::Schannel => match tls::baseline_plan() {
+                Ok(plan) => app.review_plan(plan),
+                Err(message) => app.table.status = message,
+            },
+            KeyCode::Char('s') if app.table.kind == TableKind::FsrmQuotas => {
+                if let Err(message) = app.table.open(TableKind::FsrmFileScreens) {
+                    app.table.status = message;
+                }
+            }
+            KeyCode::Char(c @ ('s' | 't' | 'c' | 'x')) if app.table.kind == TableKind::VirtualMachines => {
+                app.table_vm_action(c)
+            }
+            KeyCode::Char('n') if app.table.kind == TableKind::Profiles => {
+                if let Some(row) = app.table.selected_row() {
+                    let name = (row[1] != "(default)").then_some(row[1].as_str());
+                    app.table.status = profiles::edit(name).unwrap_or_else(|e| e);
+                }
+            }
+            KeyCode::Char(c @ ('m' | 'c' | 'x')) if app.table.kind == TableKind::RdpSessions => {
+                app.table_session_action(c)
+            }
+            KeyCode::Char(c @ ('s' | 't' | 'c' | 'h')) if app.table.kind == TableKind::IisSites => {
+                app.table_iis_action(c)
+            }
+            KeyCode::Char(c @ ('s' | 't' | 'm')) => app.table_task_action(c),
+            _ => {}
+        },
+        AppState::TextView => match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => app.back(),
+            KeyCode::Down | KeyCode::Char('j') => {
+                app.text_scroll = app.text_scroll.saturating_add(1);
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                app.text_scroll = app.text_scroll.saturating_sub(1);
+            }
+            KeyCode::PageDown => app.text_scroll = app.text_scroll.saturating_add(10),
+            KeyCode::PageUp => app.text_scroll = app.text_scroll.saturating_sub(10),
+            _ => {}
+        },
+        AppState::Input(purpose) => {
+            let purpose = purpose.clone();
+            match key.code {
+                KeyCode::Esc => app.transition(purpose.return_state()),
+                KeyCode::Backspace => {
+                    app.input.pop();
+                }
+                KeyCode::Char(c) => app.input.push(c),
+                KeyCode::Enter => {
+                    let value = app.input.trim().to_string();
+                    let next = match purpose {
+                        InputPurpose::ConnectivityTargets => {
+                            AppState::Running(Task::ConnectivityTest(value))
+                        }
+                        InputPurpose::DnsLookup => match app.table.open(TableKind::DnsLookup(value)) {
+                            Ok(()) => AppState::Table,
+                            Err(message) => AppState::Result(OperationOutcome::failed(message)),
+                        },
+                        InputPurpose::RemoteBackupSource => match app.table.open(TableKind::RemoteBackups(value)) {
+                            Ok(()) => AppState::Table,
+                            Err(message) => AppState::Result(OperationOutcome::failed(message)),
+                        },
+                        InputPurpose::AddRoute => {
+                            let status = network::add_route(&value).unwrap_or_else(|e| e);
+                            app.refresh_table_with_status(status)
+                        }
+                        InputPurpose::AddHostsEntry => {
+                            let status = hosts::add_entry(&value).unwrap_or_else(|e| e);
+                            app.refresh_table_with_status(status)
+                        }
+                        InputPurpose::AddEnvVar => {
+                            let status = environment::set_from_assignment(&value).unwrap_or_else(|e| e);
+                            app.refresh_table_with_status(status)
+                        }
+                        InputPurpose::EditEnvVar(name) => {
+                            let status = environment::set_variable(&name, &value).unwrap_or_else(|e| e);
+                            app.refresh_table_with_status(status)
+                        }
+                        InputPurpose::AddPathEntry => {
+                            let status = environment::add_path_entry(&value).unwrap_or_else(|e| e);
+                            app.refresh_table_with_status(status)
+                        }
+                        InputPurpose::Pagefile(_) => {
+                            let (success, message) =
+                                app.with_checkpoint("Pagefile change", |_| performance::set_pagefile(&value));
+                            AppState::Result((success, message).into())
+                        }
+                        InputPurpose::AgentFleet(agent) => AppState::Running(Task::InstallAgentFleet {
+                            agent,
+                            hosts: agents::split_hosts(&value),
+                        }),
+                        InputPurpose::AdapterProperty { adapter, property } => {
+                            let status = network::set_adapter_property(&adapter, &property, &value)
+                                .unwrap_or_else(|e| e);
+                            app.refresh_table_with_status(status)
+                        }
+                        InputPurpose::AccountPolicyValue(setting) => {
+                            match security::account_policy_change(&setting, &value) {
+                                Ok(change) => AppState::Confirm {
+                                    message: format!(
+                                        "Set {} to {}?\n\n  net accounts {}\n\n{}(y/n)",
+                                        setting.to_lowercase(),
+                                        value,
+                                        change.argument,
+                                        change.warning.map(|w| format!("Warning: {}\n\n", w)).unwrap_or_default()
+                                    ),
+                                    action: ConfirmAction::SetAccountPolicy { setting, argument: change.argument },
+                                },
+                                Err(status) => app.refresh_table_with_status(status),
+                            }
+                        }
+                        InputPurpose::SaveMacro(steps) => match macros::save(&value, &steps) {
+                            Ok(path) => AppState::Result(OperationOutcome::Success(format!(
+                                "Saved the macro {} ({}) to:\n  {}\n\n\
+                                Replay it from Macros, or on another server with:\n  server-helper.exe --macro {}",
+                                value.trim(),
+                                macros::summary(&steps),
+                                path.display(),
+                                path.display()
+                            ))),
+                            Err(message) => {
+                                // Kept so that m on the menu asks for the name again
+                                app.recording = Some(steps);
+                                AppState::Result(OperationOutcome::failed(format!(
+                                    "{}\n\nThe recording is kept: press m on the menu to name it again.",
+                                    message
+                                )))
+                            }
+                        },
+                        InputPurpose::BackupNote => match platform::current().backup() {
+                            OperationOutcome::Success(message) if !value.is_empty() => {
+                                let note = catalog::annotate_newest(&value).unwrap_or_else(|e| e);
+                                AppState::Result(OperationOutcome::Success(format!("{}\n\n{}", message, note)))
+                            }
+                            outcome => AppState::Result(outcome),
+                        },
+                        InputPurpose::AnnotateBackup(backup) => {
+                            let status = catalog::annotate(&backup, &value).unwrap_or_else(|e| e);
+                            app.refresh_table_with_status(status)
+                        }
+                        InputPurpose::GoldenRole(backup) => {
+                            let status = golden::designate(&backup, &value).unwrap_or_else(|e| e);
+                            app.refresh_table_with_status(status)
+                        }
+                        InputPurpose::GoldenCompare(role) => match golden::compare(&role, &value) {
+                            Ok(report) => {
+                                app.show_text(format!(" Golden {} Baseline ", role), report);
+                                return ControlFlow::Continue(());
+                            }
+                            Err(message) => AppState::Result(OperationOutcome::failed(message)),
+                        },
+                        InputPurpose::AddProfile => {
+                            let status = profiles::create(&value).unwrap_or_else(|e| e);
+                            app.refresh_table_with_status(status)
+                        }
+                        InputPurpose::SessionMessage(id) => {
+                            let status = sessions::message(&id, &value).unwrap_or_else(|e| e);
+                            app.refresh_table_with_status(status)
+                        }
+                        InputPurpose::AdapterVlan(adapter) => {
+                            let status = network::set_vlan(&adapter, &value).unwrap_or_else(|e| e);
+                            app.refresh_table_with_status(status)
+                        }
+                        InputPurpose::RdsLicensing => {
+                            let (success, message) = rds::set_licensing(&value);
+                            AppState::Result((success, message).into())
+                        }
+                        InputPurpose::ProxySettings => match proxy::set(&value) {
+                            Ok(_) => match app.open_checklist(ChecklistKind::Proxy) {
+                                Ok(()) => AppState::Checklist,
+                                Err(message) => AppState::Result(OperationOutcome::failed(message)),
+                            },
+                            Err(message) => AppState::Result(OperationOutcome::failed(message)),
+                        },
+                        InputPurpose::WsusSettings => match wsus::set(&value) {
+                            Ok(_) => match app.open_checklist(ChecklistKind::Wsus) {
+                                Ok(()) => AppState::Checklist,
+                                Err(message) => AppState::Result(OperationOutcome::failed(message)),
+                            },
+                            Err(message) => AppState::Result(OperationOutcome::failed(message)),
+                        },
+                        InputPurpose::TelemetryLevel => match telemetry::set_level(&value) {
+                            Ok(_) => match app.open_checklist(ChecklistKind::Telemetry) {
+                                Ok(()) => AppState::Checklist,
+                                Err(message) => AppState::Result(OperationOutcome::failed(message)),
+                            },
+                            Err(message) => AppState::Result(OperationOutcome::failed(message)),
+                        },
+                        InputPurpose::CrashDumpType => match crashdump::set_type(&value) {
+                            Ok(_) => match app.open_checklist(ChecklistKind::CrashDump) {
+                                Ok(()) => AppState::Checklist,
+                                Err(message) => AppState::Result(OperationOutcome::failed(message)),
+                            },
+                            Err(message) => AppState::Result(OperationOutcome::failed(message)),
+                        },
+                        InputPurpose::CsrSubject => {
+                            app.input = certs::default_names().1;
+                            AppState::Input(InputPurpose::CsrNames { subject: value })
+                        }
+                        InputPurpose::CsrNames { subject } => {
+                            let (success, message) = certs::create_request(&subject, &value);
+                            AppState::Result((success, message).into())
+                        }
+                        InputPurpose::CleanupCategories => match cleanup::cleanup_plan(&value) {
+                            Ok(plan) => app.stage_plan(plan),
+                            Err(message) => AppState::Result(OperationOutcome::failed(message)),
+                        },
+                        InputPurpose::SharePath => {
+                            app.input.clear();
+                            AppState::Input(InputPurpose::ShareUser(value))
+                        }
+                        InputPurpose::ShareUser(path) if value.is_empty() => {
+                            AppState::Running(Task::ShareTest { path, credentials: None })
+                        }
+                        InputPurpose::ShareUser(path) => {
+                            app.input.clear();
+                            AppState::Input(InputPurpose::SharePassword { path, user: value })
+                        }
+                        InputPurpose::SharePassword { path, user } => {
+                            let password = std::mem::take(&mut app.input);
+                            AppState::Running(Task::ShareTest { path, credentials: Some((user, password)) })
+                        }
+                        InputPurpose::AclFolders => AppState::Running(Task::BackupAcls(value)),
+                        InputPurpose::AddQuota => {
+                            let status = fsrm::add_quota(&value).unwrap_or_else(|e| e);
+                            app.refresh_table_with_status(status)
+                        }
+                        InputPurpose::FirewallRuleName if value.is_empty() => {
+                            AppState::Result(OperationOutcome::failed("Enter a rule name.".to_string()))
+                        }
+                        InputPurpose::FirewallRuleName => {
+                            app.input.clear();
+                            AppState::Input(InputPurpose::FirewallRuleTarget(value))
+                        }
+                        InputPurpose::FirewallRuleTarget(name) => {
+                            app.input = "domain private".to_string();
+                            AppState::Input(InputPurpose::FirewallRuleProfiles { name, target: value })
+                        }
+                        InputPurpose::FirewallRuleProfiles { name, target } => {
+                            match firewall::InboundRule::new(name, &target, &value) {
+                                Ok(rule) => AppState::Confirm {
+                                    message: format!(
+                                        "Create inbound rule '{}' allowing {} on the {} profile(s)?\n\n  {}\n\n(y/n)",
+                                        rule.name,
+                                        rule.describe(),
+                                        rule.profiles.join(", "),
+                                        rule.command()
+                                    ),
+                                    action: ConfirmAction::AddFirewallRule(rule),
+                                },
+                                Err(message) => AppState::Result(OperationOutcome::failed(message)),
+                            }
+                        }
+                        InputPurpose::AdminPasswordSave { name, .. } => {
+                            let save = match value.to_lowercase().as_str() {
+                                "y" | "yes" => Some(true),
+                                "n" | "no" | "" => Some(false),
+                                _ => None,
+                            };
+                            match save {
+                                Some(save) => AppState::Confirm {
+                                    message: format!(
+                                        "Set a new random password on the local {} account?\n\n\
+                                        Anything still using the old password (services, scheduled tasks, \
+                                        saved RDP credentials) will stop working.\n\n(y/n)",
+                                        name
+                                    ),
+                                    action: ConfirmAction::RotateAdminPassword { save },
+                                },
+                                None => AppState::Result(OperationOutcome::failed("Answer yes or no.".to_string())),
+                            }
+                        }
+                        InputPurpose::ExportVms => {
+                            let names = hyperv::split_names(&value);
+                            if names.is_empty() {
+                                AppState::Result(OperationOutcome::failed("No VMs entered.".to_string()))
+                            } else {
+                                let id = jobs::enqueue(jobs::JobKind::VmExport(names));
+                                match app.table.open(TableKind::Jobs) {
+                                    Ok(()) => {
+                                        app.table.status = format!(
+                                            "Queued job #{}. Press Enter on it to follow its progress.",
+                                            id
+                                        );
+                                        AppState::Table
                                     }
-                                    Some(5) => {
-                                        // Open file browser for restore
-                                        app.load_directory();
-                                        app.state = AppState::FileBrowser;
+                                    Err(message) => AppState::Result(OperationOutcome::failed(message)),
+                                }
+                            }
+                        }
+                        InputPurpose::ImportVmsTo(manifest) => match hyperv::import_plan(&manifest, &value) {
+                            Ok(plan) => app.stage_plan(plan),
+                            Err(message) => AppState::Result(OperationOutcome::failed(message)),
+                        },
+                        InputPurpose::DedupUsage(volume) => {
+                            let status = dedup::enable(&volume, &value).unwrap_or_else(|e| e);
+                            app.refresh_table_with_status(status)
+                        }
+                        InputPurpose::AddFileScreen => {
+                            let status = fsrm::add_file_screen(&value).unwrap_or_else(|e| e);
+                            app.refresh_table_with_status(status)
+                        }
+                        InputPurpose::ServiceCommand => match platform::parse_service_command(&value) {
+                            Ok((action, name)) => AppState::Running(Task::Service { action, name }),
+                            Err(message) => AppState::Result(OperationOutcome::failed(message)),
+                        },
+                        InputPurpose::KitDestination => match value.trim() {
+                            "" => AppState::Result(OperationOutcome::failed("No folder entered.".to_string())),
+                            folder => AppState::Running(Task::BuildKit(PathBuf::from(folder))),
+                        },
+                        InputPurpose::CopySource => {
+                            app.input.clear();
+                            AppState::Input(InputPurpose::CopyDestination(value))
+                        }
+                        InputPurpose::CopyDestination(source) => {
+                            app.input = "copy acls".to_string();
+                            AppState::Input(InputPurpose::CopyOptions { source, destination: value })
+                        }
+                        InputPurpose::CopyOptions { source, destination } => {
+                            match robocopy::Migration::new(source, destination, &value) {
+                                Ok(migration) => AppState::Confirm {
+                                    message: format!(
+                                        "{}?\n\n  {}\n\n{}This runs as a background job. (y/n)",
+                                        migration.describe(),
+                                        migration.command_line(),
+                                        if migration.mirror {
+                                            "Mirror DELETES files in the destination that are not in the source.\n"
+                                        } else {
+                                            ""
+                                        }
+                                    ),
+                                    action: ConfirmAction::Migrate(migration),
+                                },
+                                Err(message) => AppState::Result(OperationOutcome::failed(message)),
+                            }
+                        }
+                        InputPurpose::SqlInstance => {
+                            app.input.clear();
+                            AppState::Input(InputPurpose::SqlPassword(value))
+                        }
+                        InputPurpose::SqlPassword(instance) => {
+                            // Keep the password as typed; spaces at either end are allowed
+                            let password = std::mem::take(&mut app.input);
+                            match sql::validate(&instance, &password) {
+                                Ok(()) => AppState::Installing(InstallItem::SqlExpress { instance, password }),
+                                Err(message) => AppState::Result(OperationOutcome::failed(message)),
+                            }
+                        }
+                        InputPurpose::HttpsBindingPort { site, thumbprint } => match value.parse::<u16>() {
+                            Ok(port) => {
+                                let (_, status) = certs::bind_iis(&thumbprint, &site, port);
+                                match app.table.open(TableKind::IisSites) {
+                                    Ok(()) => {
+                                        app.table.status = status;
+                                        AppState::Table
                                     }
-                                    Some(6) => return Ok(()),
-                                    _ => {}
+                                    Err(message) => AppState::Result(OperationOutcome::failed(message)),
                                 }
                             }
-                            _ => {}
+                            Err(_) => AppState::Result(OperationOutcome::failed(format!(
+                                "'{}' is not a valid port number.",
+                                value
+                            ))),
+                        },
+                        InputPurpose::CertificateBinding { thumbprint, imported } => {
+                            let (success, message) = certs::bind(&thumbprint, &value);
+                            AppState::Result((success, format!("{}\n\n{}", imported, message)).into())
+                        }
+                        InputPurpose::ClusterNodes => {
+                            AppState::Running(Task::ValidateCluster(agents::split_hosts(&value)))
+                        }
+                        InputPurpose::SnmpOptions => match snmp::setup_plan(&value) {
+                            Ok(plan) => app.stage_plan(plan),
+                            Err(message) => AppState::Result(OperationOutcome::failed(message)),
+                        },
+                        InputPurpose::SystemStateTarget => match wbadmin::parse_target(&value) {
+                            Ok(target) => {
+                                let id = jobs::enqueue(jobs::JobKind::SystemStateBackup { target });
+                                app.table.status =
+                                    format!("Queued job #{}. Press v to follow its progress.", id);
+                                AppState::Table
+                            }
+                            Err(message) => AppState::Result(OperationOutcome::failed(message)),
                         },
-                        AppState::FileBrowser => match key.code {
-                            KeyCode::Esc | KeyCode::Char('q') => {
-                                app.state = AppState::Menu;
+                        InputPurpose::AdminCenterOptions => match admin_center::parse_options(&value) {
+                            Ok((port, thumbprint)) => {
+                                AppState::Installing(InstallItem::AdminCenter { port, thumbprint })
                             }
-                            KeyCode::Down | KeyCode::Char('j') => app.file_browser_next(),
-                            KeyCode::Up | KeyCode::Char('k') => app.file_browser_previous(),
-                            KeyCode::Enter => {
-                                if let Some(file) = app.file_browser_select() {
-                                    app.selected_file = Some(file);
-                                    app.state = AppState::Restoring;
+                            Err(message) => AppState::Result(OperationOutcome::failed(message)),
+                        },
+                    };
+                    app.transition(next);
+                }
+                _ => {}
+            }
+        }
+        AppState::Confirm { action, .. } => {
+            let action = action.clone();
+            match key.code {
+                KeyCode::Char('y') | KeyCode::Char('Y') => {
+                    let next = match action {
+                        ConfirmAction::Remediate(i) => {
+                            let reason = app
+                                .checklist
+                                .get(i)
+                                .map(|item| format!("Fix {}", item.name))
+                                .unwrap_or_default();
+                            let (success, message) = app.with_checkpoint(&reason, |app| app.apply_remediation(i));
+                            AppState::Result((success, message).into())
+                        }
+                        ConfirmAction::RemoveRoute { prefix, next_hop } => {
+                            let status = network::remove_route(&prefix, &next_hop).unwrap_or_else(|e| e);
+                            app.refresh_table_with_status(status)
+                        }
+                        ConfirmAction::RemoveHostsLine(row) => {
+                            let status = hosts::remove_line(&row).unwrap_or_else(|e| e);
+                            app.refresh_table_with_status(status)
+                        }
+                        ConfirmAction::DeleteEnvVar(name) => {
+                            let status = environment::delete_variable(&name).unwrap_or_else(|e| e);
+                            app.refresh_table_with_status(status)
+                        }
+                        ConfirmAction::RemovePathEntry(index, dir) => {
+                            let status = environment::remove_path_entry(index, &dir).unwrap_or_else(|e| e);
+                            app.refresh_table_with_status(status)
+                        }
+                        ConfirmAction::AddFirewallRule(rule) => {
+                            let (success, message) = firewall::create(&rule);
+                            AppState::Result((success, message).into())
+                        }
+                        ConfirmAction::RotateAdminPassword { save } => {
+                            let (success, message) = localadmin::rotate(save);
+                            app.secret_result = success;
+                            AppState::Result((success, message).into())
+                        }
+                        ConfirmAction::StopVm(name) => {
+                            let status = hyperv::stop_vm(&name).unwrap_or_else(|e| e);
+                            app.refresh_table_with_status(status)
+                        }
+                        ConfirmAction::SetAccountPolicy { setting, argument } => {
+                            let status = security::set_account_policy(&setting, &argument).unwrap_or_else(|e| e);
+                            app.refresh_table_with_status(status)
+                        }
+                        ConfirmAction::DeleteMacro(name) => {
+                            let status = macros::delete(&name).unwrap_or_else(|e| e);
+                            app.refresh_table_with_status(status)
+                        }
+                        ConfirmAction::PurgeCache(hash) => {
+                            let status = match cache::purge(hash.as_deref()) {
+                                Ok(status) => format!("{} {}", status, cache::summary()),
+                                Err(e) => e,
+                            };
+                            app.refresh_table_with_status(status)
+                        }
+                        ConfirmAction::KillProcess { pid, name } => {
+                            let status = processes::kill_process(&pid, &name).unwrap_or_else(|e| e);
+                            app.refresh_table_with_status(status)
+                        }
+                        ConfirmAction::DisconnectSession(id) => {
+                            let status = sessions::disconnect(&id).unwrap_or_else(|e| e);
+                            app.refresh_table_with_status(status)
+                        }
+                        ConfirmAction::LogOffSession(id) => {
+                            let status = sessions::log_off(&id).unwrap_or_else(|e| e);
+                            app.refresh_table_with_status(status)
+                        }
+                        ConfirmAction::SetHighPerformance => {
+                            let (success, message) =
+                                app.with_checkpoint("Power plan change", |_| performance::set_high_performance());
+                            AppState::Result((success, message).into())
+                        }
+                        ConfirmAction::FixSpooler => {
+                            let (success, message) = spooler::fix_spooler();
+                            AppState::Result((success, message).into())
+                        }
+                        ConfirmAction::Migrate(migration) => {
+                            let id = jobs::enqueue(jobs::JobKind::Migration(migration));
+                            match app.table.open(TableKind::Jobs) {
+                                Ok(()) => {
+                                    app.table.status =
+                                        format!("Queued job #{}. Press Enter on it to follow its progress.", id);
+                                    AppState::Table
                                 }
+                                Err(message) => AppState::Result(OperationOutcome::failed(message)),
                             }
-                            KeyCode::Backspace => {
-                                // Go to parent directory
-                                if let Some(parent) = app.current_dir.parent() {
-                                    app.current_dir = parent.to_path_buf();
-                                    app.load_directory();
+                        }
+                        ConfirmAction::WingetConfigure(path) => {
+                            let id = jobs::enqueue(jobs::JobKind::WingetConfigure(path));
+                            match app.table.open(TableKind::Jobs) {
+                                Ok(()) => {
+                                    app.table.status =
+                                        format!("Queued job #{}. Press Enter on it to follow its progress.", id);
+                                    AppState::Table
                                 }
+                                Err(message) => AppState::Result(OperationOutcome::failed(message)),
                             }
-                            _ => {}
-                        },
-                        AppState::Restoring => {
-                            // Restoration will be handled in the draw loop
                         }
-                        AppState::Installing(_) => {
-                            // Installation will be handled in the draw loop
+                        ConfirmAction::HealthRepair => {
+                            let id = jobs::enqueue(jobs::JobKind::HealthRepair);
+                            match app.table.open(TableKind::Jobs) {
+                                Ok(()) => {
+                                    app.table.status =
+                                        format!("Queued job #{}. Press Enter on it to follow its progress.", id);
+                                    AppState::Table
+                                }
+                                Err(message) => AppState::Result(OperationOutcome::failed(message)),
+                            }
                         }
-                        AppState::Result { .. } => match key.code {
-                            KeyCode::Enter | KeyCode::Esc | KeyCode::Char('q') => {
-                                app.state = AppState::Menu;
+                        ConfirmAction::InstallAgent(name) => AppState::Running(Task::InstallAgent(name)),
+                        ConfirmAction::AcceptEula { agent, fleet } => match agents::accept_eula(&agent) {
+                            Ok(status) => {
+                                app.table.status = status;
+                                app.agent_install_step(agent, fleet)
+                            }
+                            Err(status) => {
+                                app.table.status = status;
+                                AppState::Table
                             }
-                            _ => {}
                         },
-                    }
+                        ConfirmAction::Quit => return ControlFlow::Break(()),
+                        ConfirmAction::SetupDocker => {
+                            let id = jobs::enqueue(jobs::JobKind::DockerHost);
+                            match app.table.open(TableKind::Jobs) {
+                                Ok(()) => {
+                                    app.table.status =
+                                        format!("Queued job #{}. Press Enter on it to follow each step.", id);
+                                    AppState::Table
+                                }
+                                Err(message) => AppState::Result(OperationOutcome::failed(message)),
+                            }
+                        }
+                        ConfirmAction::ChangeFeature { name, install } => {
+                            let id = jobs::enqueue(jobs::JobKind::Feature { name, install });
+                            app.table.status = format!(
+                                "Queued job #{} ({}). Press v to view jobs.",
+                                id,
+                                jobs::summary()
+                            );
+                            AppState::Table
+                        }
+                    };
+                    app.transition(next);
                 }
+                KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                    let next = match action {
+                        ConfirmAction::Remediate(_) => AppState::Checklist,
+                        ConfirmAction::RemoveRoute { .. }
+                        | ConfirmAction::RemoveHostsLine(_)
+                        | ConfirmAction::DeleteEnvVar(_)
+                        | ConfirmAction::RemovePathEntry(..)
+                        | ConfirmAction::KillProcess { .. }
+                        | ConfirmAction::DisconnectSession(_)
+                        | ConfirmAction::LogOffSession(_)
+                        | ConfirmAction::SetAccountPolicy { .. }
+                        | ConfirmAction::PurgeCache(_)
+                        | ConfirmAction::DeleteMacro(_)
+                        | ConfirmAction::StopVm(_)
+                        | ConfirmAction::InstallAgent(_)
+                        | ConfirmAction::AcceptEula { .. }
+                        | ConfirmAction::ChangeFeature { .. } => AppState::Table,
+                        ConfirmAction::SetHighPerformance
+                        | ConfirmAction::SetupDocker
+                        | ConfirmAction::FixSpooler
+                        | ConfirmAction::HealthRepair
+                        | ConfirmAction::WingetConfigure(_)
+                        | ConfirmAction::Migrate(_)
+                        | ConfirmAction::AddFirewallRule(_)
+                        | ConfirmAction::RotateAdminPassword { .. }
+                        | ConfirmAction::Quit => AppState::Menu,
+                    };
+                    app.transition(next);
+                }
+                _ => {}
             }
         }
-
-        // Handle installation state
-        if let AppState::Installing(ref item) = app.state.clone() {
-            let (title, msg) = match item {
-                InstallItem::Winget => (" Installing Winget ", "Installing Winget... Please wait.\n\nThis may take a few minutes."),
-                InstallItem::NetBird => (" Installing NetBird ", "Installing NetBird... Please wait.\n\nThis may take a few minutes."),
-            };
-            
-            terminal.draw(|f| {
-                let area = f.area();
-                let block = Block::default()
-                    .title(title)
-                    .borders(Borders::ALL)
-                    .border_style(Style::default().fg(Color::Yellow));
-                let inner = block.inner(area);
-                f.render_widget(block, area);
-                
-                let text = Paragraph::new(msg)
-                    .style(Style::default().fg(Color::Yellow))
-                    .wrap(Wrap { trim: true });
-                f.render_widget(text, inner);
-            })?;
-
-            let (success, message) = match item {
-                InstallItem::Winget => app.install_winget(),
-                InstallItem::NetBird => app.install_netbird(),
-            };
-            app.state = AppState::Result { success, message };
-        }
-
-        // Handle restoring state
-        if app.state == AppState::Restoring {
-            terminal.draw(|f| {
-                let area = f.area();
-                let block = Block::default()
-                    .title(" Restoring Server Roles & Features ")
-                    .borders(Borders::ALL)
-                    .border_style(Style::default().fg(Color::Yellow));
-                let inner = block.inner(area);
-                f.render_widget(block, area);
-                
-                let text = Paragraph::new("Restoring Server Roles and Features...\n\nThis may take several minutes. Please wait.")
-                    .style(Style::default().fg(Color::Yellow))
-                    .wrap(Wrap { trim: true });
-                f.render_widget(text, inner);
-            })?;
-
-            if let Some(ref file) = app.selected_file.clone() {
-                let (success, message) = app.restore_server_roles(file);
-                app.state = AppState::Result { success, message };
-            } else {
-                app.state = AppState::Result {
-                    success: false,
-                    message: "No file selected.".to_string(),
+        AppState::PlanReview => match key.code {
+            // Conflicts need the explicit override below
+            KeyCode::Char('y') | KeyCode::Char('Y') if app.plan.as_ref().is_some_and(|p| !p.conflicts.is_empty()) => {}
+            KeyCode::Char('o') if app.plan.as_ref().is_some_and(|p| !p.conflicts.is_empty()) => {
+                if let Some(plan) = &app.plan {
+                    history::record(&format!("Overrode conflicts for plan: {}\n{}", plan.title, plan.conflicts.join("\n")));
+                }
+                app.transition(AppState::Applying);
+            }
+            KeyCode::Char('y') | KeyCode::Char('Y') => {
+                let next = match &app.plan {
+                    Some(plan) if plan.steps.is_empty() => AppState::Result(OperationOutcome::Success(format!(
+                        "{}\n\nNothing to do.",
+                        plan.render()
+                    ))),
+                    _ => AppState::Applying,
                 };
+                app.transition(next);
+            }
+            KeyCode::Char('n') | KeyCode::Esc | KeyCode::Char('q') => app.leave(),
+            KeyCode::Down | KeyCode::Char('j') => {
+                app.text_scroll = app.text_scroll.saturating_add(1);
             }
+            KeyCode::Up | KeyCode::Char('k') => {
+                app.text_scroll = app.text_scroll.saturating_sub(1);
+            }
+            KeyCode::PageDown => app.text_scroll = app.text_scroll.saturating_add(10),
+            KeyCode::PageUp => app.text_scroll = app.text_scroll.saturating_sub(10),
+            _ => {}
+        },
+        AppState::Installing(_) | AppState::Applying | AppState::Running(_) => {
+            // Busy states wait for their operation to finish
         }
+        AppState::Result(_) => match key.code {
+            KeyCode::Enter | KeyCode::Esc | KeyCode::Char('q') => app.leave(),
+            _ => {}
+        },
     }
+    ControlFlow::Continue(())
 }
 
-fn ui(f: &mut Frame, app: &mut App) {
+fn view(f: &mut Frame, app: &mut App) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .margin(1)
@@ -803,12 +4358,46 @@ fn ui(f: &mut Frame, app: &mut App) {
             Constraint::Length(3),
         ])
         .split(f.area());
+    // The split layout narrows the content to the left column
+    let chunks = if app.split {
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+            .split(chunks[1]);
+        side_pane(f, app, columns[1]);
+        vec![chunks[0], columns[0], chunks[2]]
+    } else {
+        chunks.to_vec()
+    };
 
     // Title
-    let title = Paragraph::new(format!(" Server Helper v{} ", VERSION))
-        .style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))
-        .alignment(Alignment::Center)
-        .block(Block::default().borders(Borders::ALL));
+    let title = match exec::export_status() {
+        Some((path, steps)) => Paragraph::new(format!(
+            " Server Helper v{} - EXPORT MODE: {} step(s) to {} ",
+            VERSION,
+            steps,
+            path.display()
+        ))
+        .style(Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD)),
+        None => {
+            let modes: Vec<&str> = [
+                (app.offline, " - OFFLINE"),
+                (portable_dir().is_some(), " - PORTABLE"),
+                (app.recording.is_some(), " - REC"),
+            ]
+                .into_iter()
+                .filter_map(|(on, label)| on.then_some(label))
+                .collect();
+            let profile = profiles::active().map(|name| format!(" - PROFILE: {}", name)).unwrap_or_default();
+            Paragraph::new(format!(" Server Helper v{}{}{} ", VERSION, modes.concat(), profile))
+                .style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))
+        }
+    };
+    let mut block = Block::default().borders(Borders::ALL);
+    if app.screens.len() > 1 {
+        block = block.title(format!(" {} ", app.breadcrumb()));
+    }
+    let title = title.alignment(Alignment::Center).block(block);
     f.render_widget(title, chunks[0]);
 
     match &app.state {
@@ -816,7 +4405,18 @@ fn ui(f: &mut Frame, app: &mut App) {
             let items: Vec<ListItem> = app
                 .menu_items
                 .iter()
-                .map(|i| ListItem::new(*i).style(Style::default().fg(Color::White)))
+                .map(|(label, action)| match action.needs_internet().filter(|_| app.offline) {
+                    Some(reason) => ListItem::new(format!("{} (offline: {})", label, reason))
+                        .style(Style::default().fg(Color::DarkGray)),
+                    None => {
+                        let mut spans = vec![Span::raw(*label)];
+                        if let Some(badge) = app.menu_badge(*action) {
+                            let color = level_color(badge.level);
+                            spans.push(Span::styled(format!("  [{}]", badge.text), Style::default().fg(color)));
+                        }
+                        ListItem::new(Line::from(spans)).style(Style::default().fg(Color::White))
+                    }
+                })
                 .collect();
 
             let list = List::new(items)
@@ -837,22 +4437,19 @@ fn ui(f: &mut Frame, app: &mut App) {
             f.render_stateful_widget(list, chunks[1], &mut app.menu_state);
         }
         AppState::Installing(ref item) => {
-            let msg = match item {
-                InstallItem::Winget => "Installing Winget... Please wait.",
-                InstallItem::NetBird => "Installing NetBird... Please wait.",
-            };
-            let text = Paragraph::new(msg)
+            let (title, msg) = item.progress();
+            let text = Paragraph::new(app.busy_text(msg))
                 .style(Style::default().fg(Color::Yellow))
                 .block(
                     Block::default()
-                        .title(" Installing ")
+                        .title(title)
                         .borders(Borders::ALL)
                         .border_style(Style::default().fg(Color::Yellow)),
                 )
                 .wrap(Wrap { trim: true });
             f.render_widget(text, chunks[1]);
         }
-        AppState::FileBrowser => {
+        AppState::FileBrowser(purpose) => {
             let items: Vec<ListItem> = app
                 .dir_entries
                 .iter()
@@ -862,7 +4459,11 @@ fn ui(f: &mut Frame, app: &mut App) {
                     } else if path.is_dir() {
                         format!("📁 {}", path.file_name().unwrap_or_default().to_string_lossy())
                     } else {
-                        format!("📄 {}", path.file_name().unwrap_or_default().to_string_lossy())
+                        let name = path.file_name().unwrap_or_default().to_string_lossy();
+                        match dates::from_file_name(&name) {
+                            Some(secs) => format!("📄 {}  ({})", name, dates::local(secs)),
+                            None => format!("📄 {}", name),
+                        }
                     };
                     let style = if path.is_dir() || path == &PathBuf::from("..") {
                         Style::default().fg(Color::Cyan)
@@ -873,7 +4474,7 @@ fn ui(f: &mut Frame, app: &mut App) {
                 })
                 .collect();
 
-            let title = format!(" Select Backup File - {} ", app.current_dir.display());
+            let title = format!(" {} - {} ", purpose.title(), app.current_dir.display());
             let list = List::new(items)
                 .block(
                     Block::default()
@@ -889,28 +4490,174 @@ fn ui(f: &mut Frame, app: &mut App) {
                 )
                 .highlight_symbol(">> ");
 
-            f.render_stateful_widget(list, chunks[1], &mut app.file_list_state);
+            if *purpose == BrowsePurpose::RestoreRoles {
+                let panes = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+                    .split(chunks[1]);
+                let color = if app.preview.contains("SIGNATURE CHECK FAILED") { Color::Red } else { Color::Magenta };
+                let preview = Paragraph::new(app.preview.as_str())
+                    .style(Style::default().fg(Color::White))
+                    .block(Block::default().title(" Preview ").borders(Borders::ALL).border_style(Style::default().fg(color)))
+                    .wrap(Wrap { trim: true });
+                f.render_stateful_widget(list, panes[0], &mut app.file_list_state);
+                f.render_widget(preview, panes[1]);
+            } else {
+                f.render_stateful_widget(list, chunks[1], &mut app.file_list_state);
+            }
+        }
+        AppState::PlanReview => {
+            let text = Paragraph::new(app.text.as_str())
+                .style(Style::default().fg(Color::White))
+                .block(
+                    Block::default()
+                        .title(app.text_title.as_str())
+                        .borders(Borders::ALL)
+                        .border_style(Style::default().fg(Color::Yellow)),
+                )
+                .wrap(Wrap { trim: false })
+                .scroll((app.text_scroll, 0));
+            f.render_widget(text, chunks[1]);
         }
-        AppState::Restoring => {
-            let text = Paragraph::new("Restoring Server Roles and Features...\n\nThis may take several minutes.")
+        AppState::Applying => {
+            let text = Paragraph::new(app.busy_text("Applying the change plan...\n\nThis may take several minutes. Please wait."))
                 .style(Style::default().fg(Color::Yellow))
                 .block(
                     Block::default()
-                        .title(" Restoring ")
+                        .title(app.text_title.as_str())
                         .borders(Borders::ALL)
                         .border_style(Style::default().fg(Color::Yellow)),
                 )
                 .wrap(Wrap { trim: true });
             f.render_widget(text, chunks[1]);
         }
-        AppState::Result { success, message } => {
-            let (color, title) = if *success {
-                (Color::Green, " Success ")
-            } else {
-                (Color::Red, " Error ")
-            };
+        AppState::Checklist => {
+            let width = app.checklist.iter().map(|i| i.name.len()).max().unwrap_or(0);
+            let items: Vec<ListItem> = app
+                .checklist
+                .iter()
+                .map(|item| {
+                    let (label, color) = match item.status {
+                        CheckStatus::Pass => ("[PASS]", Color::Green),
+                        CheckStatus::Fail => ("[FAIL]", Color::Red),
+                        CheckStatus::Unknown => ("[ ?? ]", Color::Yellow),
+                    };
+                    ListItem::new(Line::from(vec![
+                        Span::styled(
+                            format!("{} ", label),
+                            Style::default().fg(color).add_modifier(Modifier::BOLD),
+                        ),
+                        Span::styled(
+                            format!("{:<width$}  ", item.name, width = width),
+                            Style::default().fg(Color::White),
+                        ),
+                        Span::styled(item.detail.clone(), Style::default().fg(Color::DarkGray)),
+                    ]))
+                })
+                .collect();
+
+            let list = List::new(items)
+                .block(
+                    Block::default()
+                        .title(app.checklist_kind.title())
+                        .borders(Borders::ALL)
+                        .border_style(Style::default().fg(Color::Cyan)),
+                )
+                .highlight_style(
+                    Style::default()
+                        .bg(Color::DarkGray)
+                        .add_modifier(Modifier::BOLD),
+                )
+                .highlight_symbol(">> ");
 
+            let areas = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(5), Constraint::Length(7)])
+                .split(chunks[1]);
+            f.render_stateful_widget(list, areas[0], &mut app.checklist_state);
+
+            // Full text of the selected check, which may not fit on its list line
+            let details = app
+                .checklist_state
+                .selected()
+                .and_then(|i| app.checklist.get(i))
+                .map(|item| {
+                    let mut text = format!("{}: {}", item.name, item.detail);
+                    if let Some(ref remediation) = item.remediation {
+                        text.push_str(&format!("\n\nFix (Enter): {}", remediation.summary));
+                    }
+                    if let Some(ref advice) = item.advice {
+                        text.push_str(&format!("\n\nSuggested fix: {}", advice));
+                    }
+                    text
+                })
+                .unwrap_or_default();
+            let details = Paragraph::new(details)
+                .style(Style::default().fg(Color::White))
+                .block(Block::default().title(" Details ").borders(Borders::ALL))
+                .wrap(Wrap { trim: true });
+            f.render_widget(details, areas[1]);
+        }
+        AppState::Table => app.table.render(f, chunks[1]),
+        AppState::TextView => {
+            let text = Paragraph::new(app.text.as_str())
+                .style(Style::default().fg(Color::White))
+                .block(
+                    Block::default()
+                        .title(app.text_title.as_str())
+                        .borders(Borders::ALL)
+                        .border_style(Style::default().fg(Color::Cyan)),
+                )
+                .wrap(Wrap { trim: false })
+                .scroll((app.text_scroll, 0));
+            f.render_widget(text, chunks[1]);
+        }
+        AppState::Input(purpose) => {
+            let value = if purpose.secret() { "*".repeat(app.input.chars().count()) } else { app.input.clone() };
+            let text = Paragraph::new(format!("{}{}\n\n> {}_", purpose.context(), purpose.prompt(), value))
+                .style(Style::default().fg(Color::White))
+                .block(
+                    Block::default()
+                        .title(purpose.title())
+                        .borders(Borders::ALL)
+                        .border_style(Style::default().fg(Color::Cyan)),
+                )
+                .wrap(Wrap { trim: false });
+            f.render_widget(text, chunks[1]);
+        }
+        AppState::Running(task) => {
+            let text = Paragraph::new(app.busy_text("Working... Please wait."))
+                .style(Style::default().fg(Color::Yellow))
+                .block(
+                    Block::default()
+                        .title(task.title())
+                        .borders(Borders::ALL)
+                        .border_style(Style::default().fg(Color::Yellow)),
+                )
+                .wrap(Wrap { trim: true });
+            f.render_widget(text, chunks[1]);
+        }
+        AppState::Confirm { message, .. } => {
             let text = Paragraph::new(message.as_str())
+                .style(Style::default().fg(Color::Yellow))
+                .block(
+                    Block::default()
+                        .title(" Confirm ")
+                        .borders(Borders::ALL)
+                        .border_style(Style::default().fg(Color::Yellow)),
+                )
+                .wrap(Wrap { trim: true });
+            f.render_widget(text, chunks[1]);
+        }
+        AppState::Result(outcome) => {
+            let color = match outcome {
+                OperationOutcome::Success(_) => Color::Green,
+                OperationOutcome::SuccessNeedsReboot(_) | OperationOutcome::Partial { .. } => Color::Yellow,
+                OperationOutcome::Failed { .. } => Color::Red,
+            };
+            let title = outcome.title();
+
+            let text = Paragraph::new(outcome.message())
                 .style(Style::default().fg(color))
                 .block(
                     Block::default()
@@ -923,12 +4670,85 @@ fn ui(f: &mut Frame, app: &mut App) {
         }
     }
 
+    if app.locked {
+        let prompt = match &app.lock_pin {
+            Some(_) => format!("Enter the PIN to resume.\n\n> {}_\n\n{}", "*".repeat(app.pin_input.chars().count()), app.lock_status),
+            None => "Press any key to resume.".to_string(),
+        };
+        f.render_widget(Clear, chunks[1]);
+        f.render_widget(
+            Paragraph::new(format!("This screen was hidden after a period of inactivity.\n\n{}", prompt))
+                .alignment(Alignment::Center)
+                .style(Style::default().fg(Color::Yellow))
+                .block(
+                    Block::default()
+                        .title(" Locked ")
+                        .borders(Borders::ALL)
+                        .border_style(Style::default().fg(Color::Yellow)),
+                ),
+            chunks[1],
+        );
+    }
+
     // Footer
+    let table_footer = app.table.footer();
     let footer_text = match app.state {
-        AppState::Menu => "↑/↓: Navigate | Enter: Select | q: Quit",
-        AppState::FileBrowser => "↑/↓: Navigate | Enter: Select/Open | Backspace: Parent | Esc: Cancel",
-        AppState::Installing(_) | AppState::Restoring => "Please wait...",
-        AppState::Result { .. } => "Press Enter or Esc to return to menu",
+        _ if app.locked && app.lock_pin.is_some() => "Type the PIN | Enter: Resume",
+        _ if app.locked => "Press any key to resume",
+        AppState::Menu if app.recording.is_some() => {
+            "↑/↓: Navigate | Enter: Select | .: Repeat last | m: Stop recording | Tab: Log pane | q: Quit"
+        }
+        AppState::Menu => "↑/↓: Navigate | Enter: Select | .: Repeat last | m: Record macro | Tab: Log pane | q: Quit",
+        AppState::FileBrowser(
+            BrowsePurpose::RestoreRoles
+            | BrowsePurpose::IssuedCertificate
+            | BrowsePurpose::AclBackup
+            | BrowsePurpose::VmExport
+            | BrowsePurpose::WingetConfig,
+        ) => {
+            "↑/↓: Navigate | Enter: Select/Open | Backspace: Parent | Esc: Cancel"
+        }
+        AppState::FileBrowser(BrowsePurpose::SysmonConfig) => {
+            "↑/↓: Navigate | Enter: Select/Open | b: Use bundled default | Backspace: Parent | Esc: Cancel"
+        }
+        AppState::Checklist if app.checklist_kind == ChecklistKind::Rds => {
+            "↑/↓: Navigate | l: Set license server & mode | Esc: Back"
+        }
+        AppState::Checklist if app.checklist_kind == ChecklistKind::Proxy => {
+            "↑/↓: Navigate | p: Set proxy | i: Copy Internet Options proxy | r: Test again | Esc: Back"
+        }
+        AppState::Checklist if app.checklist_kind == ChecklistKind::Wsus => {
+            "↑/↓: Navigate | s: Set server & group | d: Detect now | r: Refresh | Esc: Back"
+        }
+        AppState::Checklist if app.checklist_kind == ChecklistKind::Telemetry => {
+            "↑/↓: Navigate | Enter: Fix selected | l: Set level | r: Refresh | Esc: Back"
+        }
+        AppState::Checklist if app.checklist_kind == ChecklistKind::CrashDump => {
+            "↑/↓: Navigate | Enter: Fix selected | t: Set dump type | r: Refresh | Esc: Back"
+        }
+        AppState::Checklist if app.checklist_kind == ChecklistKind::Validation => {
+            "↑/↓: Navigate | r: Run again | e: Edit checks | w: Save sign-off report | Esc: Back"
+        }
+        AppState::Checklist
+            if matches!(
+                app.checklist_kind,
+                ChecklistKind::DcDiag | ChecklistKind::Replication | ChecklistKind::DeviceGuard
+            ) =>
+        {
+            "↑/↓: Navigate | r: Run again | Esc: Back"
+        }
+        AppState::Checklist => "↑/↓: Navigate | Enter: Fix selected | Esc: Back",
+        AppState::Table => table_footer.as_str(),
+        AppState::TextView => "↑/↓/PgUp/PgDn: Scroll | Esc: Back",
+        AppState::Confirm { .. } => "y: Apply | n/Esc: Cancel",
+        AppState::Input(_) => "Type your input | Enter: Run | Esc: Cancel",
+        AppState::PlanReview if app.plan.as_ref().is_some_and(|p| !p.conflicts.is_empty()) => {
+            "↑/↓/PgUp/PgDn: Scroll | o: Override conflicts and apply | n/Esc: Cancel"
+        }
+        AppState::PlanReview => "↑/↓/PgUp/PgDn: Scroll | y: Apply plan | n/Esc: Cancel",
+        AppState::Installing(_) | AppState::Applying | AppState::Running(_) => "Please wait...",
+        AppState::Result(_) if app.screens.len() > 1 => "Press Enter or Esc to go back",
+        AppState::Result(_) => "Press Enter or Esc to return to menu",
     };
     
     let footer = Paragraph::new(footer_text)
@@ -936,4 +4756,357 @@ fn ui(f: &mut Frame, app: &mut App) {
         .alignment(Alignment::Center)
         .block(Block::default().borders(Borders::ALL));
     f.render_widget(footer, chunks[2]);
+    toasts(f, app, chunks[2]);
+}
+
+fn level_color(level: status::Level) -> Color {
+    match level {
+        status::Level::Good => Color::Green,
+        status::Level::Warning => Color::Yellow,
+        status::Level::Bad => Color::Red,
+    }
+}
+
+/// Draws the notifications stacked up from the bottom-right corner, just above the footer.
+fn toasts(f: &mut Frame, app: &App, footer: Rect) {
+    let width = (footer.width / 2).clamp(20, 60);
+    let mut bottom = footer.y;
+    for toast in app.toasts.visible().iter().rev() {
+        let lines = (toast.text.chars().count() as u16).div_ceil(width.saturating_sub(4).max(1)).clamp(1, 3);
+        let height = lines + 2;
+        if bottom < height {
+            break;
+        }
+        bottom -= height;
+        let area = Rect::new(footer.x + footer.width.saturating_sub(width), bottom, width, height);
+        let color = level_color(toast.level);
+        f.render_widget(Clear, area);
+        f.render_widget(
+            Paragraph::new(toast.text.as_str())
+                .style(Style::default().fg(color))
+                .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(color)))
+                .wrap(Wrap { trim: true }),
+            area,
+        );
+    }
+}
+
+/// The right-hand pane of the split layout: the last background reading of the host, the job
+/// queue and the log of the running or last operation, visible on every screen.
+fn side_pane(f: &mut Frame, app: &App, area: Rect) {
+    const JOBS_SHOWN: usize = 5;
+    let jobs = jobs::job_rows().unwrap_or_default();
+    let parts = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(6),
+            Constraint::Length(jobs.len().clamp(1, JOBS_SHOWN) as u16 + 2),
+            Constraint::Min(3),
+        ])
+        .split(area);
+    let block = |title: &'static str| {
+        Block::default().title(title).borders(Borders::ALL).border_style(Style::default().fg(Color::DarkGray))
+    };
+
+    let status: Vec<Line> = match &app.host_status {
+        Some(host) => {
+            let badge = |b: status::Badge| Line::styled(b.text, Style::default().fg(level_color(b.level)));
+            let mut lines = vec![badge(host.winget_badge()), badge(host.reboot_badge())];
+            lines.extend(host.disk_badge().map(badge));
+            lines.push(Line::styled(
+                format!("Read {}", app.status_read.map(|t| dates::local(t as i64)).unwrap_or_default()),
+                Style::default().fg(Color::DarkGray),
+            ));
+            lines
+        }
+        None if status_interval().is_none() => vec![Line::raw("Background refresh is off.")],
+        None => vec![Line::raw("Not read yet.")],
+    };
+    f.render_widget(Paragraph::new(status).block(block(" Host Status ")), parts[0]);
+
+    let rows: Vec<Line> = match jobs.is_empty() {
+        true => vec![Line::styled("No jobs.", Style::default().fg(Color::DarkGray))],
+        false => jobs.iter().take(JOBS_SHOWN).map(|r| Line::raw(format!("#{} {} - {}", r[0], r[2], r[1]))).collect(),
+    };
+    f.render_widget(Paragraph::new(rows).block(block(" Jobs ")), parts[1]);
+
+    let height = parts[2].height.saturating_sub(2) as usize;
+    let log = match app.log_messages.len() {
+        0 => "Output of the running operation shows here.".to_string(),
+        n => app.log_messages[n.saturating_sub(height)..].join("\n"),
+    };
+    f.render_widget(
+        Paragraph::new(log)
+            .style(Style::default().fg(Color::Gray))
+            .block(block(" Log "))
+            .wrap(Wrap { trim: false }),
+        parts[2],
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testing::FakeRunner;
+
+    const WINGET_URL: &str = "Microsoft.DesktopAppInstaller_8wekyb3d8bbwe.msixbundle";
+
+    #[test]
+    fn install_winget_downloads_extracts_and_installs() {
+        let runner = FakeRunner::new().on("winget --version", "v1.9.25180").install();
+
+        let outcome = App::install_winget();
+
+        assert!(matches!(&outcome, OperationOutcome::Success(m) if m.contains("v1.9.25180")), "{}", outcome.message());
+        assert!(runner.ran(&format!("download https://github.com/microsoft/winget-cli/releases/latest/download/{}", WINGET_URL)));
+        assert!(runner.ran("Expand-Archive"));
+        assert!(runner.ran("Microsoft.DesktopAppInstaller.msixbundle"));
+    }
+
+    #[test]
+    fn install_winget_stops_when_a_package_download_fails() {
+        let runner = FakeRunner::new().fail(WINGET_URL, "connection reset").install();
+
+        let outcome = App::install_winget();
+
+        match outcome {
+            OperationOutcome::Failed { error: OperationError::Download { item, reason } } => {
+                assert_eq!(item, "Winget");
+                assert_eq!(reason, "connection reset");
+            }
+            other => panic!("unexpected outcome: {}", other.message()),
+        }
+        assert!(!runner.ran("Add-AppxPackage"));
+    }
+
+    #[test]
+    fn install_winget_tolerates_a_missing_license() {
+        FakeRunner::new().fail("License1.xml", "404 Not Found").on("winget --version", "v1.9.25180").install();
+
+        assert!(matches!(App::install_winget(), OperationOutcome::Success(_)));
+    }
+
+    #[test]
+    fn install_winget_provisions_for_all_users_on_a_session_host() {
+        let runner = FakeRunner::new().on("RDS-RD-Server", "True").on("winget --version", "v1.9.25180").install();
+
+        let outcome = App::install_winget();
+
+        assert!(matches!(&outcome, OperationOutcome::Success(m) if m.contains("Provisioned for all users")), "{}", outcome.message());
+        assert!(runner.ran("Add-AppxProvisionedPackage -Online"));
+        assert!(!runner.ran("Add-AppxPackage -Path"));
+    }
+
+    #[test]
+    fn install_winget_reports_the_appx_error() {
+        FakeRunner::new().fail("Add-AppxPackage -Path '", "Deployment failed with HRESULT: 0x80073CF3").install();
+
+        match App::install_winget() {
+            OperationOutcome::Failed { error: OperationError::Command { detail, .. } } => assert!(detail.contains("0x80073CF3")),
+            other => panic!("unexpected outcome: {}", other.message()),
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "netbird")]
+    fn install_netbird_uses_winget_when_available() {
+        let runner = FakeRunner::new()
+            .on("winget --version", "v1.9.25180")
+            .on("winget install --id NetBird.NetBird", "Successfully installed")
+            .install();
+
+        assert!(matches!(App::install_netbird(), OperationOutcome::Success(_)));
+        assert!(!runner.ran("download "));
+    }
+
+    #[test]
+    fn busy_states_only_end_on_the_result_screen() {
+        for (from, next) in TRANSITIONS {
+            if matches!(from, StateKind::Installing | StateKind::Applying | StateKind::Running) {
+                assert_eq!(*next, [StateKind::Result], "{:?}", from);
+            }
+        }
+    }
+
+    #[test]
+    fn text_view_returns_to_the_screen_it_was_opened_from() {
+        FakeRunner::new().install();
+        let mut app = App::new();
+        app.table = TableView::new(TableKind::Jobs);
+        app.transition(AppState::Table);
+        app.show_text(" Log ".to_string(), "done".to_string());
+        app.text_job = Some(3);
+
+        app.back();
+
+        assert!(app.state == AppState::Table);
+        assert_eq!(app.text_job, None);
+    }
+
+    #[test]
+    fn an_idle_password_prompt_locks_until_the_pin_is_typed() {
+        FakeRunner::new().install();
+        let mut app = App::new();
+        app.idle_lock = Some(Duration::ZERO);
+        app.lock_pin = Some("42".to_string());
+        app.check_idle();
+        assert!(!app.locked, "the menu is not sensitive");
+
+        app.state = AppState::Input(InputPurpose::SqlPassword("SQLEXPRESS".to_string()));
+        app.check_idle();
+        assert!(app.locked);
+
+        let key = |code| KeyEvent::new(code, crossterm::event::KeyModifiers::NONE);
+        for code in [KeyCode::Char('4'), KeyCode::Char('1'), KeyCode::Enter] {
+            assert!(press(&mut app, key(code)).is_continue());
+        }
+        assert!(app.locked && app.input.is_empty());
+        assert_eq!(app.lock_status, "Wrong PIN.");
+
+        for code in [KeyCode::Char('4'), KeyCode::Char('2'), KeyCode::Enter] {
+            assert!(press(&mut app, key(code)).is_continue());
+        }
+        assert!(!app.locked && app.input.is_empty());
+    }
+
+    #[test]
+    fn a_recorded_password_prompt_keeps_no_password() {
+        FakeRunner::new().install();
+        let mut app = App::new();
+        app.recording = Some(Vec::new());
+        app.state = AppState::Input(InputPurpose::SharePassword { path: "\\\\fs01\\data".to_string(), user: "CORP\\svc".to_string() });
+
+        let key = |code| KeyEvent::new(code, crossterm::event::KeyModifiers::NONE);
+        for code in "Pw9!x".chars().map(KeyCode::Char).chain([KeyCode::Backspace, KeyCode::Enter]) {
+            assert!(press(&mut app, key(code)).is_continue());
+        }
+        let recorded = macros::render(&app.recording.take().unwrap_or_default());
+        assert_eq!(recorded, "secret\n");
+        assert!(!"Pw9!x".chars().any(|c| recorded.contains(c)));
+
+        app.state = AppState::Input(InputPurpose::SqlPassword("SQLEXPRESS".to_string()));
+        app.replay = vec![macros::Step::Secret, macros::Step::Key(KeyCode::Esc)].into();
+        assert!(!app.replay_waits());
+        assert!(app.replay_next().is_continue());
+        assert!(app.replay_waits(), "the replay waits for the password to be typed");
+    }
+
+    #[test]
+    fn esc_walks_back_through_the_screens_opened() {
+        FakeRunner::new().install();
+        let mut app = App::new();
+        app.table = TableView::new(TableKind::Jobs);
+        app.transition(AppState::Table);
+        app.table = TableView::new(TableKind::Macros);
+        app.sync_screens();
+        app.transition(AppState::Result(OperationOutcome::Success("done".to_string())));
+
+        assert_eq!(app.breadcrumb(), "Menu > Jobs > Macros");
+        app.leave();
+        assert!(app.state == AppState::Table && app.table.kind == TableKind::Macros);
+        app.back();
+        assert!(app.table.kind == TableKind::Jobs);
+        app.back();
+        assert!(app.state == AppState::Menu);
+        assert_eq!(app.breadcrumb(), "Menu");
+    }
+
+    #[test]
+    fn cancelling_a_plan_discards_it() {
+        FakeRunner::new().install();
+        let mut app = App::new();
+        app.review_plan(Plan::new("Nothing"));
+        assert!(app.plan.is_some());
+
+        app.transition(AppState::Menu);
+
+        assert!(app.plan.is_none());
+    }
+
+    #[test]
+    fn a_task_runs_on_a_worker_and_ends_on_the_result_screen() {
+        let runner = FakeRunner::new().on("gpupdate", "Computer Policy update has completed successfully.").install();
+        let mut app = App::new();
+
+        app.transition(AppState::Running(Task::GpUpdate));
+        while app.state.kind() == StateKind::Running {
+            let message = app.inbox.recv_timeout(Duration::from_secs(10)).expect("the task to finish");
+            assert!(update(&mut app, message).is_continue());
+        }
+
+        assert!(matches!(app.state, AppState::Result(_)));
+        assert!(runner.ran("gpupdate /force"));
+    }
+
+    #[test]
+    fn keys_are_ignored_while_an_operation_runs() {
+        let mut app = App::new();
+        app.state = AppState::Applying;
+        app.operation = Some(7);
+        let key = KeyEvent::new(KeyCode::Esc, crossterm::event::KeyModifiers::NONE);
+
+        assert!(update(&mut app, Message::KeyPressed(key)).is_continue());
+        assert!(app.state == AppState::Applying);
+
+        let outcome = OperationOutcome::Success("done".to_string());
+        assert!(update(&mut app, Message::OperationFinished { id: 7, outcome }).is_continue());
+        assert!(matches!(app.state, AppState::Result(_)));
+        assert_eq!(app.operation, None);
+    }
+
+    #[test]
+    fn an_operation_starts_once_and_stale_results_are_dropped() {
+        let runner = FakeRunner::new().install();
+        let mut app = App::new();
+
+        app.transition(AppState::Running(Task::GpUpdate));
+        app.start_operation();
+        let id = app.operation.expect("an operation in flight");
+
+        let stale = OperationOutcome::failed("from an earlier run".to_string());
+        let _ = update(&mut app, Message::OperationFinished { id: id + 1, outcome: stale });
+        assert!(app.state == AppState::Running(Task::GpUpdate));
+
+        while app.state.kind() == StateKind::Running {
+            let message = app.inbox.recv_timeout(Duration::from_secs(10)).expect("the task to finish");
+            let _ = update(&mut app, message);
+        }
+        assert_eq!(runner.calls().iter().filter(|c| c.contains("gpupdate")).count(), 1);
+    }
+
+    #[test]
+    fn progress_messages_show_on_the_busy_screen() {
+        let mut app = App::new();
+        let _ = update(&mut app, Message::CommandOutput("Installing Winget...".to_string()));
+        let _ = update(
+            &mut app,
+            Message::DownloadProgress { name: "winget.msixbundle".to_string(), received: 524_288, total: Some(1_048_576) },
+        );
+
+        let text = app.busy_text("Please wait.");
+
+        assert!(text.contains("Downloading winget.msixbundle: 0.5 of 1.0 MB (50%)"), "{}", text);
+        assert!(text.ends_with("Installing Winget..."), "{}", text);
+    }
+
+    #[test]
+    fn backup_server_roles_checks_the_exported_file() {
+        FakeRunner::new().install();
+        match App::backup_server_roles() {
+            OperationOutcome::Failed { error: OperationError::Io { path, .. } } => {
+                assert!(path.to_string_lossy().contains("ServerRoles_"))
+            }
+            other => panic!("unexpected outcome: {}", other.message()),
+        }
+
+        FakeRunner::new()
+            .on_with("Export-Clixml", |script| {
+                let path = script.rsplit("-Path '").next().unwrap_or_default().trim_end_matches('\'');
+                std::fs::write(path, "<Objs />")?;
+                Ok(testing::output(true, "", ""))
+            })
+            .install();
+        let outcome = App::backup_server_roles();
+        assert!(matches!(&outcome, OperationOutcome::Success(m) if m.contains("ServerRoles_")), "{}", outcome.message());
+    }
 }
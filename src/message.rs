@@ -0,0 +1,58 @@
+//! What the main loop reacts to. Keys come from the terminal; operations running on a worker
+//! thread send their progress and their result, so the screen keeps updating while they run.
+
+use std::{cell::RefCell, sync::mpsc::Sender};
+
+use crossterm::event::KeyEvent;
+
+use crate::{
+    outcome::OperationOutcome,
+    status::{HostStatus, Level},
+};
+
+pub enum Message {
+    KeyPressed(KeyEvent),
+    /// A progress line from the running operation.
+    CommandOutput(String),
+    /// Bytes received so far for a download; `total` is unknown when the server doesn't say.
+    DownloadProgress { name: String, received: u64, total: Option<u64> },
+    /// The operation started with `id` is done.
+    OperationFinished { id: u64, outcome: OperationOutcome },
+    /// A new background reading of the host for the menu badges.
+    StatusRefreshed(HostStatus),
+    /// A background event for a notification in the corner of the screen.
+    Notify { text: String, level: Level },
+}
+
+thread_local! {
+    static OUTBOX: RefCell<Option<Sender<Message>>> = const { RefCell::new(None) };
+}
+
+/// Where messages sent from this thread go, if anywhere.
+pub fn outbox() -> Option<Sender<Message>> {
+    OUTBOX.with(|o| o.borrow().clone())
+}
+
+/// Sends this thread's messages to the main loop. Worker threads get a clone of its sender.
+pub fn set_outbox(sender: Sender<Message>) {
+    OUTBOX.with(|o| *o.borrow_mut() = Some(sender));
+}
+
+/// Sends `message` to the main loop; without an outbox (tests, background jobs) it is dropped.
+pub fn send(message: Message) {
+    OUTBOX.with(|o| {
+        if let Some(sender) = o.borrow().as_ref() {
+            let _ = sender.send(message);
+        }
+    });
+}
+
+/// Reports a progress line for the running operation.
+pub fn log(line: impl Into<String>) {
+    send(Message::CommandOutput(line.into()));
+}
+
+/// Shows `text` as a notification that goes away on its own.
+pub fn notify(text: impl Into<String>, level: Level) {
+    send(Message::Notify { text: text.into(), level });
+}
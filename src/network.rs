@@ -0,0 +1,608 @@
+use std::{
+    net::{TcpStream, ToSocketAddrs},
+    time::{Duration, Instant},
+};
+
+use crate::exec;
+use crate::{CheckItem, CheckStatus};
+
+const LISTENERS_SCRIPT: &str = r#"
+$procs = @{}
+Get-Process | ForEach-Object { $procs[[int]$_.Id] = $_.ProcessName }
+$svcs = @{}
+Get-CimInstance Win32_Service | Where-Object { $_.ProcessId } | ForEach-Object { $svcs[[int]$_.ProcessId] += @($_.Name) }
+Get-NetTCPConnection -State Listen | ForEach-Object {
+    $p = [int]$_.OwningProcess
+    "TCP`t$($_.LocalAddress)`t$($_.LocalPort)`t$p`t$($procs[$p])`t$($svcs[$p] -join ', ')"
+}
+Get-NetUDPEndpoint | ForEach-Object {
+    $p = [int]$_.OwningProcess
+    "UDP`t$($_.LocalAddress)`t$($_.LocalPort)`t$p`t$($procs[$p])`t$($svcs[$p] -join ', ')"
+}
+"#;
+
+/// Lists TCP listeners and UDP endpoints with their owning process and hosted services.
+pub fn listening_ports() -> Result<Vec<Vec<String>>, String> {
+    let output = exec::powershell(LISTENERS_SCRIPT)
+        .map_err(|e| format!("Failed to query listening ports: {}", e))?;
+    if !output.success {
+        return Err(format!("Failed to query listening ports:\n{}", output.stderr.trim()));
+    }
+
+    let mut rows: Vec<Vec<String>> = exec::tab_rows(&output.stdout)
+        .into_iter()
+        .filter(|row| row.len() == 6)
+        .collect();
+    rows.sort_by_key(|row| (row[0].clone(), row[2].parse::<u16>().unwrap_or(0), row[1].clone()));
+    rows.dedup();
+    Ok(rows)
+}
+
+const PING_SCRIPT: &str = r#"
+$ping = New-Object System.Net.NetworkInformation.Ping
+foreach ($h in $args) {
+    $times = @()
+    foreach ($i in 1..4) {
+        try {
+            $r = $ping.Send($h, 1000)
+            if ($r.Status -eq 'Success') { $times += $r.RoundtripTime }
+        } catch {}
+    }
+    $avg = if ($times.Count) { [int]($times | Measure-Object -Average).Average } else { '' }
+    "$h`t$($times.Count)`t$avg"
+}
+"#;
+
+/// Splits `host`, `host:port` or `[v6addr]:port` into host and optional port.
+fn parse_target(target: &str) -> (String, Option<u16>) {
+    if let Some(rest) = target.strip_prefix('[') {
+        if let Some((host, port)) = rest.split_once("]:") {
+            return (host.to_string(), port.parse().ok());
+        }
+        return (rest.trim_end_matches(']').to_string(), None);
+    }
+    match target.split_once(':') {
+        Some((host, port)) if !port.contains(':') => (host.to_string(), port.parse().ok()),
+        _ => (target.to_string(), None),
+    }
+}
+
+fn tcp_probe(host: &str, port: u16) -> String {
+    let addrs = match (host, port).to_socket_addrs() {
+        Ok(addrs) => addrs.collect::<Vec<_>>(),
+        Err(e) => return format!("DNS failed ({})", e),
+    };
+    let addr = match addrs.first() {
+        Some(addr) => *addr,
+        None => return "DNS returned no addresses".to_string(),
+    };
+    let start = Instant::now();
+    match TcpStream::connect_timeout(&addr, Duration::from_secs(3)) {
+        Ok(_) => format!("open ({} ms)", start.elapsed().as_millis()),
+        Err(e) if e.kind() == std::io::ErrorKind::TimedOut => "timed out".to_string(),
+        Err(_) => "closed/refused".to_string(),
+    }
+}
+
+/// Pings each target and, when a port is given, tests a TCP connection to it.
+pub fn connectivity_test(input: &str) -> (bool, String) {
+    let targets: Vec<(String, Option<u16>)> = input
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|t| !t.is_empty())
+        .map(parse_target)
+        .collect();
+
+    if targets.is_empty() {
+        return (false, "No targets entered.".to_string());
+    }
+
+    let hosts: Vec<String> = targets.iter().map(|(h, _)| exec::quote(h)).collect();
+    let script = format!("& {{ {} }} {}", PING_SCRIPT, hosts.join(" "));
+    let pings = match exec::powershell(&script) {
+        Ok(output) => exec::tab_rows(&output.stdout),
+        Err(e) => return (false, format!("Failed to run ping: {}", e)),
+    };
+
+    let mut all_ok = true;
+    let mut report = format!("{:<32} {:<22} {}\n", "Target", "ICMP (4 pings)", "TCP");
+    for (host, port) in &targets {
+        let icmp = match pings.iter().find(|row| row.first() == Some(host)) {
+            Some(row) if row.len() == 3 && row[1] != "0" => format!("{}/4 avg {} ms", row[1], row[2]),
+            Some(_) => {
+                all_ok = false;
+                "no reply".to_string()
+            }
+            None => {
+                all_ok = false;
+                "not tested".to_string()
+            }
+        };
+        let tcp = match port {
+            Some(port) => {
+                let result = tcp_probe(host, *port);
+                if !result.starts_with("open") {
+                    all_ok = false;
+                }
+                result
+            }
+            None => "-".to_string(),
+        };
+        let label = match port {
+            Some(port) => format!("{}:{}", host, port),
+            None => host.clone(),
+        };
+        report.push_str(&format!("{:<32} {:<22} {}\n", label, icmp, tcp));
+    }
+
+    if !all_ok {
+        report.push_str("\nSome targets failed. Note that many hosts block ICMP while still accepting TCP.");
+    }
+    (all_ok, report)
+}
+
+/// Lists and writes a 1 MB test file on `$path`, mapping it with `$cred` first when given.
+/// Prints `Key=Value` lines: List/Items, Write/WriteError, or Error when the share is not accessible.
+const SHARE_SCRIPT: &str = r#"
+$drive = $null
+try {
+    if ($cred) {
+        $drive = New-PSDrive -Name ("ShareTest" + (Get-Random)) -PSProvider FileSystem -Root $path -Credential $cred -ErrorAction Stop
+    }
+    $t = Measure-Command { $items = @(Get-ChildItem -LiteralPath $path -Force -ErrorAction Stop | Select-Object -First 1000) }
+    "List=$([int]$t.TotalMilliseconds)"
+    "Items=$($items.Count)"
+    $file = Join-Path $path ".server-helper-write-test-$(Get-Random).tmp"
+    try {
+        $data = New-Object byte[] (1MB)
+        $t = Measure-Command { [IO.File]::WriteAllBytes($file, $data) }
+        "Write=$([int]$t.TotalMilliseconds)"
+        Remove-Item -LiteralPath $file -Force -ErrorAction Stop
+    } catch {
+        "WriteError=$($_.Exception.Message -replace '\s+', ' ')"
+    }
+} catch {
+    "Error=$($_.Exception.Message -replace '\s+', ' ')"
+} finally {
+    if ($drive) { Remove-PSDrive -Name $drive.Name -Force }
+}
+"#;
+
+/// Tests a UNC path as a backup destination: SMB reachability, listing latency and
+/// whether a file can be written, with the current or the given credentials.
+pub fn share_test(path: &str, credentials: Option<&(String, String)>) -> (bool, String) {
+    let host = match path.strip_prefix("\\\\").and_then(|rest| rest.split('\\').find(|p| !p.is_empty())) {
+        Some(host) if path.trim_start_matches('\\').contains('\\') => host.to_string(),
+        _ => return (false, format!("Not a UNC path: {}\nEnter it as \\\\server\\share[\\folder].", path)),
+    };
+
+    let mut report = format!("Share:        {}\n", path);
+    report.push_str(&format!(
+        "Credentials:  {}\n",
+        match credentials {
+            Some((user, _)) => user.clone(),
+            None => format!("current user ({})", std::env::var("USERNAME").unwrap_or_default()),
+        }
+    ));
+    let smb = tcp_probe(&host, 445);
+    report.push_str(&format!("SMB (TCP 445): {}\n", smb));
+    if !smb.starts_with("open") {
+        report.push_str("\nThe server does not accept SMB connections from here; check the firewall and that it is online.");
+        return (false, report);
+    }
+
+    let credential = match credentials {
+        Some((user, password)) => format!(
+            "$cred = New-Object System.Management.Automation.PSCredential({}, (ConvertTo-SecureString {} -AsPlainText -Force))",
+            exec::quote(user),
+            exec::quote(password)
+        ),
+        None => "$cred = $null".to_string(),
+    };
+    let script = format!("$path = {}\n{}\n{}", exec::quote(path), credential, SHARE_SCRIPT);
+    let values = match exec::powershell(&script) {
+        Ok(output) => exec::key_values(&output.stdout),
+        Err(e) => return (false, format!("{}\nFailed to run the share test: {}", report, e)),
+    };
+    let get = |key: &str| values.get(key).map(String::as_str).unwrap_or("");
+
+    if !get("Error").is_empty() {
+        report.push_str(&format!("Access:       FAILED - {}\n", get("Error")));
+        if get("Error").contains("multiple connections") {
+            report.push_str(
+                "\nThis session is already connected to the server with other credentials.\n\
+                Disconnect with `net use \\\\server\\share /delete` or test with the current user.",
+            );
+        }
+        return (false, report);
+    }
+    report.push_str(&format!("Access:       OK, listed {} item(s) in {} ms\n", get("Items"), get("List")));
+
+    let writable = !get("Write").is_empty();
+    match get("Write").parse::<f64>() {
+        Ok(ms) => report.push_str(&format!(
+            "Write:        OK, 1 MB written in {} ms ({:.1} MB/s), test file removed\n",
+            ms,
+            1000.0 / ms.max(1.0)
+        )),
+        Err(_) => report.push_str(&format!("Write:        FAILED - {}\n", get("WriteError"))),
+    }
+    report.push_str(if writable {
+        "\nThe share is usable as a backup destination with these credentials."
+    } else {
+        "\nThe share is read-only for these credentials; grant Modify on the share and folder to use it for backups."
+    });
+    (writable, report)
+}
+
+const DNS_SCRIPT: &str = r#"
+$servers = @(Get-DnsClientServerAddress | ForEach-Object { $_.ServerAddresses }) | Sort-Object -Unique
+function Probe($source, $server) {
+    $sw = [Diagnostics.Stopwatch]::StartNew()
+    try {
+        if ($server) { $r = Resolve-DnsName -Name $name -Server $server -DnsOnly -QuickTimeout -ErrorAction Stop }
+        else { $r = Resolve-DnsName -Name $name -ErrorAction Stop }
+        $answers = ($r | Where-Object { $_.Section -eq 'Answer' } | ForEach-Object {
+            if ($_.IPAddress) { $_.IPAddress } elseif ($_.NameHost) { "$($_.Type) $($_.NameHost)" }
+        }) -join ', '
+        "$source`t$server`t$($sw.ElapsedMilliseconds) ms`t$answers"
+    } catch {
+        "$source`t$server`t$($sw.ElapsedMilliseconds) ms`tERROR: $($_.Exception.Message)"
+    }
+}
+Probe 'System resolver' ''
+foreach ($s in $servers) { Probe 'Configured' $s }
+foreach ($s in $custom) { Probe 'Custom' $s }
+"#;
+
+/// Resolves `query` (a name optionally followed by extra DNS servers) against the
+/// system resolver, every configured server and the custom servers.
+pub fn dns_lookup(query: &str) -> Result<Vec<Vec<String>>, String> {
+    let mut parts = query.split_whitespace();
+    let name = match parts.next() {
+        Some(name) => name,
+        None => return Err("No name entered.".to_string()),
+    };
+    let custom: Vec<String> = parts.map(exec::quote).collect();
+
+    let script = format!("$name = {}; $custom = @({}); {}", exec::quote(name), custom.join(", "), DNS_SCRIPT);
+    let output = exec::powershell(&script)
+        .map_err(|e| format!("Failed to run DNS lookup: {}", e))?;
+
+    let rows: Vec<Vec<String>> = exec::tab_rows(&output.stdout)
+        .into_iter()
+        .filter(|row| row.len() == 4)
+        .map(|mut row| {
+            // The system resolver probe has no explicit server
+            if row[1].is_empty() {
+                row[1] = "(cache + hosts)".to_string();
+            }
+            row
+        })
+        .collect();
+    if rows.is_empty() {
+        return Err(format!("DNS lookup returned no results:\n{}", output.stderr.trim()));
+    }
+    Ok(rows)
+}
+
+pub fn flush_dns_cache() -> Result<(), String> {
+    match exec::change("Clear-DnsClientCache") {
+        Ok(output) if output.success => Ok(()),
+        Ok(output) => Err(output.stderr.trim().to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+const NETBIRD_CONFIG_SCRIPT: &str = r#"
+$nb = Get-NetAdapter -ErrorAction SilentlyContinue | Where-Object {
+    $_.Name -eq 'wt0' -or $_.InterfaceDescription -like '*NetBird*' -or $_.InterfaceDescription -like '*WireGuard*'
+} | Select-Object -First 1
+"NETBIRD`t$($nb.Name)"
+Get-NetRoute -AddressFamily IPv4 -ErrorAction SilentlyContinue | ForEach-Object { "ROUTE`t$($_.InterfaceAlias)`t$($_.DestinationPrefix)" }
+Get-DnsClient -ErrorAction SilentlyContinue | Where-Object { $_.ConnectionSpecificSuffix } | ForEach-Object { "SUFFIX`t$($_.InterfaceAlias)`t$($_.ConnectionSpecificSuffix)" }
+(Get-DnsClientGlobalSetting).SuffixSearchList | ForEach-Object { "SUFFIX`tglobal search list`t$_" }
+Get-DnsClientNrptRule -ErrorAction SilentlyContinue | ForEach-Object { foreach ($ns in $_.Namespace) { "NRPT`t$ns`t$($_.NameServers -join ', ')" } }
+"DOMAIN`t$((Get-CimInstance Win32_ComputerSystem).Domain)"
+"#;
+
+/// Parses `a.b.c.d/len` into a network address and prefix length.
+fn parse_prefix(prefix: &str) -> Option<(u32, u8)> {
+    let (addr, len) = prefix.split_once('/')?;
+    let addr: std::net::Ipv4Addr = addr.parse().ok()?;
+    let len: u8 = len.parse().ok()?;
+    if len > 32 {
+        return None;
+    }
+    Some((u32::from(addr), len))
+}
+
+fn prefix_mask(len: u8) -> u32 {
+    if len == 0 {
+        0
+    } else {
+        u32::MAX << (32 - len)
+    }
+}
+
+fn prefixes_overlap(a: (u32, u8), b: (u32, u8)) -> bool {
+    let mask = prefix_mask(a.1.min(b.1));
+    a.0 & mask == b.0 & mask
+}
+
+/// Routes worth comparing: skips the default route, host routes, loopback,
+/// link-local, multicast and broadcast entries that every adapter has.
+fn is_network_route(prefix: (u32, u8)) -> bool {
+    let (addr, len) = prefix;
+    let first_octet = addr >> 24;
+    len > 0 && len < 32 && first_octet != 127 && first_octet < 224 && addr & 0xFFFF_0000 != 0xA9FE_0000
+}
+
+/// Compares NetBird's routes and DNS settings with the other adapters' configuration.
+pub fn netbird_conflicts() -> Result<Vec<CheckItem>, String> {
+    let output = exec::powershell(NETBIRD_CONFIG_SCRIPT)
+        .map_err(|e| format!("Failed to read network configuration: {}", e))?;
+    let rows = exec::tab_rows(&output.stdout);
+    let field = |row: &Vec<String>, i: usize| row.get(i).cloned().unwrap_or_default();
+
+    let netbird = rows
+        .iter()
+        .find(|row| row[0] == "NETBIRD")
+        .map(|row| field(row, 1))
+        .unwrap_or_default();
+    if netbird.is_empty() {
+        return Ok(vec![CheckItem {
+            name: "NetBird interface".to_string(),
+            status: CheckStatus::Unknown,
+            detail: "No NetBird interface found".to_string(),
+            remediation: None,
+            advice: Some("Install NetBird and connect it with `netbird up`, then run this check again.".to_string()),
+        }]);
+    }
+
+    let mut items = Vec::new();
+
+    // Overlapping subnets between NetBird routes and every other adapter
+    let mut netbird_routes = Vec::new();
+    let mut local_routes = Vec::new();
+    for row in rows.iter().filter(|row| row[0] == "ROUTE" && row.len() >= 3) {
+        if let Some(prefix) = parse_prefix(&row[2]).filter(|p| is_network_route(*p)) {
+            if row[1] == netbird {
+                netbird_routes.push((row[2].clone(), prefix));
+            } else {
+                local_routes.push((row[1].clone(), row[2].clone(), prefix));
+            }
+        }
+    }
+    netbird_routes.dedup();
+
+    for (nb_text, nb_prefix) in &netbird_routes {
+        for (alias, text, prefix) in &local_routes {
+            if prefixes_overlap(*nb_prefix, *prefix) {
+                items.push(CheckItem {
+                    name: format!("Route {}", nb_text),
+                    status: CheckStatus::Fail,
+                    detail: format!("NetBird route {} overlaps {} on \"{}\"", nb_text, text, alias),
+                    remediation: None,
+                    advice: Some(format!(
+                        "Traffic for {} may leave through the wrong interface. Narrow or disable the route in the \
+                        NetBird management console (Network Routes), or remove this peer from the route's distribution groups.",
+                        text
+                    )),
+                });
+            }
+        }
+    }
+    if items.is_empty() {
+        items.push(CheckItem {
+            name: "Routes".to_string(),
+            status: CheckStatus::Pass,
+            detail: format!("{} NetBird route(s), none overlap local subnets", netbird_routes.len()),
+            remediation: None,
+            advice: None,
+        });
+    }
+
+    // Search domains that are configured on both NetBird and another adapter
+    let netbird_domains: Vec<String> = rows
+        .iter()
+        .filter(|row| (row[0] == "SUFFIX" && field(row, 1) == netbird) || row[0] == "NRPT")
+        .map(|row| {
+            let domain = if row[0] == "NRPT" { field(row, 1) } else { field(row, 2) };
+            domain.trim_start_matches('.').to_lowercase()
+        })
+        .filter(|d| !d.is_empty())
+        .collect();
+    let mut dns_conflicts = 0;
+    for row in rows.iter().filter(|row| row[0] == "SUFFIX" && field(row, 1) != netbird) {
+        let domain = field(row, 2).to_lowercase();
+        if netbird_domains.contains(&domain) {
+            dns_conflicts += 1;
+            items.push(CheckItem {
+                name: format!("Search domain {}", domain),
+                status: CheckStatus::Fail,
+                detail: format!("{} is set on both NetBird and {}", domain, field(row, 1)),
+                remediation: None,
+                advice: Some(format!(
+                    "Keep {} on one side only: remove it from the NetBird nameserver group's match domains, or clear it \
+                    on the adapter with Set-DnsClient -InterfaceAlias '{}' -ConnectionSpecificSuffix ''.",
+                    domain,
+                    field(row, 1)
+                )),
+            });
+        }
+    }
+
+    // NetBird DNS taking over the Active Directory domain breaks DC discovery
+    let ad_domain = rows
+        .iter()
+        .find(|row| row[0] == "DOMAIN")
+        .map(|row| field(row, 1).to_lowercase())
+        .unwrap_or_default();
+    if ad_domain.contains('.') {
+        for domain in &netbird_domains {
+            if ad_domain == *domain || ad_domain.ends_with(&format!(".{}", domain)) {
+                dns_conflicts += 1;
+                items.push(CheckItem {
+                    name: format!("AD domain {}", ad_domain),
+                    status: CheckStatus::Fail,
+                    detail: format!("NetBird DNS handles {}, which covers this server's domain", domain),
+                    remediation: None,
+                    advice: Some(
+                        "Domain controller lookups will be sent to NetBird's nameservers. Remove the match domain in the \
+                        NetBird console or point that nameserver group at your domain controllers."
+                            .to_string(),
+                    ),
+                });
+            }
+        }
+    }
+
+    if dns_conflicts == 0 {
+        items.push(CheckItem {
+            name: "DNS".to_string(),
+            status: CheckStatus::Pass,
+            detail: format!("{} NetBird domain(s), no duplicates with local adapters", netbird_domains.len()),
+            remediation: None,
+            advice: None,
+        });
+    }
+
+    Ok(items)
+}
+
+/// Lists IPv4 routes stored in the persistent store (`route -p` / `New-NetRoute`).
+pub fn persistent_routes() -> Result<Vec<Vec<String>>, String> {
+    let output = exec::powershell(
+        "Get-NetRoute -PolicyStore PersistentStore -AddressFamily IPv4 -ErrorAction SilentlyContinue | \
+        ForEach-Object { \"$($_.DestinationPrefix)`t$($_.NextHop)`t$($_.RouteMetric)`t$($_.InterfaceAlias)\" }",
+    )
+    .map_err(|e| format!("Failed to list routes: {}", e))?;
+    if !output.success {
+        return Err(format!("Failed to list routes:\n{}", output.stderr.trim()));
+    }
+    Ok(exec::tab_rows(&output.stdout).into_iter().filter(|row| row.len() == 4).collect())
+}
+
+/// Adds a persistent route from `destination/prefix gateway [metric]`.
+pub fn add_route(spec: &str) -> Result<String, String> {
+    let parts: Vec<&str> = spec.split_whitespace().collect();
+    if parts.len() < 2 || parts.len() > 3 {
+        return Err("Expected: destination/prefix gateway [metric]".to_string());
+    }
+    let (network, len) = parse_prefix(parts[0]).ok_or_else(|| format!("Invalid destination: {}", parts[0]))?;
+    let gateway: std::net::Ipv4Addr = parts[1].parse().map_err(|_| format!("Invalid gateway: {}", parts[1]))?;
+    let network = std::net::Ipv4Addr::from(network & prefix_mask(len)).to_string();
+    let mask = std::net::Ipv4Addr::from(prefix_mask(len)).to_string();
+    let gateway = gateway.to_string();
+
+    let mut args = vec!["-p", "add", network.as_str(), "mask", mask.as_str(), gateway.as_str()];
+    if let Some(metric) = parts.get(2) {
+        if metric.parse::<u32>().is_err() {
+            return Err(format!("Invalid metric: {}", metric));
+        }
+        args.extend(["metric", metric]);
+    }
+
+    let output = exec::change_program("route", &args).map_err(|e| format!("Failed to run route: {}", e))?;
+    // route.exe reports most failures on stdout with a zero exit code
+    let text = format!("{}{}", output.stdout, output.stderr);
+    if !output.success || text.to_lowercase().contains("failed") || text.to_lowercase().contains("bad ") {
+        return Err(format!("route add failed: {}", text.trim()));
+    }
+    Ok(format!("Added persistent route {}/{} via {}", network, len, gateway))
+}
+
+/// Removes a route from both the active and persistent stores.
+pub fn remove_route(prefix: &str, next_hop: &str) -> Result<String, String> {
+    let script = format!(
+        "Remove-NetRoute -DestinationPrefix {} -NextHop {} -Confirm:$false -ErrorAction Stop",
+        exec::quote(prefix),
+        exec::quote(next_hop)
+    );
+    match exec::change(&script) {
+        Ok(output) if output.success => Ok(format!("Removed route {} via {}", prefix, next_hop)),
+        Ok(output) => Err(format!("Failed to remove route: {}", output.stderr.trim())),
+        Err(e) => Err(format!("Failed to remove route: {}", e)),
+    }
+}
+
+const ADAPTERS_SCRIPT: &str = r#"
+Get-NetAdapter | Sort-Object Name | ForEach-Object {
+    $vlan = if ($_.VlanID) { $_.VlanID } else { '' }
+    "$($_.Name)`t$($_.InterfaceDescription)`t$($_.Status)`t$($_.LinkSpeed)`t$($_.MacAddress)`t$vlan"
+}
+"#;
+
+/// Lists physical and virtual adapters with their link state and VLAN ID.
+pub fn adapters() -> Result<Vec<Vec<String>>, String> {
+    let output = exec::powershell(ADAPTERS_SCRIPT).map_err(|e| format!("Failed to list adapters: {}", e))?;
+    if !output.success {
+        return Err(format!("Failed to list adapters:\n{}", output.stderr.trim()));
+    }
+    Ok(exec::tab_rows(&output.stdout).into_iter().filter(|row| row.len() == 6).collect())
+}
+
+/// Advanced driver properties of one adapter (jumbo packets, RSS, VLAN ID, offloads, ...)
+/// with the values the driver accepts.
+pub fn adapter_properties(adapter: &str) -> Result<Vec<Vec<String>>, String> {
+    let script = format!(
+        "Get-NetAdapterAdvancedProperty -Name {} -ErrorAction Stop | Sort-Object DisplayName | ForEach-Object {{ \
+            $allowed = if ($_.ValidDisplayValues) {{ $_.ValidDisplayValues -join ' / ' }} \
+                elseif ($null -ne $_.NumericParameterMaxValue) {{ \"$($_.NumericParameterMinValue)-$($_.NumericParameterMaxValue)\" }} \
+                else {{ '' }}; \
+            \"$($_.DisplayName)`t$($_.DisplayValue)`t$($_.RegistryKeyword)`t$allowed\" \
+        }}",
+        exec::quote(adapter)
+    );
+    let output = exec::powershell(&script).map_err(|e| format!("Failed to read adapter properties: {}", e))?;
+    if !output.success {
+        return Err(format!("Failed to read properties of {}:\n{}", adapter, output.stderr.trim()));
+    }
+    Ok(exec::tab_rows(&output.stdout)
+        .into_iter()
+        .map(|mut row| {
+            row.resize(4, String::new());
+            row
+        })
+        .collect())
+}
+
+/// Sets an advanced property by display name and display value. The driver
+/// restarts the adapter, so connectivity drops briefly.
+pub fn set_adapter_property(adapter: &str, property: &str, value: &str) -> Result<String, String> {
+    if value.is_empty() {
+        return Err("Enter a value.".to_string());
+    }
+    let script = format!(
+        "Set-NetAdapterAdvancedProperty -Name {} -DisplayName {} -DisplayValue {} -ErrorAction Stop",
+        exec::quote(adapter),
+        exec::quote(property),
+        exec::quote(value)
+    );
+    match exec::change(&script) {
+        Ok(output) if output.success => Ok(format!("{}: set {} to {}", adapter, property, value)),
+        Ok(output) => Err(format!("Failed to set {}: {}", property, output.stderr.trim())),
+        Err(e) => Err(format!("Failed to set {}: {}", property, e)),
+    }
+}
+
+/// Sets the adapter's VLAN ID (0 removes tagging).
+pub fn set_vlan(adapter: &str, value: &str) -> Result<String, String> {
+    let vlan: u16 = value
+        .trim()
+        .parse()
+        .ok()
+        .filter(|v| *v <= 4094)
+        .ok_or_else(|| format!("Invalid VLAN ID: {} (expected 0-4094)", value.trim()))?;
+    let script = format!(
+        "Set-NetAdapter -Name {} -VlanID {} -Confirm:$false -ErrorAction Stop",
+        exec::quote(adapter),
+        vlan
+    );
+    match exec::change(&script) {
+        Ok(output) if output.success && vlan == 0 => Ok(format!("{}: VLAN tagging removed", adapter)),
+        Ok(output) if output.success => Ok(format!("{}: VLAN ID set to {}", adapter, vlan)),
+        Ok(output) => Err(format!("Failed to set the VLAN ID: {}", output.stderr.trim())),
+        Err(e) => Err(format!("Failed to set the VLAN ID: {}", e)),
+    }
+}
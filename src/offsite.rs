@@ -0,0 +1,341 @@
+//! Offsite copies of role backups: each `ServerRoles_<time>.xml`, with its readable list and
+//! source record, is zipped and uploaded to the SFTP server or S3 bucket in the active
+//! profile's `offsite` setting. Backups that fail to upload stay queued in `offsite.txt` in the
+//! backup directory and are retried after the next backup or on request.
+
+use std::path::{Path, PathBuf};
+
+use crate::{backup_dir, dates, delta, exec, signing, history, message, profiles, rename, status::Level};
+
+const STATUS_FILE: &str = "offsite.txt";
+
+/// Where backups are uploaded.
+#[derive(Debug, PartialEq)]
+pub enum Destination {
+    /// `user@host`, port and remote folder, through the OpenSSH `sftp` client with key
+    /// authentication.
+    Sftp { login: String, port: Option<u16>, path: String },
+    /// `s3://bucket/prefix`, through the AWS CLI, optionally at an S3-compatible endpoint.
+    S3 { url: String, endpoint: Option<String> },
+}
+
+impl Destination {
+    fn parse(text: &str, endpoint: Option<String>) -> Result<Destination, String> {
+        let text = text.trim().trim_end_matches('/');
+        if let Some(rest) = text.strip_prefix("sftp://") {
+            let (server, path) = rest.split_once('/').map_or((rest, ""), |(s, p)| (s, p));
+            let (login, port) = match server.rsplit_once(':') {
+                Some((login, port)) => {
+                    (login, Some(port.parse().map_err(|_| format!("Invalid SFTP port in {}", text))?))
+                }
+                None => (server, None),
+            };
+            if !login.contains('@') || login.ends_with('@') {
+                return Err(format!("Give the SFTP user and host, e.g. sftp://backup@nas01/srv/backups (not {})", text));
+            }
+            return Ok(Destination::Sftp { login: login.to_string(), port, path: format!("/{}", path) });
+        }
+        match text.strip_prefix("s3://") {
+            Some(bucket) if !bucket.is_empty() => Ok(Destination::S3 { url: text.to_string(), endpoint }),
+            _ => Err(format!("The offsite destination must start with sftp:// or s3:// (not {})", text)),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Destination::Sftp { login, port: Some(port), path } => format!("sftp://{}:{}{}", login, port, path),
+            Destination::Sftp { login, port: None, path } => format!("sftp://{}{}", login, path),
+            Destination::S3 { url, endpoint: Some(endpoint) } => format!("{} at {}", url, endpoint),
+            Destination::S3 { url, endpoint: None } => url.clone(),
+        }
+    }
+
+    fn upload(&self, file: &Path) -> Result<(), String> {
+        let name = file.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        let local = file.to_string_lossy().to_string();
+        let (program, output) = match self {
+            Destination::Sftp { login, port, path } => {
+                let batch = file.with_extension("sftp");
+                let commands = format!("put \"{}\" \"{}/{}\"\n", local, path.trim_end_matches('/'), name);
+                std::fs::write(&batch, commands).map_err(|e| format!("Failed to write {}: {}", batch.display(), e))?;
+                let batch_arg = batch.to_string_lossy().to_string();
+                let port = port.map(|p| p.to_string());
+                let mut args = vec!["-b", batch_arg.as_str(), "-o", "BatchMode=yes", "-o", "ConnectTimeout=30"];
+                if let Some(port) = &port {
+                    args.extend(["-P", port.as_str()]);
+                }
+                args.push(login);
+                let output = exec::run("sftp", &args);
+                let _ = std::fs::remove_file(&batch);
+                ("sftp", output)
+            }
+            Destination::S3 { url, endpoint } => {
+                let target = format!("{}/{}", url, name);
+                let mut args = vec!["s3", "cp", local.as_str(), target.as_str(), "--only-show-errors"];
+                if let Some(endpoint) = endpoint {
+                    args.extend(["--endpoint-url", endpoint.as_str()]);
+                }
+                ("aws", exec::run("aws", &args))
+            }
+        };
+        match output {
+            Ok(output) if output.success => Ok(()),
+            Ok(output) => {
+                let error = if output.stderr.trim().is_empty() { output.stdout } else { output.stderr };
+                Err(error.trim().lines().last().unwrap_or("failed").to_string())
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(match program {
+                "sftp" => "The OpenSSH client is not installed (Add-WindowsCapability -Online -Name OpenSSH.Client~~~~0.0.1.0)",
+                _ => "The AWS CLI is not installed (winget install Amazon.AWSCLI)",
+            }
+            .to_string()),
+            Err(e) => Err(format!("Failed to run {}: {}", program, e)),
+        }
+    }
+}
+
+/// The active profile's offsite destination; `None` when uploads are not set up.
+pub fn destination() -> Option<Result<Destination, String>> {
+    let settings = profiles::settings();
+    settings.offsite.map(|text| Destination::parse(&text, settings.s3_endpoint))
+}
+
+/// A backup's upload record: uploaded, or queued with the attempts made so far.
+#[derive(Debug, PartialEq)]
+struct Entry {
+    backup: String,
+    uploaded: bool,
+    attempts: u32,
+    /// UTC time of the last attempt.
+    time: String,
+    /// Where it went, or why the last attempt failed.
+    detail: String,
+}
+
+fn read_entries(dir: &Path) -> Vec<Entry> {
+    std::fs::read_to_string(dir.join(STATUS_FILE))
+        .unwrap_or_default()
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split('\t').collect();
+            Some(Entry {
+                backup: fields.first().filter(|b| !b.is_empty())?.to_string(),
+                uploaded: fields.get(1) == Some(&"uploaded"),
+                attempts: fields.get(2).and_then(|a| a.parse().ok()).unwrap_or(0),
+                time: fields.get(3).unwrap_or(&"").to_string(),
+                detail: fields.get(4).unwrap_or(&"").to_string(),
+            })
+        })
+        .collect()
+}
+
+fn write_entries(dir: &Path, entries: &[Entry]) -> Result<(), String> {
+    let mut text = "# backup\tstate\tattempts\tlast attempt (UTC)\tdetail\r\n".to_string();
+    for entry in entries {
+        text.push_str(&format!(
+            "{}\t{}\t{}\t{}\t{}\r\n",
+            entry.backup,
+            if entry.uploaded { "uploaded" } else { "queued" },
+            entry.attempts,
+            entry.time,
+            entry.detail.replace(['\t', '\r', '\n'], " ")
+        ));
+    }
+    let path = dir.join(STATUS_FILE);
+    std::fs::write(&path, text).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Zips `backup` with its readable feature list and source record, next to it.
+fn archive(backup: &Path) -> Result<PathBuf, String> {
+    let name = backup.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let list = backup.with_file_name(name.replace("ServerRoles_", "InstalledFeatures_")).with_extension("txt");
+    let [manifest, signature] = signing::files(backup);
+    let files: Vec<String> = [backup.to_path_buf(), list, rename::source_path(backup), manifest, signature]
+        .iter()
+        .filter(|path| path.exists())
+        .map(|path| exec::quote(&path.to_string_lossy()))
+        .collect();
+    let zip = backup.with_extension("zip");
+    let script = format!(
+        "Compress-Archive -LiteralPath {} -DestinationPath {} -Force -ErrorAction Stop",
+        files.join(","),
+        exec::quote(&zip.to_string_lossy())
+    );
+    match exec::powershell(&script) {
+        Ok(output) if output.success => Ok(zip),
+        Ok(output) => Err(format!("Failed to zip {}: {}", name, output.stderr.trim())),
+        Err(e) => Err(format!("Failed to zip {}: {}", name, e)),
+    }
+}
+
+/// Adds `backup` to the queue in `dir` unless it is already there.
+fn enqueue(dir: &Path, backup: &str) -> Result<(), String> {
+    let mut entries = read_entries(dir);
+    match entries.iter_mut().find(|e| e.backup == backup) {
+        Some(entry) => entry.uploaded = false,
+        None => entries.push(Entry {
+            backup: backup.to_string(),
+            uploaded: false,
+            attempts: 0,
+            time: String::new(),
+            detail: String::new(),
+        }),
+    }
+    write_entries(dir, &entries)
+}
+
+/// Tries each queued backup in `dir` once and reports how many went offsite.
+fn process(dir: &Path, destination: &Destination) -> Result<String, String> {
+    let mut entries = read_entries(dir);
+    let (mut uploaded, mut failed) = (Vec::new(), Vec::new());
+    for entry in entries.iter_mut().filter(|e| !e.uploaded) {
+        let backup = dir.join(&entry.backup);
+        let result = if backup.exists() {
+            archive(&backup).and_then(|zip| destination.upload(&zip))
+        } else {
+            Err("The backup file is gone".to_string())
+        };
+        entry.attempts += 1;
+        entry.time = history::timestamp();
+        match result {
+            Ok(()) => {
+                entry.uploaded = true;
+                entry.detail = destination.describe();
+                uploaded.push(entry.backup.clone());
+            }
+            Err(reason) => {
+                entry.detail = reason.clone();
+                failed.push(format!("{}: {}", entry.backup, reason));
+            }
+        }
+    }
+    write_entries(dir, &entries)?;
+    if !uploaded.is_empty() {
+        history::record(&format!("Uploaded {} to {}", uploaded.join(", "), destination.describe()));
+        message::notify(format!("Offsite upload completed: {}", uploaded.join(", ")), Level::Good);
+    }
+    if !failed.is_empty() {
+        message::notify(format!("Offsite upload failed for {} backup(s); queued for retry", failed.len()), Level::Bad);
+    }
+    let mut report = match uploaded.len() {
+        0 if failed.is_empty() => "No backups are waiting to go offsite.".to_string(),
+        0 => String::new(),
+        n => format!("Uploaded {} backup(s) to {}.", n, destination.describe()),
+    };
+    if !failed.is_empty() {
+        report = format!("{} {} still queued for retry:\n{}", report, failed.len(), failed.join("\n"));
+    }
+    Ok(report.trim().to_string())
+}
+
+/// Queues `backup` and uploads the queue when an offsite destination is set, for the backup
+/// report. `None` when uploads are not set up.
+pub fn after_backup(backup: &Path) -> Option<String> {
+    let destination = match destination()? {
+        Ok(destination) => destination,
+        Err(message) => return Some(format!("Offsite: not uploaded. {}", message)),
+    };
+    let dir = backup.parent().map_or_else(backup_dir, Path::to_path_buf);
+    let name = backup.file_name()?.to_string_lossy().to_string();
+    Some(match enqueue(&dir, &name).and_then(|_| process(&dir, &destination)) {
+        Ok(report) => format!("Offsite: {}", report),
+        Err(message) => format!("Offsite: {}", message),
+    })
+}
+
+/// Uploads `backup` from the backup directory again, or for the first time.
+pub fn upload(backup: &str) -> Result<String, String> {
+    let destination = destination().ok_or_else(not_set_up)??;
+    let dir = backup_dir();
+    enqueue(&dir, backup)?;
+    process(&dir, &destination)
+}
+
+/// Retries every queued backup.
+pub fn retry() -> Result<String, String> {
+    let destination = destination().ok_or_else(not_set_up)??;
+    process(&backup_dir(), &destination)
+}
+
+fn not_set_up() -> String {
+    format!(
+        "No offsite destination is set for {}. Add offsite = sftp://... or s3://... to its profile.ini (Profiles, n).",
+        profiles::describe(profiles::active().as_deref())
+    )
+}
+
+/// Role backups in the backup directory, newest first, with whether each is offsite.
+pub fn rows() -> Result<Vec<Vec<String>>, String> {
+    let dir = backup_dir();
+    let entries = read_entries(&dir);
+    let mut backups: Vec<(i64, String)> = std::fs::read_dir(&dir)
+        .map(|list| {
+            list.flatten()
+                .map(|e| e.file_name().to_string_lossy().to_string())
+                .filter(|name| {
+                    name.starts_with("ServerRoles_") && (name.ends_with(".xml") || name.ends_with(&format!(".{}", delta::EXTENSION)))
+                })
+                .map(|name| (dates::from_file_name(&name).unwrap_or(0), name))
+                .collect()
+        })
+        .unwrap_or_default();
+    backups.sort_by(|a, b| b.cmp(a));
+    Ok(backups
+        .into_iter()
+        .map(|(secs, name)| {
+            let entry = entries.iter().find(|e| e.backup == name);
+            vec![
+                name.clone(),
+                if secs > 0 { dates::local(secs) } else { String::new() },
+                match entry {
+                    Some(e) if e.uploaded => "yes".to_string(),
+                    Some(_) => "QUEUED".to_string(),
+                    None => "-".to_string(),
+                },
+                entry.map(|e| e.attempts.to_string()).unwrap_or_default(),
+                entry.map(|e| dates::local_from_utc(&e.time)).unwrap_or_default(),
+                entry.map(|e| e.detail.clone()).unwrap_or_default(),
+            ]
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{temp_dir, FakeRunner};
+
+    #[test]
+    fn reads_sftp_and_s3_destinations() {
+        assert_eq!(
+            Destination::parse("sftp://backup@nas01:2222/srv/backups/", None),
+            Ok(Destination::Sftp { login: "backup@nas01".to_string(), port: Some(2222), path: "/srv/backups".to_string() })
+        );
+        let s3 = Destination::parse("s3://server-backups/site-a", Some("https://s3.example.com".to_string()));
+        assert_eq!(s3.map(|d| d.describe()), Ok("s3://server-backups/site-a at https://s3.example.com".to_string()));
+        assert!(Destination::parse("sftp://nas01/srv", None).is_err());
+        assert!(Destination::parse("ftp://nas01/srv", None).is_err());
+    }
+
+    #[test]
+    fn keeps_failed_uploads_queued_until_they_succeed() {
+        let dir = temp_dir("offsite");
+        std::fs::write(dir.join("ServerRoles_1700000000.xml"), "<Objs />").expect("write backup");
+        let destination = Destination::S3 { url: "s3://server-backups".to_string(), endpoint: None };
+
+        FakeRunner::new().install();
+        enqueue(&dir, "ServerRoles_1700000000.xml").expect("enqueue");
+        let report = process(&dir, &destination).unwrap_or_default();
+        assert!(report.contains("1 still queued"), "{}", report);
+        assert!(!read_entries(&dir)[0].uploaded);
+
+        let runner = FakeRunner::new().on("aws s3 cp", "").install();
+        let report = process(&dir, &destination).unwrap_or_default();
+        assert_eq!(report, "Uploaded 1 backup(s) to s3://server-backups.");
+        assert!(runner.ran("s3 cp"));
+        let entries = read_entries(&dir);
+        assert!(entries[0].uploaded);
+        assert_eq!(entries[0].attempts, 2);
+    }
+}
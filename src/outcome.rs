@@ -0,0 +1,86 @@
+use std::{fmt, path::PathBuf};
+
+/// Why an operation failed.
+#[derive(Clone, PartialEq)]
+pub enum OperationError {
+    /// A file could not be downloaded.
+    Download { item: String, reason: String },
+    /// A command, installer or script reported an error.
+    Command { action: String, detail: String },
+    /// A local file or directory could not be created, read or written.
+    Io { path: PathBuf, reason: String },
+    /// The operation was not started: bad input, or not possible on this host or in this mode.
+    Refused(String),
+    /// Anything else, already worded for the user.
+    Other(String),
+}
+
+impl fmt::Display for OperationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OperationError::Download { item, reason } => write!(f, "Failed to download {}: {}", item, reason),
+            OperationError::Command { action, detail } => write!(f, "{} failed:\n{}", action, detail.trim()),
+            OperationError::Io { path, reason } => write!(f, "{}: {}", path.display(), reason),
+            OperationError::Refused(message) | OperationError::Other(message) => f.write_str(message),
+        }
+    }
+}
+
+impl From<String> for OperationError {
+    fn from(message: String) -> Self {
+        OperationError::Other(message)
+    }
+}
+
+/// How an install, backup or restore ended, as shown on the Result screen.
+#[derive(Clone, PartialEq)]
+pub enum OperationOutcome {
+    Success(String),
+    /// Done, but a restart is needed before the change takes full effect.
+    SuccessNeedsReboot(String),
+    /// Some items were done and `failed_items` were not.
+    Partial { message: String, failed_items: Vec<String> },
+    Failed { error: OperationError },
+}
+
+impl OperationOutcome {
+    pub fn failed(error: impl Into<OperationError>) -> Self {
+        OperationOutcome::Failed { error: error.into() }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            OperationOutcome::Success(_) => " Success ",
+            OperationOutcome::SuccessNeedsReboot(_) => " Success - Restart Required ",
+            OperationOutcome::Partial { .. } => " Partially Completed ",
+            OperationOutcome::Failed { .. } => " Error ",
+        }
+    }
+
+    /// The full text for the Result screen.
+    pub fn message(&self) -> String {
+        match self {
+            OperationOutcome::Success(message) => message.clone(),
+            OperationOutcome::SuccessNeedsReboot(message) => {
+                format!("{}\n\n⚠️  A system restart is required to complete the change.", message)
+            }
+            OperationOutcome::Partial { message, failed_items } => format!(
+                "{}\n\nNot completed:\n{}",
+                message,
+                failed_items.iter().map(|i| format!("  - {}", i)).collect::<Vec<_>>().join("\n")
+            ),
+            OperationOutcome::Failed { error } => error.to_string(),
+        }
+    }
+}
+
+/// For actions that still report `(success, message)`.
+impl From<(bool, String)> for OperationOutcome {
+    fn from((success, message): (bool, String)) -> Self {
+        if success {
+            OperationOutcome::Success(message)
+        } else {
+            OperationOutcome::failed(message)
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use crate::exec;
+
+const HIGH_PERFORMANCE_GUID: &str = "8c5e7fda-e8bf-4a96-9a85-a6e23a8c635c";
+
+/// Returns the active power plan as `Name (GUID)`.
+pub fn active_power_plan() -> Result<String, String> {
+    let output = exec::run("powercfg", &["/getactivescheme"])
+        .map_err(|e| format!("Failed to run powercfg: {}", e))?;
+    if !output.success {
+        return Err(format!("Failed to read the power plan:\n{}", output.stderr.trim()));
+    }
+    // "Power Scheme GUID: 381b4222-f694-41f0-9685-ff5bb260df2e  (Balanced)"
+    let line = output.stdout.trim();
+    let rest = line.split_once(':').map(|(_, r)| r.trim()).unwrap_or(line);
+    Ok(match rest.split_once("  ") {
+        Some((guid, name)) => format!("{} ({})", name.trim().trim_matches(['(', ')']), guid.trim()),
+        None => rest.to_string(),
+    })
+}
+
+fn is_high_performance(plan: &str) -> bool {
+    plan.contains(HIGH_PERFORMANCE_GUID)
+}
+
+/// Activates the High Performance plan, restoring it first if the image has it hidden.
+pub fn set_high_performance() -> (bool, String) {
+    let before = match active_power_plan() {
+        Ok(plan) => plan,
+        Err(e) => return (false, e),
+    };
+    if is_high_performance(&before) {
+        return (true, format!("The power plan is already {}.", before));
+    }
+
+    let activated = exec::change_program("powercfg", &["/setactive", HIGH_PERFORMANCE_GUID]).map(|o| o.success);
+    if !matches!(activated, Ok(true)) {
+        // Some images ship without the plan; recreate it under its well-known GUID
+        let script = format!(
+            "powercfg /duplicatescheme {0} {0} | Out-Null; powercfg /setactive {0}; if ($LASTEXITCODE -ne 0) {{ exit 1 }}",
+            HIGH_PERFORMANCE_GUID
+        );
+        match exec::change(&script) {
+            Ok(output) if output.success => {}
+            Ok(output) => {
+                return (false, format!("Failed to activate High Performance:\n{}", output.stderr.trim()));
+            }
+            Err(e) => return (false, format!("Failed to activate High Performance: {}", e)),
+        }
+    }
+
+    let after = active_power_plan().unwrap_or_else(|e| e);
+    (true, format!("Changed the power plan:\n\nBefore: {}\nAfter:  {}", before, after))
+}
+
+const PAGEFILE_SCRIPT: &str = r#"
+$cs = Get-CimInstance Win32_ComputerSystem
+if ($cs.AutomaticManagedPagefile) { 'auto' } else {
+    Get-CimInstance Win32_PageFileSetting | ForEach-Object { "$($_.Name) $($_.InitialSize) $($_.MaximumSize)" }
+}
+"#;
+
+/// Returns the pagefile configuration in the same format `set_pagefile` accepts:
+/// `auto`, or one `path initialMB maximumMB` per pagefile.
+pub fn pagefile_spec() -> Result<String, String> {
+    let output = exec::powershell(PAGEFILE_SCRIPT).map_err(|e| format!("Failed to read pagefile settings: {}", e))?;
+    if !output.success {
+        return Err(format!("Failed to read pagefile settings:\n{}", output.stderr.trim()));
+    }
+    let lines: Vec<&str> = output.stdout.lines().map(str::trim).filter(|l| !l.is_empty()).collect();
+    Ok(if lines.is_empty() { "none".to_string() } else { lines.join(", ") })
+}
+
+/// `spec` when `set_pagefile` accepts it as is, to pre-fill the prompt: `auto` or a single
+/// pagefile. No pagefile, or several, can't be entered back.
+pub fn editable_pagefile_spec(spec: &str) -> Option<String> {
+    let single = !spec.contains(',') && spec.split_whitespace().count() == 3;
+    (spec.eq_ignore_ascii_case("auto") || single).then(|| spec.to_string())
+}
+
+/// PowerShell that replaces every pagefile with one fixed-size pagefile at `path`.
+pub fn fixed_pagefile_script(path: &str, initial: u32, maximum: u32) -> String {
+    format!(
+        "Get-CimInstance Win32_ComputerSystem | Set-CimInstance -Property @{{ AutomaticManagedPagefile = $false }} -ErrorAction Stop; \
+        Get-CimInstance Win32_PageFileSetting | Remove-CimInstance -ErrorAction Stop; \
+        New-CimInstance -ClassName Win32_PageFileSetting -Property @{{ Name = {}; InitialSize = [uint32]{}; MaximumSize = [uint32]{} }} -ErrorAction Stop | Out-Null",
+        exec::quote(path),
+        initial,
+        maximum
+    )
+}
+
+/// Applies `auto` (system managed) or `path initialMB maximumMB` (`0 0` lets Windows size that file).
+pub fn set_pagefile(spec: &str) -> (bool, String) {
+    let before = match pagefile_spec() {
+        Ok(spec) => spec,
+        Err(e) => return (false, e),
+    };
+
+    let script = if spec.eq_ignore_ascii_case("auto") {
+        "Get-CimInstance Win32_ComputerSystem | Set-CimInstance -Property @{ AutomaticManagedPagefile = $true } -ErrorAction Stop"
+            .to_string()
+    } else {
+        let parts: Vec<&str> = spec.split_whitespace().collect();
+        let (path, initial, maximum) = match parts.as_slice() {
+            [path, initial, maximum] => match (initial.parse::<u32>(), maximum.parse::<u32>()) {
+                (Ok(i), Ok(m)) if i <= m => (*path, i, m),
+                (Ok(_), Ok(_)) => return (false, "The initial size cannot exceed the maximum size.".to_string()),
+                _ => return (false, "Sizes must be whole numbers of MB.".to_string()),
+            },
+            _ => return (false, "Expected: auto, or path initialMB maximumMB".to_string()),
+        };
+        if !path.to_lowercase().ends_with("pagefile.sys") || !path.contains(":\\") {
+            return (false, format!("Expected a full path ending in pagefile.sys, got {}", path));
+        }
+        fixed_pagefile_script(path, initial, maximum)
+    };
+
+    match exec::change(&script) {
+        Ok(output) if output.success => {}
+        Ok(output) => return (false, format!("Failed to change the pagefile:\n{}", output.stderr.trim())),
+        Err(e) => return (false, format!("Failed to change the pagefile: {}", e)),
+    }
+
+    let after = pagefile_spec().unwrap_or_else(|e| e);
+    (true, format!(
+        "Changed the pagefile:\n\nBefore: {}\nAfter:  {}\n\nRestart the server for the change to take effect.",
+        before, after
+    ))
+}
@@ -0,0 +1,161 @@
+use crate::{
+    exec, history,
+    outcome::{OperationError, OperationOutcome},
+};
+
+/// One command of a change plan.
+pub struct Step {
+    pub description: String,
+    pub command: String,
+}
+
+/// Every command a composite operation will run, computed up front so it can be
+/// reviewed and approved before anything changes.
+pub struct Plan {
+    pub title: String,
+    pub steps: Vec<Step>,
+    /// Findings that need no command (already installed, unavailable, ...).
+    pub notes: Vec<String>,
+    /// Reasons the plan should not run as it is, e.g. a running workload it conflicts with.
+    /// The plan is only applied after they are explicitly overridden.
+    pub conflicts: Vec<String>,
+}
+
+impl Plan {
+    pub fn new(title: impl Into<String>) -> Plan {
+        Plan { title: title.into(), steps: Vec::new(), notes: Vec::new(), conflicts: Vec::new() }
+    }
+
+    pub fn step(&mut self, description: impl Into<String>, command: impl Into<String>) {
+        self.steps.push(Step { description: description.into(), command: command.into() });
+    }
+
+    pub fn note(&mut self, note: impl Into<String>) {
+        self.notes.push(note.into());
+    }
+
+    /// The plan as shown for approval and written to the history log.
+    pub fn render(&self) -> String {
+        let mut out = vec![format!("Plan: {}", self.title), String::new()];
+        if !self.conflicts.is_empty() {
+            out.push("CONFLICTS that hold the plan back:".to_string());
+            out.extend(self.conflicts.iter().map(|c| format!("  ! {}", c)));
+            out.push(String::new());
+            out.push("Press o to apply anyway, or n to cancel.".to_string());
+            out.push(String::new());
+        }
+        if self.steps.is_empty() {
+            out.push("No changes needed.".to_string());
+        } else {
+            out.push(format!("{} step(s) will run, in order:", self.steps.len()));
+            for (i, step) in self.steps.iter().enumerate() {
+                out.push(String::new());
+                out.push(format!("  {}. {}", i + 1, step.description));
+                out.extend(step.command.lines().map(|l| format!("       {}", l.trim_end())));
+            }
+        }
+        if !self.notes.is_empty() {
+            out.push(String::new());
+            out.push("Notes:".to_string());
+            out.extend(self.notes.iter().map(|n| format!("  - {}", n)));
+        }
+        out.join("\n")
+    }
+
+    /// Runs the steps in order, stopping at the first failure. The plan and the
+    /// outcome of every step are recorded in the history log. A failure after some
+    /// steps ran is a partial outcome listing the steps that did not complete.
+    pub fn apply(&self) -> OperationOutcome {
+        history::record(&format!("Approved plan: {}\n{}", self.title, self.render()));
+
+        let mut report = Vec::new();
+        let mut failed = false;
+        let mut completed = 0;
+        let mut not_completed = Vec::new();
+        for (i, step) in self.steps.iter().enumerate() {
+            if failed {
+                report.push(format!("[SKIP] {}. {}", i + 1, step.description));
+                not_completed.push(format!("{} (skipped)", step.description));
+                continue;
+            }
+            match exec::change(&step.command) {
+                Ok(output) if output.success => {
+                    completed += 1;
+                    report.push(format!("[OK]   {}. {}", i + 1, step.description));
+                    report.extend(
+                        output
+                            .stdout
+                            .lines()
+                            .filter(|l| !l.trim().is_empty())
+                            .map(|l| format!("         {}", l.trim_end())),
+                    );
+                }
+                Ok(output) => {
+                    failed = true;
+                    not_completed.push(format!("{} (failed)", step.description));
+                    report.push(format!("[FAIL] {}. {}", i + 1, step.description));
+                    report.extend(output.stderr.lines().map(|l| format!("         {}", l.trim_end())));
+                }
+                Err(e) => {
+                    failed = true;
+                    not_completed.push(format!("{} (failed)", step.description));
+                    report.push(format!("[FAIL] {}. {}: {}", i + 1, step.description, e));
+                }
+            }
+        }
+
+        let outcome = if failed { "failed" } else { "completed" };
+        history::record(&format!("Plan {}: {}\n{}", outcome, self.title, report.join("\n")));
+        let message = format!("{} {}.\n\n{}", self.title, outcome, report.join("\n"));
+        match (failed, completed) {
+            (false, _) => OperationOutcome::Success(message),
+            (true, 0) => OperationOutcome::failed(OperationError::Command { action: self.title.clone(), detail: report.join("\n") }),
+            (true, _) => OperationOutcome::Partial { message, failed_items: not_completed },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::FakeRunner;
+
+    fn plan() -> Plan {
+        let mut plan = Plan::new("Test plan");
+        plan.step("First", "Step-One");
+        plan.step("Second", "Step-Two");
+        plan.step("Third", "Step-Three");
+        plan
+    }
+
+    #[test]
+    fn apply_runs_every_step() {
+        let runner = FakeRunner::new().on("Step-Two", "done").install();
+
+        let outcome = plan().apply();
+
+        assert!(matches!(&outcome, OperationOutcome::Success(m) if m.contains("done")));
+        assert!(runner.ran("Step-Three"));
+    }
+
+    #[test]
+    fn apply_stops_at_a_failure_and_reports_what_did_not_complete() {
+        let runner = FakeRunner::new().fail("Step-Two", "Access denied").install();
+
+        match plan().apply() {
+            OperationOutcome::Partial { message, failed_items } => {
+                assert!(message.contains("Access denied"));
+                assert_eq!(failed_items, ["Second (failed)", "Third (skipped)"]);
+            }
+            other => panic!("unexpected outcome: {}", other.message()),
+        }
+        assert!(!runner.ran("Step-Three"));
+    }
+
+    #[test]
+    fn apply_fails_when_the_first_step_fails() {
+        FakeRunner::new().fail("Step-One", "Access denied").install();
+
+        assert!(matches!(plan().apply(), OperationOutcome::Failed { error: OperationError::Command { .. } }));
+    }
+}
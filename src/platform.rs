@@ -0,0 +1,340 @@
+//! What differs between the operating systems the TUI can prepare: the package manager, the
+//! NetBird install, service control and the backup. Windows keeps its existing actions; on
+//! Linux these go through apt or dnf, the NetBird install script and systemd, and the menu
+//! only offers the actions that have a Linux implementation.
+
+use crate::{
+    backup_dir, dates, exec, message,
+    outcome::{OperationError, OperationOutcome},
+    App, MenuAction,
+};
+
+/// NetBird's install script for Linux; it adds the package repository and installs the client.
+const NETBIRD_LINUX_INSTALLER: &str = "https://pkgs.netbird.io/install.sh";
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Os {
+    Windows,
+    Linux,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ServiceAction {
+    Start,
+    Stop,
+    Restart,
+    Status,
+}
+
+impl ServiceAction {
+    fn verb(self) -> &'static str {
+        match self {
+            ServiceAction::Start => "start",
+            ServiceAction::Stop => "stop",
+            ServiceAction::Restart => "restart",
+            ServiceAction::Status => "status",
+        }
+    }
+}
+
+/// Reads `start nginx`, `restart sshd`, `status Spooler` and the like.
+pub fn parse_service_command(text: &str) -> Result<(ServiceAction, String), String> {
+    let mut words = text.split_whitespace();
+    let action = match words.next().map(str::to_lowercase).as_deref() {
+        Some("start") => ServiceAction::Start,
+        Some("stop") => ServiceAction::Stop,
+        Some("restart") => ServiceAction::Restart,
+        Some("status") => ServiceAction::Status,
+        _ => return Err("Start with start, stop, restart or status, followed by the service name.".to_string()),
+    };
+    match (words.next(), words.next()) {
+        (Some(name), None) => Ok((action, name.to_string())),
+        (None, _) => Err("Enter the service name after the action.".to_string()),
+        (Some(_), Some(_)) => Err("Enter one service name.".to_string()),
+    }
+}
+
+pub trait Platform: Sync {
+    fn os(&self) -> Os;
+
+    /// Whether the package manager works, with its version.
+    fn package_manager_status(&self) -> OperationOutcome;
+
+    fn vpn_status(&self) -> OperationOutcome;
+
+    fn install_vpn(&self) -> OperationOutcome;
+
+    fn service(&self, name: &str, action: ServiceAction) -> OperationOutcome;
+
+    /// Records what is installed in the backup directory, for setting up a matching server.
+    fn backup(&self) -> OperationOutcome;
+}
+
+/// The platform this build runs on. Tests always get Windows, whose commands the fake runner
+/// scripts.
+pub fn current() -> &'static dyn Platform {
+    if cfg!(windows) || cfg!(test) {
+        &Windows
+    } else {
+        &Linux
+    }
+}
+
+pub struct Windows;
+
+impl Platform for Windows {
+    fn os(&self) -> Os {
+        Os::Windows
+    }
+
+    fn package_manager_status(&self) -> OperationOutcome {
+        App::check_winget_status().into()
+    }
+
+    fn vpn_status(&self) -> OperationOutcome {
+        App::check_netbird_status().into()
+    }
+
+    fn install_vpn(&self) -> OperationOutcome {
+        App::install_netbird()
+    }
+
+    fn service(&self, name: &str, action: ServiceAction) -> OperationOutcome {
+        let name = exec::quote(name);
+        let query = format!("Get-Service -Name {} -ErrorAction Stop | Format-List Name, DisplayName, Status, StartType", name);
+        let result = match action {
+            ServiceAction::Status => exec::powershell(&query),
+            ServiceAction::Start => exec::change(&format!("Start-Service -Name {} -ErrorAction Stop; {}", name, query)),
+            ServiceAction::Stop => exec::change(&format!("Stop-Service -Name {} -Force -ErrorAction Stop; {}", name, query)),
+            ServiceAction::Restart => {
+                exec::change(&format!("Restart-Service -Name {} -Force -ErrorAction Stop; {}", name, query))
+            }
+        };
+        service_outcome(action, result)
+    }
+
+    fn backup(&self) -> OperationOutcome {
+        App::backup_server_roles()
+    }
+}
+
+pub struct Linux;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum PackageManager {
+    Apt,
+    Dnf,
+}
+
+impl Linux {
+    /// apt on Debian and Ubuntu, dnf on RHEL, Rocky, Alma and Fedora.
+    fn package_manager() -> Option<(PackageManager, String)> {
+        [(PackageManager::Apt, "apt-get"), (PackageManager::Dnf, "dnf")].into_iter().find_map(|(manager, program)| {
+            let output = exec::run(program, &["--version"]).ok().filter(|o| o.success)?;
+            Some((manager, output.stdout.lines().next().unwrap_or(program).trim().to_string()))
+        })
+    }
+}
+
+/// The command listing installed packages as `name<TAB>version` lines.
+fn package_query(manager: PackageManager) -> (&'static str, &'static [&'static str]) {
+    match manager {
+        PackageManager::Apt => ("dpkg-query", &["-W", "-f=${Package}\t${Version}\n"]),
+        PackageManager::Dnf => ("rpm", &["-qa", "--qf", "%{NAME}\t%{VERSION}-%{RELEASE}\n"]),
+    }
+}
+
+/// The installed packages of this host, or of `host` over SSH with key authentication, as
+/// `name<TAB>version` lines like a `Packages_<time>.txt` backup.
+pub fn installed_packages(host: Option<&str>) -> Result<String, String> {
+    let output = match host {
+        None => {
+            let (program, args) = match Linux::package_manager() {
+                Some((manager, _)) => package_query(manager),
+                None => return Err("Neither apt-get nor dnf was found.".to_string()),
+            };
+            exec::run(program, args).map_err(|e| format!("Failed to run {}: {}", program, e))?
+        }
+        Some(host) => {
+            let remote = [PackageManager::Apt, PackageManager::Dnf]
+                .map(|manager| {
+                    let (program, args) = package_query(manager);
+                    format!("{} {}", program, args.iter().map(|a| format!("'{}'", a)).collect::<Vec<_>>().join(" "))
+                })
+                .join(" 2>/dev/null || ");
+            exec::run("ssh", &["-o", "BatchMode=yes", "-o", "ConnectTimeout=30", host, &remote])
+                .map_err(|e| format!("Failed to run ssh: {}", e))?
+        }
+    };
+    if !output.success {
+        return Err(format!("Failed to list the installed packages:\n{}", output.stderr.trim()));
+    }
+    Ok(output.stdout)
+}
+
+impl Platform for Linux {
+    fn os(&self) -> Os {
+        Os::Linux
+    }
+
+    fn package_manager_status(&self) -> OperationOutcome {
+        match Linux::package_manager() {
+            Some((_, version)) => OperationOutcome::Success(format!("Package manager: {}", version)),
+            None => OperationOutcome::failed("Neither apt-get nor dnf was found.".to_string()),
+        }
+    }
+
+    fn vpn_status(&self) -> OperationOutcome {
+        match exec::run("netbird", &["version"]) {
+            Ok(output) if output.success => {
+                let status = exec::run("netbird", &["status"]).map(|o| o.stdout).unwrap_or_default();
+                OperationOutcome::Success(format!("NetBird is installed: {}\n\n{}", output.stdout.trim(), status.trim()))
+            }
+            Ok(_) => OperationOutcome::failed("NetBird is not working properly".to_string()),
+            Err(_) => OperationOutcome::failed("NetBird is not installed".to_string()),
+        }
+    }
+
+    fn install_vpn(&self) -> OperationOutcome {
+        message::log("Starting NetBird installation...");
+        if Linux::package_manager().is_none() {
+            return OperationOutcome::failed(OperationError::Refused(
+                "NetBird's installer needs apt or dnf, and neither was found.".to_string(),
+            ));
+        }
+        message::log(format!("Running the NetBird installer from {}...", NETBIRD_LINUX_INSTALLER));
+        let script = format!("curl -fsSL {} | sh", NETBIRD_LINUX_INSTALLER);
+        match exec::run("sh", &["-c", &script]) {
+            Ok(output) if output.success => match exec::run("netbird", &["version"]) {
+                Ok(version) if version.success => OperationOutcome::Success(format!(
+                    "NetBird {} installed successfully!\n\nConnect it with:\n  netbird up --setup-key <KEY>",
+                    version.stdout.trim()
+                )),
+                _ => OperationOutcome::failed(OperationError::Command {
+                    action: "Installing NetBird".to_string(),
+                    detail: "The installer finished but netbird is not on the PATH.".to_string(),
+                }),
+            },
+            Ok(output) => OperationOutcome::failed(OperationError::Command {
+                action: "Installing NetBird".to_string(),
+                detail: format!("{}\n{}", output.stdout.trim(), output.stderr.trim()),
+            }),
+            Err(e) => OperationOutcome::failed(OperationError::Command {
+                action: "Installing NetBird".to_string(),
+                detail: e.to_string(),
+            }),
+        }
+    }
+
+    fn service(&self, name: &str, action: ServiceAction) -> OperationOutcome {
+        if action != ServiceAction::Status {
+            let outcome = service_outcome(action, exec::run("systemctl", &[action.verb(), name]));
+            if let OperationOutcome::Failed { .. } = outcome {
+                return outcome;
+            }
+        }
+        // `systemctl status` exits non-zero for stopped units; its text says so either way
+        match exec::run("systemctl", &["status", "--no-pager", name]) {
+            Ok(output) if output.stdout.trim().is_empty() => OperationOutcome::failed(OperationError::Command {
+                action: format!("systemctl status {}", name),
+                detail: output.stderr,
+            }),
+            Ok(output) => OperationOutcome::Success(output.stdout.trim_end().to_string()),
+            Err(e) => OperationOutcome::failed(OperationError::Command { action: "systemctl".to_string(), detail: e.to_string() }),
+        }
+    }
+
+    fn backup(&self) -> OperationOutcome {
+        message::log("Backing up the installed package list...");
+        let (program, args) = match Linux::package_manager() {
+            Some((manager, _)) => package_query(manager),
+            None => return OperationOutcome::failed("Neither apt-get nor dnf was found.".to_string()),
+        };
+        let output = match exec::run(program, args) {
+            Ok(output) if output.success => output,
+            Ok(output) => {
+                return OperationOutcome::failed(OperationError::Command { action: program.to_string(), detail: output.stderr })
+            }
+            Err(e) => {
+                return OperationOutcome::failed(OperationError::Command { action: program.to_string(), detail: e.to_string() })
+            }
+        };
+        let dir = backup_dir();
+        let file = dir.join(format!("Packages_{}.txt", dates::now()));
+        if let Err(e) = std::fs::create_dir_all(&dir).and_then(|_| std::fs::write(&file, &output.stdout)) {
+            return OperationOutcome::failed(OperationError::Io { path: file, reason: e.to_string() });
+        }
+        OperationOutcome::Success(format!(
+            "Backed up {} installed packages.\n\nBackup location:\n  {}",
+            output.stdout.lines().count(),
+            file.display()
+        ))
+    }
+}
+
+/// Turns the output of a service command into an outcome.
+fn service_outcome(action: ServiceAction, result: std::io::Result<exec::CommandOutput>) -> OperationOutcome {
+    let action = format!("Service {}", action.verb());
+    match result {
+        Ok(output) if output.success => OperationOutcome::Success(output.stdout.trim().to_string()),
+        Ok(output) => OperationOutcome::failed(OperationError::Command { action, detail: output.stderr }),
+        Err(e) => OperationOutcome::failed(OperationError::Command { action, detail: e.to_string() }),
+    }
+}
+
+/// The menu on Linux: the actions with a Linux implementation.
+pub const LINUX_MENU: &[(&str, MenuAction)] = &[
+    ("Check Package Manager (apt/dnf)", MenuAction::CheckWinget),
+    #[cfg(feature = "netbird")]
+    ("Check NetBird Status", MenuAction::CheckNetBird),
+    #[cfg(feature = "netbird")]
+    ("Install NetBird", MenuAction::InstallNetBird),
+    ("Back Up Installed Packages", MenuAction::BackupRoles),
+    ("Backup Catalog (Tags & Notes)", MenuAction::BackupCatalog),
+    ("Golden Baselines (Compare Servers)", MenuAction::GoldenBaselines),
+    ("Service Control (systemd)", MenuAction::ServiceControl),
+    ("History", MenuAction::History),
+    ("Offline Mode (block downloads) (on/off)", MenuAction::Offline),
+    ("Profiles (Per-Site Defaults)", MenuAction::Profiles),
+    ("Macros (Record & Replay)", MenuAction::Macros),
+    ("Exit", MenuAction::Exit),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::FakeRunner;
+
+    #[test]
+    fn parses_service_commands() {
+        assert_eq!(parse_service_command("Restart  nginx"), Ok((ServiceAction::Restart, "nginx".to_string())));
+        assert!(parse_service_command("reload nginx").is_err());
+        assert!(parse_service_command("stop").is_err());
+    }
+
+    #[test]
+    fn linux_backs_up_the_package_list_with_the_detected_manager() {
+        let runner = FakeRunner::new()
+            .on("dnf --version", "4.14.0\n")
+            .on("rpm -qa", "bash\t5.1.8-9.el9\nopenssh-server\t8.7p1-38.el9\n")
+            .install();
+
+        let outcome = Linux.backup();
+
+        assert!(matches!(&outcome, OperationOutcome::Success(m) if m.contains("Backed up 2 installed packages")), "{}", outcome.message());
+        assert!(!runner.ran("dpkg-query"));
+    }
+
+    #[test]
+    fn linux_service_control_reports_the_unit_status() {
+        let runner = FakeRunner::new()
+            .on("systemctl restart", "")
+            .on("systemctl status", "● nginx.service - A high performance web server\n     Active: active (running)\n")
+            .install();
+
+        let outcome = Linux.service("nginx", ServiceAction::Restart);
+
+        assert!(matches!(&outcome, OperationOutcome::Success(m) if m.contains("active (running)")), "{}", outcome.message());
+        assert!(runner.ran("systemctl restart nginx"));
+    }
+}
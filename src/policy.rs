@@ -0,0 +1,110 @@
+use crate::exec;
+
+/// Refreshes computer and user policy with `gpupdate /force`.
+pub fn gpupdate() -> (bool, String) {
+    let output = match exec::change_program("gpupdate", &["/force", "/wait:120"]) {
+        Ok(output) => output,
+        Err(e) => return (false, format!("Failed to run gpupdate: {}", e)),
+    };
+    if exec::export_status().is_some() {
+        return (true, "gpupdate /force recorded.".to_string());
+    }
+
+    let text = format!("{}\n{}", output.stdout, output.stderr);
+    let lines: Vec<&str> = text
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with("Updating policy"))
+        .collect();
+    let mut message = lines.join("\n");
+    if text.contains("can only run during startup") || text.contains("only run during logon") {
+        message.push_str("\n\nRestart the server (or sign out) to apply those policies.");
+    }
+    message.push_str("\n\nOpen Group Policy Result to see which GPOs applied.");
+    (output.success, message)
+}
+
+/// A line made of dashes, which gpresult uses to underline headings.
+fn is_rule(line: &str) -> bool {
+    !line.is_empty() && line.chars().all(|c| c == '-')
+}
+
+/// Runs `gpresult /r` and condenses it to the applied and filtered GPOs and the
+/// security groups for the computer and the current user.
+pub fn gpresult_summary() -> Result<String, String> {
+    let output = exec::run("gpresult", &["/r"]).map_err(|e| format!("Failed to run gpresult: {}", e))?;
+    if !output.success {
+        let text = if output.stderr.trim().is_empty() { output.stdout } else { output.stderr };
+        return Err(format!(
+            "gpresult failed:\n{}\n\nGroup Policy results are only available on domain-joined servers.",
+            text.trim()
+        ));
+    }
+
+    // (heading, lines) in the order gpresult prints them
+    let lines: Vec<&str> = output.stdout.lines().map(str::trim).collect();
+    let mut sections: Vec<(&str, Vec<&str>)> = vec![("", Vec::new())];
+    for (i, line) in lines.iter().enumerate() {
+        if lines.get(i + 1).is_some_and(|next| is_rule(next)) {
+            sections.push((line, Vec::new()));
+        } else if !line.is_empty() && !is_rule(line) {
+            if let Some((_, body)) = sections.last_mut() {
+                body.push(line);
+            }
+        }
+    }
+
+    let value = |body: &[&str], key: &str| {
+        body.iter()
+            .find_map(|l| l.strip_prefix(key))
+            .map(|v| v.trim_start_matches(':').trim().to_string())
+            .unwrap_or_default()
+    };
+    let mut out = Vec::new();
+    for (heading, body) in &sections {
+        match *heading {
+            "" => {
+                let site = value(body, "Site Name");
+                if !site.is_empty() {
+                    out.push(format!("Site: {}", site));
+                }
+            }
+            "COMPUTER SETTINGS" | "USER SETTINGS" => {
+                out.push(String::new());
+                out.push(if *heading == "COMPUTER SETTINGS" { "COMPUTER" } else { "USER" }.to_string());
+                if let Some(dn) = body.first().filter(|l| l.starts_with("CN=")) {
+                    out.push(format!("  {}", dn));
+                }
+                out.push(format!("  Last applied:  {}", value(body, "Last time Group Policy was applied")));
+                out.push(format!("  Applied from:  {}", value(body, "Group Policy was applied from")));
+            }
+            h if h.starts_with("Applied Group Policy Objects") => {
+                out.push(format!("  Applied GPOs ({}):", body.len()));
+                out.extend(body.iter().map(|gpo| format!("    {}", gpo)));
+            }
+            h if h.contains("were not applied") => {
+                out.push("  Not applied (filtered out):".to_string());
+                // Each GPO is followed by its "Filtering:  <reason>" line
+                for line in body.iter() {
+                    match line.strip_prefix("Filtering:") {
+                        Some(reason) => {
+                            if let Some(last) = out.last_mut() {
+                                last.push_str(&format!(" - {}", reason.trim()));
+                            }
+                        }
+                        None => out.push(format!("    {}", line)),
+                    }
+                }
+            }
+            h if h.contains("security groups") => {
+                out.push(format!("  Security groups ({}):", body.len()));
+                out.extend(body.iter().map(|group| format!("    {}", group)));
+            }
+            _ => {}
+        }
+    }
+    if out.iter().all(|l| !l.starts_with("  Applied GPOs")) {
+        return Err(format!("No Group Policy results found:\n{}", output.stdout.trim()));
+    }
+    Ok(out.join("\n"))
+}
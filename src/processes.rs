@@ -0,0 +1,40 @@
+use crate::exec;
+
+const PROCESSES_SCRIPT: &str = r#"
+$inv = [Globalization.CultureInfo]::InvariantCulture
+Get-Process | ForEach-Object {
+    $cpu = if ($_.CPU -ne $null) { $_.CPU.ToString('F1', $inv) } else { '' }
+    $mem = ($_.WorkingSet64 / 1MB).ToString('F1', $inv)
+    "$($_.Id)`t$($_.ProcessName)`t$cpu`t$mem`t$($_.SessionId)`t$($_.Path)"
+}
+"#;
+
+/// Lists running processes with total CPU seconds and working set in MB.
+pub fn processes() -> Result<Vec<Vec<String>>, String> {
+    let output = exec::powershell(PROCESSES_SCRIPT).map_err(|e| format!("Failed to list processes: {}", e))?;
+    if !output.success {
+        return Err(format!("Failed to list processes:\n{}", output.stderr.trim()));
+    }
+    Ok(exec::tab_rows(&output.stdout)
+        .into_iter()
+        .filter(|row| row.len() == 6)
+        .collect())
+}
+
+/// Stops process `pid` if it is still `name`, so a reused PID is never killed by mistake.
+pub fn kill_process(pid: &str, name: &str) -> Result<String, String> {
+    let pid: u32 = pid.parse().map_err(|_| format!("Invalid PID: {}", pid))?;
+    let script = format!(
+        "$p = Get-Process -Id {} -ErrorAction Stop; \
+        if ($p.ProcessName -ne {}) {{ throw 'PID now belongs to ' + $p.ProcessName }}; \
+        Stop-Process -Id {} -Force -ErrorAction Stop",
+        pid,
+        exec::quote(name),
+        pid
+    );
+    match exec::change(&script) {
+        Ok(output) if output.success => Ok(format!("Stopped {} (PID {})", name, pid)),
+        Ok(output) => Err(format!("Failed to stop {} (PID {}): {}", name, pid, output.stderr.trim())),
+        Err(e) => Err(format!("Failed to stop {} (PID {}): {}", name, pid, e)),
+    }
+}
@@ -0,0 +1,245 @@
+//! Named profiles with per-site defaults, e.g. `SiteA` or `Lab`. Each profile is a folder under
+//! `profiles` in the config directory holding its own agent catalog (the inventory and fleet
+//! hosts), validation baseline and a `profile.ini` with the backup directory, proxy and offsite
+//! upload destination; without a profile, `profile.ini` in the config directory is used. The
+//! history log, the download cache and offline mode stay shared.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::RwLock,
+};
+
+use crate::config_dir;
+
+static ACTIVE: RwLock<Option<String>> = RwLock::new(None);
+
+const SETTINGS_FILE: &str = "profile.ini";
+
+const EXAMPLE_SETTINGS: &str = "; Server Helper profile settings\n\
+;\n\
+; backup_dir is where backups are written while this profile is active.\n\
+; proxy prefills the WinHTTP proxy prompt when the server has none yet:\n\
+; the proxy server, optionally followed by a bypass list.\n\
+; The agent catalog (agents.ini) and validation baseline (validation.ini)\n\
+; of this profile live next to this file.\n\
+;\n\
+; backup_dir = D:\\ServerBackups\n\
+; proxy = proxy.site-a.local:8080 <local>;*.site-a.local\n\
+;\n\
+; offsite uploads each role backup, zipped, after it is taken: an SFTP\n\
+; destination (key authentication through the OpenSSH client) or an S3 bucket\n\
+; (through the AWS CLI). s3_endpoint points it at an S3-compatible service.\n\
+;\n\
+; offsite = sftp://backup@nas01.site-a.local/srv/backups/site-a\n\
+; offsite = s3://server-backups/site-a\n\
+; s3_endpoint = https://s3.eu-central-1.wasabisys.com\n\
+;\n\
+; lock_after blanks screens showing credentials or signed-in users after that\n\
+; many minutes without a keypress; lock_pin asks for a PIN to resume.\n\
+;\n\
+; lock_after = 10\n\
+; lock_pin = 4711\n\
+;\n\
+; sign_key signs a manifest of each role backup with minisign, so a restore\n\
+; can tell it was not changed on shared storage: a secret key without a\n\
+; password (minisign -G -W), for the machine or your user. verify_key, the\n\
+; public key file or the key itself, checks the signatures.\n\
+;\n\
+; sign_key = C:\\ProgramData\\ServerHelper\\minisign.key\n\
+; verify_key = C:\\ProgramData\\ServerHelper\\minisign.pub\n";
+
+/// Settings from a profile's `profile.ini`.
+#[derive(Default, Debug, PartialEq)]
+pub struct Settings {
+    pub backup_dir: Option<PathBuf>,
+    pub proxy: Option<String>,
+    /// `sftp://user@host[:port]/path` or `s3://bucket[/prefix]`.
+    pub offsite: Option<String>,
+    pub s3_endpoint: Option<String>,
+    /// Minutes without a keypress before sensitive screens are blanked.
+    pub lock_after: Option<u64>,
+    pub lock_pin: Option<String>,
+    /// minisign secret key that signs backup manifests.
+    pub sign_key: Option<PathBuf>,
+    /// minisign public key, as a file or the key itself, that checks them.
+    pub verify_key: Option<String>,
+}
+
+fn parse_settings(content: &str) -> Settings {
+    let mut settings = Settings::default();
+    for line in content.lines().map(str::trim).filter(|l| !l.starts_with(';') && !l.starts_with('#')) {
+        match line.split_once('=').map(|(k, v)| (k.trim().to_lowercase(), v.trim())) {
+            Some((key, value)) if key == "backup_dir" && !value.is_empty() => {
+                settings.backup_dir = Some(PathBuf::from(value))
+            }
+            Some((key, value)) if key == "proxy" && !value.is_empty() => settings.proxy = Some(value.to_string()),
+            Some((key, value)) if key == "offsite" && !value.is_empty() => settings.offsite = Some(value.to_string()),
+            Some((key, value)) if key == "s3_endpoint" && !value.is_empty() => {
+                settings.s3_endpoint = Some(value.to_string())
+            }
+            Some((key, value)) if key == "lock_after" => settings.lock_after = value.parse().ok(),
+            Some((key, value)) if key == "lock_pin" && !value.is_empty() => settings.lock_pin = Some(value.to_string()),
+            Some((key, value)) if key == "sign_key" && !value.is_empty() => settings.sign_key = Some(PathBuf::from(value)),
+            Some((key, value)) if key == "verify_key" && !value.is_empty() => {
+                settings.verify_key = Some(value.to_string())
+            }
+            _ => {}
+        }
+    }
+    settings
+}
+
+fn root() -> PathBuf {
+    config_dir().join("profiles")
+}
+
+pub fn dir(name: &str) -> PathBuf {
+    root().join(name)
+}
+
+/// The active profile, if any.
+pub fn active() -> Option<String> {
+    ACTIVE.read().ok()?.clone()
+}
+
+/// Switches to profile `name`, or back to the default configuration with `None`.
+pub fn set_active(name: Option<&str>) -> Result<(), String> {
+    let name = match name {
+        Some(name) => {
+            let found = names().into_iter().find(|n| n.eq_ignore_ascii_case(name));
+            Some(found.ok_or_else(|| format!("No profile named {}. Create it from the Profiles screen first.", name))?)
+        }
+        None => None,
+    };
+    *ACTIVE.write().map_err(|_| "The profile setting is unavailable.".to_string())? = name;
+    Ok(())
+}
+
+/// Where the agent catalog, validation baseline and `profile.ini` are read: the active
+/// profile's folder, or the config directory.
+pub fn current_dir() -> PathBuf {
+    active().map_or_else(config_dir, |name| dir(&name))
+}
+
+fn read_settings(dir: &Path) -> Settings {
+    std::fs::read_to_string(dir.join(SETTINGS_FILE)).map(|content| parse_settings(&content)).unwrap_or_default()
+}
+
+/// Settings of the active profile, or of the default configuration.
+pub fn settings() -> Settings {
+    read_settings(&current_dir())
+}
+
+/// Profile names, sorted.
+pub fn names() -> Vec<String> {
+    let mut names: Vec<String> = std::fs::read_dir(root())
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|e| e.path().is_dir())
+                .map(|e| e.file_name().to_string_lossy().to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort_by_key(|n| n.to_lowercase());
+    names
+}
+
+/// Reads `--profile NAME` or `--profile=NAME`.
+pub fn from_args(args: &[String]) -> Option<String> {
+    args.iter().enumerate().find_map(|(i, arg)| match arg.strip_prefix("--profile") {
+        Some(rest) => match rest.strip_prefix('=') {
+            Some(name) => Some(name.to_string()),
+            None if rest.is_empty() => args.get(i + 1).cloned(),
+            None => None,
+        },
+        None => None,
+    })
+}
+
+/// Creates profile `name` with a commented `profile.ini`.
+pub fn create(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err("Use letters, digits, - and _ for the profile name, e.g. SiteA.".to_string());
+    }
+    if names().iter().any(|n| n.eq_ignore_ascii_case(name)) {
+        return Err(format!("Profile {} already exists.", name));
+    }
+    let path = dir(name).join(SETTINGS_FILE);
+    std::fs::create_dir_all(dir(name))
+        .and_then(|_| std::fs::write(&path, EXAMPLE_SETTINGS.replace('\n', "\r\n")))
+        .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    Ok(format!("Created profile {}. Press n to set its backup directory, proxy and offsite destination.", name))
+}
+
+/// Opens the `profile.ini` of profile `name`, or of the default configuration, in Notepad.
+pub fn edit(name: Option<&str>) -> Result<String, String> {
+    let path = name.map_or_else(config_dir, dir).join(SETTINGS_FILE);
+    if !path.exists() {
+        std::fs::create_dir_all(path.parent().unwrap_or(&path))
+            .and_then(|_| std::fs::write(&path, EXAMPLE_SETTINGS.replace('\n', "\r\n")))
+            .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    }
+    std::process::Command::new("notepad.exe")
+        .arg(&path)
+        .spawn()
+        .map(|_| format!("Opened {} in Notepad. Press r to reload after saving.", path.display()))
+        .map_err(|e| format!("Failed to open Notepad: {}", e))
+}
+
+/// One row per profile, after the default configuration: whether it is active, the name, the
+/// backup directory, proxy and offsite destination, and whether it has its own catalog and
+/// baseline.
+pub fn rows() -> Result<Vec<Vec<String>>, String> {
+    let active = active();
+    let present = |path: PathBuf| if path.exists() { "yes" } else { "-" }.to_string();
+    let profiles = std::iter::once((None, config_dir())).chain(names().into_iter().map(|n| {
+        let path = dir(&n);
+        (Some(n), path)
+    }));
+    Ok(profiles
+        .map(|(name, path)| {
+            let settings = read_settings(&path);
+            vec![
+                if active == name { "*" } else { "" }.to_string(),
+                name.unwrap_or_else(|| "(default)".to_string()),
+                settings.backup_dir.map(|d| d.display().to_string()).unwrap_or_default(),
+                settings.proxy.unwrap_or_default(),
+                settings.offsite.unwrap_or_default(),
+                present(path.join("agents.ini")),
+                present(path.join("validation.ini")),
+            ]
+        })
+        .collect())
+}
+
+/// The profile as named in messages and the history log.
+pub fn describe(name: Option<&str>) -> String {
+    name.map_or_else(|| "the default configuration".to_string(), |n| format!("profile {}", n))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_profile_settings_and_arguments() {
+        let settings = parse_settings(
+            "; comment\r\nbackup_dir = D:\\Backups\\SiteA\r\nproxy = proxy.site-a.local:8080 <local>\r\noffsite = s3://backups/site-a\r\n\
+            lock_after = 10\r\nlock_pin = 4711\r\nverify_key = RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3\r\n",
+        );
+
+        assert_eq!(settings.backup_dir, Some(PathBuf::from("D:\\Backups\\SiteA")));
+        assert_eq!(settings.proxy.as_deref(), Some("proxy.site-a.local:8080 <local>"));
+        assert_eq!(settings.offsite.as_deref(), Some("s3://backups/site-a"));
+        assert_eq!((settings.lock_after, settings.lock_pin.as_deref()), (Some(10), Some("4711")));
+        assert_eq!(settings.verify_key.as_deref(), Some("RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3"));
+        assert_eq!(parse_settings("; backup_dir = D:\\Backups"), Settings::default());
+
+        let args = |list: &[&str]| list.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        assert_eq!(from_args(&args(&["--offline", "--profile", "Lab"])), Some("Lab".to_string()));
+        assert_eq!(from_args(&args(&["--profile=SiteA"])), Some("SiteA".to_string()));
+        assert_eq!(from_args(&args(&["--profiles"])), None);
+    }
+}
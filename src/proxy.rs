@@ -0,0 +1,165 @@
+use crate::exec;
+use crate::{profiles, CheckItem, CheckStatus};
+
+/// Sites that updates and package installs depend on, tested through the WinHTTP proxy.
+const TARGETS: [(&str, &str); 4] = [
+    ("Windows Update", "http://www.msftconnecttest.com/connecttest.txt"),
+    ("Defender updates", "https://definitionupdates.microsoft.com/"),
+    ("winget source", "https://cdn.winget.microsoft.com/cache/source.msix"),
+    ("PowerShell Gallery", "https://www.powershellgallery.com/api/v2"),
+];
+
+/// Any HTTP response counts as reachable; only connection, proxy and TLS errors fail.
+const TEST_SCRIPT: &str = r#"
+[Net.ServicePointManager]::SecurityProtocol = [Net.SecurityProtocolType]::Tls12
+function Test-Url($url) {
+    $params = @{ Uri = $url; Method = 'Head'; UseBasicParsing = $true; TimeoutSec = 10; ErrorAction = 'Stop' }
+    if ($proxy) { $params.Proxy = $proxy; $params.ProxyUseDefaultCredentials = $true }
+    try { "OK HTTP $([int](Invoke-WebRequest @params).StatusCode)" }
+    catch {
+        if ($_.Exception.Response) { "OK HTTP $([int]$_.Exception.Response.StatusCode)" }
+        else { "FAIL $($_.Exception.Message)" }
+    }
+}
+"#;
+
+/// The machine WinHTTP proxy as (server, bypass list); empty when access is direct.
+pub fn current() -> Result<(String, String), String> {
+    let output = exec::run("netsh", &["winhttp", "show", "proxy"]).map_err(|e| format!("Failed to run netsh: {}", e))?;
+    if !output.success {
+        return Err(format!("Failed to read the WinHTTP proxy:\n{}", output.stdout.trim()));
+    }
+    let value = |label: &str| {
+        output
+            .stdout
+            .lines()
+            .find(|l| l.trim_start().starts_with(label))
+            .and_then(|l| l.split_once(':'))
+            .map(|(_, v)| v.trim().to_string())
+            .unwrap_or_default()
+    };
+    Ok((value("Proxy Server"), value("Bypass List")))
+}
+
+/// The current setting as `server [bypass]`, to prefill the edit prompt, or the active
+/// profile's proxy when there is none.
+pub fn spec() -> String {
+    match current() {
+        Ok((server, bypass)) if !server.is_empty() => format!("{} {}", server, bypass).trim().to_string(),
+        _ => profiles::settings().proxy.unwrap_or_default(),
+    }
+}
+
+/// The proxy URL for HTTPS traffic: the `https=` entry of a per-scheme list, with a scheme added.
+pub fn proxy_url(server: &str) -> String {
+    let server = server
+        .split(';')
+        .find_map(|s| s.strip_prefix("https="))
+        .or_else(|| server.split(';').find_map(|s| s.strip_prefix("http=")))
+        .unwrap_or(server);
+    if server.contains("://") {
+        server.to_string()
+    } else {
+        format!("http://{}", server)
+    }
+}
+
+/// Current WinHTTP and Internet Options proxies, then whether each update source answers through it.
+pub fn checks() -> Result<Vec<CheckItem>, String> {
+    let (server, bypass) = current()?;
+    let mut items = vec![CheckItem {
+        name: "WinHTTP proxy".to_string(),
+        status: CheckStatus::Pass,
+        detail: match (server.as_str(), bypass.as_str()) {
+            ("", _) => "Direct access (no proxy server)".to_string(),
+            (server, "") => server.to_string(),
+            (server, bypass) => format!("{} (bypass: {})", server, bypass),
+        },
+        remediation: None,
+        advice: Some("Used by Windows Update, Defender and other services. Press p to set or clear it.".to_string()),
+    }];
+
+    let user = exec::powershell(
+        "$s = Get-ItemProperty 'HKCU:\\Software\\Microsoft\\Windows\\CurrentVersion\\Internet Settings' -ErrorAction SilentlyContinue\n\
+        if ($s.ProxyEnable -eq 1) { $s.ProxyServer }",
+    )
+    .map(|o| o.stdout.trim().to_string())
+    .unwrap_or_default();
+    if !user.is_empty() || !server.is_empty() {
+        let differs = !user.eq_ignore_ascii_case(&server);
+        items.push(CheckItem {
+            name: "Internet Options proxy (current user)".to_string(),
+            status: if differs { CheckStatus::Unknown } else { CheckStatus::Pass },
+            detail: if user.is_empty() { "Direct access".to_string() } else { user.clone() },
+            remediation: None,
+            advice: differs.then(|| {
+                "Differs from the WinHTTP proxy: browsers and services take different routes. \
+                Press i to copy this proxy to WinHTTP."
+                    .to_string()
+            }),
+        });
+    }
+
+    let route = if server.is_empty() { "directly".to_string() } else { format!("via {}", server) };
+    let mut script = format!("$proxy = {}\n{}", if server.is_empty() { "$null".to_string() } else { exec::quote(&proxy_url(&server)) }, TEST_SCRIPT);
+    for (_, url) in TARGETS {
+        script.push_str(&format!("Test-Url {}\n", exec::quote(url)));
+    }
+    let output = exec::powershell(&script).map_err(|e| format!("Failed to run the connectivity tests: {}", e))?;
+    let results: Vec<&str> = output.stdout.lines().map(str::trim).filter(|l| !l.is_empty()).collect();
+    for (i, (name, url)) in TARGETS.iter().enumerate() {
+        let result = results.get(i).copied().unwrap_or("FAIL no result");
+        let (status, detail) = match result.strip_prefix("OK ") {
+            Some(code) => (CheckStatus::Pass, format!("Reachable {} ({})", route, code)),
+            None => (
+                CheckStatus::Fail,
+                format!("Not reachable {}: {}", route, result.trim_start_matches("FAIL ")),
+            ),
+        };
+        let advice = (status == CheckStatus::Fail).then(|| {
+            if server.is_empty() {
+                format!("{} is blocked. If outbound traffic must use a proxy, press p to set it.", url)
+            } else {
+                format!("Check that the proxy allows {} and does not require interactive sign-in.", url)
+            }
+        });
+        items.push(CheckItem { name: name.to_string(), status, detail, remediation: None, advice });
+    }
+    Ok(items)
+}
+
+/// Sets the WinHTTP proxy from `server[:port] [bypass;list]`, or resets it to direct access
+/// when `spec` is empty or `direct`.
+pub fn set(spec: &str) -> Result<String, String> {
+    let mut words = spec.split_whitespace();
+    let server = words.next().unwrap_or_default();
+    let output = if server.is_empty() || server.eq_ignore_ascii_case("direct") {
+        exec::change_program("netsh", &["winhttp", "reset", "proxy"])
+    } else {
+        let bypass = words.collect::<Vec<_>>().join(";");
+        let mut args = vec!["winhttp".to_string(), "set".to_string(), "proxy".to_string(), format!("proxy-server={}", server)];
+        if !bypass.is_empty() {
+            args.push(format!("bypass-list={}", bypass));
+        }
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        exec::change_program("netsh", &args)
+    };
+    match output {
+        Ok(output) if output.success => Ok(if server.is_empty() || server.eq_ignore_ascii_case("direct") {
+            "WinHTTP proxy cleared (direct access).".to_string()
+        } else {
+            format!("WinHTTP proxy set to {}.", server)
+        }),
+        Ok(output) => Err(format!("Failed to set the WinHTTP proxy:\n{}", output.stdout.trim())),
+        Err(e) => Err(format!("Failed to run netsh: {}", e)),
+    }
+}
+
+/// Copies the current user's Internet Options proxy to WinHTTP.
+pub fn import_user() -> Result<String, String> {
+    match exec::change_program("netsh", &["winhttp", "import", "proxy", "source=ie"]) {
+        Ok(output) if output.success => Ok("Copied the Internet Options proxy to WinHTTP.".to_string()),
+        Ok(output) => Err(format!("Failed to import the proxy:\n{}", output.stdout.trim())),
+        Err(e) => Err(format!("Failed to run netsh: {}", e)),
+    }
+}
@@ -0,0 +1,181 @@
+use crate::exec;
+use crate::{CheckItem, CheckStatus};
+
+const TS_SETTING: &str =
+    "Get-CimInstance -Namespace root/cimv2/TerminalServices -ClassName Win32_TerminalServiceSetting";
+
+/// Prints one `Key=Value` line per setting, plus `Collection=` lines.
+const RDS_SCRIPT: &str = r#"
+"SessionHost=$((Get-WindowsFeature -Name RDS-RD-Server).Installed)"
+$ts = Get-CimInstance -Namespace root/cimv2/TerminalServices -ClassName Win32_TerminalServiceSetting -ErrorAction SilentlyContinue
+if ($ts) {
+    "LicensingType=$($ts.LicensingType)"
+    "LicensingName=$($ts.LicensingName)"
+    "LicenseServers=$(($ts | Invoke-CimMethod -MethodName GetSpecifiedLicenseServerList).SpecifiedLSList -join ', ')"
+    $grace = $ts | Invoke-CimMethod -MethodName GetGracePeriodDays -ErrorAction SilentlyContinue
+    "GraceDays=$($grace.DaysLeft)"
+}
+if (Get-Command Get-RDSessionCollection -ErrorAction SilentlyContinue) {
+    try {
+        Get-RDSessionCollection -ErrorAction Stop | ForEach-Object {
+            "Collection=$($_.CollectionName) ($($_.ResourceType), $($_.Size) host(s))"
+        }
+    } catch { "CollectionError=$($_.Exception.Message -replace '\s+', ' ')" }
+}
+"Sessions=$(@(quser 2>$null | Select-Object -Skip 1).Count)"
+"WingetProvisioned=$([bool](Get-AppxProvisionedPackage -Online -ErrorAction SilentlyContinue | Where-Object DisplayName -eq 'Microsoft.DesktopAppInstaller'))"
+"WingetForUser=$([bool](Get-AppxPackage -Name Microsoft.DesktopAppInstaller -ErrorAction SilentlyContinue))"
+"#;
+
+/// Whether the RD Session Host role is installed. App packages (appx/MSIX) installed per user
+/// there reach only the user who installed them, so installs prefer the all-users paths.
+pub fn is_session_host() -> bool {
+    exec::powershell("(Get-WindowsFeature -Name RDS-RD-Server -ErrorAction SilentlyContinue).Installed")
+        .is_ok_and(|output| output.stdout.trim() == "True")
+}
+
+/// Licensing and session checks for Remote Desktop Session Hosts.
+pub fn rds_checks() -> Result<Vec<CheckItem>, String> {
+    let output = exec::powershell(RDS_SCRIPT).map_err(|e| format!("Failed to query Remote Desktop Services: {}", e))?;
+    let values = exec::key_values(&output.stdout);
+    let get = |key: &str| values.get(key).map(String::as_str).unwrap_or("");
+
+    let mut items = Vec::new();
+    let session_host = get("SessionHost") == "True";
+    items.push(CheckItem {
+        name: "Session Host role".to_string(),
+        status: if session_host { CheckStatus::Pass } else { CheckStatus::Unknown },
+        detail: if session_host {
+            format!("RD Session Host is installed, {} session(s) active", get("Sessions"))
+        } else {
+            "RD Session Host is not installed; only the two administrative sessions are available".to_string()
+        },
+        remediation: None,
+        advice: None,
+    });
+    if !session_host {
+        return Ok(items);
+    }
+
+    // 2 = Per Device, 4 = Per User; anything else leaves the host unlicensed
+    let mode_ok = matches!(get("LicensingType"), "2" | "4");
+    items.push(CheckItem {
+        name: "Licensing mode".to_string(),
+        status: if mode_ok { CheckStatus::Pass } else { CheckStatus::Fail },
+        detail: match get("LicensingName") {
+            "" => "Not configured".to_string(),
+            name => name.to_string(),
+        },
+        remediation: None,
+        advice: Some("Press l to set the licensing mode and license server.".to_string()),
+    });
+
+    let servers = get("LicenseServers");
+    items.push(CheckItem {
+        name: "License server".to_string(),
+        status: if servers.is_empty() { CheckStatus::Fail } else { CheckStatus::Pass },
+        detail: if servers.is_empty() { "None specified".to_string() } else { servers.to_string() },
+        remediation: None,
+        advice: Some("Press l to set the licensing mode and license server.".to_string()),
+    });
+
+    let grace: Option<u32> = get("GraceDays").parse().ok();
+    let (status, detail) = match grace {
+        // Once a license server is in use, the grace period no longer matters
+        Some(_) if mode_ok && !servers.is_empty() => {
+            (CheckStatus::Pass, format!("{} day(s) left (licensed via {})", grace.unwrap_or(0), servers))
+        }
+        Some(0) => (CheckStatus::Fail, "Expired: new connections are refused without a license server".to_string()),
+        Some(days) if days < 14 => (CheckStatus::Fail, format!("{} day(s) left", days)),
+        Some(days) => (CheckStatus::Pass, format!("{} day(s) left", days)),
+        None => (CheckStatus::Unknown, "Could not read the grace period".to_string()),
+    };
+    items.push(CheckItem {
+        name: "Grace period".to_string(),
+        status,
+        detail,
+        remediation: None,
+        advice: Some("Configure a license server with installed CALs before the grace period ends.".to_string()),
+    });
+
+    let collections: Vec<&str> = output.stdout.lines().filter_map(|l| l.strip_prefix("Collection=")).collect();
+    let (status, detail) = match (collections.is_empty(), get("CollectionError")) {
+        (false, _) => (CheckStatus::Pass, collections.join("; ")),
+        (true, "") => (CheckStatus::Unknown, "No session collections (standalone Session Host)".to_string()),
+        (true, error) => (CheckStatus::Unknown, format!("Connection Broker not reachable: {}", error)),
+    };
+    items.push(CheckItem {
+        name: "Session collections".to_string(),
+        status,
+        detail,
+        remediation: None,
+        advice: None,
+    });
+
+    let (status, detail) = match (get("WingetProvisioned") == "True", get("WingetForUser") == "True") {
+        (true, _) => (CheckStatus::Pass, "Winget is provisioned for all users".to_string()),
+        (false, true) => (
+            CheckStatus::Fail,
+            "Winget is installed for this user only; other session users cannot run it".to_string(),
+        ),
+        (false, false) => (CheckStatus::Unknown, "Winget is not installed".to_string()),
+    };
+    items.push(CheckItem {
+        name: "App packages (appx)".to_string(),
+        status,
+        detail,
+        remediation: None,
+        advice: Some(
+            "Per-user appx/MSIX packages misbehave on session hosts: they reach only the user who installed \
+            them and can break profile roaming. Install Winget from the menu again to provision it for all users; \
+            winget packages are installed machine-wide here."
+                .to_string(),
+        ),
+    });
+    Ok(items)
+}
+
+/// Current `servers mode` setting, to prefill the input.
+pub fn licensing_spec() -> String {
+    let script = format!(
+        "$ts = {}; $servers = ($ts | Invoke-CimMethod -MethodName GetSpecifiedLicenseServerList).SpecifiedLSList -join ','; \
+        $mode = switch ($ts.LicensingType) {{ 2 {{ 'device' }} 4 {{ 'user' }} default {{ '' }} }}; \"$servers $mode\".Trim()",
+        TS_SETTING
+    );
+    exec::powershell(&script).map(|o| o.stdout.trim().to_string()).unwrap_or_default()
+}
+
+/// Sets the license servers and licensing mode from `server[,server...] user|device`.
+pub fn set_licensing(spec: &str) -> (bool, String) {
+    let parts: Vec<&str> = spec.split_whitespace().collect();
+    let (servers, mode) = match parts.as_slice() {
+        [servers, mode] => (*servers, mode.to_lowercase()),
+        _ => return (false, "Expected: license-server[,server...] user|device".to_string()),
+    };
+    let licensing_type = match mode.as_str() {
+        "user" => 4,
+        "device" => 2,
+        _ => return (false, format!("Unknown licensing mode '{}': use user or device", mode)),
+    };
+    let list: Vec<String> = servers.split(',').filter(|s| !s.is_empty()).map(exec::quote).collect();
+
+    let script = format!(
+        "$ts = {}\n\
+        $ts | Invoke-CimMethod -MethodName ChangeMode -Arguments @{{ LicensingType = [uint32]{} }} -ErrorAction Stop | Out-Null\n\
+        $ts = {}\n\
+        $ts | Invoke-CimMethod -MethodName SetSpecifiedLicenseServerList -Arguments @{{ ServerList = [string[]]@({}) }} -ErrorAction Stop | Out-Null",
+        TS_SETTING,
+        licensing_type,
+        TS_SETTING,
+        list.join(", ")
+    );
+    match exec::change(&script) {
+        Ok(output) if output.success => (true, format!(
+            "Licensing set to per {} with license server(s) {}.\n\n\
+            Run the checks again to confirm the grace period and server status.",
+            mode, servers
+        )),
+        Ok(output) => (false, format!("Failed to set RDS licensing:\n{}", output.stderr.trim())),
+        Err(e) => (false, format!("Failed to set RDS licensing: {}", e)),
+    }
+}
@@ -0,0 +1,195 @@
+//! Restores from a role backup kept on another server: its backup directories are listed over
+//! the admin share, or through a PowerShell session when the share is closed, and the chosen
+//! backup is copied here with its readable list and source record before the restore plan. A
+//! differential backup is copied with the full backup it was stored against.
+
+use std::path::PathBuf;
+
+use crate::{backup_dir, dates, exec};
+
+/// Where Server Helper keeps backups on a server, relative to its system drive.
+const BACKUP_DIRS: [&str; 2] = ["Users\\*\\Documents\\ServerBackups", "ServerBackups"];
+
+/// Full and differential role backups.
+const BACKUP_FILES: [&str; 2] = ["ServerRoles_*.xml", "ServerRoles_*.delta"];
+
+/// The backup files in each of `dirs`, quoted and comma-separated for `Get-ChildItem -Path`.
+fn patterns(dirs: &[String]) -> String {
+    dirs.iter()
+        .flat_map(|dir| BACKUP_FILES.iter().map(move |file| exec::quote(&format!("{}\\{}", dir, file))))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// A host name, or a UNC folder holding backups.
+fn check_source(source: &str) -> Result<&str, String> {
+    let source = source.trim().trim_end_matches('\\');
+    let host_name = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.');
+    if host_name(source) || (source.starts_with("\\\\") && source.len() > 2) {
+        Ok(source)
+    } else {
+        Err("Enter a server name, e.g. FS01, or a share holding backups, e.g. \\\\nas01\\ServerBackups.".to_string())
+    }
+}
+
+/// Lists backups as `via<TAB>path` lines: `share` for files reachable over the network,
+/// `session` for paths on the remote server.
+fn list_script(source: &str) -> String {
+    if source.starts_with("\\\\") {
+        return format!(
+            "Get-ChildItem -Path {} -ErrorAction Stop | ForEach-Object {{ \"share`t$($_.FullName)\" }}",
+            patterns(&[source.to_string()])
+        );
+    }
+    let on = |root: &str| patterns(&BACKUP_DIRS.map(|dir| format!("{}\\{}", root, dir)));
+    format!(
+        "if (Test-Path -LiteralPath {share}) {{\n\
+            Get-ChildItem -Path {shared} -ErrorAction SilentlyContinue | ForEach-Object {{ \"share`t$($_.FullName)\" }}\n\
+        }} else {{\n\
+            Invoke-Command -ComputerName {host} -ErrorAction Stop -ScriptBlock {{\n\
+                Get-ChildItem -Path {local} -ErrorAction SilentlyContinue | ForEach-Object {{ \"session`t$($_.FullName)\" }}\n\
+            }}\n\
+        }}",
+        share = exec::quote(&format!("\\\\{}\\C$", source)),
+        shared = on(&format!("\\\\{}\\C$", source)),
+        host = exec::quote(source),
+        local = on("C:"),
+    )
+}
+
+/// Role backups on `source`, newest first: file name, time taken, how it is reached and path.
+pub fn backups(source: &str) -> Result<Vec<Vec<String>>, String> {
+    let source = check_source(source)?;
+    let output = exec::powershell(&list_script(source))
+        .map_err(|e| format!("Failed to list the backups on {}: {}", source, e))?;
+    if !output.success {
+        return Err(format!("Failed to list the backups on {}:\n{}", source, output.stderr.trim()));
+    }
+    let mut rows: Vec<(i64, Vec<String>)> = exec::tab_rows(&output.stdout)
+        .into_iter()
+        .filter(|r| r.len() == 2)
+        .map(|r| {
+            let name = r[1].rsplit('\\').next().unwrap_or_default().to_string();
+            let secs = dates::from_file_name(&name).unwrap_or(0);
+            let via = if r[0] == "session" { "PowerShell session" } else { "Admin share" };
+            (secs, vec![name, if secs > 0 { dates::local(secs) } else { String::new() }, via.to_string(), r[1].clone()])
+        })
+        .collect();
+    if rows.is_empty() {
+        return Err(format!("No ServerRoles_* backups found on {}.", source));
+    }
+    rows.sort_by_key(|row| std::cmp::Reverse(row.0));
+    Ok(rows.into_iter().map(|(_, row)| row).collect())
+}
+
+/// Copies the backup at `path` on `source`, reached as `via` (a row of `backups`), into a
+/// folder named after the source in the backup directory, with the full backup a differential
+/// one needs to be rebuilt. Returns the local copy.
+pub fn fetch(source: &str, via: &str, path: &str) -> Result<PathBuf, String> {
+    let source = check_source(source)?;
+    let name = path.rsplit('\\').next().unwrap_or_default();
+    let folder = source.trim_start_matches('\\').replace('\\', "_");
+    let dir = backup_dir().join(format!("From_{}", folder));
+    let files = format!(
+        "$src = {0}\n\
+        $manifest = [IO.Path]::ChangeExtension($src, 'manifest')\n\
+        $files = @($src, [IO.Path]::ChangeExtension($src, 'source'), ($src -replace 'ServerRoles_(\\d+)\\.\\w+$', 'InstalledFeatures_$1.txt'), $manifest, \"$manifest.minisig\")\n\
+        New-Item -ItemType Directory -Path {1} -Force | Out-Null\n",
+        exec::quote(path),
+        exec::quote(&dir.to_string_lossy())
+    );
+    let copy = if via == "PowerShell session" {
+        format!(
+            "$s = New-PSSession -ComputerName {} -ErrorAction Stop\n\
+            try {{\n\
+                if ($src -like '*.delta') {{\n\
+                    $files += Invoke-Command -Session $s -ScriptBlock {{ Join-Path (Split-Path -Parent $using:src) (Import-Clixml -Path $using:src).Base }}\n\
+                }}\n\
+                foreach ($f in $files) {{\n\
+                    if (Invoke-Command -Session $s -ScriptBlock {{ Test-Path -LiteralPath $using:f }}) {{\n\
+                        Copy-Item -FromSession $s -LiteralPath $f -Destination {} -ErrorAction Stop\n\
+                    }}\n\
+                }}\n\
+            }} finally {{ Remove-PSSession $s }}",
+            exec::quote(source),
+            exec::quote(&dir.to_string_lossy())
+        )
+    } else {
+        format!(
+            "if ($src -like '*.delta') {{ $files += Join-Path (Split-Path -Parent $src) (Import-Clixml -Path $src).Base }}\n\
+            foreach ($f in $files) {{ if (Test-Path -LiteralPath $f) {{ Copy-Item -LiteralPath $f -Destination {} -ErrorAction Stop }} }}",
+            exec::quote(&dir.to_string_lossy())
+        )
+    };
+    let output = exec::powershell(&(files + &copy)).map_err(|e| format!("Failed to copy {}: {}", name, e))?;
+    let local = dir.join(name);
+    if !output.success || !local.exists() {
+        return Err(format!("Failed to copy {} from {}:\n{}", name, source, output.stderr.trim()));
+    }
+    Ok(local)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{output, FakeRunner};
+
+    #[test]
+    fn lists_remote_backups_newest_first() {
+        FakeRunner::new()
+            .on(
+                "Invoke-Command -ComputerName 'FS01'",
+                "share\t\\\\FS01\\C$\\Users\\admin\\Documents\\ServerBackups\\ServerRoles_1700000000.xml\n\
+                share\t\\\\FS01\\C$\\ServerBackups\\ServerRoles_1709288100.xml\n",
+            )
+            .install();
+
+        let rows = backups("FS01").unwrap_or_default();
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0][0], "ServerRoles_1709288100.xml");
+        assert_eq!(rows[1][2], "Admin share");
+        assert!(backups("FS01; Remove-Item").is_err());
+    }
+
+    #[test]
+    fn copies_a_backup_through_a_powershell_session() {
+        let runner = FakeRunner::new()
+            .on_with("New-PSSession", |_| {
+                let dir = backup_dir().join("From_FS01");
+                std::fs::create_dir_all(&dir).and_then(|_| std::fs::write(dir.join("ServerRoles_1709288100.xml"), "<Objs />"))?;
+                Ok(output(true, "", ""))
+            })
+            .install();
+
+        let local = fetch("FS01", "PowerShell session", "C:\\ServerBackups\\ServerRoles_1709288100.xml");
+
+        assert_eq!(local, Ok(backup_dir().join("From_FS01").join("ServerRoles_1709288100.xml")));
+        assert!(runner.ran("Copy-Item -FromSession $s"));
+    }
+
+    #[test]
+    fn lists_and_copies_a_differential_backup_with_its_base() {
+        let runner = FakeRunner::new()
+            .on(
+                "Get-ChildItem",
+                "share\t\\\\nas01\\ServerBackups\\ServerRoles_1709288100.xml\n\
+                share\t\\\\nas01\\ServerBackups\\ServerRoles_1709374500.delta\n",
+            )
+            .on_with("Copy-Item", |_| {
+                let dir = backup_dir().join("From_nas01_ServerBackups");
+                std::fs::create_dir_all(&dir).and_then(|_| std::fs::write(dir.join("ServerRoles_1709374500.delta"), "<Objs />"))?;
+                Ok(output(true, "", ""))
+            })
+            .install();
+
+        let rows = backups("\\\\nas01\\ServerBackups").unwrap_or_default();
+        assert_eq!(rows[0][0], "ServerRoles_1709374500.delta");
+        assert!(runner.ran("'\\\\nas01\\ServerBackups\\ServerRoles_*.delta'"));
+
+        let local = fetch("\\\\nas01\\ServerBackups", &rows[0][2], &rows[0][3]);
+
+        assert_eq!(local, Ok(backup_dir().join("From_nas01_ServerBackups").join("ServerRoles_1709374500.delta")));
+        assert!(runner.ran("$files += Join-Path (Split-Path -Parent $src) (Import-Clixml -Path $src).Base"));
+    }
+}
@@ -0,0 +1,189 @@
+//! Links a backup to the server it was taken on, so a restore onto a server with another name
+//! offers to update what still names the old host instead of applying it as it was.
+
+use std::path::{Path, PathBuf};
+
+use crate::{exec, plan::Plan};
+
+/// The host name, domain, shares and SPNs of this server, as `key=value` lines. Shares are
+/// `Share=name<TAB>description`.
+const SOURCE_SCRIPT: &str = r#"
+"Host=$env:COMPUTERNAME"
+"Domain=$((Get-CimInstance Win32_ComputerSystem).Domain)"
+Get-SmbShare -Special $false -ErrorAction SilentlyContinue | ForEach-Object { "Share=$($_.Name)`t$($_.Description)" }
+setspn -L $env:COMPUTERNAME 2>$null | Select-Object -Skip 1 | Where-Object { $_.Trim() } | ForEach-Object { "SPN=$($_.Trim())" }
+"#;
+
+/// Where the source of `backup` is recorded: `ServerRoles_1700000000.source` next to the XML.
+pub fn source_path(backup: &Path) -> PathBuf {
+    backup.with_extension("source")
+}
+
+/// What a server looked like when a backup was taken, or what this one looks like now.
+#[derive(Default, Debug, PartialEq)]
+pub struct Source {
+    pub host: String,
+    pub domain: String,
+    /// Share name and description.
+    pub shares: Vec<(String, String)>,
+    pub spns: Vec<String>,
+}
+
+impl Source {
+    fn parse(text: &str) -> Source {
+        let mut source = Source::default();
+        for (key, value) in text.lines().filter_map(|l| l.split_once('=')) {
+            let value = value.trim();
+            match key.trim() {
+                "Host" => source.host = value.to_string(),
+                "Domain" => source.domain = value.to_string(),
+                "Share" => {
+                    let (name, description) = value.split_once('\t').unwrap_or((value, ""));
+                    source.shares.push((name.to_string(), description.trim().to_string()));
+                }
+                "SPN" if !value.is_empty() => source.spns.push(value.to_string()),
+                _ => {}
+            }
+        }
+        source
+    }
+
+    fn render(&self) -> String {
+        let mut lines = vec![
+            "# Server the backup next to this file was taken on".to_string(),
+            format!("Host={}", self.host),
+            format!("Domain={}", self.domain),
+        ];
+        lines.extend(self.shares.iter().map(|(name, description)| format!("Share={}\t{}", name, description)));
+        lines.extend(self.spns.iter().map(|spn| format!("SPN={}", spn)));
+        lines.join("\r\n") + "\r\n"
+    }
+
+    /// This server as it is now.
+    fn current() -> Result<Source, String> {
+        let output = exec::powershell(SOURCE_SCRIPT).map_err(|e| format!("Failed to read the host name: {}", e))?;
+        let source = Source::parse(&output.stdout);
+        if source.host.is_empty() {
+            return Err(format!("Failed to read the host name:\n{}", output.stderr.trim()));
+        }
+        Ok(source)
+    }
+}
+
+/// Records this server as the source of `backup`. Returns the host name for the report.
+pub fn record(backup: &Path) -> Result<String, String> {
+    let source = Source::current()?;
+    let path = source_path(backup);
+    exec::write_file(&path, &source.render()).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(source.host)
+}
+
+/// The server `backup` was taken on, when it was recorded.
+pub fn recorded_host(backup: &Path) -> Option<String> {
+    let text = std::fs::read_to_string(source_path(backup)).ok()?;
+    Some(Source::parse(&text).host).filter(|host| !host.is_empty())
+}
+
+/// `text` with every case-insensitive occurrence of the host name `old` replaced by `new`.
+fn replace_host(text: &str, old: &str, new: &str) -> String {
+    if old.is_empty() {
+        return text.to_string();
+    }
+    let (lower, old) = (text.to_ascii_lowercase(), old.to_ascii_lowercase());
+    let mut out = String::new();
+    let mut rest = 0;
+    while let Some(found) = lower[rest..].find(&old) {
+        out.push_str(&text[rest..rest + found]);
+        out.push_str(new);
+        rest += found + old.len();
+    }
+    out.push_str(&text[rest..]);
+    out
+}
+
+/// When `backup` was taken on a server with another name, adds steps that update the share
+/// descriptions here that still name the old host, and notes the SPNs to register for the new
+/// name. Backups without a recorded source, or taken on this server, leave the plan unchanged.
+#[cfg_attr(not(feature = "windows-roles"), allow(dead_code))]
+pub fn adapt_plan(plan: &mut Plan, backup: &Path) {
+    let Ok(text) = std::fs::read_to_string(source_path(backup)) else { return };
+    let source = Source::parse(&text);
+    let current = match Source::current() {
+        Ok(current) => current,
+        Err(message) => {
+            plan.note(format!("Could not compare host names: {}", message));
+            return;
+        }
+    };
+    if source.host.is_empty() || source.host.eq_ignore_ascii_case(&current.host) {
+        return;
+    }
+
+    plan.note(format!(
+        "The backup was taken on {} and this server is {}; items naming {} are updated instead of copied",
+        source.host, current.host, source.host
+    ));
+    if !source.domain.is_empty() && !source.domain.eq_ignore_ascii_case(&current.domain) {
+        plan.note(format!("The backup came from domain {}; this server is in {}", source.domain, current.domain));
+    }
+    let old = source.host.to_ascii_lowercase();
+    for (name, description) in current.shares.iter().filter(|(_, d)| d.to_ascii_lowercase().contains(&old)) {
+        let updated = replace_host(description, &source.host, &current.host);
+        plan.step(
+            format!("Update the description of share {} to name {}", name, current.host),
+            format!("Set-SmbShare -Name {} -Description {} -Force", exec::quote(name), exec::quote(&updated)),
+        );
+    }
+    let spns: Vec<String> = source
+        .spns
+        .iter()
+        .filter(|spn| spn.to_ascii_lowercase().contains(&old))
+        .map(|spn| replace_host(spn, &source.host, &current.host))
+        .filter(|spn| !current.spns.iter().any(|s| s.eq_ignore_ascii_case(spn)))
+        .collect();
+    if !spns.is_empty() {
+        plan.note(format!(
+            "SPNs the old server had that this one lacks; register any the services here need with setspn -S <SPN> <account>:\n      {}",
+            spns.join("\n      ")
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{temp_dir, FakeRunner};
+
+    #[test]
+    fn replaces_the_host_name_in_any_case() {
+        assert_eq!(replace_host("Finance on OLDFS01 (oldfs01.corp.local)", "oldfs01", "FS02"), "Finance on FS02 (FS02.corp.local)");
+        assert_eq!(replace_host("Public", "oldfs01", "FS02"), "Public");
+    }
+
+    #[test]
+    fn offers_share_and_spn_updates_after_a_rename() {
+        FakeRunner::new()
+            .on(
+                "Get-SmbShare",
+                "Host=FS02\nDomain=corp.local\nShare=Finance\tFinance share on OLDFS01\nShare=Public\tPublic files\n",
+            )
+            .install();
+        let backup = temp_dir("rename").join("ServerRoles_1700000000.xml");
+        let source = Source {
+            host: "OLDFS01".to_string(),
+            domain: "corp.local".to_string(),
+            shares: vec![("Finance".to_string(), "Finance share on OLDFS01".to_string())],
+            spns: vec!["HTTP/oldfs01.corp.local".to_string(), "HOST/OLDFS01".to_string()],
+        };
+        std::fs::write(source_path(&backup), source.render()).expect("write source");
+        assert_eq!(Source::parse(&source.render()), source);
+
+        let mut plan = Plan::new("Restore");
+        adapt_plan(&mut plan, &backup);
+
+        assert_eq!(plan.steps.len(), 1);
+        assert_eq!(plan.steps[0].command, "Set-SmbShare -Name 'Finance' -Description 'Finance share on FS02' -Force");
+        assert!(plan.notes[0].starts_with("The backup was taken on OLDFS01 and this server is FS02"));
+        assert!(plan.notes[1].contains("HTTP/FS02.corp.local"));
+    }
+}
@@ -0,0 +1,218 @@
+use std::io::{BufRead, BufReader, Read};
+use std::process::{Command, Stdio};
+
+use crate::{backup_dir, exec};
+
+/// A data copy chosen on the guided copy screens.
+#[derive(Clone, PartialEq)]
+pub struct Migration {
+    pub source: String,
+    pub destination: String,
+    /// `/MIR`: also delete destination files that are not in the source.
+    pub mirror: bool,
+    /// Copy NTFS permissions, owner and auditing along with the data.
+    pub acls: bool,
+}
+
+impl Migration {
+    /// Parses the mode input, e.g. `copy acls` or `mirror noacls`.
+    pub fn new(source: String, destination: String, options: &str) -> Result<Migration, String> {
+        let (mut mirror, mut acls) = (false, true);
+        for option in options.split_whitespace().map(str::to_lowercase) {
+            match option.as_str() {
+                "copy" => mirror = false,
+                "mirror" => mirror = true,
+                "acls" => acls = true,
+                "noacls" => acls = false,
+                other => return Err(format!("Unknown option '{}': use copy or mirror, and acls or noacls", other)),
+            }
+        }
+        if source.trim_end_matches('\\').eq_ignore_ascii_case(destination.trim_end_matches('\\')) {
+            return Err("The source and destination are the same folder.".to_string());
+        }
+        Ok(Migration { source, destination, mirror, acls })
+    }
+
+    pub fn describe(&self) -> String {
+        format!("{} {} to {}", if self.mirror { "Mirror" } else { "Copy" }, self.source, self.destination)
+    }
+
+    /// Robocopy arguments after the source and destination.
+    fn options(&self) -> Vec<&'static str> {
+        let mut args = vec![if self.mirror { "/MIR" } else { "/E" }];
+        if self.acls {
+            args.extend(["/COPY:DATSOU", "/DCOPY:DAT", "/SECFIX"]);
+        } else {
+            args.extend(["/COPY:DAT", "/DCOPY:DAT"]);
+        }
+        // Few retries so a locked file doesn't stall the copy for a day (the default is 1 million)
+        args.extend(["/R:2", "/W:5", "/XJ", "/BYTES", "/NDL", "/FP"]);
+        args
+    }
+
+    /// The command as it will run, for the confirmation screen.
+    pub fn command_line(&self) -> String {
+        let mut parts = vec!["robocopy".to_string(), exec::quote(&self.source), exec::quote(&self.destination)];
+        parts.extend(self.options().iter().map(|a| a.to_string()));
+        parts.join(" ")
+    }
+}
+
+/// Bytes robocopy would copy, from a list-only (`/L`) pass.
+fn bytes_to_copy(migration: &Migration) -> Option<u64> {
+    let mut args = vec![migration.source.as_str(), migration.destination.as_str()];
+    args.extend(migration.options());
+    args.extend(["/L", "/NFL", "/NJH"]);
+    let output = exec::run("robocopy", &args).ok()?;
+    output
+        .stdout
+        .lines()
+        .find_map(|l| l.trim().strip_prefix("Bytes :"))
+        .and_then(|row| row.split_whitespace().nth(1))
+        .and_then(|copied| copied.parse().ok())
+}
+
+/// Per-file percentage robocopy redraws while copying, e.g. `  42.5%`.
+fn percent(line: &str) -> Option<f64> {
+    line.strip_suffix('%')?.trim().parse().ok().filter(|p| *p <= 100.0)
+}
+
+/// Size column of a file line, e.g. `New File   1048576   D:\data\a.txt`.
+fn file_size(line: &str) -> Option<u64> {
+    let mut fields = line.split('\t').map(str::trim).filter(|f| !f.is_empty());
+    let _class = fields.next()?;
+    fields.next()?.parse().ok()
+}
+
+fn megabytes(bytes: &str) -> String {
+    bytes.parse::<f64>().map(|b| format!("{:.1} MB", b / 1_048_576.0)).unwrap_or_else(|_| bytes.to_string())
+}
+
+/// Turns robocopy's closing statistics table into plain sentences.
+fn statistics(lines: &[String]) -> Vec<String> {
+    let row = |name: &str| -> Vec<String> {
+        lines
+            .iter()
+            .find_map(|l| l.trim().strip_prefix(name))
+            .map(|r| r.trim_start_matches(':').split_whitespace().map(String::from).collect())
+            .unwrap_or_default()
+    };
+    let mut out = Vec::new();
+    // Columns: Total, Copied, Skipped, Mismatch, FAILED, Extras
+    for (name, label) in [("Dirs", "Folders"), ("Files", "Files")] {
+        let r = row(name);
+        if r.len() >= 6 {
+            out.push(format!(
+                "{}: {} copied, {} skipped, {} failed, {} extra (of {})",
+                label, r[1], r[2], r[4], r[5], r[0]
+            ));
+        }
+    }
+    let bytes = row("Bytes");
+    if bytes.len() >= 6 {
+        out.push(format!(
+            "Data: {} copied, {} skipped, {} failed",
+            megabytes(&bytes[1]),
+            megabytes(&bytes[2]),
+            megabytes(&bytes[4])
+        ));
+    }
+    let times = row("Times");
+    if !times.is_empty() {
+        out.push(format!("Time: {}", times[0]));
+    }
+    if let Some(speed) = lines.iter().find_map(|l| l.trim().strip_prefix("Speed :")) {
+        out.push(format!("Speed: {}", speed.trim()));
+    }
+    out
+}
+
+/// Runs the copy, calling `progress` with each copied file and the overall percentage,
+/// and returns the statistics summary. The full output is logged to the backup directory.
+pub fn run(migration: &Migration, mut progress: impl FnMut(Option<&str>, Option<u8>)) -> Result<String, String> {
+    let stamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let log = backup_dir().join(format!("Robocopy_{}.log", stamp));
+    let log_arg = format!("/LOG:{}", log.display());
+    let mut args = vec![migration.source.as_str(), migration.destination.as_str()];
+    args.extend(migration.options());
+    args.extend([log_arg.as_str(), "/TEE"]);
+
+    if exec::export_status().is_some() {
+        exec::change_program("robocopy", &args).map_err(|e| e.to_string())?;
+        return Ok(format!("robocopy {}", args.join(" ")));
+    }
+    std::fs::create_dir_all(backup_dir()).map_err(|e| format!("Failed to create the backup directory: {}", e))?;
+
+    progress(Some("Counting the data to copy..."), Some(0));
+    let total = bytes_to_copy(migration).filter(|t| *t > 0);
+    if let Some(total) = total {
+        progress(Some(&format!("{} to copy", megabytes(&total.to_string()))), None);
+    }
+
+    let mut child = Command::new("robocopy")
+        .args(&args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to start robocopy: {}", e))?;
+
+    // Bytes of finished files, and the size of the file being copied
+    let (mut done, mut current) = (0u64, 0u64);
+    let mut lines = Vec::new();
+    if let Some(stdout) = child.stdout.take() {
+        let mut reader = BufReader::new(stdout);
+        let mut buf = Vec::new();
+        while matches!(reader.read_until(b'\n', &mut buf), Ok(n) if n > 0) {
+            // Per-file percentages are redrawn with bare carriage returns
+            for line in String::from_utf8_lossy(&buf).split('\r') {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                match (percent(line), total) {
+                    (Some(p), Some(total)) => {
+                        let copied = done + (current as f64 * p / 100.0) as u64;
+                        progress(None, Some((copied * 100 / total).min(99) as u8));
+                    }
+                    (Some(_), None) => {}
+                    (None, _) => {
+                        // Extra files are deleted by /MIR, not copied
+                        if let Some(size) = file_size(line).filter(|_| !line.contains("*EXTRA")) {
+                            done += current;
+                            current = size;
+                            progress(Some(line.trim()), None);
+                        }
+                        lines.push(line.to_string());
+                    }
+                }
+            }
+            buf.clear();
+        }
+    }
+    let mut stderr = String::new();
+    if let Some(mut err) = child.stderr.take() {
+        let _ = err.read_to_string(&mut stderr);
+    }
+    let status = child.wait().map_err(|e| format!("Failed to wait for robocopy: {}", e))?;
+    progress(None, Some(100));
+
+    let mut summary = statistics(&lines);
+    summary.push(format!("Log: {}", log.display()));
+    // Exit codes below 8 mean success (1 = files copied, 2 = extra files, 4 = mismatches)
+    match status.code() {
+        Some(code) if code < 8 => Ok(summary.join("\n")),
+        code => {
+            let errors: Vec<&str> = lines.iter().map(|l| l.trim()).filter(|l| l.contains("ERROR")).take(5).collect();
+            Err(format!(
+                "robocopy failed (exit code {}):\n{}\n{}\n{}",
+                code.map(|c| c.to_string()).unwrap_or_else(|| "unknown".to_string()),
+                errors.join("\n"),
+                stderr.trim(),
+                summary.join("\n")
+            ))
+        }
+    }
+}
@@ -0,0 +1,514 @@
+use crate::{exec, plan::Plan, tls};
+use crate::{CheckItem, CheckStatus, Remediation};
+
+/// Prints one `Key=Value` line per setting so everything is read in a single PowerShell run.
+const AUDIT_SCRIPT: &str = r#"
+$smb = (Get-SmbServerConfiguration -ErrorAction SilentlyContinue).EnableSMB1Protocol
+"SMB1=$smb"
+foreach ($p in 'TLS 1.0', 'TLS 1.1') {
+    $v = (Get-ItemProperty -Path "HKLM:\SYSTEM\CurrentControlSet\Control\SecurityProviders\SCHANNEL\Protocols\$p\Server" -Name Enabled -ErrorAction SilentlyContinue).Enabled
+    "$p=$v"
+}
+$llmnr = (Get-ItemProperty -Path 'HKLM:\SOFTWARE\Policies\Microsoft\Windows NT\DNSClient' -Name EnableMulticast -ErrorAction SilentlyContinue).EnableMulticast
+"LLMNR=$llmnr"
+$lm = (Get-ItemProperty -Path 'HKLM:\SYSTEM\CurrentControlSet\Control\Lsa' -Name LmCompatibilityLevel -ErrorAction SilentlyContinue).LmCompatibilityLevel
+"NTLM=$lm"
+"#;
+
+/// Checks SMB1, legacy TLS, LLMNR and NTLMv1 and returns one checklist entry per setting.
+pub fn audit_insecure_protocols() -> Result<Vec<CheckItem>, String> {
+    let output = exec::powershell(AUDIT_SCRIPT)
+        .map_err(|e| format!("Failed to run security audit: {}", e))?;
+    let values = exec::key_values(&output.stdout);
+    let get = |key: &str| values.get(key).map(String::as_str).unwrap_or("");
+
+    let mut items = vec![smb1_item(get("SMB1"))];
+    for protocol in ["TLS 1.0", "TLS 1.1"] {
+        items.push(tls_item(protocol, get(protocol)));
+    }
+    items.push(llmnr_item(get("LLMNR")));
+    items.push(ntlm_item(get("NTLM")));
+    Ok(items)
+}
+
+fn smb1_item(value: &str) -> CheckItem {
+    let (status, detail) = match value {
+        "True" => (CheckStatus::Fail, "SMB1 server protocol is enabled"),
+        "False" => (CheckStatus::Pass, "SMB1 server protocol is disabled"),
+        _ => (CheckStatus::Unknown, "Could not read SMB server configuration"),
+    };
+    CheckItem {
+        name: "SMB1".to_string(),
+        status,
+        detail: detail.to_string(),
+        remediation: Some(Remediation {
+            summary: "Disable the SMB1 server protocol. Clients that only speak SMB1 will no longer connect.".to_string(),
+            script: "Set-SmbServerConfiguration -EnableSMB1Protocol $false -Force".to_string(),
+        }),
+        advice: None,
+    }
+}
+
+fn tls_item(protocol: &str, value: &str) -> CheckItem {
+    // Schannel treats a missing key as "use the OS default", which is enabled on Server 2016-2022.
+    let (status, detail) = match value {
+        "0" => (CheckStatus::Pass, format!("{} is disabled", protocol)),
+        "" => (CheckStatus::Fail, format!("{} is enabled (OS default)", protocol)),
+        _ => (CheckStatus::Fail, format!("{} is explicitly enabled", protocol)),
+    };
+    CheckItem {
+        name: protocol.to_string(),
+        status,
+        detail,
+        remediation: Some(Remediation {
+            summary: format!("Disable {} for Schannel (server and client). A reboot is required.", protocol),
+            script: tls::disable_protocol_script(protocol),
+        }),
+        advice: None,
+    }
+}
+
+fn llmnr_item(value: &str) -> CheckItem {
+    let (status, detail) = match value {
+        "0" => (CheckStatus::Pass, "LLMNR is disabled by policy"),
+        _ => (CheckStatus::Fail, "LLMNR is enabled"),
+    };
+    CheckItem {
+        name: "LLMNR".to_string(),
+        status,
+        detail: detail.to_string(),
+        remediation: Some(Remediation {
+            summary: "Disable LLMNR (multicast name resolution) via local policy.".to_string(),
+            script: "$k = 'HKLM:\\SOFTWARE\\Policies\\Microsoft\\Windows NT\\DNSClient'; \
+                if (-not (Test-Path $k)) { New-Item -Path $k -Force | Out-Null }; \
+                New-ItemProperty -Path $k -Name EnableMulticast -Value 0 -PropertyType DWord -Force | Out-Null"
+                .to_string(),
+        }),
+        advice: None,
+    }
+}
+
+fn ntlm_item(value: &str) -> CheckItem {
+    // LmCompatibilityLevel defaults to 3 when the value is absent.
+    let level: u32 = value.parse().unwrap_or(3);
+    let status = if level >= 5 { CheckStatus::Pass } else { CheckStatus::Fail };
+    let detail = if level >= 5 {
+        format!("LmCompatibilityLevel = {} (NTLMv2 only, LM/NTLMv1 refused)", level)
+    } else {
+        format!("LmCompatibilityLevel = {} (LM/NTLMv1 still accepted)", level)
+    };
+    CheckItem {
+        name: "NTLMv1".to_string(),
+        status,
+        detail,
+        remediation: Some(Remediation {
+            summary: "Set LmCompatibilityLevel to 5 (send NTLMv2 only, refuse LM and NTLMv1). Legacy clients may fail to authenticate.".to_string(),
+            script: "Set-ItemProperty -Path 'HKLM:\\SYSTEM\\CurrentControlSet\\Control\\Lsa' -Name LmCompatibilityLevel -Value 5 -Type DWord".to_string(),
+        }),
+        advice: None,
+    }
+}
+
+/// Audit subcategories checked by the hardening list, keyed by GUID so the probe works on any UI language.
+const AUDIT_SUBCATEGORIES: [(&str, &str); 4] = [
+    ("Logon", "{0CCE9215-69AE-11D9-BED3-505054503030}"),
+    ("Credential Validation", "{0CCE923F-69AE-11D9-BED3-505054503030}"),
+    ("User Account Management", "{0CCE9235-69AE-11D9-BED3-505054503030}"),
+    ("Security Group Management", "{0CCE9237-69AE-11D9-BED3-505054503030}"),
+];
+
+const HARDENING_SCRIPT: &str = r#"
+net accounts
+foreach ($guid in $args) {
+    $row = auditpol /get /subcategory:$guid /r | Where-Object { $_ } | ConvertFrom-Csv
+    "AUDIT$guid=$($row.'Inclusion Setting')"
+}
+$nla = (Get-ItemProperty -Path 'HKLM:\SYSTEM\CurrentControlSet\Control\Terminal Server\WinStations\RDP-Tcp' -Name UserAuthentication -ErrorAction SilentlyContinue).UserAuthentication
+"NLA=$nla"
+Get-NetFirewallProfile | ForEach-Object { "FW$($_.Name)=$($_.Enabled)" }
+"#;
+
+/// Evaluates a curated subset of the CIS Windows Server benchmark.
+pub fn hardening_checklist() -> Result<Vec<CheckItem>, String> {
+    let guids: Vec<String> = AUDIT_SUBCATEGORIES.iter().map(|(_, guid)| format!("'{}'", guid)).collect();
+    let script = format!("& {{ {} }} {}", HARDENING_SCRIPT, guids.join(" "));
+    let output = exec::powershell(&script)
+        .map_err(|e| format!("Failed to read security policy: {}", e))?;
+    let values = exec::key_values(&output.stdout);
+    let accounts = net_accounts(&output.stdout);
+
+    let mut items = password_policy_items(&accounts);
+
+    for (name, guid) in AUDIT_SUBCATEGORIES {
+        let setting = values.get(&format!("AUDIT{}", guid)).map(String::as_str).unwrap_or("");
+        let status = match setting {
+            "" => CheckStatus::Unknown,
+            "Success and Failure" => CheckStatus::Pass,
+            _ => CheckStatus::Fail,
+        };
+        items.push(CheckItem {
+            name: format!("Audit: {}", name),
+            status,
+            detail: if setting.is_empty() { "Could not read audit policy".to_string() } else { setting.to_string() },
+            remediation: Some(Remediation {
+                summary: format!("Audit success and failure events for \"{}\".", name),
+                script: format!("auditpol /set /subcategory:\"{}\" /success:enable /failure:enable", guid),
+            }),
+            advice: None,
+        });
+    }
+
+    let nla = values.get("NLA").map(String::as_str).unwrap_or("");
+    items.push(CheckItem {
+        name: "RDP: Network Level Authentication".to_string(),
+        status: if nla == "1" { CheckStatus::Pass } else { CheckStatus::Fail },
+        detail: if nla == "1" { "Required" } else { "Not required" }.to_string(),
+        remediation: Some(Remediation {
+            summary: "Require Network Level Authentication for Remote Desktop connections.".to_string(),
+            script: "Set-ItemProperty -Path 'HKLM:\\SYSTEM\\CurrentControlSet\\Control\\Terminal Server\\WinStations\\RDP-Tcp' -Name UserAuthentication -Value 1".to_string(),
+        }),
+        advice: None,
+    });
+
+    for profile in ["Domain", "Private", "Public"] {
+        let enabled = values.get(&format!("FW{}", profile)).map(String::as_str).unwrap_or("");
+        let status = match enabled {
+            "True" => CheckStatus::Pass,
+            "False" => CheckStatus::Fail,
+            _ => CheckStatus::Unknown,
+        };
+        items.push(CheckItem {
+            name: format!("Firewall: {} profile", profile),
+            status,
+            detail: match status {
+                CheckStatus::Pass => "Enabled",
+                CheckStatus::Fail => "Disabled",
+                CheckStatus::Unknown => "Could not read firewall profile",
+            }
+            .to_string(),
+            remediation: Some(Remediation {
+                summary: format!("Enable Windows Defender Firewall for the {} profile.", profile),
+                script: format!("Set-NetFirewallProfile -Profile {} -Enabled True", profile),
+            }),
+            advice: None,
+        });
+    }
+
+    Ok(items)
+}
+
+/// Parses the `Label:   value` lines printed by `net accounts`.
+fn net_accounts(stdout: &str) -> std::collections::HashMap<String, String> {
+    stdout
+        .lines()
+        .filter(|line| !line.contains('='))
+        .filter_map(|line| line.split_once(':'))
+        .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+        .collect()
+}
+
+/// `net accounts` prints words such as "None", "Never" or "Unlimited" instead of 0.
+fn accounts_number(accounts: &std::collections::HashMap<String, String>, key: &str) -> Option<u32> {
+    let value = accounts.get(key)?;
+    Some(value.parse().unwrap_or(0))
+}
+
+/// One `net accounts` setting and the value range the benchmark accepts.
+struct PolicyRule {
+    label: &'static str,
+    key: &'static str,
+    passes: fn(u32) -> bool,
+    requirement: &'static str,
+    switch: &'static str,
+}
+
+const PASSWORD_RULES: [PolicyRule; 5] = [
+    PolicyRule {
+        label: "Minimum password length",
+        key: "Minimum password length",
+        passes: |n| n >= 14,
+        requirement: "14 or more",
+        switch: "/minpwlen:14",
+    },
+    PolicyRule {
+        label: "Password history",
+        key: "Length of password history maintained",
+        passes: |n| n >= 24,
+        requirement: "24 or more",
+        switch: "/uniquepw:24",
+    },
+    PolicyRule {
+        label: "Maximum password age",
+        key: "Maximum password age (days)",
+        passes: |n| (1..=365).contains(&n),
+        requirement: "1-365 days",
+        switch: "/maxpwage:365",
+    },
+    PolicyRule {
+        label: "Minimum password age",
+        key: "Minimum password age (days)",
+        passes: |n| n >= 1,
+        requirement: "1 or more days",
+        switch: "/minpwage:1",
+    },
+    PolicyRule {
+        label: "Account lockout threshold",
+        key: "Lockout threshold",
+        passes: |n| (1..=5).contains(&n),
+        requirement: "1-5 attempts",
+        switch: "/lockoutthreshold:5",
+    },
+];
+
+fn password_policy_items(accounts: &std::collections::HashMap<String, String>) -> Vec<CheckItem> {
+    PASSWORD_RULES
+        .iter()
+        .map(|rule| {
+            let (status, detail) = match accounts_number(accounts, rule.key) {
+                Some(n) if (rule.passes)(n) => {
+                    (CheckStatus::Pass, format!("{} (recommended {})", accounts[rule.key], rule.requirement))
+                }
+                Some(_) => (CheckStatus::Fail, format!("{} (recommended {})", accounts[rule.key], rule.requirement)),
+                None => (CheckStatus::Unknown, "Could not read local account policy".to_string()),
+            };
+            CheckItem {
+                name: rule.label.to_string(),
+                status,
+                detail,
+                remediation: Some(Remediation {
+                    summary: format!(
+                        "Set {} to {} with `net accounts {}`. Domain policy overrides this on domain members.",
+                        rule.label.to_lowercase(),
+                        rule.requirement,
+                        rule.switch
+                    ),
+                    script: format!("net accounts {}", rule.switch),
+                }),
+                advice: None,
+            }
+        })
+        .collect()
+}
+
+/// A `net accounts` setting editable from the account policy screen, with the range it accepts.
+struct AccountSetting {
+    label: &'static str,
+    key: &'static str,
+    switch: &'static str,
+    min: u32,
+    max: u32,
+    /// What 0 (or the word `net accounts` prints for it) means, if it is allowed.
+    zero: Option<&'static str>,
+}
+
+const ACCOUNT_SETTINGS: [AccountSetting; 7] = [
+    AccountSetting {
+        label: "Minimum password length",
+        key: "Minimum password length",
+        switch: "/minpwlen",
+        min: 0,
+        max: 14,
+        zero: Some("blank passwords allowed"),
+    },
+    AccountSetting {
+        label: "Maximum password age (days)",
+        key: "Maximum password age (days)",
+        switch: "/maxpwage",
+        min: 1,
+        max: 999,
+        zero: Some("never expires"),
+    },
+    AccountSetting {
+        label: "Minimum password age (days)",
+        key: "Minimum password age (days)",
+        switch: "/minpwage",
+        min: 0,
+        max: 998,
+        zero: Some("can change immediately"),
+    },
+    AccountSetting {
+        label: "Password history",
+        key: "Length of password history maintained",
+        switch: "/uniquepw",
+        min: 0,
+        max: 24,
+        zero: Some("no history"),
+    },
+    AccountSetting {
+        label: "Lockout threshold",
+        key: "Lockout threshold",
+        switch: "/lockoutthreshold",
+        min: 0,
+        max: 999,
+        zero: Some("never lock out"),
+    },
+    AccountSetting {
+        label: "Lockout duration (minutes)",
+        key: "Lockout duration (minutes)",
+        switch: "/lockoutduration",
+        min: 0,
+        max: 99_999,
+        zero: Some("until an administrator unlocks"),
+    },
+    AccountSetting {
+        label: "Lockout observation window (minutes)",
+        key: "Lockout observation window (minutes)",
+        switch: "/lockoutwindow",
+        min: 1,
+        max: 99_999,
+        zero: None,
+    },
+];
+
+/// Local account policy as rows of setting, current value, benchmark recommendation and allowed range.
+pub fn account_policy() -> Result<Vec<Vec<String>>, String> {
+    let output = exec::run("net", &["accounts"]).map_err(|e| format!("Failed to run net accounts: {}", e))?;
+    if !output.success {
+        return Err(format!("Failed to read account policy:\n{}", output.stderr.trim()));
+    }
+    let accounts = net_accounts(&output.stdout);
+    Ok(ACCOUNT_SETTINGS
+        .iter()
+        .map(|setting| {
+            let recommended = PASSWORD_RULES.iter().find(|r| r.key == setting.key).map(|r| r.requirement).unwrap_or("");
+            let allowed = match setting.zero {
+                Some(zero) if setting.min > 0 => format!("{}-{}, 0 = {}", setting.min, setting.max, zero),
+                Some(zero) => format!("0-{} (0 = {})", setting.max, zero),
+                None => format!("{}-{}", setting.min, setting.max),
+            };
+            vec![
+                setting.label.to_string(),
+                accounts.get(setting.key).cloned().unwrap_or_else(|| "?".to_string()),
+                recommended.to_string(),
+                allowed,
+            ]
+        })
+        .collect())
+}
+
+/// A validated account policy change: the `net accounts` argument and a warning worth confirming.
+pub struct AccountPolicyChange {
+    pub argument: String,
+    pub warning: Option<&'static str>,
+}
+
+/// Checks `value` against the range of the setting shown as `label`.
+pub fn account_policy_change(label: &str, value: &str) -> Result<AccountPolicyChange, String> {
+    let setting = ACCOUNT_SETTINGS
+        .iter()
+        .find(|s| s.label == label)
+        .ok_or_else(|| format!("Unknown setting: {}", label))?;
+    let n: u32 = match value.trim().to_lowercase().as_str() {
+        "never" | "none" | "unlimited" if setting.zero.is_some() => 0,
+        v => v.parse().map_err(|_| format!("Enter a number for {}.", setting.label.to_lowercase()))?,
+    };
+    let allowed = (setting.min..=setting.max).contains(&n) || (n == 0 && setting.zero.is_some());
+    if !allowed {
+        return Err(format!("{} must be between {} and {}.", setting.label, setting.min, setting.max));
+    }
+    // net accounts takes "unlimited" rather than 0 for the maximum password age
+    let argument = match (setting.switch, n) {
+        ("/maxpwage", 0) => "/maxpwage:unlimited".to_string(),
+        (switch, n) => format!("{}:{}", switch, n),
+    };
+    let warning = match (setting.switch, n) {
+        ("/minpwlen", 0) => Some("Accounts will be allowed blank passwords."),
+        ("/lockoutthreshold", 0) => Some("Accounts will never lock out, leaving passwords open to guessing."),
+        ("/lockoutthreshold", 1..=2) => Some("A threshold this low locks accounts out after a couple of typos."),
+        ("/lockoutduration", 0) => Some("Locked accounts stay locked until an administrator unlocks them."),
+        _ => None,
+    };
+    Ok(AccountPolicyChange { argument, warning })
+}
+
+/// Applies a change validated by [`account_policy_change`].
+pub fn set_account_policy(label: &str, argument: &str) -> Result<String, String> {
+    match exec::change_program("net", &["accounts", argument]) {
+        Ok(output) if output.success => Ok(format!("{}: applied net accounts {}", label, argument)),
+        Ok(output) => Err(format!(
+            "Failed to set {}: {}",
+            label.to_lowercase(),
+            [output.stderr.trim(), output.stdout.trim()].join(" ").trim()
+        )),
+        Err(e) => Err(format!("Failed to set {}: {}", label.to_lowercase(), e)),
+    }
+}
+
+/// Audit subcategories enabled by the audit policy quick-enable: name, GUID, and whether
+/// success and failure events are wanted.
+const AUDIT_BASELINE: [(&str, &str, bool, bool); 11] = [
+    ("Logon", "{0CCE9215-69AE-11D9-BED3-505054503030}", true, true),
+    ("Logoff", "{0CCE9216-69AE-11D9-BED3-505054503030}", true, false),
+    ("Account Lockout", "{0CCE9217-69AE-11D9-BED3-505054503030}", false, true),
+    ("Special Logon", "{0CCE921B-69AE-11D9-BED3-505054503030}", true, false),
+    ("Credential Validation", "{0CCE923F-69AE-11D9-BED3-505054503030}", true, true),
+    ("User Account Management", "{0CCE9235-69AE-11D9-BED3-505054503030}", true, true),
+    ("Computer Account Management", "{0CCE9236-69AE-11D9-BED3-505054503030}", true, false),
+    ("Security Group Management", "{0CCE9237-69AE-11D9-BED3-505054503030}", true, true),
+    ("File System", "{0CCE921D-69AE-11D9-BED3-505054503030}", false, true),
+    ("File Share", "{0CCE9224-69AE-11D9-BED3-505054503030}", true, true),
+    ("Removable Storage", "{0CCE9245-69AE-11D9-BED3-505054503030}", true, true),
+];
+
+fn audit_setting(success: bool, failure: bool) -> &'static str {
+    match (success, failure) {
+        (true, true) => "Success and Failure",
+        (true, false) => "Success",
+        (false, true) => "Failure",
+        (false, false) => "No Auditing",
+    }
+}
+
+/// Plans enabling the recommended logon, account management and object access audit
+/// subcategories. Settings already audited are kept, so nothing is turned off.
+pub fn audit_policy_plan() -> Result<Plan, String> {
+    let guids: Vec<&str> = AUDIT_BASELINE.iter().map(|(_, guid, _, _)| *guid).collect();
+    let script = format!(
+        "auditpol /get {} /r | Where-Object {{ $_ }} | ConvertFrom-Csv | ForEach-Object {{\n\
+            \"$($_.'Subcategory GUID')=$($_.'Inclusion Setting')\"\n\
+        }}\n\
+        \"Legacy=$((Get-ItemProperty 'HKLM:\\SYSTEM\\CurrentControlSet\\Control\\Lsa' -Name SCENoApplyLegacyAuditPolicy -ErrorAction SilentlyContinue).SCENoApplyLegacyAuditPolicy)\"",
+        exec::quote(&format!("/subcategory:{}", guids.join(",")))
+    );
+    let output = exec::powershell(&script).map_err(|e| format!("Failed to read audit policy: {}", e))?;
+    let values = exec::key_values(&output.stdout);
+    if !values.keys().any(|k| k.starts_with('{')) {
+        return Err(format!("Failed to read audit policy:\n{}", output.stderr.trim()));
+    }
+
+    let mut plan = Plan::new("Enable recommended audit policy");
+    if values.get("Legacy").map(String::as_str) != Some("1") {
+        plan.step(
+            "Let subcategory settings override legacy category settings",
+            "Set-ItemProperty -Path 'HKLM:\\SYSTEM\\CurrentControlSet\\Control\\Lsa' -Name SCENoApplyLegacyAuditPolicy -Value 1 -Type DWord",
+        );
+    }
+    let mut unchanged = Vec::new();
+    for (name, guid, success, failure) in AUDIT_BASELINE {
+        let current = values.get(guid).map(String::as_str).unwrap_or("No Auditing");
+        let proposed = audit_setting(
+            success || current.contains("Success"),
+            failure || current.contains("Failure"),
+        );
+        if proposed == current {
+            unchanged.push(format!("{} ({})", name, current));
+            continue;
+        }
+        plan.step(
+            format!("{}: {} -> {}", name, current, proposed),
+            format!(
+                "auditpol /set /subcategory:\"{}\" /success:{} /failure:{}\n\
+                if ($LASTEXITCODE -ne 0) {{ throw \"auditpol failed with exit code $LASTEXITCODE\" }}",
+                guid,
+                if proposed.contains("Success") { "enable" } else { "disable" },
+                if proposed.contains("Failure") { "enable" } else { "disable" }
+            ),
+        );
+    }
+    if !unchanged.is_empty() {
+        plan.note(format!("Already audited: {}", unchanged.join(", ")));
+    }
+    plan.note("File System auditing only records folders and files that have an auditing entry (SACL).");
+    plan.note("Advanced Audit Policy from Group Policy overrides these settings on domain members.");
+    Ok(plan)
+}
@@ -0,0 +1,115 @@
+//! `server-helper install-self` and `uninstall-self`: puts the running binary in Program Files
+//! on the machine PATH, with a Start Menu shortcut and an entry under Apps & Features, so rolling
+//! it out to many servers is one command instead of a manual copy.
+
+use std::path::Path;
+
+use crate::{exec, VERSION};
+
+/// Copies `$source` to Program Files and registers it; prints the installed path.
+const INSTALL_SCRIPT: &str = r#"
+$dir = Join-Path $env:ProgramFiles 'Server Helper'
+$exe = Join-Path $dir 'server-helper.exe'
+New-Item -ItemType Directory -Path $dir -Force | Out-Null
+if ($source -ne $exe) { Copy-Item -LiteralPath $source -Destination $exe -Force -ErrorAction Stop }
+
+$path = [Environment]::GetEnvironmentVariable('Path', 'Machine')
+if (($path -split ';') -notcontains $dir) {
+    [Environment]::SetEnvironmentVariable('Path', ($path.TrimEnd(';') + ';' + $dir), 'Machine')
+}
+
+$shortcut = (New-Object -ComObject WScript.Shell).CreateShortcut("$env:ProgramData\Microsoft\Windows\Start Menu\Programs\Server Helper.lnk")
+$shortcut.TargetPath = $exe
+$shortcut.WorkingDirectory = $dir
+$shortcut.Description = 'Windows Server setup and maintenance'
+$shortcut.Save()
+
+$key = 'HKLM:\SOFTWARE\Microsoft\Windows\CurrentVersion\Uninstall\ServerHelper'
+New-Item -Path $key -Force | Out-Null
+$entry = @{
+    DisplayName = 'Server Helper'
+    DisplayVersion = $version
+    Publisher = 'Server Helper'
+    InstallLocation = $dir
+    DisplayIcon = $exe
+    UninstallString = "`"$exe`" uninstall-self"
+    QuietUninstallString = "`"$exe`" uninstall-self"
+}
+foreach ($name in $entry.Keys) { Set-ItemProperty -Path $key -Name $name -Value $entry[$name] }
+Set-ItemProperty -Path $key -Name NoModify -Value 1 -Type DWord
+Set-ItemProperty -Path $key -Name NoRepair -Value 1 -Type DWord
+Set-ItemProperty -Path $key -Name EstimatedSize -Value ([int]((Get-Item $exe).Length / 1KB)) -Type DWord
+$exe
+"#;
+
+/// Undoes `INSTALL_SCRIPT`. The running binary can't delete itself, so the folder is removed
+/// by a short-lived cmd.exe once this process has exited.
+const UNINSTALL_SCRIPT: &str = r#"
+$dir = Join-Path $env:ProgramFiles 'Server Helper'
+$path = [Environment]::GetEnvironmentVariable('Path', 'Machine')
+$kept = ($path -split ';') | Where-Object { $_ -and $_ -ne $dir }
+[Environment]::SetEnvironmentVariable('Path', ($kept -join ';'), 'Machine')
+Remove-Item "$env:ProgramData\Microsoft\Windows\Start Menu\Programs\Server Helper.lnk" -Force -ErrorAction SilentlyContinue
+Remove-Item 'HKLM:\SOFTWARE\Microsoft\Windows\CurrentVersion\Uninstall\ServerHelper' -Recurse -Force -ErrorAction SilentlyContinue
+if (Test-Path $dir) {
+    Start-Process cmd.exe -ArgumentList "/c timeout /t 3 /nobreak >nul & rmdir /s /q `"$dir`"" -WindowStyle Hidden
+}
+"#;
+
+/// Installs the running binary for all users and returns what was done.
+pub fn install(source: &Path) -> Result<String, String> {
+    let script = format!(
+        "$source = {}\n$version = {}\n{}",
+        exec::quote(&source.to_string_lossy()),
+        exec::quote(VERSION),
+        INSTALL_SCRIPT
+    );
+    let output = exec::powershell(&script).map_err(|e| format!("Failed to install Server Helper: {}", e))?;
+    if !output.success {
+        return Err(format!(
+            "Failed to install Server Helper (are you running as Administrator?):\n{}",
+            output.stderr.trim()
+        ));
+    }
+    let installed = output.stdout.lines().last().unwrap_or_default().trim().to_string();
+    Ok(format!(
+        "Installed Server Helper v{} to {}\n\
+         Added its folder to the machine PATH (new consoles pick it up).\n\
+         Added a Start Menu shortcut and an entry under Apps & Features.",
+        VERSION, installed
+    ))
+}
+
+/// Removes what `install` added.
+pub fn uninstall() -> Result<String, String> {
+    let output = exec::powershell(UNINSTALL_SCRIPT).map_err(|e| format!("Failed to uninstall Server Helper: {}", e))?;
+    if !output.success {
+        return Err(format!(
+            "Failed to uninstall Server Helper (are you running as Administrator?):\n{}",
+            output.stderr.trim()
+        ));
+    }
+    Ok("Removed Server Helper from the PATH, the Start Menu and Apps & Features.\n\
+        Its folder in Program Files is deleted a few seconds after this exits."
+        .to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::FakeRunner;
+
+    #[test]
+    fn install_registers_the_copied_binary() {
+        let runner = FakeRunner::new()
+            .on("Uninstall\\ServerHelper", "C:\\Program Files\\Server Helper\\server-helper.exe\n")
+            .install();
+
+        let report = install(Path::new("D:\\Tools\\server-helper.exe")).unwrap_or_else(|e| e);
+
+        assert!(report.contains("to C:\\Program Files\\Server Helper\\server-helper.exe"), "{}", report);
+        let script = &runner.calls()[0];
+        assert!(script.starts_with("$source = 'D:\\Tools\\server-helper.exe'\n$version = '"));
+        assert!(script.contains("SetEnvironmentVariable('Path'"));
+    }
+}
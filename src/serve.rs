@@ -0,0 +1,475 @@
+//! `server-helper serve`: a small HTTP dashboard with the host status, the newest backups and
+//! the task history, so a NOC can check a server over the NetBird network without an
+//! interactive session. Orchestration can also POST to trigger a role backup or a status check
+//! and get the result as JSON, without WinRM. Every request needs the token stored in the
+//! config directory. `/metrics` exports gauges for Prometheus, so monitoring can alert when a
+//! server hasn't been backed up recently.
+
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    path::PathBuf,
+    sync::Mutex,
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+
+use crate::{backup_dir, config_dir, dates, exec, history, outcome::OperationOutcome, status, App, VERSION};
+
+pub const DEFAULT_PORT: u16 = 8099;
+
+/// How many backups and history entries the dashboard lists.
+const RECENT: usize = 10;
+
+/// Held while a triggered operation runs, so two callers can't start a backup at once.
+static TRIGGER: Mutex<()> = Mutex::new(());
+
+/// 24 random bytes from the system's RNG as hex.
+const TOKEN_SCRIPT: &str = "$b = [byte[]]::new(24); [Security.Cryptography.RandomNumberGenerator]::Create().GetBytes($b); \
+    [BitConverter]::ToString($b) -replace '-', ''";
+
+pub fn token_path() -> PathBuf {
+    config_dir().join("dashboard-token")
+}
+
+/// The dashboard token, created on first use and kept for later runs.
+fn load_or_create_token() -> Result<String> {
+    let path = token_path();
+    if let Ok(token) = std::fs::read_to_string(&path) {
+        if !token.trim().is_empty() {
+            return Ok(token.trim().to_string());
+        }
+    }
+    let output = exec::powershell(TOKEN_SCRIPT).context("Failed to generate a dashboard token")?;
+    let token = output.stdout.trim().to_lowercase();
+    anyhow::ensure!(output.success && token.len() == 48, "Failed to generate a dashboard token: {}", output.stderr.trim());
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(&path, &token).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(token)
+}
+
+/// The port from `--port 8099` or `--port=8099`.
+pub fn port(args: &[String]) -> Result<u16> {
+    let value = args.iter().enumerate().find_map(|(i, a)| match a.strip_prefix("--port") {
+        Some("") => Some(args.get(i + 1).cloned().unwrap_or_default()),
+        Some(rest) => rest.strip_prefix('=').map(String::from),
+        None => None,
+    });
+    match value {
+        Some(value) => value.parse().with_context(|| format!("Invalid port '{}'", value)),
+        None => Ok(DEFAULT_PORT),
+    }
+}
+
+/// Serves the dashboard until the process is stopped.
+pub fn run(port: u16) -> Result<()> {
+    let token = load_or_create_token()?;
+    let listener = TcpListener::bind(("0.0.0.0", port)).with_context(|| format!("Failed to listen on port {}", port))?;
+    println!("Server Helper v{} dashboard on http://{}:{}/", VERSION, hostname(), port);
+    println!("Token: {} (stored in {})", token, token_path().display());
+    println!("Open http://<server>:{}/?token=<token>, or send 'Authorization: Bearer <token>'. Ctrl+C stops it.", port);
+    println!("POST /api/backup or /api/check to trigger a role backup or a status check.");
+    for stream in listener.incoming().flatten() {
+        let token = token.clone();
+        std::thread::spawn(move || {
+            let _ = serve_connection(stream, &token);
+        });
+    }
+    Ok(())
+}
+
+fn hostname() -> String {
+    std::env::var("COMPUTERNAME").unwrap_or_else(|_| "localhost".to_string())
+}
+
+fn serve_connection(mut stream: TcpStream, token: &str) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut head = Vec::new();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim_end().is_empty() {
+            break;
+        }
+        head.push(line.trim_end().to_string());
+    }
+    // Triggers take no body, but read whatever was sent so the client sees the response
+    let length = head.iter().find_map(|h| {
+        let (name, value) = h.split_once(':')?;
+        name.trim().eq_ignore_ascii_case("content-length").then(|| value.trim().parse::<u64>().ok())?
+    });
+    if let Some(length) = length {
+        std::io::copy(&mut reader.by_ref().take(length.min(64 * 1024)), &mut std::io::sink())?;
+    }
+    let peer = stream.peer_addr().map(|a| a.ip().to_string()).unwrap_or_default();
+    let response = respond(&head, token, &peer);
+    stream.write_all(&response.to_bytes())?;
+    stream.flush()
+}
+
+#[derive(Debug, PartialEq)]
+struct Response {
+    status: &'static str,
+    content_type: &'static str,
+    body: String,
+}
+
+impl Response {
+    fn new(status: &'static str, content_type: &'static str, body: impl Into<String>) -> Self {
+        Response { status, content_type, body: body.into() }
+    }
+
+    fn text(status: &'static str, body: &str) -> Self {
+        Response::new(status, "text/plain; charset=utf-8", format!("{}\n", body))
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        format!(
+            "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n{}",
+            self.status,
+            self.content_type,
+            self.body.len(),
+            self.body
+        )
+        .into_bytes()
+    }
+}
+
+/// Answers a request from its request line and headers; `peer` is the caller's address.
+fn respond(head: &[String], token: &str, peer: &str) -> Response {
+    let mut request_line = head.first().map(String::as_str).unwrap_or_default().split_whitespace();
+    let (method, target) = (request_line.next().unwrap_or_default(), request_line.next().unwrap_or_default());
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let query_token = query.split('&').find_map(|pair| pair.strip_prefix("token="));
+    let header_token = head.iter().skip(1).find_map(|h| {
+        let (name, value) = h.split_once(':')?;
+        name.trim().eq_ignore_ascii_case("authorization").then(|| value.trim().strip_prefix("Bearer "))?
+    });
+    if !query_token.or(header_token).is_some_and(|given| same_token(given.trim(), token)) {
+        return Response::text("401 Unauthorized", "A valid token is required.");
+    }
+    match (method, path) {
+        ("GET", "/") => Response::new("200 OK", "text/html; charset=utf-8", html(&Snapshot::read(), query_token)),
+        ("GET", "/api/status") => Response::new("200 OK", "application/json", json(&Snapshot::read())),
+        ("GET", "/metrics") => Response::new("200 OK", "text/plain; version=0.0.4; charset=utf-8", metrics(&Snapshot::read())),
+        ("POST", "/api/backup") => trigger("backup", peer, App::backup_server_roles),
+        ("POST", "/api/check") => trigger("check", peer, check),
+        (_, "/" | "/api/status" | "/metrics") => Response::text("405 Method Not Allowed", "Use GET."),
+        (_, "/api/backup" | "/api/check") => Response::text("405 Method Not Allowed", "Use POST."),
+        _ => Response::text("404 Not Found", "Not found."),
+    }
+}
+
+/// Runs a triggered operation unless another one is running, records it in the history and
+/// returns its outcome as JSON.
+fn trigger(action: &str, peer: &str, operation: fn() -> OperationOutcome) -> Response {
+    let Ok(_running) = TRIGGER.try_lock() else {
+        return Response::text("409 Conflict", "Another triggered operation is still running.");
+    };
+    let outcome = operation();
+    let succeeded = matches!(outcome, OperationOutcome::Success(_) | OperationOutcome::SuccessNeedsReboot(_));
+    history::record(&format!(
+        "Remote {} from {}: {}\n{}",
+        action,
+        peer,
+        if succeeded { "succeeded" } else { "failed" },
+        outcome.message()
+    ));
+    let body = format!(
+        "{{\"action\":\"{}\",\"success\":{},\"reboot_required\":{},\"title\":\"{}\",\"message\":\"{}\"}}\n",
+        action,
+        succeeded,
+        matches!(outcome, OperationOutcome::SuccessNeedsReboot(_)),
+        escape_json(outcome.title().trim()),
+        escape_json(&outcome.message())
+    );
+    let status = if succeeded { "200 OK" } else { "500 Internal Server Error" };
+    Response::new(status, "application/json", body)
+}
+
+/// A fresh host status reading, as an outcome listing the badges.
+fn check() -> OperationOutcome {
+    match status::collect() {
+        Ok(status) => {
+            let lines: Vec<String> = badges(&status)
+                .iter()
+                .map(|b| format!("{}: {}", level_name(b.level), b.text))
+                .collect();
+            OperationOutcome::Success(lines.join("\n"))
+        }
+        Err(e) => OperationOutcome::failed(e),
+    }
+}
+
+/// Compares every byte so the time taken doesn't tell how much of a guess was right.
+fn same_token(given: &str, token: &str) -> bool {
+    given.len() == token.len() && given.bytes().zip(token.bytes()).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+struct Backup {
+    /// When it was taken, from the Unix seconds in its name.
+    secs: i64,
+    name: String,
+    taken: String,
+    size_kb: u64,
+}
+
+/// What the dashboard shows, read fresh for each request.
+struct Snapshot {
+    host: String,
+    status: Result<status::HostStatus, String>,
+    backups: Vec<Backup>,
+    history: Vec<String>,
+}
+
+impl Snapshot {
+    fn read() -> Self {
+        Snapshot { host: hostname(), status: status::collect(), backups: recent_backups(), history: recent_history() }
+    }
+
+    fn badges(&self) -> Vec<status::Badge> {
+        self.status.as_ref().map(badges).unwrap_or_default()
+    }
+}
+
+fn badges(status: &status::HostStatus) -> Vec<status::Badge> {
+    [Some(status.winget_badge()), Some(status.netbird_badge()), Some(status.reboot_badge()), status.disk_badge()]
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+/// The newest backups in the backup folder, by the time in their names.
+fn recent_backups() -> Vec<Backup> {
+    let mut backups: Vec<Backup> = std::fs::read_dir(backup_dir())
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|entry| {
+                    let name = entry.file_name().to_string_lossy().to_string();
+                    let secs = dates::from_file_name(&name)?;
+                    let size_kb = entry.metadata().map(|m| m.len().div_ceil(1024)).unwrap_or(0);
+                    Some(Backup { secs, name, taken: dates::local(secs), size_kb })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    backups.sort_by_key(|backup| std::cmp::Reverse(backup.secs));
+    backups.truncate(RECENT);
+    backups
+}
+
+/// The newest history entries, newest first.
+fn recent_history() -> Vec<String> {
+    let log = history::read().unwrap_or_else(|e| e);
+    let entries: Vec<String> = log
+        .replace("\r\n", "\n")
+        .split("\n\n")
+        .map(|e| e.trim().to_string())
+        .filter(|e| !e.is_empty())
+        .collect();
+    entries.into_iter().rev().take(RECENT).collect()
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn escape_json(text: &str) -> String {
+    let mut out = String::new();
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn level_name(level: status::Level) -> &'static str {
+    match level {
+        status::Level::Good => "good",
+        status::Level::Warning => "warning",
+        status::Level::Bad => "bad",
+    }
+}
+
+fn html(snapshot: &Snapshot, token: Option<&str>) -> String {
+    let refresh = token.map(|t| format!("<meta http-equiv=\"refresh\" content=\"60; url=/?token={}\">", escape_html(t))).unwrap_or_default();
+    let mut page = format!(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\">{}<title>{} - Server Helper</title>\n<style>\
+         body{{font-family:Segoe UI,sans-serif;margin:2em;background:#111;color:#ddd}} h1{{color:#4cc}} \
+         .good{{color:#4c4}} .warning{{color:#cc4}} .bad{{color:#c44}} td{{padding:0 1em 0 0}} \
+         pre{{background:#222;padding:.5em;white-space:pre-wrap}}</style></head><body>\n<h1>{}</h1>\n<h2>Status</h2>\n",
+        refresh,
+        escape_html(&snapshot.host),
+        escape_html(&snapshot.host)
+    );
+    match &snapshot.status {
+        Ok(_) => {
+            page.push_str("<ul>\n");
+            for badge in snapshot.badges() {
+                page.push_str(&format!("<li class=\"{}\">{}</li>\n", level_name(badge.level), escape_html(&badge.text)));
+            }
+            page.push_str("</ul>\n");
+        }
+        Err(e) => page.push_str(&format!("<p class=\"bad\">{}</p>\n", escape_html(e))),
+    }
+    page.push_str("<h2>Last backups</h2>\n");
+    if snapshot.backups.is_empty() {
+        page.push_str("<p>No backups yet.</p>\n");
+    } else {
+        page.push_str("<table>\n");
+        for backup in &snapshot.backups {
+            page.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td>{} KB</td></tr>\n",
+                escape_html(&backup.taken),
+                escape_html(&backup.name),
+                backup.size_kb
+            ));
+        }
+        page.push_str("</table>\n");
+    }
+    page.push_str("<h2>History</h2>\n");
+    for entry in &snapshot.history {
+        page.push_str(&format!("<pre>{}</pre>\n", escape_html(entry)));
+    }
+    page.push_str(&format!("<p><small>Server Helper v{}</small></p>\n</body></html>\n", VERSION));
+    page
+}
+
+fn json(snapshot: &Snapshot) -> String {
+    let badges: Vec<String> = snapshot
+        .badges()
+        .iter()
+        .map(|b| format!("{{\"text\":\"{}\",\"level\":\"{}\"}}", escape_json(&b.text), level_name(b.level)))
+        .collect();
+    let backups: Vec<String> = snapshot
+        .backups
+        .iter()
+        .map(|b| format!("{{\"name\":\"{}\",\"taken\":\"{}\",\"size_kb\":{}}}", escape_json(&b.name), escape_json(&b.taken), b.size_kb))
+        .collect();
+    let history: Vec<String> = snapshot.history.iter().map(|e| format!("\"{}\"", escape_json(e))).collect();
+    let error = match &snapshot.status {
+        Ok(_) => "null".to_string(),
+        Err(e) => format!("\"{}\"", escape_json(e)),
+    };
+    format!(
+        "{{\"host\":\"{}\",\"version\":\"{}\",\"status\":[{}],\"status_error\":{},\"backups\":[{}],\"history\":[{}]}}\n",
+        escape_json(&snapshot.host),
+        VERSION,
+        badges.join(","),
+        error,
+        backups.join(","),
+        history.join(",")
+    )
+}
+
+/// Prometheus text format. Status gauges are left out when the status couldn't be read, and
+/// `server_helper_status_up` says so.
+fn metrics(snapshot: &Snapshot) -> String {
+    let mut out = String::new();
+    let mut gauge = |name: &str, help: &str, labels: &str, value: String| {
+        out.push_str(&format!(
+            "# HELP server_helper_{0} {1}\n# TYPE server_helper_{0} gauge\nserver_helper_{0}{2} {3}\n",
+            name, help, labels, value
+        ));
+    };
+    let flag = |on: bool| u8::from(on).to_string();
+    gauge("info", "Server Helper version.", &format!("{{version=\"{}\"}}", VERSION), "1".to_string());
+    if let Some(newest) = snapshot.backups.first() {
+        gauge("last_backup_timestamp_seconds", "Unix time of the newest backup in the backup folder.", "", newest.secs.to_string());
+    }
+    gauge("status_up", "Whether the host status could be read.", "", flag(snapshot.status.is_ok()));
+    if let Ok(status) = &snapshot.status {
+        gauge("pending_reboot", "Whether Windows is waiting for a restart.", "", flag(status.reboot_pending));
+        gauge("winget_installed", "Whether winget is installed.", "", flag(status.winget.is_some()));
+        gauge("netbird_installed", "Whether NetBird is installed.", "", flag(status.netbird.is_some()));
+        gauge("netbird_connected", "Whether NetBird is connected to its management server.", "", flag(status.netbird_connected));
+        if let Some(bytes) = status.free_bytes {
+            let labels = format!("{{drive=\"{}\"}}", status.drive.replace(['\\', '"'], ""));
+            gauge("free_disk_bytes", "Free space on the system drive.", &labels, bytes.to_string());
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::FakeRunner;
+
+    const PEER: &str = "100.64.0.7";
+
+    fn head(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|l| l.to_string()).collect()
+    }
+
+    #[test]
+    fn requests_need_the_token_and_only_read() {
+        FakeRunner::new().on("RebootPending", "Winget=1.7.10861\nRebootPending=True\n").install();
+
+        assert_eq!(respond(&head(&["GET / HTTP/1.1"]), "secret", PEER).status, "401 Unauthorized");
+        assert_eq!(respond(&head(&["GET /?token=guess HTTP/1.1"]), "secret", PEER).status, "401 Unauthorized");
+        assert_eq!(respond(&head(&["POST /?token=secret HTTP/1.1"]), "secret", PEER).status, "405 Method Not Allowed");
+        assert_eq!(respond(&head(&["GET /admin?token=secret HTTP/1.1"]), "secret", PEER).status, "404 Not Found");
+
+        let page = respond(&head(&["GET /?token=secret HTTP/1.1"]), "secret", PEER);
+        assert_eq!(page.status, "200 OK");
+        assert!(page.body.contains("<li class=\"warning\">reboot pending</li>"));
+
+        let api = respond(&head(&["GET /api/status HTTP/1.1", "Authorization: Bearer secret"]), "secret", PEER);
+        assert_eq!(api.content_type, "application/json");
+        assert!(api.body.contains("{\"text\":\"winget 1.7.10861\",\"level\":\"good\"}"));
+    }
+
+    #[test]
+    fn triggers_return_the_outcome_and_are_recorded() {
+        FakeRunner::new().on("RebootPending", "RebootPending=True\n").fail("Export-Clixml", "Access is denied.").install();
+        let post = |path: &str| respond(&head(&[&format!("POST {} HTTP/1.1", path), "Authorization: Bearer secret"]), "secret", PEER);
+
+        assert_eq!(post("/api/check?x=1").status, "200 OK");
+        assert!(post("/api/check").body.contains("\"message\":\"bad: winget missing\\nbad: not installed\\nwarning: reboot pending\""));
+        assert_eq!(respond(&head(&["GET /api/backup?token=secret HTTP/1.1"]), "secret", PEER).status, "405 Method Not Allowed");
+
+        let backup = post("/api/backup");
+        assert_eq!(backup.status, "500 Internal Server Error");
+        assert!(backup.body.starts_with("{\"action\":\"backup\",\"success\":false,"));
+        assert!(history::read().unwrap_or_default().contains("Remote backup from 100.64.0.7: failed"));
+    }
+
+    #[test]
+    fn exports_metrics() {
+        FakeRunner::new()
+            .on("RebootPending", "NetBird=0.28.4\nNetBirdConnected=True\nRebootPending=False\nDrive=C:\nFreeBytes=45634027520\n")
+            .install();
+        std::fs::create_dir_all(backup_dir()).expect("create backup dir");
+        std::fs::write(backup_dir().join("ServerRoles_1709288100.xml"), "<Objs />").expect("write backup");
+
+        let metrics = respond(&head(&["GET /metrics HTTP/1.1", "Authorization: Bearer secret"]), "secret", PEER).body;
+        assert!(metrics.contains("\nserver_helper_pending_reboot 0\n"));
+        assert!(metrics.contains("\nserver_helper_netbird_connected 1\n"));
+        assert!(metrics.contains("\nserver_helper_winget_installed 0\n"));
+        assert!(metrics.contains("\nserver_helper_free_disk_bytes{drive=\"C:\"} 45634027520\n"));
+        // Other tests may add newer backups, so only check the gauge is there
+        assert!(metrics.contains("\n# TYPE server_helper_last_backup_timestamp_seconds gauge\nserver_helper_last_backup_timestamp_seconds 1"));
+    }
+
+    #[test]
+    fn reads_the_port() {
+        let args = |list: &[&str]| list.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(port(&args(&["serve"])).ok(), Some(DEFAULT_PORT));
+        assert_eq!(port(&args(&["serve", "--port", "9000"])).ok(), Some(9000));
+        assert_eq!(port(&args(&["serve", "--port=9001"])).ok(), Some(9001));
+        assert!(port(&args(&["serve", "--port", "http"])).is_err());
+    }
+}
@@ -0,0 +1,133 @@
+use crate::exec;
+
+/// Parses `qwinsta` into rows of ID, session name, user, state, type and whether it is the
+/// session running this TUI. The services session and listeners are left out: neither can be
+/// messaged, disconnected or logged off.
+fn parse_qwinsta(stdout: &str) -> Vec<Vec<String>> {
+    let mut lines = stdout.lines();
+    let Some(header) = lines.next() else { return Vec::new() };
+    let user_column = header.find("USERNAME").unwrap_or(19);
+    lines
+        .filter_map(|line| {
+            let current = line.starts_with('>');
+            let line = line.get(1..).unwrap_or_default();
+            // The ID is the first number; the session name and user are before it, the
+            // state and type after it
+            let mut offset = 0;
+            let (id_start, id) = line.split_whitespace().find_map(|word| {
+                let start = offset + line[offset..].find(word)?;
+                offset = start + word.len();
+                word.chars().all(|c| c.is_ascii_digit()).then_some((start, word))
+            })?;
+            let split = user_column.saturating_sub(1).min(id_start);
+            let (name, user) = (line[..split].trim(), line[split..id_start].trim());
+            let mut rest = line[id_start + id.len()..].split_whitespace();
+            let state = rest.next().unwrap_or_default();
+            let kind = rest.next().unwrap_or_default();
+            if id == "0" || state.eq_ignore_ascii_case("Listen") {
+                return None;
+            }
+            Some(vec![
+                id.to_string(),
+                name.to_string(),
+                user.to_string(),
+                state.to_string(),
+                kind.to_string(),
+                if current { "Yes" } else { "" }.to_string(),
+            ])
+        })
+        .collect()
+}
+
+/// Console and RDP sessions on this server, from `qwinsta`.
+pub fn sessions() -> Result<Vec<Vec<String>>, String> {
+    let output = exec::run("qwinsta", &[]).map_err(|e| format!("Failed to run qwinsta: {}", e))?;
+    // qwinsta exits 1 when it printed sessions but could not query one of them
+    if output.stdout.trim().is_empty() {
+        return Err(format!("Failed to list sessions:\n{}", output.stderr.trim()));
+    }
+    Ok(parse_qwinsta(&output.stdout))
+}
+
+fn session_id(id: &str) -> Result<u32, String> {
+    id.parse().map_err(|_| format!("Invalid session ID: {}", id))
+}
+
+/// Shows `text` in a message box in session `id`.
+pub fn message(id: &str, text: &str) -> Result<String, String> {
+    let id = session_id(id)?.to_string();
+    if text.trim().is_empty() {
+        return Err("Enter the message to send.".to_string());
+    }
+    match exec::run("msg", &[&id, "/time:300", text]) {
+        Ok(output) if output.success => Ok(format!("Message sent to session {}.", id)),
+        Ok(output) => Err(format!("Failed to message session {}: {}", id, output.stderr.trim())),
+        Err(e) => Err(format!("Failed to run msg: {}", e)),
+    }
+}
+
+/// Disconnects session `id`; its programs keep running until the user reconnects.
+pub fn disconnect(id: &str) -> Result<String, String> {
+    let id = session_id(id)?.to_string();
+    match exec::change_program("tsdiscon", &[&id]) {
+        Ok(output) if output.success => Ok(format!("Disconnected session {}.", id)),
+        Ok(output) => Err(format!("Failed to disconnect session {}: {}", id, output.stderr.trim())),
+        Err(e) => Err(format!("Failed to run tsdiscon: {}", e)),
+    }
+}
+
+/// Logs off session `id`, closing its programs.
+pub fn log_off(id: &str) -> Result<String, String> {
+    let id = session_id(id)?.to_string();
+    match exec::change_program("logoff", &[&id]) {
+        Ok(output) if output.success => Ok(format!("Logged off session {}.", id)),
+        Ok(output) => Err(format!("Failed to log off session {}: {}", id, output.stderr.trim())),
+        Err(e) => Err(format!("Failed to run logoff: {}", e)),
+    }
+}
+
+/// Opens a Remote Desktop shadow of session `id` with control, prompting its user for consent.
+pub fn shadow(id: &str) -> Result<String, String> {
+    let id = session_id(id)?;
+    std::process::Command::new("mstsc.exe")
+        .args([format!("/shadow:{}", id).as_str(), "/control"])
+        .spawn()
+        .map(|_| format!("Opened a shadow of session {}. Its user is asked to allow it.", id))
+        .map_err(|e| format!("Failed to start mstsc: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::FakeRunner;
+
+    const QWINSTA: &[&str] = &[
+        " SESSIONNAME       USERNAME                 ID  STATE   TYPE        DEVICE",
+        " services                                    0  Disc",
+        ">console           Administrator             1  Active",
+        " rdp-tcp#3         jdoe                      2  Active",
+        "                   asmith                    4  Disc",
+        " rdp-tcp                                 65536  Listen",
+    ];
+
+    #[test]
+    fn lists_user_sessions_and_marks_the_current_one() {
+        FakeRunner::new().on("qwinsta", &QWINSTA.join("\n")).install();
+
+        let rows = sessions().unwrap_or_default();
+
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0], ["1", "console", "Administrator", "Active", "", "Yes"]);
+        assert_eq!(rows[1][..4], ["2", "rdp-tcp#3", "jdoe", "Active"]);
+        assert_eq!(rows[2][..4], ["4", "", "asmith", "Disc"]);
+    }
+
+    #[test]
+    fn logs_off_by_session_id() {
+        let runner = FakeRunner::new().on("logoff", "").install();
+
+        assert!(log_off("2; shutdown").is_err());
+        assert_eq!(log_off("2"), Ok("Logged off session 2.".to_string()));
+        assert!(runner.ran("logoff 2"));
+    }
+}
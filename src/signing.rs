@@ -0,0 +1,272 @@
+//! Signed backup manifests. With `sign_key` in the profile settings (a minisign secret key
+//! without a password, for the machine or the user), each role backup gets
+//! `ServerRoles_<time>.manifest` with the SHA-256 of the backup, its readable list, its source
+//! record and, for a differential backup, its base, signed into `.manifest.minisig` with the
+//! minisign client. With `verify_key` set, the restore preview and the restore plan check the
+//! signature and the hashes, so a backup changed on shared storage is not restored unnoticed.
+
+use std::path::{Path, PathBuf};
+
+use crate::{delta, exec, profiles, rename};
+
+const NOT_INSTALLED: &str = "The minisign client is not installed (winget install jedisct1.minisign)";
+
+/// The trusted comment of a signature, followed by the backup's file name.
+const COMMENT_PREFIX: &str = "Server Helper backup ";
+
+/// What checking a backup's signature found.
+#[derive(Debug, PartialEq)]
+pub enum Verification {
+    Unsigned,
+    /// Signed, but no `verify_key` is set to check it with.
+    Unchecked,
+    /// The signature matches and so do the files; with the signature's trusted comment.
+    Valid(String),
+    Invalid(String),
+}
+
+impl Verification {
+    pub fn describe(&self) -> String {
+        match self {
+            Verification::Unsigned => "Not signed".to_string(),
+            Verification::Unchecked => "Signed, not checked: set verify_key in the profile settings".to_string(),
+            Verification::Valid(comment) if comment.is_empty() => "Signature verified".to_string(),
+            Verification::Valid(comment) => format!("Signature verified ({})", comment),
+            Verification::Invalid(reason) => format!("SIGNATURE CHECK FAILED: {}", reason),
+        }
+    }
+}
+
+fn manifest_path(backup: &Path) -> PathBuf {
+    backup.with_extension("manifest")
+}
+
+fn signature_path(backup: &Path) -> PathBuf {
+    backup.with_extension("manifest.minisig")
+}
+
+/// The manifest and signature of `backup`, to copy along with it.
+pub fn files(backup: &Path) -> [PathBuf; 2] {
+    [manifest_path(backup), signature_path(backup)]
+}
+
+/// SHA-256 and file name of each of `files`, as `Get-FileHash` prints them.
+fn hashes(files: &[PathBuf]) -> Result<Vec<(String, String)>, String> {
+    let paths: Vec<String> = files.iter().map(|f| exec::quote(&f.to_string_lossy())).collect();
+    let output = exec::powershell(&format!(
+        "Get-FileHash -Algorithm SHA256 -LiteralPath {} -ErrorAction Stop | \
+        ForEach-Object {{ \"$($_.Hash)`t$(Split-Path -Leaf $_.Path)\" }}",
+        paths.join(",")
+    ))
+    .map_err(|e| format!("Failed to hash the backup files: {}", e))?;
+    if !output.success {
+        return Err(format!("Failed to hash the backup files:\n{}", output.stderr.trim()));
+    }
+    Ok(exec::tab_rows(&output.stdout)
+        .into_iter()
+        .filter(|row| row.len() == 2 && row[0].len() == 64)
+        .map(|row| (row[0].clone(), row[1].clone()))
+        .collect())
+}
+
+/// `<SHA-256>  <file name>` lines, as `sha256sum` writes them.
+fn render_manifest(hashes: &[(String, String)]) -> String {
+    hashes.iter().map(|(hash, name)| format!("{}  {}\r\n", hash.to_lowercase(), name)).collect()
+}
+
+fn parse_manifest(text: &str) -> Vec<(String, String)> {
+    text.lines()
+        .filter_map(|line| line.trim_end().split_once("  "))
+        .map(|(hash, name)| (hash.to_lowercase(), name.to_string()))
+        .collect()
+}
+
+fn run_minisign(args: &[&str]) -> Result<exec::CommandOutput, String> {
+    exec::run("minisign", args).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => NOT_INSTALLED.to_string(),
+        _ => format!("Failed to run minisign: {}", e),
+    })
+}
+
+/// Writes and signs the manifest of `backup` when a `sign_key` is set. `base` is the full
+/// backup a differential one was stored against. Returns a line for the backup report.
+pub fn sign(backup: &Path, base: Option<&Path>) -> Option<Result<String, String>> {
+    let key = profiles::settings().sign_key?;
+    Some(sign_with(&key, backup, base))
+}
+
+fn sign_with(key: &Path, backup: &Path, base: Option<&Path>) -> Result<String, String> {
+    let name = backup.file_name().unwrap_or_default().to_string_lossy().to_string();
+    let list = backup.with_file_name(name.replace("ServerRoles_", "InstalledFeatures_")).with_extension("txt");
+    let files: Vec<PathBuf> = [Some(backup.to_path_buf()), Some(list), Some(rename::source_path(backup)), base.map(Path::to_path_buf)]
+        .into_iter()
+        .flatten()
+        .filter(|f| f.exists())
+        .collect();
+    let manifest = manifest_path(backup);
+    std::fs::write(&manifest, render_manifest(&hashes(&files)?))
+        .map_err(|e| format!("Failed to write {}: {}", manifest.display(), e))?;
+    let (manifest_arg, signature_arg) = (manifest.to_string_lossy().to_string(), signature_path(backup).to_string_lossy().to_string());
+    let comment = format!("{}{}", COMMENT_PREFIX, name);
+    let key_arg = key.to_string_lossy().to_string();
+    let output = run_minisign(&["-S", "-s", &key_arg, "-m", &manifest_arg, "-x", &signature_arg, "-t", &comment])?;
+    if !output.success {
+        return Err(format!("Failed to sign {}:\n{}", manifest.display(), output.stderr.trim()));
+    }
+    Ok(format!("Signed {} file(s) with {}:\n  {}", files.len(), key.display(), signature_arg))
+}
+
+/// Why `verification` holds a restore back: a failed check, or no signature while the active
+/// profile sets a `verify_key` and so expects every backup to be signed.
+pub fn integrity_problem(verification: &Verification) -> Option<String> {
+    problem_with(verification, profiles::settings().verify_key.is_some())
+}
+
+fn problem_with(verification: &Verification, required: bool) -> Option<String> {
+    match verification {
+        Verification::Invalid(reason) => Some(reason.clone()),
+        Verification::Unsigned if required => {
+            Some("the backup is not signed, but verify_key in the profile settings requires a signature".to_string())
+        }
+        _ => None,
+    }
+}
+
+/// Checks the signature of `backup` with the `verify_key` of the active profile.
+pub fn verify(backup: &Path) -> Verification {
+    verify_with(profiles::settings().verify_key.as_deref(), backup)
+}
+
+fn verify_with(key: Option<&str>, backup: &Path) -> Verification {
+    let (manifest, signature) = (manifest_path(backup), signature_path(backup));
+    if !manifest.exists() || !signature.exists() {
+        return Verification::Unsigned;
+    }
+    let Some(key) = key else { return Verification::Unchecked };
+    // A key file, or the base64 key itself as minisign -G prints it
+    let key_flag = if Path::new(key).exists() { "-p" } else { "-P" };
+    let (manifest_arg, signature_arg) = (manifest.to_string_lossy().to_string(), signature.to_string_lossy().to_string());
+    let output = match run_minisign(&["-V", key_flag, key, "-m", &manifest_arg, "-x", &signature_arg]) {
+        Ok(output) => output,
+        Err(message) => return Verification::Invalid(message),
+    };
+    if !output.success {
+        return Verification::Invalid(format!(
+            "the manifest does not match its signature, or was signed with another key ({})",
+            output.stderr.trim()
+        ));
+    }
+    let comment = output.stdout.lines().find_map(|l| l.strip_prefix("Trusted comment: ")).unwrap_or_default().trim().to_string();
+    // A genuine manifest renamed next to another backup must not vouch for it
+    let name = backup.file_name().unwrap_or_default().to_string_lossy().to_string();
+    let signed_for = comment.strip_prefix(COMMENT_PREFIX).unwrap_or_default();
+    if !signed_for.eq_ignore_ascii_case(&name) {
+        return Verification::Invalid(format!("the signature was made for {}, not {}", signed_for, name));
+    }
+
+    let listed = parse_manifest(&std::fs::read_to_string(&manifest).unwrap_or_default());
+    let mut covered = vec![name];
+    if delta::is_delta(backup) {
+        match delta::base_name(backup) {
+            Ok(base) => covered.push(base),
+            Err(message) => return Verification::Invalid(message),
+        }
+    }
+    if let Some(missing) = covered.iter().find(|c| !listed.iter().any(|(_, n)| n.eq_ignore_ascii_case(c))) {
+        return Verification::Invalid(format!("the manifest does not list {}", missing));
+    }
+    let dir = backup.parent().unwrap_or(Path::new("."));
+    let files: Vec<PathBuf> = listed.iter().map(|(_, name)| dir.join(name)).collect();
+    if let Some(missing) = files.iter().find(|f| !f.exists()) {
+        return Verification::Invalid(format!("{} is missing", missing.display()));
+    }
+    let current = match hashes(&files) {
+        Ok(current) => current,
+        Err(message) => return Verification::Invalid(message),
+    };
+    for (hash, name) in &listed {
+        if !current.iter().any(|(h, n)| h.eq_ignore_ascii_case(hash) && n.eq_ignore_ascii_case(name)) {
+            return Verification::Invalid(format!("{} changed since it was signed", name));
+        }
+    }
+    Verification::Valid(comment)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, FakeRunner};
+
+    const XML_HASH: &str = "0B9A54F2B8C4A0E1D6C43C1F2E3D4A5B6C7D8E9F0A1B2C3D4E5F60718293A4B5";
+
+    #[test]
+    fn signs_the_manifest_and_verifies_the_files() {
+        let dir = testing::temp_dir("signing");
+        let backup = dir.join("ServerRoles_1709288100.xml");
+        std::fs::write(&backup, "<Objs />").unwrap_or_default();
+        let fake = FakeRunner::new()
+            .on("Get-FileHash", &format!("{}\tServerRoles_1709288100.xml\n", XML_HASH))
+            .on("minisign -S", "")
+            .on("minisign -V", "Signature and comment signature verified\nTrusted comment: Server Helper backup ServerRoles_1709288100.xml\n")
+            .install();
+
+        let signed = sign_with(Path::new("C:\\keys\\minisign.key"), &backup, None);
+
+        assert!(signed.is_ok_and(|m| m.starts_with("Signed 1 file(s)")));
+        assert!(fake.ran("-t Server Helper backup ServerRoles_1709288100.xml"));
+        std::fs::write(signature_path(&backup), "untrusted comment: signature").unwrap_or_default();
+        assert_eq!(verify_with(None, &backup), Verification::Unchecked);
+        assert_eq!(verify_with(Some("RWQkey"), &backup), Verification::Valid("Server Helper backup ServerRoles_1709288100.xml".to_string()));
+        assert!(fake.ran("minisign -V -P RWQkey"));
+    }
+
+    #[test]
+    fn flags_a_file_changed_after_signing() {
+        let dir = testing::temp_dir("signing-changed");
+        let backup = dir.join("ServerRoles_1709288100.xml");
+        std::fs::write(&backup, "<Objs />").unwrap_or_default();
+        std::fs::write(manifest_path(&backup), render_manifest(&[(XML_HASH.to_string(), "ServerRoles_1709288100.xml".to_string())]))
+            .unwrap_or_default();
+        assert_eq!(verify_with(Some("RWQkey"), &backup), Verification::Unsigned);
+        std::fs::write(signature_path(&backup), "untrusted comment: signature").unwrap_or_default();
+
+        FakeRunner::new()
+            .on("Get-FileHash", &format!("{}\tServerRoles_1709288100.xml\n", XML_HASH.replace('0', "1")))
+            .on("minisign -V", "Trusted comment: Server Helper backup ServerRoles_1709288100.xml\n")
+            .install();
+        let changed = verify_with(Some("RWQkey"), &backup);
+        assert_eq!(changed, Verification::Invalid("ServerRoles_1709288100.xml changed since it was signed".to_string()));
+
+        FakeRunner::new().fail("minisign -V", "Signature verification failed").install();
+        assert!(matches!(verify_with(Some("RWQkey"), &backup), Verification::Invalid(r) if r.contains("another key")));
+    }
+
+    #[test]
+    fn rejects_a_manifest_signed_for_another_backup() {
+        let dir = testing::temp_dir("signing-renamed");
+        let backup = dir.join("ServerRoles_1709374500.delta");
+        std::fs::write(&backup, "<Objs />").unwrap_or_default();
+        let signed_a = render_manifest(&[(XML_HASH.to_string(), "ServerRoles_1709288100.xml".to_string())]);
+        std::fs::write(manifest_path(&backup), &signed_a).unwrap_or_default();
+        std::fs::write(signature_path(&backup), "untrusted comment: signature").unwrap_or_default();
+        let runner = FakeRunner::new()
+            .on("minisign -V", "Trusted comment: Server Helper backup ServerRoles_1709288100.xml\n")
+            .on("Get-FileHash", &format!("{}\tServerRoles_1709288100.xml\n", XML_HASH))
+            .install();
+
+        let renamed = verify_with(Some("RWQkey"), &backup);
+        assert!(matches!(renamed, Verification::Invalid(r) if r.contains("made for ServerRoles_1709288100.xml")));
+        assert!(!runner.ran("Get-FileHash"));
+
+        // Signed for this name, but the manifest lists neither the delta nor its base
+        FakeRunner::new()
+            .on("minisign -V", "Trusted comment: Server Helper backup ServerRoles_1709374500.delta\n")
+            .on("Import-Clixml", "ServerRoles_1709288100.xml\n")
+            .install();
+        let unlisted = verify_with(Some("RWQkey"), &backup);
+        assert_eq!(unlisted, Verification::Invalid("the manifest does not list ServerRoles_1709374500.delta".to_string()));
+
+        assert!(problem_with(&Verification::Unsigned, true).is_some_and(|p| p.contains("not signed")));
+        assert_eq!(problem_with(&Verification::Unsigned, false), None);
+    }
+}
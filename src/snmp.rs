@@ -0,0 +1,99 @@
+use crate::{exec, plan::Plan};
+
+const PARAMETERS: &str = "HKLM:\\SYSTEM\\CurrentControlSet\\Services\\SNMP\\Parameters";
+
+/// Parses `community[:ro|rw] manager [manager...]`.
+fn parse_options(value: &str) -> Result<(String, &'static str, Vec<String>), String> {
+    let mut parts = value.split_whitespace();
+    let first = parts.next().ok_or("Enter a community string and at least one manager.")?;
+    let (community, rights) = match first.rsplit_once(':') {
+        Some((community, "ro")) => (community, "ro"),
+        Some((community, "rw")) => (community, "rw"),
+        Some((_, other)) => return Err(format!("Unknown access '{}': use ro or rw", other)),
+        None => (first, "ro"),
+    };
+    if community.is_empty() {
+        return Err("The community string is empty.".to_string());
+    }
+    let managers: Vec<String> = parts.map(String::from).collect();
+    if managers.is_empty() {
+        return Err("Enter at least one permitted manager; without one the agent answers any host.".to_string());
+    }
+    Ok((community.to_string(), rights, managers))
+}
+
+/// Plans installing the SNMP service, setting the community and permitted managers,
+/// opening the firewall and restarting the service.
+pub fn setup_plan(value: &str) -> Result<Plan, String> {
+    let (community, rights, managers) = parse_options(value)?;
+
+    let state = exec::powershell(&format!(
+        "\"Installed=$((Get-WindowsFeature -Name SNMP-Service).Installed)\"; \
+        if (Test-Path '{0}\\ValidCommunities') {{ \
+            (Get-Item '{0}\\ValidCommunities').Property | ForEach-Object {{ \"Community=$_\" }} \
+        }}",
+        PARAMETERS
+    ))
+    .map_err(|e| format!("Failed to read the SNMP configuration: {}", e))?;
+    let installed = exec::key_values(&state.stdout).get("Installed").map(String::as_str) == Some("True");
+    let existing: Vec<&str> = state.stdout.lines().filter_map(|l| l.strip_prefix("Community=")).collect();
+
+    let mut plan = Plan::new("Set up the SNMP service");
+    if installed {
+        plan.note("The SNMP service is already installed");
+    } else {
+        plan.step(
+            "Install the SNMP service and its management tools",
+            "Install-WindowsFeature -Name SNMP-Service, RSAT-SNMP -ErrorAction Stop | \
+            Format-List Success, RestartNeeded, ExitCode, FeatureResult",
+        );
+    }
+
+    // 4 = READ ONLY, 8 = READ WRITE
+    let flag = if rights == "rw" { 8 } else { 4 };
+    plan.step(
+        format!("Set community '{}' ({})", community, if rights == "rw" { "read-write" } else { "read-only" }),
+        format!(
+            "New-Item -Path '{0}\\ValidCommunities' -Force | Out-Null\n\
+            New-ItemProperty -Path '{0}\\ValidCommunities' -Name {1} -Value {2} -PropertyType DWord -Force | Out-Null",
+            PARAMETERS,
+            exec::quote(&community),
+            flag
+        ),
+    );
+    let others: Vec<&str> = existing.into_iter().filter(|c| *c != community).collect();
+    if !others.is_empty() {
+        plan.note(format!("Other communities are kept: {}", others.join(", ")));
+    }
+
+    let values: Vec<String> = managers
+        .iter()
+        .enumerate()
+        .map(|(i, m)| {
+            format!(
+                "New-ItemProperty -Path '{}\\PermittedManagers' -Name '{}' -Value {} -PropertyType String -Force | Out-Null",
+                PARAMETERS,
+                i + 1,
+                exec::quote(m)
+            )
+        })
+        .collect();
+    plan.step(
+        format!("Allow only these managers: {}", managers.join(", ")),
+        format!(
+            "Remove-Item -Path '{0}\\PermittedManagers' -Recurse -Force -ErrorAction SilentlyContinue\n\
+            New-Item -Path '{0}\\PermittedManagers' -Force | Out-Null\n{1}",
+            PARAMETERS,
+            values.join("\n")
+        ),
+    );
+    plan.step(
+        "Open the firewall for SNMP (UDP 161)",
+        "Enable-NetFirewallRule -DisplayGroup 'SNMP Service' -ErrorAction Stop",
+    );
+    plan.step(
+        "Restart the SNMP service to load the configuration",
+        "Set-Service -Name SNMP -StartupType Automatic\nRestart-Service -Name SNMP -Force -ErrorAction Stop",
+    );
+    Ok(plan)
+}
@@ -0,0 +1,74 @@
+use crate::exec;
+
+const QUEUE_SCRIPT: &str = r#"
+"Status=$((Get-Service -Name Spooler).Status)"
+$printers = @(Get-Printer -ErrorAction SilentlyContinue)
+"Printers=$($printers.Count)"
+"Jobs=$(@($printers | ForEach-Object { Get-PrintJob -PrinterObject $_ -ErrorAction SilentlyContinue }).Count)"
+"#;
+
+/// Stops the spooler, deletes the spool files of stuck jobs and starts it again.
+const FIX_SCRIPT: &str = r#"
+$ErrorActionPreference = 'Stop'
+$dir = (Get-ItemProperty 'HKLM:\SYSTEM\CurrentControlSet\Control\Print\Printers' -ErrorAction SilentlyContinue).DefaultSpoolDirectory
+if (-not $dir) { $dir = Join-Path $env:SystemRoot 'System32\spool\PRINTERS' }
+Stop-Service -Name Spooler -Force
+(Get-Service -Name Spooler).WaitForStatus('Stopped', [TimeSpan]::FromSeconds(30))
+$files = @(Get-ChildItem -Path $dir -File -ErrorAction SilentlyContinue)
+$files | Remove-Item -Force
+"Removed=$($files.Count)"
+"Directory=$dir"
+Start-Service -Name Spooler
+"#;
+
+struct Queue {
+    status: String,
+    printers: String,
+    jobs: String,
+}
+
+fn queue() -> Result<Queue, String> {
+    let output = exec::powershell(QUEUE_SCRIPT).map_err(|e| format!("Failed to read the print queues: {}", e))?;
+    let values = exec::key_values(&output.stdout);
+    let get = |key: &str| values.get(key).cloned().unwrap_or_default();
+    Ok(Queue { status: get("Status"), printers: get("Printers"), jobs: get("Jobs") })
+}
+
+/// Clears stuck print jobs by restarting the spooler with an empty spool directory,
+/// reporting the queued job count before and after.
+pub fn fix_spooler() -> (bool, String) {
+    let before = match queue() {
+        Ok(queue) => queue,
+        Err(e) => return (false, e),
+    };
+    let output = match exec::change(FIX_SCRIPT) {
+        Ok(output) => output,
+        Err(e) => return (false, format!("Failed to reset the print spooler: {}", e)),
+    };
+    if !output.success {
+        return (false, format!(
+            "Failed to reset the print spooler:\n{}\n\nCheck that the Print Spooler service is running.",
+            output.stderr.trim()
+        ));
+    }
+    let values = exec::key_values(&output.stdout);
+    let after = match queue() {
+        Ok(queue) => queue,
+        Err(e) => return (false, e),
+    };
+
+    let before_jobs = if before.status == "Running" { before.jobs.as_str() } else { "unknown (spooler was stopped)" };
+    (after.status == "Running", format!(
+        "Print spooler reset.\n\n\
+        Queued jobs before: {}\n\
+        Spool files removed: {} (from {})\n\
+        Queued jobs after:  {}\n\n\
+        Spooler: {} ({} printer(s))",
+        before_jobs,
+        values.get("Removed").cloned().unwrap_or_default(),
+        values.get("Directory").cloned().unwrap_or_default(),
+        after.jobs,
+        after.status,
+        after.printers
+    ))
+}
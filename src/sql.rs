@@ -0,0 +1,191 @@
+use crate::{
+    exec,
+    outcome::{OperationError, OperationOutcome},
+};
+
+/// Microsoft's SQL Server 2022 Express bootstrapper.
+const BOOTSTRAPPER_URL: &str = "https://go.microsoft.com/fwlink/p/?linkid=2216019";
+
+/// Downloads the Express core media through the bootstrapper, extracts it and runs an unattended
+/// setup with mixed-mode authentication, TCP and the Browser service enabled.
+const INSTALL_SCRIPT: &str = r#"
+$ErrorActionPreference = 'Stop'
+[Net.ServicePointManager]::SecurityProtocol = [Net.SecurityProtocolType]::Tls12
+$work = Join-Path $env:TEMP 'SqlExpress'
+New-Item -ItemType Directory -Force -Path $work | Out-Null
+$ssei = Join-Path $work 'SQL2022-SSEI-Expr.exe'
+Invoke-WebRequest -Uri $url -OutFile $ssei -UseBasicParsing
+$p = Start-Process -FilePath $ssei -ArgumentList '/ACTION=Download', "/MEDIAPATH=$work", '/MEDIATYPE=Core', '/QUIET' -Wait -PassThru
+if ($p.ExitCode -ne 0) { throw "Downloading the installation media failed with exit code $($p.ExitCode)" }
+$package = Get-ChildItem -Path $work -Filter 'SQLEXPR*.exe' | Select-Object -First 1
+$setupDir = Join-Path $work 'setup'
+$p = Start-Process -FilePath $package.FullName -ArgumentList '/q', "/x:$setupDir" -Wait -PassThru
+if ($p.ExitCode -ne 0) { throw "Extracting $($package.Name) failed with exit code $($p.ExitCode)" }
+$setupArgs = @(
+    '/Q', '/ACTION=Install', '/IACCEPTSQLSERVERLICENSETERMS', '/FEATURES=SQLEngine', '/UPDATEENABLED=0',
+    "/INSTANCENAME=$instance", '/SECURITYMODE=SQL', "/SAPWD=`"$sa`"",
+    '/SQLSYSADMINACCOUNTS="BUILTIN\Administrators"', '/TCPENABLED=1', '/BROWSERSVCSTARTUPTYPE=Automatic'
+)
+$p = Start-Process -FilePath (Join-Path $setupDir 'setup.exe') -ArgumentList $setupArgs -Wait -PassThru
+"ExitCode=$($p.ExitCode)"
+if ($p.ExitCode -in 0, 3010) {
+    $id = (Get-ItemProperty 'HKLM:\SOFTWARE\Microsoft\Microsoft SQL Server\Instance Names\SQL').$instance
+    $sqlservr = Join-Path (Get-ItemProperty "HKLM:\SOFTWARE\Microsoft\Microsoft SQL Server\$id\Setup").SQLBinRoot 'sqlservr.exe'
+    if (-not (Get-NetFirewallRule -Name "SQL-$instance" -ErrorAction SilentlyContinue)) {
+        New-NetFirewallRule -Name "SQL-$instance" -DisplayName "SQL Server ($instance)" -Direction Inbound -Program $sqlservr -Action Allow | Out-Null
+    }
+    if (-not (Get-NetFirewallRule -Name 'SQL-Browser' -ErrorAction SilentlyContinue)) {
+        New-NetFirewallRule -Name 'SQL-Browser' -DisplayName 'SQL Server Browser' -Direction Inbound -Protocol UDP -LocalPort 1434 -Action Allow | Out-Null
+    }
+}
+"Log=$(Join-Path $env:ProgramFiles 'Microsoft SQL Server\160\Setup Bootstrap\Log\Summary.txt')"
+"#;
+
+/// Connects over TCP as sa (through the Browser service for a named instance).
+const VERIFY_SCRIPT: &str = r#"
+$builder = New-Object System.Data.SqlClient.SqlConnectionStringBuilder
+$builder['Data Source'] = "tcp:$env:COMPUTERNAME\$instance"
+$builder['User ID'] = 'sa'
+$builder['Password'] = $sa
+$builder['Connect Timeout'] = 15
+$connection = New-Object System.Data.SqlClient.SqlConnection $builder.ConnectionString
+try {
+    $connection.Open()
+    $command = $connection.CreateCommand()
+    $command.CommandText = "SELECT SERVERPROPERTY('ProductVersion'), SERVERPROPERTY('Edition')"
+    $reader = $command.ExecuteReader()
+    [void]$reader.Read()
+    "Version=$($reader.GetValue(0))"
+    "Edition=$($reader.GetValue(1))"
+} catch {
+    "Error=$($_.Exception.InnerException.Message -replace '\s+', ' ')"
+} finally {
+    $connection.Close()
+}
+"#;
+
+/// Validates the instance name and the sa password against SQL Server's policy
+/// (8+ characters from at least three of upper, lower, digits and symbols).
+pub fn validate(instance: &str, password: &str) -> Result<(), String> {
+    let valid_instance = !instance.is_empty()
+        && instance.len() <= 16
+        && instance.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && instance.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+    if !valid_instance || instance.eq_ignore_ascii_case("default") {
+        return Err(format!(
+            "'{}' is not a valid instance name: up to 16 letters, digits or _, starting with a letter.",
+            instance
+        ));
+    }
+    let classes = [
+        password.chars().any(|c| c.is_ascii_uppercase()),
+        password.chars().any(|c| c.is_ascii_lowercase()),
+        password.chars().any(|c| c.is_ascii_digit()),
+        password.chars().any(|c| !c.is_ascii_alphanumeric()),
+    ];
+    if password.chars().count() < 8 || classes.iter().filter(|c| **c).count() < 3 {
+        return Err("The sa password needs at least 8 characters from three of: upper case, lower case, \
+            digits and symbols."
+            .to_string());
+    }
+    if password.contains('"') {
+        return Err("The sa password cannot contain double quotes.".to_string());
+    }
+    Ok(())
+}
+
+/// Installs SQL Server Express as `instance` with mixed-mode authentication and TCP enabled,
+/// then verifies that sa can connect over TCP.
+pub fn install_express(instance: &str, password: &str) -> OperationOutcome {
+    if let Err(e) = validate(instance, password) {
+        return OperationOutcome::failed(OperationError::Refused(e));
+    }
+    // Never write the password into an exported script; prompt for it when the script runs
+    let exporting = exec::export_status().is_some();
+    let sa = if exporting {
+        "(New-Object PSCredential 'sa', (Read-Host 'sa password' -AsSecureString)).GetNetworkCredential().Password".to_string()
+    } else {
+        exec::quote(password)
+    };
+    let script = format!(
+        "$url = {}; $instance = {}; $sa = {}\n{}",
+        exec::quote(BOOTSTRAPPER_URL),
+        exec::quote(instance),
+        sa,
+        INSTALL_SCRIPT.trim()
+    );
+    let output = match exec::change(&script) {
+        Ok(output) => output,
+        Err(e) => {
+            return OperationOutcome::failed(OperationError::Command {
+                action: "SQL Server setup".to_string(),
+                detail: e.to_string(),
+            })
+        }
+    };
+    if exporting {
+        return OperationOutcome::Success(format!(
+            "SQL Server Express ({}) install recorded; the script asks for the sa password.",
+            instance
+        ));
+    }
+    let values = exec::key_values(&output.stdout);
+    let exit_code = values.get("ExitCode").map(String::as_str).unwrap_or("");
+    let log = values.get("Log").cloned().unwrap_or_default();
+    match exit_code {
+        "0" | "3010" => {}
+        "" => {
+            return OperationOutcome::failed(OperationError::Command {
+                action: "SQL Server Express installation".to_string(),
+                detail: output.stderr,
+            })
+        }
+        code => {
+            return OperationOutcome::failed(OperationError::Command {
+                action: "SQL Server setup".to_string(),
+                detail: format!("Exit code {}.\n\nSee the setup summary: {}", code, log),
+            })
+        }
+    }
+
+    let mut message = format!(
+        "SQL Server Express installed as instance {} (mixed-mode authentication, TCP enabled,\n\
+        SQL Server Browser running, firewall rules added). Local administrators are sysadmins.",
+        instance
+    );
+
+    let verify = format!("$instance = {}; $sa = {}\n{}", exec::quote(instance), exec::quote(password), VERIFY_SCRIPT.trim());
+    let values = exec::powershell(&verify).map(|o| exec::key_values(&o.stdout)).unwrap_or_default();
+    match (values.get("Version"), values.get("Error")) {
+        (Some(version), _) => {
+            message.push_str(&format!(
+                "\n\nConnectivity check: connected over TCP as sa to {}\\{} ({} {}).",
+                std::env::var("COMPUTERNAME").unwrap_or_else(|_| "localhost".to_string()),
+                instance,
+                values.get("Edition").cloned().unwrap_or_default(),
+                version
+            ));
+            if exit_code == "3010" {
+                OperationOutcome::SuccessNeedsReboot(message)
+            } else {
+                OperationOutcome::Success(message)
+            }
+        }
+        (None, error) => {
+            message.push_str(&format!(
+                "\n\nCheck that the SQL Server ({}) and SQL Server Browser services are running.",
+                instance
+            ));
+            if exit_code == "3010" {
+                message.push_str("\n\nA restart is required to finish the installation.");
+            }
+            OperationOutcome::Partial {
+                message,
+                failed_items: vec![format!(
+                    "Connectivity check: {}",
+                    error.cloned().unwrap_or_else(|| "no response".to_string())
+                )],
+            }
+        }
+    }
+}
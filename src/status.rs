@@ -0,0 +1,251 @@
+use std::time::Duration;
+
+use crate::{
+    exec,
+    message::{self, Message},
+};
+
+/// Winget and NetBird versions, NetBird's management connection, the usual pending-reboot
+/// flags and free space on the system drive, as `key=value` lines. The drive figures are plain
+/// byte counts: GB and percentages are worked out here, as PowerShell would format them with
+/// the decimal separator of the host's culture.
+const STATUS_SCRIPT: &str = r#"
+$winget = Get-Command winget -ErrorAction SilentlyContinue
+if ($winget) { "Winget=$((& $winget.Source --version) -replace '^v', '')" }
+$netbird = Get-Command netbird -ErrorAction SilentlyContinue
+if (-not $netbird -and (Test-Path "$env:ProgramFiles\NetBird\netbird.exe")) { $netbird = Get-Command "$env:ProgramFiles\NetBird\netbird.exe" }
+if ($netbird) {
+    "NetBird=$(& $netbird.Source version)"
+    "NetBirdConnected=$([bool]((& $netbird.Source status 2>$null) -match 'Management:\s+Connected'))"
+}
+$pending = (Test-Path 'HKLM:\SOFTWARE\Microsoft\Windows\CurrentVersion\Component Based Servicing\RebootPending') -or
+    (Test-Path 'HKLM:\SOFTWARE\Microsoft\Windows\CurrentVersion\WindowsUpdate\Auto Update\RebootRequired') -or
+    [bool](Get-ItemProperty 'HKLM:\SYSTEM\CurrentControlSet\Control\Session Manager' -Name PendingFileRenameOperations -ErrorAction SilentlyContinue)
+"RebootPending=$pending"
+$drive = Get-CimInstance Win32_LogicalDisk -Filter "DeviceID='$env:SystemDrive'"
+"Drive=$env:SystemDrive"
+"FreeBytes=$($drive.FreeSpace)"
+"SizeBytes=$($drive.Size)"
+"#;
+
+const GB: f64 = 1_073_741_824.0;
+
+/// Free space below this share of the system drive is shown as a problem.
+const LOW_DISK_PERCENT: u32 = 10;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Level {
+    Good,
+    Warning,
+    Bad,
+}
+
+/// A short status shown next to a menu entry.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Badge {
+    pub text: String,
+    pub level: Level,
+}
+
+fn badge(text: impl Into<String>, level: Level) -> Badge {
+    Badge { text: text.into(), level }
+}
+
+/// The last background reading of the host.
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct HostStatus {
+    /// Installed winget version, `None` when winget isn't found.
+    pub winget: Option<String>,
+    /// Installed NetBird version, `None` when NetBird isn't found.
+    pub netbird: Option<String>,
+    pub netbird_connected: bool,
+    pub reboot_pending: bool,
+    pub drive: String,
+    /// Free space on the system drive in GB and as a percentage, if it could be read.
+    pub disk_free: Option<(f64, u32)>,
+    /// Exact free bytes on the system drive, for the metrics endpoint.
+    pub free_bytes: Option<u64>,
+}
+
+impl HostStatus {
+    pub fn winget_badge(&self) -> Badge {
+        match &self.winget {
+            Some(version) => badge(format!("winget {}", version), Level::Good),
+            None => badge("winget missing", Level::Bad),
+        }
+    }
+
+    pub fn netbird_badge(&self) -> Badge {
+        match &self.netbird {
+            Some(_) if self.netbird_connected => badge("connected", Level::Good),
+            Some(_) => badge("disconnected", Level::Warning),
+            None => badge("not installed", Level::Bad),
+        }
+    }
+
+    /// For the Install Winget entry.
+    pub fn winget_install_badge(&self) -> Badge {
+        installed_badge(&self.winget)
+    }
+
+    /// For the Install NetBird entry.
+    pub fn netbird_install_badge(&self) -> Badge {
+        installed_badge(&self.netbird)
+    }
+
+    pub fn reboot_badge(&self) -> Badge {
+        if self.reboot_pending {
+            badge("reboot pending", Level::Warning)
+        } else {
+            badge("no reboot pending", Level::Good)
+        }
+    }
+
+    pub fn disk_badge(&self) -> Option<Badge> {
+        let (gb, percent) = self.disk_free?;
+        let level = if percent < LOW_DISK_PERCENT { Level::Bad } else { Level::Good };
+        Some(badge(format!("{} {:.1} GB free ({}%)", self.drive, gb, percent), level))
+    }
+}
+
+/// What changed between two readings that is worth a notification, e.g. NetBird disconnecting.
+pub fn changes(old: &HostStatus, new: &HostStatus) -> Vec<Badge> {
+    let mut changes = Vec::new();
+    match (&old.winget, &new.winget) {
+        (None, Some(version)) => changes.push(badge(format!("winget {} installed", version), Level::Good)),
+        (Some(_), None) => changes.push(badge("winget is no longer found", Level::Bad)),
+        _ => {}
+    }
+    match (old.netbird.is_some() && old.netbird_connected, new.netbird.is_some(), new.netbird_connected) {
+        (true, false, _) => changes.push(badge("NetBird is no longer found", Level::Bad)),
+        (true, true, false) => changes.push(badge("NetBird disconnected", Level::Warning)),
+        (false, true, true) => changes.push(badge("NetBird connected", Level::Good)),
+        _ => {}
+    }
+    if new.reboot_pending && !old.reboot_pending {
+        changes.push(badge("A restart is now pending", Level::Warning));
+    }
+    let low = |status: &HostStatus| status.disk_badge().is_some_and(|b| b.level == Level::Bad);
+    if let Some(disk) = new.disk_badge().filter(|_| low(new) && !low(old)) {
+        changes.push(badge(format!("Low disk space: {}", disk.text), Level::Bad));
+    }
+    changes
+}
+
+fn installed_badge(version: &Option<String>) -> Badge {
+    match version {
+        Some(version) => badge(format!("✓ installed {}", version), Level::Good),
+        None => badge("✗ not installed", Level::Bad),
+    }
+}
+
+fn parse(stdout: &str) -> HostStatus {
+    let values = exec::key_values(stdout);
+    let bytes = |key: &str| values.get(key).and_then(|b| b.trim().parse::<u64>().ok());
+    let free_bytes = bytes("FreeBytes");
+    let flag = |key: &str| values.get(key).is_some_and(|v| v.eq_ignore_ascii_case("true"));
+    let version = |key: &str| values.get(key).map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+    HostStatus {
+        winget: version("Winget"),
+        netbird: version("NetBird"),
+        netbird_connected: flag("NetBirdConnected"),
+        reboot_pending: flag("RebootPending"),
+        drive: values.get("Drive").cloned().unwrap_or_default(),
+        disk_free: free_bytes.zip(bytes("SizeBytes").filter(|size| *size > 0)).map(|(free, size)| {
+            (free as f64 / GB, (free as f64 * 100.0 / size as f64).round() as u32)
+        }),
+        free_bytes,
+    }
+}
+
+/// Reads the host status in one PowerShell call.
+pub fn collect() -> Result<HostStatus, String> {
+    let output = exec::powershell(STATUS_SCRIPT).map_err(|e| format!("Failed to read the host status: {}", e))?;
+    Ok(parse(&output.stdout))
+}
+
+/// Reads the host status now and then every `interval` on a background thread, sending each
+/// reading to the main loop. Stops when the main loop has gone.
+pub fn start_refresh(interval: Duration) {
+    spawn_reader(Some(interval));
+}
+
+/// Reads the host status once in the background, e.g. right after an install.
+pub fn refresh_now() {
+    spawn_reader(None);
+}
+
+fn spawn_reader(interval: Option<Duration>) {
+    let Some(outbox) = message::outbox() else { return };
+    let runner = exec::runner();
+    std::thread::spawn(move || {
+        exec::set_runner(runner);
+        loop {
+            if let Ok(status) = collect() {
+                if outbox.send(Message::StatusRefreshed(status)).is_err() {
+                    return;
+                }
+            }
+            match interval {
+                Some(interval) => std::thread::sleep(interval),
+                None => return,
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_a_reading() {
+        let status = parse(
+            "Winget=1.7.10861\nNetBird=0.28.4\nNetBirdConnected=True\nRebootPending=False\nDrive=C:\nFreeBytes=1325614284800\nSizeBytes=16570178560000\n",
+        );
+
+        assert_eq!(status.winget.as_deref(), Some("1.7.10861"));
+        assert_eq!(status.winget_install_badge(), badge("✓ installed 1.7.10861", Level::Good));
+        assert_eq!(status.netbird_badge(), badge("connected", Level::Good));
+        assert_eq!(status.reboot_badge().level, Level::Good);
+        assert_eq!(status.disk_badge(), Some(badge("C: 1234.6 GB free (8%)", Level::Bad)));
+        assert_eq!(status.free_bytes, Some(1_325_614_284_800));
+    }
+
+    #[test]
+    fn missing_tools_are_reported() {
+        let status = parse("RebootPending=True\n");
+
+        assert_eq!(status.winget_badge().level, Level::Bad);
+        assert_eq!(status.netbird_badge(), badge("not installed", Level::Bad));
+        assert_eq!(status.netbird_install_badge(), badge("✗ not installed", Level::Bad));
+        assert_eq!(status.reboot_badge().level, Level::Warning);
+        assert_eq!(status.disk_badge(), None);
+    }
+
+    #[test]
+    fn disk_space_reads_the_same_on_a_decimal_comma_culture() {
+        // On de-DE PowerShell writes 45.6 GB as "45,6"; only integers cross over
+        assert!(!STATUS_SCRIPT.contains("{0:N"));
+        let status = parse("Drive=C:\nFreeBytes=48962627174\nSizeBytes=107374182400\n");
+
+        assert_eq!(status.disk_badge(), Some(badge("C: 45.6 GB free (46%)", Level::Good)));
+        assert_eq!(parse("Drive=C:\nFreeBytes=45,6\nSizeBytes=100\n").disk_badge(), None);
+    }
+
+    #[test]
+    fn reports_what_changed_between_readings() {
+        let old = parse("Winget=1.7.10861\nNetBird=0.28.4\nNetBirdConnected=True\nDrive=C:\nFreeBytes=42949672960\nSizeBytes=107374182400\n");
+        let new = parse("Winget=1.7.10861\nNetBird=0.28.4\nRebootPending=True\nDrive=C:\nFreeBytes=4294967296\nSizeBytes=107374182400\n");
+
+        assert_eq!(
+            changes(&old, &new),
+            [
+                badge("NetBird disconnected", Level::Warning),
+                badge("A restart is now pending", Level::Warning),
+                badge("Low disk space: C: 4.0 GB free (4%)", Level::Bad),
+            ]
+        );
+        assert!(changes(&new, &new).is_empty());
+    }
+}
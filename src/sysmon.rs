@@ -0,0 +1,110 @@
+use std::path::Path;
+
+use crate::{
+    exec,
+    outcome::{OperationError, OperationOutcome},
+};
+
+/// Configuration used when no XML file is picked.
+const DEFAULT_CONFIG: &str = include_str!("../assets/sysmon-default.xml");
+
+const INSTALL_SCRIPT: &str = r#"
+$ErrorActionPreference = 'Stop'
+$zip = Join-Path $env:TEMP 'Sysmon.zip'
+$dir = Join-Path $env:TEMP 'Sysmon'
+Invoke-WebRequest -Uri 'https://download.sysinternals.com/files/Sysmon.zip' -OutFile $zip -UseBasicParsing
+Expand-Archive -Path $zip -DestinationPath $dir -Force
+$exe = if ([Environment]::Is64BitOperatingSystem) { Join-Path $dir 'Sysmon64.exe' } else { Join-Path $dir 'Sysmon.exe' }
+
+$service = Get-Service -Name 'Sysmon64', 'Sysmon' -ErrorAction SilentlyContinue | Select-Object -First 1
+if ($service) {
+    $out = & $exe -c $config 2>&1
+    "Action=updated"
+} else {
+    $out = & $exe -accepteula -i $config 2>&1
+    "Action=installed"
+}
+"ExitCode=$LASTEXITCODE"
+if ($LASTEXITCODE -ne 0) { $out | ForEach-Object { "Output=$_" }; exit 0 }
+
+# Generate a process creation event, then look for recent events
+$start = (Get-Date).AddSeconds(-5)
+cmd.exe /c exit | Out-Null
+Start-Sleep -Seconds 5
+$service = Get-Service -Name 'Sysmon64', 'Sysmon' -ErrorAction SilentlyContinue | Select-Object -First 1
+"Service=$($service.Name) $($service.Status)"
+$events = @(Get-WinEvent -FilterHashtable @{ LogName = 'Microsoft-Windows-Sysmon/Operational'; StartTime = $start } -ErrorAction SilentlyContinue)
+"Events=$($events.Count)"
+if ($events.Count -gt 0) { "Latest=$($events[0].TimeCreated) (event $($events[0].Id))" }
+"#;
+
+/// Downloads Sysmon and installs it with `config`, or the bundled default when `None`.
+/// Updates the configuration instead if Sysmon is already installed.
+pub fn install(config: Option<&Path>) -> OperationOutcome {
+    let config_path = match config {
+        Some(path) => path.to_path_buf(),
+        None => {
+            let path = std::env::temp_dir().join("sysmon-default.xml");
+            if let Err(e) = exec::write_file(&path, DEFAULT_CONFIG) {
+                return OperationOutcome::failed(OperationError::Io { path, reason: e.to_string() });
+            }
+            path
+        }
+    };
+    let config_name = match config {
+        Some(path) => path.display().to_string(),
+        None => "bundled default".to_string(),
+    };
+
+    let script = format!("$config = {}; {}", exec::quote(&config_path.to_string_lossy()), INSTALL_SCRIPT);
+    let output = match exec::change(&script) {
+        Ok(output) => output,
+        Err(e) => {
+            return OperationOutcome::failed(OperationError::Command {
+                action: "The Sysmon installer".to_string(),
+                detail: e.to_string(),
+            })
+        }
+    };
+    if !output.success {
+        return OperationOutcome::failed(OperationError::Command {
+            action: "Sysmon installation".to_string(),
+            detail: output.stderr,
+        });
+    }
+
+    let values = exec::key_values(&output.stdout);
+    let action = values.get("Action").cloned().unwrap_or_else(|| "installed".to_string());
+    if values.get("ExitCode").map(String::as_str) != Some("0") {
+        let details: Vec<&str> = output
+            .stdout
+            .lines()
+            .filter_map(|line| line.strip_prefix("Output="))
+            .collect();
+        return OperationOutcome::failed(OperationError::Command {
+            action: format!("Sysmon setup with {}", config_name),
+            detail: details.join("\n"),
+        });
+    }
+
+    let service = values.get("Service").cloned().unwrap_or_default();
+    let events: usize = values.get("Events").and_then(|n| n.parse().ok()).unwrap_or(0);
+    if events == 0 {
+        return OperationOutcome::Partial {
+            message: format!(
+                "Sysmon was {} with {}, but no events reached Microsoft-Windows-Sysmon/Operational.\n\n\
+                Service: {}\n\nCheck the configuration filters and the service state.",
+                action, config_name, service
+            ),
+            failed_items: vec!["Event check: no events in the last few seconds".to_string()],
+        };
+    }
+    OperationOutcome::Success(format!(
+        "Sysmon {} with {}.\n\nService: {}\nEvents in the last few seconds: {}\nLatest: {}",
+        action,
+        config_name,
+        service,
+        events,
+        values.get("Latest").cloned().unwrap_or_default()
+    ))
+}
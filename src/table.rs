@@ -0,0 +1,479 @@
+use std::cmp::Ordering;
+
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState},
+};
+
+use crate::{agents, cache, catalog, certs, dedup, environment, features, fsrm, golden, hosts, hyperv, iis, jobs, macros, network, offsite, processes, profiles, remote, security, sessions, tasks, tls, wbadmin, wsl};
+
+/// Screens that show rows loaded from a system query in a filterable table.
+#[derive(Clone, PartialEq)]
+pub enum TableKind {
+    ListeningPorts,
+    /// Name to resolve, optionally followed by extra DNS servers.
+    DnsLookup(String),
+    PersistentRoutes,
+    HostsFile,
+    EnvironmentVariables,
+    PathEntries,
+    /// Tasks under `\Microsoft\` are hidden unless `include_system` is set.
+    ScheduledTasks { include_system: bool },
+    Processes,
+    RdpSessions,
+    Agents,
+    Features,
+    Jobs,
+    LastBackup,
+    SystemStateBackups,
+    NetworkAdapters,
+    /// Advanced driver properties of the named adapter.
+    AdapterProperties(String),
+    WslDistros,
+    IisSites,
+    /// Machine certificates offered for a new HTTPS binding on the named site.
+    Certificates(String),
+    FsrmQuotas,
+    FsrmFileScreens,
+    DedupVolumes,
+    VirtualMachines,
+    AccountPolicy,
+    Schannel,
+    DownloadCache,
+    Profiles,
+    Macros,
+    OffsiteBackups,
+    BackupCatalog,
+    GoldenBaselines,
+    /// Role backups on the named server or share.
+    RemoteBackups(String),
+}
+
+impl TableKind {
+    pub fn title(&self) -> &'static str {
+        match self {
+            TableKind::ListeningPorts => "Listening Ports",
+            TableKind::DnsLookup(_) => "DNS Lookup",
+            TableKind::PersistentRoutes => "Persistent Routes",
+            TableKind::HostsFile => "Hosts File",
+            TableKind::EnvironmentVariables => "Machine Environment Variables",
+            TableKind::PathEntries => "Machine PATH",
+            TableKind::ScheduledTasks { include_system: false } => "Scheduled Tasks",
+            TableKind::ScheduledTasks { include_system: true } => "Scheduled Tasks (including Microsoft)",
+            TableKind::Processes => "Processes",
+            TableKind::RdpSessions => "Remote Desktop Sessions",
+            TableKind::Agents => "Agent Catalog",
+            TableKind::Features => "Roles & Features",
+            TableKind::Jobs => "Jobs",
+            TableKind::LastBackup => "Last Backup",
+            TableKind::SystemStateBackups => "System State Backups",
+            TableKind::NetworkAdapters => "Network Adapters",
+            TableKind::AdapterProperties(_) => "Adapter Advanced Properties",
+            TableKind::WslDistros => "WSL Distributions",
+            TableKind::IisSites => "IIS Sites",
+            TableKind::Certificates(_) => "Select Certificate for HTTPS Binding",
+            TableKind::FsrmQuotas => "FSRM Quotas",
+            TableKind::FsrmFileScreens => "FSRM File Screens",
+            TableKind::DedupVolumes => "Data Deduplication",
+            TableKind::VirtualMachines => "Hyper-V Virtual Machines",
+            TableKind::AccountPolicy => "Password & Lockout Policy (Local)",
+            TableKind::Schannel => "TLS Protocols & Ciphers (Schannel)",
+            TableKind::DownloadCache => "Download Cache",
+            TableKind::Profiles => "Profiles",
+            TableKind::Macros => "Macros",
+            TableKind::OffsiteBackups => "Offsite Backup Uploads",
+            TableKind::BackupCatalog => "Backup Catalog",
+            TableKind::GoldenBaselines => "Golden Baselines",
+            TableKind::RemoteBackups(_) => "Backups on Another Server",
+        }
+    }
+
+    /// Table that Esc returns to, for drill-down screens.
+    pub fn parent(&self) -> Option<TableKind> {
+        match self {
+            TableKind::PathEntries => Some(TableKind::EnvironmentVariables),
+            TableKind::AdapterProperties(_) => Some(TableKind::NetworkAdapters),
+            TableKind::Certificates(_) => Some(TableKind::IisSites),
+            TableKind::FsrmFileScreens => Some(TableKind::FsrmQuotas),
+            _ => None,
+        }
+    }
+
+    /// Extra keys handled for this kind, shown in the footer.
+    pub fn actions_hint(&self) -> &'static str {
+        match self {
+            TableKind::ListeningPorts | TableKind::LastBackup => "",
+            TableKind::DnsLookup(_) => "f: Flush DNS cache | ",
+            TableKind::PersistentRoutes | TableKind::HostsFile | TableKind::PathEntries => "a: Add | d: Remove | ",
+            TableKind::EnvironmentVariables => "Enter/e: Edit | a: Add | d: Delete | p: Reload PATH here | ",
+            TableKind::ScheduledTasks { .. } => "Enter: Last run | s: Run now | t: Enable/Disable | m: Microsoft tasks | ",
+            TableKind::Processes => "d: Kill | ",
+            TableKind::RdpSessions => "m: Message | c: Shadow | x: Disconnect | d: Log off | ",
+            TableKind::Agents => "i: Install here | f: Install on fleet | e: Edit catalog | ",
+            TableKind::Features => "i: Install | d: Remove | v: View jobs | ",
+            TableKind::Jobs => "Enter: View log | ",
+            TableKind::SystemStateBackups => "b: Back up now | v: View jobs | ",
+            TableKind::NetworkAdapters => "Enter: Advanced properties | v: Set VLAN ID | ",
+            TableKind::AdapterProperties(_) => "Enter/e: Edit value | ",
+            TableKind::WslDistros => "Enter: Enable WSL and install | ",
+            TableKind::IisSites => "s: Start | t: Stop | c: Recycle pool | h: Add HTTPS binding | ",
+            TableKind::Certificates(_) => "Enter: Bind | ",
+            TableKind::FsrmQuotas => "a: Add quota | s: File screens | ",
+            TableKind::FsrmFileScreens => "a: Add file screen | ",
+            TableKind::DedupVolumes => "Enter: Enable with usage type | ",
+            TableKind::VirtualMachines => "Enter: Recent events | s: Start | t: Stop | c: Checkpoint | x: Export | ",
+            TableKind::AccountPolicy => "Enter/e: Edit value | ",
+            TableKind::Schannel => "b: Apply modern baseline | ",
+            TableKind::DownloadCache => "d: Remove | p: Purge all | ",
+            TableKind::Profiles => "Enter: Switch to | a: Add | n: Edit settings | ",
+            TableKind::Macros => "Enter: Replay | d: Delete | m on the menu records one | ",
+            TableKind::OffsiteBackups => "Enter: Upload selected | u: Retry queued | ",
+            TableKind::BackupCatalog => "Enter: Edit tags & note | g: Mark as golden | ",
+            TableKind::GoldenBaselines => "Enter: Compare a server | g on Backup Catalog sets one | ",
+            TableKind::RemoteBackups(_) => "Enter: Copy here and restore | ",
+        }
+    }
+
+    pub fn headers(&self) -> &'static [&'static str] {
+        match self {
+            TableKind::ListeningPorts => &["Proto", "Address", "Port", "PID", "Process", "Services"],
+            TableKind::DnsLookup(_) => &["Source", "Server", "Time", "Answers"],
+            TableKind::PersistentRoutes => &["Destination", "Next Hop", "Metric", "Interface"],
+            TableKind::HostsFile => &["Line", "Type", "Address", "Hostnames", "Comment"],
+            TableKind::EnvironmentVariables => &["Name", "Value"],
+            TableKind::PathEntries => &["#", "Directory", "Exists"],
+            TableKind::ScheduledTasks { .. } => &["Path", "Name", "State", "Last Run", "Last Result", "Next Run"],
+            TableKind::Processes => &["PID", "Name", "CPU (s)", "Memory (MB)", "Session", "Path"],
+            TableKind::RdpSessions => &["ID", "Session", "User", "State", "Type", "This Session"],
+            TableKind::Agents => &["Agent", "Source", "Silent Arguments", "This Host", "License Agreement"],
+            TableKind::Features => &["Feature", "Name", "State"],
+            TableKind::Jobs => &["#", "Job", "State", "Restart", "Duration"],
+            TableKind::LastBackup => &["Display Name", "Name", "Type", "Path"],
+            TableKind::SystemStateBackups => &["Backup Time", "Location", "Version", "Can Recover"],
+            TableKind::NetworkAdapters => &["Name", "Description", "Status", "Link Speed", "MAC", "VLAN"],
+            TableKind::AdapterProperties(_) => &["Property", "Value", "Keyword", "Allowed Values"],
+            TableKind::WslDistros => &["Name", "Friendly Name"],
+            TableKind::IisSites => &["Site", "State", "App Pool", "Pool State", "Bindings", "Path"],
+            TableKind::Certificates(_) => &["Thumbprint", "Subject", "Names", "Expires", "Issuer"],
+            TableKind::FsrmQuotas => &["Path", "Limit", "Type", "Used", "Used %", "Template"],
+            TableKind::FsrmFileScreens => &["Path", "Mode", "Blocked File Groups", "Template"],
+            TableKind::VirtualMachines => &["Name", "State", "CPU", "vCPUs", "Memory (MB)", "Uptime", "Checkpoints"],
+            TableKind::AccountPolicy => &["Setting", "Current", "Recommended", "Allowed"],
+            TableKind::Schannel => &["Type", "Name", "Server", "Client", "Note"],
+            TableKind::DownloadCache => &["File", "Size (MB)", "Stored", "SHA-256", "URL"],
+            TableKind::RemoteBackups(_) => &["Backup", "Taken", "Reached Through", "Path"],
+            TableKind::OffsiteBackups => &["Backup", "Taken", "Offsite", "Attempts", "Last Attempt", "Detail"],
+            TableKind::BackupCatalog => &["Backup", "Taken", "Tags", "Note", "Golden For"],
+            TableKind::GoldenBaselines => &["Role Type", "Backup", "Contents", "Taken"],
+            TableKind::Macros => &["Macro", "Actions", "Steps", "File"],
+            TableKind::Profiles => {
+                &["Active", "Profile", "Backup Directory", "Proxy", "Offsite", "Agent Catalog", "Validation Baseline"]
+            }
+            TableKind::DedupVolumes => {
+                &["Volume", "Label", "Size", "Dedup", "Usage Type", "Saved", "Savings", "Last Optimization", "Jobs"]
+            }
+        }
+    }
+
+    pub fn load(&self) -> Result<Vec<Vec<String>>, String> {
+        match self {
+            TableKind::ListeningPorts => network::listening_ports(),
+            TableKind::DnsLookup(query) => network::dns_lookup(query),
+            TableKind::PersistentRoutes => network::persistent_routes(),
+            TableKind::HostsFile => hosts::hosts_entries(),
+            TableKind::EnvironmentVariables => environment::machine_variables(),
+            TableKind::PathEntries => environment::path_entries(),
+            TableKind::ScheduledTasks { include_system } => tasks::scheduled_tasks(*include_system),
+            TableKind::Processes => processes::processes(),
+            TableKind::RdpSessions => sessions::sessions(),
+            TableKind::Agents => agents::agent_rows(),
+            TableKind::Features => features::feature_rows(),
+            TableKind::Jobs => jobs::job_rows(),
+            TableKind::LastBackup => features::last_backup_rows(),
+            TableKind::SystemStateBackups => wbadmin::system_state_backups(),
+            TableKind::NetworkAdapters => network::adapters(),
+            TableKind::AdapterProperties(adapter) => network::adapter_properties(adapter),
+            TableKind::WslDistros => wsl::distros(),
+            TableKind::IisSites => iis::sites(),
+            TableKind::Certificates(_) => certs::machine_certificates(),
+            TableKind::FsrmQuotas => fsrm::quotas(),
+            TableKind::FsrmFileScreens => fsrm::file_screens(),
+            TableKind::DedupVolumes => dedup::volumes(),
+            TableKind::VirtualMachines => hyperv::vms(),
+            TableKind::AccountPolicy => security::account_policy(),
+            TableKind::Schannel => tls::schannel(),
+            TableKind::DownloadCache => cache::entries(),
+            TableKind::Profiles => profiles::rows(),
+            TableKind::Macros => macros::rows(),
+            TableKind::OffsiteBackups => offsite::rows(),
+            TableKind::BackupCatalog => catalog::rows(),
+            TableKind::GoldenBaselines => golden::rows(),
+            TableKind::RemoteBackups(source) => remote::backups(source),
+        }
+    }
+
+    /// Column the rows are sorted by when the screen is opened.
+    fn default_sort(&self) -> Option<usize> {
+        match self {
+            TableKind::Processes => Some(3),
+            _ => None,
+        }
+    }
+}
+
+/// Orders numbers first (largest first), then text alphabetically. `NaN` and `inf` count as
+/// text, so the order stays total.
+fn compare_cells(a: &str, b: &str) -> Ordering {
+    let number = |cell: &str| cell.parse::<f64>().ok().filter(|n| n.is_finite());
+    match (number(a), number(b)) {
+        (Some(x), Some(y)) => y.total_cmp(&x),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => a.to_lowercase().cmp(&b.to_lowercase()),
+    }
+}
+
+pub struct TableView {
+    pub kind: TableKind,
+    pub rows: Vec<Vec<String>>,
+    pub filter: String,
+    pub editing_filter: bool,
+    pub state: TableState,
+    /// Outcome of the last action taken on this screen.
+    pub status: String,
+    /// Column the rows are sorted by, `None` for the order the query returned.
+    pub sort_column: Option<usize>,
+}
+
+impl TableView {
+    pub fn new(kind: TableKind) -> Self {
+        Self {
+            kind,
+            rows: Vec::new(),
+            filter: String::new(),
+            editing_filter: false,
+            state: TableState::default(),
+            status: String::new(),
+            sort_column: None,
+        }
+    }
+
+    /// Runs the query for `kind` and resets filter and selection.
+    pub fn open(&mut self, kind: TableKind) -> Result<(), String> {
+        self.rows = kind.load()?;
+        self.sort_column = kind.default_sort();
+        self.kind = kind;
+        self.filter.clear();
+        self.editing_filter = false;
+        self.status.clear();
+        self.sort_rows();
+        self.reset_selection();
+        Ok(())
+    }
+
+    /// Re-runs the query, keeping the current filter.
+    pub fn refresh(&mut self) -> Result<(), String> {
+        self.rows = self.kind.load()?;
+        self.sort_rows();
+        self.reset_selection();
+        Ok(())
+    }
+
+    /// Swaps in freshly loaded rows without resetting the selection, for screens
+    /// that update while they are shown.
+    pub fn update_rows(&mut self, rows: Vec<Vec<String>>) {
+        self.rows = rows;
+        self.sort_rows();
+        let len = self.visible().len();
+        match self.state.selected() {
+            _ if len == 0 => self.state.select(None),
+            Some(i) if i >= len => self.state.select(Some(len - 1)),
+            None => self.state.select(Some(0)),
+            Some(_) => {}
+        }
+    }
+
+    /// Sorts by the next column, wrapping back to the original order after the last one.
+    /// Reloads so that going back to unsorted restores the query's order.
+    pub fn cycle_sort(&mut self) -> Result<(), String> {
+        self.sort_column = match self.sort_column {
+            None => Some(0),
+            Some(i) if i + 1 < self.kind.headers().len() => Some(i + 1),
+            Some(_) => None,
+        };
+        self.refresh()
+    }
+
+    fn sort_rows(&mut self) {
+        if let Some(column) = self.sort_column {
+            self.rows.sort_by(|a, b| {
+                compare_cells(
+                    a.get(column).map(String::as_str).unwrap_or(""),
+                    b.get(column).map(String::as_str).unwrap_or(""),
+                )
+            });
+        }
+    }
+
+    /// Indices into `rows` of the rows matching the filter (case-insensitive, any column).
+    pub fn visible(&self) -> Vec<usize> {
+        let needle = self.filter.to_lowercase();
+        self.rows
+            .iter()
+            .enumerate()
+            .filter(|(_, row)| needle.is_empty() || row.iter().any(|c| c.to_lowercase().contains(&needle)))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    pub fn selected_row(&self) -> Option<&Vec<String>> {
+        let visible = self.visible();
+        let i = *visible.get(self.state.selected()?)?;
+        self.rows.get(i)
+    }
+
+    pub fn next(&mut self) {
+        let len = self.visible().len();
+        if len == 0 {
+            return;
+        }
+        let i = match self.state.selected() {
+            Some(i) => {
+                if i >= len - 1 {
+                    0
+                } else {
+                    i + 1
+                }
+            }
+            None => 0,
+        };
+        self.state.select(Some(i));
+    }
+
+    pub fn previous(&mut self) {
+        let len = self.visible().len();
+        if len == 0 {
+            return;
+        }
+        let i = match self.state.selected() {
+            Some(i) => {
+                if i == 0 {
+                    len - 1
+                } else {
+                    i - 1
+                }
+            }
+            None => 0,
+        };
+        self.state.select(Some(i));
+    }
+
+    pub fn push_filter(&mut self, c: char) {
+        self.filter.push(c);
+        self.reset_selection();
+    }
+
+    pub fn pop_filter(&mut self) {
+        self.filter.pop();
+        self.reset_selection();
+    }
+
+    fn reset_selection(&mut self) {
+        if self.visible().is_empty() {
+            self.state.select(None);
+        } else {
+            self.state.select(Some(0));
+        }
+    }
+
+    pub fn footer(&self) -> String {
+        if self.editing_filter {
+            "Type to filter | Backspace: Delete | Enter/Esc: Done".to_string()
+        } else {
+            format!(
+                "↑/↓: Navigate | /: Filter | o: Sort | r: Refresh | {}Esc: Back",
+                self.kind.actions_hint()
+            )
+        }
+    }
+
+    pub fn render(&mut self, f: &mut Frame, area: Rect) {
+        let (area, status_area) = if self.status.is_empty() {
+            (area, None)
+        } else {
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(3), Constraint::Length(1)])
+                .split(area);
+            (chunks[0], Some(chunks[1]))
+        };
+
+        let headers = self.kind.headers();
+        let visible = self.visible();
+
+        // Size each column to its widest value, leaving the last one to take the rest
+        let mut widths: Vec<usize> = headers.iter().map(|h| h.len()).collect();
+        for &i in &visible {
+            for (w, cell) in widths.iter_mut().zip(&self.rows[i]) {
+                *w = (*w).max(cell.chars().count()).min(40);
+            }
+        }
+        let constraints: Vec<Constraint> = widths
+            .iter()
+            .enumerate()
+            .map(|(i, w)| {
+                if i == widths.len() - 1 {
+                    Constraint::Min(10)
+                } else {
+                    Constraint::Length(*w as u16)
+                }
+            })
+            .collect();
+
+        let header = Row::new(headers.iter().map(|h| Cell::from(*h)))
+            .style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD));
+        let rows: Vec<Row> = visible
+            .iter()
+            .map(|&i| Row::new(self.rows[i].iter().map(|c| Cell::from(c.as_str()))))
+            .collect();
+
+        let mut title = if self.editing_filter || !self.filter.is_empty() {
+            format!(
+                " {} ({}/{}) - filter: {}{} ",
+                self.kind.title(),
+                visible.len(),
+                self.rows.len(),
+                self.filter,
+                if self.editing_filter { "_" } else { "" }
+            )
+        } else {
+            format!(" {} ({}) ", self.kind.title(), self.rows.len())
+        };
+        if let Some(name) = self.sort_column.and_then(|i| headers.get(i)) {
+            title.push_str(&format!("- sorted by {} ", name));
+        }
+
+        let table = Table::new(rows, constraints)
+            .header(header)
+            .block(
+                Block::default()
+                    .title(title)
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Blue)),
+            )
+            .highlight_style(
+                Style::default()
+                    .bg(Color::Blue)
+                    .fg(Color::White)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .highlight_symbol(">> ");
+
+        f.render_stateful_widget(table, area, &mut self.state);
+
+        if let Some(status_area) = status_area {
+            let status = Paragraph::new(format!(" {}", self.status)).style(Style::default().fg(Color::Yellow));
+            f.render_widget(status, status_area);
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use crate::exec;
+
+const TASKS_SCRIPT: &str = r#"
+Get-ScheduledTask | Where-Object { $includeSystem -or $_.TaskPath -notlike '\Microsoft\*' } | ForEach-Object {
+    $i = $_ | Get-ScheduledTaskInfo -ErrorAction SilentlyContinue
+    "$($_.TaskPath)`t$($_.TaskName)`t$($_.State)`t$($i.LastRunTime)`t$($i.LastTaskResult)`t$($i.NextRunTime)"
+}
+"#;
+
+/// Describes the common Task Scheduler result codes.
+fn describe_result(code: &str) -> String {
+    let value: i64 = match code.parse() {
+        Ok(v) => v,
+        Err(_) => return code.to_string(),
+    };
+    let hex = format!("0x{:X}", value as u32);
+    match value as u32 {
+        0 => format!("{} (success)", hex),
+        0x41300 => format!("{} (ready)", hex),
+        0x41301 => format!("{} (running)", hex),
+        0x41303 => format!("{} (never run)", hex),
+        0x41306 => format!("{} (terminated)", hex),
+        0x8004131F => format!("{} (already running)", hex),
+        _ => format!("{} (failed)", hex),
+    }
+}
+
+/// Lists scheduled tasks, leaving out the built-in `\Microsoft\` tree unless `include_system` is set.
+pub fn scheduled_tasks(include_system: bool) -> Result<Vec<Vec<String>>, String> {
+    let script = format!(
+        "$includeSystem = ${}; {}",
+        if include_system { "true" } else { "false" },
+        TASKS_SCRIPT
+    );
+    let output = exec::powershell(&script).map_err(|e| format!("Failed to list scheduled tasks: {}", e))?;
+    if !output.success {
+        return Err(format!("Failed to list scheduled tasks:\n{}", output.stderr.trim()));
+    }
+    let mut rows: Vec<Vec<String>> = exec::tab_rows(&output.stdout)
+        .into_iter()
+        .filter(|row| row.len() == 6)
+        .map(|mut row| {
+            row[4] = describe_result(&row[4]);
+            row
+        })
+        .collect();
+    rows.sort_by_key(|row| format!("{}{}", row[0], row[1]).to_lowercase());
+    Ok(rows)
+}
+
+fn task_command(verb: &str, path: &str, name: &str) -> Result<(), String> {
+    let script = format!(
+        "{} -TaskPath {} -TaskName {} -ErrorAction Stop | Out-Null",
+        verb,
+        exec::quote(path),
+        exec::quote(name)
+    );
+    match exec::change(&script) {
+        Ok(output) if output.success => Ok(()),
+        Ok(output) => Err(output.stderr.trim().to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+pub fn run_task(path: &str, name: &str) -> Result<String, String> {
+    task_command("Start-ScheduledTask", path, name)
+        .map(|()| format!("Started {}{}", path, name))
+        .map_err(|e| format!("Failed to start {}: {}", name, e))
+}
+
+/// Enables a disabled task, or disables it otherwise.
+pub fn toggle_task(path: &str, name: &str, state: &str) -> Result<String, String> {
+    let (verb, done) = if state == "Disabled" {
+        ("Enable-ScheduledTask", "Enabled")
+    } else {
+        ("Disable-ScheduledTask", "Disabled")
+    };
+    task_command(verb, path, name)
+        .map(|()| format!("{} {}{}", done, path, name))
+        .map_err(|e| format!("Failed to change {}: {}", name, e))
+}
+
+const DETAILS_SCRIPT: &str = r#"
+$full = $path + $name
+$t = Get-ScheduledTask -TaskPath $path -TaskName $name -ErrorAction Stop
+$i = $t | Get-ScheduledTaskInfo
+"Task:       $full"
+"State:      $($t.State)"
+"Run as:     $($t.Principal.UserId) ($($t.Principal.RunLevel))"
+"Last run:   $($i.LastRunTime)"
+"Next run:   $($i.NextRunTime)"
+""
+"Actions:"
+$t.Actions | ForEach-Object { "  $($_.Execute) $($_.Arguments)" }
+""
+"Triggers:"
+$t.Triggers | ForEach-Object { "  $($_.CimClass.CimClassName -replace '^MSFT_Task', '') $($_.StartBoundary)" }
+""
+"Last run history:"
+$xpath = "*[EventData[Data[@Name='TaskName']='$full']]"
+$events = Get-WinEvent -LogName 'Microsoft-Windows-TaskScheduler/Operational' -FilterXPath $xpath -MaxEvents 50 -ErrorAction SilentlyContinue
+if ($events) {
+    $last = $events | Select-Object -First 1
+    $events | Where-Object { $_.ActivityId -eq $last.ActivityId } | Sort-Object TimeCreated | ForEach-Object {
+        "  $($_.TimeCreated)  [$($_.Id)] $($_.Message)"
+    }
+} else {
+    "  No history found. Task history may be disabled; enable it with:"
+    "  wevtutil set-log Microsoft-Windows-TaskScheduler/Operational /enabled:true"
+}
+"#;
+
+/// Shows a task's definition and the Task Scheduler events of its most recent run.
+pub fn task_details(path: &str, name: &str) -> Result<String, String> {
+    let script = format!("$path = {}; $name = {}; {}", exec::quote(path), exec::quote(name), DETAILS_SCRIPT);
+    let output = exec::powershell(&script).map_err(|e| format!("Failed to read task: {}", e))?;
+    if !output.success && output.stdout.trim().is_empty() {
+        return Err(format!("Failed to read task:\n{}", output.stderr.trim()));
+    }
+    Ok(output.stdout.trim_end().to_string())
+}
@@ -0,0 +1,161 @@
+use crate::exec;
+use crate::{CheckItem, CheckStatus, Remediation};
+
+const DATA_COLLECTION_KEY: &str = "HKLM:\\SOFTWARE\\Policies\\Microsoft\\Windows\\DataCollection";
+const CLOUD_CONTENT_KEY: &str = "HKLM:\\SOFTWARE\\Policies\\Microsoft\\Windows\\CloudContent";
+
+/// The `AllowTelemetry` levels. Hardening baselines accept Security or Required on servers.
+const LEVELS: [(u32, &str); 4] = [(0, "Security"), (1, "Required"), (2, "Enhanced"), (3, "Optional")];
+
+/// Policy values and the DiagTrack service, as `key=value` lines.
+const STATUS_SCRIPT: &str = r#"
+$data = Get-ItemProperty 'HKLM:\SOFTWARE\Policies\Microsoft\Windows\DataCollection' -ErrorAction SilentlyContinue
+$cloud = Get-ItemProperty 'HKLM:\SOFTWARE\Policies\Microsoft\Windows\CloudContent' -ErrorAction SilentlyContinue
+"Level=$($data.AllowTelemetry)"
+"FeedbackNotifications=$($data.DoNotShowFeedbackNotifications)"
+"ConsumerFeatures=$($cloud.DisableWindowsConsumerFeatures)"
+"ConsumerAccountContent=$($cloud.DisableConsumerAccountStateContent)"
+$service = Get-Service DiagTrack -ErrorAction SilentlyContinue
+"DiagTrack=$($service.Status)"
+"DiagTrackStart=$($service.StartType)"
+"#;
+
+fn level_name(level: u32) -> &'static str {
+    LEVELS.iter().find(|(l, _)| *l == level).map_or("Unknown", |(_, name)| name)
+}
+
+/// Reads `0`-`3` or a level name such as `required`.
+pub fn parse_level(text: &str) -> Result<u32, String> {
+    let text = text.trim();
+    LEVELS
+        .iter()
+        .find(|(level, name)| text == level.to_string() || text.eq_ignore_ascii_case(name))
+        .map(|(level, _)| *level)
+        .ok_or_else(|| "Enter 0 (Security), 1 (Required), 2 (Enhanced) or 3 (Optional).".to_string())
+}
+
+/// PowerShell that sets a DWORD policy value, creating the key if needed.
+fn set_policy(key: &str, name: &str, value: u32) -> String {
+    format!(
+        "$k = '{}'; if (-not (Test-Path $k)) {{ New-Item -Path $k -Force | Out-Null }}; \
+        New-ItemProperty -Path $k -Name {} -Value {} -PropertyType DWord -Force | Out-Null",
+        key, name, value
+    )
+}
+
+/// The diagnostic data level, the consumer experience policies and the DiagTrack service.
+/// Failed items carry the registry change that fixes them.
+pub fn checks() -> Result<Vec<CheckItem>, String> {
+    let output =
+        exec::powershell(STATUS_SCRIPT).map_err(|e| format!("Failed to read the diagnostic data settings: {}", e))?;
+    if !output.success {
+        return Err(format!("Failed to read the diagnostic data settings:\n{}", output.stderr.trim()));
+    }
+    let values = exec::key_values(&output.stdout);
+    let value = |key: &str| values.get(key).map(|v| v.trim().to_string()).unwrap_or_default();
+    let policy = |name: &str, key: &str, value_name: &str, set: bool, summary: &str, off: &str, on: &str| CheckItem {
+        name: name.to_string(),
+        status: if set { CheckStatus::Pass } else { CheckStatus::Fail },
+        detail: if set { on } else { off }.to_string(),
+        remediation: (!set).then(|| Remediation { summary: summary.to_string(), script: set_policy(key, value_name, 1) }),
+        advice: None,
+    };
+
+    let level = value("Level").parse::<u32>().ok();
+    let mut items = vec![CheckItem {
+        name: "Diagnostic data level".to_string(),
+        status: match level {
+            Some(0 | 1) => CheckStatus::Pass,
+            _ => CheckStatus::Fail,
+        },
+        detail: match level {
+            Some(level) => format!("{} ({}) by policy", level_name(level), level),
+            None => "Not set by policy: Windows sends Required data, or more if changed in Settings".to_string(),
+        },
+        remediation: (!matches!(level, Some(0 | 1))).then(|| Remediation {
+            summary: "Limit diagnostic data to Required (AllowTelemetry = 1) via local policy. Press l to pick another level."
+                .to_string(),
+            script: set_policy(DATA_COLLECTION_KEY, "AllowTelemetry", 1),
+        }),
+        advice: None,
+    }];
+    items.push(policy(
+        "Consumer experiences",
+        CLOUD_CONTENT_KEY,
+        "DisableWindowsConsumerFeatures",
+        value("ConsumerFeatures") == "1",
+        "Turn off Microsoft consumer experiences (suggested apps and content) via local policy.",
+        "Not disabled",
+        "Disabled by policy",
+    ));
+    items.push(policy(
+        "Consumer account content",
+        CLOUD_CONTENT_KEY,
+        "DisableConsumerAccountStateContent",
+        value("ConsumerAccountContent") == "1",
+        "Stop showing Microsoft account state content via local policy.",
+        "Not disabled",
+        "Disabled by policy",
+    ));
+    items.push(policy(
+        "Feedback notifications",
+        DATA_COLLECTION_KEY,
+        "DoNotShowFeedbackNotifications",
+        value("FeedbackNotifications") == "1",
+        "Stop Windows from asking for feedback via local policy.",
+        "Shown",
+        "Hidden by policy",
+    ));
+
+    let (service, start) = (value("DiagTrack"), value("DiagTrackStart"));
+    items.push(CheckItem {
+        name: "Connected User Experiences and Telemetry service".to_string(),
+        status: if service.is_empty() { CheckStatus::Unknown } else { CheckStatus::Pass },
+        detail: if service.is_empty() { "Not found".to_string() } else { format!("{}, {}", service, start) },
+        remediation: None,
+        advice: (!service.is_empty() && !start.eq_ignore_ascii_case("Disabled"))
+            .then(|| "It only sends what the level above allows; leave it running unless your baseline says otherwise.".to_string()),
+    });
+    Ok(items)
+}
+
+/// Sets the diagnostic data level by policy.
+pub fn set_level(text: &str) -> Result<String, String> {
+    let level = parse_level(text)?;
+    match exec::change(&set_policy(DATA_COLLECTION_KEY, "AllowTelemetry", level)) {
+        Ok(output) if output.success => Ok(format!("Diagnostic data level set to {} ({}).", level_name(level), level)),
+        Ok(output) => Err(format!("Failed to set the diagnostic data level:\n{}", output.stderr.trim())),
+        Err(e) => Err(format!("Failed to set the diagnostic data level: {}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::FakeRunner;
+
+    #[test]
+    fn flags_enhanced_diagnostic_data_and_offers_required() {
+        FakeRunner::new()
+            .on("DataCollection", "Level=2\nConsumerFeatures=1\nDiagTrack=Running\nDiagTrackStart=Automatic\n")
+            .install();
+
+        let items = checks().unwrap_or_default();
+
+        assert!(items[0].status == CheckStatus::Fail);
+        assert_eq!(items[0].detail, "Enhanced (2) by policy");
+        assert!(items[0].remediation.as_ref().is_some_and(|r| r.script.contains("-Name AllowTelemetry -Value 1 ")));
+        assert!(items[1].status == CheckStatus::Pass && items[1].remediation.is_none());
+        assert!(items[3].status == CheckStatus::Fail);
+    }
+
+    #[test]
+    fn sets_the_level_by_number_or_name() {
+        let runner = FakeRunner::new().install();
+
+        assert_eq!(parse_level("Security"), Ok(0));
+        assert!(parse_level("4").is_err());
+        assert_eq!(set_level("required"), Ok("Diagnostic data level set to Required (1).".to_string()));
+        assert!(runner.ran("-Name AllowTelemetry -Value 1 -PropertyType DWord"));
+    }
+}
@@ -0,0 +1,99 @@
+//! A scripted [`CommandRunner`] so flows that shell out can be tested without Windows.
+
+use std::{
+    io,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use crate::exec::{self, CommandOutput, CommandRunner};
+
+type Reply = Box<dyn Fn(&str) -> io::Result<CommandOutput> + Send + Sync>;
+
+/// Answers commands from rules matched by substring, in the order they were added, and
+/// records every command it sees. PowerShell without a rule succeeds with no output,
+/// programs without a rule are not found, and downloads without a rule write a small file.
+pub struct FakeRunner {
+    rules: Vec<(String, Reply)>,
+    calls: Mutex<Vec<String>>,
+}
+
+pub fn output(success: bool, stdout: &str, stderr: &str) -> CommandOutput {
+    CommandOutput { success, stdout: stdout.to_string(), stderr: stderr.to_string() }
+}
+
+impl FakeRunner {
+    pub fn new() -> Self {
+        FakeRunner { rules: Vec::new(), calls: Mutex::new(Vec::new()) }
+    }
+
+    /// Commands containing `pattern` succeed and print `stdout`.
+    pub fn on(self, pattern: &str, stdout: &str) -> Self {
+        let stdout = stdout.to_string();
+        self.on_with(pattern, move |_| Ok(output(true, &stdout, "")))
+    }
+
+    /// Commands containing `pattern` fail with `stderr`.
+    pub fn fail(self, pattern: &str, stderr: &str) -> Self {
+        let stderr = stderr.to_string();
+        self.on_with(pattern, move |_| Ok(output(false, "", &stderr)))
+    }
+
+    /// Commands containing `pattern` are answered by `reply`, which gets the full command.
+    pub fn on_with(mut self, pattern: &str, reply: impl Fn(&str) -> io::Result<CommandOutput> + Send + Sync + 'static) -> Self {
+        self.rules.push((pattern.to_string(), Box::new(reply)));
+        self
+    }
+
+    /// Makes this thread (and the workers it starts) run commands through the fake.
+    pub fn install(self) -> Arc<FakeRunner> {
+        let runner = Arc::new(self);
+        exec::set_runner(runner.clone());
+        runner
+    }
+
+    /// Every command seen so far: scripts as written, programs as `program arg...`,
+    /// downloads as `download <url>`.
+    pub fn calls(&self) -> Vec<String> {
+        self.calls.lock().map(|c| c.clone()).unwrap_or_default()
+    }
+
+    pub fn ran(&self, pattern: &str) -> bool {
+        self.calls().iter().any(|c| c.contains(pattern))
+    }
+
+    fn answer(&self, command: String) -> Option<io::Result<CommandOutput>> {
+        if let Ok(mut calls) = self.calls.lock() {
+            calls.push(command.clone());
+        }
+        self.rules.iter().find(|(pattern, _)| command.contains(pattern.as_str())).map(|(_, reply)| reply(&command))
+    }
+}
+
+impl CommandRunner for FakeRunner {
+    fn powershell(&self, script: &str) -> io::Result<CommandOutput> {
+        self.answer(script.to_string()).unwrap_or_else(|| Ok(output(true, "", "")))
+    }
+
+    fn run(&self, program: &str, args: &[&str]) -> io::Result<CommandOutput> {
+        let command = std::iter::once(program).chain(args.iter().copied()).collect::<Vec<_>>().join(" ");
+        self.answer(command)
+            .unwrap_or_else(|| Err(io::Error::new(io::ErrorKind::NotFound, format!("{} not found", program))))
+    }
+
+    fn download(&self, url: &str, path: &Path) -> Result<u64, String> {
+        match self.answer(format!("download {}", url)) {
+            Some(Ok(out)) if !out.success => Err(out.stderr.trim().to_string()),
+            Some(Err(e)) => Err(e.to_string()),
+            _ => std::fs::write(path, b"fake download").map(|_| 13).map_err(|e| e.to_string()),
+        }
+    }
+}
+
+/// A fresh directory under the system temp directory, for tests that need real files.
+pub fn temp_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("server-helper-test-{}-{}", std::process::id(), name));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("create test directory");
+    dir
+}
@@ -0,0 +1,181 @@
+use crate::{
+    backup_dir, exec,
+    plan::{Plan, Step},
+};
+
+const SCHANNEL: &str = r"SYSTEM\CurrentControlSet\Control\SecurityProviders\SCHANNEL";
+
+/// Protocols disabled by the modern baseline.
+const LEGACY_PROTOCOLS: [&str; 4] = ["SSL 2.0", "SSL 3.0", "TLS 1.0", "TLS 1.1"];
+
+/// Ciphers disabled by the modern baseline.
+const WEAK_CIPHERS: [&str; 7] =
+    ["NULL", "DES 56/56", "RC4 40/128", "RC4 56/128", "RC4 64/128", "RC4 128/128", "Triple DES 168"];
+
+/// One tab-separated row per protocol, cipher and enabled cipher suite. Keys are opened through
+/// .NET because cipher names contain `/`, which the registry provider treats as a separator.
+const SCHANNEL_SCRIPT: &str = r#"
+$root = 'SYSTEM\CurrentControlSet\Control\SecurityProviders\SCHANNEL'
+function Get-State($path) {
+    $key = [Microsoft.Win32.Registry]::LocalMachine.OpenSubKey("$root\$path")
+    $value = if ($key) { $key.GetValue('Enabled') }
+    if ($null -eq $value) { 'Default' } elseif ($value -eq 0) { 'Disabled' } else { 'Enabled' }
+}
+foreach ($p in 'SSL 2.0', 'SSL 3.0', 'TLS 1.0', 'TLS 1.1', 'TLS 1.2', 'TLS 1.3') {
+    "Protocol`t$p`t$(Get-State "Protocols\$p\Server")`t$(Get-State "Protocols\$p\Client")"
+}
+foreach ($c in 'NULL', 'DES 56/56', 'RC4 40/128', 'RC4 56/128', 'RC4 64/128', 'RC4 128/128', 'Triple DES 168', 'AES 128/128', 'AES 256/256') {
+    "Cipher`t$c`t$(Get-State "Ciphers\$c")`t"
+}
+if (Get-Command Get-TlsCipherSuite -ErrorAction SilentlyContinue) {
+    Get-TlsCipherSuite | ForEach-Object { "Cipher suite`t$($_.Name)`tEnabled`t" }
+}
+"#;
+
+/// Why the baseline disables a protocol, cipher or suite, or a remark about it.
+fn note(kind: &str, name: &str) -> &'static str {
+    match kind {
+        "Protocol" if name.starts_with("SSL") => "Broken; disabled by the baseline",
+        "Protocol" if LEGACY_PROTOCOLS.contains(&name) => "Legacy; disabled by the baseline",
+        "Protocol" if name == "TLS 1.3" => "Server 2022 and later",
+        "Cipher" if WEAK_CIPHERS.contains(&name) => "Weak; disabled by the baseline",
+        "Cipher suite" if weak_suite(name) => "Weak; disabled by the baseline",
+        "Cipher suite" if name.starts_with("TLS_RSA_") => "No forward secrecy",
+        _ => "",
+    }
+}
+
+fn weak_suite(name: &str) -> bool {
+    ["_RC4_", "_3DES_", "_DES_", "_NULL_", "_EXPORT", "_MD5"].iter().any(|weak| name.contains(weak))
+}
+
+/// Schannel protocols and ciphers with their server/client state, plus the enabled cipher suites in order.
+pub fn schannel() -> Result<Vec<Vec<String>>, String> {
+    let output = exec::powershell(SCHANNEL_SCRIPT).map_err(|e| format!("Failed to read Schannel settings: {}", e))?;
+    if !output.success {
+        return Err(format!("Failed to read Schannel settings:\n{}", output.stderr.trim()));
+    }
+    Ok(exec::tab_rows(&output.stdout)
+        .into_iter()
+        .filter(|r| r.len() >= 3)
+        .map(|mut r| {
+            r.resize(4, String::new());
+            r.push(note(&r[0], &r[1]).to_string());
+            r
+        })
+        .collect())
+}
+
+/// Disables a protocol for both the server and client side of Schannel.
+pub fn disable_protocol_script(protocol: &str) -> String {
+    format!(
+        "foreach ($side in 'Server', 'Client') {{ \
+            $k = 'HKLM:\\{}\\Protocols\\{}\\' + $side; \
+            if (-not (Test-Path $k)) {{ New-Item -Path $k -Force | Out-Null }}; \
+            New-ItemProperty -Path $k -Name Enabled -Value 0 -PropertyType DWord -Force | Out-Null; \
+            New-ItemProperty -Path $k -Name DisabledByDefault -Value 1 -PropertyType DWord -Force | Out-Null \
+        }}",
+        SCHANNEL, protocol
+    )
+}
+
+/// Plans the modern baseline: legacy protocols, weak ciphers and weak cipher suites off, TLS 1.2
+/// on and .NET using the OS protocol defaults. The Schannel and cipher suite keys are exported
+/// to the backup directory first.
+pub fn baseline_plan() -> Result<Plan, String> {
+    let rows = schannel()?;
+    let state = |kind: &str, name: &str| rows.iter().find(|r| r[0] == kind && r[1] == name).cloned();
+    let mut plan = Plan::new("Apply TLS baseline");
+
+    for protocol in LEGACY_PROTOCOLS {
+        match state("Protocol", protocol) {
+            Some(r) if r[2] == "Disabled" && r[3] == "Disabled" => {}
+            _ => plan.step(format!("Disable {} (server and client)", protocol), disable_protocol_script(protocol)),
+        }
+    }
+    if let Some(r) = state("Protocol", "TLS 1.2").filter(|r| r[2] == "Disabled" || r[3] == "Disabled") {
+        plan.step(
+            format!("Enable TLS 1.2 (server {}, client {})", r[2].to_lowercase(), r[3].to_lowercase()),
+            format!(
+                "foreach ($side in 'Server', 'Client') {{ \
+                    $k = 'HKLM:\\{}\\Protocols\\TLS 1.2\\' + $side; \
+                    if (-not (Test-Path $k)) {{ New-Item -Path $k -Force | Out-Null }}; \
+                    New-ItemProperty -Path $k -Name Enabled -Value 1 -PropertyType DWord -Force | Out-Null; \
+                    New-ItemProperty -Path $k -Name DisabledByDefault -Value 0 -PropertyType DWord -Force | Out-Null \
+                }}",
+                SCHANNEL
+            ),
+        );
+    }
+    for cipher in WEAK_CIPHERS {
+        if state("Cipher", cipher).is_some_and(|r| r[2] != "Disabled") {
+            plan.step(
+                format!("Disable the {} cipher", cipher),
+                format!(
+                    "$k = [Microsoft.Win32.Registry]::LocalMachine.CreateSubKey({})\n\
+                    $k.SetValue('Enabled', 0, 'DWord'); $k.Close()",
+                    exec::quote(&format!("{}\\Ciphers\\{}", SCHANNEL, cipher))
+                ),
+            );
+        }
+    }
+    for suite in rows.iter().filter(|r| r[0] == "Cipher suite" && weak_suite(&r[1])) {
+        plan.step(
+            format!("Disable the {} cipher suite", suite[1]),
+            format!("Disable-TlsCipherSuite -Name {} -ErrorAction Stop", exec::quote(&suite[1])),
+        );
+    }
+
+    let dotnet = exec::powershell(
+        "foreach ($k in 'HKLM:\\SOFTWARE\\Microsoft\\.NETFramework\\v4.0.30319', 'HKLM:\\SOFTWARE\\WOW6432Node\\Microsoft\\.NETFramework\\v4.0.30319') {\n\
+            $p = Get-ItemProperty -Path $k -ErrorAction SilentlyContinue\n\
+            \"$k=$($p.SchUseStrongCrypto)$($p.SystemDefaultTlsVersions)\"\n\
+        }",
+    )
+    .map(|o| exec::key_values(&o.stdout))
+    .unwrap_or_default();
+    if dotnet.values().any(|v| v != "11") {
+        plan.step(
+            "Let .NET Framework 4 applications use TLS 1.2 (SchUseStrongCrypto, SystemDefaultTlsVersions)",
+            "foreach ($k in 'HKLM:\\SOFTWARE\\Microsoft\\.NETFramework\\v4.0.30319', 'HKLM:\\SOFTWARE\\WOW6432Node\\Microsoft\\.NETFramework\\v4.0.30319') {\n\
+                if (-not (Test-Path $k)) { New-Item -Path $k -Force | Out-Null }\n\
+                New-ItemProperty -Path $k -Name SchUseStrongCrypto -Value 1 -PropertyType DWord -Force | Out-Null\n\
+                New-ItemProperty -Path $k -Name SystemDefaultTlsVersions -Value 1 -PropertyType DWord -Force | Out-Null\n\
+            }",
+        );
+    }
+
+    if plan.steps.is_empty() {
+        plan.note("The baseline is already in place.");
+        return Ok(plan);
+    }
+
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let schannel_backup = backup_dir().join(format!("Schannel_{}.reg", timestamp));
+    let suites_backup = backup_dir().join(format!("TlsCipherSuites_{}.reg", timestamp));
+    plan.steps.insert(0, Step {
+        description: format!("Back up the Schannel and cipher suite registry keys to {}", backup_dir().display()),
+        command: format!(
+            "New-Item -ItemType Directory -Path {} -Force | Out-Null\n\
+            & reg.exe export 'HKLM\\{}' {} /y\n\
+            if ($LASTEXITCODE -ne 0) {{ throw \"reg export failed with exit code $LASTEXITCODE\" }}\n\
+            & reg.exe export 'HKLM\\SYSTEM\\CurrentControlSet\\Control\\Cryptography\\Configuration\\Local\\SSL' {} /y\n\
+            if ($LASTEXITCODE -ne 0) {{ throw \"reg export failed with exit code $LASTEXITCODE\" }}",
+            exec::quote(&backup_dir().to_string_lossy()),
+            SCHANNEL,
+            exec::quote(&schannel_backup.to_string_lossy()),
+            exec::quote(&suites_backup.to_string_lossy())
+        ),
+    });
+    plan.note("A restart is required before Schannel uses the new settings.");
+    plan.note(format!(
+        "To undo, import {} and {} with reg import, then restart.",
+        schannel_backup.display(),
+        suites_backup.display()
+    ));
+    plan.note("Clients and applications limited to TLS 1.0/1.1 (old SQL clients, embedded devices) will no longer connect.");
+    Ok(plan)
+}
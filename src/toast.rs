@@ -0,0 +1,68 @@
+//! Short notifications for background events (a status refresh that found a change, a finished
+//! job, a completed offsite upload), shown in the bottom-right corner over any screen and
+//! dismissed on their own after a few seconds.
+
+use std::time::{Duration, Instant};
+
+use crate::status::Level;
+
+/// How long a notification stays on screen.
+const SHOWN_FOR: Duration = Duration::from_secs(6);
+
+/// Notifications shown at once; older ones make room for new ones.
+const MAX_SHOWN: usize = 3;
+
+pub struct Toast {
+    pub text: String,
+    pub level: Level,
+    shown_at: Instant,
+}
+
+#[derive(Default)]
+pub struct Toasts {
+    toasts: Vec<Toast>,
+}
+
+impl Toasts {
+    pub fn push(&mut self, text: impl Into<String>, level: Level) {
+        self.toasts.push(Toast { text: text.into(), level, shown_at: Instant::now() });
+        if self.toasts.len() > MAX_SHOWN {
+            self.toasts.remove(0);
+        }
+    }
+
+    /// Drops the notifications that have been shown long enough.
+    pub fn expire(&mut self) {
+        self.expire_at(Instant::now());
+    }
+
+    fn expire_at(&mut self, now: Instant) {
+        self.toasts.retain(|t| now.duration_since(t.shown_at) < SHOWN_FOR);
+    }
+
+    /// Notifications to show, oldest first.
+    pub fn visible(&self) -> &[Toast] {
+        &self.toasts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_newest_few_until_they_expire() {
+        let mut toasts = Toasts::default();
+        for n in 1..=4 {
+            toasts.push(format!("Job #{} done", n), Level::Good);
+        }
+
+        assert_eq!(toasts.visible().len(), MAX_SHOWN);
+        assert_eq!(toasts.visible()[0].text, "Job #2 done");
+
+        toasts.expire();
+        assert_eq!(toasts.visible().len(), MAX_SHOWN);
+        toasts.expire_at(Instant::now() + SHOWN_FOR);
+        assert!(toasts.visible().is_empty());
+    }
+}
@@ -0,0 +1,235 @@
+//! Renders each screen into a `TestBackend` and compares it with a stored snapshot in
+//! `src/snapshots`, so layout changes show up in review. A missing snapshot is recorded on
+//! the first run; run with `UPDATE_SNAPSHOTS=1` to accept an intended change.
+
+use std::path::PathBuf;
+
+use ratatui::{backend::TestBackend, Terminal};
+
+use super::*;
+
+const WIDTH: u16 = 100;
+const HEIGHT: u16 = 30;
+
+fn render(app: &mut App) -> String {
+    let mut terminal = Terminal::new(TestBackend::new(WIDTH, HEIGHT)).expect("create test terminal");
+    terminal.draw(|f| view(f, app)).expect("draw");
+    // The version is in the title; keep it out so a release doesn't touch every snapshot
+    format!("{:#?}\n", terminal.backend().buffer()).replace(&format!("v{}", VERSION), "vX.Y.Z")
+}
+
+fn assert_snapshot(name: &str, app: &mut App) {
+    let actual = render(app);
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("src").join("snapshots").join(format!("{}.snap", name));
+    match std::fs::read_to_string(&path) {
+        Ok(expected) if std::env::var_os("UPDATE_SNAPSHOTS").is_none() => assert!(
+            expected == actual,
+            "{} no longer matches {}; rerun with UPDATE_SNAPSHOTS=1 if the change is intended\n\n{}",
+            name,
+            path.display(),
+            actual
+        ),
+        _ => {
+            std::fs::create_dir_all(path.parent().unwrap_or(&path)).expect("create snapshot directory");
+            std::fs::write(&path, actual).expect("write snapshot");
+        }
+    }
+}
+
+fn app(state: AppState) -> App {
+    testing::FakeRunner::new().install();
+    let mut app = App::new();
+    app.state = state;
+    app
+}
+
+// The menu lists only the compiled-in features; its snapshots show the default build
+#[cfg(all(feature = "windows-roles", feature = "netbird", feature = "web"))]
+#[test]
+fn menu() {
+    let mut app = app(AppState::Menu);
+    app.menu_state.select(Some(3));
+    assert_snapshot("menu", &mut app);
+}
+
+#[cfg(all(feature = "windows-roles", feature = "netbird", feature = "web"))]
+#[test]
+fn menu_offline() {
+    let mut app = app(AppState::Menu);
+    app.offline = true;
+    assert_snapshot("menu_offline", &mut app);
+}
+
+#[cfg(all(feature = "windows-roles", feature = "netbird", feature = "web"))]
+#[test]
+fn menu_with_status_badges() {
+    let mut app = app(AppState::Menu);
+    app.host_status = Some(status::HostStatus {
+        winget: Some("1.7.10861".to_string()),
+        netbird: Some("0.28.4".to_string()),
+        netbird_connected: false,
+        reboot_pending: true,
+        drive: "C:".to_string(),
+        disk_free: Some((42.5, 35)),
+        free_bytes: Some(45_634_027_520),
+    });
+    assert_snapshot("menu_with_status_badges", &mut app);
+}
+
+#[cfg(all(feature = "windows-roles", feature = "netbird", feature = "web"))]
+#[test]
+fn menu_split() {
+    let mut app = app(AppState::Menu);
+    app.split = true;
+    app.log_messages = vec!["Downloading winget...".to_string(), "Installed winget 1.7.10861".to_string()];
+    assert_snapshot("menu_split", &mut app);
+}
+
+#[cfg(all(feature = "windows-roles", feature = "netbird", feature = "web"))]
+#[test]
+fn menu_with_toasts() {
+    let mut app = app(AppState::Menu);
+    app.toasts.push("Status refresh: NetBird disconnected", status::Level::Warning);
+    app.toasts.push("Offsite upload completed: ServerRoles_1709288100.xml", status::Level::Good);
+    assert_snapshot("menu_with_toasts", &mut app);
+}
+
+#[test]
+fn installing() {
+    assert_snapshot("installing", &mut app(AppState::Installing(InstallItem::NetBird)));
+}
+
+#[test]
+fn file_browser() {
+    let mut app = app(AppState::FileBrowser(BrowsePurpose::RestoreRoles));
+    app.current_dir = PathBuf::from("C:\\Users\\Administrator\\Documents\\ServerBackups");
+    app.dir_entries = vec![
+        PathBuf::from(".."),
+        app.current_dir.join("ServerRoles_1709288100.xml"),
+        app.current_dir.join("ServerRoles_1709371800.xml"),
+    ];
+    app.file_list_state.select(Some(1));
+    assert_snapshot("file_browser", &mut app);
+}
+
+#[test]
+fn plan_review() {
+    let mut app = app(AppState::Menu);
+    let mut plan = Plan::new("Restore roles and features");
+    plan.step("Install Web-Server", "Install-WindowsFeature -Name 'Web-Server'");
+    plan.step("Install Web-Mgmt-Console", "Install-WindowsFeature -Name 'Web-Mgmt-Console'");
+    plan.note("A restart may be required.");
+    app.review_plan(plan);
+    assert_snapshot("plan_review", &mut app);
+}
+
+#[test]
+fn applying() {
+    assert_snapshot("applying", &mut app(AppState::Applying));
+}
+
+#[test]
+fn checklist() {
+    let mut app = app(AppState::Checklist);
+    app.checklist_kind = ChecklistKind::SecurityAudit;
+    app.checklist = vec![
+        CheckItem {
+            name: "SMBv1".to_string(),
+            status: CheckStatus::Pass,
+            detail: "Disabled".to_string(),
+            remediation: None,
+            advice: None,
+        },
+        CheckItem {
+            name: "LLMNR".to_string(),
+            status: CheckStatus::Fail,
+            detail: "Enabled".to_string(),
+            remediation: Some(Remediation {
+                summary: "Disable LLMNR through policy".to_string(),
+                script: String::new(),
+            }),
+            advice: None,
+        },
+        CheckItem {
+            name: "NTLMv1".to_string(),
+            status: CheckStatus::Unknown,
+            detail: "Could not read LmCompatibilityLevel".to_string(),
+            remediation: None,
+            advice: Some("Set LmCompatibilityLevel to 5".to_string()),
+        },
+    ];
+    app.checklist_state.select(Some(1));
+    assert_snapshot("checklist", &mut app);
+}
+
+#[test]
+fn table() {
+    let mut app = app(AppState::Table);
+    app.table = TableView::new(TableKind::ListeningPorts);
+    app.table.rows = vec![
+        ["TCP", "0.0.0.0", "443", "4", "System", ""].map(String::from).to_vec(),
+        ["TCP", "0.0.0.0", "3389", "1120", "svchost", "TermService"].map(String::from).to_vec(),
+    ];
+    app.table.state.select(Some(0));
+    app.table.status = "Refreshed.".to_string();
+    assert_snapshot("table", &mut app);
+}
+
+#[test]
+fn text_view() {
+    let mut app = app(AppState::TextView);
+    app.text_title = " Task History ".to_string();
+    app.text = "2024-03-01 10:15:00\nBacked up server roles\n\n2024-03-02 09:30:00\nInstalled NetBird".to_string();
+    assert_snapshot("text_view", &mut app);
+}
+
+#[test]
+fn input() {
+    let mut app = app(AppState::Input(InputPurpose::DnsLookup));
+    app.input = "example.com 1.1.1.1".to_string();
+    assert_snapshot("input", &mut app);
+}
+
+#[test]
+fn running() {
+    assert_snapshot("running", &mut app(AppState::Running(Task::GpUpdate)));
+}
+
+#[test]
+fn confirm() {
+    let state = AppState::Confirm {
+        message: "Kill process notepad (PID 4242)?\n\nUnsaved work in it will be lost.".to_string(),
+        action: ConfirmAction::KillProcess { pid: "4242".to_string(), name: "notepad".to_string() },
+    };
+    assert_snapshot("confirm", &mut app(state));
+}
+
+#[test]
+fn result_success() {
+    let outcome = OperationOutcome::Success("NetBird installed successfully.".to_string());
+    assert_snapshot("result_success", &mut app(AppState::Result(outcome)));
+}
+
+#[test]
+fn result_needs_reboot() {
+    let outcome = OperationOutcome::SuccessNeedsReboot("SQL Server Express installed.".to_string());
+    assert_snapshot("result_needs_reboot", &mut app(AppState::Result(outcome)));
+}
+
+#[test]
+fn result_partial() {
+    let outcome = OperationOutcome::Partial {
+        message: "Installed the agent on 2 of 3 hosts.".to_string(),
+        failed_items: vec!["SRV-03: access denied".to_string()],
+    };
+    assert_snapshot("result_partial", &mut app(AppState::Result(outcome)));
+}
+
+#[test]
+fn result_failed() {
+    let outcome = OperationOutcome::failed(OperationError::Download {
+        item: "Winget".to_string(),
+        reason: "connection reset".to_string(),
+    });
+    assert_snapshot("result_failed", &mut app(AppState::Result(outcome)));
+}
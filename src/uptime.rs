@@ -0,0 +1,68 @@
+use crate::exec;
+
+/// Boot time and uptime, then the last boot-related System log events as
+/// tab-separated `time, id, source, message` lines.
+const UPTIME_SCRIPT: &str = r#"
+$os = Get-CimInstance Win32_OperatingSystem
+"Boot=$($os.LastBootUpTime.ToString('yyyy-MM-dd HH:mm:ss'))"
+$up = (Get-Date) - $os.LastBootUpTime
+"Uptime=$($up.Days)d $($up.Hours)h $($up.Minutes)m"
+# 41: rebooted without clean shutdown, 6008: unexpected shutdown, 1001: bugcheck, 1074: planned restart/shutdown
+Get-WinEvent -FilterHashtable @{ LogName = 'System'; Id = 41, 6008, 1001, 1074 } -MaxEvents 200 -ErrorAction SilentlyContinue |
+    Where-Object { $_.Id -ne 1001 -or $_.ProviderName -like '*WER-SystemErrorReporting*' } |
+    Select-Object -First 15 |
+    ForEach-Object {
+        "Event=$($_.TimeCreated.ToString('yyyy-MM-dd HH:mm:ss'))`t$($_.Id)`t$($_.ProviderName)`t$(($_.Message -split "`r?`n")[0] -replace '\s+', ' ')"
+    }
+"#;
+
+fn kind(id: &str) -> &'static str {
+    match id {
+        "41" => "UNEXPECTED (Kernel-Power)",
+        "6008" => "UNEXPECTED shutdown",
+        "1001" => "BUGCHECK (blue screen)",
+        "1074" => "Planned",
+        _ => "",
+    }
+}
+
+/// Uptime, last boot time and the recent unexpected shutdowns, bugchecks and planned restarts.
+pub fn uptime_report() -> Result<String, String> {
+    let output = exec::powershell(UPTIME_SCRIPT).map_err(|e| format!("Failed to read the boot history: {}", e))?;
+    let values = exec::key_values(&output.stdout);
+    let events: Vec<Vec<&str>> = output
+        .stdout
+        .lines()
+        .filter_map(|l| l.strip_prefix("Event="))
+        .map(|l| l.splitn(4, '\t').collect::<Vec<_>>())
+        .filter(|e| e.len() == 4)
+        .collect();
+
+    let mut out = vec![
+        format!("Uptime:    {}", values.get("Uptime").cloned().unwrap_or_default()),
+        format!("Last boot: {}", values.get("Boot").cloned().unwrap_or_default()),
+        String::new(),
+    ];
+
+    // The newest event explains why the server last restarted
+    let unexpected = events.iter().filter(|e| e[1] != "1074").count();
+    match events.first() {
+        Some(e) if e[1] == "1074" => out.push(format!("Last restart was planned: {}", e[3])),
+        Some(e) => out.push(format!("Last restart was {} at {}", kind(e[1]).to_lowercase(), e[0])),
+        None => out.push("No shutdown or crash events found in the System log.".to_string()),
+    }
+    out.push(format!("Unexpected shutdowns and bugchecks in the recent history: {}", unexpected));
+    if unexpected >= 3 {
+        out.push("Repeated unexpected restarts point to power, hardware or driver problems;".to_string());
+        out.push("check the hardware event log and C:\\Windows\\Minidump for crash dumps.".to_string());
+    }
+
+    if !events.is_empty() {
+        out.push(String::new());
+        out.push("Recent events (newest first):".to_string());
+        for e in &events {
+            out.push(format!("  {}  {:<26} {}", e[0], kind(e[1]), e[3]));
+        }
+    }
+    Ok(out.join("\n"))
+}
@@ -0,0 +1,202 @@
+use std::path::PathBuf;
+
+use crate::{backup_dir, exec, history, profiles};
+use crate::{CheckItem, CheckStatus};
+
+const EXAMPLE_CHECKS: &str = r#"; Server Helper post-provision validation
+;
+; One assertion per line, checked in order by Post-Provision Validation:
+;
+;   service = <name>      the service is running
+;   port = <number>       something listens on the TCP port
+;   share = <UNC path>    the share is reachable with the current credentials
+;   vpn = netbird         NetBird is connected to its management and signal servers
+;   vpn = <adapter>       the named network adapter (e.g. a VPN interface) is up
+;   dns = <name>          the name resolves
+;
+; Uncomment and adapt the lines below.
+;
+; service = W32Time
+; service = WinRM
+; port = 3389
+; port = 5985
+; share = \\nas01\backups
+; vpn = netbird
+; dns = dc01.corp.local
+"#;
+
+/// The validation baseline of the active profile.
+pub fn checks_path() -> PathBuf {
+    profiles::current_dir().join("validation.ini")
+}
+
+/// Creates a commented example file on first use.
+fn ensure_checks_file() -> Result<PathBuf, String> {
+    let path = checks_path();
+    if !path.exists() {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        std::fs::write(&path, EXAMPLE_CHECKS.replace('\n', "\r\n"))
+            .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    }
+    Ok(path)
+}
+
+/// Loads the assertions as `(kind, value)` pairs.
+fn load_checks() -> Result<Vec<(String, String)>, String> {
+    let path = ensure_checks_file()?;
+    let content = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    let mut checks = Vec::new();
+    for (i, raw) in content.lines().enumerate() {
+        let line = raw.trim();
+        if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
+            continue;
+        }
+        let (key, value) = line
+            .split_once('=')
+            .map(|(k, v)| (k.trim().to_lowercase(), v.trim().to_string()))
+            .ok_or_else(|| format!("validation.ini line {}: expected key = value", i + 1))?;
+        if !matches!(key.as_str(), "service" | "port" | "share" | "vpn" | "dns") {
+            return Err(format!("validation.ini line {}: unknown check '{}'", i + 1, key));
+        }
+        checks.push((key, value));
+    }
+    Ok(checks)
+}
+
+fn item(name: String, pass: bool, detail: String) -> CheckItem {
+    CheckItem {
+        name,
+        status: if pass { CheckStatus::Pass } else { CheckStatus::Fail },
+        detail,
+        remediation: None,
+        advice: None,
+    }
+}
+
+fn check(kind: &str, value: &str) -> CheckItem {
+    let quoted = exec::quote(value);
+    let script = match kind {
+        "service" => format!("(Get-Service -Name {} -ErrorAction Stop).Status", quoted),
+        "port" => match value.parse::<u16>() {
+            Ok(port) => format!(
+                "$l = @(Get-NetTCPConnection -State Listen -LocalPort {} -ErrorAction SilentlyContinue); \
+                if ($l) {{ (Get-Process -Id $l[0].OwningProcess).ProcessName }}",
+                port
+            ),
+            Err(_) => return item(format!("Port {}", value), false, "Not a port number".to_string()),
+        },
+        "share" => format!(
+            "$t = Measure-Command {{ $ok = Test-Path -LiteralPath {} }}; if ($ok) {{ \"$([int]$t.TotalMilliseconds) ms\" }}",
+            quoted
+        ),
+        "vpn" if value.eq_ignore_ascii_case("netbird") => "netbird status 2>&1".to_string(),
+        "vpn" => format!("(Get-NetAdapter -Name {} -ErrorAction Stop).Status", quoted),
+        _ => format!("(Resolve-DnsName -Name {} -ErrorAction Stop | Where-Object IPAddress).IPAddress -join ', '", quoted),
+    };
+    let output = exec::powershell(&script);
+    let (stdout, stderr) = match &output {
+        Ok(o) => (o.stdout.trim().to_string(), o.stderr.trim().lines().next().unwrap_or("").to_string()),
+        Err(e) => (String::new(), e.to_string()),
+    };
+    let failure = |fallback: &str| if stderr.is_empty() { fallback.to_string() } else { stderr.clone() };
+
+    match kind {
+        "service" => match stdout.as_str() {
+            "Running" => item(format!("Service {}", value), true, "Running".to_string()),
+            "" => item(format!("Service {}", value), false, failure("Not found")),
+            status => item(format!("Service {}", value), false, status.to_string()),
+        },
+        "port" => match stdout.as_str() {
+            "" => item(format!("Port {}", value), false, "Nothing listening".to_string()),
+            process => item(format!("Port {}", value), true, format!("Listening ({})", process)),
+        },
+        "share" => match stdout.as_str() {
+            "" => item(format!("Share {}", value), false, failure("Not reachable")),
+            latency => item(format!("Share {}", value), true, format!("Reachable in {}", latency)),
+        },
+        "vpn" if value.eq_ignore_ascii_case("netbird") => {
+            let connected = |what: &str| {
+                stdout.lines().any(|l| l.trim_start().starts_with(what) && l.contains("Connected"))
+            };
+            let up = connected("Management:") && connected("Signal:");
+            let detail = if up {
+                "Connected".to_string()
+            } else if stdout.is_empty() {
+                failure("NetBird is not installed")
+            } else {
+                stdout.lines().find(|l| l.contains("Management:")).unwrap_or("Not connected").trim().to_string()
+            };
+            item("VPN NetBird".to_string(), up, detail)
+        }
+        "vpn" => match stdout.as_str() {
+            "Up" => item(format!("VPN {}", value), true, "Up".to_string()),
+            "" => item(format!("VPN {}", value), false, failure("Adapter not found")),
+            status => item(format!("VPN {}", value), false, status.to_string()),
+        },
+        _ => match stdout.as_str() {
+            "" => item(format!("DNS {}", value), false, failure("Does not resolve")),
+            addresses => item(format!("DNS {}", value), true, addresses.to_string()),
+        },
+    }
+}
+
+/// Runs every assertion in validation.ini.
+pub fn run_checks() -> Result<Vec<CheckItem>, String> {
+    let checks = load_checks()?;
+    if checks.is_empty() {
+        return Ok(vec![CheckItem {
+            name: "No checks configured".to_string(),
+            status: CheckStatus::Unknown,
+            detail: checks_path().display().to_string(),
+            remediation: None,
+            advice: Some("Press e to open validation.ini, add checks, save it and press r to run them.".to_string()),
+        }]);
+    }
+    Ok(checks.iter().map(|(kind, value)| check(kind, value)).collect())
+}
+
+/// Writes a sign-off report for the results to the backup directory.
+pub fn write_report(items: &[CheckItem]) -> Result<PathBuf, String> {
+    let host = std::env::var("COMPUTERNAME").unwrap_or_else(|_| "unknown".to_string());
+    let passed = items.iter().filter(|i| i.status == CheckStatus::Pass).count();
+    let mut lines = vec![
+        "Post-Provision Validation Report".to_string(),
+        String::new(),
+        format!("Server:  {}", host),
+        format!("Date:    {} UTC", history::timestamp()),
+        format!("Result:  {} ({} of {} checks passed)", if passed == items.len() { "PASS" } else { "FAIL" }, passed, items.len()),
+        String::new(),
+    ];
+    for i in items {
+        let mark = match i.status {
+            CheckStatus::Pass => "[PASS]",
+            CheckStatus::Fail => "[FAIL]",
+            CheckStatus::Unknown => "[ -- ]",
+        };
+        lines.push(format!("{} {:<40} {}", mark, i.name, i.detail));
+    }
+    lines.extend([
+        String::new(),
+        "Validated by: ______________________    Signature: ______________________".to_string(),
+    ]);
+
+    let dir = backup_dir();
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let stamp = history::timestamp().replace([' ', ':', '-'], "");
+    let path = dir.join(format!("Validation_{}_{}.txt", host, stamp));
+    std::fs::write(&path, lines.join("\r\n")).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+/// Opens validation.ini in Notepad.
+pub fn edit_checks() -> Result<(), String> {
+    let path = ensure_checks_file()?;
+    std::process::Command::new("notepad.exe")
+        .arg(path)
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| format!("Failed to open Notepad: {}", e))
+}
@@ -0,0 +1,48 @@
+use crate::{exec, plan::Plan};
+
+/// The 2015, 2017, 2019 and 2022 runtimes share one binary-compatible package (v14),
+/// so the latest installer covers all four.
+const ARCHITECTURES: [&str; 2] = ["x64", "x86"];
+
+const STATE_SCRIPT: &str = r#"
+foreach ($arch in 'x64', 'x86') {
+    $key = Get-ItemProperty "HKLM:\SOFTWARE\WOW6432Node\Microsoft\VisualStudio\14.0\VC\Runtimes\$arch" -ErrorAction SilentlyContinue
+    if (-not $key) { $key = Get-ItemProperty "HKLM:\SOFTWARE\Microsoft\VisualStudio\14.0\VC\Runtimes\$arch" -ErrorAction SilentlyContinue }
+    if ($key.Installed -eq 1) { "$arch=$($key.Version)" } else { "$arch=" }
+}
+"#;
+
+/// Downloads and silently runs the installer; 1638 means a newer version is already present.
+const INSTALL_SCRIPT: &str = r#"
+$ErrorActionPreference = 'Stop'
+[Net.ServicePointManager]::SecurityProtocol = [Net.SecurityProtocolType]::Tls12
+$file = Join-Path $env:TEMP "vc_redist.$arch.exe"
+Invoke-WebRequest -Uri "https://aka.ms/vs/17/release/vc_redist.$arch.exe" -OutFile $file -UseBasicParsing
+$p = Start-Process -FilePath $file -ArgumentList '/install', '/quiet', '/norestart' -Wait -PassThru
+if ($p.ExitCode -notin 0, 1638, 3010) { throw "vc_redist.$arch.exe failed with exit code $($p.ExitCode)" }
+"ExitCode=$($p.ExitCode)"
+"#;
+
+/// Plans installing the Visual C++ 2015-2022 redistributable for each architecture that lacks it.
+pub fn install_plan() -> Result<Plan, String> {
+    let output = exec::powershell(STATE_SCRIPT)
+        .map_err(|e| format!("Failed to read the installed Visual C++ runtimes: {}", e))?;
+    let values = exec::key_values(&output.stdout);
+
+    let mut plan = Plan::new("Install the Visual C++ 2015-2022 redistributables");
+    for arch in ARCHITECTURES {
+        match values.get(arch).map(String::as_str) {
+            Some(version) if !version.is_empty() => {
+                plan.note(format!("{} is already installed ({})", arch, version.trim_start_matches('v')))
+            }
+            _ => plan.step(
+                format!("Download and install vc_redist.{}.exe silently", arch),
+                format!("$arch = '{}'\n{}", arch, INSTALL_SCRIPT.trim()),
+            ),
+        }
+    }
+    if !plan.steps.is_empty() {
+        plan.note("Exit code 3010 means the install succeeded and a restart completes it.");
+    }
+    Ok(plan)
+}
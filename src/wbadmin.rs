@@ -0,0 +1,130 @@
+use std::{
+    io::{BufRead, BufReader, Read},
+    process::{Command, Stdio},
+};
+
+use crate::exec;
+
+const NOT_INSTALLED: &str =
+    "wbadmin was not found. Install the Windows Server Backup feature (Windows-Server-Backup) first.";
+
+/// Checks a backup target: a volume such as `E:` or a UNC share such as `\\nas01\backups`.
+pub fn parse_target(value: &str) -> Result<String, String> {
+    let target = value.trim().trim_end_matches('\\');
+    let bytes = target.as_bytes();
+    let volume = bytes.len() == 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':';
+    let share = target.starts_with("\\\\") && target[2..].split('\\').filter(|p| !p.is_empty()).count() >= 2;
+    if volume || share {
+        Ok(target.to_string())
+    } else {
+        Err(format!("Invalid backup target: '{}'. Enter a volume (E:) or a share (\\\\server\\share).", value.trim()))
+    }
+}
+
+/// System state backups in the local backup catalog, newest first:
+/// [Backup Time, Location, Version Identifier, Can Recover].
+pub fn system_state_backups() -> Result<Vec<Vec<String>>, String> {
+    let output = exec::run("wbadmin", &["get", "versions"]).map_err(|_| NOT_INSTALLED.to_string())?;
+    if !output.success {
+        if output.stdout.contains("No backup was found") || output.stdout.contains("no backups") {
+            return Ok(Vec::new());
+        }
+        return Err(format!("wbadmin get versions failed:\n{}{}", output.stdout.trim(), output.stderr.trim()));
+    }
+
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    for line in output.stdout.lines() {
+        let (key, value) = match line.split_once(':') {
+            Some((key, value)) => (key.trim(), value.trim().to_string()),
+            None => continue,
+        };
+        match key {
+            "Backup time" => rows.push(vec![value, String::new(), String::new(), String::new()]),
+            "Backup location" => {
+                if let Some(row) = rows.last_mut() {
+                    row[1] = value;
+                }
+            }
+            "Version identifier" => {
+                if let Some(row) = rows.last_mut() {
+                    row[2] = value;
+                }
+            }
+            "Can recover" => {
+                if let Some(row) = rows.last_mut() {
+                    row[3] = value;
+                }
+            }
+            _ => {}
+        }
+    }
+    rows.retain(|row| row[3].contains("System State"));
+    rows.reverse();
+    Ok(rows)
+}
+
+/// Last `NN%` in a wbadmin progress line.
+fn percent(line: &str) -> Option<u8> {
+    let end = line.rfind('%')?;
+    let start = line[..end]
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| c.is_ascii_digit())
+        .last()
+        .map(|(i, _)| i)?;
+    line[start..end].parse().ok().filter(|p| *p <= 100)
+}
+
+/// Runs `wbadmin start systemstatebackup` to `target`, calling `progress` with
+/// each output line, or with the percentage for progress updates.
+pub fn system_state_backup(
+    target: &str,
+    mut progress: impl FnMut(Option<&str>, Option<u8>),
+) -> Result<String, String> {
+    let target_arg = format!("-backupTarget:{}", target);
+    let args = ["start", "systemstatebackup", target_arg.as_str(), "-quiet"];
+    if exec::export_status().is_some() {
+        exec::change_program("wbadmin", &args).map_err(|e| e.to_string())?;
+        return Ok(format!("wbadmin {}", args.join(" ")));
+    }
+
+    let mut child = Command::new("wbadmin")
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|_| NOT_INSTALLED.to_string())?;
+
+    let mut last_lines: Vec<String> = Vec::new();
+    if let Some(stdout) = child.stdout.take() {
+        let mut reader = BufReader::new(stdout);
+        let mut buf = Vec::new();
+        while matches!(reader.read_until(b'\n', &mut buf), Ok(n) if n > 0) {
+            // Progress updates may be separated by bare carriage returns
+            for line in String::from_utf8_lossy(&buf).split('\r') {
+                let line = line.trim();
+                if !line.is_empty() {
+                    match percent(line) {
+                        Some(p) => progress(None, Some(p)),
+                        None => progress(Some(line), None),
+                    }
+                    last_lines.push(line.to_string());
+                }
+            }
+            buf.clear();
+        }
+    }
+    let mut stderr = String::new();
+    if let Some(mut err) = child.stderr.take() {
+        let _ = err.read_to_string(&mut stderr);
+    }
+    let status = child.wait().map_err(|e| format!("Failed to wait for wbadmin: {}", e))?;
+
+    if status.success() {
+        Ok(format!("System state backup to {} completed.", target))
+    } else {
+        let tail = last_lines.split_off(last_lines.len().saturating_sub(5));
+        Err(format!("wbadmin failed:\n{}\n{}", tail.join("\n"), stderr.trim()))
+    }
+}
@@ -0,0 +1,116 @@
+//! Applies a WinGet Configuration file (`configuration.dsc.yaml`) with `winget configure`,
+//! streaming its output into a background job.
+
+use std::path::Path;
+
+use crate::{exec, health};
+
+/// winget gained `configure` in 1.6.
+const MINIMUM: (u32, u32) = (1, 6);
+
+/// `(major, minor)` from `winget --version`, e.g. `v1.7.10861`.
+fn parse_version(text: &str) -> Option<(u32, u32)> {
+    let mut parts = text.trim().trim_start_matches('v').split('.').map(|p| p.parse::<u32>().ok());
+    Some((parts.next()??, parts.next()??))
+}
+
+/// The resources a configuration file sets, e.g. `Microsoft.WinGet.DSC/WinGetPackage`, in order.
+pub fn resources(yaml: &str) -> Vec<String> {
+    yaml.lines()
+        .filter_map(|line| line.trim().trim_start_matches("- ").strip_prefix("resource:"))
+        .map(|value| value.trim().trim_matches(|c| c == '"' || c == '\'').to_string())
+        .filter(|value| !value.is_empty())
+        .collect()
+}
+
+/// What applying `path` will do, for the confirmation prompt.
+pub fn describe(path: &Path) -> Result<String, String> {
+    let yaml = std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let resources = resources(&yaml);
+    if resources.is_empty() {
+        return Err(format!("{} has no resources. Pick a WinGet Configuration (.dsc.yaml) file.", path.display()));
+    }
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    for resource in resources.iter() {
+        match counts.iter_mut().find(|(r, _)| r == resource) {
+            Some((_, n)) => *n += 1,
+            None => counts.push((resource.clone(), 1)),
+        }
+    }
+    Ok(format!(
+        "Apply the WinGet Configuration {}?\n\n{} resource(s):\n{}\n\n\
+        winget downloads the DSC modules it needs from the PowerShell Gallery, then applies each\n\
+        resource in order. It runs as a background job. (y/n)",
+        path.display(),
+        resources.len(),
+        counts.iter().map(|(r, n)| format!("  {} x {}", n, r)).collect::<Vec<_>>().join("\n")
+    ))
+}
+
+/// Runs `winget configure` on `path`, passing each output line to `progress`.
+pub fn apply(path: &Path, mut progress: impl FnMut(Option<&str>, Option<u8>)) -> Result<String, String> {
+    let file = path.to_string_lossy().to_string();
+    let args = [
+        "configure",
+        "--file",
+        file.as_str(),
+        "--accept-configuration-agreements",
+        "--disable-interactivity",
+    ];
+    if exec::export_status().is_some() {
+        exec::change_program("winget", &args).map_err(|e| e.to_string())?;
+        return Ok(format!("winget configure --file {} recorded.", file));
+    }
+
+    let version = exec::run("winget", &["--version"]).map_err(|e| format!("winget is not available: {}", e))?;
+    match parse_version(&version.stdout) {
+        Some(found) if found >= MINIMUM => {}
+        _ => {
+            return Err(format!(
+                "winget configure needs winget {}.{} or later; this server has {}. Update it with Install Winget.",
+                MINIMUM.0,
+                MINIMUM.1,
+                version.stdout.trim()
+            ))
+        }
+    }
+
+    progress(Some(&format!("Applying {} with winget {}...", file, version.stdout.trim())), Some(0));
+    let (ok, lines) = health::stream("winget", &args, (0, 100), &mut progress)?;
+    progress(None, Some(100));
+    if ok {
+        Ok("The configuration was applied.".to_string())
+    } else {
+        Err(format!(
+            "winget configure failed:\n{}",
+            lines.iter().rev().take(10).rev().cloned().collect::<Vec<_>>().join("\n")
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_resources_and_checks_the_winget_version() {
+        let yaml = [
+            "properties:",
+            "  resources:",
+            "    - resource: PSDscResources/WindowsFeature",
+            "      id: iis",
+            "    - resource: \"Microsoft.WinGet.DSC/WinGetPackage\"",
+            "      settings:",
+            "        id: Microsoft.PowerShell",
+            "    - resource: Microsoft.WinGet.DSC/WinGetPackage",
+        ]
+        .join("\n");
+
+        assert_eq!(
+            resources(&yaml),
+            ["PSDscResources/WindowsFeature", "Microsoft.WinGet.DSC/WinGetPackage", "Microsoft.WinGet.DSC/WinGetPackage"]
+        );
+        assert_eq!(parse_version("v1.7.10861\r\n"), Some((1, 7)));
+        assert!(parse_version("v1.5.1881") < Some(MINIMUM));
+    }
+}
@@ -0,0 +1,136 @@
+use crate::{exec, plan::Plan};
+
+const FEATURES: [&str; 2] = ["Microsoft-Windows-Subsystem-Linux", "VirtualMachinePlatform"];
+
+/// Used when `wsl --list --online` is not available yet (WSL not enabled).
+const KNOWN_DISTROS: &[(&str, &str)] = &[
+    ("Ubuntu", "Ubuntu"),
+    ("Ubuntu-24.04", "Ubuntu 24.04 LTS"),
+    ("Ubuntu-22.04", "Ubuntu 22.04 LTS"),
+    ("Debian", "Debian GNU/Linux"),
+    ("kali-linux", "Kali Linux Rolling"),
+    ("OracleLinux_9_1", "Oracle Linux 9.1"),
+    ("openSUSE-Leap-15.6", "openSUSE Leap 15.6"),
+];
+
+const STATE_SCRIPT: &str = r#"
+$os = Get-CimInstance Win32_OperatingSystem
+"Build=$($os.BuildNumber)"
+"ProductType=$($os.ProductType)"
+"Caption=$($os.Caption)"
+"Hypervisor=$((Get-CimInstance Win32_ComputerSystem).HypervisorPresent)"
+"Firmware=$(@(Get-CimInstance Win32_Processor)[0].VirtualizationFirmwareEnabled)"
+foreach ($f in 'Microsoft-Windows-Subsystem-Linux', 'VirtualMachinePlatform') {
+    "$f=$((Get-WindowsOptionalFeature -Online -FeatureName $f -ErrorAction SilentlyContinue).State)"
+}
+"#;
+
+struct State {
+    values: std::collections::HashMap<String, String>,
+}
+
+impl State {
+    fn get(&self, key: &str) -> &str {
+        self.values.get(key).map(String::as_str).unwrap_or("")
+    }
+
+    fn virtualization(&self) -> bool {
+        self.get("Hypervisor") == "True" || self.get("Firmware") == "True"
+    }
+}
+
+/// Reads the OS and feature state, failing with an explanation on SKUs that can't run WSL here.
+fn supported_state() -> Result<State, String> {
+    let output = exec::powershell(STATE_SCRIPT).map_err(|e| format!("Failed to read the OS version: {}", e))?;
+    let state = State { values: exec::key_values(&output.stdout) };
+    let build: u32 = state.get("Build").parse().unwrap_or(0);
+    let server = state.get("ProductType") != "1";
+    let caption = state.get("Caption");
+
+    if server && build < 17763 {
+        return Err(format!(
+            "{} (build {}) does not support WSL. Windows Server 2019 or later is required.",
+            caption, build
+        ));
+    }
+    if server && build < 20348 {
+        return Err(format!(
+            "{} (build {}) supports only WSL 1 with manually downloaded distribution packages \
+            (Add-AppxPackage of the .appx bundle). Installing distributions with wsl --install \
+            needs Windows Server 2022 or later.",
+            caption, build
+        ));
+    }
+    Ok(state)
+}
+
+/// Distributions available to install: [Name, Friendly Name].
+pub fn distros() -> Result<Vec<Vec<String>>, String> {
+    supported_state()?;
+    let online = exec::powershell("$env:WSL_UTF8 = 1; wsl.exe --list --online 2>$null")
+        .map(|o| o.stdout)
+        .unwrap_or_default();
+    // The list follows a "NAME  FRIENDLY NAME" header
+    let rows: Vec<Vec<String>> = online
+        .lines()
+        .skip_while(|l| !l.trim_start().starts_with("NAME"))
+        .skip(1)
+        .filter_map(|l| {
+            let l = l.trim();
+            let (name, friendly) = l.split_once(char::is_whitespace)?;
+            Some(vec![name.trim_start_matches('*').to_string(), friendly.trim().to_string()])
+        })
+        .collect();
+    if rows.is_empty() {
+        return Ok(KNOWN_DISTROS.iter().map(|(n, f)| vec![n.to_string(), f.to_string()]).collect());
+    }
+    Ok(rows)
+}
+
+/// Plans enabling WSL and, once the features are on, installing `distro`.
+pub fn install_plan(distro: &str) -> Result<Plan, String> {
+    let state = supported_state()?;
+    let mut plan = Plan::new(format!("Enable WSL and install {}", distro));
+
+    let mut enabling = false;
+    for feature in FEATURES {
+        if state.get(feature) == "Enabled" {
+            plan.note(format!("{} is already enabled", feature));
+            continue;
+        }
+        enabling = true;
+        plan.step(
+            format!("Enable {}", feature),
+            format!(
+                "Enable-WindowsOptionalFeature -Online -FeatureName {} -All -NoRestart -ErrorAction Stop | \
+                Format-List RestartNeeded",
+                feature
+            ),
+        );
+    }
+
+    if enabling {
+        plan.note(format!(
+            "Restart after enabling the features, then choose {} again to install it.",
+            distro
+        ));
+    } else {
+        plan.step(
+            format!("Install {}", distro),
+            format!(
+                "$env:WSL_UTF8 = 1\nwsl.exe --install --distribution {} --no-launch\n\
+                if ($LASTEXITCODE -ne 0) {{ throw \"wsl --install failed with exit code $LASTEXITCODE\" }}",
+                exec::quote(distro)
+            ),
+        );
+        plan.note(format!("Start it with: wsl -d {}  (you are asked for a Linux user name on first launch)", distro));
+    }
+
+    if !state.virtualization() {
+        plan.note(
+            "Hardware virtualization is not available (a VM without nested virtualization?). \
+            WSL 2 cannot start; run wsl --set-default-version 1 to use WSL 1 instead.",
+        );
+    }
+    Ok(plan)
+}
@@ -0,0 +1,235 @@
+use crate::exec;
+use crate::{dates, CheckItem, CheckStatus};
+
+const POLICY_KEY: &str = "HKLM:\\SOFTWARE\\Policies\\Microsoft\\Windows\\WindowsUpdate";
+
+/// A client that hasn't checked in for longer than this is reported as a problem.
+const STALE_DAYS: i64 = 7;
+
+/// WSUS policy values, the last successful detection (UTC) and whether the server answers,
+/// as `key=value` lines.
+const STATUS_SCRIPT: &str = r#"
+$policy = Get-ItemProperty 'HKLM:\SOFTWARE\Policies\Microsoft\Windows\WindowsUpdate' -ErrorAction SilentlyContinue
+$au = Get-ItemProperty 'HKLM:\SOFTWARE\Policies\Microsoft\Windows\WindowsUpdate\AU' -ErrorAction SilentlyContinue
+"Server=$($policy.WUServer)"
+"StatusServer=$($policy.WUStatusServer)"
+"TargetGroup=$($policy.TargetGroup)"
+"TargetGroupEnabled=$($policy.TargetGroupEnabled)"
+"UseServer=$($au.UseWUServer)"
+$service = Get-Service wuauserv -ErrorAction SilentlyContinue
+"Service=$($service.Status)"
+"ServiceStart=$($service.StartType)"
+try {
+    $results = (New-Object -ComObject Microsoft.Update.AutoUpdate).Results
+    if ($results.LastSearchSuccessDate -and $results.LastSearchSuccessDate.Year -gt 1601) {
+        "LastSearch=$($results.LastSearchSuccessDate.ToString('yyyy-MM-dd HH:mm:ss'))"
+    }
+} catch { }
+if ($policy.WUServer) {
+    $uri = [Uri]$policy.WUServer
+    $client = New-Object Net.Sockets.TcpClient
+    $reachable = $client.ConnectAsync($uri.Host, $uri.Port).Wait(5000) -and $client.Connected
+    $client.Dispose()
+    "Reachable=$reachable"
+}
+"#;
+
+/// The WSUS server URL and target group as `url [group]`, to prefill the edit prompt.
+pub fn spec() -> String {
+    let Ok(output) = exec::powershell(STATUS_SCRIPT) else { return String::new() };
+    let values = exec::key_values(&output.stdout);
+    let value = |key: &str| values.get(key).cloned().unwrap_or_default();
+    format!("{} {}", value("Server"), value("TargetGroup")).trim().to_string()
+}
+
+/// The WSUS policy, whether the server is reachable, the Windows Update service and when
+/// the client last checked in.
+pub fn checks() -> Result<Vec<CheckItem>, String> {
+    let output = exec::powershell(STATUS_SCRIPT).map_err(|e| format!("Failed to read the WSUS settings: {}", e))?;
+    if !output.success {
+        return Err(format!("Failed to read the WSUS settings:\n{}", output.stderr.trim()));
+    }
+    let values = exec::key_values(&output.stdout);
+    let value = |key: &str| values.get(key).map(|v| v.trim().to_string()).unwrap_or_default();
+    let (server, used) = (value("Server"), value("UseServer") == "1");
+    let item = |name: &str, status, detail: String, advice: Option<&str>| CheckItem {
+        name: name.to_string(),
+        status,
+        detail,
+        remediation: None,
+        advice: advice.map(String::from),
+    };
+
+    let mut items = vec![match (server.is_empty(), used) {
+        (true, _) => item(
+            "WSUS server",
+            CheckStatus::Unknown,
+            "Not set: updates come from Microsoft Update".to_string(),
+            Some("Press s to point this server at WSUS."),
+        ),
+        (false, true) => item("WSUS server", CheckStatus::Pass, server.clone(), None),
+        (false, false) => item(
+            "WSUS server",
+            CheckStatus::Fail,
+            format!("{} (set, but UseWUServer is off)", server),
+            Some("Press s to set it again, which also turns UseWUServer on."),
+        ),
+    }];
+    if !server.is_empty() {
+        let status_server = value("StatusServer");
+        items.push(item(
+            "Status server",
+            if status_server.is_empty() { CheckStatus::Fail } else { CheckStatus::Pass },
+            if status_server.is_empty() { "Not set: the client can't report to WSUS".to_string() } else { status_server },
+            None,
+        ));
+        let group = value("TargetGroup");
+        items.push(match (group.is_empty(), value("TargetGroupEnabled") == "1") {
+            (false, true) => item("Target group", CheckStatus::Pass, group, None),
+            (false, false) => item(
+                "Target group",
+                CheckStatus::Fail,
+                format!("{} (set, but client-side targeting is off)", group),
+                Some("Press s to set it again, which also turns targeting on."),
+            ),
+            (true, _) => item(
+                "Target group",
+                CheckStatus::Unknown,
+                "None: the WSUS console decides the group".to_string(),
+                None,
+            ),
+        });
+        let reachable = value("Reachable").eq_ignore_ascii_case("true");
+        items.push(item(
+            "WSUS reachable",
+            if reachable { CheckStatus::Pass } else { CheckStatus::Fail },
+            if reachable { "Port answers".to_string() } else { "No answer within 5 seconds".to_string() },
+            (!reachable).then_some("Check the URL and port (8530 for HTTP, 8531 for HTTPS) and the firewall."),
+        ));
+    }
+
+    let (service, start) = (value("Service"), value("ServiceStart"));
+    items.push(match (service.is_empty(), start.eq_ignore_ascii_case("Disabled")) {
+        (true, _) => item("Windows Update service", CheckStatus::Fail, "Not found".to_string(), None),
+        (false, true) => item(
+            "Windows Update service",
+            CheckStatus::Fail,
+            format!("{}, disabled", service),
+            Some("The client can't check in while wuauserv is disabled; set it to Manual."),
+        ),
+        (false, false) => item("Windows Update service", CheckStatus::Pass, format!("{}, {}", service, start), None),
+    });
+
+    let last = value("LastSearch");
+    items.push(match dates::parse_utc(&last) {
+        Some(secs) => {
+            let stale = dates::now() as i64 - secs > STALE_DAYS * 86_400;
+            item(
+                "Last check-in",
+                if stale { CheckStatus::Fail } else { CheckStatus::Pass },
+                dates::local(secs),
+                stale.then_some("Older than a week. Press d to detect now, then r to refresh."),
+            )
+        }
+        None => item(
+            "Last check-in",
+            CheckStatus::Unknown,
+            "Never, or not recorded".to_string(),
+            Some("Press d to detect now."),
+        ),
+    });
+    Ok(items)
+}
+
+/// Points the client at the WSUS server from `url [target group]`, or removes the WSUS
+/// settings when `spec` is empty or `none`.
+pub fn set(spec: &str) -> Result<String, String> {
+    let mut words = spec.split_whitespace();
+    let server = words.next().unwrap_or_default().trim_end_matches('/');
+    let group = words.collect::<Vec<_>>().join(" ");
+    let script = if server.is_empty() || server.eq_ignore_ascii_case("none") {
+        format!(
+            "Remove-ItemProperty -Path '{0}' -Name WUServer, WUStatusServer, TargetGroup, TargetGroupEnabled -ErrorAction SilentlyContinue\n\
+            Remove-ItemProperty -Path '{0}\\AU' -Name UseWUServer -ErrorAction SilentlyContinue\n\
+            Restart-Service wuauserv -ErrorAction SilentlyContinue",
+            POLICY_KEY
+        )
+    } else {
+        let lower = server.to_lowercase();
+        if !lower.starts_with("http://") && !lower.starts_with("https://") {
+            return Err(format!("Enter the server as a URL, e.g. http://{}:8530", server));
+        }
+        let targeting = if group.is_empty() {
+            "Remove-ItemProperty -Path $key -Name TargetGroup, TargetGroupEnabled -ErrorAction SilentlyContinue".to_string()
+        } else {
+            format!(
+                "Set-ItemProperty -Path $key -Name TargetGroup -Value {}\n\
+                Set-ItemProperty -Path $key -Name TargetGroupEnabled -Value 1 -Type DWord",
+                exec::quote(&group)
+            )
+        };
+        format!(
+            "$key = '{}'\n\
+            New-Item -Path \"$key\\AU\" -Force | Out-Null\n\
+            Set-ItemProperty -Path $key -Name WUServer -Value {1}\n\
+            Set-ItemProperty -Path $key -Name WUStatusServer -Value {1}\n\
+            Set-ItemProperty -Path \"$key\\AU\" -Name UseWUServer -Value 1 -Type DWord\n\
+            {2}\n\
+            Restart-Service wuauserv -ErrorAction SilentlyContinue",
+            POLICY_KEY,
+            exec::quote(server),
+            targeting
+        )
+    };
+    match exec::change(&script) {
+        Ok(output) if output.success => Ok(if server.is_empty() || server.eq_ignore_ascii_case("none") {
+            "WSUS settings removed; updates come from Microsoft Update.".to_string()
+        } else {
+            format!("WSUS server set to {}.", server)
+        }),
+        Ok(output) => Err(format!("Failed to set the WSUS server:\n{}", output.stderr.trim())),
+        Err(e) => Err(format!("Failed to set the WSUS server: {}", e)),
+    }
+}
+
+/// Asks the client to check in now: `usoclient StartScan` on Server 2016 and later,
+/// `wuauclt /detectnow /reportnow` before that.
+pub fn detect_now() -> Result<String, String> {
+    let started = |program: &str, args: &[&str]| exec::change_program(program, args).is_ok_and(|o| o.success);
+    if started("usoclient", &["StartScan"]) {
+        return Ok("Started a scan with usoclient StartScan. Refresh in a minute to see the new check-in time.".to_string());
+    }
+    if started("wuauclt", &["/detectnow", "/reportnow"]) {
+        return Ok("Started detection with wuauclt /detectnow /reportnow. Refresh in a minute to see the new check-in time.".to_string());
+    }
+    Err("Neither usoclient nor wuauclt could start a scan.".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::FakeRunner;
+
+    #[test]
+    fn reports_a_server_that_is_set_but_not_used() {
+        FakeRunner::new()
+            .on("Microsoft.Update.AutoUpdate", "Server=http://wsus01:8530\nUseServer=0\nService=Running\nReachable=True\n")
+            .install();
+
+        let items = checks().unwrap_or_default();
+
+        assert!(items[0].status == CheckStatus::Fail);
+        assert_eq!(items[1].detail, "Not set: the client can't report to WSUS");
+        assert!(items.iter().any(|i| i.name == "Last check-in" && i.status == CheckStatus::Unknown));
+    }
+
+    #[test]
+    fn sets_the_server_and_target_group() {
+        let runner = FakeRunner::new().install();
+
+        assert!(set("wsus01:8530").is_err());
+        assert_eq!(set("http://wsus01.corp.local:8530/ Member Servers"), Ok("WSUS server set to http://wsus01.corp.local:8530.".to_string()));
+        assert!(runner.ran("-Name TargetGroup -Value 'Member Servers'"));
+        assert!(runner.ran("-Name WUStatusServer -Value 'http://wsus01.corp.local:8530'"));
+    }
+}